@@ -0,0 +1,126 @@
+//! OxGL Derive Macros
+//!
+//! Provides `#[derive(Uniforms)]`, which maps a struct's fields to GLSL
+//! uniforms. Use it through the `derive` feature of `oxgl` rather than
+//! depending on this crate directly.
+//!
+//! ## Examples
+//!
+//! ```ignore
+//! use oxgl::common::Uniforms;
+//! use glam::Vec3;
+//!
+//! #[derive(Clone, Uniforms)]
+//! struct Glow {
+//!		color: Vec3,           // uniform vec3 color;
+//!		glow_strength: f32,    // uniform float glowStrength;
+//!		#[uniform(name = "uTime")]
+//!		time: f32,             // uniform float uTime;
+//!		#[uniform(skip)]
+//!		label: String,
+//! }
+//! ```
+//!
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{Data, DeriveInput, Fields, LitStr, parse_macro_input};
+
+/// Derives `oxgl::common::Uniforms` for a struct with named fields.
+///
+/// Each field is uploaded to the uniform named after it in camelCase, so
+/// `glow_strength` maps to `glowStrength`. Field attributes:
+///
+/// - `#[uniform(name = "...")]` - use an explicit uniform name
+/// - `#[uniform(skip)]` - don't upload the field
+#[proc_macro_derive(Uniforms, attributes(uniform))]
+pub fn derive_uniforms(input: TokenStream) -> TokenStream {
+	let input = parse_macro_input!(input as DeriveInput);
+
+	expand(input)
+		.unwrap_or_else(|e| e.to_compile_error())
+		.into()
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+	let fields = match &input.data {
+		Data::Struct(data) => match &data.fields {
+			Fields::Named(fields) => &fields.named,
+			_ => return Err(syn::Error::new_spanned(&input.ident, "Uniforms can only be derived for structs with named fields")),
+		},
+		_ => return Err(syn::Error::new_spanned(&input.ident, "Uniforms can only be derived for structs")),
+	};
+
+	let mut names = Vec::new();
+	let mut uploads = Vec::new();
+
+	for field in fields {
+		let ident = field.ident.as_ref().expect("named field");
+		let mut name = camel_case(&ident.to_string());
+		let mut skip = false;
+
+		for attr in field.attrs.iter().filter(|a| a.path().is_ident("uniform")) {
+			attr.parse_nested_meta(|meta| {
+				if meta.path.is_ident("skip") {
+					skip = true;
+					Ok(())
+				} else if meta.path.is_ident("name") {
+					name = meta.value()?.parse::<LitStr>()?.value();
+					Ok(())
+				} else {
+					Err(meta.error("expected `name = \"...\"` or `skip`"))
+				}
+			})?;
+		}
+
+		if skip {
+			continue;
+		}
+
+		let index = names.len();
+
+		names.push(name);
+		uploads.push(quote! {
+			if let Some(Some(loc)) = locations.get(#index) {
+				::oxgl::common::UniformValue::upload(&self.#ident, gl, loc);
+			}
+		});
+	}
+
+	let ident = &input.ident;
+	let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+	Ok(quote! {
+		impl #impl_generics ::oxgl::common::Uniforms for #ident #ty_generics #where_clause {
+			const NAMES: &'static [&'static str] = &[#(#names),*];
+
+			fn upload(
+				&self,
+				gl: &::oxgl::common::uniforms::__private::GL,
+				locations: &[Option<::oxgl::common::uniforms::__private::WebGlUniformLocation>],
+			) {
+				#(#uploads)*
+			}
+		}
+	})
+}
+
+/// Converts a snake_case field name to camelCase.
+fn camel_case(name: &str) -> String {
+	let mut result = String::with_capacity(name.len());
+	let mut upper = false;
+
+	for c in name.trim_start_matches("r#").chars() {
+		if c == '_' {
+			upper = !result.is_empty();
+		} else if upper {
+			result.extend(c.to_uppercase());
+			upper = false;
+		} else {
+			result.push(c);
+		}
+	}
+
+	result
+}
@@ -0,0 +1,245 @@
+//! Asynchronous Asset Loading
+//!
+//! Provides an [`AssetLoader`] that fetches text, binary data, OBJ meshes,
+//! fonts and textures over HTTP without blocking the render loop. Each load either
+//! returns an [`AssetHandle`] to poll from the frame loop, or takes a
+//! callback that runs once the asset has arrived.
+//!
+//! ## Examples
+//!
+//! ```ignore
+//! use oxgl::common::{AssetLoader, Mesh};
+//!
+//! let loader = AssetLoader::new().with_base_url("assets/");
+//!
+//! // Poll from the render loop
+//! let teapot = loader.load_obj("teapot.obj");
+//!
+//! // Each frame
+//! if let Some(result) = teapot.take() {
+//!		match result {
+//!			Ok(meshes) => { /* add meshes to the scene */ }
+//!			Err(e) => log::error!("{}", e),
+//!		}
+//! }
+//!
+//! // Or use a callback
+//! loader.on_bytes("model.glb", |result| match result {
+//!		Ok(bytes) => log::info!("Loaded {} bytes", bytes.len()),
+//!		Err(e) => log::error!("{}", e),
+//! });
+//!
+//! // Textures are usable right away and fill in once loaded
+//! let albedo = loader.load_texture(&gl, "albedo.png")?;
+//! ```
+//!
+
+use std::{cell::{Cell, RefCell}, rc::Rc};
+use js_sys::{ArrayBuffer, Promise, Uint8Array};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{
+	Response, WebGl2RenderingContext as GL,
+	wasm_bindgen::{JsCast, JsValue},
+};
+
+use super::{MeshData, Texture, TrueTypeFont};
+
+enum Slot<T> {
+	Loading,
+	Done(Result<T, String>),
+	Taken,
+}
+
+/// A pollable handle to an asset that is being loaded.
+///
+/// Cloning is cheap and shares the same result.
+pub struct AssetHandle<T> {
+	slot: Rc<RefCell<Slot<T>>>,
+}
+
+impl<T> Clone for AssetHandle<T> {
+	fn clone(&self) -> Self {
+		Self { slot: self.slot.clone() }
+	}
+}
+
+impl<T> AssetHandle<T> {
+	pub(crate) fn new() -> Self {
+		Self { slot: Rc::new(RefCell::new(Slot::Loading)) }
+	}
+
+	pub(crate) fn complete(&self, result: Result<T, String>) {
+		*self.slot.borrow_mut() = Slot::Done(result);
+	}
+
+	/// Returns `true` while the asset is still being fetched.
+	pub fn is_loading(&self) -> bool {
+		matches!(*self.slot.borrow(), Slot::Loading)
+	}
+
+	/// Returns `true` once loading finished, successfully or not, and the
+	/// result hasn't been taken yet.
+	pub fn is_done(&self) -> bool {
+		matches!(*self.slot.borrow(), Slot::Done(_))
+	}
+
+	/// Takes the result once loading has finished.
+	///
+	/// Returns `None` while loading, and after the result was taken.
+	pub fn take(&self) -> Option<Result<T, String>> {
+		let mut slot = self.slot.borrow_mut();
+
+		match std::mem::replace(&mut *slot, Slot::Taken) {
+			Slot::Done(result) => Some(result),
+			other => {
+				*slot = other;
+				None
+			}
+		}
+	}
+}
+
+/// Fetches assets over HTTP.
+///
+/// Relative URLs are resolved against the base URL, if one is set.
+#[derive(Clone, Default)]
+pub struct AssetLoader {
+	base_url: String,
+	pending: Rc<Cell<usize>>,
+}
+
+impl AssetLoader {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Sets a prefix for relative URLs, such as `"assets/"`.
+	pub fn with_base_url(mut self, base_url: &str) -> Self {
+		self.base_url = base_url.to_string();
+		self
+	}
+
+	/// Returns the number of requests that haven't finished yet.
+	pub fn pending(&self) -> usize {
+		self.pending.get()
+	}
+
+	/// Returns the full URL a path is fetched from.
+	pub fn resolve(&self, url: &str) -> String {
+		let absolute = url.starts_with('/') || url.contains("://") || url.starts_with("data:");
+
+		if absolute || self.base_url.is_empty() {
+			url.to_string()
+		} else {
+			format!("{}{}", self.base_url, url)
+		}
+	}
+
+	pub fn load_text(&self, url: &str) -> AssetHandle<String> {
+		let handle = AssetHandle::new();
+		let target = handle.clone();
+		self.on_text(url, move |result| target.complete(result));
+		handle
+	}
+
+	pub fn load_bytes(&self, url: &str) -> AssetHandle<Vec<u8>> {
+		let handle = AssetHandle::new();
+		let target = handle.clone();
+		self.on_bytes(url, move |result| target.complete(result));
+		handle
+	}
+
+	/// Loads and parses an OBJ file.
+	///
+	/// See [`MeshData::from_obj`].
+	pub fn load_obj(&self, url: &str) -> AssetHandle<Vec<MeshData>> {
+		let handle = AssetHandle::new();
+		let target = handle.clone();
+		self.on_obj(url, move |result| target.complete(result));
+		handle
+	}
+
+	/// Loads and parses a `.ttf` font, see [`TrueTypeFont::parse`].
+	pub fn load_truetype(&self, url: &str) -> AssetHandle<TrueTypeFont> {
+		let handle = AssetHandle::new();
+		let target = handle.clone();
+		self.on_truetype(url, move |result| target.complete(result));
+		handle
+	}
+
+	/// Starts loading a texture.
+	///
+	/// The texture is usable immediately as a placeholder, see
+	/// [`Texture::load`].
+	pub fn load_texture(&self, gl: &GL, url: &str) -> Result<Texture, String> {
+		Texture::load(gl, &self.resolve(url))
+	}
+
+	/// Fetches a text file and passes it to a callback.
+	pub fn on_text(&self, url: &str, callback: impl FnOnce(Result<String, String>) + 'static) {
+		self.fetch(url, Response::text, |value| {
+			value.as_string().ok_or_else(|| "Response body is not text".to_string())
+		}, callback);
+	}
+
+	/// Fetches a binary file, such as a `.glb`, and passes it to a callback.
+	pub fn on_bytes(&self, url: &str, callback: impl FnOnce(Result<Vec<u8>, String>) + 'static) {
+		self.fetch(url, Response::array_buffer, |value| {
+			let buffer: ArrayBuffer = value.dyn_into().map_err(|_| "Response body is not binary")?;
+			Ok(Uint8Array::new(&buffer).to_vec())
+		}, callback);
+	}
+
+	/// Fetches and parses an OBJ file, and passes the meshes to a callback.
+	pub fn on_obj(&self, url: &str, callback: impl FnOnce(Result<Vec<MeshData>, String>) + 'static) {
+		self.on_text(url, move |result| callback(result.and_then(|text| MeshData::from_obj(&text))));
+	}
+
+	/// Fetches and parses a `.ttf` font, and passes it to a callback.
+	pub fn on_truetype(&self, url: &str, callback: impl FnOnce(Result<TrueTypeFont, String>) + 'static) {
+		self.on_bytes(url, move |result| callback(result.and_then(TrueTypeFont::parse)));
+	}
+
+	fn fetch<T: 'static>(
+		&self,
+		url: &str,
+		body: fn(&Response) -> Result<Promise, JsValue>,
+		convert: impl FnOnce(JsValue) -> Result<T, String> + 'static,
+		callback: impl FnOnce(Result<T, String>) + 'static,
+	) {
+		let url = self.resolve(url);
+
+		let Some(window) = web_sys::window() else {
+			callback(Err("No window available".to_string()));
+			return;
+		};
+
+		let pending = self.pending.clone();
+		pending.set(pending.get() + 1);
+
+		wasm_bindgen_futures::spawn_local(async move {
+			let result = async {
+				let response: Response = JsFuture::from(window.fetch_with_str(&url))
+					.await
+					.map_err(|e| rejection(&url, e))?
+					.unchecked_into();
+
+				if !response.ok() {
+					return Err(format!("HTTP {} loading {}", response.status(), url));
+				}
+
+				let promise = body(&response).map_err(|e| format!("Failed to read {}: {:?}", url, e))?;
+				JsFuture::from(promise).await.map_err(|e| rejection(&url, e))
+			}.await;
+
+			pending.set(pending.get().saturating_sub(1));
+			callback(result.and_then(convert));
+		});
+	}
+}
+
+fn rejection(url: &str, error: JsValue) -> String {
+	error
+		.as_string()
+		.unwrap_or_else(|| format!("Failed to fetch {}: {:?}", url, error))
+}
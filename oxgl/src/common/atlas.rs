@@ -0,0 +1,184 @@
+//! Texture Atlases
+//!
+//! Provides [`TextureAtlas`], named regions of one texture such as icons
+//! packed together or the frames of a sprite sheet, and [`UvRect`], the
+//! offset and scale a shader applies to its UVs to sample one region.
+//!
+//! Regions are given in pixels from the top left of the image, the same
+//! way image editors and atlas packers report them.
+//!
+//! ## Examples
+//!
+//! ```ignore
+//! use oxgl::common::{Texture, TextureAtlas};
+//!
+//! let texture = Texture::load(&gl, "assets/icons.png")?;
+//!
+//! // The texture loads asynchronously, so the atlas is given its size
+//! let mut atlas = TextureAtlas::new(256, 256);
+//! atlas.add("heart", 0, 0, 32, 32);
+//! atlas.add("coin", 32, 0, 32, 32);
+//!
+//! let sprite = Sprite::new(position, SpriteSize::Pixels(Vec2::splat(32.0)))
+//!		.with_texture(texture)
+//!		.with_uv(atlas["coin"]);
+//!
+//! // An 8x4 sprite sheet, with frames named "0" to "31" left to right,
+//! // top to bottom
+//! let explosion = TextureAtlas::grid(512, 256, 8, 4);
+//! ```
+//!
+
+use std::{collections::HashMap, ops::Index};
+use glam::{Vec2, Vec4};
+
+/// A rectangle in texture space, applied in shaders as `uv * scale + offset`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct UvRect {
+	/// Top left corner.
+	pub offset: Vec2,
+	/// Width and height.
+	pub scale: Vec2,
+}
+
+impl Default for UvRect {
+	fn default() -> Self {
+		Self::FULL
+	}
+}
+
+impl UvRect {
+	/// The whole texture.
+	pub const FULL: Self = Self { offset: Vec2::ZERO, scale: Vec2::ONE };
+
+	pub fn new(offset: Vec2, scale: Vec2) -> Self {
+		Self { offset, scale }
+	}
+
+	/// Creates a rectangle from pixel coordinates in a texture of the
+	/// given size.
+	///
+	/// ```
+	/// use glam::Vec2;
+	/// use oxgl::common::UvRect;
+	///
+	/// let rect = UvRect::from_pixels(64, 0, 64, 32, 256, 128);
+	///
+	/// assert_eq!(rect.offset, Vec2::new(0.25, 0.0));
+	/// assert_eq!(rect.scale, Vec2::new(0.25, 0.25));
+	/// ```
+	pub fn from_pixels(x: u32, y: u32, width: u32, height: u32, texture_width: u32, texture_height: u32) -> Self {
+		let size = Vec2::new(texture_width.max(1) as f32, texture_height.max(1) as f32);
+
+		Self {
+			offset: Vec2::new(x as f32, y as f32) / size,
+			scale: Vec2::new(width as f32, height as f32) / size,
+		}
+	}
+
+	/// Maps a UV inside the rectangle to the texture.
+	pub fn apply(&self, uv: Vec2) -> Vec2 {
+		uv * self.scale + self.offset
+	}
+
+	/// Returns the rectangle packed as `(offset.x, offset.y, scale.x, scale.y)`
+	/// for a single `vec4` uniform.
+	pub fn to_vec4(&self) -> Vec4 {
+		Vec4::new(self.offset.x, self.offset.y, self.scale.x, self.scale.y)
+	}
+}
+
+/// Named regions of a texture.
+#[derive(Clone, Debug, Default)]
+pub struct TextureAtlas {
+	width: u32,
+	height: u32,
+	regions: HashMap<String, UvRect>,
+}
+
+impl TextureAtlas {
+	/// Creates an empty atlas for a texture of the given size in pixels.
+	pub fn new(width: u32, height: u32) -> Self {
+		Self { width, height, regions: HashMap::new() }
+	}
+
+	/// Creates an atlas of equally sized cells, such as a sprite sheet.
+	///
+	/// Cells are named by their index, counting left to right and then top
+	/// to bottom.
+	///
+	/// ```
+	/// use oxgl::common::TextureAtlas;
+	///
+	/// let sheet = TextureAtlas::grid(256, 128, 4, 2);
+	///
+	/// assert_eq!(sheet.len(), 8);
+	/// assert_eq!(sheet["5"].offset.x, 0.25);
+	/// assert_eq!(sheet["5"].offset.y, 0.5);
+	/// ```
+	pub fn grid(width: u32, height: u32, columns: u32, rows: u32) -> Self {
+		let mut atlas = Self::new(width, height);
+		let cell_width = width / columns.max(1);
+		let cell_height = height / rows.max(1);
+
+		for index in 0..columns * rows {
+			let (column, row) = (index % columns, index / columns);
+			atlas.add(&index.to_string(), column * cell_width, row * cell_height, cell_width, cell_height);
+		}
+
+		atlas
+	}
+
+	/// Adds or replaces a region, in pixels from the top left.
+	pub fn add(&mut self, name: &str, x: u32, y: u32, width: u32, height: u32) -> UvRect {
+		let rect = UvRect::from_pixels(x, y, width, height, self.width, self.height);
+		self.regions.insert(name.to_string(), rect);
+		rect
+	}
+
+	pub fn remove(&mut self, name: &str) -> Option<UvRect> {
+		self.regions.remove(name)
+	}
+
+	pub fn get(&self, name: &str) -> Option<UvRect> {
+		self.regions.get(name).copied()
+	}
+
+	/// Returns the regions of a sprite sheet's frames, in order.
+	///
+	/// Frames missing from the atlas are skipped.
+	pub fn frames<'a>(&self, names: impl IntoIterator<Item = &'a str>) -> Vec<UvRect> {
+		names.into_iter().filter_map(|name| self.get(name)).collect()
+	}
+
+	/// Returns the regions of frames `start..end` of a [`grid`](Self::grid).
+	pub fn frame_range(&self, start: u32, end: u32) -> Vec<UvRect> {
+		(start..end).filter_map(|index| self.get(&index.to_string())).collect()
+	}
+
+	pub fn names(&self) -> impl Iterator<Item = &str> {
+		self.regions.keys().map(String::as_str)
+	}
+
+	pub fn len(&self) -> usize {
+		self.regions.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.regions.is_empty()
+	}
+
+	/// Returns the texture size the atlas was made for.
+	pub fn size(&self) -> (u32, u32) {
+		(self.width, self.height)
+	}
+}
+
+impl Index<&str> for TextureAtlas {
+	type Output = UvRect;
+
+	/// Panics if the region doesn't exist.
+	fn index(&self, name: &str) -> &UvRect {
+		self.regions.get(name).unwrap_or_else(|| panic!("No atlas region named {}", name))
+	}
+}
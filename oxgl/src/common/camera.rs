@@ -3,7 +3,9 @@
 //! Provides perspective camera implementation for 3D rendering.
 //!
 
-use glam::{Mat4, Vec3};
+use glam::{Mat4, Quat, Vec2, Vec3};
+
+use crate::core::{Path, Ray};
 
 /// A perspective camera for 3D scene viewing.
 ///
@@ -28,6 +30,17 @@ pub struct Camera {
 	pub aspect: f32,
 	pub near: f32,
 	pub far: f32,
+	/// Replaces the perspective built from `fov_y` and `aspect`, for
+	/// off-axis projections such as WebXR eyes.
+	pub custom_projection: Option<Mat4>,
+	/// Spreads depth precision logarithmically between `near` and `far`,
+	/// for scenes with both close and very distant geometry that z-fight
+	/// with standard depth.
+	///
+	/// Applied by built-in shaders and custom shaders that pass their
+	/// clip position through `logDepth` from `#include <logdepth>`. WebGL
+	/// can't remap clip depth, so reversed Z gains no precision there.
+	pub logarithmic_depth: bool,
 }
 
 impl Camera {
@@ -40,6 +53,46 @@ impl Camera {
 			aspect,
 			near: 0.1,
 			far: 100.0,
+			custom_projection: None,
+			logarithmic_depth: false,
+		}
+	}
+
+	/// Creates a camera from a view and a perspective projection matrix.
+	///
+	/// The position and orientation are taken from the view, and the field
+	/// of view, aspect and clip distances are estimated from the
+	/// projection, which is used as is for rendering.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use oxgl::common::Camera;
+	/// use glam::{Mat4, Vec3};
+	///
+	/// let view = Mat4::look_at_rh(Vec3::new(0.0, 1.0, 4.0), Vec3::ZERO, Vec3::Y);
+	/// let projection = Mat4::perspective_rh_gl(1.0, 1.5, 0.1, 50.0);
+	/// let camera = Camera::from_matrices(view, projection);
+	///
+	/// assert!(camera.position.distance(Vec3::new(0.0, 1.0, 4.0)) < 1e-4);
+	/// assert!((camera.fov_y - 1.0).abs() < 1e-4);
+	/// assert!((camera.far - 50.0).abs() < 1e-2);
+	/// ```
+	pub fn from_matrices(view: Mat4, projection: Mat4) -> Self {
+		let world = view.inverse();
+		let position = world.w_axis.truncate();
+		let (z, w) = (projection.z_axis.z, projection.w_axis.z);
+
+		Self {
+			position,
+			target: position - world.z_axis.truncate(),
+			up: world.y_axis.truncate(),
+			fov_y: 2.0 * (1.0 / projection.y_axis.y).atan(),
+			aspect: projection.y_axis.y / projection.x_axis.x,
+			near: w / (z - 1.0),
+			far: w / (z + 1.0),
+			custom_projection: Some(projection),
+			logarithmic_depth: false,
 		}
 	}
 
@@ -53,6 +106,104 @@ impl Camera {
 		self
 	}
 
+	pub fn with_logarithmic_depth(mut self, enabled: bool) -> Self {
+		self.logarithmic_depth = enabled;
+		self
+	}
+
+	/// Returns the `logDepthFactor` shader uniform, or `0.0` when
+	/// [`logarithmic_depth`](Self::logarithmic_depth) is off.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use oxgl::common::Camera;
+	///
+	/// let camera = Camera::new(1.0);
+	/// assert_eq!(camera.log_depth_factor(), 0.0);
+	///
+	/// let camera = camera.with_logarithmic_depth(true);
+	/// assert!((camera.log_depth_factor() - 2.0 / 101f32.log2()).abs() < 1e-6);
+	/// ```
+	pub fn log_depth_factor(&self) -> f32 {
+		if self.logarithmic_depth {
+			2.0 / (self.far + 1.0).log2()
+		} else {
+			0.0
+		}
+	}
+
+	/// Moves the camera to progress `t` along a path.
+	///
+	/// The camera looks at the point `look_ahead` further along, or along the
+	/// direction of travel when that is zero or past the end of the path.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use oxgl::{common::Camera, core::Path};
+	/// use glam::Vec3;
+	///
+	/// let path = Path::catmull_rom(vec![Vec3::ZERO, Vec3::new(0.0, 0.0, -10.0)]).unwrap();
+	/// let mut camera = Camera::new(1.0);
+	///
+	/// camera.follow_path(&path, 0.5, 0.0);
+	///
+	/// assert!(camera.position.distance(Vec3::new(0.0, 0.0, -5.0)) < 1e-4);
+	/// assert!((camera.target - camera.position).normalize().distance(Vec3::NEG_Z) < 1e-4);
+	/// ```
+	pub fn follow_path(&mut self, path: &Path, t: f32, look_ahead: f32) {
+		let (position, tangent) = path.sample(t);
+		let ahead = path.sample(t + look_ahead).0;
+
+		self.position = position;
+		self.target = if look_ahead > 0.0 && ahead.distance_squared(position) > 1e-8 {
+			ahead
+		} else {
+			position + tangent
+		};
+	}
+
+	/// Returns a camera part way from this one to `other`, for `t` from
+	/// `0.0` to `1.0`.
+	///
+	/// Positions, target distances and lens settings are interpolated
+	/// linearly and the orientation spherically, so the view turns evenly
+	/// instead of swinging through a point between the two targets. Custom
+	/// projections and logarithmic depth switch over halfway.
+	///
+	/// ```
+	/// use glam::Vec3;
+	/// use oxgl::common::Camera;
+	///
+	/// let a = Camera::new(1.0).with_position(Vec3::new(0.0, 0.0, 5.0));
+	/// let b = Camera::new(1.0).with_position(Vec3::new(5.0, 0.0, 0.0));
+	///
+	/// let halfway = a.blend(&b, 0.5);
+	/// assert!(halfway.position.abs_diff_eq(Vec3::new(2.5, 0.0, 2.5), 1e-5));
+	/// ```
+	pub fn blend(&self, other: &Camera, t: f32) -> Camera {
+		let orientation = |camera: &Camera| Quat::from_mat4(&camera.view_matrix().inverse()).normalize();
+		let rotation = orientation(self).slerp(orientation(other), t);
+
+		let lerp = |a: f32, b: f32| a + (b - a) * t;
+		let position = self.position.lerp(other.position, t);
+		let distance = lerp(self.position.distance(self.target), other.position.distance(other.target));
+		let later = if t < 0.5 { self } else { other };
+
+		Camera {
+			position,
+			target: position + rotation * Vec3::NEG_Z * distance,
+			up: rotation * Vec3::Y,
+			fov_y: lerp(self.fov_y, other.fov_y),
+			aspect: lerp(self.aspect, other.aspect),
+			near: lerp(self.near, other.near),
+			far: lerp(self.far, other.far),
+			custom_projection: later.custom_projection,
+			logarithmic_depth: later.logarithmic_depth,
+		}
+	}
+
 	/// Returns the view matrix (world to camera space).
 	pub fn view_matrix(&self) -> Mat4 {
 		Mat4::look_at_rh(self.position, self.target, self.up)
@@ -60,6 +211,56 @@ impl Camera {
 
 	/// Returns the projection matrix (camera to clip space).
 	pub fn projection_matrix(&self) -> Mat4 {
+		if let Some(projection) = self.custom_projection {
+			return projection;
+		}
+
 		Mat4::perspective_rh_gl(self.fov_y, self.aspect, self.near, self.far)
 	}
+
+	/// Returns the world-space ray through a point in normalized device
+	/// coordinates (-1 to 1, Y up).
+	pub fn ray_from_ndc(&self, ndc: Vec2) -> Ray {
+		let inv = (self.projection_matrix() * self.view_matrix()).inverse();
+		let near = inv.project_point3(ndc.extend(-1.0));
+		let far = inv.project_point3(ndc.extend(1.0));
+
+		Ray::new(near, far - near)
+	}
+
+	/// Projects a world position to a pixel of a viewport, measured from the
+	/// top-left corner like [`screen_ray`](Self::screen_ray).
+	///
+	/// Returns `None` for points behind the camera. Points outside the view
+	/// return coordinates outside the viewport.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use oxgl::common::Camera;
+	/// use glam::{Vec2, Vec3};
+	///
+	/// let camera = Camera::new(1.0).with_position(Vec3::new(0.0, 0.0, 5.0));
+	///
+	/// assert_eq!(camera.project(Vec3::ZERO, 800.0, 800.0), Some(Vec2::new(400.0, 400.0)));
+	/// assert_eq!(camera.project(Vec3::new(0.0, 0.0, 10.0), 800.0, 800.0), None);
+	/// ```
+	pub fn project(&self, point: Vec3, width: f32, height: f32) -> Option<Vec2> {
+		let clip = self.projection_matrix() * self.view_matrix() * point.extend(1.0);
+
+		if clip.w <= 0.0 {
+			return None;
+		}
+
+		let ndc = clip.truncate() / clip.w;
+
+		Some(Vec2::new((ndc.x + 1.0) * 0.5 * width, (1.0 - ndc.y) * 0.5 * height))
+	}
+
+	/// Returns the world-space ray through a pixel of a viewport.
+	///
+	/// `x` and `y` are measured from the top-left corner, as in mouse events.
+	pub fn screen_ray(&self, x: f32, y: f32, width: f32, height: f32) -> Ray {
+		self.ray_from_ndc(Vec2::new(x / width * 2.0 - 1.0, 1.0 - y / height * 2.0))
+	}
 }
\ No newline at end of file
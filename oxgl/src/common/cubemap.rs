@@ -0,0 +1,176 @@
+//! Cube Maps
+//!
+//! Provides [`CubeMap`], six square RGBA faces sampled by direction, used
+//! for skyboxes and reflections. Faces are ordered +X, -X, +Y, -Y, +Z, -Z.
+//!
+//! ## Examples
+//!
+//! ```ignore
+//! use oxgl::common::CubeMap;
+//!
+//! // Starts as grey placeholders and fills in once every face has loaded
+//! let sky = CubeMap::load(&gl, [
+//!		"assets/sky/px.jpg", "assets/sky/nx.jpg",
+//!		"assets/sky/py.jpg", "assets/sky/ny.jpg",
+//!		"assets/sky/pz.jpg", "assets/sky/nz.jpg",
+//! ])?;
+//!
+//! // Bind to texture unit 4 and sample it as a `samplerCube`
+//! sky.bind(&gl, 4);
+//! ```
+//!
+
+use std::{cell::Cell, rc::Rc};
+use glam::Vec3;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{HtmlImageElement, WebGlTexture, WebGl2RenderingContext as GL};
+
+/// Target, view direction and up vector of each face, in the order of the
+/// `TEXTURE_CUBE_MAP_*` targets.
+pub(crate) const CUBE_FACES: [(u32, Vec3, Vec3); 6] = [
+	(GL::TEXTURE_CUBE_MAP_POSITIVE_X, Vec3::X, Vec3::NEG_Y),
+	(GL::TEXTURE_CUBE_MAP_NEGATIVE_X, Vec3::NEG_X, Vec3::NEG_Y),
+	(GL::TEXTURE_CUBE_MAP_POSITIVE_Y, Vec3::Y, Vec3::Z),
+	(GL::TEXTURE_CUBE_MAP_NEGATIVE_Y, Vec3::NEG_Y, Vec3::NEG_Z),
+	(GL::TEXTURE_CUBE_MAP_POSITIVE_Z, Vec3::Z, Vec3::NEG_Y),
+	(GL::TEXTURE_CUBE_MAP_NEGATIVE_Z, Vec3::NEG_Z, Vec3::NEG_Y),
+];
+
+/// A cube map texture.
+///
+/// Cloning is cheap and shares the underlying GPU texture.
+#[derive(Clone)]
+pub struct CubeMap {
+	texture: WebGlTexture,
+	size: Rc<Cell<u32>>,
+	/// Faces still loading from [`load`](Self::load).
+	pending: Rc<Cell<u32>>,
+}
+
+impl CubeMap {
+	/// Creates a cube map with uninitialized `size` by `size` faces,
+	/// typically rendered into.
+	pub fn empty(gl: &GL, size: u32) -> Result<Self, String> {
+		let cubemap = Self::create(gl)?;
+
+		for (target, _, _) in CUBE_FACES {
+			gl.tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
+				target, 0, GL::RGBA as i32, size as i32, size as i32, 0,
+				GL::RGBA, GL::UNSIGNED_BYTE, None
+			).map_err(|e| format!("Failed to allocate cube map face: {:?}", e))?;
+		}
+
+		cubemap.size.set(size);
+		Ok(cubemap)
+	}
+
+	/// Creates a cube map from six faces of tightly packed RGBA8 pixels.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the texture can't be created or a face's data
+	/// size doesn't match the dimensions.
+	pub fn from_pixels(gl: &GL, size: u32, faces: [&[u8]; 6]) -> Result<Self, String> {
+		let expected = (size * size * 4) as usize;
+
+		if let Some(face) = faces.iter().find(|face| face.len() != expected) {
+			return Err(format!("Expected {} bytes of RGBA data per face, got {}", expected, face.len()));
+		}
+
+		let cubemap = Self::create(gl)?;
+
+		for ((target, _, _), pixels) in CUBE_FACES.into_iter().zip(faces) {
+			gl.tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
+				target, 0, GL::RGBA as i32, size as i32, size as i32, 0,
+				GL::RGBA, GL::UNSIGNED_BYTE, Some(pixels)
+			).map_err(|e| format!("Failed to upload cube map face: {:?}", e))?;
+		}
+
+		cubemap.size.set(size);
+		Ok(cubemap)
+	}
+
+	/// Starts loading six square face images from URLs.
+	///
+	/// The cube map is usable immediately as a 1x1 grey placeholder. Each
+	/// face is replaced as its image loads, and the cube map samples
+	/// black until every face has the same size. Faces that fail to load
+	/// or decode are logged, and the cube map is never
+	/// [loaded](Self::is_loaded).
+	pub fn load(gl: &GL, urls: [&str; 6]) -> Result<Self, String> {
+		let grey = [128, 128, 128, 255];
+		let cubemap = Self::from_pixels(gl, 1, [&grey; 6])?;
+		cubemap.pending.set(6);
+
+		for ((target, _, _), url) in CUBE_FACES.into_iter().zip(urls) {
+			let image = HtmlImageElement::new()
+				.map_err(|e| format!("Failed to create image: {:?}", e))?;
+
+			image.set_cross_origin(Some("anonymous"));
+
+			image.set_src(url);
+
+			let face = cubemap.clone();
+			let gl = gl.clone();
+			let url = url.to_string();
+
+			wasm_bindgen_futures::spawn_local(async move {
+				if let Err(e) = JsFuture::from(image.decode()).await {
+					log::error!("Failed to load cube map face {}: {:?}", url, e);
+					return;
+				}
+
+				gl.bind_texture(GL::TEXTURE_CUBE_MAP, Some(&face.texture));
+
+				if let Err(e) = gl.tex_image_2d_with_u32_and_u32_and_html_image_element(
+					target, 0, GL::RGBA as i32, GL::RGBA, GL::UNSIGNED_BYTE, &image
+				) {
+					log::error!("Failed to upload cube map face {}: {:?}", url, e);
+					return;
+				}
+
+				face.size.set(image.natural_width());
+				face.pending.set(face.pending.get().saturating_sub(1));
+			});
+		}
+
+		Ok(cubemap)
+	}
+
+	/// Binds the cube map to a texture unit.
+	pub fn bind(&self, gl: &GL, unit: u32) {
+		gl.active_texture(GL::TEXTURE0 + unit);
+		gl.bind_texture(GL::TEXTURE_CUBE_MAP, Some(&self.texture));
+	}
+
+	pub fn texture(&self) -> &WebGlTexture {
+		&self.texture
+	}
+
+	/// Returns the width and height of each face.
+	pub fn size(&self) -> u32 {
+		self.size.get()
+	}
+
+	/// Returns `false` while faces passed to [`load`](Self::load) are still
+	/// loading.
+	pub fn is_loaded(&self) -> bool {
+		self.pending.get() == 0
+	}
+
+	fn create(gl: &GL) -> Result<Self, String> {
+		let texture = gl.create_texture().ok_or("Failed to create cube map")?;
+
+		gl.bind_texture(GL::TEXTURE_CUBE_MAP, Some(&texture));
+		gl.tex_parameteri(GL::TEXTURE_CUBE_MAP, GL::TEXTURE_MIN_FILTER, GL::LINEAR as i32);
+		gl.tex_parameteri(GL::TEXTURE_CUBE_MAP, GL::TEXTURE_MAG_FILTER, GL::LINEAR as i32);
+		gl.tex_parameteri(GL::TEXTURE_CUBE_MAP, GL::TEXTURE_WRAP_S, GL::CLAMP_TO_EDGE as i32);
+		gl.tex_parameteri(GL::TEXTURE_CUBE_MAP, GL::TEXTURE_WRAP_T, GL::CLAMP_TO_EDGE as i32);
+
+		Ok(Self {
+			texture,
+			size: Rc::new(Cell::new(0)),
+			pending: Rc::new(Cell::new(0)),
+		})
+	}
+}
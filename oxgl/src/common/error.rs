@@ -0,0 +1,195 @@
+//! Error Types
+//!
+//! Provides [`OxglError`], returned when GPU resources such as shaders,
+//! programs and framebuffers can't be created. Shader compile errors carry
+//! the lines of source the driver complained about, so mistakes can be found
+//! without counting lines in an expanded shader.
+//!
+//! ## Examples
+//!
+//! ```ignore
+//! use oxgl::common::{Material, OxglError};
+//!
+//! match Material::from_source(&gl, vert_src, frag_src) {
+//!		Ok(material) => scene.add(Mesh::new(&gl, &vertices, material), Transform3D::new()),
+//!		Err(OxglError::ShaderCompile { stage, log, annotated_source }) => {
+//!			log::error!("{} shader:\n{}\n{}", stage, log, annotated_source);
+//!		}
+//!		Err(error) => log::error!("{}", error),
+//! }
+//! ```
+//!
+
+use std::fmt;
+use web_sys::WebGl2RenderingContext as GL;
+
+/// Lines of source shown before and after each line with an error.
+const CONTEXT_LINES: usize = 2;
+
+/// The shader stage a compile error came from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShaderStage {
+	Vertex,
+	Fragment,
+}
+
+impl ShaderStage {
+	/// Returns the stage for a `VERTEX_SHADER` or `FRAGMENT_SHADER` type.
+	pub fn from_gl(shader_type: u32) -> Self {
+		if shader_type == GL::VERTEX_SHADER { Self::Vertex } else { Self::Fragment }
+	}
+}
+
+impl fmt::Display for ShaderStage {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Vertex => write!(f, "Vertex"),
+			Self::Fragment => write!(f, "Fragment"),
+		}
+	}
+}
+
+/// An error creating a GPU resource.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum OxglError {
+	/// A shader failed to preprocess or compile.
+	ShaderCompile {
+		stage: ShaderStage,
+		/// The driver's info log, or the preprocessor error.
+		log: String,
+		/// Numbered source lines around each line named in the log, with the
+		/// offending lines marked by `>`. Empty if the log names no lines.
+		annotated_source: String,
+	},
+	/// Compiled shaders failed to link into a program.
+	ProgramLink {
+		log: String,
+	},
+	/// A framebuffer was missing or had mismatched attachments.
+	FramebufferIncomplete {
+		/// What the framebuffer was for, such as `"Shadow"`.
+		framebuffer: String,
+		/// The `checkFramebufferStatus` result.
+		status: u32,
+	},
+	/// A buffer, texture, framebuffer or other object couldn't be created.
+	ResourceCreation(String),
+}
+
+impl OxglError {
+	/// Creates a shader compile error, annotating `source` with the lines
+	/// named in `log`.
+	///
+	/// Line numbers are read from log entries like `ERROR: 0:12: ...`, which
+	/// refer to the source as it was passed to the driver, after
+	/// `#include` expansion.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use oxgl::common::{OxglError, ShaderStage};
+	///
+	/// let source = "precision mediump float;\nvoid main() {\n\tgl_FragColor = colr;\n}";
+	/// let log = "ERROR: 0:3: 'colr' : undeclared identifier";
+	///
+	/// let OxglError::ShaderCompile { annotated_source, .. } = OxglError::shader_compile(ShaderStage::Fragment, log, source) else {
+	///		unreachable!();
+	/// };
+	///
+	/// assert!(annotated_source.contains("> 3 | \tgl_FragColor = colr;"));
+	/// assert!(annotated_source.contains("  1 | precision mediump float;"));
+	/// ```
+	pub fn shader_compile(stage: ShaderStage, log: &str, source: &str) -> Self {
+		Self::ShaderCompile {
+			stage,
+			log: log.trim().to_string(),
+			annotated_source: annotate(source, &error_lines(log)),
+		}
+	}
+
+	/// Returns a readable name for a `checkFramebufferStatus` result.
+	pub fn framebuffer_status_name(status: u32) -> &'static str {
+		match status {
+			GL::FRAMEBUFFER_INCOMPLETE_ATTACHMENT => "INCOMPLETE_ATTACHMENT",
+			GL::FRAMEBUFFER_INCOMPLETE_MISSING_ATTACHMENT => "MISSING_ATTACHMENT",
+			GL::FRAMEBUFFER_INCOMPLETE_DIMENSIONS => "INCOMPLETE_DIMENSIONS",
+			GL::FRAMEBUFFER_INCOMPLETE_MULTISAMPLE => "INCOMPLETE_MULTISAMPLE",
+			GL::FRAMEBUFFER_UNSUPPORTED => "UNSUPPORTED",
+			_ => "UNKNOWN",
+		}
+	}
+}
+
+impl fmt::Display for OxglError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::ShaderCompile { stage, log, annotated_source } => {
+				write!(f, "{} shader failed to compile:\n{}", stage, log)?;
+
+				if !annotated_source.is_empty() {
+					write!(f, "\n\n{}", annotated_source)?;
+				}
+
+				Ok(())
+			}
+			Self::ProgramLink { log } => write!(f, "Program failed to link:\n{}", log),
+			Self::FramebufferIncomplete { framebuffer, status } => write!(
+				f, "{} framebuffer incomplete: {} ({})",
+				framebuffer, Self::framebuffer_status_name(*status), status
+			),
+			Self::ResourceCreation(message) => write!(f, "{}", message),
+		}
+	}
+}
+
+impl std::error::Error for OxglError {}
+
+impl From<OxglError> for String {
+	fn from(error: OxglError) -> Self {
+		error.to_string()
+	}
+}
+
+/// Returns the 1-based line numbers named in a shader info log.
+fn error_lines(log: &str) -> Vec<usize> {
+	let mut lines: Vec<usize> = log
+		.lines()
+		.filter_map(|entry| {
+			// ERROR: <source string>:<line>: <message>
+			let mut parts = entry.splitn(4, ':');
+			parts.next()?;
+			parts.next()?.trim().parse::<u32>().ok()?;
+			parts.next()?.trim().parse().ok()
+		})
+		.collect();
+
+	lines.sort_unstable();
+	lines.dedup();
+	lines
+}
+
+/// Formats the numbered source lines around each error line.
+fn annotate(source: &str, error_lines: &[usize]) -> String {
+	let lines: Vec<&str> = source.lines().collect();
+	let width = lines.len().to_string().len();
+	let mut output = String::new();
+	let mut last_shown = 0;
+
+	for &error in error_lines.iter().filter(|&&line| line >= 1 && line <= lines.len()) {
+		let first = error.saturating_sub(CONTEXT_LINES).max(last_shown + 1).max(1);
+		let last = (error + CONTEXT_LINES).min(lines.len());
+
+		if last_shown > 0 && first > last_shown + 1 {
+			output.push_str("...\n");
+		}
+
+		for number in first..=last {
+			let marker = if error_lines.contains(&number) { '>' } else { ' ' };
+			output.push_str(&format!("{} {:>width$} | {}\n", marker, number, lines[number - 1], width = width));
+		}
+
+		last_shown = last_shown.max(last);
+	}
+
+	output.trim_end().to_string()
+}
@@ -2,6 +2,9 @@
 //!
 //! Provides utilities for loading 3D mesh data from various file formats.
 //! Currently supports OBJ file parsing with automatic normal computation.
+//! OBJ models are split into one [`MeshData`] per object, group and
+//! material, see [`MtlMaterial`](super::MtlMaterial) for the material
+//! libraries.
 //!
 //! ## Examples
 //!
@@ -12,17 +15,19 @@
 //! let meshes = MeshData::from_obj(obj_content)?;
 //!
 //! for mesh in meshes {
+//!		log::info!("Loaded {:?}", mesh.name);
 //!		let interleaved = mesh.interleaved_vertices();
 //!		// Use with Mesh::with_normals...
 //! }
 //! ```
 //!
 
+use std::collections::HashMap;
 use glam::Vec3;
 
 /// Raw mesh data containing vertex attributes.
 ///
-/// Stores position, normal, and UV data in separate flat arrays.
+/// Stores position, normal, UV and color data in separate flat arrays.
 /// Can be loaded from OBJ files or constructed manually.
 ///
 /// ## Construction
@@ -35,13 +40,29 @@ pub struct MeshData {
 	pub positions: Vec<f32>,
 	pub normals: Vec<f32>,
 	pub uvs: Vec<f32>,
+	/// RGB colors per vertex, or empty for meshes without vertex colors.
+	pub colors: Vec<f32>,
+	/// Name of the object or group from `o`/`g`, if any.
+	pub name: Option<String>,
+	/// Name of the material assigned with `usemtl`, if any.
+	pub material: Option<String>,
 }
 
 impl MeshData {
 	/// Parses mesh data from OBJ file content.
 	///
+	/// Produces one mesh per object or group (`o`/`g`), in file order. An
+	/// object using several `usemtl` materials is split further into one
+	/// mesh per material, each keeping the object's name. Faces before any
+	/// `o`/`g` form an unnamed mesh.
+	///
+	/// Vertex colors written as `v x y z r g b` are read into
+	/// [`colors`](Self::colors). In a mesh using any colored vertex, the
+	/// uncolored ones are white.
+	///
 	/// If the OBJ file doesn't contain normals, they are computed automatically
-	/// using face normals.
+	/// using face normals. Use [`compute_smooth_normals`](Self::compute_smooth_normals)
+	/// afterwards for curved models.
 	///
 	/// # Errors
 	///
@@ -61,12 +82,16 @@ impl MeshData {
 	///
 	pub fn from_obj(content: &str) -> Result<Vec<MeshData>, String> {
 		let mut positions: Vec<Vec3> = Vec::new();
+		let mut colors: Vec<Option<Vec3>> = Vec::new();
 		let mut normals: Vec<Vec3> = Vec::new();
 		let mut uvs: Vec<[f32; 2]> = Vec::new();
 
-		let mut out_positions: Vec<f32> = Vec::new();
-		let mut out_normals: Vec<f32> = Vec::new();
-		let mut out_uvs: Vec<f32> = Vec::new();
+		let mut meshes: Vec<MeshData> = vec![MeshData::default()];
+		// Whether each mesh has a vertex with a color
+		let mut colored = vec![false];
+		let mut current = 0;
+		let mut name: Option<String> = None;
+		let mut material: Option<String> = None;
 
 		for line in content.lines() {
 			let line = line.trim();
@@ -89,6 +114,11 @@ impl MeshData {
 						let z: f32 = parts[3].parse().unwrap_or(0.0);
 
 						positions.push(Vec3::new(x, y, z));
+
+						let color = (parts.len() >= 7).then(|| {
+							Vec3::from_array([4, 5, 6].map(|i| parts[i].parse().unwrap_or(1.0)))
+						});
+						colors.push(color);
 					}
 				}
 				"vn" => {
@@ -108,7 +138,34 @@ impl MeshData {
 						uvs.push([u, v]);
 					}
 				}
+				"o" | "g" | "usemtl" => {
+					let value = (parts.len() > 1).then(|| parts[1..].join(" "));
+
+					if parts[0] == "usemtl" {
+						material = value;
+					} else {
+						name = value;
+					}
+
+					let existing = meshes
+						.iter()
+						.position(|m| m.name == name && m.material == material);
+
+					current = match existing {
+						Some(index) => index,
+						None => {
+							meshes.push(MeshData {
+								name: name.clone(),
+								material: material.clone(),
+								..Default::default()
+							});
+							colored.push(false);
+							meshes.len() - 1
+						}
+					};
+				}
 				"f" => {
+					let out = &mut meshes[current];
 					let face_verts: Vec<_> = parts[1..].iter().map(|p| parse_face_vertex(p)).collect();
 
 					for i in 1..face_verts.len() - 1 {
@@ -116,17 +173,23 @@ impl MeshData {
 							let (vi, ti, ni) = face_verts[idx];
 
 							if let Some(pos) = positions.get(vi) {
-								out_positions.extend_from_slice(&[pos.x, pos.y, pos.z]);
+								out.positions.extend_from_slice(&[pos.x, pos.y, pos.z]);
+
+								// Uncolored vertices are white, in case the
+								// mesh turns out to have colored ones
+								let color = colors[vi];
+								out.colors.extend_from_slice(&color.unwrap_or(Vec3::ONE).to_array());
+								colored[current] |= color.is_some();
 							}
 
 							if let Some(norm) = ni.and_then(|i| normals.get(i)) {
-								out_normals.extend_from_slice(&[norm.x, norm.y, norm.z]);
+								out.normals.extend_from_slice(&[norm.x, norm.y, norm.z]);
 							} else {
-								out_normals.extend_from_slice(&[0.0, 1.0, 0.0]);
+								out.normals.extend_from_slice(&[0.0, 1.0, 0.0]);
 							}
 
 							if let Some(uv) = ti.and_then(|i| uvs.get(i)) {
-								out_uvs.extend_from_slice(uv);
+								out.uvs.extend_from_slice(uv);
 							}
 						}
 					}
@@ -135,15 +198,120 @@ impl MeshData {
 			}
 		}
 
-		if out_normals.iter().all(|&n| n == 0.0 || n == 1.0) {
-			out_normals = compute_normals(&out_positions);
+		for (mesh, colored) in meshes.iter_mut().zip(colored) {
+			if !colored {
+				mesh.colors.clear();
+			}
+		}
+
+		// Objects and groups without faces, such as a `g` directly followed
+		// by `usemtl`, don't produce meshes
+		meshes.retain(|m| !m.positions.is_empty());
+
+		if meshes.is_empty() {
+			meshes.push(MeshData::default());
+		}
+
+		for mesh in &mut meshes {
+			if mesh.normals.iter().all(|&n| n == 0.0 || n == 1.0) {
+				mesh.normals = compute_normals(&mesh.positions);
+			}
 		}
 
-		Ok(vec![MeshData {
-			positions: out_positions,
-			normals: out_normals,
-			uvs: out_uvs,
-		}])
+		Ok(meshes)
+	}
+
+	/// Replaces the normals with smooth normals averaged across shared
+	/// positions.
+	///
+	/// Faces meeting at an angle greater than `angle_threshold` (in radians)
+	/// keep a hard crease between them, so a cylinder can have smooth sides
+	/// and flat caps. Larger faces contribute more to the average.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use oxgl::common::MeshData;
+	///
+	/// // Two triangles folded 90 degrees along a shared edge
+	/// let mut data = MeshData::from_obj("
+	///		v 0 0 0
+	///		v 1 0 0
+	///		v 0 0 -1
+	///		v 0 1 0
+	///		f 1 2 3
+	///		f 1 4 2
+	/// ").unwrap().remove(0);
+	///
+	/// // Below the crease angle, the edge stays sharp
+	/// data.compute_smooth_normals(30f32.to_radians());
+	/// assert_eq!(&data.normals[0..3], &[0.0, 1.0, 0.0]);
+	///
+	/// // Above it, shared vertices point halfway between +Y and -Z
+	/// data.compute_smooth_normals(100f32.to_radians());
+	/// assert!((data.normals[1] - 0.5f32.sqrt()).abs() < 1e-6);
+	/// assert!((data.normals[2] + 0.5f32.sqrt()).abs() < 1e-6);
+	/// ```
+	pub fn compute_smooth_normals(&mut self, angle_threshold: f32) {
+		let face_normals: Vec<Vec3> = self.positions
+			.chunks_exact(9)
+			.map(|tri| {
+				let v0 = Vec3::new(tri[0], tri[1], tri[2]);
+				let v1 = Vec3::new(tri[3], tri[4], tri[5]);
+				let v2 = Vec3::new(tri[6], tri[7], tri[8]);
+
+				// Unnormalized, so the average is weighted by area
+				(v1 - v0).cross(v2 - v0)
+			})
+			.collect();
+
+		let vertex_count = face_normals.len() * 3;
+		let key = |i: usize| {
+			// Adding 0.0 folds -0.0 into 0.0 so both hash the same
+			[0, 1, 2].map(|axis| (self.positions[i * 3 + axis] + 0.0).to_bits())
+		};
+
+		let mut shared: HashMap<[u32; 3], Vec<usize>> = HashMap::new();
+
+		for i in 0..vertex_count {
+			shared.entry(key(i)).or_default().push(i / 3);
+		}
+
+		let min_cos = angle_threshold.cos();
+		let mut normals = Vec::with_capacity(vertex_count * 3);
+
+		for i in 0..vertex_count {
+			let face = face_normals[i / 3].normalize_or_zero();
+
+			let sum: Vec3 = shared[&key(i)]
+				.iter()
+				.map(|&other| face_normals[other])
+				.filter(|other| other.normalize_or_zero().dot(face) >= min_cos)
+				.sum();
+
+			let normal = sum.try_normalize().unwrap_or(face);
+			normals.extend_from_slice(&[normal.x, normal.y, normal.z]);
+		}
+
+		self.normals = normals;
+	}
+
+	/// Returns the material library paths referenced by `mtllib` statements.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use oxgl::common::MeshData;
+	///
+	/// let libraries = MeshData::material_libraries("mtllib chair.mtl\nv 0 0 0");
+	/// assert_eq!(libraries, vec!["chair.mtl".to_string()]);
+	/// ```
+	pub fn material_libraries(content: &str) -> Vec<String> {
+		content
+			.lines()
+			.filter_map(|line| line.trim().strip_prefix("mtllib "))
+			.flat_map(|paths| paths.split_whitespace().map(str::to_string))
+			.collect()
 	}
 
 	/// Converts the mesh data to interleaved vertex format.
@@ -151,11 +319,16 @@ impl MeshData {
 	/// Produces a flat array with interleaved position and normal data:
 	/// `[px, py, pz, nx, ny, nz, px, py, pz, nx, ny, nz, ...]`
 	///
-	/// This format is suitable for use with [`Mesh::with_normals`](crate::common::Mesh::with_normals).
+	/// Mesh data with [`colors`](Self::colors) gets an RGB color after each
+	/// normal, with white for vertices past the end of the colors.
+	///
+	/// Without colors, this format is suitable for use with
+	/// [`Mesh::with_normals`](crate::common::Mesh::with_normals).
 	///
 	/// # Returns
 	///
-	/// A flat `Vec<f32>` with 6 floats per vertex (3 position + 3 normal).
+	/// A flat `Vec<f32>` with [`vertex_stride`](Self::vertex_stride) floats
+	/// per vertex.
 	///
 	/// # Examples
 	///
@@ -175,7 +348,7 @@ impl MeshData {
 	/// ```
 	pub fn interleaved_vertices(&self) -> Vec<f32> {
 		let vertex_count = self.positions.len() / 3;
-		let mut result = Vec::with_capacity(vertex_count * 6);
+		let mut result = Vec::with_capacity(vertex_count * self.vertex_stride());
 
 		for i in 0..vertex_count {
 			result.push(self.positions[i * 3]);
@@ -191,10 +364,34 @@ impl MeshData {
 				result.push(1.0);
 				result.push(0.0);
 			}
+
+			if !self.colors.is_empty() {
+				let color = self.colors.get(i * 3..i * 3 + 3).unwrap_or(&[1.0, 1.0, 1.0]);
+				result.extend_from_slice(color);
+			}
 		}
 
 		result
 	}
+
+	/// Returns the floats per vertex in
+	/// [`interleaved_vertices`](Self::interleaved_vertices).
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use oxgl::common::MeshData;
+	///
+	/// let mut data = MeshData::from_obj("v 0 0 0 1 0 0\nv 1 0 0 0 1 0\nv 0 1 0 0 0 1\nf 1 2 3").unwrap().remove(0);
+	/// assert_eq!(data.vertex_stride(), 9);
+	/// assert_eq!(&data.interleaved_vertices()[6..9], &[1.0, 0.0, 0.0]);
+	///
+	/// data.colors.clear();
+	/// assert_eq!(data.vertex_stride(), 6);
+	/// ```
+	pub fn vertex_stride(&self) -> usize {
+		if self.colors.is_empty() { 6 } else { 9 }
+	}
 }
 
 /// Parses a single face vertex definition from OBJ format.
@@ -8,8 +8,12 @@ use std::collections::HashMap;
 use glam::{Vec2, Vec3, Vec4, Mat4};
 use web_sys::{WebGlProgram, WebGl2RenderingContext as GL};
 
-use crate::renderer_3d::{Light, apply_lights};
-use super::{compile_shader, link_program};
+use crate::renderer_3d::{Light, apply_lights, reflection_probe::PROBE_UNIT, scene::SCENE_DEPTH_UNIT};
+use super::{compile_shader, link_program, CubeMap, OxglError, Texture, UniformBlocks, Uniforms};
+
+/// Maximum number of clip planes per draw, shared by the scene and the
+/// material. Must match `MAX_CLIP_PLANES` in the `clipping` shader chunk.
+pub const MAX_CLIP_PLANES: usize = 6;
 
 /// Represents a shader uniform value.
 ///
@@ -38,6 +42,10 @@ impl Uniform {
 	}
 }
 
+/// Texture units material textures and cube maps are bound to, after the
+/// shadow maps and before the units the scene reserves.
+const MATERIAL_UNITS: std::ops::Range<u32> = 2..if SCENE_DEPTH_UNIT < PROBE_UNIT { SCENE_DEPTH_UNIT } else { PROBE_UNIT };
+
 /// A material consisting of a shader program and uniform values.
 ///
 /// Materials define how a mesh is rendered, including its shader
@@ -60,7 +68,44 @@ impl Uniform {
 pub struct Material {
 	program: WebGlProgram,
 	uniforms: HashMap<String, Uniform>,
+	blocks: UniformBlocks,
+	textures: Vec<(String, Texture)>,
+	cube_maps: Vec<(String, CubeMap)>,
+	/// Planes clipping only meshes using this material, in addition to the
+	/// scene's, see [`set_clip_planes`](Self::set_clip_planes).
+	pub clip_planes: Vec<Vec4>,
 	pub needs_normals: bool,
+	/// Whether the [deferred path](crate::renderer_3d::RenderPath::Deferred)
+	/// can shade this material from its `color`, `ambient`, `shininess` and
+	/// `specularStrength` uniforms. Set by the Lambert and Phong presets.
+	/// [Emissive](Self::set_emissive), [lightmapped](Self::set_lightmap) and
+	/// [reflective](Self::set_reflectivity) materials are drawn forward
+	/// instead.
+	pub deferred: bool,
+	/// Draws back faces too, with their normals flipped, for thin surfaces
+	/// such as ground quads and leaves.
+	pub double_sided: bool,
+	/// Shades each triangle with its face normal instead of the interpolated
+	/// vertex normals, for low-poly looks and meshes with bad normals.
+	///
+	/// Both flags apply to shaders including the `normals` chunk, which
+	/// covers the lit presets.
+	pub flat_shading: bool,
+	/// Gives the shader the depth of the scene behind the mesh, through the
+	/// `scene_depth` chunk, for water and other surfaces tinted by how much
+	/// lies beneath them. Such meshes are left out of that depth.
+	pub scene_depth: bool,
+	/// Grows the mesh's bounds by this distance, in local units, when
+	/// frustum culling, so meshes whose vertex shader moves them aren't
+	/// culled while still on screen. The displacing presets set it for their
+	/// default amplitudes, so raise it along with them.
+	pub cull_padding: f32,
+	/// Draws the shadow and camera depth passes with this material's own
+	/// program, with the `depthPass` uniform set and `lightSpace` holding
+	/// the pass's view-projection, instead of the plain depth program. For
+	/// shaders that move vertices or cut out texels, so their shadows and
+	/// depth match what is drawn.
+	pub depth_pass: bool,
 }
 
 impl Material {
@@ -68,8 +113,8 @@ impl Material {
 	///
 	/// ## Errors
 	///
-	/// Returns an error string if shader compilation or linking fails.
-	pub fn from_source(gl: &GL, vert_src: &str, frag_src: &str) -> Result<Self, String> {
+	/// Returns an [`OxglError`] if shader compilation or linking fails.
+	pub fn from_source(gl: &GL, vert_src: &str, frag_src: &str) -> Result<Self, OxglError> {
 		let vert_shader = compile_shader(gl, vert_src, GL::VERTEX_SHADER)?;
 		let frag_shader = compile_shader(gl, frag_src, GL::FRAGMENT_SHADER)?;
 		let program = link_program(gl, &vert_shader, &frag_shader)?;
@@ -78,15 +123,64 @@ impl Material {
 		Ok(Self {
 			program,
 			uniforms: HashMap::new(),
+			blocks: UniformBlocks::default(),
+			textures: Vec::new(),
+			cube_maps: Vec::new(),
+			clip_planes: Vec::new(),
 			needs_normals,
+			deferred: false,
+			double_sided: false,
+			flat_shading: false,
+			scene_depth: false,
+			cull_padding: 0.0,
+			depth_pass: false,
 		})
 	}
 
+	/// Replaces the shader program with one compiled from new source.
+	///
+	/// Uniforms, textures and clip planes are kept, so live-editing tools can
+	/// recompile on every change. On failure the material keeps drawing with
+	/// its previous program. Clones of the material keep the program they
+	/// had, so it isn't deleted.
+	///
+	/// ## Errors
+	///
+	/// Returns the compile or link error of the new source.
+	///
+	/// ## Examples
+	///
+	/// ```ignore
+	/// // From a text area's input event
+	/// if let Err(error) = material.recompile(&gl, vert_src, &editor.value()) {
+	///		status.set_text_content(Some(&error.to_string()));
+	/// }
+	/// ```
+	pub fn recompile(&mut self, gl: &GL, vert_src: &str, frag_src: &str) -> Result<(), OxglError> {
+		let vert_shader = compile_shader(gl, vert_src, GL::VERTEX_SHADER)?;
+		let frag_shader = compile_shader(gl, frag_src, GL::FRAGMENT_SHADER)?;
+
+		self.program = link_program(gl, &vert_shader, &frag_shader)?;
+		self.needs_normals = vert_src.contains("attribute vec3 normal");
+
+		Ok(())
+	}
+
 	pub fn set(&mut self, name: &str, value: Uniform) -> &mut Self {
 		self.uniforms.insert(name.to_string(), value);
 		self
 	}
 
+	/// Returns the value of a uniform set on the material.
+	pub fn get(&self, name: &str) -> Option<&Uniform> {
+		self.uniforms.get(name)
+	}
+
+	/// Returns every uniform set on the material, in no particular order.
+	pub fn uniform_values(&self) -> impl Iterator<Item = (&str, &Uniform)> {
+		self.uniforms.iter().map(|(name, value)| (name.as_str(), value))
+	}
+
 	pub fn set_float(&mut self, name: &str, v: f32) -> &mut Self {
 		self.set(name, Uniform::Float(v))
 	}
@@ -107,6 +201,158 @@ impl Material {
 		self.set_vec4("color", Vec4::new(r, g, b, a))
 	}
 
+	/// Sets the light given off by the surface, for lamps, holograms and
+	/// neon signs.
+	///
+	/// Emission is added on top of lighting and is also drawn to the
+	/// `emissiveTexture` of [post effects](super::PostProcessStack), so
+	/// glow effects pick it up. Applies to shaders including the `emissive`
+	/// chunk, which covers the presets.
+	pub fn set_emissive(&mut self, color: Vec3, intensity: f32) -> &mut Self {
+		self.set_vec3("emissive", color);
+		self.set_float("emissiveIntensity", intensity)
+	}
+
+	/// Shades the material with light baked into a texture instead of the
+	/// scene's lights, skipping real-time lighting and shadows.
+	///
+	/// The mesh needs [lightmap UVs](super::Mesh::set_lightmap_uvs). Bake
+	/// lightmaps with [`Scene::bake_lightmap`](crate::renderer_3d::Scene::bake_lightmap)
+	/// or load ones holding linear light. Applies to the Lambert and Phong
+	/// presets.
+	///
+	/// ```ignore
+	/// let lightmap = scene.bake_lightmap(&gl, floor, 512)?;
+	/// scene.objects[floor].mesh.material.set_lightmap(lightmap, 1.0);
+	/// ```
+	pub fn set_lightmap(&mut self, lightmap: Texture, intensity: f32) -> &mut Self {
+		self.set_texture("lightmap", lightmap);
+		self.set_float("lightmapIntensity", intensity)
+	}
+
+	pub fn has_lightmap(&self) -> bool {
+		self.texture("lightmap").is_some() && matches!(self.get("lightmapIntensity"), Some(Uniform::Float(intensity)) if *intensity > 0.0)
+	}
+
+	/// Reflects the [reflection probe](crate::renderer_3d::ReflectionProbe)
+	/// around the object, with `reflectivity` the share of light reflected
+	/// when facing the surface. More is reflected at grazing angles.
+	/// Applies to the Phong and waves presets.
+	///
+	/// ```ignore
+	/// scene.add_reflection_probe(ReflectionProbe::new(&gl, Vec3::new(0.0, 1.5, 0.0), room, 256)?);
+	/// floor.mesh.material.set_reflectivity(0.3);
+	/// ```
+	pub fn set_reflectivity(&mut self, reflectivity: f32) -> &mut Self {
+		self.set_float("reflectivity", reflectivity)
+	}
+
+	pub fn is_reflective(&self) -> bool {
+		matches!(self.get("reflectivity"), Some(Uniform::Float(reflectivity)) if *reflectivity > 0.0)
+	}
+
+	/// Returns whether the [deferred path](crate::renderer_3d::RenderPath::Deferred)
+	/// shades this material, which excludes emissive, lightmapped and
+	/// reflective ones.
+	pub(crate) fn uses_deferred(&self) -> bool {
+		self.deferred && !self.is_emissive() && !self.has_lightmap() && !self.is_reflective()
+	}
+
+	/// Returns whether the material gives off light.
+	pub fn is_emissive(&self) -> bool {
+		let intensity = match self.get("emissiveIntensity") {
+			Some(Uniform::Float(intensity)) => *intensity,
+			_ => 0.0,
+		};
+
+		matches!(self.get("emissive"), Some(Uniform::Vec3(color)) if *color != Vec3::ZERO) && intensity > 0.0
+	}
+
+	/// Sets a typed uniform struct, replacing any previous value of its type.
+	///
+	/// See [`Uniforms`].
+	pub fn set_uniforms<T: Uniforms + Clone>(&mut self, value: T) -> &mut Self {
+		self.blocks.set(value);
+		self
+	}
+
+	pub fn uniforms<T: Uniforms>(&self) -> Option<&T> {
+		self.blocks.get()
+	}
+
+	pub fn uniforms_mut<T: Uniforms>(&mut self) -> Option<&mut T> {
+		self.blocks.get_mut()
+	}
+
+	/// Binds a texture to a `sampler2D` uniform, replacing any previous one.
+	///
+	/// Textures are bound to consecutive units starting at 2, as units 0 and
+	/// 1 are reserved for the shadow and contact shadow depth maps. Units
+	/// from 14 hold the scene depth and reflection probe, so textures and
+	/// cube maps past the twelfth are logged and not bound.
+	pub fn set_texture(&mut self, name: &str, texture: Texture) -> &mut Self {
+		match self.textures.iter_mut().find(|(n, _)| n == name) {
+			Some((_, existing)) => *existing = texture,
+			None => {
+				self.textures.push((name.to_string(), texture));
+				self.warn_unit_overflow(name);
+			}
+		}
+		self
+	}
+
+	pub fn texture(&self, name: &str) -> Option<&Texture> {
+		self.textures.iter().find(|(n, _)| n == name).map(|(_, t)| t)
+	}
+
+	/// Returns every bound texture.
+	pub fn textures(&self) -> impl Iterator<Item = &Texture> {
+		self.textures.iter().map(|(_, t)| t)
+	}
+
+	/// Returns the sampler uniform names of every bound texture.
+	pub fn texture_names(&self) -> impl Iterator<Item = &str> {
+		self.textures.iter().map(|(n, _)| n.as_str())
+	}
+
+	/// Binds a cube map to a `samplerCube` uniform, replacing any previous
+	/// one.
+	///
+	/// Cube maps are bound to the units following the 2D textures, see
+	/// [`set_texture`](Self::set_texture) for how many fit.
+	pub fn set_cube_map(&mut self, name: &str, cube_map: CubeMap) -> &mut Self {
+		match self.cube_maps.iter_mut().find(|(n, _)| n == name) {
+			Some((_, existing)) => *existing = cube_map,
+			None => {
+				self.cube_maps.push((name.to_string(), cube_map));
+				self.warn_unit_overflow(name);
+			}
+		}
+		self
+	}
+
+	fn warn_unit_overflow(&self, name: &str) {
+		if self.textures.len() + self.cube_maps.len() > MATERIAL_UNITS.len() {
+			log::warn!("Material has more than {} textures, so `{}` won't be bound", MATERIAL_UNITS.len(), name);
+		}
+	}
+
+	pub fn cube_map(&self, name: &str) -> Option<&CubeMap> {
+		self.cube_maps.iter().find(|(n, _)| n == name).map(|(_, c)| c)
+	}
+
+	/// Sets world-space planes that cut away parts of meshes using this
+	/// material.
+	///
+	/// Each plane is `(normal, distance)`; points where
+	/// `normal.dot(point) + distance` is negative are discarded. Only
+	/// shaders including the `clipping` chunk are clipped, which covers the
+	/// built-in presets and material graphs.
+	pub fn set_clip_planes(&mut self, planes: &[Vec4]) -> &mut Self {
+		self.clip_planes = planes.to_vec();
+		self
+	}
+
 	pub fn program(&self) -> &WebGlProgram {
 		&self.program
 	}
@@ -119,6 +365,36 @@ impl Material {
 			}
 		}
 
+		for (unit, (name, texture)) in MATERIAL_UNITS.zip(&self.textures) {
+			if let Some(loc) = gl.get_uniform_location(&self.program, name) {
+				texture.bind(gl, unit);
+				gl.uniform1i(Some(&loc), unit as i32);
+			}
+		}
+
+		let cube_units = MATERIAL_UNITS.skip(self.textures.len());
+
+		for (unit, (name, cube_map)) in cube_units.zip(&self.cube_maps) {
+			if let Some(loc) = gl.get_uniform_location(&self.program, name) {
+				cube_map.bind(gl, unit);
+				gl.uniform1i(Some(&loc), unit as i32);
+			}
+		}
+
+		// Cube samplers must not share unit 0 with the shadow map, even
+		// when no probe is bound
+		if let Some(loc) = gl.get_uniform_location(&self.program, "probeMap") {
+			gl.uniform1i(Some(&loc), PROBE_UNIT as i32);
+		}
+
+		if let Some(loc) = gl.get_uniform_location(&self.program, "doubleSided") {
+			gl.uniform1i(Some(&loc), self.double_sided as i32);
+		}
+		if let Some(loc) = gl.get_uniform_location(&self.program, "flatShading") {
+			gl.uniform1i(Some(&loc), self.flat_shading as i32);
+		}
+
+		self.blocks.apply(gl, &self.program);
 		apply_lights(gl, &self.program, lights);
 	}
 }
@@ -128,11 +404,47 @@ impl Clone for Material {
 		Self {
 			program: self.program.clone(),
 			uniforms: self.uniforms.clone(),
+			blocks: self.blocks.clone(),
+			textures: self.textures.clone(),
+			cube_maps: self.cube_maps.clone(),
+			clip_planes: self.clip_planes.clone(),
 			needs_normals: self.needs_normals,
+			deferred: self.deferred,
+			double_sided: self.double_sided,
+			flat_shading: self.flat_shading,
+			scene_depth: self.scene_depth,
+			cull_padding: self.cull_padding,
+			depth_pass: self.depth_pass,
 		}
 	}
 }
 
+/// Uploads clip planes to the `clipping` chunk uniforms of a program: the
+/// scene's planes followed by the material's.
+///
+/// Planes past [`MAX_CLIP_PLANES`] are ignored.
+pub fn apply_clip_planes(gl: &GL, program: &WebGlProgram, scene: &[Vec4], material: &[Vec4]) {
+	let mut data = [0.0; MAX_CLIP_PLANES * 4];
+	let mut count = 0;
+
+	for (plane, slot) in scene.iter().chain(material).zip(data.chunks_exact_mut(4)) {
+		slot.copy_from_slice(&plane.to_array());
+		count += 1;
+	}
+
+	if let Some(loc) = gl.get_uniform_location(program, "numClipPlanes") {
+		gl.uniform1i(Some(&loc), count as i32);
+	}
+
+	if count == 0 {
+		return;
+	}
+
+	if let Some(loc) = gl.get_uniform_location(program, "clipPlanes") {
+		gl.uniform4fv_with_f32_array(Some(&loc), &data[..count * 4]);
+	}
+}
+
 /// Builder for creating materials with a fluent API.
 ///
 /// ## Examples
@@ -189,6 +501,15 @@ impl<'a> MaterialBuilder<'a> {
 		self.uniform("specularStrength", Uniform::Float(v))
 	}
 
+	pub fn emissive(self, color: Vec3, intensity: f32) -> Self {
+		self.uniform("emissive", Uniform::Vec3(color))
+			.uniform("emissiveIntensity", Uniform::Float(intensity))
+	}
+
+	pub fn reflectivity(self, v: f32) -> Self {
+		self.uniform("reflectivity", Uniform::Float(v))
+	}
+
 	/// Builds the material.
 	///
 	/// ## Panics
@@ -214,26 +535,283 @@ pub mod presets {
 	const LAMBERT_FRAG: &str = include_str!("../shaders/lambert.frag");
 	const PHONG_VERT: &str = include_str!("../shaders/phong.vert");
 	const PHONG_FRAG: &str = include_str!("../shaders/phong.frag");
+	const WAVES_VERT: &str = include_str!("../shaders/waves.vert");
+	const TOON_FRAG: &str = include_str!("../shaders/toon.frag");
+	const REFLECTIVE_FRAG: &str = include_str!("../shaders/reflective.frag");
+	const VEGETATION_VERT: &str = include_str!("../shaders/vegetation.vert");
+	const VEGETATION_FRAG: &str = include_str!("../shaders/vegetation.frag");
+	const WATER_FRAG: &str = include_str!("../shaders/water.frag");
+	const DISTORTION_VERT: &str = include_str!("../shaders/distortion.vert");
+	const DISTORTION_FRAG: &str = include_str!("../shaders/distortion.frag");
 
 	pub fn unlit(gl: &GL, color: Vec4) -> Material {
 		MaterialBuilder::new(gl, UNLIT_VERT, UNLIT_FRAG)
 			.color4(color.x, color.y, color.z, color.w)
+			.emissive(Vec3::ZERO, 1.0)
 			.build()
 	}
 
 	pub fn lambert(gl: &GL, color: Vec3) -> Material {
-		MaterialBuilder::new(gl, LAMBERT_VERT, LAMBERT_FRAG)
+		let mut material = MaterialBuilder::new(gl, LAMBERT_VERT, LAMBERT_FRAG)
 			.color3(color.x, color.y, color.z)
 			.ambient(0.1)
-			.build()
+			.emissive(Vec3::ZERO, 1.0)
+			.build();
+		material.deferred = true;
+		material
 	}
 
 	pub fn phong(gl: &GL, color: Vec3) -> Material {
-		MaterialBuilder::new(gl, PHONG_VERT, PHONG_FRAG)
+		let mut material = MaterialBuilder::new(gl, PHONG_VERT, PHONG_FRAG)
 			.color3(color.x, color.y, color.z)
 			.ambient(0.1)
 			.shininess(32.0)
 			.specular(0.5)
+			.emissive(Vec3::ZERO, 1.0)
+			.reflectivity(0.0)
+			.build();
+		material.deferred = true;
+		material
+	}
+
+	/// A phong material that ripples the mesh along its local Y axis.
+	///
+	/// Heights animate from the scene's `time` uniform, so the mesh should be
+	/// a finely subdivided plane in XZ, such as a flat [`Terrain`](crate::renderer_3d::Terrain).
+	/// Tune the waves with the `waveAmplitude`, `waveLength` and `waveSpeed`
+	/// uniforms, and raise [`cull_padding`](Material::cull_padding) with the
+	/// amplitude.
+	///
+	/// ```ignore
+	/// use oxgl::renderer_3d::Terrain;
+	///
+	/// let water = Terrain::from_fn(128, |_, _| 0.0).with_size(40.0, 40.0);
+	///
+	/// for mesh in water.build(&gl, presets::waves(&gl, Vec3::new(0.1, 0.35, 0.6))) {
+	///		scene.add(mesh, Transform3D::new());
+	/// }
+	/// ```
+	pub fn waves(gl: &GL, color: Vec3) -> Material {
+		let mut material = MaterialBuilder::new(gl, WAVES_VERT, PHONG_FRAG)
+			.color3(color.x, color.y, color.z)
+			.ambient(0.15)
+			.shininess(64.0)
+			.specular(0.8)
+			.emissive(Vec3::ZERO, 1.0)
+			.reflectivity(0.0)
+			.uniform("waveAmplitude", Uniform::Float(0.2))
+			.uniform("waveLength", Uniform::Float(4.0))
+			.uniform("waveSpeed", Uniform::Float(1.0))
+			.build();
+
+		// Crests reach one and a half amplitudes
+		material.cull_padding = 0.3;
+		material
+	}
+
+	/// A cel-shaded material that steps diffuse lighting into `bands` flat
+	/// levels, with hard-edged shadows.
+	///
+	/// Uses the scene's lights and shadow map like [`phong`]. A rim light
+	/// along the silhouette is off until `rimStrength` is raised:
+	///
+	/// - `rimStrength` (`0.0`) - brightness of the rim
+	/// - `rimPower` (`3.0`) - higher values give a thinner rim
+	/// - `rimColor` (`vec3`, white)
+	///
+	/// Pairs well with the [outline](crate::common::postprocessing::presets::outline)
+	/// post effect.
+	///
+	/// ```ignore
+	/// let mut material = presets::toon(&gl, Vec3::new(0.9, 0.4, 0.2), 3);
+	/// material.set_float("rimStrength", 0.4);
+	/// ```
+	pub fn toon(gl: &GL, color: Vec3, bands: u32) -> Material {
+		MaterialBuilder::new(gl, PHONG_VERT, TOON_FRAG)
+			.color3(color.x, color.y, color.z)
+			.ambient(0.15)
+			.emissive(Vec3::ZERO, 1.0)
+			.uniform("bands", Uniform::Float(bands.max(1) as f32))
+			.uniform("rimStrength", Uniform::Float(0.0))
+			.uniform("rimPower", Uniform::Float(3.0))
+			.uniform("rimColor", Uniform::Vec3(Vec3::ONE))
 			.build()
 	}
-}
\ No newline at end of file
+
+	/// A shiny Phong material mirroring `cubemap` by the reflected view
+	/// direction, such as chrome, glass or polished stone.
+	///
+	/// `reflectivity` is the share of the environment reflected when facing
+	/// the surface, rising towards `1.0` at grazing angles. Inside a
+	/// [reflection probe](crate::renderer_3d::ReflectionProbe) the probe is
+	/// reflected instead, so nearby objects show up.
+	///
+	/// ```ignore
+	/// use oxgl::renderer_3d::{Background, Skybox};
+	///
+	/// let sky = Skybox::from_cube_map(&gl, CubeMap::load(&gl, faces)?)?;
+	/// let chrome = presets::reflective(&gl, sky.cube_map().unwrap().clone(), 0.8);
+	/// scene.set_background(Background::Skybox(sky));
+	/// ```
+	pub fn reflective(gl: &GL, cubemap: CubeMap, reflectivity: f32) -> Material {
+		let mut material = MaterialBuilder::new(gl, PHONG_VERT, REFLECTIVE_FRAG)
+			.color3(1.0, 1.0, 1.0)
+			.ambient(0.1)
+			.shininess(64.0)
+			.specular(0.8)
+			.emissive(Vec3::ZERO, 1.0)
+			.reflectivity(reflectivity)
+			.build();
+		material.set_cube_map("environment", cubemap);
+		material
+	}
+
+	/// A double-sided diffuse material for grass, leaves and bushes that
+	/// sways in the wind, animated from the scene's `time` uniform.
+	///
+	/// Vertices bend by the square of their height above the mesh origin, so
+	/// plants should be modelled standing on `y = 0`. Each instance of a
+	/// [`Scatter`](crate::renderer_3d::Scatter) mesh sways out of step with
+	/// its neighbours, and regular meshes work too.
+	///
+	/// With a `texture`, read through a `uv` attribute, texels with alpha
+	/// below the cutoff are discarded, for leaf cards. Shadows and the
+	/// camera depth sway and cut out along with it, see
+	/// [`depth_pass`](Material::depth_pass). Tune it with:
+	///
+	/// - `windDirection` (`vec3`, `(1, 0, 0)`)
+	/// - `windAmplitude` (`0.05`) - sway one unit above the origin
+	/// - `windFrequency` (`0.5`) - sways per second
+	/// - `alphaCutoff` (`0.5`)
+	///
+	/// ```ignore
+	/// use oxgl::renderer_3d::Scatter;
+	///
+	/// let mut leaves = presets::vegetation(&gl, Vec3::new(0.4, 0.7, 0.3), Some(leaf_texture));
+	/// leaves.set_float("windAmplitude", 0.1);
+	///
+	/// let bushes = Scatter::new(0.5).with_scale_range(0.8, 1.3);
+	/// scene.add(bushes.build(&gl, &bush, &ground, Mat4::IDENTITY, leaves)?, Transform3D::new());
+	/// ```
+	pub fn vegetation(gl: &GL, color: Vec3, texture: Option<Texture>) -> Material {
+		let mut material = MaterialBuilder::new(gl, VEGETATION_VERT, VEGETATION_FRAG)
+			.color3(color.x, color.y, color.z)
+			.ambient(0.25)
+			.emissive(Vec3::ZERO, 1.0)
+			.uniform("windDirection", Uniform::Vec3(Vec3::X))
+			.uniform("windAmplitude", Uniform::Float(0.05))
+			.uniform("windFrequency", Uniform::Float(0.5))
+			.uniform("alphaCutoff", Uniform::Float(0.5))
+			.uniform("useTexture", Uniform::Int(texture.is_some() as i32))
+			.build();
+
+		if let Some(texture) = texture {
+			material.set_texture("leafTexture", texture);
+		}
+
+		material.double_sided = true;
+		material.cull_padding = 0.5;
+		material.depth_pass = true;
+		material
+	}
+
+	/// A water surface with the [`waves`] displacement, rippling normals and
+	/// a color that deepens with the depth of the scene beneath it.
+	///
+	/// The shallow `color` fades towards `deepColor` as light is absorbed on
+	/// its way through, read from the [scene depth](Material::scene_depth).
+	/// With a `reflection` texture from a mirrored
+	/// [`SceneTexture`](crate::renderer_3d::SceneTexture), the surface
+	/// reflects the scene, more so at grazing angles. Tune it with:
+	///
+	/// - `waveAmplitude` (`0.1`), `waveLength` (`6.0`), `waveSpeed` (`0.8`) -
+	///   as for [`waves`]
+	/// - `rippleScale` (`2.0`) - ripples per world unit, roughly
+	/// - `rippleStrength` (`0.08`) - how far ripples bend the normal
+	/// - `rippleSpeed` (`1.5`)
+	/// - `deepColor` (`vec3`, `color` darkened)
+	/// - `absorption` (`0.6`) - how quickly depth turns the water deep, per
+	///   world unit
+	/// - `reflectionStrength` (`1.0`)
+	/// - `reflectionDistortion` (`0.02`) - how far ripples shift the reflection
+	///
+	/// ```ignore
+	/// use oxgl::renderer_3d::{SceneTexture, Terrain};
+	/// use oxgl::common::Camera;
+	/// use glam::Vec4;
+	///
+	/// // Reflect the scene in a lake at y = 0
+	/// let reflection = SceneTexture::new(&gl, 1024, 512, Camera::new(1.0))?
+	///		.with_mirror(Vec4::new(0.0, 1.0, 0.0, 0.0));
+	/// let mut material = presets::water(&gl, Vec3::new(0.2, 0.6, 0.6), Some(reflection.texture().clone()));
+	/// material.set_vec3("deepColor", Vec3::new(0.0, 0.08, 0.15));
+	///
+	/// scene.add_scene_texture(reflection);
+	///
+	/// let lake = Terrain::from_fn(128, |_, _| 0.0).with_size(60.0, 60.0);
+	///
+	/// for mesh in lake.build(&gl, material) {
+	///		scene.add(mesh, Transform3D::new());
+	/// }
+	/// ```
+	pub fn water(gl: &GL, color: Vec3, reflection: Option<Texture>) -> Material {
+		let mut material = MaterialBuilder::new(gl, WAVES_VERT, WATER_FRAG)
+			.color3(color.x, color.y, color.z)
+			.ambient(0.2)
+			.shininess(128.0)
+			.specular(1.0)
+			.emissive(Vec3::ZERO, 1.0)
+			.uniform("waveAmplitude", Uniform::Float(0.1))
+			.uniform("waveLength", Uniform::Float(6.0))
+			.uniform("waveSpeed", Uniform::Float(0.8))
+			.uniform("rippleScale", Uniform::Float(2.0))
+			.uniform("rippleStrength", Uniform::Float(0.08))
+			.uniform("rippleSpeed", Uniform::Float(1.5))
+			.uniform("deepColor", Uniform::Vec3(color * 0.15))
+			.uniform("absorption", Uniform::Float(0.6))
+			.uniform("useReflection", Uniform::Int(reflection.is_some() as i32))
+			.uniform("reflectionStrength", Uniform::Float(1.0))
+			.uniform("reflectionDistortion", Uniform::Float(0.02))
+			.build();
+
+		if let Some(reflection) = reflection {
+			material.set_texture("reflectionTexture", reflection);
+		}
+
+		material.scene_depth = true;
+		material.cull_padding = 0.15;
+		material
+	}
+
+	/// A mask for [distorters](crate::renderer_3d::Scene::set_distortion),
+	/// bending the image behind the mesh by its normals like a lens, for
+	/// glass and shockwaves.
+	///
+	/// Heat haze comes from the shimmer, which drifts upwards and is off
+	/// until `noiseStrength` is raised. Offsets are scaled again by the
+	/// [distortion](crate::common::postprocessing::presets::distortion)
+	/// effect. Tune it with:
+	///
+	/// - `noiseStrength` (`0.0`) - shimmer added to the normal bending
+	/// - `noiseScale` (`4.0`) - shimmer waves per world unit, roughly
+	/// - `noiseSpeed` (`1.0`) - rising speed in world units per second
+	/// - `edgeFade` (`0.0`) - fades the mask towards the silhouette, `1.0`
+	///   for volumes without a visible edge
+	///
+	/// ```ignore
+	/// // Heat haze over a road, without lens bending
+	/// let mut haze = presets::distortion(&gl, 0.0);
+	/// haze.set_float("noiseStrength", 0.5).set_float("edgeFade", 1.0);
+	///
+	/// scene.set_distortion(haze_volume, Some(haze));
+	/// ```
+	pub fn distortion(gl: &GL, strength: f32) -> Material {
+		MaterialBuilder::new(gl, DISTORTION_VERT, DISTORTION_FRAG)
+			.uniform("strength", Uniform::Float(strength))
+			.uniform("noiseStrength", Uniform::Float(0.0))
+			.uniform("noiseScale", Uniform::Float(4.0))
+			.uniform("noiseSpeed", Uniform::Float(1.0))
+			.uniform("edgeFade", Uniform::Float(0.0))
+			.build()
+	}
+}
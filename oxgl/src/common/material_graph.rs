@@ -0,0 +1,484 @@
+//! Material Graphs
+//!
+//! Provides a node-based material model that compiles to GLSL, so materials
+//! can be authored without writing shaders and generated by tools. Graphs are
+//! plain data and serialize to JSON with the `serde` feature.
+//!
+//! Each node produces a `float`, `vec2`, `vec3` or `vec4`. Node inputs refer
+//! to earlier nodes by [`GraphNodeId`], and the node passed to
+//! [`MaterialGraph::set_output`] becomes the fragment color.
+//!
+//! ## Examples
+//!
+//! ```ignore
+//! use oxgl::common::{GraphNode, LightingModel, MaterialGraph, ValueType};
+//! use glam::{Vec3, Vec4};
+//!
+//! let mut graph = MaterialGraph::new();
+//!
+//! let tint = graph.add(GraphNode::Parameter {
+//!		name: "tint".into(),
+//!		ty: ValueType::Vec3,
+//!		default: Vec4::new(0.2, 0.4, 1.0, 1.0),
+//! });
+//! let lit = graph.add(GraphNode::Lighting {
+//!		albedo: tint,
+//!		model: LightingModel::Phong { shininess: 32.0, specular: 0.5 },
+//! });
+//!
+//! // Add a rim glow on top of the lit surface
+//! let rim = graph.add(GraphNode::Fresnel { power: 3.0 });
+//! let glow = graph.add(GraphNode::Multiply { a: rim, b: tint });
+//! let color = graph.add(GraphNode::Add { a: lit, b: glow });
+//! graph.set_output(color);
+//!
+//! let mut material = graph.build(&gl)?;
+//! material.set_vec3("tint", Vec3::new(1.0, 0.3, 0.1));
+//! ```
+//!
+
+use glam::{Vec3, Vec4};
+use web_sys::WebGl2RenderingContext as GL;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::{Material, Uniform, shader::preprocess};
+
+const GRAPH_VERT: &str = include_str!("../shaders/phong.vert");
+
+/// Index of a node within a [`MaterialGraph`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
+pub struct GraphNodeId(pub usize);
+
+/// The GLSL type of a node's output.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "snake_case"))]
+pub enum ValueType {
+	Float,
+	Vec2,
+	Vec3,
+	Vec4,
+}
+
+impl ValueType {
+	fn glsl(self) -> &'static str {
+		match self {
+			ValueType::Float => "float",
+			ValueType::Vec2 => "vec2",
+			ValueType::Vec3 => "vec3",
+			ValueType::Vec4 => "vec4",
+		}
+	}
+
+	fn components(self) -> usize {
+		self as usize + 1
+	}
+
+	fn with_components(count: usize) -> Option<Self> {
+		match count {
+			1 => Some(ValueType::Float),
+			2 => Some(ValueType::Vec2),
+			3 => Some(ValueType::Vec3),
+			4 => Some(ValueType::Vec4),
+			_ => None,
+		}
+	}
+}
+
+/// How a [`GraphNode::Lighting`] node shades its albedo.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(tag = "type", rename_all = "snake_case"))]
+pub enum LightingModel {
+	/// Diffuse only.
+	Lambert,
+	/// Diffuse with Blinn-Phong specular highlights.
+	Phong { shininess: f32, specular: f32 },
+}
+
+/// A single operation in a material graph.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(tag = "type", rename_all = "snake_case"))]
+pub enum GraphNode {
+	/// A constant `float`.
+	Float { value: f32 },
+	/// A constant `vec3` color.
+	Color { value: Vec3 },
+	/// A uniform that can be changed on the built material.
+	///
+	/// Only the first `ty` components of `default` are used. `vec3` and
+	/// `vec4` parameters are colors, given in sRGB like [`Color`](Self::Color)
+	/// and decoded to linear light, keeping the alpha.
+	Parameter { name: String, ty: ValueType, default: Vec4 },
+	/// Samples a `sampler2D` uniform, bound with
+	/// [`Material::set_texture`]. `uv` must be a `vec2`. The color is
+	/// decoded from sRGB to linear light, keeping the alpha.
+	Texture { name: String, uv: GraphNodeId },
+	/// World-space surface position (`vec3`).
+	WorldPosition,
+	/// Normalized world-space surface normal (`vec3`).
+	Normal,
+	/// Normalized direction from the surface to the camera (`vec3`).
+	ViewDirection,
+	Add { a: GraphNodeId, b: GraphNodeId },
+	Subtract { a: GraphNodeId, b: GraphNodeId },
+	Multiply { a: GraphNodeId, b: GraphNodeId },
+	/// Linear interpolation from `a` to `b` by `t`.
+	Mix { a: GraphNodeId, b: GraphNodeId, t: GraphNodeId },
+	/// Selects components with a GLSL mask such as `"xy"` or `"rgb"`.
+	Swizzle { input: GraphNodeId, mask: String },
+	/// Rim factor (`float`), strongest where the surface faces away from the camera.
+	Fresnel { power: f32 },
+	/// Shades an albedo color with the scene's lights and shadows (`vec3`).
+	Lighting { albedo: GraphNodeId, model: LightingModel },
+}
+
+/// A graph of material nodes with a single color output.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MaterialGraph {
+	nodes: Vec<GraphNode>,
+	output: Option<GraphNodeId>,
+}
+
+impl MaterialGraph {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Adds a node and returns its id.
+	pub fn add(&mut self, node: GraphNode) -> GraphNodeId {
+		self.nodes.push(node);
+		GraphNodeId(self.nodes.len() - 1)
+	}
+
+	/// Sets the node whose value becomes the fragment color.
+	///
+	/// `float` and `vec3` outputs are opaque; `vec4` outputs keep their alpha.
+	pub fn set_output(&mut self, id: GraphNodeId) -> &mut Self {
+		self.output = Some(id);
+		self
+	}
+
+	pub fn output(&self) -> Option<GraphNodeId> {
+		self.output
+	}
+
+	pub fn node(&self, id: GraphNodeId) -> Option<&GraphNode> {
+		self.nodes.get(id.0)
+	}
+
+	pub fn node_mut(&mut self, id: GraphNodeId) -> Option<&mut GraphNode> {
+		self.nodes.get_mut(id.0)
+	}
+
+	pub fn nodes(&self) -> &[GraphNode] {
+		&self.nodes
+	}
+
+	/// Generates the vertex and fragment shader sources.
+	///
+	/// Only nodes reachable from the output are emitted.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the graph has no output, references missing
+	/// nodes, contains a cycle, connects incompatible types, has a
+	/// non-finite constant, or names a parameter or texture after an
+	/// identifier the generated shaders already use.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use oxgl::common::{GraphNode, MaterialGraph, ValueType};
+	/// use glam::Vec4;
+	///
+	/// let mut graph = MaterialGraph::new();
+	/// let tint = graph.add(GraphNode::Parameter { name: "tint".into(), ty: ValueType::Vec3, default: Vec4::ONE });
+	/// graph.set_output(tint);
+	/// assert!(graph.compile().is_ok());
+	///
+	/// if let Some(GraphNode::Parameter { name, .. }) = graph.node_mut(tint) {
+	///		*name = "cameraPosition".into();
+	/// }
+	/// assert!(graph.compile().is_err());
+	///
+	/// let nan = graph.add(GraphNode::Float { value: f32::NAN });
+	/// graph.set_output(nan);
+	/// assert!(graph.compile().is_err());
+	/// ```
+	pub fn compile(&self) -> Result<(String, String), String> {
+		let output = self.output.ok_or("Material graph has no output")?;
+
+		let mut compiler = Compiler {
+			graph: self,
+			types: vec![None; self.nodes.len()],
+			visiting: vec![false; self.nodes.len()],
+			uniforms: Vec::new(),
+			body: String::new(),
+			lit: false,
+		};
+
+		let color = match compiler.emit(output)? {
+			ValueType::Float => format!("vec4(vec3(n{}), 1.0)", output.0),
+			ValueType::Vec2 => return Err("Material graph output must be a float, vec3 or vec4".into()),
+			ValueType::Vec3 => format!("vec4(n{}, 1.0)", output.0),
+			ValueType::Vec4 => format!("n{}", output.0),
+		};
+
+		let mut frag = String::from("precision highp float;\n\nuniform vec3 cameraPosition;\n\n");
+
+		if compiler.lit {
+			frag.push_str("#include <lighting>\n");
+		}
+
+		frag.push_str("#include <fog>\n#include <clipping>\n\n");
+
+		frag.push_str("varying vec3 vNormal;\nvarying vec3 vWorldPos;\nvarying vec4 vPosLightSpace;\n\n");
+
+		let main = "\nvoid main() {\n\tapplyClipping(vWorldPos);\n";
+		let tail = "\tgl_FragColor = vec4(toOutput(applyFog(outColor.rgb, length(vWorldPos - cameraPosition))), outColor.a);\n}\n";
+
+		// User names share a scope with everything the shaders declare, so a
+		// clash has to be caught here rather than when the shader compiles
+		let scaffold = preprocess(&format!("{}\n{}{}\tvec4 outColor;\n{}", GRAPH_VERT, frag, main, tail), &[])?;
+		let reserved: Vec<&str> = scaffold
+			.lines()
+			.filter_map(|line| line.split("//").next())
+			.flat_map(|code| code.split(|c: char| !c.is_ascii_alphanumeric() && c != '_'))
+			.collect();
+
+		for (name, declaration) in &compiler.uniforms {
+			if reserved.contains(&name.as_str()) || GLSL_KEYWORDS.contains(&name.as_str()) {
+				return Err(format!("Uniform name '{}' is reserved", name));
+			}
+
+			frag.push_str(declaration);
+			frag.push('\n');
+		}
+
+		frag.push_str(main);
+		frag.push_str(&compiler.body);
+		frag.push_str(&format!("\tvec4 outColor = {};\n", color));
+		frag.push_str(tail);
+
+		Ok((GRAPH_VERT.to_string(), frag))
+	}
+
+	/// Compiles the graph into a material.
+	///
+	/// Parameters are initialized to their defaults, and lit graphs get an
+	/// ambient term of `0.1`.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the graph is invalid or the generated shaders
+	/// fail to compile.
+	pub fn build(&self, gl: &GL) -> Result<Material, String> {
+		let (vert, frag) = self.compile()?;
+		let mut material = Material::from_source(gl, &vert, &frag)?;
+
+		material.set_float("ambient", 0.1);
+
+		for node in &self.nodes {
+			if let GraphNode::Parameter { name, ty, default } = node {
+				let value = match ty {
+					ValueType::Float => Uniform::Float(default.x),
+					ValueType::Vec2 => Uniform::Vec2(default.truncate().truncate()),
+					ValueType::Vec3 => Uniform::Vec3(default.truncate()),
+					ValueType::Vec4 => Uniform::Vec4(*default),
+				};
+				material.set(name, value);
+			}
+		}
+
+		Ok(material)
+	}
+}
+
+struct Compiler<'a> {
+	graph: &'a MaterialGraph,
+	types: Vec<Option<ValueType>>,
+	visiting: Vec<bool>,
+	/// Uniform names and their declarations.
+	uniforms: Vec<(String, String)>,
+	body: String,
+	lit: bool,
+}
+
+impl Compiler<'_> {
+	/// Emits a node and its inputs, returning the node's type.
+	fn emit(&mut self, id: GraphNodeId) -> Result<ValueType, String> {
+		let node = self.graph.nodes.get(id.0).ok_or_else(|| format!("Missing node {}", id.0))?;
+
+		if let Some(ty) = self.types[id.0] {
+			return Ok(ty);
+		}
+
+		if self.visiting[id.0] {
+			return Err(format!("Material graph contains a cycle through node {}", id.0));
+		}
+
+		self.visiting[id.0] = true;
+
+		let (ty, expr) = match node {
+			GraphNode::Float { value } => (ValueType::Float, float(*value)?),
+			GraphNode::Color { value } => (
+				ValueType::Vec3,
+				format!("toLinear(vec3({}, {}, {}))", float(value.x)?, float(value.y)?, float(value.z)?),
+			),
+			GraphNode::Parameter { name, ty, .. } => {
+				self.declare(name, &format!("uniform {} {};", ty.glsl(), name))?;
+
+				match ty {
+					ValueType::Vec3 | ValueType::Vec4 => (*ty, format!("toLinear({})", name)),
+					_ => (*ty, name.clone()),
+				}
+			}
+			GraphNode::Texture { name, uv } => {
+				if self.emit(*uv)? != ValueType::Vec2 {
+					return Err(format!("Texture node {} needs vec2 coordinates", id.0));
+				}
+
+				self.declare(name, &format!("uniform sampler2D {};", name))?;
+				(ValueType::Vec4, format!("toLinear(texture2D({}, n{}))", name, uv.0))
+			}
+			GraphNode::WorldPosition => (ValueType::Vec3, "vWorldPos".into()),
+			GraphNode::Normal => (ValueType::Vec3, "normalize(vNormal)".into()),
+			GraphNode::ViewDirection => (ValueType::Vec3, "normalize(cameraPosition - vWorldPos)".into()),
+			GraphNode::Add { a, b } => (self.unify(id, *a, *b)?, format!("n{} + n{}", a.0, b.0)),
+			GraphNode::Subtract { a, b } => (self.unify(id, *a, *b)?, format!("n{} - n{}", a.0, b.0)),
+			GraphNode::Multiply { a, b } => (self.unify(id, *a, *b)?, format!("n{} * n{}", a.0, b.0)),
+			GraphNode::Mix { a, b, t } => {
+				let ty = self.unify(id, *a, *b)?;
+				let t_ty = self.emit(*t)?;
+
+				if t_ty != ValueType::Float && t_ty != ty {
+					return Err(format!("Mix node {} can't blend {} by {}", id.0, ty.glsl(), t_ty.glsl()));
+				}
+
+				(ty, format!("mix({}, {}, n{})", self.cast(*a, ty), self.cast(*b, ty), t.0))
+			}
+			GraphNode::Swizzle { input, mask } => {
+				let input_ty = self.emit(*input)?;
+				let valid = ["xyzw", "rgba"].iter().any(|set| {
+					mask.chars().all(|c| set[..input_ty.components()].contains(c))
+				});
+
+				let ty = ValueType::with_components(mask.len())
+					.filter(|_| valid && input_ty != ValueType::Float)
+					.ok_or_else(|| format!("Invalid swizzle '{}' on {} in node {}", mask, input_ty.glsl(), id.0))?;
+
+				(ty, format!("n{}.{}", input.0, mask))
+			}
+			GraphNode::Fresnel { power } => (
+				ValueType::Float,
+				format!(
+					"pow(1.0 - max(dot(normalize(vNormal), normalize(cameraPosition - vWorldPos)), 0.0), {})",
+					float(*power)?
+				),
+			),
+			GraphNode::Lighting { albedo, model } => {
+				let albedo_ty = self.emit(*albedo)?;
+
+				if albedo_ty == ValueType::Vec2 {
+					return Err(format!("Lighting node {} needs a vec3 albedo", id.0));
+				}
+
+				let (shininess, specular) = match model {
+					LightingModel::Lambert => (1.0, 0.0),
+					LightingModel::Phong { shininess, specular } => (*shininess, *specular),
+				};
+
+				self.lit = true;
+				(
+					ValueType::Vec3,
+					format!(
+						"shadeSurface({}, normalize(vNormal), normalize(cameraPosition - vWorldPos), vWorldPos, calculateShadow(vPosLightSpace), {}, {})",
+						self.cast(*albedo, ValueType::Vec3), float(shininess)?, float(specular)?
+					),
+				)
+			}
+		};
+
+		self.body.push_str(&format!("\t{} n{} = {};\n", ty.glsl(), id.0, expr));
+		self.visiting[id.0] = false;
+		self.types[id.0] = Some(ty);
+
+		Ok(ty)
+	}
+
+	/// Emits two operands and returns their common type, broadcasting floats.
+	fn unify(&mut self, id: GraphNodeId, a: GraphNodeId, b: GraphNodeId) -> Result<ValueType, String> {
+		let a_ty = self.emit(a)?;
+		let b_ty = self.emit(b)?;
+
+		let ty = match (a_ty, b_ty) {
+			(ValueType::Float, ty) | (ty, ValueType::Float) => ty,
+			(a_ty, b_ty) if a_ty == b_ty => a_ty,
+			_ => return Err(format!("Node {} mixes {} and {}", id.0, a_ty.glsl(), b_ty.glsl())),
+		};
+
+		Ok(ty)
+	}
+
+	/// Returns an already emitted node converted to a type.
+	///
+	/// Floats are broadcast and `vec4`s are truncated to `vec3`.
+	fn cast(&self, id: GraphNodeId, ty: ValueType) -> String {
+		match (self.types[id.0], ty) {
+			(Some(from), to) if from == to => format!("n{}", id.0),
+			(Some(ValueType::Float), to) => format!("{}(n{})", to.glsl(), id.0),
+			(Some(ValueType::Vec4), ValueType::Vec3) => format!("n{}.rgb", id.0),
+			_ => format!("n{}", id.0),
+		}
+	}
+
+	fn declare(&mut self, name: &str, declaration: &str) -> Result<(), String> {
+		let valid = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+			&& name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+
+		if !valid {
+			return Err(format!("Invalid uniform name '{}'", name));
+		}
+
+		// Node values are emitted as `n<index>`, and GLSL reserves the rest
+		let node_name = name.strip_prefix('n').is_some_and(|i| !i.is_empty() && i.chars().all(|c| c.is_ascii_digit()));
+
+		if node_name || name.starts_with("gl_") || name.contains("__") {
+			return Err(format!("Uniform name '{}' is reserved", name));
+		}
+
+		match self.uniforms.iter().find(|(n, _)| n == name) {
+			Some((_, existing)) if existing != declaration => {
+				Err(format!("Uniform '{}' is declared with conflicting types", name))
+			}
+			Some(_) => Ok(()),
+			None => {
+				self.uniforms.push((name.to_string(), declaration.to_string()));
+				Ok(())
+			}
+		}
+	}
+}
+
+/// GLSL keywords that may not appear in the generated source but still
+/// can't be used as names.
+const GLSL_KEYWORDS: &[&str] = &[
+	"attribute", "const", "uniform", "varying", "layout", "centroid", "flat", "smooth", "break",
+	"continue", "do", "for", "while", "switch", "case", "default", "if", "else", "in", "out",
+	"inout", "float", "int", "uint", "void", "bool", "true", "false", "invariant", "discard",
+	"return", "lowp", "mediump", "highp", "precision", "struct", "sampler2D", "samplerCube",
+];
+
+/// Formats a float as a GLSL literal.
+///
+/// Returns an error for NaN and infinities, which GLSL has no literal for.
+fn float(value: f32) -> Result<String, String> {
+	if !value.is_finite() {
+		return Err(format!("Material graph constant {} is not finite", value));
+	}
+
+	Ok(format!("{:?}", value))
+}
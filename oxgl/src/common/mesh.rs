@@ -6,7 +6,7 @@
 //! ## Examples
 //!
 //! ```ignore
-//! use oxgl::common::{Mesh, material::presets};
+//! use oxgl::common::{Mesh, MeshBuilder, material::presets};
 //! use oxgl::renderer_3d::Primitive;
 //! use glam::Vec3;
 //!
@@ -19,17 +19,28 @@
 //!
 //! // Load from OBJ file
 //! let meshes = Mesh::from_obj(&gl, obj_content, material).unwrap();
+//!
+//! // Custom interleaved layout for a shader with per-vertex colors
+//! let mesh = MeshBuilder::new(&vertices, 9)
+//!		.attribute("position", 3, 0)
+//!		.attribute("normal", 3, 3)
+//!		.attribute("vertexColor", 3, 6)
+//!		.build(&gl, material)?;
 //! ```
 //!
 
+use glam::{Mat4, Vec3};
 use web_sys::{WebGlBuffer, WebGlProgram, WebGl2RenderingContext as GL};
 
-use super::{Camera, Material, MeshData};
+use super::{Camera, Material, MeshData, MtlMaterial};
 use crate::{
 	renderer_3d::{VertexData, Light},
-	core::{Transform3D, Transformable}
+	core::{Aabb, Transform3D, Transformable}
 };
 
+/// Per-instance model matrix columns read by the `instancing` shader chunk.
+pub(crate) const INSTANCE_MATRIX_ATTRIBUTES: [&str; 4] = ["instanceMatrix0", "instanceMatrix1", "instanceMatrix2", "instanceMatrix3"];
+
 /// A renderable 3D mesh with associated material.
 ///
 /// Manages vertex buffer data on the GPU and provides methods for rendering
@@ -41,17 +52,27 @@ use crate::{
 /// - [`Mesh::with_normals`] - Mesh with interleaved position and normal data
 /// - [`Mesh::from_data`] - From [`MeshData`] struct
 /// - [`Mesh::from_obj`] - Parse from OBJ file content
+/// - [`Mesh::from_obj_with_mtl`] - Parse from OBJ and MTL file content
+/// - [`MeshBuilder`] - Arbitrary interleaved attributes and instancing
 ///
 /// ## Rendering
 ///
 /// - [`Mesh::draw`] - Full render with material, lighting, and transforms
 /// - [`Mesh::draw_depth_only`] - Depth-only render for shadow passes
 ///
+/// ## Sharing
+///
+/// Cloning a mesh shares its GPU buffers, so vertex updates on one clone
+/// show up in all of them. Each clone has its own material.
+///
+#[derive(Clone)]
 pub struct Mesh {
-	vertex_buffer: WebGlBuffer,
+	buffers: Vec<AttributeBuffer>,
 	vertex_count: i32,
-	stride: i32,
-	has_normals: bool,
+	instance_count: Option<i32>,
+	bounds: Aabb,
+	/// Name of the OBJ object or group the mesh was loaded from.
+	pub name: Option<String>,
 	pub material: Material,
 }
 
@@ -75,31 +96,27 @@ impl Mesh {
 	/// let mesh = Mesh::new(&gl, &vertices, material);
 	/// ```
 	pub fn new(gl: &GL, vertices: &[f32], material: Material) -> Self {
-		let vertex_buffer = gl.create_buffer().expect("Failed to create buffer");
-
-		gl.bind_buffer(GL::ARRAY_BUFFER, Some(&vertex_buffer));
-
-		let vert_array = unsafe {
-			std::slice::from_raw_parts(
-				vertices.as_ptr() as *const u8,
-				vertices.len() * std::mem::size_of::<f32>(),
-			)
-		};
-
-		gl.buffer_data_with_u8_array(GL::ARRAY_BUFFER, vert_array, GL::STATIC_DRAW);
+		let buffer = upload(gl, vertices, GL::STATIC_DRAW).expect("Failed to create buffer");
 
 		Self {
-			vertex_buffer,
+			buffers: vec![AttributeBuffer {
+				buffer,
+				stride: 3,
+				attributes: vec![VertexAttribute::new("position", 3, 0)],
+			}],
 			vertex_count: (vertices.len() / 3) as i32,
-			stride: 3 * 4,
-			has_normals: false,
+			instance_count: None,
+			bounds: Aabb::from_vertices(vertices, 3),
+			name: None,
 			material,
 		}
 	}
 
 	/// Creates a mesh from [`MeshData`].
 	///
-	/// Converts the mesh data to interleaved vertex format with normals.
+	/// Converts the mesh data to interleaved vertex format with normals,
+	/// and vertex colors if it has any. UVs, when every vertex has one, go
+	/// in a second buffer as the `uv` attribute.
 	///
 	/// # Examples
 	///
@@ -115,19 +132,29 @@ impl Mesh {
 	/// let mesh = Mesh::from_data(&gl, &data, material);
 	/// ```
 	pub fn from_data(gl: &GL, data: &MeshData, material: Material) -> Self {
-		let vertices = data.interleaved_vertices();
-		let vertex_data = VertexData {
-			data: vertices,
-			vertex_count: data.positions.len() as i32 / 3,
-		};
+		let mut builder = MeshBuilder::new(&data.interleaved_vertices(), data.vertex_stride())
+			.attribute("position", 3, 0)
+			.attribute("normal", 3, 3);
+
+		if !data.colors.is_empty() {
+			builder = builder.attribute("vertexColor", 3, 6);
+		}
 
-		Self::with_normals(gl, &vertex_data, material)
+		if !data.uvs.is_empty() && data.uvs.len() / 2 == data.positions.len() / 3 {
+			builder = builder.buffer(&data.uvs, 2).attribute("uv", 2, 0);
+		}
+
+		let mut mesh = builder.build(gl, material).expect("Failed to create buffer");
+		mesh.name = data.name.clone();
+		mesh
 	}
 
 	/// Creates meshes from OBJ file content.
 	///
-	/// Parses the OBJ content and creates a mesh for each object/group found.
-	/// All meshes share the same material.
+	/// Parses the OBJ content and creates a mesh for each object/group found,
+	/// named after it. All meshes share the same material.
+	///
+	/// See [`MeshData::from_obj`] for how the file is split.
 	///
 	/// # Errors
 	///
@@ -142,7 +169,8 @@ impl Mesh {
 	/// let meshes = Mesh::from_obj(&gl, obj_content, material)?;
 	///
 	/// for mesh in meshes {
-	///		// Add each mesh to the scene
+	///		// Each mesh can be transformed independently
+	///		scene.add(mesh, Transform3D::new());
 	/// }
 	/// ```
 	pub fn from_obj(gl: &GL, obj_content: &str, material: Material) -> Result<Vec<Self>, String> {
@@ -154,6 +182,42 @@ impl Mesh {
 			.collect())
 	}
 
+	/// Creates meshes from OBJ file content and its MTL material library.
+	///
+	/// Each mesh gets a material built from the MTL definition it uses, see
+	/// [`MtlMaterial::to_material`]. Meshes without a matching definition use
+	/// `fallback`.
+	///
+	/// # Errors
+	///
+	/// Returns an error string if either file can't be parsed.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use oxgl::common::Mesh;
+	///
+	/// let obj_content = include_str!("model.obj");
+	/// let mtl_content = include_str!("model.mtl");
+	/// let meshes = Mesh::from_obj_with_mtl(&gl, obj_content, mtl_content, material)?;
+	/// ```
+	pub fn from_obj_with_mtl(gl: &GL, obj_content: &str, mtl_content: &str, fallback: Material) -> Result<Vec<Self>, String> {
+		let mesh_data = MeshData::from_obj(obj_content)?;
+		let materials = MtlMaterial::parse(mtl_content)?;
+
+		Ok(mesh_data
+			.iter()
+			.map(|data| {
+				let material = data.material
+					.as_ref()
+					.and_then(|name| materials.iter().find(|m| &m.name == name))
+					.map_or_else(|| fallback.clone(), |m| m.to_material(gl));
+
+				Self::from_data(gl, data, material)
+			})
+			.collect())
+	}
+
 	/// Creates a mesh with interleaved position and normal data.
 	///
 	/// This is the preferred constructor for meshes that will be rendered
@@ -170,28 +234,144 @@ impl Mesh {
 	/// let mesh = Mesh::with_normals(&gl, &cube_data, material);
 	/// ```
 	pub fn with_normals(gl: &GL, data: &VertexData, material: Material) -> Self {
-		let vertex_buffer = gl.create_buffer().expect("Failed to create buffer");
-
-		gl.bind_buffer(GL::ARRAY_BUFFER, Some(&vertex_buffer));
-
-		let vert_array = unsafe {
-			std::slice::from_raw_parts(
-				data.data.as_ptr() as *const u8,
-				data.data.len() * std::mem::size_of::<f32>(),
-			)
-		};
-
-		gl.buffer_data_with_u8_array(GL::ARRAY_BUFFER, vert_array, GL::STATIC_DRAW);
+		let buffer = upload(gl, &data.data, GL::STATIC_DRAW).expect("Failed to create buffer");
 
 		Self {
-			vertex_buffer,
+			buffers: vec![AttributeBuffer {
+				buffer,
+				stride: 6,
+				attributes: vec![
+					VertexAttribute::new("position", 3, 0),
+					VertexAttribute::new("normal", 3, 3),
+				],
+			}],
 			vertex_count: data.vertex_count,
-			stride: 6 * 4,
-			has_normals: true,
+			instance_count: None,
+			bounds: Aabb::from_vertices(&data.data, 6),
+			name: None,
 			material,
 		}
 	}
 
+	/// Returns the number of vertices drawn per instance.
+	pub fn vertex_count(&self) -> i32 {
+		self.vertex_count
+	}
+
+	/// Returns the number of triangles drawn per call, across all instances.
+	pub fn triangle_count(&self) -> u32 {
+		self.vertex_count as u32 / 3 * self.instance_count.unwrap_or(1) as u32
+	}
+
+	/// Returns the number of instances drawn, or `None` for a regular mesh.
+	pub fn instance_count(&self) -> Option<i32> {
+		self.instance_count
+	}
+
+	/// Sets the number of instances drawn per call.
+	///
+	/// Attributes with a divisor advance once per `divisor` instances, so
+	/// their buffers must hold enough entries for the count.
+	pub fn set_instance_count(&mut self, count: Option<i32>) {
+		self.instance_count = count;
+	}
+
+	/// Returns the vertex attributes of every buffer.
+	pub fn attributes(&self) -> impl Iterator<Item = &VertexAttribute> {
+		self.buffers.iter().flat_map(|buffer| &buffer.attributes)
+	}
+
+	/// Returns the local-space bounds of the vertex data.
+	///
+	/// Computed at creation and kept up to date by
+	/// [`update_vertices`](Self::update_vertices).
+	pub fn bounds(&self) -> Aabb {
+		self.bounds
+	}
+
+	/// Returns the bounds used for frustum culling, grown by the material's
+	/// [`cull_padding`](crate::common::Material::cull_padding).
+	pub fn cull_bounds(&self) -> Aabb {
+		self.bounds.expanded(self.material.cull_padding)
+	}
+
+	/// Replaces the per-vertex data in the first buffer.
+	///
+	/// `data` uses the same interleaved layout the mesh was created with.
+	/// Data of the same size is written in place with `bufferSubData`;
+	/// a new size reallocates the buffer with `DYNAMIC_DRAW` and changes
+	/// the vertex count. Bounds are recomputed.
+	///
+	/// # Errors
+	///
+	/// Returns an error if `data` isn't a whole number of vertices.
+	///
+	/// # Examples
+	///
+	/// ```ignore
+	/// // Ripple a water surface every frame
+	/// for (i, vertex) in water.chunks_exact_mut(6).enumerate() {
+	///		vertex[1] = (time + i as f32 * 0.1).sin() * 0.2;
+	/// }
+	///
+	/// scene.objects[surface].mesh.update_vertices(&gl, &water)?;
+	/// ```
+	pub fn update_vertices(&mut self, gl: &GL, data: &[f32]) -> Result<(), String> {
+		let buffer = &self.buffers[0];
+		let stride = buffer.stride as usize;
+
+		if !data.len().is_multiple_of(stride) {
+			return Err(format!("Vertex data has {} floats, not a multiple of the stride {}", data.len(), stride));
+		}
+
+		let vertex_count = (data.len() / stride) as i32;
+
+		gl.bind_buffer(GL::ARRAY_BUFFER, Some(&buffer.buffer));
+
+		if vertex_count == self.vertex_count {
+			gl.buffer_sub_data_with_i32_and_u8_array(GL::ARRAY_BUFFER, 0, as_bytes(data));
+		} else {
+			gl.buffer_data_with_u8_array(GL::ARRAY_BUFFER, as_bytes(data), GL::DYNAMIC_DRAW);
+			self.vertex_count = vertex_count;
+		}
+
+		self.bounds = position_bounds(data, stride, &buffer.attributes);
+
+		Ok(())
+	}
+
+	/// Overwrites vertices starting at `first_vertex` in the first buffer.
+	///
+	/// Only the given range is uploaded, so small edits to large meshes stay
+	/// cheap. Bounds grow to include the new vertices but don't shrink; call
+	/// [`update_vertices`](Self::update_vertices) to recompute them exactly.
+	///
+	/// # Errors
+	///
+	/// Returns an error if `data` isn't a whole number of vertices or runs
+	/// past the end of the buffer.
+	pub fn update_vertex_range(&mut self, gl: &GL, first_vertex: usize, data: &[f32]) -> Result<(), String> {
+		let buffer = &self.buffers[0];
+		let stride = buffer.stride as usize;
+
+		if !data.len().is_multiple_of(stride) {
+			return Err(format!("Vertex data has {} floats, not a multiple of the stride {}", data.len(), stride));
+		}
+		if first_vertex + data.len() / stride > self.vertex_count as usize {
+			return Err(format!(
+				"Vertices {}..{} are past the end of the mesh ({} vertices)",
+				first_vertex, first_vertex + data.len() / stride, self.vertex_count
+			));
+		}
+
+		gl.bind_buffer(GL::ARRAY_BUFFER, Some(&buffer.buffer));
+		gl.buffer_sub_data_with_i32_and_u8_array(GL::ARRAY_BUFFER, (first_vertex * stride * 4) as i32, as_bytes(data));
+
+		self.bounds = self.bounds.union(position_bounds(data, stride, &buffer.attributes));
+
+		Ok(())
+	}
+
 	/// Renders the mesh for depth-only passes.
 	///
 	/// Used for shadow map generation where only depth information is needed.
@@ -208,18 +388,43 @@ impl Mesh {
 	/// mesh.draw_depth_only(&gl, &shadow_program);
 	/// ```
 	pub fn draw_depth_only(&self, gl: &GL, program: &WebGlProgram) {
-		gl.bind_buffer(GL::ARRAY_BUFFER, Some(&self.vertex_buffer));
+		self.draw_arrays(gl, program);
+	}
+
+	/// Sets a second set of texture coordinates, read by shaders as the
+	/// `uv2` attribute, for [lightmaps](Material::set_lightmap).
+	///
+	/// `uvs` holds two floats per vertex. Lightmap UVs must not overlap,
+	/// and islands need a few texels of padding between them.
+	///
+	/// # Errors
+	///
+	/// Returns an error if `uvs` doesn't have two floats per vertex or the
+	/// buffer can't be created.
+	pub fn set_lightmap_uvs(&mut self, gl: &GL, uvs: &[f32]) -> Result<(), String> {
+		if uvs.len() != self.vertex_count as usize * 2 {
+			return Err(format!("Expected {} lightmap UV floats for {} vertices, got {}", self.vertex_count * 2, self.vertex_count, uvs.len()));
+		}
 
-		let pos_loc = gl.get_attrib_location(program, "position");
+		let buffer = upload(gl, uvs, GL::STATIC_DRAW)?;
 
-		if pos_loc >= 0 {
-			gl.enable_vertex_attrib_array(pos_loc as u32);
-			gl.vertex_attrib_pointer_with_i32(
-				pos_loc as u32, 3, GL::FLOAT, false, self.stride, 0
-			);
+		for existing in &mut self.buffers {
+			existing.attributes.retain(|attribute| attribute.name != "uv2");
 		}
 
-		gl.draw_arrays(GL::TRIANGLES, 0, self.vertex_count);
+		self.buffers.retain(|existing| !existing.attributes.is_empty());
+		self.buffers.push(AttributeBuffer {
+			buffer,
+			stride: 2,
+			attributes: vec![VertexAttribute::new("uv2", 2, 0)],
+		});
+
+		Ok(())
+	}
+
+	/// Returns whether the mesh has [lightmap UVs](Self::set_lightmap_uvs).
+	pub fn has_lightmap_uvs(&self) -> bool {
+		self.attributes().any(|attribute| attribute.name == "uv2")
 	}
 
 	/// Renders the mesh with full material and lighting.
@@ -239,14 +444,28 @@ impl Mesh {
 	/// mesh.draw(&gl, &transform, &camera, &lights);
 	/// ```
 	pub fn draw(&self, gl: &GL, transform: &Transform3D, camera: &Camera, lights: &[Light]) {
-		let program = self.material.program();
+		self.draw_with_matrix(gl, &transform.to_matrix(), camera, lights);
+	}
+
+	/// Renders the mesh with an explicit model matrix.
+	///
+	/// Same as [`Mesh::draw`], but takes a precomputed world matrix. Used by
+	/// [`Scene`](crate::renderer_3d::Scene) for parented objects.
+	pub fn draw_with_matrix(&self, gl: &GL, model: &Mat4, camera: &Camera, lights: &[Light]) {
+		self.draw_with_material(gl, model, camera, lights, &self.material);
+	}
+
+	/// Renders the mesh with another material than its own, such as a mask
+	/// for a separate pass.
+	pub fn draw_with_material(&self, gl: &GL, model: &Mat4, camera: &Camera, lights: &[Light], material: &Material) {
+		let program = material.program();
 
 		gl.use_program(Some(program));
-		self.material.apply(gl, lights);
+		material.apply(gl, lights);
 
 		if let Some(loc) = gl.get_uniform_location(program, "model") {
 			gl.uniform_matrix4fv_with_f32_array(
-				Some(&loc), false, &transform.to_matrix().to_cols_array()
+				Some(&loc), false, &model.to_cols_array()
 			);
 		}
 		if let Some(loc) = gl.get_uniform_location(program, "view") {
@@ -264,29 +483,337 @@ impl Mesh {
 				Some(&loc), &camera.position.to_array()
 			);
 		}
+		if let Some(loc) = gl.get_uniform_location(program, "logDepthFactor") {
+			gl.uniform1f(Some(&loc), camera.log_depth_factor());
+		}
 
-		gl.bind_buffer(GL::ARRAY_BUFFER, Some(&self.vertex_buffer));
+		let culling = material.double_sided && gl.is_enabled(GL::CULL_FACE);
 
-		let pos_loc = gl.get_attrib_location(program, "position");
+		if culling {
+			gl.disable(GL::CULL_FACE);
+		}
 
-		if pos_loc >= 0 {
-			gl.enable_vertex_attrib_array(pos_loc as u32);
-			gl.vertex_attrib_pointer_with_i32(
-				pos_loc as u32, 3, GL::FLOAT, false, self.stride, 0
-			);
+		self.draw_arrays(gl, program);
+
+		if culling {
+			gl.enable(GL::CULL_FACE);
 		}
+	}
 
-		if self.has_normals {
-			let norm_loc = gl.get_attrib_location(program, "normal");
+	/// Binds the per-vertex attribute `name` to `loc` so it advances once per
+	/// instance, for drawing one shape per vertex such as the
+	/// [normal gizmos](crate::renderer_3d::GizmoRenderer::vertex_vectors).
+	/// Returns the number of vertices, or `None` if the mesh has no such
+	/// attribute.
+	///
+	/// Callers reset the divisor of `loc` to `0` after drawing.
+	pub(crate) fn bind_per_instance(&self, gl: &GL, name: &str, loc: u32) -> Option<i32> {
+		let (buffer, attribute) = self.buffers.iter().find_map(|buffer| {
+			buffer.attributes.iter()
+				.find(|attribute| attribute.name == name && attribute.divisor == 0)
+				.map(|attribute| (buffer, attribute))
+		})?;
+
+		gl.bind_buffer(GL::ARRAY_BUFFER, Some(&buffer.buffer));
+		gl.enable_vertex_attrib_array(loc);
+		gl.vertex_attrib_pointer_with_i32(loc, attribute.size, GL::FLOAT, false, buffer.stride * 4, attribute.offset * 4);
+		gl.vertex_attrib_divisor(loc, 1);
+
+		Some(self.vertex_count)
+	}
+
+	/// Binds every attribute the program uses and issues the draw call.
+	///
+	/// Attributes other than `position` are disabled again afterwards and
+	/// their divisors reset, so they don't leak into meshes drawn later.
+	/// Shaders reading `vertexColor` get white for meshes without colors,
+	/// and shaders reading the `instancing` chunk get the identity for
+	/// meshes without instance matrices.
+	fn draw_arrays(&self, gl: &GL, program: &WebGlProgram) {
+		let color_loc = gl.get_attrib_location(program, "vertexColor");
 
-			if norm_loc >= 0 {
-				gl.enable_vertex_attrib_array(norm_loc as u32);
+		if color_loc >= 0 {
+			gl.vertex_attrib3f(color_loc as u32, 1.0, 1.0, 1.0);
+		}
+
+		for (column, name) in INSTANCE_MATRIX_ATTRIBUTES.iter().enumerate() {
+			let loc = gl.get_attrib_location(program, name);
+
+			if loc >= 0 {
+				let mut value = [0.0; 4];
+				value[column] = 1.0;
+				gl.vertex_attrib4fv_with_f32_array(loc as u32, &value);
+			}
+		}
+
+		let mut enabled = Vec::new();
+
+		for buffer in &self.buffers {
+			gl.bind_buffer(GL::ARRAY_BUFFER, Some(&buffer.buffer));
+
+			for attribute in &buffer.attributes {
+				let loc = gl.get_attrib_location(program, &attribute.name);
+
+				if loc < 0 {
+					continue;
+				}
+
+				let loc = loc as u32;
+
+				gl.enable_vertex_attrib_array(loc);
 				gl.vertex_attrib_pointer_with_i32(
-					norm_loc as u32, 3, GL::FLOAT, false, self.stride, 12
+					loc, attribute.size, GL::FLOAT, false, buffer.stride * 4, attribute.offset * 4
 				);
+				gl.vertex_attrib_divisor(loc, attribute.divisor);
+
+				if attribute.name != "position" {
+					enabled.push((loc, attribute.divisor));
+				}
+			}
+		}
+
+		match self.instance_count {
+			Some(count) => gl.draw_arrays_instanced(GL::TRIANGLES, 0, self.vertex_count, count),
+			None => gl.draw_arrays(GL::TRIANGLES, 0, self.vertex_count),
+		}
+
+		for (loc, divisor) in enabled {
+			if divisor != 0 {
+				gl.vertex_attrib_divisor(loc, 0);
+			}
+
+			gl.disable_vertex_attrib_array(loc);
+		}
+	}
+}
+
+/// A vertex attribute read from an interleaved buffer.
+///
+/// Sizes and offsets are counted in floats.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VertexAttribute {
+	/// Name of the `attribute` in the shader.
+	pub name: String,
+	/// Number of components, from 1 to 4.
+	pub size: i32,
+	/// Offset from the start of each vertex.
+	pub offset: i32,
+	/// Number of instances drawn before the attribute advances, or `0` to
+	/// advance per vertex.
+	pub divisor: u32,
+}
+
+impl VertexAttribute {
+	pub fn new(name: &str, size: i32, offset: i32) -> Self {
+		Self { name: name.to_string(), size, offset, divisor: 0 }
+	}
+
+	pub fn with_divisor(mut self, divisor: u32) -> Self {
+		self.divisor = divisor;
+		self
+	}
+}
+
+/// A GPU buffer and the attributes interleaved in it.
+#[derive(Clone)]
+struct AttributeBuffer {
+	buffer: WebGlBuffer,
+	/// Floats per vertex.
+	stride: i32,
+	attributes: Vec<VertexAttribute>,
+}
+
+/// Vertex data waiting to be uploaded by a [`MeshBuilder`].
+struct BufferLayout {
+	data: Vec<f32>,
+	stride: usize,
+	attributes: Vec<VertexAttribute>,
+}
+
+/// Builds a [`Mesh`] with an arbitrary vertex layout.
+///
+/// Each buffer holds interleaved floats, and each attribute names the
+/// shader input it feeds along with its size and offset within a vertex.
+/// Attributes apply to the most recently added buffer.
+///
+/// The first buffer is per-vertex data and sets the vertex count. Further
+/// buffers can hold more per-vertex data or, with a divisor, per-instance
+/// data for [`draw_arrays_instanced`](GL::draw_arrays_instanced).
+///
+/// # Examples
+///
+/// ```ignore
+/// use oxgl::common::MeshBuilder;
+///
+/// // Position and color interleaved, one offset per instance
+/// let mesh = MeshBuilder::new(&vertices, 6)
+///		.attribute("position", 3, 0)
+///		.attribute("vertexColor", 3, 3)
+///		.buffer(&offsets, 3)
+///		.attribute_with_divisor("instanceOffset", 3, 0, 1)
+///		.instances(100)
+///		.build(&gl, material)?;
+/// ```
+pub struct MeshBuilder {
+	buffers: Vec<BufferLayout>,
+	instance_count: Option<i32>,
+	bounds: Option<Aabb>,
+	name: Option<String>,
+	usage: u32,
+}
+
+impl MeshBuilder {
+	/// Starts a mesh from per-vertex data with `stride` floats per vertex.
+	pub fn new(vertices: &[f32], stride: usize) -> Self {
+		Self {
+			buffers: vec![BufferLayout { data: vertices.to_vec(), stride, attributes: Vec::new() }],
+			instance_count: None,
+			bounds: None,
+			name: None,
+			usage: GL::STATIC_DRAW,
+		}
+	}
+
+	/// Adds another buffer with `stride` floats per entry.
+	pub fn buffer(mut self, data: &[f32], stride: usize) -> Self {
+		self.buffers.push(BufferLayout { data: data.to_vec(), stride, attributes: Vec::new() });
+		self
+	}
+
+	/// Adds a per-vertex attribute to the last buffer.
+	pub fn attribute(self, name: &str, size: i32, offset: i32) -> Self {
+		self.attribute_with_divisor(name, size, offset, 0)
+	}
+
+	/// Adds an attribute to the last buffer that advances once every
+	/// `divisor` instances.
+	pub fn attribute_with_divisor(mut self, name: &str, size: i32, offset: i32, divisor: u32) -> Self {
+		if let Some(buffer) = self.buffers.last_mut() {
+			buffer.attributes.push(VertexAttribute::new(name, size, offset).with_divisor(divisor));
+		}
+
+		self
+	}
+
+	/// Draws the mesh `count` times in one call.
+	///
+	/// Without this, meshes with divisor attributes draw as many instances
+	/// as their per-instance buffers hold.
+	pub fn instances(mut self, count: i32) -> Self {
+		self.instance_count = Some(count);
+		self
+	}
+
+	/// Overrides the bounds computed from the `position` attribute, such as
+	/// for instances spread beyond the vertex data, so culling and picking
+	/// see all of them.
+	pub fn with_bounds(mut self, bounds: Aabb) -> Self {
+		self.bounds = Some(bounds);
+		self
+	}
+
+	pub fn with_name(mut self, name: &str) -> Self {
+		self.name = Some(name.to_string());
+		self
+	}
+
+	/// Hints that the vertices will be rewritten often with
+	/// [`Mesh::update_vertices`], allocating the buffers with `DYNAMIC_DRAW`.
+	pub fn dynamic(mut self) -> Self {
+		self.usage = GL::DYNAMIC_DRAW;
+		self
+	}
+
+	/// Uploads the buffers and creates the mesh.
+	///
+	/// Unless set with [`with_bounds`](Self::with_bounds), bounds are
+	/// computed from the `position` attribute of the first buffer, if there
+	/// is one.
+	///
+	/// # Errors
+	///
+	/// Returns an error if a stride doesn't divide its buffer, an attribute
+	/// doesn't fit in its stride, or a buffer can't be created.
+	pub fn build(self, gl: &GL, material: Material) -> Result<Mesh, String> {
+		for (i, layout) in self.buffers.iter().enumerate() {
+			if layout.stride == 0 || layout.data.len() % layout.stride != 0 {
+				return Err(format!("Buffer {} has {} floats, not a multiple of its stride {}", i, layout.data.len(), layout.stride));
+			}
+
+			for attribute in &layout.attributes {
+				if !(1..=4).contains(&attribute.size) {
+					return Err(format!("Attribute '{}' has size {}, expected 1 to 4", attribute.name, attribute.size));
+				}
+				if attribute.offset < 0 || (attribute.offset + attribute.size) as usize > layout.stride {
+					return Err(format!("Attribute '{}' doesn't fit in a stride of {}", attribute.name, layout.stride));
+				}
 			}
 		}
 
-		gl.draw_arrays(GL::TRIANGLES, 0, self.vertex_count);
+		let vertices = &self.buffers[0];
+		let vertex_count = (vertices.data.len() / vertices.stride) as i32;
+		let bounds = self.bounds.unwrap_or_else(|| position_bounds(&vertices.data, vertices.stride, &vertices.attributes));
+
+		// Instanced buffers limit how many instances can be drawn
+		let instance_count = self.instance_count.or_else(|| {
+			self.buffers
+				.iter()
+				.flat_map(|layout| {
+					layout.attributes
+						.iter()
+						.filter(|a| a.divisor > 0)
+						.map(move |a| (layout.data.len() / layout.stride) as i32 * a.divisor as i32)
+				})
+				.min()
+		});
+
+		let buffers = self.buffers
+			.into_iter()
+			.map(|layout| Ok(AttributeBuffer {
+				buffer: upload(gl, &layout.data, self.usage)?,
+				stride: layout.stride as i32,
+				attributes: layout.attributes,
+			}))
+			.collect::<Result<Vec<_>, String>>()?;
+
+		Ok(Mesh {
+			buffers,
+			vertex_count,
+			instance_count,
+			bounds,
+			name: self.name,
+			material,
+		})
+	}
+}
+
+/// Creates a buffer holding `data`.
+fn upload(gl: &GL, data: &[f32], usage: u32) -> Result<WebGlBuffer, String> {
+	let buffer = gl.create_buffer().ok_or("Failed to create buffer")?;
+
+	gl.bind_buffer(GL::ARRAY_BUFFER, Some(&buffer));
+	gl.buffer_data_with_u8_array(GL::ARRAY_BUFFER, as_bytes(data), usage);
+
+	Ok(buffer)
+}
+
+fn as_bytes(data: &[f32]) -> &[u8] {
+	unsafe {
+		std::slice::from_raw_parts(data.as_ptr() as *const u8, std::mem::size_of_val(data))
 	}
+}
+
+/// Returns the bounds of the `position` attribute in interleaved data, or
+/// an empty box if there is none.
+fn position_bounds(data: &[f32], stride: usize, attributes: &[VertexAttribute]) -> Aabb {
+	let Some(position) = attributes.iter().find(|a| a.name == "position" && a.divisor == 0 && a.size >= 2) else {
+		return Aabb::EMPTY;
+	};
+
+	let offset = position.offset as usize;
+
+	data.chunks_exact(stride).fold(Aabb::EMPTY, |aabb, v| {
+		let z = if position.size >= 3 { v[offset + 2] } else { 0.0 };
+		aabb.including(Vec3::new(v[offset], v[offset + 1], z))
+	})
 }
\ No newline at end of file
@@ -3,16 +3,44 @@
 //! This module provides shared types used across the rendering system.
 //! 
 
+pub mod assets;
+pub mod atlas;
 pub mod camera;
+pub mod cubemap;
+pub mod error;
+pub mod orientation;
 pub mod material;
+pub mod material_graph;
 pub mod mesh;
+pub mod mtl;
 pub mod shader;
 pub mod loader;
 pub mod postprocessing;
+pub mod recorder;
+pub mod render_state;
+pub mod render_target;
+pub mod texture;
+pub mod truetype;
+pub mod uniforms;
 
+pub use assets::{AssetLoader, AssetHandle};
+pub use atlas::{TextureAtlas, UvRect};
 pub use camera::Camera;
+pub use cubemap::CubeMap;
+pub use error::{OxglError, ShaderStage};
+pub use orientation::{DeviceOrientationController, OrientationMode};
 pub use loader::MeshData;
-pub use material::{Uniform, Material, MaterialBuilder, presets};
-pub use mesh::Mesh;
+pub use material::{Uniform, Material, MaterialBuilder, MAX_CLIP_PLANES, apply_clip_planes, presets};
+pub use material_graph::{MaterialGraph, GraphNode, GraphNodeId, LightingModel, ValueType};
+pub use mesh::{Mesh, MeshBuilder, VertexAttribute};
+pub use mtl::MtlMaterial;
+pub use recorder::{Recorder, RecorderState};
+pub use render_state::{RenderState, CullFace, FrontFace, DepthFunc};
+pub use render_target::RenderTarget;
 pub use shader::{compile_shader, link_program};
+pub use truetype::TrueTypeFont;
+pub use texture::{Texture, SamplerDesc, TextureFilter, TextureWrap};
+pub use uniforms::{UniformValue, Uniforms, UniformBlocks, UniformLocations};
+#[cfg(feature = "derive")]
+pub use oxgl_derive::Uniforms;
 pub use postprocessing::{PostProcessStack, PostProcessEffect, PostProcessEffectBuilder};
@@ -0,0 +1,169 @@
+//! MTL Material Libraries
+//!
+//! Parses Wavefront MTL files, the material libraries referenced by OBJ
+//! models through `mtllib`. Each parsed [`MtlMaterial`] can be turned into a
+//! phong [`Material`] for the meshes that use it.
+//!
+//! ## Examples
+//!
+//! ```ignore
+//! use oxgl::common::{Mesh, material::presets};
+//! use glam::Vec3;
+//!
+//! let obj = include_str!("chair.obj");
+//! let mtl = include_str!("chair.mtl");
+//!
+//! // One mesh per material, each with its own colors
+//! let meshes = Mesh::from_obj_with_mtl(&gl, obj, mtl, presets::phong(&gl, Vec3::splat(0.8)))?;
+//! ```
+//!
+
+use glam::Vec3;
+use web_sys::WebGl2RenderingContext as GL;
+
+use super::{Material, presets};
+
+/// A material definition from an MTL file.
+///
+/// Only the properties used by the phong shading model are kept.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MtlMaterial {
+	pub name: String,
+	/// Diffuse color (`Kd`).
+	pub diffuse: Vec3,
+	/// Specular color (`Ks`).
+	pub specular: Vec3,
+	/// Specular exponent (`Ns`).
+	pub shininess: f32,
+	/// Opacity (`d`, or `1 - Tr`).
+	pub opacity: f32,
+	/// Path of the diffuse texture (`map_Kd`), relative to the MTL file.
+	pub diffuse_map: Option<String>,
+}
+
+impl MtlMaterial {
+	pub fn new(name: &str) -> Self {
+		Self {
+			name: name.to_string(),
+			diffuse: Vec3::splat(0.8),
+			specular: Vec3::splat(0.5),
+			shininess: 32.0,
+			opacity: 1.0,
+			diffuse_map: None,
+		}
+	}
+
+	/// Parses all materials in an MTL file.
+	///
+	/// Unknown statements are ignored.
+	///
+	/// # Errors
+	///
+	/// Returns an error if a property appears before any `newmtl`.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use oxgl::common::MtlMaterial;
+	/// use glam::Vec3;
+	///
+	/// let materials = MtlMaterial::parse("
+	///		newmtl wood
+	///		Kd 0.6 0.4 0.2
+	///		Ns 10
+	///		map_Kd textures/wood.png
+	///
+	///		newmtl glass
+	///		d 0.3
+	/// ").unwrap();
+	///
+	/// assert_eq!(materials.len(), 2);
+	/// assert_eq!(materials[0].diffuse, Vec3::new(0.6, 0.4, 0.2));
+	/// assert_eq!(materials[0].diffuse_map.as_deref(), Some("textures/wood.png"));
+	/// assert_eq!(materials[1].opacity, 0.3);
+	/// ```
+	pub fn parse(content: &str) -> Result<Vec<MtlMaterial>, String> {
+		let mut materials: Vec<MtlMaterial> = Vec::new();
+
+		for (number, line) in content.lines().enumerate() {
+			let line = line.trim();
+
+			if line.is_empty() || line.starts_with('#') {
+				continue;
+			}
+
+			let parts: Vec<&str> = line.split_whitespace().collect();
+
+			if parts[0] == "newmtl" {
+				materials.push(MtlMaterial::new(parts.get(1).copied().unwrap_or("")));
+				continue;
+			}
+
+			let Some(current) = materials.last_mut() else {
+				return Err(format!("Line {}: '{}' before newmtl", number + 1, parts[0]));
+			};
+
+			match parts[0] {
+				"Kd" => current.diffuse = parse_color(&parts[1..]).unwrap_or(current.diffuse),
+				"Ks" => current.specular = parse_color(&parts[1..]).unwrap_or(current.specular),
+				"Ns" => {
+					current.shininess = parts.get(1)
+						.and_then(|p| p.parse().ok())
+						.unwrap_or(current.shininess);
+				}
+				"d" => {
+					current.opacity = parts.get(1)
+						.and_then(|p| p.parse().ok())
+						.unwrap_or(current.opacity);
+				}
+				"Tr" => {
+					current.opacity = parts.get(1)
+						.and_then(|p| p.parse::<f32>().ok())
+						.map_or(current.opacity, |t| 1.0 - t);
+				}
+				"map_Kd" => {
+					// Options such as `-s 1 1 1` come before the path
+					current.diffuse_map = parts.last()
+						.filter(|_| parts.len() > 1)
+						.map(|p| p.to_string());
+				}
+				_ => {}
+			}
+		}
+
+		Ok(materials)
+	}
+
+	/// Creates a phong material with this definition's colors.
+	///
+	/// Diffuse maps aren't applied, as meshes don't carry texture
+	/// coordinates yet; the path is kept in [`diffuse_map`](Self::diffuse_map).
+	///
+	/// An [`opacity`](Self::opacity) below 1 is drawn dithered, discarding
+	/// that share of pixels, and always on the forward path. Shadows stay
+	/// solid.
+	pub fn to_material(&self, gl: &GL) -> Material {
+		let mut material = presets::phong(gl, self.diffuse);
+
+		material
+			.set_float("shininess", self.shininess.max(1.0))
+			.set_float("specularStrength", self.specular.max_element());
+
+		if self.opacity < 1.0 {
+			material.set_float("transparency", 1.0 - self.opacity.max(0.0));
+			material.deferred = false;
+		}
+
+		material
+	}
+}
+
+fn parse_color(parts: &[&str]) -> Option<Vec3> {
+	let r: f32 = parts.first()?.parse().ok()?;
+
+	// A single value is used for all channels
+	let g = parts.get(1).and_then(|p| p.parse().ok()).unwrap_or(r);
+	let b = parts.get(2).and_then(|p| p.parse().ok()).unwrap_or(r);
+
+	Some(Vec3::new(r, g, b))
+}
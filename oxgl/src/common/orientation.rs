@@ -0,0 +1,232 @@
+//! Device Orientation Camera Control
+//!
+//! Drives a [`Camera`] from the `deviceorientation` event, so mobile users can
+//! look around a scene by moving their phone.
+//!
+//! iOS 13+ requires explicit permission before orientation events are
+//! delivered, which must be requested from a user gesture such as a click.
+//!
+//! ## Examples
+//!
+//! ```ignore
+//! use oxgl::common::{DeviceOrientationController, OrientationMode};
+//!
+//! let controller = Rc::new(RefCell::new(
+//!		DeviceOrientationController::new()?.with_mode(OrientationMode::Orbit)
+//! ));
+//!
+//! // From a button's click handler
+//! DeviceOrientationController::request_permission(|granted| {
+//!		log::info!("Orientation permission granted: {granted}");
+//! });
+//!
+//! app.run(move |scene, _time| {
+//!		controller.borrow_mut().update(&mut scene.camera);
+//! });
+//! ```
+//!
+
+use std::{cell::RefCell, rc::Rc};
+use glam::{EulerRot, Quat, Vec3};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{
+	DeviceOrientationEvent,
+	wasm_bindgen::{JsCast, JsValue, prelude::Closure},
+};
+
+use super::Camera;
+
+/// How device orientation is applied to the camera.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OrientationMode {
+	/// Rotates the view around the camera position, for panoramas.
+	#[default]
+	Look,
+	/// Rotates the camera position around its target, for product views.
+	Orbit,
+}
+
+#[derive(Default)]
+struct OrientationState {
+	alpha: f32,
+	beta: f32,
+	gamma: f32,
+	received: bool,
+}
+
+/// Camera controller driven by device orientation events.
+///
+/// The event listener is registered on creation and removed on drop.
+pub struct DeviceOrientationController {
+	state: Rc<RefCell<OrientationState>>,
+	listener: Closure<dyn FnMut(DeviceOrientationEvent)>,
+	pub mode: OrientationMode,
+	pub enabled: bool,
+	heading_offset: f32,
+	smoothing: f32,
+	current: Option<Quat>,
+}
+
+impl DeviceOrientationController {
+	/// Creates a controller listening to `deviceorientation` on the window.
+	///
+	/// # Errors
+	///
+	/// Returns an error if there is no window or the listener can't be added.
+	pub fn new() -> Result<Self, String> {
+		let window = web_sys::window().ok_or("No window")?;
+		let state = Rc::new(RefCell::new(OrientationState::default()));
+		let listener_state = state.clone();
+
+		let listener = Closure::<dyn FnMut(DeviceOrientationEvent)>::new(move |event: DeviceOrientationEvent| {
+			let (Some(alpha), Some(beta), Some(gamma)) = (event.alpha(), event.beta(), event.gamma()) else {
+				return;
+			};
+
+			let mut state = listener_state.borrow_mut();
+			state.alpha = (alpha as f32).to_radians();
+			state.beta = (beta as f32).to_radians();
+			state.gamma = (gamma as f32).to_radians();
+			state.received = true;
+		});
+
+		window
+			.add_event_listener_with_callback("deviceorientation", listener.as_ref().unchecked_ref())
+			.map_err(|e| format!("Failed to add orientation listener: {:?}", e))?;
+
+		Ok(Self {
+			state,
+			listener,
+			mode: OrientationMode::default(),
+			enabled: true,
+			heading_offset: 0.0,
+			smoothing: 0.0,
+			current: None,
+		})
+	}
+
+	pub fn with_mode(mut self, mode: OrientationMode) -> Self {
+		self.mode = mode;
+		self
+	}
+
+	/// Sets smoothing between frames (0 = none, close to 1 = slow).
+	pub fn with_smoothing(mut self, smoothing: f32) -> Self {
+		self.smoothing = smoothing.clamp(0.0, 0.99);
+		self
+	}
+
+	/// Returns `true` if the browser requires [`request_permission`](Self::request_permission).
+	pub fn needs_permission() -> bool {
+		request_permission_fn().is_some()
+	}
+
+	/// Requests permission to receive orientation events.
+	///
+	/// Must be called from a user gesture. `on_result` receives `true` if
+	/// access was granted, and is called immediately with `true` on browsers
+	/// that don't require permission.
+	pub fn request_permission(on_result: impl FnOnce(bool) + 'static) {
+		let Some((class, request)) = request_permission_fn() else {
+			on_result(true);
+			return;
+		};
+
+		let promise = match request.call0(&class).map(js_sys::Promise::from) {
+			Ok(promise) => promise,
+			Err(_) => {
+				on_result(false);
+				return;
+			}
+		};
+
+		wasm_bindgen_futures::spawn_local(async move {
+			let state = JsFuture::from(promise).await.ok().and_then(|value| value.as_string());
+			on_result(state.as_deref() == Some("granted"));
+		});
+	}
+
+	/// Returns `true` once at least one orientation event was received.
+	pub fn is_active(&self) -> bool {
+		self.state.borrow().received
+	}
+
+	/// Returns the device orientation as a camera rotation.
+	///
+	/// The identity rotation looks down -Z with the device held upright in
+	/// portrait, facing the heading set by [`recenter`](Self::recenter).
+	pub fn orientation(&self) -> Quat {
+		let state = self.state.borrow();
+
+		// Device frame (Z up, screen facing the user) to camera frame (Y up, looking down -Z)
+		let device = Quat::from_euler(EulerRot::YXZ, state.alpha, state.beta, -state.gamma);
+		let to_camera = Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2);
+		let screen = Quat::from_rotation_z(-screen_angle());
+
+		Quat::from_rotation_y(self.heading_offset) * device * to_camera * screen
+	}
+
+	/// Makes the current device heading face down -Z.
+	pub fn recenter(&mut self) {
+		self.heading_offset = 0.0;
+
+		let forward = self.orientation() * Vec3::NEG_Z;
+		self.heading_offset = forward.x.atan2(-forward.z).rem_euclid(std::f32::consts::TAU);
+		self.current = None;
+	}
+
+	/// Applies the latest orientation to a camera.
+	///
+	/// Returns `false` without changing the camera when disabled or before any
+	/// orientation event was received.
+	pub fn update(&mut self, camera: &mut Camera) -> bool {
+		if !self.enabled || !self.is_active() {
+			return false;
+		}
+
+		let target = self.orientation();
+		let rotation = match self.current {
+			Some(current) => current.slerp(target, 1.0 - self.smoothing),
+			None => target,
+		};
+
+		self.current = Some(rotation);
+
+		let forward = rotation * Vec3::NEG_Z;
+		let distance = camera.position.distance(camera.target).max(f32::EPSILON);
+
+		match self.mode {
+			OrientationMode::Look => camera.target = camera.position + forward * distance,
+			OrientationMode::Orbit => camera.position = camera.target - forward * distance,
+		}
+
+		camera.up = rotation * Vec3::Y;
+		true
+	}
+}
+
+impl Drop for DeviceOrientationController {
+	fn drop(&mut self) {
+		if let Some(window) = web_sys::window() {
+			let _ = window.remove_event_listener_with_callback("deviceorientation", self.listener.as_ref().unchecked_ref());
+		}
+	}
+}
+
+/// Returns `DeviceOrientationEvent` and its `requestPermission` function, if present.
+fn request_permission_fn() -> Option<(JsValue, js_sys::Function)> {
+	let window = web_sys::window()?;
+	let class = js_sys::Reflect::get(&window, &JsValue::from_str("DeviceOrientationEvent")).ok()?;
+	let request = js_sys::Reflect::get(&class, &JsValue::from_str("requestPermission")).ok()?;
+
+	request.dyn_into::<js_sys::Function>().ok().map(|f| (class, f))
+}
+
+/// Returns the screen rotation in radians.
+fn screen_angle() -> f32 {
+	web_sys::window()
+		.and_then(|w| w.screen().ok())
+		.and_then(|s| s.orientation().angle().ok())
+		.map(|angle| (angle as f32).to_radians())
+		.unwrap_or(0.0)
+}
@@ -1,7 +1,8 @@
 //! Post-Processing Effects
 //!
 //! Provides a stackable post-processing system with built-in effects like
-//! vignette, chromatic aberration, blur, and film grain.
+//! vignette, chromatic aberration, blur, film grain, outlines and screen-space
+//! reflections.
 //!
 //! ## Architecture
 //!
@@ -9,6 +10,25 @@
 //! Each effect renders to an intermediate texture which becomes the input for
 //! the next effect in the chain.
 //!
+//! Effects can also sample the scene's depth as `depthTexture`, along with
+//! the `projection`, `inverseProjection`, `view` and `inverseView` matrices
+//! of the camera set with [`PostProcessStack::set_camera`]. The
+//! [`Scene`](crate::renderer_3d::Scene) sets its camera before applying the
+//! stack.
+//!
+//! Effects sampling `emissiveTexture` get the glow of emissive materials
+//! alone, for bloom-like effects. The scene only draws it while an enabled
+//! effect uses it.
+//!
+//! Effects sampling `distortionTexture` get the screen-space offsets drawn
+//! by objects with a [distortion mask](crate::renderer_3d::Scene::set_distortion),
+//! for heat haze, glass and shockwaves. It's likewise only drawn while
+//! used, see [`presets::distortion`].
+//!
+//! An effect can follow one of the scene's lights through its
+//! [`light`](PostProcessEffect::light), getting the light's screen position
+//! every frame, as [`presets::god_rays`] does.
+//!
 //! ## Examples
 //!
 //! ```ignore
@@ -31,13 +51,20 @@
 
 use std::collections::HashMap;
 use web_sys::{
-	WebGlFramebuffer, WebGlTexture, WebGlRenderbuffer, WebGlBuffer, WebGlProgram,
+	WebGlFramebuffer, WebGlTexture, WebGlBuffer, WebGlProgram,
 	WebGl2RenderingContext as GL,
 };
-use glam::{Vec2, Vec3};
+use glam::{Mat4, Vec2, Vec3, Vec4};
+use slotmap::SlotMap;
+
+use super::{Camera, Texture, Uniform};
+use crate::common::{compile_shader, link_program, OxglError, UniformBlocks, Uniforms};
+use crate::core::LightId;
+use crate::renderer_3d::{Light, LightType};
 
-use super::Uniform;
-use crate::common::{compile_shader, link_program};
+/// Texture unit of the first effect texture, after the screen, depth,
+/// emissive and distortion textures.
+const FIRST_EFFECT_UNIT: u32 = 4;
 
 /// A single post-processing effect.
 ///
@@ -45,7 +72,12 @@ use crate::common::{compile_shader, link_program};
 pub struct PostProcessEffect {
 	program: WebGlProgram,
 	uniforms: HashMap<String, Uniform>,
+	blocks: UniformBlocks,
+	textures: Vec<(String, Texture)>,
 	pub enabled: bool,
+	/// Light whose screen position is passed to the effect each frame, see
+	/// [`PostProcessStack::update_lights`].
+	pub light: Option<LightId>,
 }
 
 impl PostProcessEffect {
@@ -55,20 +87,41 @@ impl PostProcessEffect {
 	///
 	/// ## Errors
 	///
-	/// Returns an error if shader compilation fails.
-	pub fn new(gl: &GL, frag_src: &str) -> Result<Self, String> {
-		let vert_src = include_str!("../pp_shaders/postprocess.vert");
-		let vert_shader = compile_shader(gl, vert_src, GL::VERTEX_SHADER)?;
-		let frag_shader = compile_shader(gl, frag_src, GL::FRAGMENT_SHADER)?;
-		let program = link_program(gl, &vert_shader, &frag_shader)?;
-
+	/// Returns an [`OxglError`] if shader compilation fails.
+	pub fn new(gl: &GL, frag_src: &str) -> Result<Self, OxglError> {
 		Ok(Self {
-			program,
+			program: Self::compile(gl, frag_src)?,
 			uniforms: HashMap::new(),
+			blocks: UniformBlocks::default(),
+			textures: Vec::new(),
 			enabled: true,
+			light: None,
 		})
 	}
 
+	/// Replaces the fragment shader with new source.
+	///
+	/// Uniforms are kept. On failure the effect keeps its previous program.
+	///
+	/// ## Errors
+	///
+	/// Returns the compile or link error of the new source.
+	pub fn recompile(&mut self, gl: &GL, frag_src: &str) -> Result<(), OxglError> {
+		let program = Self::compile(gl, frag_src)?;
+		gl.delete_program(Some(&std::mem::replace(&mut self.program, program)));
+
+		Ok(())
+	}
+
+	/// Links a fragment shader with the fullscreen quad vertex shader.
+	fn compile(gl: &GL, frag_src: &str) -> Result<WebGlProgram, OxglError> {
+		let vert_src = include_str!("../pp_shaders/postprocess.vert");
+		let vert_shader = compile_shader(gl, vert_src, GL::VERTEX_SHADER)?;
+		let frag_shader = compile_shader(gl, frag_src, GL::FRAGMENT_SHADER)?;
+
+		link_program(gl, &vert_shader, &frag_shader)
+	}
+
 	pub fn set(&mut self, name: &str, value: Uniform) -> &mut Self {
 		self.uniforms.insert(name.to_string(), value);
 		self
@@ -86,17 +139,60 @@ impl PostProcessEffect {
 		self.set(name, Uniform::Vec3(v))
 	}
 
+	/// Sets a texture sampled by the effect, replacing any previous texture
+	/// with the same name.
+	///
+	/// Its size in pixels is passed as a `vec2` uniform named after the
+	/// texture with a `Size` suffix, such as `lutSize` for `lut`.
+	pub fn set_texture(&mut self, name: &str, texture: Texture) -> &mut Self {
+		match self.textures.iter_mut().find(|(n, _)| n == name) {
+			Some((_, existing)) => *existing = texture,
+			None => self.textures.push((name.to_string(), texture)),
+		}
+
+		self
+	}
+
+	/// Sets a typed uniform struct, replacing any previous value of its type.
+	///
+	/// See [`Uniforms`].
+	pub fn set_uniforms<T: Uniforms + Clone>(&mut self, value: T) -> &mut Self {
+		self.blocks.set(value);
+		self
+	}
+
+	pub fn uniforms<T: Uniforms>(&self) -> Option<&T> {
+		self.blocks.get()
+	}
+
+	pub fn uniforms_mut<T: Uniforms>(&mut self) -> Option<&mut T> {
+		self.blocks.get_mut()
+	}
+
 	pub fn program(&self) -> &WebGlProgram {
 		&self.program
 	}
 
-	/// Uploads all uniforms to the GPU.
+	/// Uploads all uniforms to the GPU and binds the effect's textures.
 	pub fn apply_uniforms(&self, gl: &GL) {
 		for (name, value) in &self.uniforms {
 			if let Some(loc) = gl.get_uniform_location(&self.program, name) {
 				value.apply(gl, &loc);
 			}
 		}
+
+		for (unit, (name, texture)) in (FIRST_EFFECT_UNIT..).zip(&self.textures) {
+			texture.bind(gl, unit);
+
+			if let Some(loc) = gl.get_uniform_location(&self.program, name) {
+				gl.uniform1i(Some(&loc), unit as i32);
+			}
+			if let Some(loc) = gl.get_uniform_location(&self.program, &format!("{}Size", name)) {
+				gl.uniform2f(Some(&loc), texture.width() as f32, texture.height() as f32);
+			}
+		}
+
+		self.blocks.apply(gl, &self.program);
 	}
 }
 
@@ -114,6 +210,7 @@ pub struct PostProcessEffectBuilder<'a> {
 	gl: &'a GL,
 	frag_src: &'a str,
 	uniforms: HashMap<String, Uniform>,
+	textures: Vec<(String, Texture)>,
 }
 
 impl<'a> PostProcessEffectBuilder<'a> {
@@ -122,6 +219,7 @@ impl<'a> PostProcessEffectBuilder<'a> {
 			gl,
 			frag_src,
 			uniforms: HashMap::new(),
+			textures: Vec::new(),
 		}
 	}
 
@@ -146,6 +244,11 @@ impl<'a> PostProcessEffectBuilder<'a> {
 		self.uniform(name, Uniform::Int(v))
 	}
 
+	pub fn texture(mut self, name: &str, texture: Texture) -> Self {
+		self.textures.push((name.to_string(), texture));
+		self
+	}
+
 	/// Builds the effect.
 	///
 	/// ## Panics
@@ -155,6 +258,7 @@ impl<'a> PostProcessEffectBuilder<'a> {
 		let mut effect = PostProcessEffect::new(self.gl, self.frag_src)
 			.expect("Failed to compile post-process shader");
 		effect.uniforms = self.uniforms;
+		effect.textures = self.textures;
 		effect
 	}
 }
@@ -167,21 +271,21 @@ struct PingPongBuffer {
 }
 
 impl PingPongBuffer {
-	fn new(gl: &GL, width: i32, height: i32) -> Result<Self, String> {
+	fn new(gl: &GL, width: i32, height: i32) -> Result<Self, OxglError> {
 		let mut framebuffers = Vec::with_capacity(2);
 		let mut textures = Vec::with_capacity(2);
 
 		for _ in 0..2 {
 			let fb = gl.create_framebuffer()
-				.ok_or("Failed to create ping-pong framebuffer")?;
+				.ok_or_else(|| OxglError::ResourceCreation("Failed to create ping-pong framebuffer".to_string()))?;
 			let tex = gl.create_texture()
-				.ok_or("Failed to create ping-pong texture")?;
+				.ok_or_else(|| OxglError::ResourceCreation("Failed to create ping-pong texture".to_string()))?;
 
 			gl.bind_texture(GL::TEXTURE_2D, Some(&tex));
 			gl.tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
 				GL::TEXTURE_2D, 0, GL::RGBA as i32, width, height, 0,
 				GL::RGBA, GL::UNSIGNED_BYTE, None,
-			).map_err(|e| format!("Failed to create texture: {:?}", e))?;
+			).map_err(|e| OxglError::ResourceCreation(format!("Failed to create texture: {:?}", e)))?;
 
 			gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_MIN_FILTER, GL::LINEAR as i32);
 			gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_MAG_FILTER, GL::LINEAR as i32);
@@ -248,12 +352,20 @@ impl PingPongBuffer {
 pub struct PostProcessStack {
 	scene_framebuffer: WebGlFramebuffer,
 	scene_texture: WebGlTexture,
-	depth_renderbuffer: WebGlRenderbuffer,
+	depth_texture: WebGlTexture,
+	/// Emission of emissive materials, depth tested against the scene.
+	emissive_framebuffer: WebGlFramebuffer,
+	emissive_texture: WebGlTexture,
+	/// Offsets drawn by distorting objects, depth tested against the scene.
+	distortion_framebuffer: WebGlFramebuffer,
+	distortion_texture: WebGlTexture,
 	ping_pong: PingPongBuffer,
 	quad_buffer: WebGlBuffer,
 	effects: Vec<PostProcessEffect>,
 	width: i32,
 	height: i32,
+	/// View and projection of the camera the scene was drawn with.
+	camera: Option<(Mat4, Mat4, f32)>,
 	pub enabled: bool,
 }
 
@@ -262,42 +374,52 @@ impl PostProcessStack {
 	///
 	/// ## Errors
 	///
-	/// Returns an error if framebuffer creation fails.
-	pub fn new(gl: &GL, width: i32, height: i32) -> Result<Self, String> {
+	/// Returns an [`OxglError`] if framebuffer creation fails.
+	pub fn new(gl: &GL, width: i32, height: i32) -> Result<Self, OxglError> {
 		let scene_framebuffer = gl.create_framebuffer()
-			.ok_or("Failed to create scene framebuffer")?;
+			.ok_or_else(|| OxglError::ResourceCreation("Failed to create scene framebuffer".to_string()))?;
 		let scene_texture = gl.create_texture()
-			.ok_or("Failed to create scene texture")?;
+			.ok_or_else(|| OxglError::ResourceCreation("Failed to create scene texture".to_string()))?;
 
 		gl.bind_texture(GL::TEXTURE_2D, Some(&scene_texture));
 		gl.tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
 			GL::TEXTURE_2D, 0, GL::RGBA as i32, width, height, 0,
 			GL::RGBA, GL::UNSIGNED_BYTE, None,
-		).map_err(|e| format!("Failed to create scene texture: {:?}", e))?;
+		).map_err(|e| OxglError::ResourceCreation(format!("Failed to create scene texture: {:?}", e)))?;
 
 		gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_MIN_FILTER, GL::LINEAR as i32);
 		gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_MAG_FILTER, GL::LINEAR as i32);
 		gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_WRAP_S, GL::CLAMP_TO_EDGE as i32);
 		gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_WRAP_T, GL::CLAMP_TO_EDGE as i32);
 
-		let depth_renderbuffer = gl.create_renderbuffer()
-			.ok_or("Failed to create depth renderbuffer")?;
-		gl.bind_renderbuffer(GL::RENDERBUFFER, Some(&depth_renderbuffer));
-		gl.renderbuffer_storage(GL::RENDERBUFFER, GL::DEPTH_COMPONENT24, width, height);
+		let depth_texture = gl.create_texture()
+			.ok_or_else(|| OxglError::ResourceCreation("Failed to create scene depth texture".to_string()))?;
+
+		gl.bind_texture(GL::TEXTURE_2D, Some(&depth_texture));
+		allocate_depth(gl, width, height)
+			.map_err(|e| OxglError::ResourceCreation(format!("Failed to create scene depth texture: {:?}", e)))?;
+
+		gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_MIN_FILTER, GL::NEAREST as i32);
+		gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_MAG_FILTER, GL::NEAREST as i32);
+		gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_WRAP_S, GL::CLAMP_TO_EDGE as i32);
+		gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_WRAP_T, GL::CLAMP_TO_EDGE as i32);
 
 		gl.bind_framebuffer(GL::FRAMEBUFFER, Some(&scene_framebuffer));
 		gl.framebuffer_texture_2d(
 			GL::FRAMEBUFFER, GL::COLOR_ATTACHMENT0, GL::TEXTURE_2D, Some(&scene_texture), 0,
 		);
-		gl.framebuffer_renderbuffer(
-			GL::FRAMEBUFFER, GL::DEPTH_ATTACHMENT, GL::RENDERBUFFER, Some(&depth_renderbuffer),
+		gl.framebuffer_texture_2d(
+			GL::FRAMEBUFFER, GL::DEPTH_ATTACHMENT, GL::TEXTURE_2D, Some(&depth_texture), 0,
 		);
 
 		let status = gl.check_framebuffer_status(GL::FRAMEBUFFER);
 		if status != GL::FRAMEBUFFER_COMPLETE {
-			return Err(format!("Scene framebuffer incomplete: {}", status));
+			return Err(OxglError::FramebufferIncomplete { framebuffer: "Scene".to_string(), status });
 		}
 
+		let (emissive_framebuffer, emissive_texture) = depth_sharing_target(gl, width, height, &depth_texture, "Emissive")?;
+		let (distortion_framebuffer, distortion_texture) = depth_sharing_target(gl, width, height, &depth_texture, "Distortion")?;
+
 		gl.bind_framebuffer(GL::FRAMEBUFFER, None);
 
 		let ping_pong = PingPongBuffer::new(gl, width, height)?;
@@ -312,7 +434,7 @@ impl PostProcessStack {
 		];
 
 		let quad_buffer = gl.create_buffer()
-			.ok_or("Failed to create quad buffer")?;
+			.ok_or_else(|| OxglError::ResourceCreation("Failed to create quad buffer".to_string()))?;
 		gl.bind_buffer(GL::ARRAY_BUFFER, Some(&quad_buffer));
 
 		let vert_array = unsafe {
@@ -326,12 +448,17 @@ impl PostProcessStack {
 		Ok(Self {
 			scene_framebuffer,
 			scene_texture,
-			depth_renderbuffer,
+			depth_texture,
+			emissive_framebuffer,
+			emissive_texture,
+			distortion_framebuffer,
+			distortion_texture,
 			ping_pong,
 			quad_buffer,
 			effects: Vec::new(),
 			width,
 			height,
+			camera: None,
 			enabled: true,
 		})
 	}
@@ -347,8 +474,17 @@ impl PostProcessStack {
 			GL::RGBA, GL::UNSIGNED_BYTE, None,
 		);
 
-		gl.bind_renderbuffer(GL::RENDERBUFFER, Some(&self.depth_renderbuffer));
-		gl.renderbuffer_storage(GL::RENDERBUFFER, GL::DEPTH_COMPONENT24, width, height);
+		gl.bind_texture(GL::TEXTURE_2D, Some(&self.depth_texture));
+		if let Err(e) = allocate_depth(gl, width, height) {
+			log::error!("Failed to resize post-process depth texture: {:?}", e);
+		}
+
+		for texture in [&self.emissive_texture, &self.distortion_texture] {
+			gl.bind_texture(GL::TEXTURE_2D, Some(texture));
+			if let Err(e) = allocate_color(gl, width, height) {
+				log::error!("Failed to resize post-process texture: {:?}", e);
+			}
+		}
 
 		self.ping_pong.resize(gl, width, height);
 	}
@@ -377,6 +513,68 @@ impl PostProcessStack {
 		self.effects.clear();
 	}
 
+	/// Sets the camera whose matrices are passed to effects that sample
+	/// `depthTexture`.
+	pub fn set_camera(&mut self, camera: &Camera) {
+		self.camera = Some((camera.view_matrix(), camera.projection_matrix(), camera.log_depth_factor()));
+	}
+
+	/// Passes the lights followed by effects their place on screen, as seen
+	/// by the camera set with [`set_camera`](Self::set_camera).
+	///
+	/// Effects with a [`light`](PostProcessEffect::light) get:
+	///
+	/// - `lightPosition` (`vec2`) - screen coordinate from 0 to 1, possibly
+	///   off screen
+	/// - `lightDepth` - depth buffer value at the light, `1.0` for
+	///   directional lights, which are infinitely far away
+	/// - `lightColor` (`vec3`) - color scaled by intensity
+	/// - `lightVisibility` - fades from `1.0` to `0.0` as the light leaves
+	///   the screen, and is `0.0` behind the camera or for missing and
+	///   disabled lights
+	///
+	/// The [`Scene`](crate::renderer_3d::Scene) calls this before applying
+	/// the stack.
+	pub fn update_lights(&mut self, lights: &SlotMap<LightId, Light>) {
+		let Some((view, projection, _)) = self.camera else {
+			return;
+		};
+
+		let view_projection = projection * view;
+
+		for effect in &mut self.effects {
+			let Some(id) = effect.light else {
+				continue;
+			};
+
+			let light = lights.get(id).filter(|light| light.enabled);
+
+			let clip = match light {
+				Some(light) if matches!(light.light_type, LightType::Directional) => view_projection * (-light.direction).extend(0.0),
+				Some(light) => view_projection * light.position.extend(1.0),
+				None => Vec4::ZERO,
+			};
+
+			if clip.w <= 0.0 {
+				effect.set_float("lightVisibility", 0.0);
+				continue;
+			}
+
+			let ndc = clip.truncate() / clip.w;
+			let edge = ndc.x.abs().max(ndc.y.abs());
+			let depth = match light {
+				Some(light) if matches!(light.light_type, LightType::Directional) => 1.0,
+				_ => (ndc.z * 0.5 + 0.5).clamp(0.0, 1.0),
+			};
+
+			effect
+				.set_vec2("lightPosition", ndc.truncate() * 0.5 + 0.5)
+				.set_float("lightDepth", depth)
+				.set_vec3("lightColor", light.map_or(Vec3::ONE, |light| light.color * light.intensity))
+				.set_float("lightVisibility", ((1.5 - edge) * 2.0).clamp(0.0, 1.0));
+		}
+	}
+
 	
 	/// Begins scene rendering to the post-process framebuffer.
 	///
@@ -391,10 +589,61 @@ impl PostProcessStack {
 		gl.clear(GL::COLOR_BUFFER_BIT | GL::DEPTH_BUFFER_BIT);
 	}
 
+	/// Switches to drawing the emissive texture, sharing the scene's depth.
+	///
+	/// Call this after rendering your scene. Returns `false`, without
+	/// switching, when no enabled effect samples `emissiveTexture`.
+	pub fn begin_emissive(&self, gl: &GL) -> bool {
+		let sampled = self.enabled && self.effects
+			.iter()
+			.filter(|effect| effect.enabled)
+			.any(|effect| gl.get_uniform_location(effect.program(), "emissiveTexture").is_some());
+
+		if sampled {
+			gl.bind_framebuffer(GL::FRAMEBUFFER, Some(&self.emissive_framebuffer));
+			gl.viewport(0, 0, self.width, self.height);
+			gl.clear_color(0.0, 0.0, 0.0, 0.0);
+			gl.clear(GL::COLOR_BUFFER_BIT);
+		}
+
+		sampled
+	}
+
+	/// Switches to drawing the distortion texture, sharing the scene's
+	/// depth.
+	///
+	/// Call this after rendering your scene. The texture is cleared to no
+	/// offset. Returns `false`, without switching, when no enabled effect
+	/// samples `distortionTexture`.
+	pub fn begin_distortion(&self, gl: &GL) -> bool {
+		let sampled = self.enabled && self.effects
+			.iter()
+			.filter(|effect| effect.enabled)
+			.any(|effect| gl.get_uniform_location(effect.program(), "distortionTexture").is_some());
+
+		if sampled {
+			gl.bind_framebuffer(GL::FRAMEBUFFER, Some(&self.distortion_framebuffer));
+			gl.viewport(0, 0, self.width, self.height);
+			gl.clear_color(0.5, 0.5, 0.0, 0.0);
+			gl.clear(GL::COLOR_BUFFER_BIT);
+		}
+
+		sampled
+	}
+
 	/// Ends scene rendering and applies all effects.
 	///
 	/// Call this after rendering your scene.
 	pub fn end(&mut self, gl: &GL, time: f32) {
+		self.end_at(gl, time, 0, 0);
+	}
+
+	/// Ends scene rendering and applies all effects, placing the result at
+	/// a pixel offset on the canvas.
+	///
+	/// Used to post-process a single viewport. Only the stack's own area of
+	/// the canvas is written.
+	pub fn end_at(&mut self, gl: &GL, time: f32, x: i32, y: i32) {
 		if !self.enabled {
 			return;
 		}
@@ -409,7 +658,7 @@ impl PostProcessStack {
 			.collect();
 
 		if enabled_effects.is_empty() {
-			self.blit_to_screen(gl);
+			self.blit_to_screen(gl, x, y);
 		} else {
 			self.blit_texture(gl, &self.scene_texture, self.ping_pong.write_framebuffer());
 			self.ping_pong.swap();
@@ -418,12 +667,16 @@ impl PostProcessStack {
 				let is_last = i == enabled_effects.len() - 1;
 				
 				if is_last {
+					// Keeps the clear inside this stack's area of the canvas
+					gl.enable(GL::SCISSOR_TEST);
+					gl.scissor(x, y, self.width, self.height);
 					gl.bind_framebuffer(GL::FRAMEBUFFER, None);
+					gl.viewport(x, y, self.width, self.height);
 				} else {
 					gl.bind_framebuffer(GL::FRAMEBUFFER, Some(self.ping_pong.write_framebuffer()));
+					gl.viewport(0, 0, self.width, self.height);
 				}
-				
-				gl.viewport(0, 0, self.width, self.height);
+
 				gl.clear(GL::COLOR_BUFFER_BIT);
 
 				let effect = &self.effects[effect_idx];
@@ -435,9 +688,18 @@ impl PostProcessStack {
 			}
 		}
 
+		gl.disable(GL::SCISSOR_TEST);
 		gl.enable(GL::DEPTH_TEST);
 	}
 
+	pub fn width(&self) -> i32 {
+		self.width
+	}
+
+	pub fn height(&self) -> i32 {
+		self.height
+	}
+
 	fn apply_effect(&self, gl: &GL, effect: &PostProcessEffect, input_texture: &WebGlTexture, time: f32) {
 		let program = effect.program();
 		gl.use_program(Some(program));
@@ -455,6 +717,49 @@ impl PostProcessStack {
 			gl.uniform2f(Some(&loc), self.width as f32, self.height as f32);
 		}
 
+		gl.active_texture(GL::TEXTURE1);
+		gl.bind_texture(GL::TEXTURE_2D, Some(&self.depth_texture));
+
+		if let Some(loc) = gl.get_uniform_location(program, "depthTexture") {
+			gl.uniform1i(Some(&loc), 1);
+		}
+
+		gl.active_texture(GL::TEXTURE2);
+		gl.bind_texture(GL::TEXTURE_2D, Some(&self.emissive_texture));
+
+		if let Some(loc) = gl.get_uniform_location(program, "emissiveTexture") {
+			gl.uniform1i(Some(&loc), 2);
+		}
+
+		gl.active_texture(GL::TEXTURE3);
+		gl.bind_texture(GL::TEXTURE_2D, Some(&self.distortion_texture));
+
+		if let Some(loc) = gl.get_uniform_location(program, "distortionTexture") {
+			gl.uniform1i(Some(&loc), 3);
+		}
+
+		if let Some((view, projection, log_depth_factor)) = self.camera {
+			let matrices = [
+				("view", view),
+				("inverseView", view.inverse()),
+				("projection", projection),
+				("inverseProjection", projection.inverse()),
+			];
+
+			for (name, matrix) in matrices {
+				if let Some(loc) = gl.get_uniform_location(program, name) {
+					gl.uniform_matrix4fv_with_f32_array(Some(&loc), false, &matrix.to_cols_array());
+				}
+			}
+
+			if let Some(loc) = gl.get_uniform_location(program, "logDepthFactor") {
+				gl.uniform1f(Some(&loc), log_depth_factor);
+			}
+			if let Some(loc) = gl.get_uniform_location(program, "depthProjection") {
+				gl.uniform2f(Some(&loc), projection.z_axis.z, projection.w_axis.z);
+			}
+		}
+
 		effect.apply_uniforms(gl);
 
 		self.draw_quad(gl, program);
@@ -477,12 +782,12 @@ impl PostProcessStack {
 		);
 	}
 
-	fn blit_to_screen(&self, gl: &GL) {
+	fn blit_to_screen(&self, gl: &GL, x: i32, y: i32) {
 		gl.bind_framebuffer(GL::READ_FRAMEBUFFER, Some(&self.scene_framebuffer));
 		gl.bind_framebuffer(GL::DRAW_FRAMEBUFFER, None);
 		gl.blit_framebuffer(
 			0, 0, self.width, self.height,
-			0, 0, self.width, self.height,
+			x, y, x + self.width, y + self.height,
 			GL::COLOR_BUFFER_BIT,
 			GL::NEAREST,
 		);
@@ -507,6 +812,55 @@ impl PostProcessStack {
 	}
 }
 
+/// Creates a color texture and a framebuffer drawing into it with the
+/// scene's depth, so only what's in front of the scene is drawn.
+fn depth_sharing_target(gl: &GL, width: i32, height: i32, depth_texture: &WebGlTexture, name: &str) -> Result<(WebGlFramebuffer, WebGlTexture), OxglError> {
+	let lower = name.to_lowercase();
+	let framebuffer = gl.create_framebuffer()
+		.ok_or_else(|| OxglError::ResourceCreation(format!("Failed to create {} framebuffer", lower)))?;
+	let texture = gl.create_texture()
+		.ok_or_else(|| OxglError::ResourceCreation(format!("Failed to create {} texture", lower)))?;
+
+	gl.bind_texture(GL::TEXTURE_2D, Some(&texture));
+	allocate_color(gl, width, height)
+		.map_err(|e| OxglError::ResourceCreation(format!("Failed to create {} texture: {:?}", lower, e)))?;
+
+	gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_MIN_FILTER, GL::LINEAR as i32);
+	gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_MAG_FILTER, GL::LINEAR as i32);
+	gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_WRAP_S, GL::CLAMP_TO_EDGE as i32);
+	gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_WRAP_T, GL::CLAMP_TO_EDGE as i32);
+
+	gl.bind_framebuffer(GL::FRAMEBUFFER, Some(&framebuffer));
+	gl.framebuffer_texture_2d(
+		GL::FRAMEBUFFER, GL::COLOR_ATTACHMENT0, GL::TEXTURE_2D, Some(&texture), 0,
+	);
+	gl.framebuffer_texture_2d(
+		GL::FRAMEBUFFER, GL::DEPTH_ATTACHMENT, GL::TEXTURE_2D, Some(depth_texture), 0,
+	);
+
+	let status = gl.check_framebuffer_status(GL::FRAMEBUFFER);
+	if status != GL::FRAMEBUFFER_COMPLETE {
+		return Err(OxglError::FramebufferIncomplete { framebuffer: name.to_string(), status });
+	}
+
+	Ok((framebuffer, texture))
+}
+
+/// Allocates RGBA8 storage for the bound texture.
+fn allocate_color(gl: &GL, width: i32, height: i32) -> Result<(), web_sys::wasm_bindgen::JsValue> {
+	gl.tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
+		GL::TEXTURE_2D, 0, GL::RGBA as i32, width, height, 0,
+		GL::RGBA, GL::UNSIGNED_BYTE, None,
+	)
+}
+
+/// Allocates 24-bit depth storage for the bound texture.
+fn allocate_depth(gl: &GL, width: i32, height: i32) -> Result<(), web_sys::wasm_bindgen::JsValue> {
+	gl.tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
+		GL::TEXTURE_2D, 0, GL::DEPTH_COMPONENT24 as i32, width, height, 0,
+		GL::DEPTH_COMPONENT, GL::UNSIGNED_INT, None,
+	)
+}
 
 pub mod presets {
 	use super::*;
@@ -519,6 +873,11 @@ pub mod presets {
 	const INVERT_FRAG: &str = include_str!("../pp_shaders/invert.frag");
 	const PIXELATE_FRAG: &str = include_str!("../pp_shaders/pixelate.frag");
 	const FILM_GRAIN_FRAG: &str = include_str!("../pp_shaders/film_grain.frag");
+	const SSR_FRAG: &str = include_str!("../pp_shaders/ssr.frag");
+	const COLOR_GRADING_FRAG: &str = include_str!("../pp_shaders/color_grading.frag");
+	const OUTLINE_FRAG: &str = include_str!("../pp_shaders/outline.frag");
+	const GOD_RAYS_FRAG: &str = include_str!("../pp_shaders/god_rays.frag");
+	const DISTORTION_FRAG: &str = include_str!("../pp_shaders/distortion.frag");
 
 	pub fn grayscale(gl: &GL) -> PostProcessEffect {
 		PostProcessEffectBuilder::new(gl, GRAYSCALE_FRAG).build()
@@ -558,4 +917,187 @@ pub mod presets {
 			.float("intensity", intensity)
 			.build()
 	}
-}
\ No newline at end of file
+
+	/// Screen-space reflections for glossy floors and water.
+	///
+	/// Rays are marched through the scene's depth, with normals rebuilt
+	/// from neighbouring depths. Only surfaces facing up more than
+	/// `minNormalY` (`0.7` by default) reflect, strongest at grazing angles.
+	/// Rays that leave the screen or hit nothing fall back to
+	/// `environment`, usually the [skybox](crate::renderer_3d::Skybox)
+	/// texture, or to no reflection without one.
+	///
+	/// - `max_distance` - how far rays travel, in world units
+	/// - `steps` - samples along each ray, up to 128
+	/// - `thickness` - how far behind a surface a ray still counts as a hit
+	///
+	/// ## Examples
+	///
+	/// ```ignore
+	/// let ssr = presets::screen_space_reflections(&gl, 0.8, 20.0, 48, 0.5, skybox.texture());
+	/// pp.push(ssr);
+	///
+	/// // Reflect walls too
+	/// pp.get_mut(0).unwrap().set_float("minNormalY", -1.0);
+	/// ```
+	pub fn screen_space_reflections(
+		gl: &GL,
+		intensity: f32,
+		max_distance: f32,
+		steps: i32,
+		thickness: f32,
+		environment: Option<&Texture>,
+	) -> PostProcessEffect {
+		let builder = PostProcessEffectBuilder::new(gl, SSR_FRAG)
+			.float("intensity", intensity)
+			.float("maxDistance", max_distance)
+			.int("steps", steps.clamp(1, 128))
+			.float("thickness", thickness)
+			.float("minNormalY", 0.7)
+			.float("environmentYaw", 0.0)
+			.int("hasEnvironment", environment.is_some() as i32);
+
+		match environment {
+			Some(texture) => builder.texture("environment", texture.clone()).build(),
+			None => builder.build(),
+		}
+	}
+
+	/// Grades the final image with lift, gamma and gain, saturation and
+	/// contrast, then an optional look-up table.
+	///
+	/// `lut` is a 3D LUT laid out as a horizontal strip of square slices,
+	/// such as a 256x16 image for 16 levels, or one made from a `.cube`
+	/// file with [`Texture::from_cube_lut`]. The neutral defaults leave the
+	/// image unchanged apart from the LUT:
+	///
+	/// - `lift` (`vec3`, `0.0`) - raises the shadows
+	/// - `gamma` (`vec3`, `1.0`) - brightens the midtones above `1.0`
+	/// - `gain` (`vec3`, `1.0`) - scales the highlights
+	/// - `saturation` and `contrast` (`1.0`)
+	/// - `lutIntensity` (`1.0`) - blends between the graded and LUT colors
+	///
+	/// ## Examples
+	///
+	/// ```ignore
+	/// let lut = Texture::load(&gl, "assets/teal_orange.png")?;
+	/// let index = pp.push(presets::color_grading(&gl, Some(&lut)));
+	///
+	/// pp.get_mut(index).unwrap()
+	///		.set_vec3("lift", Vec3::new(0.0, 0.01, 0.03))
+	///		.set_float("saturation", 1.15)
+	///		.set_float("lutIntensity", 0.7);
+	/// ```
+	pub fn color_grading(gl: &GL, lut: Option<&Texture>) -> PostProcessEffect {
+		let builder = PostProcessEffectBuilder::new(gl, COLOR_GRADING_FRAG)
+			.vec3("lift", Vec3::ZERO)
+			.vec3("gamma", Vec3::ONE)
+			.vec3("gain", Vec3::ONE)
+			.float("saturation", 1.0)
+			.float("contrast", 1.0)
+			.float("lutIntensity", 1.0);
+
+		match lut {
+			Some(texture) => builder.texture("lut", texture.clone()).build(),
+			None => builder.build(),
+		}
+	}
+
+	/// Draws outlines where the scene's depth or normals change sharply,
+	/// for toon-shaded and technical-illustration looks.
+	///
+	/// Edges are found with a Sobel filter over the depth and the normals
+	/// rebuilt from it, so silhouettes, creases and intersections are all
+	/// outlined. `thickness` is the sample spacing in pixels.
+	///
+	/// - `depthThreshold` (`0.1`) - relative depth change that counts as an edge
+	/// - `normalThreshold` (`0.8`) - normal change that counts as an edge
+	/// - `opacity` (`1.0`) - blends the outline over the image
+	///
+	/// ## Examples
+	///
+	/// ```ignore
+	/// let index = pp.push(presets::outline(&gl, 1.5, Vec3::ZERO));
+	///
+	/// // Only outline silhouettes
+	/// pp.get_mut(index).unwrap().set_float("normalThreshold", 10.0);
+	/// ```
+	pub fn outline(gl: &GL, thickness: f32, color: Vec3) -> PostProcessEffect {
+		PostProcessEffectBuilder::new(gl, OUTLINE_FRAG)
+			.float("thickness", thickness)
+			.vec3("color", color)
+			.float("opacity", 1.0)
+			.float("depthThreshold", 0.1)
+			.float("normalThreshold", 0.8)
+			.build()
+	}
+
+	/// Shafts of light streaming from a light past the objects in front of
+	/// it, also known as god rays or crepuscular rays.
+	///
+	/// Every pixel is blurred towards the light's position on screen,
+	/// gathering only what lies behind the light: the sky for
+	/// [directional](crate::renderer_3d::Light::directional) lights, which
+	/// should be placed where the sun is drawn. The light is followed
+	/// automatically once the stack is set on the scene with
+	/// [`Scene::set_post_process`](crate::renderer_3d::Scene::set_post_process),
+	/// and the rays fade out as it leaves the screen.
+	///
+	/// - `density` - how far towards the light each pixel samples, from 0 to 1
+	/// - `decay` - how much each further sample counts, just below 1
+	/// - `weight` (`0.02`) - brightness of each sample
+	/// - `exposure` (`1.0`) - brightness of the rays
+	/// - `samples` (`64`) - samples per pixel, up to 128
+	///
+	/// ## Examples
+	///
+	/// ```ignore
+	/// let sun = scene.add_light(Light::directional(Vec3::new(-0.3, -0.4, 1.0), Vec3::new(1.0, 0.9, 0.7), 1.0));
+	///
+	/// let index = pp.push(presets::god_rays(&gl, sun, 0.9, 0.96));
+	/// pp.get_mut(index).unwrap().set_float("exposure", 0.6);
+	///
+	/// scene.set_post_process(pp);
+	/// ```
+	pub fn god_rays(gl: &GL, light: LightId, density: f32, decay: f32) -> PostProcessEffect {
+		let mut effect = PostProcessEffectBuilder::new(gl, GOD_RAYS_FRAG)
+			.float("density", density)
+			.float("decay", decay)
+			.float("weight", 0.02)
+			.float("exposure", 1.0)
+			.int("samples", 64)
+			.float("lightVisibility", 0.0)
+			.build();
+
+		effect.light = Some(light);
+		effect
+	}
+
+	/// Bends the image by the offsets drawn by objects with a
+	/// [distortion mask](crate::renderer_3d::Scene::set_distortion), for
+	/// heat haze, glass and shockwaves.
+	///
+	/// `strength` scales the offsets, as a fraction of the screen. Masks
+	/// only bend what's behind them, not objects in front.
+	///
+	/// ## Examples
+	///
+	/// ```ignore
+	/// use oxgl::common::presets as material_presets;
+	///
+	/// pp.push(presets::distortion(&gl, 0.03));
+	/// scene.set_post_process(pp);
+	///
+	/// // Shimmering air above a campfire
+	/// let mut haze = material_presets::distortion(&gl, 0.0);
+	/// haze.set_float("noiseStrength", 0.5).set_float("edgeFade", 1.0);
+	///
+	/// let column = scene.add(column_mesh, Transform3D::new().with_position(fire_position));
+	/// scene.set_distortion(column, Some(haze));
+	/// ```
+	pub fn distortion(gl: &GL, strength: f32) -> PostProcessEffect {
+		PostProcessEffectBuilder::new(gl, DISTORTION_FRAG)
+			.float("strength", strength)
+			.build()
+	}
+}
@@ -0,0 +1,238 @@
+//! Canvas Recording
+//!
+//! Provides a [`Recorder`] that captures the canvas with `captureStream()`
+//! and encodes it to WebM with the browser's `MediaRecorder`, for exporting
+//! demo clips straight from an app.
+//!
+//! ## Examples
+//!
+//! ```ignore
+//! use oxgl::common::Recorder;
+//!
+//! let mut recorder = Recorder::new(renderer.canvas())
+//!		.with_frame_rate(60.0)
+//!		.with_bitrate(12_000_000);
+//!
+//! recorder.start()?;
+//!
+//! // Later, for example from a button handler
+//! recorder.stop(|video| {
+//!		if let Err(e) = Recorder::download(&video, "oxgl-demo.webm") {
+//!			log::error!("{}", e);
+//!		}
+//! })?;
+//! ```
+//!
+
+use std::{cell::RefCell, rc::Rc};
+use js_sys::{Array, Promise};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{
+	Blob, BlobEvent, BlobPropertyBag, HtmlAnchorElement, HtmlCanvasElement, MediaRecorder,
+	MediaRecorderOptions, MediaStream, MediaStreamTrack, RecordingState, Url,
+	wasm_bindgen::{JsCast, prelude::Closure},
+};
+
+/// Container formats tried in order when no MIME type is set.
+const MIME_TYPES: &[&str] = &["video/webm;codecs=vp9", "video/webm;codecs=vp8", "video/webm"];
+
+/// Whether a [`Recorder`] is capturing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RecorderState {
+	Inactive,
+	Recording,
+	Paused,
+}
+
+struct Session {
+	recorder: MediaRecorder,
+	stream: MediaStream,
+	mime_type: String,
+	chunks: Rc<RefCell<Vec<Blob>>>,
+	on_data: Closure<dyn FnMut(BlobEvent)>,
+}
+
+/// Records a canvas to a WebM video.
+///
+/// Each [`start`](Self::start) begins a new clip, delivered as a `Blob` when
+/// the recording is stopped.
+pub struct Recorder {
+	canvas: HtmlCanvasElement,
+	/// Frames captured per second. `None` captures whenever the canvas
+	/// changes.
+	pub frame_rate: Option<f64>,
+	/// Target video bitrate in bits per second.
+	pub bitrate: u32,
+	/// Container and codec, such as `video/webm;codecs=vp9`. `None` picks
+	/// the first supported WebM codec.
+	pub mime_type: Option<String>,
+	session: Option<Session>,
+}
+
+impl Recorder {
+	pub fn new(canvas: &HtmlCanvasElement) -> Self {
+		Self {
+			canvas: canvas.clone(),
+			frame_rate: Some(60.0),
+			bitrate: 8_000_000,
+			mime_type: None,
+			session: None,
+		}
+	}
+
+	pub fn with_frame_rate(mut self, frame_rate: f64) -> Self {
+		self.frame_rate = Some(frame_rate);
+		self
+	}
+
+	pub fn with_bitrate(mut self, bitrate: u32) -> Self {
+		self.bitrate = bitrate;
+		self
+	}
+
+	pub fn with_mime_type(mut self, mime_type: &str) -> Self {
+		self.mime_type = Some(mime_type.to_string());
+		self
+	}
+
+	/// Starts recording a new clip.
+	///
+	/// # Errors
+	///
+	/// Returns an error if a recording is already in progress, no supported
+	/// format is found, or the browser refuses to capture the canvas.
+	pub fn start(&mut self) -> Result<(), String> {
+		if self.state() != RecorderState::Inactive {
+			return Err("Recording already in progress".into());
+		}
+
+		let mime_type = match &self.mime_type {
+			Some(mime_type) if MediaRecorder::is_type_supported(mime_type) => mime_type.clone(),
+			Some(mime_type) => return Err(format!("Unsupported recording format: {}", mime_type)),
+			None => MIME_TYPES
+				.iter()
+				.find(|mime_type| MediaRecorder::is_type_supported(mime_type))
+				.ok_or("No supported WebM recording format")?
+				.to_string(),
+		};
+
+		let stream = match self.frame_rate {
+			Some(rate) => self.canvas.capture_stream_with_frame_request_rate(rate),
+			None => self.canvas.capture_stream(),
+		}
+		.map_err(|e| format!("Failed to capture canvas: {:?}", e))?;
+
+		let options = MediaRecorderOptions::new();
+		options.set_mime_type(&mime_type);
+		options.set_video_bits_per_second(self.bitrate);
+
+		let recorder = MediaRecorder::new_with_media_stream_and_media_recorder_options(&stream, &options)
+			.map_err(|e| format!("Failed to create media recorder: {:?}", e))?;
+
+		let chunks = Rc::new(RefCell::new(Vec::new()));
+		let received = chunks.clone();
+
+		let on_data = Closure::<dyn FnMut(BlobEvent)>::new(move |event: BlobEvent| {
+			if let Some(data) = event.data().filter(|data| data.size() > 0.0) {
+				received.borrow_mut().push(data);
+			}
+		});
+
+		recorder.set_ondataavailable(Some(on_data.as_ref().unchecked_ref()));
+
+		// Flush a chunk every second so long clips don't build up in one buffer
+		recorder.start_with_time_slice(1000)
+			.map_err(|e| format!("Failed to start recording: {:?}", e))?;
+
+		self.session = Some(Session { recorder, stream, mime_type, chunks, on_data });
+
+		Ok(())
+	}
+
+	/// Pauses recording, leaving a seamless cut in the clip.
+	pub fn pause(&self) -> Result<(), String> {
+		match &self.session {
+			Some(session) if self.state() == RecorderState::Recording => session.recorder.pause()
+				.map_err(|e| format!("Failed to pause recording: {:?}", e)),
+			_ => Err("Not recording".into()),
+		}
+	}
+
+	pub fn resume(&self) -> Result<(), String> {
+		match &self.session {
+			Some(session) if self.state() == RecorderState::Paused => session.recorder.resume()
+				.map_err(|e| format!("Failed to resume recording: {:?}", e)),
+			_ => Err("Recording is not paused".into()),
+		}
+	}
+
+	/// Stops recording and passes the finished video to `on_finished` once
+	/// the encoder has flushed.
+	///
+	/// # Errors
+	///
+	/// Returns an error if nothing is being recorded.
+	pub fn stop(&mut self, on_finished: impl FnOnce(Blob) + 'static) -> Result<(), String> {
+		let session = self.session.take().ok_or("Not recording")?;
+
+		let recorder = session.recorder.clone();
+		let stopped = Promise::new(&mut |resolve, _| recorder.set_onstop(Some(&resolve)));
+
+		wasm_bindgen_futures::spawn_local(async move {
+			let _ = JsFuture::from(stopped).await;
+
+			let parts: Array = session.chunks.borrow().iter().collect();
+			let options = BlobPropertyBag::new();
+			options.set_type(&session.mime_type);
+
+			for track in session.stream.get_tracks().iter() {
+				track.unchecked_into::<MediaStreamTrack>().stop();
+			}
+
+			session.recorder.set_onstop(None);
+			session.recorder.set_ondataavailable(None);
+
+			match Blob::new_with_blob_sequence_and_options(&parts, &options) {
+				Ok(video) => on_finished(video),
+				Err(e) => log::error!("Failed to assemble recording: {:?}", e),
+			}
+
+			// Keeps the data callback alive until the final chunk arrives
+			drop(session.on_data);
+		});
+
+		recorder.stop().map_err(|e| format!("Failed to stop recording: {:?}", e))
+	}
+
+	pub fn state(&self) -> RecorderState {
+		match self.session.as_ref().map(|s| s.recorder.state()) {
+			Some(RecordingState::Recording) => RecorderState::Recording,
+			Some(RecordingState::Paused) => RecorderState::Paused,
+			_ => RecorderState::Inactive,
+		}
+	}
+
+	pub fn is_recording(&self) -> bool {
+		self.state() == RecorderState::Recording
+	}
+
+	/// Saves a recorded video through the browser's download prompt.
+	pub fn download(video: &Blob, file_name: &str) -> Result<(), String> {
+		let url = Url::create_object_url_with_blob(video)
+			.map_err(|e| format!("Failed to create video URL: {:?}", e))?;
+
+		let anchor = web_sys::window()
+			.and_then(|w| w.document())
+			.ok_or("No document")?
+			.create_element("a")
+			.map_err(|_| "Failed to create download link")?
+			.dyn_into::<HtmlAnchorElement>()
+			.map_err(|_| "Failed to cast download link")?;
+
+		anchor.set_href(&url);
+		anchor.set_download(file_name);
+		anchor.click();
+
+		Url::revoke_object_url(&url).map_err(|e| format!("Failed to release video URL: {:?}", e))
+	}
+}
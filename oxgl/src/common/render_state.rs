@@ -0,0 +1,249 @@
+//! Render State
+//!
+//! Provides [`RenderState`], the fixed-function pipeline settings applied at
+//! the start of every frame: face culling, the depth function, clear values
+//! and scissoring. Set it on the [`Renderer`](crate::Renderer) for every scene,
+//! or on a [`Scene`](crate::renderer_3d::Scene) to override it there.
+//!
+//! ## Examples
+//!
+//! ```ignore
+//! use oxgl::common::{CullFace, DepthFunc, RenderState};
+//! use glam::Vec4;
+//!
+//! app.renderer.set_render_state(
+//!		RenderState::new()
+//!			.with_cull_face(CullFace::Back)
+//!			.with_clear_color(Vec4::new(0.05, 0.05, 0.08, 1.0))
+//! );
+//!
+//! // Draw only the left half of the canvas for this scene
+//! scene.render_state = Some(RenderState::new().with_scissor([0, 0, width / 2, height]));
+//! ```
+//!
+
+use glam::Vec4;
+use web_sys::{WebGl2RenderingContext as GL, wasm_bindgen::JsCast};
+
+/// Which faces are discarded before rasterization.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CullFace {
+	Front,
+	Back,
+	FrontAndBack,
+}
+
+impl CullFace {
+	fn to_gl(self) -> u32 {
+		match self {
+			CullFace::Front => GL::FRONT,
+			CullFace::Back => GL::BACK,
+			CullFace::FrontAndBack => GL::FRONT_AND_BACK,
+		}
+	}
+}
+
+/// The vertex order of front-facing triangles, as seen on screen.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FrontFace {
+	#[default]
+	CounterClockwise,
+	Clockwise,
+}
+
+/// How a fragment's depth is compared to the depth buffer.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DepthFunc {
+	Never,
+	#[default]
+	Less,
+	Equal,
+	LessEqual,
+	Greater,
+	NotEqual,
+	GreaterEqual,
+	Always,
+}
+
+impl DepthFunc {
+	fn to_gl(self) -> u32 {
+		match self {
+			DepthFunc::Never => GL::NEVER,
+			DepthFunc::Less => GL::LESS,
+			DepthFunc::Equal => GL::EQUAL,
+			DepthFunc::LessEqual => GL::LEQUAL,
+			DepthFunc::Greater => GL::GREATER,
+			DepthFunc::NotEqual => GL::NOTEQUAL,
+			DepthFunc::GreaterEqual => GL::GEQUAL,
+			DepthFunc::Always => GL::ALWAYS,
+		}
+	}
+}
+
+/// Pipeline settings applied at the start of every frame.
+///
+/// ## Defaults
+///
+/// No face culling, counter-clockwise front faces, [`DepthFunc::Less`],
+/// clearing to the scene's background and a depth of `1.0`, and no scissor.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RenderState {
+	/// Faces to cull, or `None` to draw both sides.
+	pub cull_face: Option<CullFace>,
+	pub front_face: FrontFace,
+	pub depth_func: DepthFunc,
+	/// Replaces the scene's background color when clearing.
+	pub clear_color: Option<Vec4>,
+	pub clear_depth: f32,
+	/// Limits drawing to `[x, y, width, height]` in pixels from the
+	/// bottom-left corner. Only used by [`Scene::render`](crate::renderer_3d::Scene::render),
+	/// as viewports and render targets scissor themselves.
+	pub scissor: Option<[i32; 4]>,
+}
+
+impl Default for RenderState {
+	fn default() -> Self {
+		Self {
+			cull_face: None,
+			front_face: FrontFace::default(),
+			depth_func: DepthFunc::default(),
+			clear_color: None,
+			clear_depth: 1.0,
+			scissor: None,
+		}
+	}
+}
+
+impl RenderState {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	pub fn with_cull_face(mut self, face: CullFace) -> Self {
+		self.cull_face = Some(face);
+		self
+	}
+
+	pub fn with_front_face(mut self, front_face: FrontFace) -> Self {
+		self.front_face = front_face;
+		self
+	}
+
+	pub fn with_depth_func(mut self, func: DepthFunc) -> Self {
+		self.depth_func = func;
+		self
+	}
+
+	pub fn with_clear_color(mut self, color: Vec4) -> Self {
+		self.clear_color = Some(color);
+		self
+	}
+
+	pub fn with_clear_depth(mut self, depth: f32) -> Self {
+		self.clear_depth = depth;
+		self
+	}
+
+	pub fn with_scissor(mut self, rect: [i32; 4]) -> Self {
+		self.scissor = Some(rect);
+		self
+	}
+
+	/// Sets culling, the depth function, the clear depth and the scissor.
+	///
+	/// The clear color is left to the caller, which falls back to its
+	/// background when [`clear_color`](Self::clear_color) is `None`.
+	pub fn apply(&self, gl: &GL) {
+		match self.cull_face {
+			Some(face) => {
+				gl.enable(GL::CULL_FACE);
+				gl.cull_face(face.to_gl());
+			}
+			None => gl.disable(GL::CULL_FACE),
+		}
+
+		gl.front_face(match self.front_face {
+			FrontFace::CounterClockwise => GL::CCW,
+			FrontFace::Clockwise => GL::CW,
+		});
+
+		gl.depth_func(self.depth_func.to_gl());
+		gl.clear_depth(self.clear_depth);
+
+		match self.scissor {
+			Some([x, y, width, height]) => {
+				gl.enable(GL::SCISSOR_TEST);
+				gl.scissor(x, y, width, height);
+			}
+			None => gl.disable(GL::SCISSOR_TEST),
+		}
+	}
+}
+
+/// Returns the depth function currently set, for passes that change it
+/// temporarily.
+pub(crate) fn current_depth_func(gl: &GL) -> u32 {
+	parameter_u32(gl, GL::DEPTH_FUNC, GL::LESS)
+}
+
+fn parameter_u32(gl: &GL, parameter: u32, fallback: u32) -> u32 {
+	gl.get_parameter(parameter)
+		.ok()
+		.and_then(|value| value.as_f64())
+		.map_or(fallback, |value| value as u32)
+}
+
+/// Blending, depth and culling state captured before a pass changes it, so
+/// it can be put back afterwards.
+pub(crate) struct SavedState {
+	blend: bool,
+	/// Source and destination factors for color, then alpha.
+	blend_func: [u32; 4],
+	depth_test: bool,
+	depth_mask: bool,
+	cull_face: bool,
+	cull_mode: u32,
+}
+
+impl SavedState {
+	pub(crate) fn capture(gl: &GL) -> Self {
+		Self {
+			blend: gl.is_enabled(GL::BLEND),
+			blend_func: [
+				parameter_u32(gl, GL::BLEND_SRC_RGB, GL::ONE),
+				parameter_u32(gl, GL::BLEND_DST_RGB, GL::ZERO),
+				parameter_u32(gl, GL::BLEND_SRC_ALPHA, GL::ONE),
+				parameter_u32(gl, GL::BLEND_DST_ALPHA, GL::ZERO),
+			],
+			depth_test: gl.is_enabled(GL::DEPTH_TEST),
+			depth_mask: gl.get_parameter(GL::DEPTH_WRITEMASK).ok().and_then(|value| value.as_bool()).unwrap_or(true),
+			cull_face: gl.is_enabled(GL::CULL_FACE),
+			cull_mode: parameter_u32(gl, GL::CULL_FACE_MODE, GL::BACK),
+		}
+	}
+
+	pub(crate) fn restore(&self, gl: &GL) {
+		let toggle = |capability, enabled| if enabled { gl.enable(capability) } else { gl.disable(capability) };
+
+		toggle(GL::BLEND, self.blend);
+		toggle(GL::DEPTH_TEST, self.depth_test);
+		toggle(GL::CULL_FACE, self.cull_face);
+
+		let [src_rgb, dst_rgb, src_alpha, dst_alpha] = self.blend_func;
+		gl.blend_func_separate(src_rgb, dst_rgb, src_alpha, dst_alpha);
+		gl.depth_mask(self.depth_mask);
+		gl.cull_face(self.cull_mode);
+	}
+}
+
+/// Returns the viewport currently set as `[x, y, width, height]`, for passes
+/// that draw into part of the canvas.
+pub(crate) fn current_viewport(gl: &GL) -> [i32; 4] {
+	gl.get_parameter(GL::VIEWPORT)
+		.ok()
+		.and_then(|value| value.dyn_into::<js_sys::Int32Array>().ok())
+		.filter(|viewport| viewport.length() == 4)
+		.map_or([0, 0, gl.drawing_buffer_width(), gl.drawing_buffer_height()], |viewport| {
+			[viewport.get_index(0), viewport.get_index(1), viewport.get_index(2), viewport.get_index(3)]
+		})
+}
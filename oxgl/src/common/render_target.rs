@@ -0,0 +1,135 @@
+//! Render Targets
+//!
+//! Provides offscreen framebuffers with a color texture and depth buffer.
+//! The scene can be rendered into a target from any camera with
+//! [`Scene::render_to_target`](crate::renderer_3d::Scene::render_to_target),
+//! and the result sampled by materials like any other [`Texture`], for
+//! mirrors, portals, security-camera screens and thumbnails.
+//!
+//! ## Examples
+//!
+//! ```ignore
+//! use oxgl::common::RenderTarget;
+//!
+//! let target = RenderTarget::new(&gl, 256, 256)?;
+//! monitor_material.set_texture("screen", target.texture().clone());
+//!
+//! // In the render loop, before rendering the main view
+//! scene.render_to_target(&renderer, &target, &security_camera);
+//! scene.render(&renderer, time);
+//! ```
+//!
+
+use web_sys::{WebGlFramebuffer, WebGlRenderbuffer, WebGl2RenderingContext as GL};
+
+use super::{OxglError, Texture};
+
+/// An offscreen framebuffer with a color texture and depth attachment.
+pub struct RenderTarget {
+	framebuffer: WebGlFramebuffer,
+	color: Texture,
+	depth_renderbuffer: WebGlRenderbuffer,
+	width: i32,
+	height: i32,
+	/// Color the target is cleared to when bound.
+	pub clear_color: [f32; 4],
+}
+
+impl RenderTarget {
+	/// Creates a render target of the given size in pixels.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the framebuffer can't be created or is incomplete.
+	pub fn new(gl: &GL, width: i32, height: i32) -> Result<Self, OxglError> {
+		let (width, height) = (width.max(1), height.max(1));
+
+		let framebuffer = gl.create_framebuffer()
+			.ok_or_else(|| OxglError::ResourceCreation("Failed to create render target framebuffer".to_string()))?;
+		let color = Texture::empty(gl, width as u32, height as u32).map_err(OxglError::ResourceCreation)?;
+
+		let depth_renderbuffer = gl.create_renderbuffer()
+			.ok_or_else(|| OxglError::ResourceCreation("Failed to create depth renderbuffer".to_string()))?;
+		gl.bind_renderbuffer(GL::RENDERBUFFER, Some(&depth_renderbuffer));
+		gl.renderbuffer_storage(GL::RENDERBUFFER, GL::DEPTH_COMPONENT24, width, height);
+
+		gl.bind_framebuffer(GL::FRAMEBUFFER, Some(&framebuffer));
+		gl.framebuffer_texture_2d(
+			GL::FRAMEBUFFER, GL::COLOR_ATTACHMENT0, GL::TEXTURE_2D, Some(color.texture()), 0,
+		);
+		gl.framebuffer_renderbuffer(
+			GL::FRAMEBUFFER, GL::DEPTH_ATTACHMENT, GL::RENDERBUFFER, Some(&depth_renderbuffer),
+		);
+
+		let status = gl.check_framebuffer_status(GL::FRAMEBUFFER);
+		gl.bind_framebuffer(GL::FRAMEBUFFER, None);
+
+		if status != GL::FRAMEBUFFER_COMPLETE {
+			return Err(OxglError::FramebufferIncomplete { framebuffer: "Render target".to_string(), status });
+		}
+
+		Ok(Self {
+			framebuffer,
+			color,
+			depth_renderbuffer,
+			width,
+			height,
+			clear_color: [0.1, 0.1, 0.1, 1.0],
+		})
+	}
+
+	/// Resizes the attachments, discarding their contents.
+	///
+	/// Textures handed out by [`texture`](Self::texture) stay valid.
+	pub fn resize(&mut self, gl: &GL, width: i32, height: i32) {
+		let (width, height) = (width.max(1), height.max(1));
+
+		if (width, height) == (self.width, self.height) {
+			return;
+		}
+
+		self.width = width;
+		self.height = height;
+
+		if let Err(e) = self.color.allocate(gl, width as u32, height as u32) {
+			log::error!("{}", e);
+		}
+
+		gl.bind_renderbuffer(GL::RENDERBUFFER, Some(&self.depth_renderbuffer));
+		gl.renderbuffer_storage(GL::RENDERBUFFER, GL::DEPTH_COMPONENT24, width, height);
+	}
+
+	/// Binds the target for drawing, sets the viewport to cover it and
+	/// clears it.
+	pub fn bind(&self, gl: &GL) {
+		let [r, g, b, a] = self.clear_color;
+
+		gl.bind_framebuffer(GL::FRAMEBUFFER, Some(&self.framebuffer));
+		gl.viewport(0, 0, self.width, self.height);
+		gl.clear_color(r, g, b, a);
+		gl.clear(GL::COLOR_BUFFER_BIT | GL::DEPTH_BUFFER_BIT);
+	}
+
+	/// Restores drawing to the canvas with a viewport of the given size.
+	pub fn unbind(&self, gl: &GL, width: i32, height: i32) {
+		gl.bind_framebuffer(GL::FRAMEBUFFER, None);
+		gl.viewport(0, 0, width, height);
+	}
+
+	/// Returns the color attachment, for use as a material texture.
+	pub fn texture(&self) -> &Texture {
+		&self.color
+	}
+
+	pub fn framebuffer(&self) -> &WebGlFramebuffer {
+		&self.framebuffer
+	}
+
+	pub fn width(&self) -> i32 {
+		self.width
+	}
+
+	pub fn height(&self) -> i32 {
+		self.height
+	}
+}
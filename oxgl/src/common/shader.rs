@@ -1,7 +1,25 @@
 //! Shader Compilation and Linking
 //!
 //! Provides low-level utilities for compiling GLSL shaders and linking them
-//! into shader programs for WebGL2.
+//! into shader programs for WebGL2, and a small preprocessor that resolves
+//! `#include <chunk>` directives against the engine's built-in shader chunks.
+//!
+//! ## Chunks
+//!
+//! - `lights` - the `Light` struct, light uniforms, attenuation and `lightDirection`
+//! - `shadows` - shadow map uniforms and `calculateShadow`
+//! - `contact_shadows` - screen-space `calculateContactShadow`
+//! - `lighting` - `shadeSurface`, a Lambert/Blinn-Phong loop over all lights
+//! - `fog` - fog uniforms and `applyFog`
+//! - `clipping` - clip plane uniforms and `applyClipping`
+//! - `logdepth` - `logDepth` for [logarithmic depth](super::Camera::logarithmic_depth)
+//! - `normals` - `surfaceNormal` for double-sided and flat-shaded materials
+//! - `emissive` - emissive uniforms and `emission`
+//! - `lightmap` - lightmap uniforms, `hasLightmap` and `bakedLight`
+//! - `probes` - reflection probe uniforms, `reflectance` and box-projected `applyReflection`
+//! - `depth_buffer` - `viewPosition` and `viewNormal` from the scene depth, for post effects
+//! - `instancing` - per-instance matrix attributes and `instanceMatrix`, for [`Scatter`](crate::renderer_3d::Scatter) meshes
+//! - `scene_depth` - `sceneViewDepth` behind the mesh, for [`scene_depth`](super::Material::scene_depth) materials
 //!
 //! ## Examples
 //!
@@ -31,18 +49,118 @@
 
 use web_sys::{WebGlProgram, WebGl2RenderingContext as GL, WebGlShader};
 
+use super::{OxglError, error::ShaderStage};
+
+/// Built-in chunks available to `#include <name>`.
+const CHUNKS: &[(&str, &str)] = &[
+	("colorspace", include_str!("../shaders/chunks/colorspace.glsl")),
+	("lights", include_str!("../shaders/chunks/lights.glsl")),
+	("shadows", include_str!("../shaders/chunks/shadows.glsl")),
+	("contact_shadows", include_str!("../shaders/chunks/contact_shadows.glsl")),
+	("lighting", include_str!("../shaders/chunks/lighting.glsl")),
+	("fog", include_str!("../shaders/chunks/fog.glsl")),
+	("clipping", include_str!("../shaders/chunks/clipping.glsl")),
+	("logdepth", include_str!("../shaders/chunks/logdepth.glsl")),
+	("depth_buffer", include_str!("../shaders/chunks/depth_buffer.glsl")),
+	("normals", include_str!("../shaders/chunks/normals.glsl")),
+	("emissive", include_str!("../shaders/chunks/emissive.glsl")),
+	("lightmap", include_str!("../shaders/chunks/lightmap.glsl")),
+	("probes", include_str!("../shaders/chunks/probes.glsl")),
+	("instancing", include_str!("../shaders/chunks/instancing.glsl")),
+	("scene_depth", include_str!("../shaders/chunks/scene_depth.glsl")),
+];
+
+/// Expands `#include <chunk>` directives and prepends `#define`s, keeping
+/// any `#version` directive on the first line.
+///
+/// Each chunk is included at most once, so chunks can include their own
+/// dependencies without causing redefinitions.
+///
+/// # Errors
+///
+/// Returns an error if an include is malformed or names an unknown chunk.
+///
+/// # Examples
+///
+/// ```
+/// use oxgl::common::shader::preprocess;
+///
+/// let source = "#include <lights>\n#include <lights>\nvoid main() {}";
+/// let output = preprocess(source, &[("USE_FOG", "1")]).unwrap();
+///
+/// assert!(output.starts_with("#define USE_FOG 1\n"));
+/// assert_eq!(output.matches("struct Light").count(), 1);
+/// assert!(preprocess("#include <missing>", &[]).is_err());
+///
+/// let versioned = preprocess("#version 300 es\nvoid main() {}", &[("USE_FOG", "1")]).unwrap();
+/// assert!(versioned.starts_with("#version 300 es\n#define USE_FOG 1\n"));
+/// ```
+pub fn preprocess(source: &str, defines: &[(&str, &str)]) -> Result<String, String> {
+	let mut output = String::with_capacity(source.len());
+
+	// `#version` has to stay the first line, so the defines go after it
+	let source = match source.trim_start().strip_prefix("#version") {
+		Some(rest) => {
+			let (version, body) = rest.split_once('\n').unwrap_or((rest, ""));
+			output.push_str(&format!("#version{}\n", version));
+			body
+		}
+		None => source,
+	};
+
+	for (name, value) in defines {
+		output.push_str(&format!("#define {} {}\n", name, value));
+	}
+
+	expand_includes(source, &mut Vec::new(), &mut output)?;
+	Ok(output)
+}
+
+fn expand_includes(source: &str, included: &mut Vec<&'static str>, output: &mut String) -> Result<(), String> {
+	for line in source.lines() {
+		let Some(rest) = line.trim().strip_prefix("#include") else {
+			output.push_str(line);
+			output.push('\n');
+			continue;
+		};
+
+		let name = rest
+			.trim()
+			.strip_prefix('<')
+			.and_then(|r| r.strip_suffix('>'))
+			.ok_or_else(|| format!("Malformed include: {}", line.trim()))?;
+
+		let &(name, chunk) = CHUNKS
+			.iter()
+			.find(|(chunk, _)| *chunk == name)
+			.ok_or_else(|| format!("Unknown shader chunk <{}>", name))?;
+
+		if !included.contains(&name) {
+			included.push(name);
+			expand_includes(chunk, included, output)?;
+		}
+	}
+
+	Ok(())
+}
+
 /// Compiles a GLSL shader from source code.
 ///
 /// Takes GLSL source code and compiles it into a shader object that can be
-/// linked into a program with [`link_program`].
+/// linked into a program with [`link_program`]. The source is run through
+/// [`preprocess`] first, so it may include built-in chunks.
 ///
 /// # Errors
 ///
-/// Returns an error string containing the shader compilation log if:
-/// - The shader object could not be created
+/// Returns [`OxglError::ShaderCompile`] with the compilation log and the
+/// offending source lines if:
+/// - The source includes an unknown chunk
 /// - The shader source contains syntax errors
 /// - The shader uses unsupported GLSL features
 ///
+/// Returns [`OxglError::ResourceCreation`] if the shader object could not
+/// be created.
+///
 /// # Examples
 ///
 /// ```
@@ -68,21 +186,26 @@ use web_sys::{WebGlProgram, WebGl2RenderingContext as GL, WebGlShader};
 /// ```
 /// match compile_shader(&gl, bad_source, GL::FRAGMENT_SHADER) {
 ///		Ok(shader) => { /* use shader */ }
-///		Err(log) => {
-///			log::error!("Shader compilation failed:\n{}", log);
+///		Err(error) => {
+///			// Includes the log and the numbered lines it refers to
+///			log::error!("{}", error);
 ///		}
 /// }
 /// ```
-pub fn compile_shader(gl: &GL, source: &str, shader_type: u32) -> Result<WebGlShader, String> {
-	let shader = gl.create_shader(shader_type).ok_or("Unable to create shader")?;
+pub fn compile_shader(gl: &GL, source: &str, shader_type: u32) -> Result<WebGlShader, OxglError> {
+	let stage = ShaderStage::from_gl(shader_type);
+	let source = preprocess(source, &[]).map_err(|log| OxglError::shader_compile(stage, &log, ""))?;
+	let shader = gl.create_shader(shader_type)
+		.ok_or_else(|| OxglError::ResourceCreation("Unable to create shader".to_string()))?;
 
-	gl.shader_source(&shader, source);
+	gl.shader_source(&shader, &source);
 	gl.compile_shader(&shader);
 
 	if gl.get_shader_parameter(&shader, GL::COMPILE_STATUS).as_bool().unwrap_or(false) {
 		Ok(shader)
 	} else {
-		Err(gl.get_shader_info_log(&shader).unwrap_or_else(|| "Unknown error".to_string()))
+		let log = gl.get_shader_info_log(&shader).unwrap_or_else(|| "Unknown error".to_string());
+		Err(OxglError::shader_compile(stage, &log, &source))
 	}
 }
 
@@ -93,11 +216,13 @@ pub fn compile_shader(gl: &GL, source: &str, shader_type: u32) -> Result<WebGlSh
 ///
 /// # Errors
 ///
-/// Returns an error string containing the program link log if:
-/// - The program object could not be created
+/// Returns [`OxglError::ProgramLink`] with the program link log if:
 /// - The shaders have mismatched varyings (outputs don't match inputs)
 /// - Required attributes or uniforms are missing
 ///
+/// Returns [`OxglError::ResourceCreation`] if the program object could not
+/// be created.
+///
 /// # Examples
 ///
 /// ```
@@ -119,14 +244,15 @@ pub fn compile_shader(gl: &GL, source: &str, shader_type: u32) -> Result<WebGlSh
 /// use oxgl::common::{compile_shader, link_program};
 /// use web_sys::WebGl2RenderingContext as GL;
 ///
-/// fn create_program(gl: &GL, vert_src: &str, frag_src: &str) -> Result<WebGlProgram, String> {
+/// fn create_program(gl: &GL, vert_src: &str, frag_src: &str) -> Result<WebGlProgram, OxglError> {
 ///		let vert = compile_shader(gl, vert_src, GL::VERTEX_SHADER)?;
 ///		let frag = compile_shader(gl, frag_src, GL::FRAGMENT_SHADER)?;
 ///		link_program(gl, &vert, &frag)
 /// }
 /// ```
-pub fn link_program(gl: &GL, vert_shader: &WebGlShader, frag_shader: &WebGlShader) -> Result<WebGlProgram, String> {
-	let program = gl.create_program().ok_or("Unable to create program")?;
+pub fn link_program(gl: &GL, vert_shader: &WebGlShader, frag_shader: &WebGlShader) -> Result<WebGlProgram, OxglError> {
+	let program = gl.create_program()
+		.ok_or_else(|| OxglError::ResourceCreation("Unable to create program".to_string()))?;
 
 	gl.attach_shader(&program, vert_shader);
 	gl.attach_shader(&program, frag_shader);
@@ -135,6 +261,8 @@ pub fn link_program(gl: &GL, vert_shader: &WebGlShader, frag_shader: &WebGlShade
 	if gl.get_program_parameter(&program, GL::LINK_STATUS).as_bool().unwrap_or(false) {
 		Ok(program)
 	} else {
-		Err(gl.get_program_info_log(&program).unwrap_or_else(|| "Unknown error".to_string()))
+		Err(OxglError::ProgramLink {
+			log: gl.get_program_info_log(&program).unwrap_or_else(|| "Unknown error".to_string()),
+		})
 	}
 }
\ No newline at end of file
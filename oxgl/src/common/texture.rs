@@ -0,0 +1,482 @@
+//! Textures
+//!
+//! Provides 2D textures created from pixel data or images, including
+//! asynchronous loading from a URL, and [`SamplerDesc`] for how they're
+//! filtered and wrapped.
+//!
+//! ## Examples
+//!
+//! ```ignore
+//! use oxgl::common::Texture;
+//!
+//! // Starts as a 1x1 placeholder and fills in once the image has loaded
+//! let texture = Texture::load(&gl, "assets/panorama.jpg")?;
+//!
+//! // Bind to texture unit 0 before drawing
+//! texture.bind(&gl, 0);
+//!
+//! // A tiling floor texture, sharp at grazing angles
+//! texture.set_sampler(&gl, SamplerDesc::new()
+//!		.with_wrap(TextureWrap::Repeat, TextureWrap::Repeat)
+//!		.with_mipmaps(TextureFilter::Linear)
+//!		.with_anisotropy(8.0));
+//! ```
+//!
+
+use std::{cell::Cell, rc::Rc};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{HtmlCanvasElement, HtmlImageElement, WebGlTexture, WebGl2RenderingContext as GL};
+
+/// `EXT_texture_filter_anisotropic` enums, which web-sys doesn't define.
+const TEXTURE_MAX_ANISOTROPY_EXT: u32 = 0x84FE;
+const MAX_TEXTURE_MAX_ANISOTROPY_EXT: u32 = 0x84FF;
+
+/// How texels are blended when sampled between them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TextureFilter {
+	/// The closest texel, for pixel art and data textures.
+	Nearest,
+	#[default]
+	Linear,
+}
+
+/// How texture coordinates outside `0` to `1` are handled.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TextureWrap {
+	#[default]
+	ClampToEdge,
+	Repeat,
+	MirroredRepeat,
+}
+
+impl TextureWrap {
+	fn to_gl(self) -> u32 {
+		match self {
+			TextureWrap::ClampToEdge => GL::CLAMP_TO_EDGE,
+			TextureWrap::Repeat => GL::REPEAT,
+			TextureWrap::MirroredRepeat => GL::MIRRORED_REPEAT,
+		}
+	}
+}
+
+/// Sampling settings of a [`Texture`].
+///
+/// ## Defaults
+///
+/// Linear filtering without mipmaps, clamped to the edges and no
+/// anisotropic filtering.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SamplerDesc {
+	pub min_filter: TextureFilter,
+	pub mag_filter: TextureFilter,
+	/// Filtering between mip levels, or `None` to sample the full-size
+	/// image only. Mipmaps are generated whenever the contents change.
+	pub mipmap_filter: Option<TextureFilter>,
+	pub wrap_s: TextureWrap,
+	pub wrap_t: TextureWrap,
+	/// Maximum anisotropy, clamped to what the GPU supports. `1.0` turns
+	/// it off. Needs `EXT_texture_filter_anisotropic`, and is ignored
+	/// without it.
+	pub anisotropy: f32,
+}
+
+impl Default for SamplerDesc {
+	fn default() -> Self {
+		Self {
+			min_filter: TextureFilter::Linear,
+			mag_filter: TextureFilter::Linear,
+			mipmap_filter: None,
+			wrap_s: TextureWrap::ClampToEdge,
+			wrap_t: TextureWrap::ClampToEdge,
+			anisotropy: 1.0,
+		}
+	}
+}
+
+impl SamplerDesc {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Nearest filtering without mipmaps, for pixel art.
+	pub fn nearest() -> Self {
+		Self::new().with_filter(TextureFilter::Nearest, TextureFilter::Nearest)
+	}
+
+	pub fn with_filter(mut self, min: TextureFilter, mag: TextureFilter) -> Self {
+		self.min_filter = min;
+		self.mag_filter = mag;
+		self
+	}
+
+	pub fn with_mipmaps(mut self, filter: TextureFilter) -> Self {
+		self.mipmap_filter = Some(filter);
+		self
+	}
+
+	pub fn with_wrap(mut self, s: TextureWrap, t: TextureWrap) -> Self {
+		self.wrap_s = s;
+		self.wrap_t = t;
+		self
+	}
+
+	pub fn with_anisotropy(mut self, anisotropy: f32) -> Self {
+		self.anisotropy = anisotropy;
+		self
+	}
+
+	/// Returns the `TEXTURE_MIN_FILTER` value, combining the minification
+	/// and mipmap filters.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use oxgl::common::{SamplerDesc, TextureFilter};
+	/// use web_sys::WebGl2RenderingContext as GL;
+	///
+	/// assert_eq!(SamplerDesc::new().min_filter_gl(), GL::LINEAR);
+	/// assert_eq!(SamplerDesc::new().with_mipmaps(TextureFilter::Linear).min_filter_gl(), GL::LINEAR_MIPMAP_LINEAR);
+	/// assert_eq!(SamplerDesc::nearest().with_mipmaps(TextureFilter::Linear).min_filter_gl(), GL::NEAREST_MIPMAP_LINEAR);
+	/// ```
+	pub fn min_filter_gl(&self) -> u32 {
+		use TextureFilter::{Linear, Nearest};
+
+		match (self.min_filter, self.mipmap_filter) {
+			(Nearest, None) => GL::NEAREST,
+			(Linear, None) => GL::LINEAR,
+			(Nearest, Some(Nearest)) => GL::NEAREST_MIPMAP_NEAREST,
+			(Nearest, Some(Linear)) => GL::NEAREST_MIPMAP_LINEAR,
+			(Linear, Some(Nearest)) => GL::LINEAR_MIPMAP_NEAREST,
+			(Linear, Some(Linear)) => GL::LINEAR_MIPMAP_LINEAR,
+		}
+	}
+
+	fn mag_filter_gl(&self) -> u32 {
+		match self.mag_filter {
+			TextureFilter::Nearest => GL::NEAREST,
+			TextureFilter::Linear => GL::LINEAR,
+		}
+	}
+
+	/// Sets the parameters of the texture bound to `TEXTURE_2D`.
+	fn apply(&self, gl: &GL) {
+		gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_MIN_FILTER, self.min_filter_gl() as i32);
+		gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_MAG_FILTER, self.mag_filter_gl() as i32);
+		gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_WRAP_S, self.wrap_s.to_gl() as i32);
+		gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_WRAP_T, self.wrap_t.to_gl() as i32);
+
+		if let Ok(Some(_)) = gl.get_extension("EXT_texture_filter_anisotropic") {
+			let max = gl.get_parameter(MAX_TEXTURE_MAX_ANISOTROPY_EXT).ok()
+				.and_then(|value| value.as_f64())
+				.unwrap_or(1.0) as f32;
+
+			gl.tex_parameterf(GL::TEXTURE_2D, TEXTURE_MAX_ANISOTROPY_EXT, self.anisotropy.clamp(1.0, max.max(1.0)));
+		}
+	}
+}
+
+/// A 2D RGBA texture.
+///
+/// Cloning is cheap and shares the underlying GPU texture, including its
+/// [sampler settings](SamplerDesc).
+#[derive(Clone)]
+pub struct Texture {
+	texture: WebGlTexture,
+	size: Rc<Cell<(u32, u32)>>,
+	loaded: Rc<Cell<bool>>,
+	sampler: Rc<Cell<SamplerDesc>>,
+}
+
+impl Texture {
+	/// Creates a texture from tightly packed RGBA8 pixels.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the texture can't be created or the data size
+	/// doesn't match the dimensions.
+	pub fn from_pixels(gl: &GL, width: u32, height: u32, pixels: &[u8]) -> Result<Self, String> {
+		if pixels.len() != (width * height * 4) as usize {
+			return Err(format!("Expected {} bytes of RGBA data, got {}", width * height * 4, pixels.len()));
+		}
+
+		let texture = Self::create(gl)?;
+
+		gl.bind_texture(GL::TEXTURE_2D, Some(&texture.texture));
+		gl.tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
+			GL::TEXTURE_2D, 0, GL::RGBA as i32, width as i32, height as i32, 0,
+			GL::RGBA, GL::UNSIGNED_BYTE, Some(pixels)
+		).map_err(|e| format!("Failed to upload texture: {:?}", e))?;
+
+		texture.size.set((width, height));
+		texture.loaded.set(true);
+		texture.update_mipmaps(gl);
+
+		Ok(texture)
+	}
+
+	/// Creates a greyscale texture from a function of the normalized pixel
+	/// center coordinates, such as [noise](crate::core::noise).
+	///
+	/// Values from 0 to 1 map to black and white, and are clamped outside
+	/// that range. `v` runs from the top row to the bottom.
+	pub fn from_fn(gl: &GL, width: u32, height: u32, value: impl Fn(f32, f32) -> f32) -> Result<Self, String> {
+		let pixels: Vec<u8> = (0..height)
+			.flat_map(|row| (0..width).map(move |column| (column, row)))
+			.flat_map(|(column, row)| {
+				let u = (column as f32 + 0.5) / width as f32;
+				let v = (row as f32 + 0.5) / height as f32;
+				let level = (value(u, v).clamp(0.0, 1.0) * 255.0).round() as u8;
+
+				[level, level, level, 255]
+			})
+			.collect();
+
+		Self::from_pixels(gl, width, height, &pixels)
+	}
+
+	/// Creates an uninitialized texture, typically used as a render target.
+	pub fn empty(gl: &GL, width: u32, height: u32) -> Result<Self, String> {
+		let texture = Self::create(gl)?;
+		texture.allocate(gl, width, height)?;
+		Ok(texture)
+	}
+
+	/// Creates a texture from an already loaded image element.
+	pub fn from_image(gl: &GL, image: &HtmlImageElement) -> Result<Self, String> {
+		let texture = Self::create(gl)?;
+		texture.upload_image(gl, image)?;
+		Ok(texture)
+	}
+
+	/// Creates a texture from the current contents of a canvas.
+	pub fn from_canvas(gl: &GL, canvas: &HtmlCanvasElement) -> Result<Self, String> {
+		let texture = Self::create(gl)?;
+
+		gl.bind_texture(GL::TEXTURE_2D, Some(&texture.texture));
+		gl.tex_image_2d_with_u32_and_u32_and_html_canvas_element(
+			GL::TEXTURE_2D, 0, GL::RGBA as i32, GL::RGBA, GL::UNSIGNED_BYTE, canvas
+		).map_err(|e| format!("Failed to upload canvas: {:?}", e))?;
+
+		texture.size.set((canvas.width(), canvas.height()));
+		texture.loaded.set(true);
+		texture.update_mipmaps(gl);
+
+		Ok(texture)
+	}
+
+	/// Creates a strip look-up table from the text of a `.cube` 3D LUT, for
+	/// [`presets::color_grading`](super::postprocessing::presets::color_grading).
+	///
+	/// A LUT of size N becomes an N²xN texture of N slices, one per blue
+	/// level. Domains other than 0 to 1 aren't supported.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the file has no `LUT_3D_SIZE`, a malformed entry
+	/// or the wrong number of entries.
+	pub fn from_cube_lut(gl: &GL, source: &str) -> Result<Self, String> {
+		let (size, pixels) = parse_cube_lut(source)?;
+
+		Self::from_pixels(gl, size * size, size, &pixels)
+	}
+
+	/// Starts loading an image from a URL.
+	///
+	/// The texture is usable immediately as a 1x1 grey placeholder, and is
+	/// replaced with the image once it has loaded. Images that fail to load
+	/// or decode are logged and leave the placeholder.
+	pub fn load(gl: &GL, url: &str) -> Result<Self, String> {
+		let texture = Self::from_pixels(gl, 1, 1, &[128, 128, 128, 255])?;
+		texture.loaded.set(false);
+
+		let image = HtmlImageElement::new()
+			.map_err(|e| format!("Failed to create image: {:?}", e))?;
+
+		image.set_cross_origin(Some("anonymous"));
+
+		image.set_src(url);
+
+		let target = texture.clone();
+		let gl = gl.clone();
+		let url = url.to_string();
+
+		wasm_bindgen_futures::spawn_local(async move {
+			let result = match JsFuture::from(image.decode()).await {
+				Ok(_) => target.upload_image(&gl, &image),
+				Err(e) => Err(format!("Failed to load image {}: {:?}", url, e)),
+			};
+
+			if let Err(e) = result {
+				log::error!("{}", e);
+			}
+		});
+
+		Ok(texture)
+	}
+
+	/// Replaces the texture contents with an image.
+	pub fn upload_image(&self, gl: &GL, image: &HtmlImageElement) -> Result<(), String> {
+		gl.bind_texture(GL::TEXTURE_2D, Some(&self.texture));
+		gl.tex_image_2d_with_u32_and_u32_and_html_image_element(
+			GL::TEXTURE_2D, 0, GL::RGBA as i32, GL::RGBA, GL::UNSIGNED_BYTE, image
+		).map_err(|e| format!("Failed to upload image: {:?}", e))?;
+
+		self.size.set((image.natural_width(), image.natural_height()));
+		self.loaded.set(true);
+		self.update_mipmaps(gl);
+
+		Ok(())
+	}
+
+	/// Reallocates the texture storage at a new size, discarding its
+	/// contents.
+	pub fn allocate(&self, gl: &GL, width: u32, height: u32) -> Result<(), String> {
+		gl.bind_texture(GL::TEXTURE_2D, Some(&self.texture));
+		gl.tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
+			GL::TEXTURE_2D, 0, GL::RGBA as i32, width as i32, height as i32, 0,
+			GL::RGBA, GL::UNSIGNED_BYTE, None
+		).map_err(|e| format!("Failed to allocate texture: {:?}", e))?;
+
+		self.size.set((width, height));
+		self.loaded.set(true);
+		self.update_mipmaps(gl);
+
+		Ok(())
+	}
+
+	/// Changes how the texture is filtered and wrapped, for every clone
+	/// sharing it.
+	///
+	/// Mipmaps are generated now if enabled, and again whenever the
+	/// contents are replaced. Render target textures drawn into later
+	/// should call [`generate_mipmaps`](Self::generate_mipmaps) after
+	/// drawing.
+	pub fn set_sampler(&self, gl: &GL, sampler: SamplerDesc) {
+		self.sampler.set(sampler);
+
+		gl.bind_texture(GL::TEXTURE_2D, Some(&self.texture));
+		sampler.apply(gl);
+		self.update_mipmaps(gl);
+	}
+
+	pub fn with_sampler(self, gl: &GL, sampler: SamplerDesc) -> Self {
+		self.set_sampler(gl, sampler);
+		self
+	}
+
+	pub fn sampler(&self) -> SamplerDesc {
+		self.sampler.get()
+	}
+
+	/// Regenerates the mip levels from the full-size image.
+	pub fn generate_mipmaps(&self, gl: &GL) {
+		gl.bind_texture(GL::TEXTURE_2D, Some(&self.texture));
+		gl.generate_mipmap(GL::TEXTURE_2D);
+	}
+
+	/// Regenerates mipmaps if the sampler uses them and there's an image.
+	fn update_mipmaps(&self, gl: &GL) {
+		let (width, height) = self.size.get();
+
+		if self.sampler.get().mipmap_filter.is_some() && width > 0 && height > 0 {
+			self.generate_mipmaps(gl);
+		}
+	}
+
+	/// Binds the texture to a texture unit.
+	pub fn bind(&self, gl: &GL, unit: u32) {
+		gl.active_texture(GL::TEXTURE0 + unit);
+		gl.bind_texture(GL::TEXTURE_2D, Some(&self.texture));
+	}
+
+	pub fn texture(&self) -> &WebGlTexture {
+		&self.texture
+	}
+
+	pub fn width(&self) -> u32 {
+		self.size.get().0
+	}
+
+	pub fn height(&self) -> u32 {
+		self.size.get().1
+	}
+
+	/// Returns `false` while an image passed to [`load`](Self::load) is still loading.
+	pub fn is_loaded(&self) -> bool {
+		self.loaded.get()
+	}
+
+	fn create(gl: &GL) -> Result<Self, String> {
+		let texture = gl.create_texture().ok_or("Failed to create texture")?;
+
+		let sampler = SamplerDesc::default();
+
+		gl.bind_texture(GL::TEXTURE_2D, Some(&texture));
+		gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_MIN_FILTER, sampler.min_filter_gl() as i32);
+		gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_MAG_FILTER, sampler.mag_filter_gl() as i32);
+		gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_WRAP_S, sampler.wrap_s.to_gl() as i32);
+		gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_WRAP_T, sampler.wrap_t.to_gl() as i32);
+
+		Ok(Self {
+			texture,
+			size: Rc::new(Cell::new((0, 0))),
+			loaded: Rc::new(Cell::new(false)),
+			sampler: Rc::new(Cell::new(sampler)),
+		})
+	}
+}
+
+/// Parses a `.cube` 3D LUT into the RGBA8 pixels of a strip LUT.
+fn parse_cube_lut(source: &str) -> Result<(u32, Vec<u8>), String> {
+	let mut size = None;
+	let mut entries = Vec::new();
+
+	for line in source.lines().map(str::trim) {
+		if line.is_empty() || line.starts_with('#') {
+			continue;
+		}
+
+		if let Some(value) = line.strip_prefix("LUT_3D_SIZE") {
+			size = Some(value.trim().parse::<u32>().map_err(|_| format!("Invalid LUT size: {}", line))?);
+			continue;
+		}
+
+		// TITLE, DOMAIN_MIN, DOMAIN_MAX and other keywords
+		if line.starts_with(|c: char| c.is_ascii_alphabetic()) {
+			continue;
+		}
+
+		let rgb: Vec<f32> = line
+			.split_whitespace()
+			.map(str::parse)
+			.collect::<Result<_, _>>()
+			.map_err(|_| format!("Invalid LUT entry: {}", line))?;
+
+		if rgb.len() != 3 {
+			return Err(format!("Invalid LUT entry: {}", line));
+		}
+
+		entries.push([rgb[0], rgb[1], rgb[2]]);
+	}
+
+	let size = size.filter(|&size| size >= 2).ok_or("Missing or invalid LUT_3D_SIZE")?;
+	let n = size as usize;
+
+	if entries.len() != n * n * n {
+		return Err(format!("Expected {} LUT entries, got {}", n * n * n, entries.len()));
+	}
+
+	let mut pixels = vec![255; n * n * n * 4];
+
+	// Entries run red fastest, then green, then blue
+	for (i, rgb) in entries.iter().enumerate() {
+		let (r, g, b) = (i % n, (i / n) % n, i / (n * n));
+		let pixel = (g * n * n + b * n + r) * 4;
+
+		for (channel, value) in rgb.iter().enumerate() {
+			pixels[pixel + channel] = (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+		}
+	}
+
+	Ok((size, pixels))
+}
@@ -0,0 +1,553 @@
+//! TrueType Fonts
+//!
+//! Provides a minimal [`TrueTypeFont`] parser that reads glyph outlines,
+//! advances and kerning from `.ttf` files, for building real geometry from
+//! text with [`TextGeometry`](crate::renderer_3d::TextGeometry).
+//!
+//! Only TrueType outlines (`glyf`) are supported, not the CFF outlines of
+//! most `.otf` files. Hinting instructions are ignored.
+//!
+//! ## Examples
+//!
+//! ```ignore
+//! use oxgl::common::AssetLoader;
+//!
+//! let loader = AssetLoader::new();
+//!
+//! loader.on_truetype("fonts/Inter-Bold.ttf", |result| {
+//!		let font = result?;
+//!		let glyph = font.glyph_index('A');
+//!
+//!		// Contours of 'A' in font units, with curves split into 8 segments
+//!		let contours = font.contours(glyph, 8);
+//!		log::info!("{} contours, advance {}", contours.len(), font.advance(glyph));
+//!		Ok(())
+//! });
+//! ```
+//!
+
+use std::collections::HashMap;
+use glam::Vec2;
+
+/// Composite glyphs nested deeper than this are ignored.
+const MAX_COMPOSITE_DEPTH: u32 = 8;
+
+/// A point of a glyph outline in font units.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct OutlinePoint {
+	position: Vec2,
+	on_curve: bool,
+}
+
+/// A parsed TrueType font.
+#[derive(Clone, Debug)]
+pub struct TrueTypeFont {
+	data: Vec<u8>,
+	/// Offsets of the tables used for outlines.
+	glyf: usize,
+	loca: usize,
+	hmtx: usize,
+	/// `loca` uses 32-bit offsets.
+	long_offsets: bool,
+	glyph_count: u16,
+	metric_count: u16,
+	units_per_em: f32,
+	ascender: f32,
+	descender: f32,
+	line_gap: f32,
+	characters: HashMap<char, u16>,
+	kerning: HashMap<(u16, u16), f32>,
+}
+
+impl TrueTypeFont {
+	/// Parses a font from the bytes of a `.ttf` file.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the data isn't a TrueType font, is truncated,
+	/// or has no outlines.
+	pub fn parse(data: Vec<u8>) -> Result<Self, String> {
+		let version = read_u32(&data, 0).ok_or("Font data is empty")?;
+
+		match version {
+			0x0001_0000 | 0x7472_7565 => {}
+			0x4F54_544F => return Err("CFF outlines (OpenType .otf) are not supported".into()),
+			_ => return Err("Not a TrueType font".into()),
+		}
+
+		let table_count = read_u16(&data, 4).ok_or("Truncated font header")? as usize;
+		let mut tables = HashMap::new();
+
+		for i in 0..table_count {
+			let record = 12 + i * 16;
+			let tag = data.get(record..record + 4).ok_or("Truncated table directory")?;
+			let offset = read_u32(&data, record + 8).ok_or("Truncated table directory")? as usize;
+			tables.insert(String::from_utf8_lossy(tag).into_owned(), offset);
+		}
+
+		let table = |tag: &str| tables.get(tag).copied().ok_or_else(|| format!("Font has no `{}` table", tag));
+
+		let head = table("head")?;
+		let hhea = table("hhea")?;
+		let maxp = table("maxp")?;
+		let truncated = || "Truncated font tables".to_string();
+
+		let mut font = Self {
+			glyf: table("glyf")?,
+			loca: table("loca")?,
+			hmtx: table("hmtx")?,
+			long_offsets: read_i16(&data, head + 50).ok_or_else(truncated)? != 0,
+			glyph_count: read_u16(&data, maxp + 4).ok_or_else(truncated)?,
+			metric_count: read_u16(&data, hhea + 34).ok_or_else(truncated)?,
+			units_per_em: read_u16(&data, head + 18).ok_or_else(truncated)?.max(1) as f32,
+			ascender: read_i16(&data, hhea + 4).ok_or_else(truncated)? as f32,
+			descender: read_i16(&data, hhea + 6).ok_or_else(truncated)? as f32,
+			line_gap: read_i16(&data, hhea + 8).ok_or_else(truncated)? as f32,
+			characters: HashMap::new(),
+			kerning: HashMap::new(),
+			data,
+		};
+
+		font.characters = font.parse_cmap(table("cmap")?)?;
+
+		if let Ok(kern) = table("kern") {
+			font.kerning = font.parse_kern(kern);
+		}
+
+		Ok(font)
+	}
+
+	/// Returns the font units in one em, usually 1000 or 2048.
+	pub fn units_per_em(&self) -> f32 {
+		self.units_per_em
+	}
+
+	/// Returns the height above the baseline, in font units.
+	pub fn ascender(&self) -> f32 {
+		self.ascender
+	}
+
+	/// Returns the depth below the baseline as a negative number, in font
+	/// units.
+	pub fn descender(&self) -> f32 {
+		self.descender
+	}
+
+	/// Returns the distance between baselines, in font units.
+	pub fn line_height(&self) -> f32 {
+		self.ascender - self.descender + self.line_gap
+	}
+
+	pub fn glyph_count(&self) -> u16 {
+		self.glyph_count
+	}
+
+	/// Returns the glyph of a character, or glyph 0, the missing glyph box.
+	pub fn glyph_index(&self, c: char) -> u16 {
+		self.characters.get(&c).copied().unwrap_or(0)
+	}
+
+	pub fn has_glyph(&self, c: char) -> bool {
+		self.characters.contains_key(&c)
+	}
+
+	/// Returns the distance the pen moves after a glyph, in font units.
+	pub fn advance(&self, glyph: u16) -> f32 {
+		let index = glyph.min(self.metric_count.saturating_sub(1)) as usize;
+		read_u16(&self.data, self.hmtx + index * 4).unwrap_or(0) as f32
+	}
+
+	/// Returns the adjustment between two glyphs from the `kern` table, in
+	/// font units.
+	pub fn kerning(&self, left: u16, right: u16) -> f32 {
+		self.kerning.get(&(left, right)).copied().unwrap_or(0.0)
+	}
+
+	/// Returns the closed contours of a glyph in font units, with Y up and
+	/// quadratic curves split into `curve_segments` lines.
+	///
+	/// The first point isn't repeated at the end. Solid areas and holes
+	/// wind in opposite directions.
+	pub fn contours(&self, glyph: u16, curve_segments: u32) -> Vec<Vec<Vec2>> {
+		let mut outline = Vec::new();
+		self.outline(glyph, glam::Affine2::IDENTITY, 0, &mut outline);
+
+		outline
+			.iter()
+			.map(|contour| flatten(contour, curve_segments.max(1)))
+			.filter(|contour| contour.len() >= 3)
+			.collect()
+	}
+
+	/// Appends the raw contours of a glyph, transformed, to `contours`.
+	fn outline(&self, glyph: u16, transform: glam::Affine2, depth: u32, contours: &mut Vec<Vec<OutlinePoint>>) {
+		let Some((start, end)) = self.glyph_range(glyph) else {
+			return;
+		};
+
+		if end <= start {
+			return;
+		}
+
+		let Some(contour_count) = read_i16(&self.data, start) else {
+			return;
+		};
+
+		if contour_count >= 0 {
+			if let Some(simple) = self.simple_glyph(start, contour_count as usize) {
+				contours.extend(simple.into_iter().map(|contour| {
+					contour.into_iter().map(|point| OutlinePoint {
+						position: transform.transform_point2(point.position),
+						on_curve: point.on_curve,
+					}).collect()
+				}));
+			}
+		} else if depth < MAX_COMPOSITE_DEPTH {
+			self.composite_glyph(start + 10, transform, depth, contours);
+		}
+	}
+
+	/// Returns the byte range of a glyph in `glyf`.
+	fn glyph_range(&self, glyph: u16) -> Option<(usize, usize)> {
+		if glyph >= self.glyph_count {
+			return None;
+		}
+
+		let index = glyph as usize;
+		let (start, end) = if self.long_offsets {
+			(read_u32(&self.data, self.loca + index * 4)? as usize, read_u32(&self.data, self.loca + index * 4 + 4)? as usize)
+		} else {
+			(read_u16(&self.data, self.loca + index * 2)? as usize * 2, read_u16(&self.data, self.loca + index * 2 + 2)? as usize * 2)
+		};
+
+		Some((self.glyf + start, self.glyf + end))
+	}
+
+	fn simple_glyph(&self, start: usize, contour_count: usize) -> Option<Vec<Vec<OutlinePoint>>> {
+		let data = &self.data;
+		let mut offset = start + 10;
+		let mut ends = Vec::with_capacity(contour_count);
+
+		for _ in 0..contour_count {
+			ends.push(read_u16(data, offset)? as usize);
+			offset += 2;
+		}
+
+		let point_count = ends.last().map_or(0, |&end| end + 1);
+		let instructions = read_u16(data, offset)? as usize;
+		offset += 2 + instructions;
+
+		let mut flags = Vec::with_capacity(point_count);
+
+		while flags.len() < point_count {
+			let flag = *data.get(offset)?;
+			offset += 1;
+			flags.push(flag);
+
+			if flag & 8 != 0 {
+				let repeat = *data.get(offset)?;
+				offset += 1;
+				flags.extend(std::iter::repeat_n(flag, repeat as usize));
+			}
+		}
+
+		flags.truncate(point_count);
+
+		let mut read_coordinates = |short: u8, same_or_positive: u8| -> Option<Vec<f32>> {
+			let mut value = 0i32;
+			let mut values = Vec::with_capacity(point_count);
+
+			for &flag in &flags {
+				if flag & short != 0 {
+					let delta = *data.get(offset)? as i32;
+					offset += 1;
+					value += if flag & same_or_positive != 0 { delta } else { -delta };
+				} else if flag & same_or_positive == 0 {
+					value += read_i16(data, offset)? as i32;
+					offset += 2;
+				}
+
+				values.push(value as f32);
+			}
+
+			Some(values)
+		};
+
+		let xs = read_coordinates(2, 16)?;
+		let ys = read_coordinates(4, 32)?;
+
+		let mut contours = Vec::with_capacity(contour_count);
+		let mut first = 0;
+
+		for end in ends {
+			if end < first || end >= point_count {
+				return None;
+			}
+
+			contours.push((first..=end).map(|i| OutlinePoint {
+				position: Vec2::new(xs[i], ys[i]),
+				on_curve: flags[i] & 1 != 0,
+			}).collect());
+
+			first = end + 1;
+		}
+
+		Some(contours)
+	}
+
+	fn composite_glyph(&self, mut offset: usize, transform: glam::Affine2, depth: u32, contours: &mut Vec<Vec<OutlinePoint>>) {
+		const ARGS_ARE_WORDS: u16 = 0x0001;
+		const ARGS_ARE_XY: u16 = 0x0002;
+		const HAS_SCALE: u16 = 0x0008;
+		const MORE_COMPONENTS: u16 = 0x0020;
+		const HAS_XY_SCALE: u16 = 0x0040;
+		const HAS_2X2: u16 = 0x0080;
+
+		let data = &self.data;
+
+		loop {
+			let (Some(flags), Some(glyph)) = (read_u16(data, offset), read_u16(data, offset + 2)) else {
+				return;
+			};
+			offset += 4;
+
+			let (dx, dy) = if flags & ARGS_ARE_WORDS != 0 {
+				let args = (read_i16(data, offset), read_i16(data, offset + 2));
+				offset += 4;
+				(args.0.unwrap_or(0) as f32, args.1.unwrap_or(0) as f32)
+			} else {
+				let args = (data.get(offset).copied(), data.get(offset + 1).copied());
+				offset += 2;
+				(args.0.unwrap_or(0) as i8 as f32, args.1.unwrap_or(0) as i8 as f32)
+			};
+
+			// Components placed by matching points are left unmoved
+			let translation = if flags & ARGS_ARE_XY != 0 { Vec2::new(dx, dy) } else { Vec2::ZERO };
+
+			let f2dot14 = |offset: usize| read_i16(data, offset).unwrap_or(0) as f32 / 16384.0;
+			let (x_axis, y_axis) = if flags & HAS_SCALE != 0 {
+				offset += 2;
+				let scale = f2dot14(offset - 2);
+				(Vec2::new(scale, 0.0), Vec2::new(0.0, scale))
+			} else if flags & HAS_XY_SCALE != 0 {
+				offset += 4;
+				(Vec2::new(f2dot14(offset - 4), 0.0), Vec2::new(0.0, f2dot14(offset - 2)))
+			} else if flags & HAS_2X2 != 0 {
+				offset += 8;
+				(Vec2::new(f2dot14(offset - 8), f2dot14(offset - 6)), Vec2::new(f2dot14(offset - 4), f2dot14(offset - 2)))
+			} else {
+				(Vec2::X, Vec2::Y)
+			};
+
+			let component = glam::Affine2::from_cols(x_axis, y_axis, translation);
+			self.outline(glyph, transform * component, depth + 1, contours);
+
+			if flags & MORE_COMPONENTS == 0 {
+				return;
+			}
+		}
+	}
+
+	/// Reads the best Unicode subtable of `cmap`.
+	fn parse_cmap(&self, cmap: usize) -> Result<HashMap<char, u16>, String> {
+		let data = &self.data;
+		let subtable_count = read_u16(data, cmap + 2).ok_or("Truncated cmap")? as usize;
+		let mut best: Option<(u32, usize)> = None;
+
+		for i in 0..subtable_count {
+			let record = cmap + 4 + i * 8;
+			let (Some(platform), Some(encoding), Some(offset)) =
+				(read_u16(data, record), read_u16(data, record + 2), read_u32(data, record + 4)) else {
+				break;
+			};
+			let subtable = cmap + offset as usize;
+
+			// Prefer full Unicode, then the Basic Multilingual Plane
+			let rank = match (platform, encoding, read_u16(data, subtable)) {
+				(3, 10, Some(12)) | (0, _, Some(12)) => 2,
+				(3, 1, Some(4)) | (0, _, Some(4)) => 1,
+				_ => continue,
+			};
+
+			if best.is_none_or(|(best_rank, _)| rank > best_rank) {
+				best = Some((rank, subtable));
+			}
+		}
+
+		let (_, subtable) = best.ok_or("Font has no Unicode character map")?;
+		let mut characters = HashMap::new();
+
+		if read_u16(data, subtable) == Some(12) {
+			let groups = read_u32(data, subtable + 12).unwrap_or(0) as usize;
+
+			for group in 0..groups {
+				let record = subtable + 16 + group * 12;
+				let (Some(first), Some(last), Some(glyph)) =
+					(read_u32(data, record), read_u32(data, record + 4), read_u32(data, record + 8)) else {
+					break;
+				};
+
+				for code in first..=last.min(first.saturating_add(0xFFFF)) {
+					if let Some(c) = char::from_u32(code) {
+						characters.insert(c, (glyph + code - first) as u16);
+					}
+				}
+			}
+		} else {
+			let segments = read_u16(data, subtable + 6).unwrap_or(0) as usize / 2;
+			let ends = subtable + 14;
+			let starts = ends + segments * 2 + 2;
+			let deltas = starts + segments * 2;
+			let range_offsets = deltas + segments * 2;
+
+			for segment in 0..segments {
+				let (Some(end), Some(start), Some(delta), Some(range_offset)) = (
+					read_u16(data, ends + segment * 2),
+					read_u16(data, starts + segment * 2),
+					read_u16(data, deltas + segment * 2),
+					read_u16(data, range_offsets + segment * 2),
+				) else {
+					break;
+				};
+
+				for code in start..=end {
+					if code == 0xFFFF {
+						break;
+					}
+
+					let glyph = if range_offset == 0 {
+						code.wrapping_add(delta)
+					} else {
+						let address = range_offsets + segment * 2 + range_offset as usize + (code - start) as usize * 2;
+
+						match read_u16(data, address) {
+							Some(0) | None => 0,
+							Some(glyph) => glyph.wrapping_add(delta),
+						}
+					};
+
+					if glyph != 0 && let Some(c) = char::from_u32(code as u32) {
+						characters.insert(c, glyph);
+					}
+				}
+			}
+		}
+
+		Ok(characters)
+	}
+
+	/// Reads horizontal pairs from the first format 0 subtable of `kern`.
+	fn parse_kern(&self, kern: usize) -> HashMap<(u16, u16), f32> {
+		let data = &self.data;
+		let mut pairs = HashMap::new();
+		let tables = read_u16(data, kern + 2).unwrap_or(0) as usize;
+		let mut offset = kern + 4;
+
+		for _ in 0..tables {
+			let (Some(length), Some(coverage)) = (read_u16(data, offset + 2), read_u16(data, offset + 4)) else {
+				break;
+			};
+
+			// Format 0, horizontal, not cross-stream
+			if coverage >> 8 == 0 && coverage & 1 != 0 && coverage & 4 == 0 {
+				let count = read_u16(data, offset + 6).unwrap_or(0) as usize;
+
+				for pair in 0..count {
+					let record = offset + 14 + pair * 6;
+					let (Some(left), Some(right), Some(value)) =
+						(read_u16(data, record), read_u16(data, record + 2), read_i16(data, record + 4)) else {
+						break;
+					};
+
+					pairs.insert((left, right), value as f32);
+				}
+
+				break;
+			}
+
+			offset += length as usize;
+		}
+
+		pairs
+	}
+}
+
+/// Converts a contour of on- and off-curve points into a polygon.
+///
+/// Two off-curve points in a row imply an on-curve point between them.
+fn flatten(points: &[OutlinePoint], segments: u32) -> Vec<Vec2> {
+	let count = points.len();
+
+	if count == 0 {
+		return Vec::new();
+	}
+
+	let start_index = points.iter().position(|point| point.on_curve);
+	let start = match start_index {
+		Some(i) => points[i].position,
+		None => (points[0].position + points[1 % count].position) * 0.5,
+	};
+	// Without on-curve points, start halfway to the second point
+	let first = start_index.map_or(1, |i| i + 1);
+
+	let mut polygon = vec![start];
+	let mut current = start;
+	let mut control: Option<Vec2> = None;
+
+	let curve_to = |polygon: &mut Vec<Vec2>, from: Vec2, control: Vec2, to: Vec2| {
+		for step in 1..=segments {
+			let t = step as f32 / segments as f32;
+			let a = from.lerp(control, t);
+			let b = control.lerp(to, t);
+			polygon.push(a.lerp(b, t));
+		}
+	};
+
+	for i in 0..count {
+		let point = points[(first + i) % count];
+
+		match (point.on_curve, control) {
+			(true, None) => {
+				polygon.push(point.position);
+				current = point.position;
+			}
+			(true, Some(c)) => {
+				curve_to(&mut polygon, current, c, point.position);
+				current = point.position;
+				control = None;
+			}
+			(false, None) => control = Some(point.position),
+			(false, Some(c)) => {
+				let middle = (c + point.position) * 0.5;
+				curve_to(&mut polygon, current, c, middle);
+				current = middle;
+				control = Some(point.position);
+			}
+		}
+	}
+
+	if let Some(c) = control {
+		curve_to(&mut polygon, current, c, start);
+	}
+
+	// Drop repeated points, including the closing one
+	polygon.dedup_by(|a, b| a.distance_squared(*b) < 1e-6);
+
+	while polygon.len() > 1 && polygon[0].distance_squared(polygon[polygon.len() - 1]) < 1e-6 {
+		polygon.pop();
+	}
+
+	polygon
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+	Some(u16::from_be_bytes(data.get(offset..offset + 2)?.try_into().ok()?))
+}
+
+fn read_i16(data: &[u8], offset: usize) -> Option<i16> {
+	read_u16(data, offset).map(|value| value as i16)
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+	Some(u32::from_be_bytes(data.get(offset..offset + 4)?.try_into().ok()?))
+}
@@ -0,0 +1,233 @@
+//! Typed Uniforms
+//!
+//! Maps Rust structs to GLSL uniforms, replacing stringly-typed
+//! `set_float`/`set_vec3` calls. Uniform locations are looked up once per
+//! program and cached.
+//!
+//! With the `derive` feature, implement [`Uniforms`] with
+//! `#[derive(Uniforms)]`. Fields map to camelCase uniform names, which can be
+//! overridden with `#[uniform(name = "...")]` or skipped with
+//! `#[uniform(skip)]`.
+//!
+//! ## Examples
+//!
+//! ```ignore
+//! use oxgl::common::Uniforms;
+//! use glam::Vec3;
+//!
+//! #[derive(Clone, Uniforms)]
+//! struct Glow {
+//!		color: Vec3,
+//!		glow_strength: f32,
+//! }
+//!
+//! material.set_uniforms(Glow { color: Vec3::X, glow_strength: 0.5 });
+//!
+//! // Later, e.g. in the update loop
+//! if let Some(glow) = material.uniforms_mut::<Glow>() {
+//!		glow.glow_strength = time.sin().abs();
+//! }
+//! ```
+//!
+
+use std::{any::Any, cell::RefCell};
+use glam::{IVec2, IVec3, IVec4, Mat3, Mat4, Vec2, Vec3, Vec4};
+use web_sys::{WebGlProgram, WebGlUniformLocation, WebGl2RenderingContext as GL};
+
+#[doc(hidden)]
+pub mod __private {
+	pub use web_sys::{WebGl2RenderingContext as GL, WebGlUniformLocation};
+}
+
+/// A value that can be uploaded to a single uniform.
+pub trait UniformValue {
+	fn upload(&self, gl: &GL, location: &WebGlUniformLocation);
+}
+
+impl UniformValue for f32 {
+	fn upload(&self, gl: &GL, location: &WebGlUniformLocation) {
+		gl.uniform1f(Some(location), *self);
+	}
+}
+
+impl UniformValue for i32 {
+	fn upload(&self, gl: &GL, location: &WebGlUniformLocation) {
+		gl.uniform1i(Some(location), *self);
+	}
+}
+
+impl UniformValue for u32 {
+	fn upload(&self, gl: &GL, location: &WebGlUniformLocation) {
+		gl.uniform1ui(Some(location), *self);
+	}
+}
+
+impl UniformValue for bool {
+	fn upload(&self, gl: &GL, location: &WebGlUniformLocation) {
+		gl.uniform1i(Some(location), *self as i32);
+	}
+}
+
+impl UniformValue for Vec2 {
+	fn upload(&self, gl: &GL, location: &WebGlUniformLocation) {
+		gl.uniform2fv_with_f32_array(Some(location), &self.to_array());
+	}
+}
+
+impl UniformValue for Vec3 {
+	fn upload(&self, gl: &GL, location: &WebGlUniformLocation) {
+		gl.uniform3fv_with_f32_array(Some(location), &self.to_array());
+	}
+}
+
+impl UniformValue for Vec4 {
+	fn upload(&self, gl: &GL, location: &WebGlUniformLocation) {
+		gl.uniform4fv_with_f32_array(Some(location), &self.to_array());
+	}
+}
+
+impl UniformValue for IVec2 {
+	fn upload(&self, gl: &GL, location: &WebGlUniformLocation) {
+		gl.uniform2i(Some(location), self.x, self.y);
+	}
+}
+
+impl UniformValue for IVec3 {
+	fn upload(&self, gl: &GL, location: &WebGlUniformLocation) {
+		gl.uniform3i(Some(location), self.x, self.y, self.z);
+	}
+}
+
+impl UniformValue for IVec4 {
+	fn upload(&self, gl: &GL, location: &WebGlUniformLocation) {
+		gl.uniform4i(Some(location), self.x, self.y, self.z, self.w);
+	}
+}
+
+impl UniformValue for Mat3 {
+	fn upload(&self, gl: &GL, location: &WebGlUniformLocation) {
+		gl.uniform_matrix3fv_with_f32_array(Some(location), false, &self.to_cols_array());
+	}
+}
+
+impl UniformValue for Mat4 {
+	fn upload(&self, gl: &GL, location: &WebGlUniformLocation) {
+		gl.uniform_matrix4fv_with_f32_array(Some(location), false, &self.to_cols_array());
+	}
+}
+
+/// A struct whose fields map to shader uniforms.
+///
+/// Usually implemented with `#[derive(Uniforms)]`.
+pub trait Uniforms: 'static {
+	/// GLSL uniform names, in upload order.
+	const NAMES: &'static [&'static str];
+
+	/// Uploads every field, given the locations resolved for [`NAMES`](Self::NAMES).
+	fn upload(&self, gl: &GL, locations: &[Option<WebGlUniformLocation>]);
+}
+
+/// Uniform locations cached for the last program they were resolved against.
+#[derive(Clone, Default)]
+pub struct UniformLocations {
+	program: Option<WebGlProgram>,
+	locations: Vec<Option<WebGlUniformLocation>>,
+}
+
+impl UniformLocations {
+	/// Returns the locations of `names` in `program`, resolving them only
+	/// when the program changed since the last call.
+	pub fn resolve(&mut self, gl: &GL, program: &WebGlProgram, names: &[&str]) -> &[Option<WebGlUniformLocation>] {
+		if self.program.as_ref() != Some(program) {
+			self.locations = names.iter().map(|name| gl.get_uniform_location(program, name)).collect();
+			self.program = Some(program.clone());
+		}
+
+		&self.locations
+	}
+}
+
+/// Type-erased typed uniform block, as stored by [`UniformBlocks`].
+trait UniformBlock {
+	fn apply(&self, gl: &GL, program: &WebGlProgram);
+	fn as_any(&self) -> &dyn Any;
+	fn as_any_mut(&mut self) -> &mut dyn Any;
+	fn clone_box(&self) -> Box<dyn UniformBlock>;
+}
+
+struct TypedBlock<T: Uniforms + Clone> {
+	value: T,
+	locations: RefCell<UniformLocations>,
+}
+
+impl<T: Uniforms + Clone> UniformBlock for TypedBlock<T> {
+	fn apply(&self, gl: &GL, program: &WebGlProgram) {
+		let mut locations = self.locations.borrow_mut();
+		self.value.upload(gl, locations.resolve(gl, program, T::NAMES));
+	}
+
+	fn as_any(&self) -> &dyn Any {
+		&self.value
+	}
+
+	fn as_any_mut(&mut self) -> &mut dyn Any {
+		&mut self.value
+	}
+
+	fn clone_box(&self) -> Box<dyn UniformBlock> {
+		Box::new(TypedBlock {
+			value: self.value.clone(),
+			locations: RefCell::new(self.locations.borrow().clone()),
+		})
+	}
+}
+
+/// A set of typed uniform structs, at most one per type.
+///
+/// Used by [`Material`](super::Material) and
+/// [`PostProcessEffect`](super::PostProcessEffect).
+#[derive(Default)]
+pub struct UniformBlocks {
+	blocks: Vec<Box<dyn UniformBlock>>,
+}
+
+impl UniformBlocks {
+	/// Adds a uniform struct, replacing any previous value of the same type.
+	pub fn set<T: Uniforms + Clone>(&mut self, value: T) {
+		match self.get_mut::<T>() {
+			Some(existing) => *existing = value,
+			None => self.blocks.push(Box::new(TypedBlock {
+				value,
+				locations: RefCell::new(UniformLocations::default()),
+			})),
+		}
+	}
+
+	pub fn get<T: Uniforms>(&self) -> Option<&T> {
+		self.blocks.iter().find_map(|b| b.as_any().downcast_ref())
+	}
+
+	pub fn get_mut<T: Uniforms>(&mut self) -> Option<&mut T> {
+		self.blocks.iter_mut().find_map(|b| b.as_any_mut().downcast_mut())
+	}
+
+	/// Removes the uniform struct of a type.
+	pub fn remove<T: Uniforms>(&mut self) -> bool {
+		let before = self.blocks.len();
+		self.blocks.retain(|b| !b.as_any().is::<T>());
+		self.blocks.len() != before
+	}
+
+	/// Uploads every uniform struct to a program.
+	pub fn apply(&self, gl: &GL, program: &WebGlProgram) {
+		for block in &self.blocks {
+			block.apply(gl, program);
+		}
+	}
+}
+
+impl Clone for UniformBlocks {
+	fn clone(&self) -> Self {
+		Self { blocks: self.blocks.iter().map(|b| b.clone_box()).collect() }
+	}
+}
@@ -0,0 +1,793 @@
+//! Keyframe Animation
+//!
+//! Provides transform animation clips, cross-fade blending, layered and additive
+//! blending, 1D blend trees, and a parameter-driven state machine for
+//! controlling playback.
+//!
+//! ## Examples
+//!
+//! ```
+//! use oxgl::core::{Transform3D, animation::*};
+//! use glam::Vec3;
+//!
+//! let idle = AnimationClip::new("idle")
+//!		.with_position_key(0.0, Vec3::ZERO)
+//!		.with_position_key(1.0, Vec3::new(0.0, 0.1, 0.0));
+//! let walk = AnimationClip::new("walk")
+//!		.with_position_key(0.0, Vec3::ZERO)
+//!		.with_position_key(0.5, Vec3::new(0.0, 0.3, 0.0));
+//! let run = AnimationClip::new("run")
+//!		.with_position_key(0.0, Vec3::ZERO)
+//!		.with_position_key(0.3, Vec3::new(0.0, 0.5, 0.0));
+//! let jump = AnimationClip::new("jump")
+//!		.with_position_key(0.0, Vec3::ZERO)
+//!		.with_position_key(0.6, Vec3::new(0.0, 1.0, 0.0));
+//!
+//! // Walking blends into running as the speed goes from 1.5 to 4
+//! let locomotion = BlendTree1D::new("speed")
+//!		.with_clip(1.5, walk)
+//!		.with_clip(4.0, run);
+//!
+//! let mut layer = AnimationLayer::new(BlendMode::Override);
+//! let idle_state = layer.add_state(AnimationState::new(idle));
+//! let move_state = layer.add_state(AnimationState::blend_tree(locomotion));
+//! let jump_state = layer.add_state(AnimationState::new(jump).with_looping(false));
+//! layer.add_transition(
+//!		Transition::new(idle_state, move_state, 0.25).when(Condition::Greater("speed".into(), 0.1))
+//! );
+//! layer.add_transition(
+//!		Transition::new(move_state, idle_state, 0.25).when(Condition::Less("speed".into(), 0.1))
+//! );
+//! layer.add_transition(Transition::from_any(jump_state, 0.1).when(Condition::Triggered("jump".into())));
+//! layer.add_transition(Transition::new(jump_state, idle_state, 0.2).with_exit_time(1.0));
+//!
+//! let mut controller = AnimationController::new();
+//! controller.add_layer(layer);
+//! controller.set_float("speed", 2.5);
+//! controller.update(1.0 / 60.0);
+//!
+//! // Triggers stay set until a transition uses them, here once the fade
+//! // into the blend tree has finished
+//! controller.set_trigger("jump");
+//! controller.update(0.3);
+//! assert_eq!(controller.parameter("jump"), Some(Parameter::Trigger(false)));
+//!
+//! let pose = controller.evaluate(&Transform3D::new());
+//! ```
+//!
+
+use std::collections::HashMap;
+use glam::{Quat, Vec3};
+
+use super::Transform3D;
+
+/// Values that can be interpolated between keyframes.
+pub trait Interpolate: Copy {
+	fn interpolate(a: Self, b: Self, t: f32) -> Self;
+}
+
+impl Interpolate for f32 {
+	fn interpolate(a: Self, b: Self, t: f32) -> Self {
+		a + (b - a) * t
+	}
+}
+
+impl Interpolate for Vec3 {
+	fn interpolate(a: Self, b: Self, t: f32) -> Self {
+		a.lerp(b, t)
+	}
+}
+
+impl Interpolate for Quat {
+	fn interpolate(a: Self, b: Self, t: f32) -> Self {
+		a.slerp(b, t)
+	}
+}
+
+/// A single keyframe of an animation track.
+#[derive(Clone, Copy, Debug)]
+pub struct Keyframe<T> {
+	pub time: f32,
+	pub value: T,
+}
+
+/// A sorted list of keyframes sampled with linear interpolation.
+#[derive(Clone, Debug)]
+pub struct Track<T> {
+	keyframes: Vec<Keyframe<T>>,
+}
+
+impl<T> Default for Track<T> {
+	fn default() -> Self {
+		Self { keyframes: Vec::new() }
+	}
+}
+
+impl<T: Interpolate> Track<T> {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Inserts a keyframe, keeping the track sorted by time.
+	pub fn insert(&mut self, time: f32, value: T) {
+		let index = self.keyframes.partition_point(|k| k.time <= time);
+		self.keyframes.insert(index, Keyframe { time, value });
+	}
+
+	pub fn keyframes(&self) -> &[Keyframe<T>] {
+		&self.keyframes
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.keyframes.is_empty()
+	}
+
+	/// Returns the time of the last keyframe.
+	pub fn duration(&self) -> f32 {
+		self.keyframes.last().map(|k| k.time).unwrap_or(0.0)
+	}
+
+	/// Samples the track at the given time.
+	///
+	/// Times outside the keyframe range are clamped. Returns `None` for an
+	/// empty track.
+	pub fn sample(&self, time: f32) -> Option<T> {
+		let first = self.keyframes.first()?;
+		let last = self.keyframes.last()?;
+
+		if time <= first.time {
+			return Some(first.value);
+		}
+		if time >= last.time {
+			return Some(last.value);
+		}
+
+		let next = self.keyframes.partition_point(|k| k.time <= time);
+		let a = &self.keyframes[next - 1];
+		let b = &self.keyframes[next];
+		let t = (time - a.time) / (b.time - a.time);
+
+		Some(T::interpolate(a.value, b.value, t))
+	}
+}
+
+/// A named set of transform tracks.
+///
+/// Channels without keyframes fall back to the base transform passed to
+/// [`AnimationClip::sample`].
+#[derive(Clone, Debug)]
+pub struct AnimationClip {
+	pub name: String,
+	pub position: Track<Vec3>,
+	pub rotation: Track<Quat>,
+	pub scale: Track<Vec3>,
+}
+
+impl AnimationClip {
+	pub fn new(name: &str) -> Self {
+		Self {
+			name: name.to_string(),
+			position: Track::new(),
+			rotation: Track::new(),
+			scale: Track::new(),
+		}
+	}
+
+	pub fn with_position_key(mut self, time: f32, value: Vec3) -> Self {
+		self.position.insert(time, value);
+		self
+	}
+
+	pub fn with_rotation_key(mut self, time: f32, value: Quat) -> Self {
+		self.rotation.insert(time, value);
+		self
+	}
+
+	pub fn with_scale_key(mut self, time: f32, value: Vec3) -> Self {
+		self.scale.insert(time, value);
+		self
+	}
+
+	/// Returns the length of the clip in seconds.
+	pub fn duration(&self) -> f32 {
+		self.position.duration()
+			.max(self.rotation.duration())
+			.max(self.scale.duration())
+	}
+
+	/// Samples the clip, using `base` for channels the clip doesn't animate.
+	pub fn sample(&self, time: f32, base: &Transform3D) -> Transform3D {
+		Transform3D {
+			position: self.position.sample(time).unwrap_or(base.position),
+			rotation: self.rotation.sample(time).unwrap_or(base.rotation),
+			scale: self.scale.sample(time).unwrap_or(base.scale),
+		}
+	}
+
+	/// Samples the clip as a delta relative to its first frame.
+	///
+	/// Used for additive blending. Scale axes that start at zero have no
+	/// meaningful ratio and are left unscaled.
+	fn sample_delta(&self, time: f32) -> Transform3D {
+		let identity = Transform3D::new();
+		let reference = self.sample(0.0, &identity);
+		let current = self.sample(time, &identity);
+		let scale = Vec3::select(reference.scale.cmpeq(Vec3::ZERO), Vec3::ONE, current.scale / reference.scale);
+
+		Transform3D {
+			position: current.position - reference.position,
+			rotation: current.rotation * reference.rotation.inverse(),
+			scale,
+		}
+	}
+}
+
+/// Blends two transforms, returning `a` at `weight = 0` and `b` at `weight = 1`.
+pub fn blend_transforms(a: &Transform3D, b: &Transform3D, weight: f32) -> Transform3D {
+	a.lerp(b, weight)
+}
+
+/// Applies a delta transform on top of `base`, scaled by `weight`.
+fn apply_additive(base: &Transform3D, delta: &Transform3D, weight: f32) -> Transform3D {
+	Transform3D {
+		position: base.position + delta.position * weight,
+		rotation: Quat::IDENTITY.slerp(delta.rotation, weight) * base.rotation,
+		scale: base.scale * Vec3::ONE.lerp(delta.scale, weight),
+	}
+}
+
+/// How a layer combines with the layers below it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BlendMode {
+	/// Replaces the pose below, weighted by the layer weight.
+	Override,
+	/// Adds the clip's motion relative to its first frame on top of the pose below.
+	Additive,
+}
+
+/// A named animation parameter used to drive transitions and blend trees.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Parameter {
+	Float(f32),
+	Bool(bool),
+	/// A one-shot flag, set by
+	/// [`AnimationController::set_trigger`] and cleared when a transition
+	/// it enables fires.
+	Trigger(bool),
+}
+
+/// A condition on a parameter that must hold for a transition to fire.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Condition {
+	Greater(String, f32),
+	Less(String, f32),
+	True(String),
+	False(String),
+	/// Holds while a trigger is set. Firing the transition clears it.
+	Triggered(String),
+}
+
+impl Condition {
+	fn evaluate(&self, parameters: &HashMap<String, Parameter>) -> bool {
+		match self {
+			Condition::Greater(name, v) => matches!(parameters.get(name), Some(Parameter::Float(p)) if p > v),
+			Condition::Less(name, v) => matches!(parameters.get(name), Some(Parameter::Float(p)) if p < v),
+			Condition::True(name) => matches!(parameters.get(name), Some(Parameter::Bool(true))),
+			Condition::False(name) => !matches!(parameters.get(name), Some(Parameter::Bool(true))),
+			Condition::Triggered(name) => matches!(parameters.get(name), Some(Parameter::Trigger(true))),
+		}
+	}
+}
+
+/// Index of a state within an [`AnimationLayer`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct StateId(pub usize);
+
+/// Clips blended by a float parameter, such as idle, walk and run by speed.
+///
+/// Each clip sits at a threshold of the parameter. Values between two
+/// thresholds blend their clips, values outside play the nearest clip.
+/// Blended clips play at the same normalized time, so cycles of different
+/// lengths like footsteps stay in step.
+///
+/// ## Examples
+///
+/// ```
+/// use oxgl::core::{Transform3D, animation::*};
+/// use glam::Vec3;
+///
+/// let low = AnimationClip::new("low").with_position_key(0.0, Vec3::ZERO).with_position_key(1.0, Vec3::ZERO);
+/// let high = AnimationClip::new("high").with_position_key(0.0, Vec3::Y).with_position_key(2.0, Vec3::Y);
+///
+/// let tree = BlendTree1D::new("speed").with_clip(0.0, low).with_clip(4.0, high);
+///
+/// let pose = tree.sample(0.0, 1.0, &Transform3D::new());
+/// assert!((pose.position.y - 0.25).abs() < 1e-5);
+/// assert!((tree.duration(1.0) - 1.25).abs() < 1e-5);
+/// ```
+#[derive(Clone, Debug)]
+pub struct BlendTree1D {
+	/// Name of the float parameter choosing the blend.
+	pub parameter: String,
+	clips: Vec<(f32, AnimationClip)>,
+}
+
+impl BlendTree1D {
+	pub fn new(parameter: &str) -> Self {
+		Self { parameter: parameter.to_string(), clips: Vec::new() }
+	}
+
+	/// Adds a clip played fully when the parameter equals `threshold`.
+	pub fn with_clip(mut self, threshold: f32, clip: AnimationClip) -> Self {
+		let index = self.clips.partition_point(|(t, _)| *t <= threshold);
+		self.clips.insert(index, (threshold, clip));
+		self
+	}
+
+	/// Returns the clips with their thresholds, in increasing order.
+	pub fn clips(&self) -> &[(f32, AnimationClip)] {
+		&self.clips
+	}
+
+	/// Returns the blended length in seconds for a parameter value.
+	pub fn duration(&self, value: f32) -> f32 {
+		self.blend(value).map_or(0.0, |(a, b, weight)| {
+			let (a, b) = (self.clips[a].1.duration(), self.clips[b].1.duration());
+			a + (b - a) * weight
+		})
+	}
+
+	/// Samples the blend at a normalized time (0-1) for a parameter value.
+	pub fn sample(&self, progress: f32, value: f32, base: &Transform3D) -> Transform3D {
+		self.blend_with(progress, value, base.clone(), |clip, time| clip.sample(time, base))
+	}
+
+	fn sample_delta(&self, progress: f32, value: f32) -> Transform3D {
+		self.blend_with(progress, value, Transform3D::new(), |clip, time| clip.sample_delta(time))
+	}
+
+	fn blend_with(&self, progress: f32, value: f32, empty: Transform3D, sample: impl Fn(&AnimationClip, f32) -> Transform3D) -> Transform3D {
+		let Some((a, b, weight)) = self.blend(value) else {
+			return empty;
+		};
+
+		let (clip_a, clip_b) = (&self.clips[a].1, &self.clips[b].1);
+		let pose_a = sample(clip_a, progress * clip_a.duration());
+
+		if a == b {
+			return pose_a;
+		}
+
+		blend_transforms(&pose_a, &sample(clip_b, progress * clip_b.duration()), weight)
+	}
+
+	/// Returns the indices of the two clips to blend and the weight of the second.
+	fn blend(&self, value: f32) -> Option<(usize, usize, f32)> {
+		let last = self.clips.len().checked_sub(1)?;
+		let upper = self.clips.partition_point(|(t, _)| *t <= value);
+
+		Some(match upper {
+			0 => (0, 0, 0.0),
+			_ if upper > last => (last, last, 0.0),
+			_ => {
+				let (low, high) = (self.clips[upper - 1].0, self.clips[upper].0);
+				(upper - 1, upper, (value - low) / (high - low))
+			}
+		})
+	}
+}
+
+/// What an [`AnimationState`] plays.
+#[derive(Clone, Debug)]
+pub enum Motion {
+	Clip(AnimationClip),
+	Blend1D(BlendTree1D),
+}
+
+impl Motion {
+	/// Returns the length in seconds with the given parameters.
+	fn duration(&self, parameters: &HashMap<String, Parameter>) -> f32 {
+		match self {
+			Motion::Clip(clip) => clip.duration(),
+			Motion::Blend1D(tree) => tree.duration(float_parameter(parameters, &tree.parameter)),
+		}
+	}
+
+	fn sample(&self, progress: f32, base: &Transform3D, parameters: &HashMap<String, Parameter>) -> Transform3D {
+		match self {
+			Motion::Clip(clip) => clip.sample(progress * clip.duration(), base),
+			Motion::Blend1D(tree) => tree.sample(progress, float_parameter(parameters, &tree.parameter), base),
+		}
+	}
+
+	fn sample_delta(&self, progress: f32, parameters: &HashMap<String, Parameter>) -> Transform3D {
+		match self {
+			Motion::Clip(clip) => clip.sample_delta(progress * clip.duration()),
+			Motion::Blend1D(tree) => tree.sample_delta(progress, float_parameter(parameters, &tree.parameter)),
+		}
+	}
+}
+
+fn float_parameter(parameters: &HashMap<String, Parameter>, name: &str) -> f32 {
+	match parameters.get(name) {
+		Some(Parameter::Float(v)) => *v,
+		_ => 0.0,
+	}
+}
+
+/// A state playing a clip or a blend tree.
+#[derive(Clone, Debug)]
+pub struct AnimationState {
+	pub motion: Motion,
+	pub speed: f32,
+	pub looping: bool,
+}
+
+impl AnimationState {
+	pub fn new(clip: AnimationClip) -> Self {
+		Self { motion: Motion::Clip(clip), speed: 1.0, looping: true }
+	}
+
+	/// Creates a state blending clips by a parameter.
+	pub fn blend_tree(tree: BlendTree1D) -> Self {
+		Self { motion: Motion::Blend1D(tree), speed: 1.0, looping: true }
+	}
+
+	pub fn with_speed(mut self, speed: f32) -> Self {
+		self.speed = speed;
+		self
+	}
+
+	pub fn with_looping(mut self, looping: bool) -> Self {
+		self.looping = looping;
+		self
+	}
+
+	/// Returns how far the normalized time moves in `dt` seconds.
+	fn advance(&self, dt: f32, parameters: &HashMap<String, Parameter>) -> f32 {
+		let duration = self.motion.duration(parameters);
+
+		if duration > 0.0 { dt * self.speed / duration } else { 1.0 }
+	}
+
+	fn wrap(&self, progress: f32) -> f32 {
+		if self.looping {
+			progress.rem_euclid(1.0)
+		} else {
+			progress.clamp(0.0, 1.0)
+		}
+	}
+}
+
+/// A transition between two states.
+///
+/// Fires when all conditions hold and, if set, the source state has played
+/// past `exit_time` (normalized, 0-1). A `from` of `None` means any state.
+#[derive(Clone, Debug)]
+pub struct Transition {
+	pub from: Option<StateId>,
+	pub to: StateId,
+	pub duration: f32,
+	pub conditions: Vec<Condition>,
+	pub exit_time: Option<f32>,
+}
+
+impl Transition {
+	pub fn new(from: StateId, to: StateId, duration: f32) -> Self {
+		Self { from: Some(from), to, duration, conditions: Vec::new(), exit_time: None }
+	}
+
+	/// Creates a transition that can fire from any state.
+	pub fn from_any(to: StateId, duration: f32) -> Self {
+		Self { from: None, to, duration, conditions: Vec::new(), exit_time: None }
+	}
+
+	pub fn when(mut self, condition: Condition) -> Self {
+		self.conditions.push(condition);
+		self
+	}
+
+	pub fn with_exit_time(mut self, exit_time: f32) -> Self {
+		self.exit_time = Some(exit_time);
+		self
+	}
+}
+
+/// An in-progress cross-fade from a previous state.
+#[derive(Clone, Debug)]
+struct Fade {
+	from: StateId,
+	from_progress: f32,
+	/// The fade that was still running when this one started. Its pose is
+	/// what this one fades from, so interrupting a fade doesn't snap.
+	previous: Option<Box<Fade>>,
+	elapsed: f32,
+	duration: f32,
+}
+
+impl Fade {
+	fn weight(&self) -> f32 {
+		(self.elapsed / self.duration).min(1.0)
+	}
+
+	/// Advances the fade and the fades it started from, dropping those that
+	/// finished.
+	fn advance(&mut self, dt: f32, states: &[AnimationState], parameters: &HashMap<String, Parameter>) {
+		self.elapsed += dt;
+		self.from_progress += states[self.from.0].advance(dt, parameters);
+
+		if let Some(previous) = &mut self.previous {
+			previous.advance(dt, states, parameters);
+
+			if previous.elapsed >= previous.duration {
+				self.previous = None;
+			}
+		}
+	}
+
+	/// Samples the pose being faded from.
+	fn sample(&self, states: &[AnimationState], base: &Transform3D, parameters: &HashMap<String, Parameter>) -> Transform3D {
+		let state = &states[self.from.0];
+		let pose = state.motion.sample(state.wrap(self.from_progress), base, parameters);
+
+		match &self.previous {
+			Some(previous) => blend_transforms(&previous.sample(states, base, parameters), &pose, previous.weight()),
+			None => pose,
+		}
+	}
+
+	/// Samples the additive delta being faded from.
+	fn sample_delta(&self, states: &[AnimationState], parameters: &HashMap<String, Parameter>) -> Transform3D {
+		let state = &states[self.from.0];
+		let delta = state.motion.sample_delta(state.wrap(self.from_progress), parameters);
+
+		match &self.previous {
+			Some(previous) => blend_transforms(&previous.sample_delta(states, parameters), &delta, previous.weight()),
+			None => delta,
+		}
+	}
+}
+
+/// A state machine whose pose is blended onto the layers below it.
+#[derive(Clone, Debug)]
+pub struct AnimationLayer {
+	states: Vec<AnimationState>,
+	transitions: Vec<Transition>,
+	current: StateId,
+	/// Normalized time of the current state, unwrapped.
+	progress: f32,
+	fade: Option<Fade>,
+	pub weight: f32,
+	pub mode: BlendMode,
+}
+
+impl AnimationLayer {
+	pub fn new(mode: BlendMode) -> Self {
+		Self {
+			states: Vec::new(),
+			transitions: Vec::new(),
+			current: StateId(0),
+			progress: 0.0,
+			fade: None,
+			weight: 1.0,
+			mode,
+		}
+	}
+
+	pub fn with_weight(mut self, weight: f32) -> Self {
+		self.weight = weight;
+		self
+	}
+
+	/// Adds a state. The first state added is the initial state.
+	pub fn add_state(&mut self, state: AnimationState) -> StateId {
+		self.states.push(state);
+		StateId(self.states.len() - 1)
+	}
+
+	pub fn add_transition(&mut self, transition: Transition) {
+		self.transitions.push(transition);
+	}
+
+	pub fn state_mut(&mut self, id: StateId) -> Option<&mut AnimationState> {
+		self.states.get_mut(id.0)
+	}
+
+	pub fn current_state(&self) -> StateId {
+		self.current
+	}
+
+	/// Returns `true` while a cross-fade is in progress.
+	pub fn is_transitioning(&self) -> bool {
+		self.fade.is_some()
+	}
+
+	/// Switches to a state immediately, restarting it.
+	pub fn play(&mut self, state: StateId) {
+		self.current = state;
+		self.progress = 0.0;
+		self.fade = None;
+	}
+
+	/// Cross-fades from the current state to another over `duration` seconds.
+	///
+	/// Interrupting a cross-fade fades from the blended pose it had reached.
+	pub fn cross_fade(&mut self, state: StateId, duration: f32) {
+		if duration <= 0.0 {
+			self.play(state);
+			return;
+		}
+
+		self.fade = Some(Fade {
+			from: self.current,
+			from_progress: self.progress,
+			previous: self.fade.take().map(Box::new),
+			elapsed: 0.0,
+			duration,
+		});
+		self.current = state;
+		self.progress = 0.0;
+	}
+
+	/// Advances the layer and takes the first matching transition, adding
+	/// the triggers it used to `used_triggers`.
+	fn update(&mut self, dt: f32, parameters: &HashMap<String, Parameter>, used_triggers: &mut Vec<String>) {
+		let Some(state) = self.states.get(self.current.0) else { return };
+		self.progress += state.advance(dt, parameters);
+
+		if let Some(fade) = &mut self.fade {
+			fade.advance(dt, &self.states, parameters);
+
+			if fade.elapsed >= fade.duration {
+				self.fade = None;
+			}
+		}
+
+		if self.fade.is_some() {
+			return;
+		}
+
+		let progress = if state.looping { self.progress.fract() } else { self.progress.min(1.0) };
+
+		let next = self.transitions.iter().find(|t| {
+			t.from.is_none_or(|from| from == self.current)
+				&& t.to != self.current
+				&& t.exit_time.is_none_or(|exit| progress >= exit)
+				&& t.conditions.iter().all(|c| c.evaluate(parameters))
+		});
+
+		if let Some(transition) = next.cloned() {
+			for condition in &transition.conditions {
+				if let Condition::Triggered(name) = condition {
+					used_triggers.push(name.clone());
+				}
+			}
+
+			self.cross_fade(transition.to, transition.duration);
+		}
+	}
+
+	fn sample(&self, base: &Transform3D, parameters: &HashMap<String, Parameter>) -> Option<Transform3D> {
+		let state = self.states.get(self.current.0)?;
+		let pose = state.motion.sample(state.wrap(self.progress), base, parameters);
+
+		let Some(fade) = &self.fade else { return Some(pose) };
+
+		Some(blend_transforms(&fade.sample(&self.states, base, parameters), &pose, fade.weight()))
+	}
+
+	fn sample_delta(&self, parameters: &HashMap<String, Parameter>) -> Option<Transform3D> {
+		let state = self.states.get(self.current.0)?;
+		let delta = state.motion.sample_delta(state.wrap(self.progress), parameters);
+
+		let Some(fade) = &self.fade else { return Some(delta) };
+
+		Some(blend_transforms(&fade.sample_delta(&self.states, parameters), &delta, fade.weight()))
+	}
+}
+
+/// Drives a stack of animation layers from named parameters.
+///
+/// Call [`update`](Self::update) once per frame, then
+/// [`evaluate`](Self::evaluate) or [`apply`](Self::apply) to get the pose.
+///
+/// ## Examples
+///
+/// ```ignore
+/// app.run(move |scene, time| {
+///		let dt = time - last_time;
+///		controller.set_float("speed", player_speed);
+///		controller.update(dt);
+///
+///		if let Some(obj) = scene.get_mut(character) {
+///			controller.apply(&rest_pose, &mut obj.transform);
+///		}
+/// });
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct AnimationController {
+	layers: Vec<AnimationLayer>,
+	parameters: HashMap<String, Parameter>,
+}
+
+impl AnimationController {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Adds a layer on top of the existing ones and returns its index.
+	pub fn add_layer(&mut self, layer: AnimationLayer) -> usize {
+		self.layers.push(layer);
+		self.layers.len() - 1
+	}
+
+	pub fn layer_mut(&mut self, index: usize) -> Option<&mut AnimationLayer> {
+		self.layers.get_mut(index)
+	}
+
+	pub fn set_float(&mut self, name: &str, value: f32) {
+		self.parameters.insert(name.to_string(), Parameter::Float(value));
+	}
+
+	pub fn set_bool(&mut self, name: &str, value: bool) {
+		self.parameters.insert(name.to_string(), Parameter::Bool(value));
+	}
+
+	/// Sets a trigger, which stays set until a transition with a
+	/// [`Condition::Triggered`] on it fires.
+	pub fn set_trigger(&mut self, name: &str) {
+		self.parameters.insert(name.to_string(), Parameter::Trigger(true));
+	}
+
+	/// Clears a trigger that hasn't been used yet.
+	pub fn reset_trigger(&mut self, name: &str) {
+		self.parameters.insert(name.to_string(), Parameter::Trigger(false));
+	}
+
+	pub fn parameter(&self, name: &str) -> Option<Parameter> {
+		self.parameters.get(name).copied()
+	}
+
+	/// Advances all layers and evaluates transitions.
+	///
+	/// Triggers are cleared once every layer has seen them, so layers that
+	/// react to the same trigger all fire.
+	pub fn update(&mut self, dt: f32) {
+		let mut used_triggers = Vec::new();
+
+		for layer in &mut self.layers {
+			layer.update(dt, &self.parameters, &mut used_triggers);
+		}
+
+		for name in used_triggers {
+			self.parameters.insert(name, Parameter::Trigger(false));
+		}
+	}
+
+	/// Computes the blended pose, starting from the `rest` transform.
+	pub fn evaluate(&self, rest: &Transform3D) -> Transform3D {
+		let mut pose = rest.clone();
+
+		for layer in &self.layers {
+			match layer.mode {
+				BlendMode::Override => {
+					if let Some(layer_pose) = layer.sample(&pose, &self.parameters) {
+						pose = blend_transforms(&pose, &layer_pose, layer.weight);
+					}
+				}
+				BlendMode::Additive => {
+					if let Some(delta) = layer.sample_delta(&self.parameters) {
+						pose = apply_additive(&pose, &delta, layer.weight);
+					}
+				}
+			}
+		}
+
+		pose
+	}
+
+	/// Evaluates the pose and writes it into `target`.
+	pub fn apply(&self, rest: &Transform3D, target: &mut Transform3D) {
+		*target = self.evaluate(rest);
+	}
+}
@@ -1,15 +1,270 @@
 //! Browser Animation Loop
 //!
 //! Provides a wrapper around `requestAnimationFrame` for smooth rendering loops.
+//! Runs on the main thread and in web workers.
 //!
+//! Loops can be paced with [`FramePacing`], capping the frame rate to save
+//! battery and pausing while the tab is hidden, and their clock can be
+//! paused, slowed down or stepped one frame at a time with [`TimeScale`].
+//!
+//! ## Examples
+//!
+//! ```ignore
+//! use oxgl::core::{Animator, FramePacing};
+//!
+//! // Render at most 30 times a second, and not at all in background tabs
+//! let pacing = FramePacing::new().with_max_fps(30.0).with_pause_when_hidden(true);
+//!
+//! Animator::start_paced(pacing, |frame| {
+//!		if frame.skipped {
+//!			return;
+//!		}
+//!
+//!		scene.render(&renderer, frame.time);
+//! });
+//!
+//! // Or pace an app's loop
+//! app.run(|scene, time| {}).set_pacing(pacing);
+//!
+//! // Slow an app down to quarter speed, then pause and step through frames
+//! let time_scale = app.time_scale.clone();
+//! time_scale.set_scale(0.25);
+//! time_scale.set_paused(true);
+//! time_scale.step();
+//! ```
+//!
+
+use std::{cell::{Cell, RefCell}, rc::Rc};
+use js_sys::{Function, Reflect};
+use web_sys::wasm_bindgen::{JsValue, prelude::{Closure, JsCast}};
+
+/// How often an [`Animator`] runs its frames.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FramePacing {
+	/// Frames faster than this many per second are skipped, or `None` to
+	/// follow the display's refresh rate.
+	pub max_fps: Option<f32>,
+	/// Skips frames while the page is hidden, such as in a background tab.
+	/// Browsers usually stop animation frames there anyway, but not always
+	/// for embedded frames and other windows.
+	pub pause_when_hidden: bool,
+}
+
+impl FramePacing {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	pub fn with_max_fps(mut self, fps: f32) -> Self {
+		self.max_fps = Some(fps);
+		self
+	}
+
+	pub fn with_pause_when_hidden(mut self, pause: bool) -> Self {
+		self.pause_when_hidden = pause;
+		self
+	}
+
+	/// Returns the shortest time between rendered frames, in seconds.
+	///
+	/// ```
+	/// use oxgl::core::FramePacing;
+	///
+	/// assert_eq!(FramePacing::new().min_interval(), 0.0);
+	/// assert_eq!(FramePacing::new().with_max_fps(50.0).min_interval(), 0.02);
+	/// ```
+	pub fn min_interval(&self) -> f32 {
+		match self.max_fps {
+			Some(fps) if fps > 0.0 => 1.0 / fps,
+			_ => 0.0,
+		}
+	}
+}
+
+/// A controllable clock for an [`Animator`].
+///
+/// Starts at the time since the page loaded and runs at real speed until
+/// changed. Cloning is cheap and shares the clock, so a clone can pause an
+/// animator from an input handler or its own update callback.
+#[derive(Clone, Debug, Default)]
+pub struct TimeScale {
+	state: Rc<Cell<TimeState>>,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct TimeState {
+	scale: f32,
+	paused: bool,
+	/// Frames left to step while paused.
+	steps: u32,
+	step_size: f32,
+	/// Scaled and real time the clock was last rebased at, so the scaled
+	/// time doesn't drift from summing frame deltas.
+	base: Option<(f32, f32)>,
+	time: f32,
+	real_time: f32,
+}
+
+impl Default for TimeState {
+	fn default() -> Self {
+		Self {
+			scale: 1.0,
+			paused: false,
+			steps: 0,
+			step_size: 1.0 / 60.0,
+			base: None,
+			time: 0.0,
+			real_time: 0.0,
+		}
+	}
+}
+
+impl TimeScale {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	pub fn with_scale(self, scale: f32) -> Self {
+		self.set_scale(scale);
+		self
+	}
+
+	pub fn with_step_size(self, seconds: f32) -> Self {
+		self.set_step_size(seconds);
+		self
+	}
+
+	/// Returns how fast the clock runs, where `1.0` is real time.
+	pub fn scale(&self) -> f32 {
+		self.state.get().scale
+	}
+
+	/// Changes how fast the clock runs, such as `0.1` for slow motion.
+	/// Negative scales are clamped to zero.
+	pub fn set_scale(&self, scale: f32) {
+		self.update(|state| {
+			state.rebase();
+			state.scale = scale.max(0.0);
+		});
+	}
+
+	pub fn is_paused(&self) -> bool {
+		self.state.get().paused
+	}
+
+	/// Stops or restarts the clock. Paused clocks still run frames, so
+	/// scenes keep rendering with the same time.
+	pub fn set_paused(&self, paused: bool) {
+		self.update(|state| {
+			state.rebase();
+			state.paused = paused;
+			state.steps = 0;
+		});
+	}
+
+	pub fn toggle_paused(&self) {
+		self.set_paused(!self.is_paused());
+	}
+
+	/// Advances a paused clock by one [step](Self::set_step_size) on the
+	/// next frame. Calling it several times before a frame steps that many
+	/// times, one per frame.
+	pub fn step(&self) {
+		self.update(|state| {
+			if state.paused {
+				state.steps += 1;
+			}
+		});
+	}
+
+	pub fn step_size(&self) -> f32 {
+		self.state.get().step_size
+	}
+
+	/// Changes how far [`step`](Self::step) advances the clock, in scaled
+	/// seconds. Defaults to a 60 Hz frame.
+	pub fn set_step_size(&self, seconds: f32) {
+		self.update(|state| state.step_size = seconds.max(0.0));
+	}
+
+	/// Returns the scaled time of the latest frame, in seconds.
+	pub fn time(&self) -> f32 {
+		self.state.get().time
+	}
+
+	/// Returns the unscaled time of the latest frame, in seconds since the
+	/// page loaded, for things like frame rate counters.
+	pub fn real_time(&self) -> f32 {
+		self.state.get().real_time
+	}
+
+	/// Returns the scaled time at `real_time`. With `advance`, moves the
+	/// clock there and takes a pending step, otherwise only peeks.
+	fn tick(&self, real_time: f32, advance: bool) -> f32 {
+		let mut state = self.state.get();
+		let (base_time, base_real) = *state.base.get_or_insert((real_time, real_time));
+
+		let time = if state.paused {
+			let mut time = base_time;
 
-use std::{cell::RefCell, rc::Rc};
-use web_sys::wasm_bindgen::prelude::{Closure, JsCast};
+			if advance && state.steps > 0 {
+				state.steps -= 1;
+				time += state.step_size;
+			}
+
+			if advance {
+				state.base = Some((time, real_time));
+			}
+
+			time
+		} else {
+			base_time + (real_time - base_real) * state.scale
+		};
+
+		if advance {
+			state.time = time;
+			state.real_time = real_time;
+			self.state.set(state);
+		}
+
+		time
+	}
+
+	fn update(&self, f: impl FnOnce(&mut TimeState)) {
+		let mut state = self.state.get();
+		f(&mut state);
+		self.state.set(state);
+	}
+}
+
+impl TimeState {
+	/// Restarts scaling from the latest frame.
+	fn rebase(&mut self) {
+		if self.base.is_some() {
+			self.base = Some((self.time, self.real_time));
+		}
+	}
+}
+
+/// A frame passed to a paced [`Animator`] callback.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Frame {
+	/// Seconds on the animator's [`TimeScale`], which starts at the time
+	/// since the page loaded.
+	pub time: f32,
+	/// Scaled seconds since the last frame that wasn't skipped.
+	pub delta: f32,
+	/// Whether the frame should be skipped to keep to the
+	/// [`FramePacing`]. Skipped frames shouldn't render, but can still
+	/// poll input or run cheap logic.
+	pub skipped: bool,
+}
 
 /// Browser animation frame loop manager.
 ///
 /// Wraps `requestAnimationFrame` to provide a consistent render loop
-/// with elapsed time tracking.
+/// with elapsed time tracking. Time passes through the animator's
+/// [`TimeScale`], so it can be paused and slowed down.
 ///
 /// ## Examples
 ///
@@ -21,41 +276,168 @@ use web_sys::wasm_bindgen::prelude::{Closure, JsCast};
 /// ```
 pub struct Animator {
 	running: Rc<RefCell<bool>>,
+	pacing: Rc<Cell<FramePacing>>,
+	time_scale: TimeScale,
 }
 
 impl Animator {
-	pub fn start<F>(mut update: F) -> Self 
-	where 
+	/// Starts a loop calling `update` every frame that isn't skipped by
+	/// the [pacing](Self::set_pacing), which is unpaced by default.
+	pub fn start<F>(update: F) -> Self
+	where
 		F: FnMut(f32) + 'static
+	{
+		Self::start_scaled(TimeScale::new(), update)
+	}
+
+	/// Starts a loop like [`start`](Self::start), with time passing
+	/// through an existing clock.
+	pub fn start_scaled<F>(time_scale: TimeScale, mut update: F) -> Self
+	where
+		F: FnMut(f32) + 'static
+	{
+		Self::spawn(FramePacing::default(), time_scale, move |frame| {
+			if !frame.skipped {
+				update(frame.time);
+			}
+		})
+	}
+
+	/// Starts a loop calling `update` on every animation frame, telling it
+	/// which frames `pacing` skips.
+	pub fn start_paced<F>(pacing: FramePacing, update: F) -> Self
+	where
+		F: FnMut(Frame) + 'static
+	{
+		Self::spawn(pacing, TimeScale::new(), update)
+	}
+
+	fn spawn<F>(pacing: FramePacing, time_scale: TimeScale, mut update: F) -> Self
+	where
+		F: FnMut(Frame) + 'static
 	{
 		let running = Rc::new(RefCell::new(true));
 		let running_clone = running.clone();
+		let pacing = Rc::new(Cell::new(pacing));
+		let frame_pacing = pacing.clone();
+		let frame_time_scale = time_scale.clone();
+		let visibility = Visibility::watch();
+		let mut last_frame: Option<f32> = None;
+		let mut last_scaled: Option<f32> = None;
 
 		let f: Rc<RefCell<Option<Closure<dyn FnMut(f64)>>>> = Rc::new(RefCell::new(None));
 		let g = f.clone();
 
 		*g.borrow_mut() = Some(Closure::new(move |time_ms: f64| {
 			if !*running_clone.borrow() {
+				visibility.unwatch();
 				return;
 			}
-			
-			update((time_ms / 1000.0) as f32);
-			
-			web_sys::window()
-				.unwrap()
-				.request_animation_frame(f.borrow().as_ref().unwrap().as_ref().unchecked_ref())
-				.unwrap();
+
+			let real_time = (time_ms / 1000.0) as f32;
+			let pacing = frame_pacing.get();
+			let hidden = pacing.pause_when_hidden && visibility.is_hidden();
+			let interval = pacing.min_interval();
+			let real_delta = last_frame.map_or(0.0, |last| real_time - last);
+
+			// A millisecond of slack keeps a 60 Hz cap from dropping frames
+			// that arrive slightly early
+			let skipped = hidden || last_frame.is_some() && real_delta < interval - 0.001;
+			let time = frame_time_scale.tick(real_time, !skipped);
+			let delta = last_scaled.map_or(0.0, |last| time - last);
+
+			if !skipped {
+				// Carry the overshoot so capped frames stay evenly spaced
+				let overshoot = if interval > 0.0 && real_delta >= interval { real_delta % interval } else { 0.0 };
+				last_frame = Some(real_time - overshoot);
+				last_scaled = Some(time);
+			} else if hidden {
+				// Don't report the hidden time as one long frame
+				last_frame = None;
+				last_scaled = None;
+			}
+
+			update(Frame { time, delta, skipped });
+
+			request_frame(f.borrow().as_ref().unwrap().as_ref().unchecked_ref());
 		}));
 
-		web_sys::window()
-			.unwrap()
-			.request_animation_frame(g.borrow().as_ref().unwrap().as_ref().unchecked_ref())
-			.unwrap();
+		request_frame(g.borrow().as_ref().unwrap().as_ref().unchecked_ref());
 
-		Self { running }
+		Self { running, pacing, time_scale }
 	}
 
 	pub fn stop(&self) {
 		*self.running.borrow_mut() = false;
 	}
-}
\ No newline at end of file
+
+	pub fn pacing(&self) -> FramePacing {
+		self.pacing.get()
+	}
+
+	/// Changes the pacing of the running loop.
+	pub fn set_pacing(&self, pacing: FramePacing) {
+		self.pacing.set(pacing);
+	}
+
+	/// Returns the loop's clock, for pausing or slowing it down.
+	pub fn time_scale(&self) -> &TimeScale {
+		&self.time_scale
+	}
+}
+
+/// Tracks whether the page is hidden through `visibilitychange` events.
+struct Visibility {
+	hidden: Rc<Cell<bool>>,
+	listener: Option<(web_sys::Document, Closure<dyn FnMut()>)>,
+}
+
+impl Visibility {
+	/// Starts listening, or never reports hidden outside a document, such
+	/// as in workers.
+	fn watch() -> Self {
+		let hidden = Rc::new(Cell::new(false));
+		let Some(document) = web_sys::window().and_then(|window| window.document()) else {
+			return Self { hidden, listener: None };
+		};
+
+		hidden.set(document.hidden());
+
+		let listener_hidden = hidden.clone();
+		let listener_document = document.clone();
+		let listener = Closure::<dyn FnMut()>::new(move || {
+			listener_hidden.set(listener_document.hidden());
+		});
+
+		let _ = document.add_event_listener_with_callback("visibilitychange", listener.as_ref().unchecked_ref());
+
+		Self { hidden, listener: Some((document, listener)) }
+	}
+
+	fn is_hidden(&self) -> bool {
+		self.hidden.get()
+	}
+
+	fn unwatch(&self) {
+		if let Some((document, listener)) = &self.listener {
+			let _ = document.remove_event_listener_with_callback("visibilitychange", listener.as_ref().unchecked_ref());
+		}
+	}
+}
+
+/// Calls `requestAnimationFrame` on the window, or on the worker scope
+/// inside a web worker.
+fn request_frame(callback: &Function) {
+	if let Some(window) = web_sys::window() {
+		window.request_animation_frame(callback).unwrap();
+		return;
+	}
+
+	let global = js_sys::global();
+	let request = Reflect::get(&global, &JsValue::from_str("requestAnimationFrame"))
+		.ok()
+		.and_then(|request| request.dyn_into::<Function>().ok())
+		.expect("requestAnimationFrame is not available in this worker");
+
+	request.call1(&global, callback).unwrap();
+}
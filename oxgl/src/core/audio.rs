@@ -0,0 +1,545 @@
+//! Audio
+//!
+//! Provides a Web Audio `AnalyserNode` wrapper that exposes frequency bands and
+//! overall energy, usable as a
+//! [`BindingSource`](crate::renderer_3d::BindingSource) to drive uniforms and
+//! lights.
+//!
+//! For sound effects and ambience, [`AudioPlayer`] plays decoded
+//! [`AudioClip`]s either directly or from [`AudioEmitter`]s placed in the
+//! scene. Emitters use `PannerNode`s and can follow objects, and the
+//! listener follows the scene camera, so sounds pan and fade with distance.
+//!
+//! ## Examples
+//!
+//! ```ignore
+//! use oxgl::core::audio::{AudioAnalyser, AudioFeature};
+//! use oxgl::renderer_3d::BindingTarget;
+//!
+//! let analyser = AudioAnalyser::from_media_element(&audio_element, 1024)?;
+//!
+//! // Pulse a light with the bass
+//! scene.bindings.add_mapped(
+//!		analyser.source(AudioFeature::Bass).with_smoothing(0.8),
+//!		BindingTarget::LightIntensity(light_id),
+//!		4.0, 0.5,
+//! );
+//! ```
+//!
+//! ```ignore
+//! use oxgl::core::audio::AudioPlayer;
+//!
+//! let player = Rc::new(RefCell::new(AudioPlayer::new()?));
+//! let engine_hum = player.borrow_mut().add_emitter(Some(car_id))?;
+//!
+//! let target = player.clone();
+//! player.borrow().load("assets/engine.ogg", move |result| match result {
+//!		Ok(clip) => { let _ = target.borrow().play_at(engine_hum, &clip, true); }
+//!		Err(e) => log::error!("{}", e),
+//! });
+//!
+//! app.run(move |scene, _time| {
+//!		scene.update_audio(&mut player.borrow_mut());
+//! });
+//! ```
+//!
+
+use std::{cell::{Cell, RefCell}, rc::Rc};
+use glam::Vec3;
+use js_sys::{ArrayBuffer, Uint8Array};
+use slotmap::SlotMap;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{
+	AnalyserNode, AudioBuffer, AudioBufferSourceNode, AudioContext, AudioScheduledSourceNode,
+	DistanceModelType, GainNode,
+	HtmlMediaElement, MediaStream, PannerNode, PanningModelType, Response,
+	wasm_bindgen::JsCast,
+};
+
+use super::{AudioEmitterId, ObjectId};
+
+/// A value extracted from the analysed audio, normalized to 0-1.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AudioFeature {
+	/// Average level across the whole spectrum.
+	Energy,
+	/// Average level from 20Hz to 250Hz.
+	Bass,
+	/// Average level from 250Hz to 4kHz.
+	Mid,
+	/// Average level from 4kHz to 20kHz.
+	Treble,
+	/// Average level of a custom frequency range in Hz.
+	Band { min_hz: f32, max_hz: f32 },
+}
+
+impl AudioFeature {
+	fn range(&self, nyquist: f32) -> (f32, f32) {
+		match *self {
+			AudioFeature::Energy => (0.0, nyquist),
+			AudioFeature::Bass => (20.0, 250.0),
+			AudioFeature::Mid => (250.0, 4000.0),
+			AudioFeature::Treble => (4000.0, 20000.0),
+			AudioFeature::Band { min_hz, max_hz } => (min_hz, max_hz),
+		}
+	}
+}
+
+struct AnalyserInner {
+	context: AudioContext,
+	analyser: AnalyserNode,
+	data: RefCell<Vec<u8>>,
+	/// Context time of the last read, as the data only changes once per
+	/// rendered block of audio.
+	read_at: Cell<f64>,
+}
+
+/// Frequency analyser for an audio element, media stream, or custom node graph.
+///
+/// Cloning is cheap and shares the underlying node.
+#[derive(Clone)]
+pub struct AudioAnalyser {
+	inner: Rc<AnalyserInner>,
+}
+
+impl AudioAnalyser {
+	/// Creates an analyser with its own audio context and no input.
+	///
+	/// Connect sources to [`node`](Self::node) manually.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the audio context or analyser node can't be created.
+	pub fn new(fft_size: u32) -> Result<Self, String> {
+		let context = AudioContext::new()
+			.map_err(|e| format!("Failed to create audio context: {:?}", e))?;
+		Self::with_context(context, fft_size)
+	}
+
+	/// Creates an analyser on an existing audio context.
+	pub fn with_context(context: AudioContext, fft_size: u32) -> Result<Self, String> {
+		let analyser = context.create_analyser()
+			.map_err(|e| format!("Failed to create analyser: {:?}", e))?;
+
+		analyser.set_fft_size(fft_size);
+
+		let data = RefCell::new(vec![0; analyser.frequency_bin_count() as usize]);
+
+		Ok(Self { inner: Rc::new(AnalyserInner { context, analyser, data, read_at: Cell::new(-1.0) }) })
+	}
+
+	/// Analyses an `<audio>` or `<video>` element.
+	///
+	/// The element stays audible, as the analyser is passed through to the
+	/// audio destination.
+	pub fn from_media_element(element: &HtmlMediaElement, fft_size: u32) -> Result<Self, String> {
+		let analyser = Self::new(fft_size)?;
+		let context = &analyser.inner.context;
+
+		let source = context.create_media_element_source(element)
+			.map_err(|e| format!("Failed to create media source: {:?}", e))?;
+
+		source.connect_with_audio_node(&analyser.inner.analyser)
+			.and_then(|_| analyser.inner.analyser.connect_with_audio_node(&context.destination()))
+			.map_err(|e| format!("Failed to connect audio nodes: {:?}", e))?;
+
+		Ok(analyser)
+	}
+
+	/// Analyses a media stream, such as microphone input.
+	///
+	/// The stream is not routed to the speakers.
+	pub fn from_stream(stream: &MediaStream, fft_size: u32) -> Result<Self, String> {
+		let analyser = Self::new(fft_size)?;
+
+		let source = analyser.inner.context.create_media_stream_source(stream)
+			.map_err(|e| format!("Failed to create stream source: {:?}", e))?;
+
+		source.connect_with_audio_node(&analyser.inner.analyser)
+			.map_err(|e| format!("Failed to connect audio nodes: {:?}", e))?;
+
+		Ok(analyser)
+	}
+
+	pub fn node(&self) -> &AnalyserNode {
+		&self.inner.analyser
+	}
+
+	pub fn context(&self) -> &AudioContext {
+		&self.inner.context
+	}
+
+	/// Resumes the audio context.
+	///
+	/// Browsers start contexts suspended until a user gesture, so call this
+	/// from a click handler.
+	pub fn resume(&self) {
+		let _ = self.inner.context.resume();
+	}
+
+	/// Reads the latest frequency data from the analyser.
+	///
+	/// Does nothing when no audio was rendered since the last read, so
+	/// sources sharing an analyser read it once.
+	pub fn update(&self) {
+		let now = self.inner.context.current_time();
+
+		if self.inner.read_at.replace(now) != now {
+			self.inner.analyser.get_byte_frequency_data(&mut self.inner.data.borrow_mut());
+		}
+	}
+
+	/// Returns the level of a feature from the last [`update`](Self::update).
+	pub fn sample(&self, feature: AudioFeature) -> f32 {
+		let nyquist = self.inner.context.sample_rate() * 0.5;
+		let (min_hz, max_hz) = feature.range(nyquist);
+		self.average(min_hz / nyquist, max_hz / nyquist)
+	}
+
+	/// Splits the spectrum into `count` logarithmically spaced bands.
+	pub fn bands(&self, count: usize) -> Vec<f32> {
+		let nyquist = self.inner.context.sample_rate() * 0.5;
+		let (low, high) = (20.0f32.ln(), nyquist.ln());
+
+		(0..count)
+			.map(|i| {
+				let from = (low + (high - low) * i as f32 / count as f32).exp();
+				let to = (low + (high - low) * (i + 1) as f32 / count as f32).exp();
+				self.average(from / nyquist, to / nyquist)
+			})
+			.collect()
+	}
+
+	/// Creates a binding source that samples `feature` every frame.
+	pub fn source(&self, feature: AudioFeature) -> AudioSource {
+		AudioSource {
+			analyser: self.clone(),
+			feature,
+			smoothing: 0.0,
+			value: 0.0,
+		}
+	}
+
+	fn average(&self, from: f32, to: f32) -> f32 {
+		let data = self.inner.data.borrow();
+		let len = data.len();
+
+		if len == 0 {
+			return 0.0;
+		}
+
+		let start = ((from.clamp(0.0, 1.0) * len as f32) as usize).min(len - 1);
+		let end = ((to.clamp(0.0, 1.0) * len as f32).ceil() as usize).clamp(start + 1, len);
+		let sum: u32 = data[start..end].iter().map(|&v| v as u32).sum();
+
+		sum as f32 / ((end - start) as f32 * 255.0)
+	}
+}
+
+/// A [`BindingSource`](crate::renderer_3d::BindingSource) reading one
+/// feature of an [`AudioAnalyser`].
+pub struct AudioSource {
+	analyser: AudioAnalyser,
+	feature: AudioFeature,
+	smoothing: f32,
+	value: f32,
+}
+
+impl AudioSource {
+	/// Sets exponential smoothing between frames (0 = none, close to 1 = slow).
+	pub fn with_smoothing(mut self, smoothing: f32) -> Self {
+		self.smoothing = smoothing.clamp(0.0, 0.99);
+		self
+	}
+
+	/// Reads the analyser and returns the smoothed level of the feature.
+	pub fn sample(&mut self) -> f32 {
+		self.analyser.update();
+
+		let target = self.analyser.sample(self.feature);
+		self.value = self.value * self.smoothing + target * (1.0 - self.smoothing);
+		self.value
+	}
+}
+
+/// Decoded audio, ready to be played any number of times.
+///
+/// Cloning is cheap and shares the decoded samples.
+#[derive(Clone)]
+pub struct AudioClip {
+	buffer: AudioBuffer,
+}
+
+impl AudioClip {
+	/// Returns the length of the clip in seconds.
+	pub fn duration(&self) -> f32 {
+		self.buffer.duration() as f32
+	}
+
+	pub fn buffer(&self) -> &AudioBuffer {
+		&self.buffer
+	}
+}
+
+/// A playing clip, which can be stopped early.
+#[derive(Clone)]
+pub struct Sound {
+	source: AudioBufferSourceNode,
+}
+
+impl Sound {
+	/// Stops playback. Stopped sounds can't be restarted.
+	pub fn stop(&self) {
+		let _ = AudioScheduledSourceNode::stop(&self.source);
+	}
+
+	/// Sets the playback speed, which also shifts the pitch.
+	pub fn set_playback_rate(&self, rate: f32) {
+		self.source.playback_rate().set_value(rate);
+	}
+
+	pub fn source(&self) -> &AudioBufferSourceNode {
+		&self.source
+	}
+}
+
+/// A positional sound source in the scene.
+///
+/// Follows its [`object`](Self::object) if set, otherwise stays at
+/// [`position`](Self::position).
+pub struct AudioEmitter {
+	panner: PannerNode,
+	gain: GainNode,
+	pub object: Option<ObjectId>,
+	/// World position, updated from the object each
+	/// [`AudioPlayer::update`].
+	pub position: Vec3,
+}
+
+impl AudioEmitter {
+	pub fn set_volume(&self, volume: f32) {
+		self.gain.gain().set_value(volume);
+	}
+
+	/// Sets the distance at which the volume starts falling off.
+	pub fn set_ref_distance(&self, distance: f32) {
+		self.panner.set_ref_distance(distance as f64);
+	}
+
+	/// Sets the distance beyond which the volume stops falling off.
+	pub fn set_max_distance(&self, distance: f32) {
+		self.panner.set_max_distance(distance as f64);
+	}
+
+	/// Sets how quickly the volume falls off with distance.
+	pub fn set_rolloff(&self, rolloff: f32) {
+		self.panner.set_rolloff_factor(rolloff as f64);
+	}
+
+	pub fn panner(&self) -> &PannerNode {
+		&self.panner
+	}
+}
+
+/// Plays audio clips, positioned in the scene or not.
+///
+/// Call [`update`](Self::update) every frame so the listener follows the
+/// camera and emitters follow their objects.
+pub struct AudioPlayer {
+	context: AudioContext,
+	master: GainNode,
+	emitters: SlotMap<AudioEmitterId, AudioEmitter>,
+}
+
+impl AudioPlayer {
+	/// Creates a player with its own audio context.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the audio context or output gain can't be created.
+	pub fn new() -> Result<Self, String> {
+		let context = AudioContext::new()
+			.map_err(|e| format!("Failed to create audio context: {:?}", e))?;
+		Self::with_context(context)
+	}
+
+	/// Creates a player on an existing audio context.
+	pub fn with_context(context: AudioContext) -> Result<Self, String> {
+		let master = context.create_gain()
+			.map_err(|e| format!("Failed to create gain node: {:?}", e))?;
+
+		master.connect_with_audio_node(&context.destination())
+			.map_err(|e| format!("Failed to connect audio nodes: {:?}", e))?;
+
+		Ok(Self { context, master, emitters: SlotMap::with_key() })
+	}
+
+	pub fn context(&self) -> &AudioContext {
+		&self.context
+	}
+
+	/// Resumes the audio context.
+	///
+	/// Browsers start contexts suspended until a user gesture, so call this
+	/// from a click handler.
+	pub fn resume(&self) {
+		let _ = self.context.resume();
+	}
+
+	/// Sets the volume of everything the player outputs.
+	pub fn set_volume(&self, volume: f32) {
+		self.master.gain().set_value(volume);
+	}
+
+	/// Fetches and decodes an audio file, and passes the clip to a callback.
+	pub fn load(&self, url: &str, callback: impl FnOnce(Result<AudioClip, String>) + 'static) {
+		let context = self.context.clone();
+		let url = url.to_string();
+
+		wasm_bindgen_futures::spawn_local(async move {
+			callback(fetch(&context, &url).await);
+		});
+	}
+
+	/// Decodes audio file contents, such as MP3 or OGG, and passes the clip
+	/// to a callback.
+	pub fn decode(&self, bytes: &[u8], callback: impl FnOnce(Result<AudioClip, String>) + 'static) {
+		let context = self.context.clone();
+		let data = Uint8Array::from(bytes).buffer();
+
+		wasm_bindgen_futures::spawn_local(async move {
+			callback(decode(&context, &data).await);
+		});
+	}
+
+	/// Plays a clip without positioning, such as music or interface sounds.
+	pub fn play(&self, clip: &AudioClip, looping: bool) -> Result<Sound, String> {
+		self.start(clip, looping, &self.master)
+	}
+
+	/// Plays a clip from an emitter.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the emitter doesn't exist or playback fails.
+	pub fn play_at(&self, emitter: AudioEmitterId, clip: &AudioClip, looping: bool) -> Result<Sound, String> {
+		let emitter = self.emitters.get(emitter).ok_or("Audio emitter not found")?;
+		self.start(clip, looping, &emitter.gain)
+	}
+
+	/// Adds an emitter that follows an object, or stays at the origin until
+	/// its position is set.
+	pub fn add_emitter(&mut self, object: Option<ObjectId>) -> Result<AudioEmitterId, String> {
+		let panner = self.context.create_panner()
+			.map_err(|e| format!("Failed to create panner node: {:?}", e))?;
+		let gain = self.context.create_gain()
+			.map_err(|e| format!("Failed to create gain node: {:?}", e))?;
+
+		panner.set_panning_model(PanningModelType::Hrtf);
+		panner.set_distance_model(DistanceModelType::Inverse);
+
+		gain.connect_with_audio_node(&panner)
+			.and_then(|_| panner.connect_with_audio_node(&self.master))
+			.map_err(|e| format!("Failed to connect audio nodes: {:?}", e))?;
+
+		Ok(self.emitters.insert(AudioEmitter { panner, gain, object, position: Vec3::ZERO }))
+	}
+
+	/// Adds an emitter at a fixed world position.
+	pub fn add_emitter_at(&mut self, position: Vec3) -> Result<AudioEmitterId, String> {
+		let id = self.add_emitter(None)?;
+		self.emitters[id].position = position;
+		Ok(id)
+	}
+
+	/// Removes an emitter, cutting off any sounds it is playing.
+	pub fn remove_emitter(&mut self, id: AudioEmitterId) -> bool {
+		match self.emitters.remove(id) {
+			Some(emitter) => {
+				let _ = emitter.panner.disconnect();
+				true
+			}
+			None => false,
+		}
+	}
+
+	pub fn emitter(&self, id: AudioEmitterId) -> Option<&AudioEmitter> {
+		self.emitters.get(id)
+	}
+
+	pub fn emitter_mut(&mut self, id: AudioEmitterId) -> Option<&mut AudioEmitter> {
+		self.emitters.get_mut(id)
+	}
+
+	/// Places the listener at `position`, facing `forward`.
+	///
+	/// [`Scene::update_audio`](crate::renderer_3d::Scene::update_audio)
+	/// places it at the scene camera.
+	pub fn set_listener(&self, position: Vec3, forward: Vec3, up: Vec3) {
+		let listener = self.context.listener();
+
+		listener.set_position(position.x as f64, position.y as f64, position.z as f64);
+		listener.set_orientation(
+			forward.x as f64, forward.y as f64, forward.z as f64,
+			up.x as f64, up.y as f64, up.z as f64,
+		);
+	}
+
+	/// Moves emitters to their objects, at the world positions `locate`
+	/// returns.
+	///
+	/// Emitters whose object can't be found stay where it last was.
+	pub fn update_emitters(&mut self, locate: impl Fn(ObjectId) -> Option<Vec3>) {
+		for emitter in self.emitters.values_mut() {
+			if let Some(position) = emitter.object.and_then(&locate) {
+				emitter.position = position;
+			}
+
+			emitter.panner.position_x().set_value(emitter.position.x);
+			emitter.panner.position_y().set_value(emitter.position.y);
+			emitter.panner.position_z().set_value(emitter.position.z);
+		}
+	}
+
+	fn start(&self, clip: &AudioClip, looping: bool, output: &GainNode) -> Result<Sound, String> {
+		let source = self.context.create_buffer_source()
+			.map_err(|e| format!("Failed to create buffer source: {:?}", e))?;
+
+		source.set_buffer(Some(&clip.buffer));
+		source.set_loop(looping);
+
+		source.connect_with_audio_node(output)
+			.map_err(|e| format!("Failed to connect audio nodes: {:?}", e))?;
+		source.start()
+			.map_err(|e| format!("Failed to start playback: {:?}", e))?;
+
+		Ok(Sound { source })
+	}
+}
+
+async fn fetch(context: &AudioContext, url: &str) -> Result<AudioClip, String> {
+	let window = web_sys::window().ok_or("No window available")?;
+	let response: Response = JsFuture::from(window.fetch_with_str(url))
+		.await
+		.map_err(|e| format!("Failed to fetch {}: {:?}", url, e))?
+		.unchecked_into();
+
+	if !response.ok() {
+		return Err(format!("HTTP {} loading {}", response.status(), url));
+	}
+
+	let promise = response.array_buffer().map_err(|e| format!("Failed to read {}: {:?}", url, e))?;
+	let data: ArrayBuffer = JsFuture::from(promise)
+		.await
+		.map_err(|e| format!("Failed to read {}: {:?}", url, e))?
+		.unchecked_into();
+
+	decode(context, &data).await
+}
+
+async fn decode(context: &AudioContext, data: &ArrayBuffer) -> Result<AudioClip, String> {
+	let promise = context.decode_audio_data(data).map_err(|e| format!("Failed to decode audio: {:?}", e))?;
+	let buffer = JsFuture::from(promise).await.map_err(|e| format!("Failed to decode audio: {:?}", e))?;
+
+	Ok(AudioClip { buffer: buffer.unchecked_into() })
+}
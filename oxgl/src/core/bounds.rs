@@ -0,0 +1,162 @@
+//! Bounding Volumes
+//!
+//! Provides axis-aligned bounding boxes for culling, picking and debug
+//! visualization.
+//!
+//! ## Examples
+//!
+//! ```
+//! use oxgl::core::Aabb;
+//! use glam::{Mat4, Vec3};
+//!
+//! let bounds = Aabb::from_points(&[Vec3::new(-1.0, 0.0, 0.0), Vec3::new(1.0, 2.0, 0.5)]);
+//! assert_eq!(bounds.center(), Vec3::new(0.0, 1.0, 0.25));
+//!
+//! // World-space bounds of a translated object
+//! let world = bounds.transformed(&Mat4::from_translation(Vec3::Y));
+//! assert_eq!(world.min, Vec3::new(-1.0, 1.0, 0.0));
+//! ```
+//!
+
+use glam::{Mat4, Vec3};
+
+/// An axis-aligned bounding box.
+///
+/// A box with `min` greater than `max` on any axis is empty.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Aabb {
+	pub min: Vec3,
+	pub max: Vec3,
+}
+
+impl Default for Aabb {
+	fn default() -> Self {
+		Self::EMPTY
+	}
+}
+
+impl Aabb {
+	/// A box containing nothing, which grows to fit the first point added.
+	pub const EMPTY: Self = Self {
+		min: Vec3::splat(f32::MAX),
+		max: Vec3::splat(f32::MIN),
+	};
+
+	pub fn new(min: Vec3, max: Vec3) -> Self {
+		Self { min, max }
+	}
+
+	/// Returns the smallest box containing all points.
+	pub fn from_points(points: &[Vec3]) -> Self {
+		points.iter().fold(Self::EMPTY, |aabb, &p| aabb.including(p))
+	}
+
+	/// Returns the bounds of the positions in interleaved vertex data.
+	///
+	/// `stride` is the number of floats per vertex, with the position first.
+	pub fn from_vertices(data: &[f32], stride: usize) -> Self {
+		data.chunks_exact(stride)
+			.fold(Self::EMPTY, |aabb, v| aabb.including(Vec3::new(v[0], v[1], v[2])))
+	}
+
+	/// Returns the box grown to contain a point.
+	pub fn including(self, point: Vec3) -> Self {
+		Self { min: self.min.min(point), max: self.max.max(point) }
+	}
+
+	/// Returns the smallest box containing both boxes.
+	pub fn union(self, other: Self) -> Self {
+		Self { min: self.min.min(other.min), max: self.max.max(other.max) }
+	}
+
+	/// Returns the box covered by both boxes, which is empty if they don't
+	/// overlap.
+	pub fn intersection(self, other: Self) -> Self {
+		Self { min: self.min.max(other.min), max: self.max.min(other.max) }
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.min.cmpgt(self.max).any()
+	}
+
+	pub fn center(&self) -> Vec3 {
+		(self.min + self.max) * 0.5
+	}
+
+	pub fn size(&self) -> Vec3 {
+		self.max - self.min
+	}
+
+	pub fn contains(&self, point: Vec3) -> bool {
+		point.cmpge(self.min).all() && point.cmple(self.max).all()
+	}
+
+	/// Returns whether two boxes overlap, including touching faces.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use oxgl::core::Aabb;
+	/// use glam::Vec3;
+	///
+	/// let a = Aabb::new(Vec3::ZERO, Vec3::ONE);
+	///
+	/// assert!(a.intersects(&Aabb::new(Vec3::splat(0.5), Vec3::splat(2.0))));
+	/// assert!(!a.intersects(&Aabb::new(Vec3::splat(1.5), Vec3::splat(2.0))));
+	/// ```
+	pub fn intersects(&self, other: &Self) -> bool {
+		self.min.cmple(other.max).all() && self.max.cmpge(other.min).all()
+	}
+
+	/// Returns the point in or on the box closest to `point`.
+	pub fn closest_point(&self, point: Vec3) -> Vec3 {
+		point.clamp(self.min, self.max)
+	}
+
+	/// Returns whether a sphere overlaps the box.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use oxgl::core::Aabb;
+	/// use glam::Vec3;
+	///
+	/// let bounds = Aabb::new(Vec3::ZERO, Vec3::ONE);
+	///
+	/// assert!(bounds.intersects_sphere(Vec3::new(1.5, 0.5, 0.5), 0.6));
+	/// // Close to a corner, but not touching it
+	/// assert!(!bounds.intersects_sphere(Vec3::splat(1.5), 0.8));
+	/// ```
+	pub fn intersects_sphere(&self, center: Vec3, radius: f32) -> bool {
+		self.closest_point(center).distance_squared(center) <= radius * radius
+	}
+
+	/// Returns the box grown by `amount` on every side.
+	pub fn expanded(&self, amount: f32) -> Self {
+		Self { min: self.min - Vec3::splat(amount), max: self.max + Vec3::splat(amount) }
+	}
+
+	/// Returns the eight corners of the box.
+	pub fn corners(&self) -> [Vec3; 8] {
+		std::array::from_fn(|i| Vec3::new(
+			if i & 1 == 0 { self.min.x } else { self.max.x },
+			if i & 2 == 0 { self.min.y } else { self.max.y },
+			if i & 4 == 0 { self.min.z } else { self.max.z },
+		))
+	}
+
+	/// Returns the matrix mapping the unit cube (-0.5 to 0.5) onto this box.
+	pub fn to_matrix(&self) -> Mat4 {
+		Mat4::from_translation(self.center()) * Mat4::from_scale(self.size())
+	}
+
+	/// Returns the axis-aligned box containing this box after a transform.
+	pub fn transformed(&self, matrix: &Mat4) -> Self {
+		if self.is_empty() {
+			return *self;
+		}
+
+		Self::from_points(&self.corners().map(|c| matrix.transform_point3(c)))
+	}
+}
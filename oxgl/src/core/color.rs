@@ -231,6 +231,50 @@ impl Color {
 		)
 	}
 
+	/// Creates a color from a black-body temperature in Kelvin.
+	///
+	/// Uses a curve fit of the black-body spectrum, valid from roughly
+	/// 1000K (deep orange) to 40000K (blue). 6500K is approximately white.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use oxgl::core::Color;
+	///
+	/// let candle = Color::from_temperature(1900.0);
+	/// let daylight = Color::from_temperature(6500.0);
+	/// ```
+	pub fn from_temperature(kelvin: f32) -> Self {
+		let t = kelvin.clamp(1000.0, 40000.0) / 100.0;
+
+		let r = if t <= 66.0 {
+			255.0
+		} else {
+			329.698_73 * (t - 60.0).powf(-0.133_204_76)
+		};
+
+		let g = if t <= 66.0 {
+			99.470_8 * t.ln() - 161.119_57
+		} else {
+			288.122_16 * (t - 60.0).powf(-0.075_514_85)
+		};
+
+		let b = if t >= 66.0 {
+			255.0
+		} else if t <= 19.0 {
+			0.0
+		} else {
+			138.517_73 * (t - 10.0).ln() - 305.044_8
+		};
+
+		Color::Rgba(
+			r.clamp(0.0, 255.0) as u8,
+			g.clamp(0.0, 255.0) as u8,
+			b.clamp(0.0, 255.0) as u8,
+			255,
+		)
+	}
+
 	pub fn lighten(&self, amount: f32) -> Self {
 		let (h, s, v, a) = self.to_hsva_tuple();
 		Self::hsv_to_rgba(h, s, (v + amount).clamp(0.0, 1.0), a)
@@ -376,4 +420,63 @@ impl Color {
 	pub const CYAN: Color = Color::Rgba(0, 255, 255, 255);
 	pub const MAGENTA: Color = Color::Rgba(255, 0, 255, 255);
 	pub const TRANSPARENT: Color = Color::Rgba(0, 0, 0, 0);
-}
\ No newline at end of file
+}
+// ─────────────────────────────────────────────────────────────────────────────
+// Color Spaces
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// The color space the renderer writes to the canvas.
+///
+/// With [`Srgb`](Self::Srgb), the built-in shaders treat material, light,
+/// vertex and fog colors as sRGB, decode them to linear light before
+/// shading, and encode the result back to sRGB for display. This keeps
+/// falloff and blending between lights physically plausible. [`Linear`](Self::Linear)
+/// shades the raw values and writes them out unchanged.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorSpace {
+	/// Shade and output colors as given, without gamma handling.
+	Linear,
+	/// Decode sRGB inputs to linear light and encode the output as sRGB.
+	#[default]
+	Srgb,
+}
+
+impl ColorSpace {
+	/// Converts a color channel in this color space to linear light.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use oxgl::core::ColorSpace;
+	///
+	/// let linear = ColorSpace::Srgb.to_linear(0.5);
+	///
+	/// assert!((linear - 0.214).abs() < 1e-3);
+	/// assert!((ColorSpace::Srgb.from_linear(linear) - 0.5).abs() < 1e-5);
+	/// assert_eq!(ColorSpace::Linear.to_linear(0.5), 0.5);
+	/// ```
+	pub fn to_linear(&self, value: f32) -> f32 {
+		match self {
+			Self::Linear => value,
+			Self::Srgb if value <= 0.04045 => value / 12.92,
+			Self::Srgb => ((value + 0.055) / 1.055).powf(2.4),
+		}
+	}
+
+	/// Converts a linear light color channel to this color space.
+	pub fn from_linear(&self, value: f32) -> f32 {
+		match self {
+			Self::Linear => value,
+			Self::Srgb if value <= 0.0031308 => value * 12.92,
+			Self::Srgb => 1.055 * value.powf(1.0 / 2.4) - 0.055,
+		}
+	}
+
+	/// Returns the `colorSpace` uniform value.
+	pub(crate) fn uniform(&self) -> i32 {
+		match self {
+			Self::Linear => 0,
+			Self::Srgb => 1,
+		}
+	}
+}
@@ -0,0 +1,75 @@
+//! View Frustums
+//!
+//! Provides a frustum type extracted from a view-projection matrix, used to
+//! skip objects that are outside the camera's view.
+//!
+//! ## Examples
+//!
+//! ```
+//! use oxgl::core::{Aabb, Frustum};
+//! use glam::{Mat4, Vec3};
+//!
+//! let projection = Mat4::perspective_rh_gl(1.0, 1.0, 0.1, 100.0);
+//! let view = Mat4::look_at_rh(Vec3::new(0.0, 0.0, 5.0), Vec3::ZERO, Vec3::Y);
+//! let frustum = Frustum::from_matrix(&(projection * view));
+//!
+//! assert!(frustum.intersects_aabb(&Aabb::new(Vec3::splat(-0.5), Vec3::splat(0.5))));
+//! assert!(!frustum.intersects_aabb(&Aabb::new(Vec3::new(0.0, 0.0, 6.0), Vec3::new(1.0, 1.0, 7.0))));
+//! ```
+//!
+
+use glam::{Mat4, Vec3, Vec4};
+
+use super::Aabb;
+
+/// The six planes bounding a camera's view volume.
+///
+/// Plane normals point inwards, so points inside the frustum are on the
+/// positive side of every plane.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Frustum {
+	/// Left, right, bottom, top, near and far planes as `(normal, distance)`.
+	pub planes: [Vec4; 6],
+}
+
+impl Frustum {
+	/// Extracts the frustum planes from a view-projection matrix.
+	pub fn from_matrix(view_projection: &Mat4) -> Self {
+		let rows = [0, 1, 2, 3].map(|i| view_projection.row(i));
+
+		let planes = [
+			rows[3] + rows[0],
+			rows[3] - rows[0],
+			rows[3] + rows[1],
+			rows[3] - rows[1],
+			rows[3] + rows[2],
+			rows[3] - rows[2],
+		]
+		.map(|plane| plane / plane.truncate().length());
+
+		Self { planes }
+	}
+
+	/// Returns `true` if a point is inside the frustum.
+	pub fn contains_point(&self, point: Vec3) -> bool {
+		self.planes.iter().all(|plane| plane.truncate().dot(point) + plane.w >= 0.0)
+	}
+
+	/// Returns `true` if a box is at least partially inside the frustum.
+	///
+	/// Conservative: boxes near a corner of the frustum may be reported as
+	/// visible when they aren't. Empty boxes are never visible.
+	pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+		if aabb.is_empty() {
+			return false;
+		}
+
+		self.planes.iter().all(|plane| {
+			let normal = plane.truncate();
+
+			// The box corner furthest along the plane normal
+			let corner = Vec3::select(normal.cmpge(Vec3::ZERO), aabb.max, aabb.min);
+			normal.dot(corner) + plane.w >= 0.0
+		})
+	}
+}
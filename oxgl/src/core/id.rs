@@ -13,4 +13,30 @@ new_key_type! {
 	pub struct LightId;
 	/// Identifier for 3D css elements;
 	pub struct CSS3DElementId;
+	/// Identifier for value bindings in a scene.
+	pub struct BindingId;
+	/// Identifier for panorama hotspots.
+	pub struct HotspotId;
+	/// Identifier for text labels.
+	pub struct LabelId;
+	/// Identifier for sprites in a scene.
+	pub struct SpriteId;
+	/// Identifier for polylines in a scene.
+	pub struct PolylineId;
+	/// Identifier for screen-space HTML labels.
+	pub struct HtmlLabelId;
+	/// Identifier for positional audio emitters.
+	pub struct AudioEmitterId;
+	/// Identifier for scene event listeners.
+	pub struct SceneListenerId;
+	/// Identifier for reflection probes in a scene.
+	pub struct ReflectionProbeId;
+	/// Identifier for decals in a scene.
+	pub struct DecalId;
+	/// Identifier for scene textures in a scene.
+	pub struct SceneTextureId;
+	/// Identifier for text meshes in a scene.
+	pub struct TextId;
+	/// Identifier for cameras registered with a scene.
+	pub struct CameraId;
 }
\ No newline at end of file
@@ -0,0 +1,235 @@
+//! Inverse Kinematics
+//!
+//! Provides [`IkSolver`], which bends a chain of joints so its end reaches
+//! a target, for placing feet on uneven ground, reaching hands towards
+//! objects or aiming turrets. Chains of three joints, such as an upper arm,
+//! forearm and hand, are solved exactly; longer chains use FABRIK (forward
+//! and backward reaching inverse kinematics).
+//!
+//! A pole target picks the direction the chain bends towards, such as in
+//! front of the knee or behind the elbow.
+//!
+//! ## Examples
+//!
+//! ```
+//! use oxgl::core::IkSolver;
+//! use glam::Vec3;
+//!
+//! // A straight two-bone leg
+//! let mut joints = [Vec3::new(0.0, 2.0, 0.0), Vec3::new(0.0, 1.0, 0.0), Vec3::ZERO];
+//!
+//! let solver = IkSolver::new(Vec3::new(0.5, 0.5, 0.0)).with_pole(Vec3::new(0.0, 1.0, 1.0));
+//! assert!(solver.solve(&mut joints));
+//!
+//! assert!(joints[2].distance(Vec3::new(0.5, 0.5, 0.0)) < 1e-4);
+//! assert!((joints[1].distance(joints[0]) - 1.0).abs() < 1e-4);
+//! assert!(joints[1].z > 0.0); // The knee bends towards the pole
+//! ```
+//!
+//! Scene objects parented into a chain can be posed with
+//! [`Scene::solve_ik`](crate::renderer_3d::Scene::solve_ik).
+//!
+
+use glam::{Mat4, Quat, Vec3};
+
+use super::{Transform3D, Transformable};
+
+/// Settings for bending a chain of joints towards a target.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct IkSolver {
+	/// Position the end of the chain reaches for.
+	pub target: Vec3,
+	/// Position the chain bends towards, or `None` to keep the current bend.
+	pub pole: Option<Vec3>,
+	/// Maximum FABRIK passes for chains longer than three joints.
+	pub iterations: u32,
+	/// Distance from the target at which FABRIK stops early.
+	pub tolerance: f32,
+}
+
+impl IkSolver {
+	pub fn new(target: Vec3) -> Self {
+		Self {
+			target,
+			pole: None,
+			iterations: 10,
+			tolerance: 1e-3,
+		}
+	}
+
+	pub fn with_pole(mut self, pole: Vec3) -> Self {
+		self.pole = Some(pole);
+		self
+	}
+
+	pub fn with_iterations(mut self, iterations: u32) -> Self {
+		self.iterations = iterations;
+		self
+	}
+
+	pub fn with_tolerance(mut self, tolerance: f32) -> Self {
+		self.tolerance = tolerance;
+		self
+	}
+
+	/// Moves joint positions so the last one reaches the target, keeping the
+	/// first in place and the distances between neighbors.
+	///
+	/// Returns `true` if the target is within reach. Out of reach, the chain
+	/// stretches straight towards it.
+	pub fn solve(&self, joints: &mut [Vec3]) -> bool {
+		match joints.len() {
+			0 | 1 => false,
+			3 => self.solve_two_bone(joints),
+			_ => self.solve_fabrik(joints),
+		}
+	}
+
+	/// Rotates a chain of transforms, each parented to the one before, so
+	/// the origin of the last reaches the target.
+	///
+	/// `parent` is the world matrix the first transform is relative to. Only
+	/// rotations change; the last transform's rotation is left as is.
+	pub fn solve_transforms(&self, parent: Mat4, chain: &mut [Transform3D]) -> bool {
+		let worlds = world_matrices(parent, chain);
+		let mut joints: Vec<Vec3> = worlds.iter().map(|m| m.w_axis.truncate()).collect();
+		let reached = self.solve(&mut joints);
+
+		let mut parent_world = parent;
+
+		for i in 0..chain.len().saturating_sub(1) {
+			let world = parent_world * chain[i].to_matrix();
+			let next = (world * chain[i + 1].to_matrix()).w_axis.truncate();
+			let origin = world.w_axis.truncate();
+
+			let (from, to) = ((next - origin).normalize_or_zero(), (joints[i + 1] - joints[i]).normalize_or_zero());
+
+			if from != Vec3::ZERO && to != Vec3::ZERO {
+				let (_, world_rotation, _) = world.to_scale_rotation_translation();
+				let (_, parent_rotation, _) = parent_world.to_scale_rotation_translation();
+				let rotated = Quat::from_rotation_arc(from, to) * world_rotation;
+
+				chain[i].rotation = (parent_rotation.inverse() * rotated).normalize();
+			}
+
+			parent_world *= chain[i].to_matrix();
+		}
+
+		reached
+	}
+
+	/// Solves three joints exactly with the law of cosines.
+	fn solve_two_bone(&self, joints: &mut [Vec3]) -> bool {
+		let (root, mid, end) = (joints[0], joints[1], joints[2]);
+		let (upper, lower) = (root.distance(mid), mid.distance(end));
+		let to_target = self.target - root;
+		let reach = to_target.length();
+
+		let Some(forward) = to_target.try_normalize() else {
+			return false;
+		};
+
+		let (closest, farthest) = ((upper - lower).abs(), upper + lower);
+		let distance = reach.clamp(closest + 1e-5, (farthest - 1e-5).max(closest + 1e-5));
+		let bend = self.bend_direction(root, mid, forward);
+
+		let cos = ((upper * upper + distance * distance - lower * lower) / (2.0 * upper * distance).max(f32::EPSILON)).clamp(-1.0, 1.0);
+		let sin = (1.0 - cos * cos).sqrt();
+
+		joints[1] = root + (forward * cos + bend * sin) * upper;
+		joints[2] = root + forward * distance;
+
+		// Targets closer than the difference of the bones can't be touched either
+		reach >= closest && reach <= farthest
+	}
+
+	fn solve_fabrik(&self, joints: &mut [Vec3]) -> bool {
+		let lengths: Vec<f32> = joints.windows(2).map(|pair| pair[0].distance(pair[1])).collect();
+		let root = joints[0];
+		let last = joints.len() - 1;
+
+		if root.distance(self.target) >= lengths.iter().sum::<f32>() {
+			let Some(forward) = (self.target - root).try_normalize() else {
+				return false;
+			};
+
+			for i in 1..=last {
+				joints[i] = joints[i - 1] + forward * lengths[i - 1];
+			}
+
+			return false;
+		}
+
+		for _ in 0..self.iterations {
+			joints[last] = self.target;
+
+			for i in (0..last).rev() {
+				joints[i] = joints[i + 1] + (joints[i] - joints[i + 1]).normalize_or_zero() * lengths[i];
+			}
+
+			joints[0] = root;
+
+			for i in 1..=last {
+				joints[i] = joints[i - 1] + (joints[i] - joints[i - 1]).normalize_or_zero() * lengths[i - 1];
+			}
+
+			if let Some(pole) = self.pole {
+				for i in 1..last {
+					joints[i] = bend_towards(joints[i - 1], joints[i], joints[i + 1], pole);
+				}
+			}
+
+			if joints[last].distance(self.target) <= self.tolerance {
+				break;
+			}
+		}
+
+		true
+	}
+
+	/// Returns the unit direction, perpendicular to `forward`, the middle
+	/// joint moves towards.
+	fn bend_direction(&self, root: Vec3, mid: Vec3, forward: Vec3) -> Vec3 {
+		let perpendicular = |point: Vec3| {
+			let offset = point - root;
+			(offset - forward * offset.dot(forward)).try_normalize()
+		};
+
+		self.pole
+			.and_then(perpendicular)
+			.or_else(|| perpendicular(mid))
+			.unwrap_or_else(|| forward.any_orthonormal_vector())
+	}
+}
+
+/// Rotates a joint around the line between its neighbors so it faces the
+/// pole, which keeps its distance to both.
+fn bend_towards(previous: Vec3, joint: Vec3, next: Vec3, pole: Vec3) -> Vec3 {
+	let Some(axis) = (next - previous).try_normalize() else {
+		return joint;
+	};
+
+	let project = |point: Vec3| {
+		let offset = point - previous;
+		offset - axis * offset.dot(axis)
+	};
+
+	let (current, wanted) = (project(joint), project(pole));
+
+	if current.length_squared() < 1e-10 || wanted.length_squared() < 1e-10 {
+		return joint;
+	}
+
+	let angle = current.cross(wanted).dot(axis).atan2(current.dot(wanted));
+	previous + Quat::from_axis_angle(axis, angle) * (joint - previous)
+}
+
+fn world_matrices(parent: Mat4, chain: &[Transform3D]) -> Vec<Mat4> {
+	chain
+		.iter()
+		.scan(parent, |world, transform| {
+			*world *= transform.to_matrix();
+			Some(*world)
+		})
+		.collect()
+}
@@ -7,8 +7,23 @@ pub mod transform;
 pub mod color;
 pub mod id;
 pub mod animator;
+pub mod animation;
+pub mod audio;
+pub mod ray;
+pub mod bounds;
+pub mod frustum;
+pub mod path;
+pub mod noise;
+pub mod ik;
 
 pub use transform::{Transform3D, Transformable};
-pub use id::{ObjectId, LightId, CSS3DElementId};
-pub use color::Color;
-pub use animator::Animator;
\ No newline at end of file
+pub use id::{ObjectId, LightId, CSS3DElementId, BindingId, HotspotId, LabelId, SpriteId, PolylineId, HtmlLabelId, AudioEmitterId, SceneListenerId, ReflectionProbeId, DecalId, SceneTextureId, TextId, CameraId};
+pub use color::{Color, ColorSpace};
+pub use ray::Ray;
+pub use bounds::Aabb;
+pub use frustum::Frustum;
+pub use path::Path;
+pub use noise::{Fbm, Noise, NoiseKind};
+pub use ik::IkSolver;
+pub use animator::{Animator, Frame, FramePacing, TimeScale};
+pub use animation::{AnimationClip, AnimationController, AnimationLayer, AnimationState, BlendTree1D};
\ No newline at end of file
@@ -0,0 +1,365 @@
+//! Procedural Noise
+//!
+//! Provides seeded gradient noise: [`Noise`] samples Perlin and simplex
+//! noise in two and three dimensions, and [`Fbm`] layers octaves of it into
+//! fractal Brownian motion for natural-looking terrain, clouds and
+//! turbulence.
+//!
+//! Samples range roughly from -1 to 1, are zero at integer coordinates for
+//! Perlin noise, and are the same for the same seed on every platform.
+//! [`Texture::from_fn`](crate::common::Texture::from_fn) bakes noise into a
+//! texture for shaders.
+//!
+//! ## Examples
+//!
+//! ```ignore
+//! use oxgl::core::{Fbm, Noise, NoiseKind};
+//! use oxgl::common::Texture;
+//! use oxgl::renderer_3d::Terrain;
+//!
+//! let noise = Noise::new(42);
+//! let wobble = noise.simplex3(position.x, position.y, time);
+//!
+//! // Rolling hills
+//! let hills = Fbm::new(Noise::new(7)).with_octaves(6).with_frequency(4.0);
+//! let terrain = Terrain::from_fn(256, |u, v| hills.sample2(u, v) * 0.5 + 0.5)
+//!		.with_height_scale(20.0);
+//!
+//! // A cloud texture for a shader
+//! let clouds = Fbm::new(Noise::new(3)).with_kind(NoiseKind::Perlin).with_frequency(8.0);
+//! let texture = Texture::from_fn(&gl, 256, 256, |u, v| clouds.sample2(u, v) * 0.5 + 0.5)?;
+//! ```
+//!
+
+/// Gradients to the edge midpoints of a cube, shared by every noise.
+const GRADIENTS: [[f32; 3]; 12] = [
+	[1.0, 1.0, 0.0], [-1.0, 1.0, 0.0], [1.0, -1.0, 0.0], [-1.0, -1.0, 0.0],
+	[1.0, 0.0, 1.0], [-1.0, 0.0, 1.0], [1.0, 0.0, -1.0], [-1.0, 0.0, -1.0],
+	[0.0, 1.0, 1.0], [0.0, -1.0, 1.0], [0.0, 1.0, -1.0], [0.0, -1.0, -1.0],
+];
+
+/// Skew and unskew factors between simplex and grid space.
+const F2: f32 = 0.366_025_42;
+const G2: f32 = 0.211_324_87;
+const F3: f32 = 1.0 / 3.0;
+const G3: f32 = 1.0 / 6.0;
+
+/// Which gradient noise an [`Fbm`] layers.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NoiseKind {
+	/// Grid-aligned, slightly blocky, zero at integer coordinates.
+	Perlin,
+	/// Cheaper in 3D, with fewer directional artifacts.
+	#[default]
+	Simplex,
+}
+
+/// A seeded gradient noise generator.
+#[derive(Clone)]
+pub struct Noise {
+	seed: u32,
+	/// Shuffled 0-255, repeated so lookups can overflow without wrapping.
+	perm: [u8; 512],
+}
+
+impl Default for Noise {
+	fn default() -> Self {
+		Self::new(0)
+	}
+}
+
+impl std::fmt::Debug for Noise {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("Noise").field("seed", &self.seed).finish()
+	}
+}
+
+impl Noise {
+	/// Creates a generator. Different seeds give unrelated noise.
+	///
+	/// ```
+	/// use oxgl::core::Noise;
+	///
+	/// let a = Noise::new(1);
+	///
+	/// assert_eq!(a.simplex2(0.3, 1.7), Noise::new(1).simplex2(0.3, 1.7));
+	/// assert_eq!(a.perlin2(3.0, 4.0), 0.0);
+	/// ```
+	pub fn new(seed: u32) -> Self {
+		let mut table: [u8; 256] = std::array::from_fn(|i| i as u8);
+		let mut state = seed;
+
+		// Fisher-Yates with a splitmix32 stream
+		for i in (1..256).rev() {
+			state = state.wrapping_add(0x9E37_79B9);
+			let mut z = state;
+			z = (z ^ (z >> 16)).wrapping_mul(0x85EB_CA6B);
+			z = (z ^ (z >> 13)).wrapping_mul(0xC2B2_AE35);
+			z ^= z >> 16;
+
+			table.swap(i, z as usize % (i + 1));
+		}
+
+		Self {
+			seed,
+			perm: std::array::from_fn(|i| table[i & 255]),
+		}
+	}
+
+	pub fn seed(&self) -> u32 {
+		self.seed
+	}
+
+	/// Samples 2D Perlin noise.
+	pub fn perlin2(&self, x: f32, y: f32) -> f32 {
+		let (xi, yi) = (x.floor(), y.floor());
+		let (xf, yf) = (x - xi, y - yi);
+		let (xi, yi) = (xi as i32 & 255, yi as i32 & 255);
+		let (u, v) = (fade(xf), fade(yf));
+
+		let corner = |dx: i32, dy: i32| {
+			let hash = self.hash2(xi + dx, yi + dy);
+			dot2(hash, xf - dx as f32, yf - dy as f32)
+		};
+
+		lerp(
+			lerp(corner(0, 0), corner(1, 0), u),
+			lerp(corner(0, 1), corner(1, 1), u),
+			v,
+		)
+	}
+
+	/// Samples 3D Perlin noise.
+	pub fn perlin3(&self, x: f32, y: f32, z: f32) -> f32 {
+		let (xi, yi, zi) = (x.floor(), y.floor(), z.floor());
+		let (xf, yf, zf) = (x - xi, y - yi, z - zi);
+		let (xi, yi, zi) = (xi as i32 & 255, yi as i32 & 255, zi as i32 & 255);
+		let (u, v, w) = (fade(xf), fade(yf), fade(zf));
+
+		let corner = |dx: i32, dy: i32, dz: i32| {
+			let hash = self.hash3(xi + dx, yi + dy, zi + dz);
+			dot3(hash, xf - dx as f32, yf - dy as f32, zf - dz as f32)
+		};
+
+		lerp(
+			lerp(
+				lerp(corner(0, 0, 0), corner(1, 0, 0), u),
+				lerp(corner(0, 1, 0), corner(1, 1, 0), u),
+				v,
+			),
+			lerp(
+				lerp(corner(0, 0, 1), corner(1, 0, 1), u),
+				lerp(corner(0, 1, 1), corner(1, 1, 1), u),
+				v,
+			),
+			w,
+		)
+	}
+
+	/// Samples 2D simplex noise.
+	pub fn simplex2(&self, x: f32, y: f32) -> f32 {
+		let skew = (x + y) * F2;
+		let (i, j) = ((x + skew).floor(), (y + skew).floor());
+		let unskew = (i + j) * G2;
+		let (x0, y0) = (x - (i - unskew), y - (j - unskew));
+		let (i, j) = (i as i32 & 255, j as i32 & 255);
+
+		// Which triangle of the skewed cell the point is in
+		let (i1, j1) = if x0 > y0 { (1, 0) } else { (0, 1) };
+
+		let corners = [
+			(0, 0, x0, y0),
+			(i1, j1, x0 - i1 as f32 + G2, y0 - j1 as f32 + G2),
+			(1, 1, x0 - 1.0 + 2.0 * G2, y0 - 1.0 + 2.0 * G2),
+		];
+
+		let sum: f32 = corners.iter().map(|&(di, dj, cx, cy)| {
+			let t = 0.5 - cx * cx - cy * cy;
+
+			if t < 0.0 {
+				0.0
+			} else {
+				let t = t * t;
+				t * t * dot2(self.hash2(i + di, j + dj), cx, cy)
+			}
+		}).sum();
+
+		70.0 * sum
+	}
+
+	/// Samples 3D simplex noise.
+	pub fn simplex3(&self, x: f32, y: f32, z: f32) -> f32 {
+		let skew = (x + y + z) * F3;
+		let (i, j, k) = ((x + skew).floor(), (y + skew).floor(), (z + skew).floor());
+		let unskew = (i + j + k) * G3;
+		let (x0, y0, z0) = (x - (i - unskew), y - (j - unskew), z - (k - unskew));
+		let (i, j, k) = (i as i32 & 255, j as i32 & 255, k as i32 & 255);
+
+		// Which of the six tetrahedra of the skewed cell the point is in
+		let (first, second) = if x0 >= y0 {
+			if y0 >= z0 {
+				((1, 0, 0), (1, 1, 0))
+			} else if x0 >= z0 {
+				((1, 0, 0), (1, 0, 1))
+			} else {
+				((0, 0, 1), (1, 0, 1))
+			}
+		} else if y0 < z0 {
+			((0, 0, 1), (0, 1, 1))
+		} else if x0 < z0 {
+			((0, 1, 0), (0, 1, 1))
+		} else {
+			((0, 1, 0), (1, 1, 0))
+		};
+
+		let corner = |(di, dj, dk): (i32, i32, i32), offset: f32| {
+			let (cx, cy, cz) = (x0 - di as f32 + offset, y0 - dj as f32 + offset, z0 - dk as f32 + offset);
+			let t = 0.6 - cx * cx - cy * cy - cz * cz;
+
+			if t < 0.0 {
+				0.0
+			} else {
+				let t = t * t;
+				t * t * dot3(self.hash3(i + di, j + dj, k + dk), cx, cy, cz)
+			}
+		};
+
+		32.0 * (corner((0, 0, 0), 0.0)
+			+ corner(first, G3)
+			+ corner(second, 2.0 * G3)
+			+ corner((1, 1, 1), 3.0 * G3))
+	}
+
+	/// Samples 2D noise of either kind.
+	pub fn sample2(&self, kind: NoiseKind, x: f32, y: f32) -> f32 {
+		match kind {
+			NoiseKind::Perlin => self.perlin2(x, y),
+			NoiseKind::Simplex => self.simplex2(x, y),
+		}
+	}
+
+	/// Samples 3D noise of either kind.
+	pub fn sample3(&self, kind: NoiseKind, x: f32, y: f32, z: f32) -> f32 {
+		match kind {
+			NoiseKind::Perlin => self.perlin3(x, y, z),
+			NoiseKind::Simplex => self.simplex3(x, y, z),
+		}
+	}
+
+	fn hash2(&self, x: i32, y: i32) -> usize {
+		self.perm[self.perm[x as usize] as usize + y as usize] as usize
+	}
+
+	fn hash3(&self, x: i32, y: i32, z: i32) -> usize {
+		self.perm[self.hash2(x, y) + z as usize] as usize
+	}
+}
+
+/// Fractal Brownian motion: octaves of noise at rising frequencies and
+/// falling amplitudes, summed and normalized back to roughly -1 to 1.
+///
+/// ## Examples
+///
+/// ```
+/// use oxgl::core::{Fbm, Noise};
+///
+/// let fbm = Fbm::new(Noise::new(9)).with_octaves(4);
+/// let height = fbm.sample2(0.25, 0.75);
+///
+/// assert!((-1.0..=1.0).contains(&height));
+/// ```
+#[derive(Clone, Debug)]
+pub struct Fbm {
+	pub noise: Noise,
+	pub kind: NoiseKind,
+	pub octaves: u32,
+	/// Frequency of the first octave.
+	pub frequency: f32,
+	/// Frequency multiplier between octaves.
+	pub lacunarity: f32,
+	/// Amplitude multiplier between octaves, lower for smoother results.
+	pub gain: f32,
+}
+
+impl Fbm {
+	/// Creates five octaves of simplex noise, each twice the frequency and
+	/// half the amplitude of the last.
+	pub fn new(noise: Noise) -> Self {
+		Self {
+			noise,
+			kind: NoiseKind::Simplex,
+			octaves: 5,
+			frequency: 1.0,
+			lacunarity: 2.0,
+			gain: 0.5,
+		}
+	}
+
+	pub fn with_kind(mut self, kind: NoiseKind) -> Self {
+		self.kind = kind;
+		self
+	}
+
+	pub fn with_octaves(mut self, octaves: u32) -> Self {
+		self.octaves = octaves;
+		self
+	}
+
+	pub fn with_frequency(mut self, frequency: f32) -> Self {
+		self.frequency = frequency;
+		self
+	}
+
+	pub fn with_lacunarity(mut self, lacunarity: f32) -> Self {
+		self.lacunarity = lacunarity;
+		self
+	}
+
+	pub fn with_gain(mut self, gain: f32) -> Self {
+		self.gain = gain;
+		self
+	}
+
+	pub fn sample2(&self, x: f32, y: f32) -> f32 {
+		self.layer(|frequency| self.noise.sample2(self.kind, x * frequency, y * frequency))
+	}
+
+	pub fn sample3(&self, x: f32, y: f32, z: f32) -> f32 {
+		self.layer(|frequency| self.noise.sample3(self.kind, x * frequency, y * frequency, z * frequency))
+	}
+
+	/// Sums octaves of `sample` at each frequency.
+	fn layer(&self, sample: impl Fn(f32) -> f32) -> f32 {
+		let mut frequency = self.frequency;
+		let mut amplitude = 1.0;
+		let mut sum = 0.0;
+		let mut total = 0.0;
+
+		for _ in 0..self.octaves {
+			sum += sample(frequency) * amplitude;
+			total += amplitude;
+			frequency *= self.lacunarity;
+			amplitude *= self.gain;
+		}
+
+		if total > 0.0 { (sum / total).clamp(-1.0, 1.0) } else { 0.0 }
+	}
+}
+
+fn fade(t: f32) -> f32 {
+	t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+	a + (b - a) * t
+}
+
+fn dot2(hash: usize, x: f32, y: f32) -> f32 {
+	let [gx, gy, _] = GRADIENTS[hash % 12];
+	gx * x + gy * y
+}
+
+fn dot3(hash: usize, x: f32, y: f32, z: f32) -> f32 {
+	let [gx, gy, gz] = GRADIENTS[hash % 12];
+	gx * x + gy * y + gz * z
+}
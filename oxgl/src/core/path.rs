@@ -0,0 +1,323 @@
+//! Spline Paths
+//!
+//! Provides [`Path`], a smooth curve through 3D space built from Catmull-Rom
+//! or cubic Bezier splines. Paths can be sampled by progress or by distance
+//! travelled, and used to fly a [`Camera`](crate::common::Camera) through a
+//! scene or move objects and lights along a route.
+//!
+//! ## Examples
+//!
+//! ```ignore
+//! use oxgl::core::Path;
+//! use glam::Vec3;
+//!
+//! let route = Path::catmull_rom_closed(vec![
+//!		Vec3::new(-4.0, 2.0, 4.0),
+//!		Vec3::new(4.0, 3.0, 4.0),
+//!		Vec3::new(4.0, 2.0, -4.0),
+//!		Vec3::new(-4.0, 1.0, -4.0),
+//! ])?;
+//!
+//! app.run(move |scene, time| {
+//!		let t = time / 20.0;
+//!
+//!		// Fly the camera around the loop, looking slightly ahead
+//!		scene.camera.follow_path(&route, t, 0.02);
+//!
+//!		// Carry a light along the same route
+//!		if let Some(light) = scene.get_light_mut(lamp) {
+//!			light.position = route.sample(t + 0.5).0;
+//!		}
+//! });
+//!
+//! // Visualize the spline and its control points
+//! gizmos.path(&gl, &camera, &route, Vec3::new(1.0, 0.6, 0.0));
+//! ```
+//!
+
+use glam::{Quat, Vec3};
+
+use super::{Transform3D, transform::look_rotation};
+
+/// Arc length samples taken per segment for distance lookups.
+const LENGTH_SAMPLES: usize = 16;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Spline {
+	CatmullRom { closed: bool },
+	Bezier,
+}
+
+/// A smooth curve through 3D space.
+///
+/// Progress `t` runs from `0.0` at the start to `1.0` at the end, with each
+/// segment taking an equal share. Closed paths wrap around, so any `t` is
+/// valid and `1.0` returns to the start.
+#[derive(Clone, Debug)]
+pub struct Path {
+	points: Vec<Vec3>,
+	spline: Spline,
+	/// Cumulative length at each arc length sample.
+	distances: Vec<f32>,
+}
+
+impl Path {
+	/// Creates a Catmull-Rom spline passing through every point.
+	///
+	/// # Errors
+	///
+	/// Returns an error if there are fewer than two points.
+	pub fn catmull_rom(points: Vec<Vec3>) -> Result<Self, String> {
+		if points.len() < 2 {
+			return Err("Catmull-Rom paths need at least 2 points".into());
+		}
+
+		Ok(Self::build(points, Spline::CatmullRom { closed: false }))
+	}
+
+	/// Creates a Catmull-Rom spline that loops back to its first point.
+	///
+	/// # Errors
+	///
+	/// Returns an error if there are fewer than three points.
+	pub fn catmull_rom_closed(points: Vec<Vec3>) -> Result<Self, String> {
+		if points.len() < 3 {
+			return Err("Closed Catmull-Rom paths need at least 3 points".into());
+		}
+
+		Ok(Self::build(points, Spline::CatmullRom { closed: true }))
+	}
+
+	/// Creates a chain of cubic Bezier segments.
+	///
+	/// Points are given as `start, control, control, end`, with each further
+	/// segment adding two controls and an end, so the count is `3n + 1`.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the point count isn't `3n + 1` for some `n >= 1`.
+	pub fn bezier(points: Vec<Vec3>) -> Result<Self, String> {
+		if points.len() < 4 || !(points.len() - 1).is_multiple_of(3) {
+			return Err(format!("Bezier paths need 3n + 1 points, got {}", points.len()));
+		}
+
+		Ok(Self::build(points, Spline::Bezier))
+	}
+
+	fn build(points: Vec<Vec3>, spline: Spline) -> Self {
+		let mut path = Self { points, spline, distances: Vec::new() };
+		let count = path.segment_count() * LENGTH_SAMPLES;
+		let mut previous = path.points[0];
+		let mut total = 0.0;
+
+		path.distances.reserve(count + 1);
+		path.distances.push(0.0);
+
+		for i in 1..=count {
+			let (position, _) = path.sample(i as f32 / count as f32);
+			total += position.distance(previous);
+			path.distances.push(total);
+			previous = position;
+		}
+
+		path
+	}
+
+	/// Returns the points the path was created from.
+	pub fn points(&self) -> &[Vec3] {
+		&self.points
+	}
+
+	/// Returns whether the path loops back to its start.
+	pub fn is_closed(&self) -> bool {
+		self.spline == Spline::CatmullRom { closed: true }
+	}
+
+	/// Returns the number of curve segments.
+	pub fn segment_count(&self) -> usize {
+		match self.spline {
+			Spline::CatmullRom { closed: true } => self.points.len(),
+			Spline::CatmullRom { closed: false } => self.points.len() - 1,
+			Spline::Bezier => (self.points.len() - 1) / 3,
+		}
+	}
+
+	/// Returns the approximate length of the path.
+	pub fn length(&self) -> f32 {
+		self.distances.last().copied().unwrap_or(0.0)
+	}
+
+	/// Samples the path, returning the position and the unit direction of
+	/// travel at progress `t`.
+	///
+	/// Open paths clamp `t` to `0.0..=1.0`, closed paths wrap it.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use oxgl::core::Path;
+	/// use glam::Vec3;
+	///
+	/// let path = Path::catmull_rom(vec![Vec3::ZERO, Vec3::X, Vec3::new(2.0, 0.0, 0.0)]).unwrap();
+	///
+	/// // Catmull-Rom splines pass through their points
+	/// let (position, tangent) = path.sample(0.5);
+	/// assert!(position.distance(Vec3::X) < 1e-5);
+	/// assert!(tangent.distance(Vec3::X) < 1e-5);
+	///
+	/// assert_eq!(path.sample(2.0).0, path.sample(1.0).0);
+	/// ```
+	pub fn sample(&self, t: f32) -> (Vec3, Vec3) {
+		let segments = self.segment_count();
+		let t = if self.is_closed() { t.rem_euclid(1.0) } else { t.clamp(0.0, 1.0) };
+		let scaled = t * segments as f32;
+		let segment = (scaled as usize).min(segments - 1);
+		let u = scaled - segment as f32;
+
+		let [a, b, c, d] = self.segment_points(segment);
+
+		let (position, derivative) = match self.spline {
+			Spline::CatmullRom { .. } => catmull_rom(a, b, c, d, u),
+			Spline::Bezier => bezier(a, b, c, d, u),
+		};
+
+		// Coincident Bezier controls leave no derivative at the ends
+		let tangent = derivative.try_normalize()
+			.or_else(|| (self.segment_end(segment) - self.segment_start(segment)).try_normalize())
+			.unwrap_or(Vec3::NEG_Z);
+
+		(position, tangent)
+	}
+
+	/// Samples the path a distance along it from the start, so objects move
+	/// at a constant speed regardless of how points are spaced.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use oxgl::core::Path;
+	/// use glam::Vec3;
+	///
+	/// let path = Path::catmull_rom(vec![Vec3::ZERO, Vec3::new(10.0, 0.0, 0.0)]).unwrap();
+	///
+	/// assert!((path.length() - 10.0).abs() < 1e-3);
+	/// assert!(path.sample_at_distance(2.5).0.distance(Vec3::new(2.5, 0.0, 0.0)) < 1e-3);
+	/// ```
+	pub fn sample_at_distance(&self, distance: f32) -> (Vec3, Vec3) {
+		self.sample(self.progress_at_distance(distance))
+	}
+
+	/// Converts a distance along the path to progress `t`.
+	///
+	/// Distances past the end wrap on closed paths.
+	pub fn progress_at_distance(&self, distance: f32) -> f32 {
+		let length = self.length();
+
+		if length <= 0.0 {
+			return 0.0;
+		}
+
+		let distance = if self.is_closed() { distance.rem_euclid(length) } else { distance.clamp(0.0, length) };
+		let next = self.distances.partition_point(|&d| d < distance).clamp(1, self.distances.len() - 1);
+		let (a, b) = (self.distances[next - 1], self.distances[next]);
+		let fraction = if b > a { (distance - a) / (b - a) } else { 0.0 };
+
+		(next as f32 - 1.0 + fraction) / (self.distances.len() - 1) as f32
+	}
+
+	/// Returns a rotation facing along the path at `t`, with local -Z
+	/// pointing in the direction of travel.
+	pub fn orientation(&self, t: f32, up: Vec3) -> Quat {
+		look_rotation(self.sample(t).1, up)
+	}
+
+	/// Moves a transform to `t` along the path, facing the direction of
+	/// travel. The scale is left unchanged.
+	pub fn follow(&self, t: f32, up: Vec3, transform: &mut Transform3D) {
+		transform.position = self.sample(t).0;
+		transform.rotation = self.orientation(t, up);
+	}
+
+	/// Returns the lines joining each Bezier end point to its neighbouring
+	/// controls. Empty for Catmull-Rom paths, which pass through every point.
+	pub fn handles(&self) -> Vec<(Vec3, Vec3)> {
+		if self.spline != Spline::Bezier {
+			return Vec::new();
+		}
+
+		(0..self.segment_count())
+			.flat_map(|segment| {
+				let [a, b, c, d] = self.segment_points(segment);
+				[(a, b), (c, d)]
+			})
+			.collect()
+	}
+
+	/// Returns points along the path for drawing it as a line strip.
+	pub fn polyline(&self, samples_per_segment: usize) -> Vec<Vec3> {
+		let count = self.segment_count() * samples_per_segment.max(1);
+
+		(0..=count).map(|i| self.sample(i as f32 / count as f32).0).collect()
+	}
+
+	fn segment_start(&self, segment: usize) -> Vec3 {
+		self.segment_points(segment)[if self.spline == Spline::Bezier { 0 } else { 1 }]
+	}
+
+	fn segment_end(&self, segment: usize) -> Vec3 {
+		self.segment_points(segment)[if self.spline == Spline::Bezier { 3 } else { 2 }]
+	}
+
+	/// Returns the four points shaping a segment.
+	fn segment_points(&self, segment: usize) -> [Vec3; 4] {
+		let points = &self.points;
+		let n = points.len();
+
+		match self.spline {
+			Spline::Bezier => {
+				let i = segment * 3;
+				[points[i], points[i + 1], points[i + 2], points[i + 3]]
+			}
+			Spline::CatmullRom { closed: true } => {
+				[n - 1, 0, 1, 2].map(|offset| points[(segment + offset) % n])
+			}
+			Spline::CatmullRom { closed: false } => {
+				// Open ends mirror their neighbour so the curve reaches them
+				let point = |i: isize| -> Vec3 {
+					if i < 0 {
+						2.0 * points[0] - points[1]
+					} else if i as usize >= n {
+						2.0 * points[n - 1] - points[n - 2]
+					} else {
+						points[i as usize]
+					}
+				};
+				let i = segment as isize;
+				[point(i - 1), point(i), point(i + 1), point(i + 2)]
+			}
+		}
+	}
+}
+
+/// Evaluates a uniform Catmull-Rom segment between `b` and `c`, returning
+/// the position and derivative.
+fn catmull_rom(a: Vec3, b: Vec3, c: Vec3, d: Vec3, u: f32) -> (Vec3, Vec3) {
+	let c1 = c - a;
+	let c2 = 2.0 * a - 5.0 * b + 4.0 * c - d;
+	let c3 = -a + 3.0 * b - 3.0 * c + d;
+
+	let position = 0.5 * (2.0 * b + c1 * u + c2 * u * u + c3 * u * u * u);
+	let derivative = 0.5 * (c1 + 2.0 * c2 * u + 3.0 * c3 * u * u);
+
+	(position, derivative)
+}
+
+/// Evaluates a cubic Bezier segment, returning the position and derivative.
+fn bezier(a: Vec3, b: Vec3, c: Vec3, d: Vec3, u: f32) -> (Vec3, Vec3) {
+	let v = 1.0 - u;
+
+	let position = v * v * v * a + 3.0 * v * v * u * b + 3.0 * v * u * u * c + u * u * u * d;
+	let derivative = 3.0 * v * v * (b - a) + 6.0 * v * u * (c - b) + 3.0 * u * u * (d - c);
+
+	(position, derivative)
+}
@@ -0,0 +1,145 @@
+//! Rays
+//!
+//! Provides a ray type with triangle and box intersection tests, used for
+//! picking and simple collision queries.
+//!
+//! ## Examples
+//!
+//! ```
+//! use oxgl::core::Ray;
+//! use glam::Vec3;
+//!
+//! let ray = Ray::new(Vec3::new(0.0, 0.0, 5.0), Vec3::NEG_Z);
+//!
+//! let hit = ray.intersect_triangle(
+//!		Vec3::new(-1.0, -1.0, 0.0),
+//!		Vec3::new(1.0, -1.0, 0.0),
+//!		Vec3::new(0.0, 1.0, 0.0),
+//! );
+//!
+//! assert_eq!(hit, Some(5.0));
+//! assert_eq!(ray.at(5.0), Vec3::ZERO);
+//! ```
+//!
+
+use glam::{Mat4, Vec3};
+
+/// A half-line starting at `origin` and extending along `direction`.
+///
+/// The direction is normalized on construction, so distances returned by
+/// intersection tests are in world units.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Ray {
+	pub origin: Vec3,
+	pub direction: Vec3,
+}
+
+impl Ray {
+	pub fn new(origin: Vec3, direction: Vec3) -> Self {
+		Self { origin, direction: direction.normalize() }
+	}
+
+	/// Returns the point at a distance along the ray.
+	pub fn at(&self, distance: f32) -> Vec3 {
+		self.origin + self.direction * distance
+	}
+
+	/// Transforms the ray by a matrix.
+	///
+	/// The direction is renormalized, so distances along the result are in
+	/// the target space's units.
+	pub fn transform(&self, matrix: &Mat4) -> Self {
+		Self::new(matrix.transform_point3(self.origin), matrix.transform_vector3(self.direction))
+	}
+
+	/// Returns the distance to a triangle, if hit from either side.
+	///
+	/// Uses the Möller–Trumbore algorithm.
+	pub fn intersect_triangle(&self, a: Vec3, b: Vec3, c: Vec3) -> Option<f32> {
+		let edge1 = b - a;
+		let edge2 = c - a;
+		let p = self.direction.cross(edge2);
+		let det = edge1.dot(p);
+
+		if det.abs() < f32::EPSILON {
+			return None;
+		}
+
+		let inv_det = 1.0 / det;
+		let t = self.origin - a;
+		let u = t.dot(p) * inv_det;
+
+		if !(0.0..=1.0).contains(&u) {
+			return None;
+		}
+
+		let q = t.cross(edge1);
+		let v = self.direction.dot(q) * inv_det;
+
+		if v < 0.0 || u + v > 1.0 {
+			return None;
+		}
+
+		let distance = edge2.dot(q) * inv_det;
+		(distance >= 0.0).then_some(distance)
+	}
+
+	/// Returns the distance to a plane, if hit from either side.
+	pub fn intersect_plane(&self, point: Vec3, normal: Vec3) -> Option<f32> {
+		let denom = self.direction.dot(normal);
+
+		if denom.abs() < f32::EPSILON {
+			return None;
+		}
+
+		let distance = (point - self.origin).dot(normal) / denom;
+		(distance >= 0.0).then_some(distance)
+	}
+
+	/// Returns the entry distance into an axis-aligned box, if hit.
+	///
+	/// Returns `0.0` when the origin is inside the box.
+	pub fn intersect_aabb(&self, min: Vec3, max: Vec3) -> Option<f32> {
+		let inv = self.direction.recip();
+		let t1 = (min - self.origin) * inv;
+		let t2 = (max - self.origin) * inv;
+
+		let near = t1.min(t2).max_element();
+		let far = t1.max(t2).min_element();
+
+		(far >= near.max(0.0)).then_some(near.max(0.0))
+	}
+
+	/// Returns the entry distance into a sphere, if hit.
+	///
+	/// Returns `0.0` when the origin is inside the sphere.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use oxgl::core::Ray;
+	/// use glam::Vec3;
+	///
+	/// let ray = Ray::new(Vec3::ZERO, Vec3::X);
+	///
+	/// assert_eq!(ray.intersect_sphere(Vec3::new(5.0, 0.0, 0.0), 1.0), Some(4.0));
+	/// assert_eq!(ray.intersect_sphere(Vec3::new(5.0, 2.0, 0.0), 1.0), None);
+	/// ```
+	pub fn intersect_sphere(&self, center: Vec3, radius: f32) -> Option<f32> {
+		let offset = self.origin - center;
+		let b = offset.dot(self.direction);
+		let c = offset.length_squared() - radius * radius;
+
+		if c <= 0.0 {
+			return Some(0.0);
+		}
+
+		let discriminant = b * b - c;
+
+		if b > 0.0 || discriminant < 0.0 {
+			return None;
+		}
+
+		Some(-b - discriminant.sqrt())
+	}
+}
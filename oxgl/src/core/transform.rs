@@ -3,7 +3,7 @@
 //! Provides position, rotation, and scale transformations for 3D objects.
 //!
 
-use glam::{Mat4, Quat, Vec3};
+use glam::{Mat3, Mat4, Quat, Vec3};
 
 /// Trait for types that can be transformed in 3D space.
 pub trait Transformable<V, M> {
@@ -58,6 +58,116 @@ impl Transform3D {
 		self.scale = scale;
 		self
 	}
+
+	/// Returns the direction the transform faces, its local -Z axis.
+	pub fn forward(&self) -> Vec3 {
+		self.rotation * Vec3::NEG_Z
+	}
+
+	/// Returns the local +X axis in world space.
+	pub fn right(&self) -> Vec3 {
+		self.rotation * Vec3::X
+	}
+
+	/// Returns the local +Y axis in world space.
+	pub fn up(&self) -> Vec3 {
+		self.rotation * Vec3::Y
+	}
+
+	/// Moves the transform by a world space offset.
+	pub fn translate(&mut self, delta: Vec3) {
+		self.position += delta;
+	}
+
+	/// Moves the transform by an offset along its own axes.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use oxgl::core::Transform3D;
+	/// use glam::{Quat, Vec3};
+	///
+	/// let mut transform = Transform3D::new().with_rotation(Quat::from_rotation_y(std::f32::consts::FRAC_PI_2));
+	///
+	/// // One unit forward, which now faces -X
+	/// transform.translate_local(Vec3::NEG_Z);
+	/// assert!(transform.position.distance(Vec3::NEG_X) < 1e-6);
+	/// ```
+	pub fn translate_local(&mut self, delta: Vec3) {
+		self.position += self.rotation * delta;
+	}
+
+	/// Rotates the transform around a world space axis by an angle in radians.
+	pub fn rotate_axis_angle(&mut self, axis: Vec3, angle: f32) {
+		self.rotation = (Quat::from_axis_angle(axis.normalize(), angle) * self.rotation).normalize();
+	}
+
+	/// Rotates the transform around one of its own axes by an angle in radians.
+	pub fn rotate_local_axis_angle(&mut self, axis: Vec3, angle: f32) {
+		self.rotation = (self.rotation * Quat::from_axis_angle(axis.normalize(), angle)).normalize();
+	}
+
+	/// Turns the transform to face a point, keeping its top towards `up`.
+	///
+	/// Does nothing if `target` is at the transform's position.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use oxgl::core::Transform3D;
+	/// use glam::Vec3;
+	///
+	/// let mut transform = Transform3D::new().with_position(Vec3::new(0.0, 0.0, 5.0));
+	/// transform.look_at(Vec3::new(5.0, 0.0, 5.0), Vec3::Y);
+	///
+	/// assert!(transform.forward().distance(Vec3::X) < 1e-6);
+	/// assert!(transform.up().distance(Vec3::Y) < 1e-6);
+	/// ```
+	pub fn look_at(&mut self, target: Vec3, up: Vec3) {
+		if let Some(direction) = (target - self.position).try_normalize() {
+			self.rotation = look_rotation(direction, up);
+		}
+	}
+
+	/// Interpolates between two transforms, returning `self` at `t = 0` and
+	/// `other` at `t = 1`.
+	///
+	/// Position and scale are interpolated linearly and rotation spherically,
+	/// so the rotation speed stays constant.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use oxgl::core::Transform3D;
+	/// use glam::{Quat, Vec3};
+	///
+	/// let a = Transform3D::new();
+	/// let b = Transform3D::new()
+	///		.with_position(Vec3::new(2.0, 0.0, 0.0))
+	///		.with_rotation(Quat::from_rotation_y(1.0));
+	///
+	/// let half = a.lerp(&b, 0.5);
+	/// assert_eq!(half.position, Vec3::new(1.0, 0.0, 0.0));
+	/// assert!(half.rotation.angle_between(Quat::from_rotation_y(0.5)) < 1e-3);
+	/// ```
+	pub fn lerp(&self, other: &Self, t: f32) -> Self {
+		Self {
+			position: self.position.lerp(other.position, t),
+			rotation: self.rotation.slerp(other.rotation, t),
+			scale: self.scale.lerp(other.scale, t),
+		}
+	}
+}
+
+/// Returns a rotation pointing local -Z along `forward`, with local +Y as
+/// close to `up` as possible. Falls back to another up axis when `forward`
+/// is parallel to `up`.
+pub(crate) fn look_rotation(forward: Vec3, up: Vec3) -> Quat {
+	let right = forward.cross(up).try_normalize()
+		.or_else(|| forward.cross(Vec3::Z).try_normalize())
+		.unwrap_or(Vec3::X);
+
+	Quat::from_mat3(&Mat3::from_cols(right, right.cross(forward), -forward))
 }
 
 impl Transformable<Vec3, Mat4> for Transform3D {
@@ -43,16 +43,78 @@
 pub mod core;
 pub mod common;
 pub mod renderer_3d;
+pub mod worker;
 
-use std::{cell::RefCell, rc::Rc};
+use std::{cell::{Cell, RefCell}, rc::Rc};
 use glam::Vec3;
-use web_sys::{HtmlCanvasElement, WebGl2RenderingContext as GL, wasm_bindgen::JsCast};
+use web_sys::{
+	Event, HtmlCanvasElement, OffscreenCanvas, PointerEvent, WebGl2RenderingContext as GL,
+	wasm_bindgen::{JsCast, prelude::Closure},
+};
 
-use crate::{renderer_3d::{Scene, GizmoRenderer, DebugSettings}, common::Camera, core::Animator};
+use crate::{
+	renderer_3d::{Background, CSS3DRenderer, Scene, SceneDescription, SceneInstance, GizmoRenderer, DebugSettings, Selection, StatsOverlay},
+	common::{Camera, RenderState},
+	core::{Animator, ColorSpace, CSS3DElementId, TimeScale, Transform3D},
+};
+
+/// The canvas a [`Renderer`] draws into.
+///
+/// An `OffscreenCanvas` is used when rendering in a web worker, see
+/// [`worker`].
+#[derive(Clone)]
+pub enum RenderCanvas {
+	Element(HtmlCanvasElement),
+	Offscreen(OffscreenCanvas),
+}
+
+impl RenderCanvas {
+	pub fn width(&self) -> u32 {
+		match self {
+			Self::Element(canvas) => canvas.width(),
+			Self::Offscreen(canvas) => canvas.width(),
+		}
+	}
+
+	pub fn height(&self) -> u32 {
+		match self {
+			Self::Element(canvas) => canvas.height(),
+			Self::Offscreen(canvas) => canvas.height(),
+		}
+	}
+
+	/// Resizes the drawing buffer.
+	pub fn set_size(&self, width: u32, height: u32) {
+		match self {
+			Self::Element(canvas) => {
+				canvas.set_width(width);
+				canvas.set_height(height);
+			}
+			Self::Offscreen(canvas) => {
+				canvas.set_width(width);
+				canvas.set_height(height);
+			}
+		}
+	}
+
+	/// Returns the canvas element, or `None` for an `OffscreenCanvas`.
+	///
+	/// HTML overlays, stats and recording need the element, so they're only
+	/// available on the main thread.
+	pub fn element(&self) -> Option<&HtmlCanvasElement> {
+		match self {
+			Self::Element(canvas) => Some(canvas),
+			Self::Offscreen(_) => None,
+		}
+	}
+}
 
 /// Low-level WebGL2 renderer wrapper.
 ///
 /// Provides access to the WebGL2 context and canvas element.
+///
+/// Renderers made with [`from_offscreen_canvas`](Self::from_offscreen_canvas)
+/// have no element; [`surface`](Self::surface) works for both.
 /// Typically used through [`App`] rather than directly.
 ///
 /// ## Examples
@@ -63,7 +125,9 @@ use crate::{renderer_3d::{Scene, GizmoRenderer, DebugSettings}, common::Camera,
 /// ```
 pub struct Renderer {
 	pub gl: GL,
-	pub canvas: HtmlCanvasElement,
+	surface: RenderCanvas,
+	color_space: Cell<ColorSpace>,
+	render_state: Cell<RenderState>,
 }
 
 impl Renderer {
@@ -95,17 +159,117 @@ impl Renderer {
 			.dyn_into::<GL>()
 			.unwrap();
 
+		Self::with_context(gl, RenderCanvas::Element(canvas))
+	}
+
+	/// Creates a renderer drawing into an `OffscreenCanvas`, usually inside
+	/// a web worker.
+	///
+	/// ## Errors
+	///
+	/// Returns an error if WebGL2 context creation fails.
+	///
+	/// ## Examples
+	///
+	/// ```ignore
+	/// let renderer = Renderer::from_offscreen_canvas(canvas)?;
+	/// ```
+	pub fn from_offscreen_canvas(canvas: OffscreenCanvas) -> Result<Self, String> {
+		let gl = canvas
+			.get_context("webgl2")
+			.map_err(|e| format!("Failed to get WebGL2 context: {:?}", e))?
+			.ok_or("WebGL2 is not supported in this context")?
+			.dyn_into::<GL>()
+			.map_err(|_| "Failed to cast WebGL2 context")?;
+
+		Ok(Self::with_context(gl, RenderCanvas::Offscreen(canvas)))
+	}
+
+	fn with_context(gl: GL, surface: RenderCanvas) -> Self {
 		gl.enable(GL::DEPTH_TEST);
+		RenderState::default().apply(&gl);
 
-		Self { gl, canvas }
+		Self {
+			gl,
+			surface,
+			color_space: Cell::new(ColorSpace::default()),
+			render_state: Cell::new(RenderState::default()),
+		}
 	}
 
+	/// Returns the canvas element.
+	///
+	/// ## Panics
+	///
+	/// Panics if the renderer draws into an `OffscreenCanvas`, see
+	/// [`surface`](Self::surface).
 	pub fn canvas(&self) -> &HtmlCanvasElement {
-		&self.canvas
+		self.surface.element().expect("Renderer draws into an OffscreenCanvas")
 	}
 
+	/// Returns the canvas the renderer draws into, element or offscreen.
+	pub fn surface(&self) -> &RenderCanvas {
+		&self.surface
+	}
+
+	/// Returns the color space the built-in shaders output, sRGB by default.
+	pub fn color_space(&self) -> ColorSpace {
+		self.color_space.get()
+	}
+
+	/// Sets the color space the built-in shaders output.
+	///
+	/// [`ColorSpace::Srgb`] shades in linear light and encodes the result
+	/// for display. [`ColorSpace::Linear`] writes shaded values unchanged,
+	/// for custom pipelines that handle gamma themselves. Post-processing
+	/// effects run on the encoded output.
+	///
+	/// ## Examples
+	///
+	/// ```ignore
+	/// use oxgl::core::ColorSpace;
+	///
+	/// app.renderer.set_color_space(ColorSpace::Linear);
+	/// ```
+	pub fn set_color_space(&self, color_space: ColorSpace) {
+		self.color_space.set(color_space);
+	}
+
+	/// Returns the pipeline state applied at the start of every frame.
+	pub fn render_state(&self) -> RenderState {
+		self.render_state.get()
+	}
+
+	/// Sets the face culling, depth function, clear values and scissor
+	/// scenes are rendered with, unless a scene sets its own
+	/// [`render_state`](Scene::render_state).
+	///
+	/// ## Examples
+	///
+	/// ```ignore
+	/// use oxgl::common::{CullFace, RenderState};
+	///
+	/// app.renderer.set_render_state(RenderState::new().with_cull_face(CullFace::Back));
+	/// ```
+	pub fn set_render_state(&self, state: RenderState) {
+		self.render_state.set(state);
+	}
+
+	/// Clears the canvas to the default background color.
 	pub fn clear(&self) {
-		self.gl.clear_color(0.1, 0.1, 0.1, 1.0);
+		self.clear_to(&Background::default());
+	}
+
+	/// Clears the canvas to a background's color, or the
+	/// [render state](Self::render_state)'s clear color if it has one.
+	///
+	/// Skyboxes clear to black, as they're drawn by the [`Scene`].
+	pub fn clear_to(&self, background: &Background) {
+		let state = self.render_state();
+		let [r, g, b, a] = state.clear_color.map_or(background.clear_color(), |color| color.to_array());
+
+		self.gl.clear_color(r, g, b, a);
+		self.gl.clear_depth(state.clear_depth);
 		self.gl.clear(GL::COLOR_BUFFER_BIT | GL::DEPTH_BUFFER_BIT);
 	}
 }
@@ -136,6 +300,31 @@ pub struct App {
 	pub scene: Rc<RefCell<Scene>>,
 	pub gizmos: Rc<GizmoRenderer>,
 	pub debug: Rc<RefCell<DebugSettings>>,
+	/// HTML overlay kept in sync with the scene camera, see [`enable_css3d`](Self::enable_css3d).
+	pub css3d: Rc<RefCell<Option<CSS3DRenderer>>>,
+	/// Clock of the render loop. Clone it before [`run`](Self::run) to
+	/// pause, slow down or step the time passed to updates and shaders.
+	pub time_scale: TimeScale,
+	/// Selected objects and transform handles, see
+	/// [`enable_selection`](Self::enable_selection).
+	pub selection: Rc<RefCell<Selection>>,
+	selection_input: Option<CanvasListeners>,
+}
+
+type CanvasListener = (&'static str, Closure<dyn FnMut(Event)>);
+
+/// Event listeners added to a canvas, removed again when dropped.
+struct CanvasListeners {
+	canvas: HtmlCanvasElement,
+	listeners: Vec<CanvasListener>,
+}
+
+impl Drop for CanvasListeners {
+	fn drop(&mut self) {
+		for (name, closure) in &self.listeners {
+			let _ = self.canvas.remove_event_listener_with_callback(name, closure.as_ref().unchecked_ref());
+		}
+	}
 }
 
 impl App {
@@ -155,7 +344,7 @@ impl App {
 	/// ```
 	pub fn new(canvas_id: &str) -> Self {
 		let renderer = Rc::new(Renderer::new(canvas_id));
-		let aspect = renderer.canvas.width() as f32 / renderer.canvas.height() as f32;
+		let aspect = renderer.surface().width() as f32 / renderer.surface().height() as f32;
 		
 		let camera = Camera::new(aspect)
 			.with_position(Vec3::new(0.0, 2.0, 5.0))
@@ -164,8 +353,167 @@ impl App {
 		let scene = Rc::new(RefCell::new(Scene::new(camera)));
 		let gizmos = Rc::new(GizmoRenderer::new(&renderer.gl));
 		let debug = Rc::new(RefCell::new(DebugSettings::default()));
+		let css3d = Rc::new(RefCell::new(None));
+		let time_scale = TimeScale::new();
+		let selection = Rc::new(RefCell::new(Selection::new()));
 		
-		Self { renderer, scene, gizmos, debug }
+		Self { renderer, scene, gizmos, debug, css3d, time_scale, selection, selection_input: None }
+	}
+
+	/// Creates the CSS3D overlay for placing HTML elements in the scene.
+	///
+	/// Once enabled, [`run`](Self::run) resizes the overlay with the canvas
+	/// and renders it with the scene camera every frame. Does nothing if the
+	/// overlay already exists.
+	///
+	/// ## Errors
+	///
+	/// Returns an error if the canvas has no id or the overlay elements
+	/// can't be created.
+	///
+	/// ## Examples
+	///
+	/// ```ignore
+	/// app.enable_css3d()?;
+	///
+	/// let panel = app.add_css3d_element(
+	///		"<div class=\"panel\">Hello</div>",
+	///		Transform3D::new().with_position(Vec3::new(0.0, 2.0, 0.0)),
+	/// )?;
+	/// let tag = app.add_css3d_billboard("<span>Tag</span>", Vec3::new(1.0, 1.0, 0.0))?;
+	///
+	/// // Hide elements behind scene geometry
+	/// if let Some(css3d) = app.css3d.borrow_mut().as_mut() {
+	///		css3d.enable_occlusion(&app.renderer.gl)?;
+	/// }
+	/// ```
+	pub fn enable_css3d(&self) -> Result<(), String> {
+		if self.css3d.borrow().is_some() {
+			return Ok(());
+		}
+
+		let canvas_id = self.renderer.surface().element().map(|canvas| canvas.id()).unwrap_or_default();
+
+		if canvas_id.is_empty() {
+			return Err("The canvas needs an id to attach a CSS3D overlay".into());
+		}
+
+		let fov = self.scene.borrow().camera.fov_y.to_degrees();
+		let renderer = CSS3DRenderer::new(&canvas_id, self.renderer.surface().width(), self.renderer.surface().height(), fov)?;
+
+		*self.css3d.borrow_mut() = Some(renderer);
+		Ok(())
+	}
+
+	/// Adds an HTML element to the CSS3D overlay.
+	///
+	/// ## Errors
+	///
+	/// Returns an error if [`enable_css3d`](Self::enable_css3d) hasn't been
+	/// called or the element can't be created.
+	pub fn add_css3d_element(&self, html: &str, transform: Transform3D) -> Result<CSS3DElementId, String> {
+		self.css3d
+			.borrow()
+			.as_ref()
+			.ok_or("CSS3D is not enabled")?
+			.add_element(html, transform)
+	}
+
+	/// Adds an HTML element to the CSS3D overlay that always faces the camera.
+	///
+	/// ## Errors
+	///
+	/// Returns an error if [`enable_css3d`](Self::enable_css3d) hasn't been
+	/// called or the element can't be created.
+	pub fn add_css3d_billboard(&self, html: &str, position: Vec3) -> Result<CSS3DElementId, String> {
+		self.css3d
+			.borrow()
+			.as_ref()
+			.ok_or("CSS3D is not enabled")?
+			.add_billboard(html, position)
+	}
+
+	/// Removes an element from the CSS3D overlay, returning whether it existed.
+	pub fn remove_css3d_element(&self, id: CSS3DElementId) -> bool {
+		self.css3d
+			.borrow()
+			.as_ref()
+			.is_some_and(|css3d| css3d.remove_element(id))
+	}
+
+	/// Lets objects be selected by clicking them on the canvas.
+	///
+	/// Clicking an object selects it, shift, ctrl or cmd-clicking toggles it
+	/// in the [`selection`](Self::selection) and clicking empty space clears
+	/// it. The hovered object is highlighted, and the primary selection can
+	/// be moved, rotated or scaled with its transform handles. Does nothing
+	/// if already enabled.
+	///
+	/// ## Errors
+	///
+	/// Returns an error when rendering to an `OffscreenCanvas`, or if the
+	/// pointer listeners can't be added.
+	///
+	/// ## Examples
+	///
+	/// ```ignore
+	/// app.enable_selection()?;
+	///
+	/// let selection = app.selection.clone();
+	/// app.scene.borrow_mut().on_event(move |event| {
+	///		if let SceneEvent::Selected(_) | SceneEvent::Deselected(_) = event {
+	///			set_inspected.set(selection.borrow().primary());
+	///		}
+	/// });
+	/// ```
+	pub fn enable_selection(&mut self) -> Result<(), String> {
+		if self.selection_input.is_some() {
+			return Ok(());
+		}
+
+		let canvas = self.renderer.surface().element().ok_or("Selection needs a canvas element")?.clone();
+		let mut input = CanvasListeners { canvas: canvas.clone(), listeners: Vec::new() };
+
+		for name in ["pointerdown", "pointermove", "pointerup", "pointercancel"] {
+			let canvas_ref = canvas.clone();
+			let scene = self.scene.clone();
+			let selection = self.selection.clone();
+
+			let closure = Closure::<dyn FnMut(Event)>::new(move |event: Event| {
+				let Some(event) = event.dyn_ref::<PointerEvent>() else { return };
+				let (x, y) = (event.offset_x() as f32, event.offset_y() as f32);
+				let (width, height) = (canvas_ref.client_width() as f32, canvas_ref.client_height() as f32);
+				let mut scene = scene.borrow_mut();
+				let mut selection = selection.borrow_mut();
+
+				match name {
+					"pointerdown" => {
+						if selection.pointer_down(&scene, x, y, width, height) {
+							// Keep camera controls from also reacting to the drag
+							let _ = canvas_ref.set_pointer_capture(event.pointer_id());
+							event.stop_immediate_propagation();
+						}
+					}
+					"pointermove" => {
+						selection.pointer_move(&mut scene, x, y, width, height);
+					}
+					"pointerup" => {
+						let additive = event.shift_key() || event.ctrl_key() || event.meta_key();
+						selection.pointer_up(&mut scene, x, y, width, height, additive);
+					}
+					_ => selection.cancel(&mut scene),
+				}
+			});
+
+			canvas
+				.add_event_listener_with_callback(name, closure.as_ref().unchecked_ref())
+				.map_err(|e| format!("Failed to add {} listener: {:?}", name, e))?;
+
+			input.listeners.push((name, closure));
+		}
+
+		self.selection_input = Some(input);
+		Ok(())
 	}
 
 	pub fn set_debug(&self, enabled: bool) {
@@ -178,7 +526,8 @@ impl App {
 	/// Starts the render loop with the provided update callback.
 	///
 	/// The callback is called every frame with mutable access to the scene
-	/// and the elapsed time in seconds since the application started.
+	/// and the elapsed time in seconds since the application started, as
+	/// measured by [`time_scale`](Self::time_scale).
 	///
 	/// This method consumes the `App` and runs indefinitely.
 	///
@@ -190,6 +539,16 @@ impl App {
 	///			obj.transform.rotation = Quat::from_rotation_y(time);
 	///		}
 	/// });
+	///
+	/// // Freeze animations, shaders and particles on a key press
+	/// let time_scale = app.time_scale.clone();
+	/// let on_key = Closure::<dyn FnMut(KeyboardEvent)>::new(move |event: KeyboardEvent| {
+	///		match event.key().as_str() {
+	///			"p" => time_scale.toggle_paused(),
+	///			"." => time_scale.step(),
+	///			_ => {}
+	///		}
+	/// });
 	/// ```
 	pub fn run<F>(self, mut update: F) -> Animator
 	where
@@ -199,8 +558,14 @@ impl App {
 		let renderer = self.renderer;
 		let gizmos = self.gizmos;
 		let debug = self.debug;
+		let css3d = self.css3d;
+		let time_scale = self.time_scale;
+		let clock = time_scale.clone();
+		let selection = self.selection;
+		let selection_input = self.selection_input;
+		let mut stats_overlay: Option<StatsOverlay> = None;
 
-		Animator::start(move |time| {
+		Animator::start_scaled(time_scale, move |time| {
 			//renderer.clear();
 
 			{
@@ -214,7 +579,162 @@ impl App {
 
 				let settings = debug.borrow();
 				scene.render_debug(&renderer, &gizmos, &settings, false);
+
+				if selection_input.is_some() {
+					let mut selection = selection.borrow_mut();
+					selection.prune(&scene);
+					selection.draw(&renderer.gl, &gizmos, &scene);
+				}
+
+				if let Some(css3d) = css3d.borrow_mut().as_mut() {
+					css3d.sync(&renderer.gl, &scene.camera, renderer.surface().width(), renderer.surface().height());
+				}
+
+				if settings.show_stats && stats_overlay.is_none() && let Some(canvas) = renderer.surface().element() {
+					match StatsOverlay::new(canvas) {
+						Ok(overlay) => stats_overlay = Some(overlay),
+						Err(e) => log::error!("{}", e),
+					}
+				}
+
+				if let Some(overlay) = &mut stats_overlay {
+					overlay.set_visible(settings.show_stats);
+					overlay.update(scene.stats(), clock.real_time());
+				}
+			}
+		})
+	}
+}
+
+/// A canvas shown by a [`MultiApp`], with its own renderer and scene.
+pub struct AppView {
+	pub renderer: Rc<Renderer>,
+	pub instance: Rc<RefCell<SceneInstance>>,
+}
+
+/// Application wrapper showing one [`SceneDescription`] in several canvases.
+///
+/// Each canvas has its own WebGL context, so every [view](AppView) builds
+/// its own copy of the scene and keeps it in sync with the shared
+/// description. Views follow the description's camera unless given their
+/// own, and can change their scene directly for view-specific extras.
+///
+/// ## Examples
+///
+/// ```ignore
+/// use oxgl::MultiApp;
+/// use oxgl::renderer_3d::MaterialDescription;
+///
+/// let mut app = MultiApp::new();
+/// app.add_view("main-canvas");
+///
+/// // A top-down minimap with its own camera
+/// let minimap_camera = Camera::new(1.0)
+///		.with_position(Vec3::new(0.0, 30.0, 0.01))
+///		.with_target(Vec3::ZERO);
+/// let minimap = app.add_view_with_camera("minimap-canvas", minimap_camera);
+/// let minimap = app.views[minimap].instance.clone();
+///
+/// let player = app.description.borrow_mut().add(
+///		Rc::new(Primitive::Cube.with_face_colors()),
+///		MaterialDescription::Phong(Vec3::ONE),
+///		Transform3D::new(),
+/// );
+///
+/// app.run(move |description, time| {
+///		description.objects[player].transform.position.x = time.sin() * 3.0;
+///		minimap.borrow_mut().scene.camera.target = description.objects[player].transform.position;
+/// });
+/// ```
+pub struct MultiApp {
+	pub description: Rc<RefCell<SceneDescription>>,
+	pub views: Vec<AppView>,
+}
+
+impl Default for MultiApp {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl MultiApp {
+	/// Creates an application with an empty description and no views.
+	pub fn new() -> Self {
+		let camera = Camera::new(1.0)
+			.with_position(Vec3::new(0.0, 2.0, 5.0))
+			.with_target(Vec3::ZERO);
+
+		Self {
+			description: Rc::new(RefCell::new(SceneDescription::new(camera))),
+			views: Vec::new(),
+		}
+	}
+
+	/// Adds a view of the canvas with the given id, following the
+	/// description's camera, and returns its index in [`views`](Self::views).
+	///
+	/// ## Panics
+	///
+	/// Panics if the canvas element is not found or WebGL2 initialization fails.
+	pub fn add_view(&mut self, canvas_id: &str) -> usize {
+		let renderer = Rc::new(Renderer::new(canvas_id));
+		let aspect = renderer.surface().width() as f32 / renderer.surface().height() as f32;
+		let camera = Camera {
+			aspect,
+			..self.description.borrow().camera.clone()
+		};
+
+		self.push_view(renderer, SceneInstance::new(camera))
+	}
+
+	/// Adds a view of the canvas with the given id, seen from its own
+	/// camera, and returns its index in [`views`](Self::views).
+	///
+	/// ## Panics
+	///
+	/// Panics if the canvas element is not found or WebGL2 initialization fails.
+	pub fn add_view_with_camera(&mut self, canvas_id: &str, camera: Camera) -> usize {
+		let renderer = Rc::new(Renderer::new(canvas_id));
+		let mut instance = SceneInstance::new(camera);
+		instance.follow_camera = false;
+
+		self.push_view(renderer, instance)
+	}
+
+	fn push_view(&mut self, renderer: Rc<Renderer>, instance: SceneInstance) -> usize {
+		self.views.push(AppView {
+			renderer,
+			instance: Rc::new(RefCell::new(instance)),
+		});
+		self.views.len() - 1
+	}
+
+	/// Starts the render loop with the provided update callback.
+	///
+	/// The callback is called every frame with mutable access to the shared
+	/// description and the elapsed time in seconds, after which every view
+	/// syncs its scene and renders it.
+	pub fn run<F>(self, mut update: F) -> Animator
+	where
+		F: FnMut(&mut SceneDescription, f32) + 'static,
+	{
+		let description = self.description;
+		let views = self.views;
+
+		Animator::start(move |time| {
+			update(&mut description.borrow_mut(), time);
+
+			let description = description.borrow();
+
+			for view in &views {
+				let mut instance = view.instance.borrow_mut();
+
+				if let Err(e) = instance.sync(&view.renderer.gl, &description) {
+					log::error!("{}", e);
+				}
+
+				instance.scene.render(&view.renderer, time);
 			}
 		})
 	}
-}
\ No newline at end of file
+}
@@ -0,0 +1,55 @@
+precision highp float;
+
+uniform sampler2D screenTexture;
+// A 3D LUT laid out as a strip of size x size slices, with blue selecting
+// the slice, red increasing to the right and green downwards
+uniform sampler2D lut;
+uniform vec2 lutSize;
+uniform float lutIntensity;
+
+uniform vec3 lift;
+uniform vec3 gamma;
+uniform vec3 gain;
+uniform float saturation;
+uniform float contrast;
+
+varying vec2 vUv;
+
+vec3 sampleLut(vec3 color) {
+	float size = lutSize.y;
+	float maxIndex = size - 1.0;
+
+	color = clamp(color, 0.0, 1.0);
+
+	float blue = color.b * maxIndex;
+	float slice = floor(blue);
+	float nextSlice = min(slice + 1.0, maxIndex);
+
+	float x = (color.r * maxIndex + 0.5) / (size * size);
+	float y = (color.g * maxIndex + 0.5) / size;
+
+	vec3 low = texture2D(lut, vec2(x + slice / size, y)).rgb;
+	vec3 high = texture2D(lut, vec2(x + nextSlice / size, y)).rgb;
+
+	return mix(low, high, blue - slice);
+}
+
+void main() {
+	vec4 color = texture2D(screenTexture, vUv);
+	vec3 result = color.rgb;
+
+	// Lift raises shadows, gain scales highlights, gamma bends the midtones
+	result = gain * (result + lift * (1.0 - result));
+	result = pow(max(result, 0.0), 1.0 / max(gamma, vec3(1e-3)));
+
+	float luma = dot(result, vec3(0.2126, 0.7152, 0.0722));
+	result = mix(vec3(luma), result, saturation);
+	result = (result - 0.5) * contrast + 0.5;
+
+	// A LUT still loading is a 1x1 placeholder
+	if (lutSize.y >= 2.0) {
+		result = mix(result, sampleLut(result), lutIntensity);
+	}
+
+	gl_FragColor = vec4(clamp(result, 0.0, 1.0), color.a);
+}
@@ -0,0 +1,16 @@
+precision highp float;
+
+uniform sampler2D screenTexture;
+// Offsets drawn by distorting objects: xy from 0 to 1 around 0.5, and
+// their weight in alpha
+uniform sampler2D distortionTexture;
+uniform float strength;
+
+varying vec2 vUv;
+
+void main() {
+	vec4 mask = texture2D(distortionTexture, vUv);
+	vec2 offset = (mask.rg * 2.0 - 1.0) * mask.a * strength;
+
+	gl_FragColor = texture2D(screenTexture, clamp(vUv + offset, 0.0, 1.0));
+}
@@ -0,0 +1,57 @@
+precision highp float;
+
+#include <logdepth>
+
+uniform sampler2D screenTexture;
+uniform sampler2D depthTexture;
+uniform vec2 depthProjection;
+
+// Set every frame by PostProcessStack::update_lights
+uniform vec2 lightPosition;
+uniform float lightDepth;
+uniform vec3 lightColor;
+uniform float lightVisibility;
+
+uniform float density;
+uniform float decay;
+uniform float weight;
+uniform float exposure;
+uniform int samples;
+
+varying vec2 vUv;
+
+const int MAX_SAMPLES = 128;
+
+// Color at a screen coordinate if it lies behind the light, black where
+// something in front of the light blocks it
+vec3 lightSource(vec2 uv) {
+	float depth = texture2D(depthTexture, uv).r;
+	bool behind = depth >= 1.0 || standardDepth(depth, depthProjection) >= lightDepth;
+
+	return behind ? texture2D(screenTexture, uv).rgb : vec3(0.0);
+}
+
+void main() {
+	vec4 screen = texture2D(screenTexture, vUv);
+
+	if (lightVisibility <= 0.0) {
+		gl_FragColor = screen;
+		return;
+	}
+
+	// March from the pixel towards the light, each sample counting less
+	vec2 delta = (vUv - lightPosition) * density / float(samples);
+	vec2 uv = vUv;
+	float illumination = 1.0;
+	vec3 rays = vec3(0.0);
+
+	for (int i = 0; i < MAX_SAMPLES; i++) {
+		if (i >= samples) break;
+
+		uv -= delta;
+		rays += lightSource(clamp(uv, 0.0, 1.0)) * illumination * weight;
+		illumination *= decay;
+	}
+
+	gl_FragColor = vec4(screen.rgb + rays * lightColor * exposure * lightVisibility, screen.a);
+}
@@ -0,0 +1,66 @@
+precision highp float;
+
+#include <depth_buffer>
+
+uniform sampler2D screenTexture;
+
+uniform float thickness;
+uniform vec3 color;
+uniform float opacity;
+// Relative depth change that counts as an edge
+uniform float depthThreshold;
+// Normal change that counts as an edge, from 0 to 2
+uniform float normalThreshold;
+
+varying vec2 vUv;
+
+const float FAR_DEPTH = 1.0e6;
+
+// Distance from the camera, far away for the background so silhouettes
+// against it are found
+float linearDepth(vec2 uv) {
+	if (texture2D(depthTexture, uv).r >= 1.0) return FAR_DEPTH;
+
+	return -viewPosition(uv).z;
+}
+
+void main() {
+	vec4 screen = texture2D(screenTexture, vUv);
+	vec2 offset = thickness / resolution;
+
+	// Sobel kernels, sampled in a 3x3 grid around the pixel
+	float depthX = 0.0;
+	float depthY = 0.0;
+	vec3 normalX = vec3(0.0);
+	vec3 normalY = vec3(0.0);
+
+	for (int y = -1; y <= 1; y++) {
+		for (int x = -1; x <= 1; x++) {
+			if (x == 0 && y == 0) continue;
+
+			vec2 uv = vUv + vec2(float(x), float(y)) * offset;
+			float weightX = float(x) * (y == 0 ? 2.0 : 1.0);
+			float weightY = float(y) * (x == 0 ? 2.0 : 1.0);
+
+			float depth = linearDepth(uv);
+			vec3 normal = viewNormal(uv);
+
+			depthX += depth * weightX;
+			depthY += depth * weightY;
+			normalX += normal * weightX;
+			normalY += normal * weightY;
+		}
+	}
+
+	// Relative to the distance so edges far away aren't thicker
+	float center = min(linearDepth(vUv), FAR_DEPTH);
+	float depthEdge = length(vec2(depthX, depthY)) / max(center, 1.0e-4);
+	float normalEdge = sqrt(dot(normalX, normalX) + dot(normalY, normalY));
+
+	float edge = max(
+		step(depthThreshold, depthEdge),
+		step(normalThreshold, normalEdge)
+	);
+
+	gl_FragColor = vec4(mix(screen.rgb, color, edge * opacity), screen.a);
+}
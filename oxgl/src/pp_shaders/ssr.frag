@@ -0,0 +1,114 @@
+precision highp float;
+
+#include <depth_buffer>
+
+uniform sampler2D screenTexture;
+uniform sampler2D environment;
+
+uniform mat4 projection;
+uniform mat4 inverseView;
+
+uniform float intensity;
+uniform float maxDistance;
+uniform int steps;
+uniform float thickness;
+// Surfaces whose world normal points up less than this aren't reflective
+uniform float minNormalY;
+uniform int hasEnvironment;
+uniform float environmentYaw;
+
+varying vec2 vUv;
+
+const int MAX_STEPS = 128;
+const int REFINE_STEPS = 5;
+const float PI = 3.14159265359;
+
+vec2 projectToUv(vec3 position) {
+	vec4 clip = projection * vec4(position, 1.0);
+
+	return clip.xy / clip.w * 0.5 + 0.5;
+}
+
+vec3 sampleEnvironment(vec3 direction) {
+	float u = atan(direction.x, -direction.z) / (2.0 * PI) + 0.5 + environmentYaw / (2.0 * PI);
+	float v = acos(clamp(direction.y, -1.0, 1.0)) / PI;
+
+	return texture2D(environment, vec2(fract(u), v)).rgb;
+}
+
+void main() {
+	vec4 color = texture2D(screenTexture, vUv);
+
+	if (texture2D(depthTexture, vUv).r >= 1.0) {
+		gl_FragColor = color;
+		return;
+	}
+
+	vec3 position = viewPosition(vUv);
+	vec3 normal = viewNormal(vUv);
+	vec3 worldNormal = mat3(inverseView) * normal;
+
+	if (worldNormal.y < minNormalY) {
+		gl_FragColor = color;
+		return;
+	}
+
+	vec3 viewDir = normalize(position);
+	vec3 reflected = normalize(reflect(viewDir, normal));
+	float fresnel = 0.04 + 0.96 * pow(1.0 - max(dot(-viewDir, normal), 0.0), 5.0);
+
+	float stepSize = maxDistance / float(steps);
+	vec3 previous = position;
+	vec3 reflection = vec3(0.0);
+	float weight = 0.0;
+
+	for (int i = 1; i <= MAX_STEPS; i++) {
+		if (i > steps) break;
+
+		vec3 current = position + reflected * stepSize * float(i);
+		vec2 uv = projectToUv(current);
+
+		if (uv.x < 0.0 || uv.x > 1.0 || uv.y < 0.0 || uv.y > 1.0 || current.z > 0.0) break;
+
+		// View-space z is negative, so the ray is behind the surface when
+		// the surface is closer to the camera
+		float behind = viewPosition(uv).z - current.z;
+
+		if (behind > 0.0 && behind < thickness) {
+			// Binary search between the last two samples for the crossing
+			vec3 low = previous;
+			vec3 high = current;
+
+			for (int j = 0; j < REFINE_STEPS; j++) {
+				vec3 middle = (low + high) * 0.5;
+
+				if (viewPosition(projectToUv(middle)).z - middle.z > 0.0) {
+					high = middle;
+				} else {
+					low = middle;
+				}
+			}
+
+			uv = projectToUv(high);
+
+			// Fade towards the screen edges, where rays run out of data
+			vec2 edge = smoothstep(0.0, 0.1, uv) * (1.0 - smoothstep(0.9, 1.0, uv));
+			float travelled = 1.0 - float(i) / float(steps);
+
+			weight = edge.x * edge.y * travelled;
+			reflection = texture2D(screenTexture, uv).rgb;
+			break;
+		}
+
+		previous = current;
+	}
+
+	if (hasEnvironment != 0 && weight < 1.0) {
+		vec3 sky = sampleEnvironment(normalize(mat3(inverseView) * reflected));
+
+		reflection = mix(sky, reflection, weight);
+		weight = 1.0;
+	}
+
+	gl_FragColor = vec4(mix(color.rgb, reflection, intensity * fresnel * weight), color.a);
+}
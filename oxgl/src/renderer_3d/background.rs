@@ -0,0 +1,273 @@
+//! Scene Background
+//!
+//! Provides [`Background`], what the [`Scene`](super::Scene) clears to
+//! before drawing: a solid color, nothing at all for canvases layered over
+//! page content, or a [`Skybox`] image or cube map surrounding the camera.
+//!
+//! Transparent canvases are composited by the browser with premultiplied
+//! alpha, so the scene clears to `(0, 0, 0, 0)` and built-in blended
+//! renderers accumulate alpha with `ONE, ONE_MINUS_SRC_ALPHA` to keep the
+//! output premultiplied.
+//!
+//! ## Examples
+//!
+//! ```ignore
+//! use oxgl::renderer_3d::{Background, Skybox};
+//! use oxgl::core::Color;
+//!
+//! scene.set_background(Background::Color(Color::rgb(135, 206, 235)));
+//!
+//! // Show the page behind the canvas
+//! scene.set_background(Background::Transparent);
+//!
+//! // An equirectangular sky image
+//! let sky = Skybox::new(&gl, Texture::load(&gl, "assets/sky.jpg")?)?;
+//! scene.set_background(Background::Skybox(sky));
+//!
+//! // Six cube faces, which reflective materials can share
+//! let faces = CubeMap::load(&gl, ["px.jpg", "nx.jpg", "py.jpg", "ny.jpg", "pz.jpg", "nz.jpg"])?;
+//! let sky = Skybox::from_cube_map(&gl, faces)?;
+//! ```
+//!
+
+use web_sys::{WebGlBuffer, WebGlProgram, WebGl2RenderingContext as GL};
+
+use crate::{
+	common::{Camera, CubeMap, Texture, compile_shader, link_program, render_state::current_depth_func},
+	core::Color,
+};
+
+const SKYBOX_VERT: &str = include_str!("../shaders/panorama.vert");
+const SKYBOX_FRAG: &str = include_str!("../shaders/panorama.frag");
+
+/// What the scene shows behind its objects.
+pub enum Background {
+	/// A solid color.
+	Color(Color),
+	/// Fully transparent, showing whatever is behind the canvas.
+	Transparent,
+	/// An image around the camera.
+	Skybox(Skybox),
+}
+
+impl Default for Background {
+	fn default() -> Self {
+		Self::Color(Color::Rgb(26, 26, 26))
+	}
+}
+
+impl Background {
+	/// Returns the premultiplied RGBA color the canvas is cleared to.
+	///
+	/// Skyboxes cover the whole view, so they clear to opaque black.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use oxgl::renderer_3d::Background;
+	/// use oxgl::core::Color;
+	///
+	/// assert_eq!(Background::Transparent.clear_color(), [0.0; 4]);
+	///
+	/// // Translucent colors are premultiplied for the browser compositor
+	/// let [r, _, _, a] = Background::Color(Color::Rgba(255, 0, 0, 51)).clear_color();
+	/// assert!((r - 0.2).abs() < 1e-6 && (a - 0.2).abs() < 1e-6);
+	/// ```
+	pub fn clear_color(&self) -> [f32; 4] {
+		match self {
+			Self::Color(color) => {
+				let color = color.to_vec4();
+				[color.x * color.w, color.y * color.w, color.z * color.w, color.w]
+			}
+			Self::Transparent => [0.0; 4],
+			Self::Skybox(_) => [0.0, 0.0, 0.0, 1.0],
+		}
+	}
+
+	/// Returns whether the canvas shows content behind it.
+	pub fn is_transparent(&self) -> bool {
+		match self {
+			Self::Color(color) => color.to_vec4().w < 1.0,
+			Self::Transparent => true,
+			Self::Skybox(_) => false,
+		}
+	}
+}
+
+/// An equirectangular image or cube map drawn on a sphere around the camera.
+///
+/// The image center faces -Z. Unlike a [`Panorama`](super::Panorama), a
+/// skybox doesn't move the camera.
+pub struct Skybox {
+	program: WebGlProgram,
+	sphere_buffer: WebGlBuffer,
+	vertex_count: i32,
+	texture: Texture,
+	/// Shown instead of the texture when set.
+	cube_map: Option<CubeMap>,
+	/// Rotation around the Y axis, in radians.
+	pub yaw: f32,
+}
+
+impl Skybox {
+	/// Creates a skybox showing the equirectangular `texture`.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the shaders fail to compile or the sphere buffer
+	/// can't be created.
+	pub fn new(gl: &GL, texture: Texture) -> Result<Self, String> {
+		Self::with_images(gl, texture, None)
+	}
+
+	/// Creates a skybox showing a cube map.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the shaders fail to compile or the sphere buffer
+	/// can't be created.
+	pub fn from_cube_map(gl: &GL, cube_map: CubeMap) -> Result<Self, String> {
+		let placeholder = Texture::from_pixels(gl, 1, 1, &[128, 128, 128, 255])?;
+		Self::with_images(gl, placeholder, Some(cube_map))
+	}
+
+	fn with_images(gl: &GL, texture: Texture, cube_map: Option<CubeMap>) -> Result<Self, String> {
+		let vert = compile_shader(gl, SKYBOX_VERT, GL::VERTEX_SHADER)?;
+		let frag = compile_shader(gl, SKYBOX_FRAG, GL::FRAGMENT_SHADER)?;
+		let program = link_program(gl, &vert, &frag)?;
+
+		let vertices = sphere_vertices(32, 16);
+		let sphere_buffer = gl.create_buffer().ok_or("Failed to create skybox buffer")?;
+
+		gl.bind_buffer(GL::ARRAY_BUFFER, Some(&sphere_buffer));
+
+		let data = unsafe {
+			std::slice::from_raw_parts(
+				vertices.as_ptr() as *const u8,
+				vertices.len() * std::mem::size_of::<f32>(),
+			)
+		};
+
+		gl.buffer_data_with_u8_array(GL::ARRAY_BUFFER, data, GL::STATIC_DRAW);
+
+		Ok(Self {
+			program,
+			sphere_buffer,
+			vertex_count: (vertices.len() / 3) as i32,
+			texture,
+			cube_map,
+			yaw: 0.0,
+		})
+	}
+
+	/// Returns the equirectangular texture, a 1x1 grey placeholder for
+	/// skyboxes created [from a cube map](Self::from_cube_map).
+	pub fn texture(&self) -> &Texture {
+		&self.texture
+	}
+
+	/// Shows an equirectangular texture, replacing the cube map if one was
+	/// shown.
+	pub fn set_texture(&mut self, texture: Texture) {
+		self.texture = texture;
+		self.cube_map = None;
+	}
+
+	/// Returns the cube map, or `None` when showing an equirectangular
+	/// texture. Share it with [`presets::reflective`](crate::common::presets::reflective)
+	/// materials to reflect the sky.
+	pub fn cube_map(&self) -> Option<&CubeMap> {
+		self.cube_map.as_ref()
+	}
+
+	pub fn set_cube_map(&mut self, cube_map: CubeMap) {
+		self.cube_map = Some(cube_map);
+	}
+
+	/// Draws the sphere at the far plane, behind everything else.
+	///
+	/// Doesn't write depth, so it can be drawn before the scene.
+	pub fn draw(&self, gl: &GL, camera: &Camera) {
+		gl.use_program(Some(&self.program));
+
+		if let Some(loc) = gl.get_uniform_location(&self.program, "view") {
+			gl.uniform_matrix4fv_with_f32_array(Some(&loc), false, &camera.view_matrix().to_cols_array());
+		}
+		if let Some(loc) = gl.get_uniform_location(&self.program, "projection") {
+			gl.uniform_matrix4fv_with_f32_array(Some(&loc), false, &camera.projection_matrix().to_cols_array());
+		}
+		if let Some(loc) = gl.get_uniform_location(&self.program, "panorama") {
+			gl.uniform1i(Some(&loc), 0);
+		}
+		if let Some(loc) = gl.get_uniform_location(&self.program, "cubeMap") {
+			gl.uniform1i(Some(&loc), 1);
+		}
+		if let Some(loc) = gl.get_uniform_location(&self.program, "useCubeMap") {
+			gl.uniform1i(Some(&loc), self.cube_map.is_some() as i32);
+		}
+		if let Some(loc) = gl.get_uniform_location(&self.program, "yawOffset") {
+			gl.uniform1f(Some(&loc), self.yaw);
+		}
+
+		// Each sampler type gets its own unit, as WebGL rejects a 2D and a
+		// cube sampler sharing one
+		match &self.cube_map {
+			Some(cube_map) => cube_map.bind(gl, 1),
+			None => self.texture.bind(gl, 0),
+		}
+
+		gl.bind_buffer(GL::ARRAY_BUFFER, Some(&self.sphere_buffer));
+
+		let pos_loc = gl.get_attrib_location(&self.program, "position");
+
+		if pos_loc >= 0 {
+			gl.enable_vertex_attrib_array(pos_loc as u32);
+			gl.vertex_attrib_pointer_with_i32(pos_loc as u32, 3, GL::FLOAT, false, 0, 0);
+		}
+
+		let depth_func = current_depth_func(gl);
+
+		gl.depth_mask(false);
+		gl.depth_func(GL::LEQUAL);
+		gl.draw_arrays(GL::TRIANGLES, 0, self.vertex_count);
+		gl.depth_func(depth_func);
+		gl.depth_mask(true);
+	}
+}
+
+/// Sets blending for straight-alpha colors over a premultiplied canvas.
+///
+/// Color is blended as usual, while alpha accumulates coverage so
+/// translucent pixels stay correct over a transparent background.
+pub(crate) fn blend_over(gl: &GL) {
+	gl.enable(GL::BLEND);
+	gl.blend_func_separate(GL::SRC_ALPHA, GL::ONE_MINUS_SRC_ALPHA, GL::ONE, GL::ONE_MINUS_SRC_ALPHA);
+}
+
+/// Generates a unit UV sphere as a triangle list.
+fn sphere_vertices(segments: u32, rings: u32) -> Vec<f32> {
+	use std::f32::consts::{PI, TAU};
+
+	let point = |ring: u32, segment: u32| {
+		let theta = ring as f32 / rings as f32 * PI;
+		let phi = segment as f32 / segments as f32 * TAU;
+		[theta.sin() * phi.cos(), theta.cos(), theta.sin() * phi.sin()]
+	};
+
+	let mut vertices = Vec::with_capacity((segments * rings * 18) as usize);
+
+	for ring in 0..rings {
+		for segment in 0..segments {
+			let a = point(ring, segment);
+			let b = point(ring + 1, segment);
+			let c = point(ring + 1, segment + 1);
+			let d = point(ring, segment + 1);
+
+			for p in [a, c, b, a, d, c] {
+				vertices.extend_from_slice(&p);
+			}
+		}
+	}
+
+	vertices
+}
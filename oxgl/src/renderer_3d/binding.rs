@@ -0,0 +1,154 @@
+//! Value Bindings
+//!
+//! Connects per-frame value sources to material uniforms, post-process
+//! uniforms, and light parameters. Bindings are applied automatically at the
+//! start of [`Scene::render`](super::Scene::render).
+//!
+//! ## Examples
+//!
+//! ```ignore
+//! use oxgl::renderer_3d::BindingTarget;
+//!
+//! // Any closure returning f32 can be a source
+//! let start = js_sys::Date::now();
+//! scene.bindings.add(
+//!		move || ((js_sys::Date::now() - start) / 1000.0).sin() as f32,
+//!		BindingTarget::Uniform { object: cube_id, name: "ambient".into() },
+//! );
+//! ```
+//!
+
+use slotmap::SlotMap;
+
+use crate::{
+	common::PostProcessStack,
+	core::{BindingId, LightId, ObjectId, audio::AudioSource},
+};
+use super::{Light, SceneObject};
+
+/// Produces a value once per frame.
+pub trait BindingSource {
+	fn sample(&mut self) -> f32;
+}
+
+impl<F: FnMut() -> f32> BindingSource for F {
+	fn sample(&mut self) -> f32 {
+		self()
+	}
+}
+
+impl BindingSource for AudioSource {
+	fn sample(&mut self) -> f32 {
+		AudioSource::sample(self)
+	}
+}
+
+/// The scene parameter a binding writes to.
+#[derive(Clone, Debug, PartialEq)]
+pub enum BindingTarget {
+	/// A float uniform on an object's material.
+	Uniform { object: ObjectId, name: String },
+	/// A float uniform on a post-process effect, by stack index.
+	PostProcessUniform { effect: usize, name: String },
+	/// The intensity of a light.
+	LightIntensity(LightId),
+}
+
+/// A source connected to a target, with a linear mapping.
+///
+/// The written value is `source * scale + offset`.
+pub struct Binding {
+	source: Box<dyn BindingSource>,
+	pub target: BindingTarget,
+	pub scale: f32,
+	pub offset: f32,
+}
+
+/// The set of active bindings of a scene.
+#[derive(Default)]
+pub struct Bindings {
+	entries: SlotMap<BindingId, Binding>,
+}
+
+impl Bindings {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Binds a source directly to a target.
+	pub fn add<S>(&mut self, source: S, target: BindingTarget) -> BindingId
+	where
+		S: BindingSource + 'static,
+	{
+		self.add_mapped(source, target, 1.0, 0.0)
+	}
+
+	/// Binds a source to a target, writing `source * scale + offset`.
+	pub fn add_mapped<S>(&mut self, source: S, target: BindingTarget, scale: f32, offset: f32) -> BindingId
+	where
+		S: BindingSource + 'static,
+	{
+		self.entries.insert(Binding {
+			source: Box::new(source),
+			target,
+			scale,
+			offset,
+		})
+	}
+
+	pub fn remove(&mut self, id: BindingId) -> bool {
+		self.entries.remove(id).is_some()
+	}
+
+	pub fn get_mut(&mut self, id: BindingId) -> Option<&mut Binding> {
+		self.entries.get_mut(id)
+	}
+
+	pub fn clear(&mut self) {
+		self.entries.clear();
+	}
+
+	pub fn len(&self) -> usize {
+		self.entries.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.entries.is_empty()
+	}
+
+	/// Samples every source and writes the results to their targets.
+	///
+	/// Bindings whose target no longer exists are skipped, without sampling
+	/// their source.
+	pub fn apply(
+		&mut self,
+		objects: &mut SlotMap<ObjectId, SceneObject>,
+		lights: &mut SlotMap<LightId, Light>,
+		post_process: Option<&mut PostProcessStack>,
+	) {
+		let mut post_process = post_process;
+
+		for binding in self.entries.values_mut() {
+			let Binding { source, target, scale, offset } = binding;
+			let mut value = || source.sample() * *scale + *offset;
+
+			match target {
+				BindingTarget::Uniform { object, name } => {
+					if let Some(obj) = objects.get_mut(*object) {
+						obj.mesh.material.set_float(name, value());
+					}
+				}
+				BindingTarget::PostProcessUniform { effect, name } => {
+					if let Some(effect) = post_process.as_deref_mut().and_then(|pp| pp.get_mut(*effect)) {
+						effect.set_float(name, value());
+					}
+				}
+				BindingTarget::LightIntensity(id) => {
+					if let Some(light) = lights.get_mut(*id) {
+						light.intensity = value();
+					}
+				}
+			}
+		}
+	}
+}
@@ -0,0 +1,434 @@
+//! Cloth Simulation
+//!
+//! Provides [`Cloth`], a grid of particles simulated with Verlet integration
+//! on the CPU, for flags, capes and curtains. Particles can be pinned in
+//! place, are pushed by gravity and wind, and collide with spheres and
+//! planes. After each [`step`](Cloth::step), the mesh is updated in place
+//! with [`Mesh::update_vertices`].
+//!
+//! Particles are simulated in world space, so add the mesh with an identity
+//! transform and move pinned particles to attach the cloth to moving
+//! objects.
+//!
+//! ## Examples
+//!
+//! ```ignore
+//! use oxgl::renderer_3d::{Cloth, ClothCollider};
+//!
+//! // A flag hanging from its left edge
+//! let mut flag = Cloth::new(3.0, 2.0, 30, 20)
+//!		.with_origin(Vec3::new(0.0, 4.0, 0.0))
+//!		.with_wind(Vec3::new(6.0, 0.0, 1.0));
+//! flag.pin_left_edge();
+//! flag.colliders.push(ClothCollider::Sphere { center: Vec3::new(1.0, 3.0, 0.5), radius: 0.5 });
+//!
+//! let mut material = presets::lambert(&gl, Vec3::new(0.8, 0.1, 0.1));
+//! material.double_sided = true;
+//! let id = scene.add(flag.build(&gl, material), Transform3D::new());
+//!
+//! app.run(move |scene, time| {
+//!		flag.wind.z = (time * 1.3).sin() * 2.0;
+//!		flag.step(1.0 / 60.0);
+//!
+//!		if let Some(obj) = scene.get_mut(id) {
+//!			flag.update_mesh(&gl, &mut obj.mesh).ok();
+//!		}
+//! });
+//! ```
+//!
+
+use glam::Vec3;
+use web_sys::WebGl2RenderingContext as GL;
+
+use crate::common::{Material, Mesh, MeshData};
+
+/// Longest time step simulated at once, so frame hitches don't make the
+/// cloth explode.
+const MAX_STEP: f32 = 1.0 / 30.0;
+
+/// A shape cloth particles are kept out of.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ClothCollider {
+	Sphere { center: Vec3, radius: f32 },
+	/// An infinite plane through `point`, with particles kept on the side
+	/// `normal` points to.
+	Plane { point: Vec3, normal: Vec3 },
+}
+
+/// A distance kept between two particles.
+#[derive(Clone, Copy, Debug)]
+struct Constraint {
+	a: usize,
+	b: usize,
+	rest: f32,
+}
+
+/// A rectangular piece of cloth simulated as a grid of particles.
+///
+/// The grid starts flat in the XY plane, with its top edge centered on the
+/// [origin](Self::with_origin) and rows hanging down along -Y.
+///
+/// ## Examples
+///
+/// ```ignore
+/// use oxgl::renderer_3d::Cloth;
+///
+/// let mut curtain = Cloth::new(2.0, 3.0, 20, 30);
+/// curtain.pin_top_edge();
+///
+/// curtain.step(1.0 / 60.0);
+/// assert_eq!(curtain.position(0, 0), Vec3::new(-1.0, 0.0, 0.0));
+/// ```
+#[derive(Clone, Debug)]
+pub struct Cloth {
+	columns: u32,
+	rows: u32,
+	positions: Vec<Vec3>,
+	previous: Vec<Vec3>,
+	pinned: Vec<bool>,
+	constraints: Vec<Constraint>,
+	/// Acceleration applied to every particle, in units per second squared.
+	pub gravity: Vec3,
+	/// Air velocity pushing on the cloth's faces, in units per second.
+	pub wind: Vec3,
+	/// Fraction of velocity lost per step, from 0 to 1.
+	pub damping: f32,
+	/// Constraint passes per step. More passes make the cloth less stretchy.
+	pub iterations: u32,
+	/// Distance particles keep from colliders.
+	pub thickness: f32,
+	pub colliders: Vec<ClothCollider>,
+}
+
+impl Cloth {
+	/// Creates a `width` by `height` cloth with `columns` by `rows` particles.
+	///
+	/// Particles are linked to their direct and diagonal neighbors, and to
+	/// the ones two apart to resist bending.
+	pub fn new(width: f32, height: f32, columns: u32, rows: u32) -> Self {
+		let (columns, rows) = (columns.max(2), rows.max(2));
+		let spacing = (width / (columns - 1) as f32, height / (rows - 1) as f32);
+
+		let positions: Vec<Vec3> = (0..rows)
+			.flat_map(|row| (0..columns).map(move |column| {
+				Vec3::new(column as f32 * spacing.0 - width * 0.5, -(row as f32) * spacing.1, 0.0)
+			}))
+			.collect();
+
+		let mut cloth = Self {
+			columns,
+			rows,
+			previous: positions.clone(),
+			pinned: vec![false; positions.len()],
+			positions,
+			constraints: Vec::new(),
+			gravity: Vec3::new(0.0, -9.81, 0.0),
+			wind: Vec3::ZERO,
+			damping: 0.01,
+			iterations: 8,
+			thickness: 0.01,
+			colliders: Vec::new(),
+		};
+
+		// Structural, shear and bend links, as offsets from a grid cell
+		let links: [((u32, u32), (u32, u32)); 6] = [
+			((0, 0), (1, 0)),
+			((0, 0), (0, 1)),
+			((0, 0), (1, 1)),
+			((1, 0), (0, 1)),
+			((0, 0), (2, 0)),
+			((0, 0), (0, 2)),
+		];
+
+		for row in 0..rows {
+			for column in 0..columns {
+				for (from, to) in links {
+					let (from, to) = ((column + from.0, row + from.1), (column + to.0, row + to.1));
+
+					if from.0 < columns && to.0 < columns && from.1 < rows && to.1 < rows {
+						cloth.link(cloth.index(from.0, from.1), cloth.index(to.0, to.1));
+					}
+				}
+			}
+		}
+
+		cloth
+	}
+
+	/// Moves the whole cloth so its top edge is centered on `origin`.
+	pub fn with_origin(mut self, origin: Vec3) -> Self {
+		for position in self.positions.iter_mut().chain(self.previous.iter_mut()) {
+			*position += origin;
+		}
+		self
+	}
+
+	pub fn with_gravity(mut self, gravity: Vec3) -> Self {
+		self.gravity = gravity;
+		self
+	}
+
+	pub fn with_wind(mut self, wind: Vec3) -> Self {
+		self.wind = wind;
+		self
+	}
+
+	pub fn with_damping(mut self, damping: f32) -> Self {
+		self.damping = damping;
+		self
+	}
+
+	pub fn with_iterations(mut self, iterations: u32) -> Self {
+		self.iterations = iterations;
+		self
+	}
+
+	pub fn with_collider(mut self, collider: ClothCollider) -> Self {
+		self.colliders.push(collider);
+		self
+	}
+
+	pub fn columns(&self) -> u32 {
+		self.columns
+	}
+
+	pub fn rows(&self) -> u32 {
+		self.rows
+	}
+
+	/// Returns the world position of a particle.
+	///
+	/// ## Panics
+	///
+	/// Panics if the particle is outside the grid.
+	pub fn position(&self, column: u32, row: u32) -> Vec3 {
+		self.positions[self.index(column, row)]
+	}
+
+	/// Teleports a particle, without giving it any velocity.
+	///
+	/// Use on pinned particles to carry the cloth along with an object.
+	pub fn set_position(&mut self, column: u32, row: u32, position: Vec3) {
+		let i = self.index(column, row);
+		self.positions[i] = position;
+		self.previous[i] = position;
+	}
+
+	/// Fixes a particle in place, unaffected by forces and constraints.
+	pub fn pin(&mut self, column: u32, row: u32) {
+		let i = self.index(column, row);
+		self.pinned[i] = true;
+	}
+
+	pub fn unpin(&mut self, column: u32, row: u32) {
+		let i = self.index(column, row);
+		self.pinned[i] = false;
+	}
+
+	pub fn is_pinned(&self, column: u32, row: u32) -> bool {
+		self.pinned[self.index(column, row)]
+	}
+
+	/// Pins the first row, for curtains and capes.
+	pub fn pin_top_edge(&mut self) {
+		for column in 0..self.columns {
+			self.pin(column, 0);
+		}
+	}
+
+	/// Pins the first column, for flags.
+	pub fn pin_left_edge(&mut self) {
+		for row in 0..self.rows {
+			self.pin(0, row);
+		}
+	}
+
+	/// Advances the simulation by `dt` seconds.
+	///
+	/// Steps longer than 1/30 s are split up so the cloth stays stable.
+	pub fn step(&mut self, dt: f32) {
+		if dt <= 0.0 {
+			return;
+		}
+
+		let steps = (dt / MAX_STEP).ceil().max(1.0) as u32;
+		let dt = dt / steps as f32;
+
+		for _ in 0..steps {
+			self.integrate(dt);
+
+			for _ in 0..self.iterations {
+				self.satisfy_constraints();
+				self.collide();
+			}
+		}
+	}
+
+	/// Returns the cloth as a triangle list with smooth normals, facing +Z
+	/// at rest.
+	pub fn mesh_data(&self) -> MeshData {
+		let normals = self.normals();
+		let mut data = MeshData {
+			name: Some("Cloth".to_string()),
+			..Default::default()
+		};
+
+		for i in self.triangle_indices() {
+			data.positions.extend_from_slice(&self.positions[i].to_array());
+			data.normals.extend_from_slice(&normals[i].to_array());
+		}
+
+		data
+	}
+
+	/// Creates a mesh of the cloth's current shape.
+	///
+	/// Set [`Material::double_sided`] so the back is lit too.
+	pub fn build(&self, gl: &GL, material: Material) -> Mesh {
+		Mesh::from_data(gl, &self.mesh_data(), material)
+	}
+
+	/// Writes the current shape into a mesh created with [`build`](Self::build).
+	///
+	/// ## Errors
+	///
+	/// Returns an error if the mesh doesn't use the position and normal
+	/// layout of [`build`](Self::build).
+	pub fn update_mesh(&self, gl: &GL, mesh: &mut Mesh) -> Result<(), String> {
+		let normals = self.normals();
+		let mut vertices = Vec::with_capacity(mesh.vertex_count().max(0) as usize * 6);
+
+		for i in self.triangle_indices() {
+			vertices.extend_from_slice(&self.positions[i].to_array());
+			vertices.extend_from_slice(&normals[i].to_array());
+		}
+
+		mesh.update_vertices(gl, &vertices)
+	}
+
+	fn index(&self, column: u32, row: u32) -> usize {
+		(row * self.columns + column) as usize
+	}
+
+	fn link(&mut self, a: usize, b: usize) {
+		let rest = self.positions[a].distance(self.positions[b]);
+		self.constraints.push(Constraint { a, b, rest });
+	}
+
+	/// Returns particle indices, three per triangle, two triangles per cell.
+	fn triangle_indices(&self) -> impl Iterator<Item = usize> + '_ {
+		(0..self.rows - 1).flat_map(move |row| (0..self.columns - 1).flat_map(move |column| {
+			let (a, b) = (self.index(column, row), self.index(column + 1, row));
+			let (c, d) = (self.index(column, row + 1), self.index(column + 1, row + 1));
+			[a, c, b, b, c, d]
+		}))
+	}
+
+	/// Returns area-weighted vertex normals.
+	fn normals(&self) -> Vec<Vec3> {
+		let mut normals = vec![Vec3::ZERO; self.positions.len()];
+		let indices: Vec<usize> = self.triangle_indices().collect();
+
+		for triangle in indices.chunks_exact(3) {
+			let [a, b, c] = [triangle[0], triangle[1], triangle[2]];
+			let normal = (self.positions[b] - self.positions[a]).cross(self.positions[c] - self.positions[a]);
+
+			for i in [a, b, c] {
+				normals[i] += normal;
+			}
+		}
+
+		normals.into_iter().map(|n| n.try_normalize().unwrap_or(Vec3::Z)).collect()
+	}
+
+	/// Moves particles by their velocity, gravity and wind.
+	fn integrate(&mut self, dt: f32) {
+		let mut forces = vec![self.gravity; self.positions.len()];
+
+		if self.wind != Vec3::ZERO {
+			let indices: Vec<usize> = self.triangle_indices().collect();
+
+			for triangle in indices.chunks_exact(3) {
+				let [a, b, c] = [triangle[0], triangle[1], triangle[2]];
+				let cross = (self.positions[b] - self.positions[a]).cross(self.positions[c] - self.positions[a]);
+				let Some(normal) = cross.try_normalize() else { continue };
+
+				// Wind pushes on the part of the face it meets head-on
+				let velocity = (self.positions[a] + self.positions[b] + self.positions[c]
+					- self.previous[a] - self.previous[b] - self.previous[c]) / (3.0 * dt);
+				let force = normal * normal.dot(self.wind - velocity) * cross.length() * 0.5;
+
+				for i in [a, b, c] {
+					forces[i] += force / 3.0;
+				}
+			}
+		}
+
+		let keep = 1.0 - self.damping.clamp(0.0, 1.0);
+
+		let particles = self.positions.iter_mut().zip(&mut self.previous).zip(&self.pinned).zip(forces);
+
+		for (((position, previous), pinned), force) in particles {
+			if *pinned {
+				*previous = *position;
+				continue;
+			}
+
+			let current = *position;
+			*position += (current - *previous) * keep + force * dt * dt;
+			*previous = current;
+		}
+	}
+
+	fn satisfy_constraints(&mut self) {
+		for &Constraint { a, b, rest } in &self.constraints {
+			let weights = (!self.pinned[a] as u8 as f32, !self.pinned[b] as u8 as f32);
+			let total = weights.0 + weights.1;
+
+			if total == 0.0 {
+				continue;
+			}
+
+			let delta = self.positions[b] - self.positions[a];
+			let distance = delta.length();
+
+			if distance <= f32::EPSILON {
+				continue;
+			}
+
+			let correction = delta * ((distance - rest) / distance / total);
+			self.positions[a] += correction * weights.0;
+			self.positions[b] -= correction * weights.1;
+		}
+	}
+
+	fn collide(&mut self) {
+		for collider in &self.colliders {
+			for (position, pinned) in self.positions.iter_mut().zip(&self.pinned) {
+				if *pinned {
+					continue;
+				}
+
+				match *collider {
+					ClothCollider::Sphere { center, radius } => {
+						let offset = *position - center;
+						let distance = offset.length();
+						let min = radius + self.thickness;
+
+						if distance < min {
+							*position = center + offset.try_normalize().unwrap_or(Vec3::Y) * min;
+						}
+					}
+					ClothCollider::Plane { point, normal } => {
+						let normal = normal.normalize_or_zero();
+						let distance = (*position - point).dot(normal);
+
+						if distance < self.thickness {
+							*position += normal * (self.thickness - distance);
+						}
+					}
+				}
+			}
+		}
+	}
+}
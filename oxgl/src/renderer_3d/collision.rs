@@ -0,0 +1,215 @@
+//! Collision Meshes and Queries
+//!
+//! Provides simplified, CPU-side triangle meshes that stand in for render
+//! meshes during picking. Render meshes only live on the GPU, so objects
+//! need a collision mesh to be pickable with [`Scene::raycast`](super::Scene::raycast).
+//!
+//! Dense meshes such as photogrammetry scans can be decimated with
+//! [`CollisionMesh::simplified`] to keep memory and raycast cost low.
+//!
+//! For gameplay logic that doesn't need a physics engine, the scene also
+//! answers overlap and sphere cast queries against objects' world bounds,
+//! see [`Scene::overlapping`](super::Scene::overlapping) and
+//! [`Scene::sphere_cast`](super::Scene::sphere_cast).
+//!
+//! ## Examples
+//!
+//! ```ignore
+//! use oxgl::common::MeshData;
+//! use oxgl::renderer_3d::CollisionMesh;
+//!
+//! let data = &MeshData::from_obj(scan_obj)?[0];
+//!
+//! // Merge vertices closer than 5cm for a much lighter proxy
+//! let proxy = CollisionMesh::from_positions(&data.positions).simplified(0.05);
+//! scene.set_collider(scan_id, proxy);
+//!
+//! if let Some(hit) = scene.pick(mouse_x, mouse_y, width, height) {
+//!		log::info!("Picked {:?} at {}", hit.object, hit.point);
+//! }
+//!
+//! // In an update callback
+//! for other in scene.overlapping(player) {
+//!		log::info!("Player touches {:?}", other);
+//! }
+//!
+//! if let Some(hit) = scene.sphere_cast(position, velocity, 0.5) {
+//!		if hit.distance < velocity.length() * dt { /* about to collide */ }
+//! }
+//! ```
+//!
+
+use std::collections::{HashMap, HashSet};
+use glam::{IVec3, Vec3};
+
+use crate::core::{Aabb, ObjectId, Ray};
+use super::VertexData;
+
+/// The closest object hit by a scene raycast.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RayHit {
+	pub object: ObjectId,
+	/// Distance from the ray origin in world units.
+	pub distance: f32,
+	/// World-space hit position.
+	pub point: Vec3,
+}
+
+/// Returns the distance a sphere travels along a ray before touching a box.
+///
+/// Tests against the box grown by the radius, so hits near edges and
+/// corners are reported slightly early. Returns `0.0` if the sphere starts
+/// overlapping the box.
+///
+/// # Examples
+///
+/// ```
+/// use oxgl::{core::{Aabb, Ray}, renderer_3d::collision::sphere_cast_aabb};
+/// use glam::Vec3;
+///
+/// let wall = Aabb::new(Vec3::new(5.0, -1.0, -1.0), Vec3::new(6.0, 1.0, 1.0));
+/// let ray = Ray::new(Vec3::ZERO, Vec3::X);
+///
+/// assert_eq!(sphere_cast_aabb(&ray, 0.5, &wall), Some(4.5));
+/// assert_eq!(sphere_cast_aabb(&Ray::new(Vec3::ZERO, Vec3::Y), 0.5, &wall), None);
+/// ```
+pub fn sphere_cast_aabb(ray: &Ray, radius: f32, aabb: &Aabb) -> Option<f32> {
+	if aabb.intersects_sphere(ray.origin, radius) {
+		return Some(0.0);
+	}
+
+	let grown = aabb.expanded(radius);
+	ray.intersect_aabb(grown.min, grown.max)
+}
+
+/// An indexed triangle mesh used for intersection tests.
+#[derive(Clone, Debug, Default)]
+pub struct CollisionMesh {
+	positions: Vec<Vec3>,
+	triangles: Vec<[u32; 3]>,
+	bounds: Aabb,
+}
+
+impl CollisionMesh {
+	/// Creates a mesh from indexed triangles.
+	///
+	/// Triangles referencing out-of-range vertices are dropped.
+	pub fn new(positions: Vec<Vec3>, triangles: Vec<[u32; 3]>) -> Self {
+		let count = positions.len() as u32;
+		let triangles = triangles
+			.into_iter()
+			.filter(|t| t.iter().all(|&i| i < count))
+			.collect();
+
+		let bounds = Aabb::from_points(&positions);
+
+		Self { positions, triangles, bounds }
+	}
+
+	/// Creates a mesh from a flat, non-indexed triangle list of positions.
+	///
+	/// This matches the layout of [`MeshData::positions`](crate::common::MeshData).
+	pub fn from_positions(positions: &[f32]) -> Self {
+		Self::from_strided(positions, 3)
+	}
+
+	/// Creates a mesh from interleaved position and normal data.
+	pub fn from_vertex_data(data: &VertexData) -> Self {
+		Self::from_strided(&data.data, 6)
+	}
+
+	fn from_strided(data: &[f32], stride: usize) -> Self {
+		let positions: Vec<Vec3> = data
+			.chunks_exact(stride)
+			.map(|v| Vec3::new(v[0], v[1], v[2]))
+			.collect();
+
+		let triangles = (0..positions.len() as u32 / 3)
+			.map(|t| [t * 3, t * 3 + 1, t * 3 + 2])
+			.collect();
+
+		Self::new(positions, triangles)
+	}
+
+	/// Returns a decimated copy using vertex clustering.
+	///
+	/// Vertices within the same grid cell of size `cell_size` are merged into
+	/// their average, and triangles that collapse are removed. Larger cells
+	/// give coarser, lighter meshes.
+	pub fn simplified(&self, cell_size: f32) -> Self {
+		if cell_size <= 0.0 {
+			return self.clone();
+		}
+
+		let mut cells: HashMap<IVec3, u32> = HashMap::new();
+		let mut sums: Vec<(Vec3, u32)> = Vec::new();
+
+		let remap: Vec<u32> = self.positions
+			.iter()
+			.map(|&p| {
+				let cell = (p / cell_size).floor().as_ivec3();
+				let index = *cells.entry(cell).or_insert_with(|| {
+					sums.push((Vec3::ZERO, 0));
+					sums.len() as u32 - 1
+				});
+
+				sums[index as usize].0 += p;
+				sums[index as usize].1 += 1;
+				index
+			})
+			.collect();
+
+		let positions = sums.iter().map(|&(sum, count)| sum / count as f32).collect();
+
+		let mut seen = HashSet::new();
+		let triangles = self.triangles
+			.iter()
+			.map(|t| t.map(|i| remap[i as usize]))
+			.filter(|[a, b, c]| a != b && b != c && a != c)
+			.filter(|t| {
+				let mut key = *t;
+				key.sort_unstable();
+				seen.insert(key)
+			})
+			.collect();
+
+		Self::new(positions, triangles)
+	}
+
+	pub fn positions(&self) -> &[Vec3] {
+		&self.positions
+	}
+
+	pub fn triangles(&self) -> &[[u32; 3]] {
+		&self.triangles
+	}
+
+	pub fn vertex_count(&self) -> usize {
+		self.positions.len()
+	}
+
+	pub fn triangle_count(&self) -> usize {
+		self.triangles.len()
+	}
+
+	/// Returns the local-space bounding box.
+	pub fn bounds(&self) -> Aabb {
+		self.bounds
+	}
+
+	/// Returns the distance to the closest triangle hit by a local-space ray.
+	pub fn raycast(&self, ray: &Ray) -> Option<f32> {
+		ray.intersect_aabb(self.bounds.min, self.bounds.max)?;
+
+		self.triangles
+			.iter()
+			.filter_map(|&[a, b, c]| {
+				ray.intersect_triangle(
+					self.positions[a as usize],
+					self.positions[b as usize],
+					self.positions[c as usize],
+				)
+			})
+			.min_by(f32::total_cmp)
+	}
+}
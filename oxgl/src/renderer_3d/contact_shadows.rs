@@ -0,0 +1,172 @@
+//! Contact Shadows
+//!
+//! Provides the camera depth buffer used for screen-space contact shadows.
+//! The scene is rendered depth-only from the camera, and lit shaders march
+//! short rays towards each light through this buffer to find nearby
+//! occluders. This grounds objects with fine detail that shadow maps are
+//! too coarse to capture.
+//!
+//! ## Examples
+//!
+//! ```ignore
+//! use oxgl::renderer_3d::Light;
+//! use glam::Vec3;
+//!
+//! scene.enable_contact_shadows(&gl)?;
+//!
+//! // Contact shadows are opted into per light
+//! scene.add_light(
+//!		Light::directional(Vec3::new(-1.0, -1.0, -0.5), Vec3::ONE, 1.0)
+//!			.with_contact_shadows(0.25, 0.8)
+//! );
+//! ```
+//!
+
+use glam::{Mat4, Vec2};
+use web_sys::{WebGlFramebuffer, WebGlTexture, WebGl2RenderingContext as GL};
+
+use crate::common::OxglError;
+
+/// A screen-sized camera depth texture for contact shadows.
+///
+/// Sized by the scene once per frame to the largest view it renders.
+/// Smaller views draw into the bottom-left corner, so samplers scale their
+/// coordinates by [`uv_scale`](Self::uv_scale).
+pub struct ContactShadowMap {
+	pub framebuffer: WebGlFramebuffer,
+	pub depth_texture: WebGlTexture,
+	/// Camera view-projection the depth was rendered with.
+	pub view_projection: Mat4,
+	pub width: i32,
+	pub height: i32,
+	/// Size of the region the last depth pass covered.
+	pub view_width: i32,
+	pub view_height: i32,
+}
+
+impl ContactShadowMap {
+	/// Creates a depth-only framebuffer of the given size.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the framebuffer or depth texture can't be created,
+	/// or the framebuffer is incomplete.
+	pub fn new(gl: &GL, width: i32, height: i32) -> Result<Self, OxglError> {
+		let framebuffer = gl
+			.create_framebuffer()
+			.ok_or_else(|| OxglError::ResourceCreation("Failed to create contact shadow framebuffer".to_string()))?;
+
+		let depth_texture = gl
+			.create_texture()
+			.ok_or_else(|| OxglError::ResourceCreation("Failed to create contact shadow texture".to_string()))?;
+
+		let map = Self {
+			framebuffer,
+			depth_texture,
+			view_projection: Mat4::IDENTITY,
+			width,
+			height,
+			view_width: width,
+			view_height: height,
+		};
+
+		map.allocate(gl)?;
+
+		gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_MIN_FILTER, GL::NEAREST as i32);
+		gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_MAG_FILTER, GL::NEAREST as i32);
+		gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_WRAP_S, GL::CLAMP_TO_EDGE as i32);
+		gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_WRAP_T, GL::CLAMP_TO_EDGE as i32);
+
+		gl.bind_framebuffer(GL::FRAMEBUFFER, Some(&map.framebuffer));
+		gl.framebuffer_texture_2d(
+			GL::FRAMEBUFFER,
+			GL::DEPTH_ATTACHMENT,
+			GL::TEXTURE_2D,
+			Some(&map.depth_texture),
+			0,
+		);
+
+		gl.draw_buffers(&js_sys::Array::new());
+
+		let status = gl.check_framebuffer_status(GL::FRAMEBUFFER);
+
+		gl.bind_framebuffer(GL::FRAMEBUFFER, None);
+		gl.bind_texture(GL::TEXTURE_2D, None);
+
+		if status != GL::FRAMEBUFFER_COMPLETE {
+			return Err(OxglError::FramebufferIncomplete { framebuffer: "Contact shadow".to_string(), status });
+		}
+
+		Ok(map)
+	}
+
+	/// Reallocates the depth texture if the size changed, and covers all
+	/// of it.
+	pub fn resize(&mut self, gl: &GL, width: i32, height: i32) -> Result<(), OxglError> {
+		self.view_width = width;
+		self.view_height = height;
+
+		if (self.width, self.height) == (width, height) {
+			return Ok(());
+		}
+
+		self.width = width;
+		self.height = height;
+		self.allocate(gl)
+	}
+
+	/// Covers a view of the given size in the next depth pass, growing the
+	/// depth texture only if it's too small.
+	pub fn fit(&mut self, gl: &GL, width: i32, height: i32) -> Result<(), OxglError> {
+		if width > self.width || height > self.height {
+			self.resize(gl, width.max(self.width), height.max(self.height))?;
+		}
+
+		self.view_width = width;
+		self.view_height = height;
+		Ok(())
+	}
+
+	/// Returns the ratio between the covered region and the whole texture,
+	/// which texture coordinates of the view are scaled by.
+	pub fn uv_scale(&self) -> Vec2 {
+		Vec2::new(
+			self.view_width as f32 / self.width.max(1) as f32,
+			self.view_height as f32 / self.height.max(1) as f32,
+		)
+	}
+
+	/// Binds the framebuffer and clears it for the depth pass.
+	pub fn bind(&self, gl: &GL) {
+		gl.bind_framebuffer(GL::FRAMEBUFFER, Some(&self.framebuffer));
+		gl.viewport(0, 0, self.view_width, self.view_height);
+		gl.clear(GL::DEPTH_BUFFER_BIT);
+	}
+
+	/// Returns rendering to the default framebuffer.
+	pub fn unbind(&self, gl: &GL) {
+		gl.bind_framebuffer(GL::FRAMEBUFFER, None);
+		gl.viewport(0, 0, self.view_width, self.view_height);
+	}
+
+	/// Binds the depth texture for sampling.
+	pub fn bind_texture(&self, gl: &GL, unit: u32) {
+		gl.active_texture(GL::TEXTURE0 + unit);
+		gl.bind_texture(GL::TEXTURE_2D, Some(&self.depth_texture));
+	}
+
+	fn allocate(&self, gl: &GL) -> Result<(), OxglError> {
+		gl.bind_texture(GL::TEXTURE_2D, Some(&self.depth_texture));
+		gl.tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
+			GL::TEXTURE_2D,
+			0,
+			GL::DEPTH_COMPONENT24 as i32,
+			self.width,
+			self.height,
+			0,
+			GL::DEPTH_COMPONENT,
+			GL::UNSIGNED_INT,
+			None,
+		).map_err(|e| OxglError::ResourceCreation(format!("Failed to create depth texture: {:?}", e)))
+	}
+}
@@ -2,20 +2,74 @@
 //!
 //! Overlays HTML elements in 3D space, synchronized with the WebGL camera.
 //!
+//! The overlay sits on top of the canvas, so elements would otherwise show
+//! through scene geometry. With [occlusion](CSS3DRenderer::enable_occlusion)
+//! enabled, a small proxy quad at each element's anchor is depth tested
+//! against the scene with an occlusion query, and elements whose anchor is
+//! hidden fade out.
+//!
+//! ## Examples
+//!
+//! ```ignore
+//! let mut css3d = CSS3DRenderer::new("webgl-canvas", 800, 600, 45.0)?;
+//! css3d.enable_occlusion(&gl)?;
+//!
+//! let tag = css3d.add_billboard("<span>Engine</span>", Vec3::new(0.0, 1.5, 0.0))?;
+//!
+//! // Each frame, after the scene has been drawn to the canvas
+//! css3d.sync(&gl, &scene.camera, canvas.width(), canvas.height());
+//! ```
+//!
 
 use std::cell::RefCell;
 use glam::{Mat4, Vec3};
 use slotmap::SlotMap;
-use web_sys::{HtmlElement, wasm_bindgen::JsCast};
+use web_sys::{HtmlElement, WebGlBuffer, WebGlProgram, WebGlQuery, WebGl2RenderingContext as GL, wasm_bindgen::JsCast};
 
-use crate::common::Camera;
+use crate::common::{Camera, OxglError, compile_shader, link_program};
 use crate::core::{Transform3D, Transformable, CSS3DElementId};
+use super::occlusion::{issue_query, query_pass, read_query};
+
+const PROXY_VERT: &str = r#"
+	#include <logdepth>
+
+	attribute vec2 corner;
+	uniform mat4 viewProjection;
+	uniform vec3 center;
+	uniform vec3 right;
+	uniform vec3 up;
+
+	void main() {
+		gl_Position = logDepth(viewProjection * vec4(center + right * corner.x + up * corner.y, 1.0));
+	}
+"#;
+
+const PROXY_FRAG: &str = r#"
+	precision mediump float;
+
+	void main() {
+		gl_FragColor = vec4(0.0);
+	}
+"#;
 
 /// A CSS3D renderable object.
 pub struct CSS3DObject {
 	pub element: HtmlElement,
 	pub transform: Transform3D,
 	pub billboard: bool,
+	/// Hides the element when its anchor is behind scene geometry, while
+	/// [occlusion](CSS3DRenderer::enable_occlusion) is enabled.
+	pub occlude: bool,
+	query: Option<WebGlQuery>,
+	occluded: bool,
+}
+
+/// Depth tests element anchors against the scene with occlusion queries.
+struct Occluder {
+	program: WebGlProgram,
+	quad_buffer: WebGlBuffer,
+	/// Half the width of each anchor's proxy quad, in world units.
+	proxy_size: f32,
 }
 
 /// Renders HTML elements in 3D space synchronized with a WebGL camera.
@@ -27,6 +81,8 @@ pub struct CSS3DRenderer {
 	width: f32,
 	height: f32,
 	fov: f32,
+	occluder: Option<Occluder>,
+	stale_queries: RefCell<Vec<WebGlQuery>>,
 }
 
 impl CSS3DRenderer {
@@ -126,6 +182,8 @@ impl CSS3DRenderer {
 			width: width as f32,
 			height: height as f32,
 			fov,
+			occluder: None,
+			stale_queries: RefCell::new(Vec::new()),
 		})
 	}
 
@@ -147,6 +205,7 @@ impl CSS3DRenderer {
 		let _ = style.set_property("transform-style", "preserve-3d");
 		let _ = style.set_property("pointer-events", "auto");
 		let _ = style.set_property("white-space", "nowrap");
+		let _ = style.set_property("transition", "opacity 0.15s");
 
 		self.scene_element
 			.append_child(&wrapper)
@@ -156,6 +215,9 @@ impl CSS3DRenderer {
 			element: wrapper,
 			transform,
 			billboard: false,
+			occlude: true,
+			query: None,
+			occluded: false,
 		};
 
 		let id = self.objects.borrow_mut().insert(object);
@@ -177,6 +239,12 @@ impl CSS3DRenderer {
 	pub fn remove_element(&self, id: CSS3DElementId) -> bool {
 		if let Some(obj) = self.objects.borrow_mut().remove(id) {
 			let _ = obj.element.remove();
+
+			// Deleted on the next occlusion test, which has the context
+			if let Some(query) = obj.query {
+				self.stale_queries.borrow_mut().push(query);
+			}
+
 			true
 		} else {
 			false
@@ -262,6 +330,175 @@ impl CSS3DRenderer {
 		);
 	}
 
+	/// Enables hiding elements whose anchor is behind scene geometry.
+	///
+	/// Anchors are tested against the canvas depth buffer, so the scene must
+	/// be drawn to the canvas before [`sync`](Self::sync). Results arrive a
+	/// frame or two late, and scenes rendered offscreen for post-processing
+	/// leave no depth to test against.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the proxy shaders fail to compile or the quad
+	/// buffer can't be created.
+	pub fn enable_occlusion(&mut self, gl: &GL) -> Result<(), OxglError> {
+		if self.occluder.is_some() {
+			return Ok(());
+		}
+
+		let vert = compile_shader(gl, PROXY_VERT, GL::VERTEX_SHADER)?;
+		let frag = compile_shader(gl, PROXY_FRAG, GL::FRAGMENT_SHADER)?;
+		let program = link_program(gl, &vert, &frag)?;
+
+		let quad_buffer = gl
+			.create_buffer()
+			.ok_or_else(|| OxglError::ResourceCreation("Failed to create CSS3D proxy buffer".into()))?;
+		let corners: [f32; 8] = [-1.0, -1.0, 1.0, -1.0, -1.0, 1.0, 1.0, 1.0];
+
+		gl.bind_buffer(GL::ARRAY_BUFFER, Some(&quad_buffer));
+
+		let data = unsafe {
+			std::slice::from_raw_parts(corners.as_ptr() as *const u8, std::mem::size_of_val(&corners))
+		};
+
+		gl.buffer_data_with_u8_array(GL::ARRAY_BUFFER, data, GL::STATIC_DRAW);
+
+		self.occluder = Some(Occluder { program, quad_buffer, proxy_size: 0.02 });
+		Ok(())
+	}
+
+	/// Disables occlusion, showing every element again and freeing its
+	/// queries.
+	pub fn disable_occlusion(&mut self, gl: &GL) {
+		let Some(occluder) = self.occluder.take() else { return };
+
+		for obj in self.objects.borrow_mut().values_mut() {
+			if let Some(query) = obj.query.take() {
+				gl.delete_query(Some(&query));
+			}
+
+			obj.set_occluded(false);
+		}
+
+		for query in self.stale_queries.borrow_mut().drain(..) {
+			gl.delete_query(Some(&query));
+		}
+
+		gl.delete_program(Some(&occluder.program));
+		gl.delete_buffer(Some(&occluder.quad_buffer));
+	}
+
+	/// Returns whether occlusion is enabled.
+	pub fn occlusion_enabled(&self) -> bool {
+		self.occluder.is_some()
+	}
+
+	/// Sets the half width, in world units, of the quad depth tested at each
+	/// anchor. Larger quads keep elements visible until their anchor is
+	/// fully covered.
+	pub fn set_occlusion_proxy_size(&mut self, size: f32) {
+		if let Some(occluder) = &mut self.occluder {
+			occluder.proxy_size = size;
+		}
+	}
+
+	/// Reads finished occlusion queries to show or hide elements, then
+	/// queues new queries for the current frame.
+	///
+	/// Does nothing unless [`enable_occlusion`](Self::enable_occlusion) has
+	/// been called.
+	pub fn test_occlusion(&self, gl: &GL, camera: &Camera) {
+		let Some(occluder) = &self.occluder else { return };
+
+		for query in self.stale_queries.borrow_mut().drain(..) {
+			gl.delete_query(Some(&query));
+		}
+
+		let view = camera.view_matrix();
+		let view_projection = camera.projection_matrix() * view;
+		// Camera axes in world space are the rows of the view matrix
+		let right = view.row(0).truncate() * occluder.proxy_size;
+		let up = view.row(1).truncate() * occluder.proxy_size;
+
+		gl.use_program(Some(&occluder.program));
+
+		if let Some(loc) = gl.get_uniform_location(&occluder.program, "viewProjection") {
+			gl.uniform_matrix4fv_with_f32_array(Some(&loc), false, &view_projection.to_cols_array());
+		}
+		if let Some(loc) = gl.get_uniform_location(&occluder.program, "logDepthFactor") {
+			gl.uniform1f(Some(&loc), camera.log_depth_factor());
+		}
+		if let Some(loc) = gl.get_uniform_location(&occluder.program, "right") {
+			gl.uniform3fv_with_f32_array(Some(&loc), &right.to_array());
+		}
+		if let Some(loc) = gl.get_uniform_location(&occluder.program, "up") {
+			gl.uniform3fv_with_f32_array(Some(&loc), &up.to_array());
+		}
+
+		gl.bind_buffer(GL::ARRAY_BUFFER, Some(&occluder.quad_buffer));
+
+		let corner_loc = gl.get_attrib_location(&occluder.program, "corner");
+
+		if corner_loc >= 0 {
+			gl.enable_vertex_attrib_array(corner_loc as u32);
+			gl.vertex_attrib_pointer_with_i32(corner_loc as u32, 2, GL::FLOAT, false, 0, 0);
+		}
+
+		let center_loc = gl.get_uniform_location(&occluder.program, "center");
+
+		query_pass(gl, || {
+			for obj in self.objects.borrow_mut().values_mut() {
+				if !obj.occlude {
+					obj.set_occluded(false);
+					continue;
+				}
+
+				match obj.query.as_ref().map(|query| read_query(gl, query)) {
+					Some(Some(passed)) => obj.set_occluded(!passed),
+					Some(None) => continue,
+					None => {}
+				}
+
+				// Lift the proxy towards the camera so surfaces the anchor sits on
+				// don't hide it
+				let anchor = obj.transform.position;
+				let lift = (camera.position - anchor).normalize_or_zero() * occluder.proxy_size;
+
+				gl.uniform3fv_with_f32_array(center_loc.as_ref(), &(anchor + lift).to_array());
+				obj.query = issue_query(gl, obj.query.take(), || gl.draw_arrays(GL::TRIANGLE_STRIP, 0, 4));
+			}
+		});
+
+		if corner_loc >= 0 {
+			gl.disable_vertex_attrib_array(corner_loc as u32);
+		}
+	}
+
+	/// Returns the viewport size in pixels.
+	pub fn size(&self) -> (u32, u32) {
+		(self.width as u32, self.height as u32)
+	}
+
+	/// Matches the overlay to a canvas size and a camera's field of view,
+	/// then renders all elements with the camera.
+	///
+	/// Called every frame by [`App::run`](crate::App::run) once
+	/// [`App::enable_css3d`](crate::App::enable_css3d) has been called.
+	///
+	/// Also [tests occlusion](Self::test_occlusion) when enabled, so the
+	/// scene should already be drawn.
+	pub fn sync(&mut self, gl: &GL, camera: &Camera, width: u32, height: u32) {
+		let fov = camera.fov_y.to_degrees();
+
+		if self.size() != (width, height) || (self.fov - fov).abs() > f32::EPSILON {
+			self.fov = fov;
+			self.resize(width, height);
+		}
+
+		self.test_occlusion(gl, camera);
+		self.render(camera);
+	}
+
 	/// Resizes the CSS3D viewport.
 	pub fn resize(&mut self, width: u32, height: u32) {
 		self.width = width as f32;
@@ -274,4 +511,18 @@ impl CSS3DRenderer {
 		let _ = self.container.style().set_property("height", &format!("{}px", height));
 		let _ = self.camera_element.style().set_property("perspective", &format!("{}px", perspective));
 	}
-}
\ No newline at end of file
+}
+
+impl CSS3DObject {
+	fn set_occluded(&mut self, occluded: bool) {
+		if self.occluded == occluded {
+			return;
+		}
+
+		self.occluded = occluded;
+
+		let style = self.element.style();
+		let _ = style.set_property("opacity", if occluded { "0" } else { "1" });
+		let _ = style.set_property("pointer-events", if occluded { "none" } else { "auto" });
+	}
+}
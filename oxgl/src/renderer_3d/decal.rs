@@ -0,0 +1,264 @@
+//! Decals
+//!
+//! Provides [`Decal`], a texture projected onto whatever surfaces lie
+//! inside a box, for bullet holes, stickers, footprints and blob shadows.
+//! Decals are drawn after the scene's objects, reconstructing the surface
+//! under every pixel from a depth pre-pass, so they follow any geometry
+//! without extra meshes.
+//!
+//! The box is a unit cube shaped by the decal's transform. The texture is
+//! projected down its local -Y axis, with the top of the image towards
+//! local -Z, onto surfaces facing up the box.
+//!
+//! ## Examples
+//!
+//! ```ignore
+//! use oxgl::renderer_3d::{Decal, DecalBlend};
+//! use oxgl::core::Transform3D;
+//!
+//! // A bullet hole on a wall facing +Z
+//! let hole = Texture::load(&gl, "assets/bullet_hole.png")?;
+//! scene.add_decal(
+//!		Decal::new(hole).with_transform(
+//!			Transform3D::new()
+//!				.with_position(hit.point)
+//!				.with_rotation(Quat::from_rotation_arc(Vec3::Y, hit.normal))
+//!				.with_scale(Vec3::new(0.2, 0.1, 0.2))
+//!		)
+//! );
+//!
+//! // A blob shadow darkening the ground under a character
+//! let blob = scene.add_decal(
+//!		Decal::new(Texture::load(&gl, "assets/blob.png")?)
+//!			.with_blend(DecalBlend::Multiply)
+//!			.with_transform(Transform3D::new().with_scale(Vec3::new(1.0, 2.0, 1.0)))
+//! );
+//! scene.decals[blob].transform.position = character_position;
+//! ```
+//!
+
+use glam::{Mat4, Vec4};
+use web_sys::{WebGlBuffer, WebGlProgram, WebGl2RenderingContext as GL};
+
+use crate::{
+	common::{Camera, Texture, compile_shader, link_program, render_state::{SavedState, current_viewport}},
+	core::{Transform3D, Transformable},
+};
+use super::{ContactShadowMap, background::blend_over};
+
+const DECAL_VERT: &str = include_str!("../shaders/decal.vert");
+const DECAL_FRAG: &str = include_str!("../shaders/decal.frag");
+
+/// How a [`Decal`] is combined with the surface under it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DecalBlend {
+	/// Drawn over the surface by its alpha.
+	#[default]
+	Alpha,
+	/// Added to the surface, for glowing marks.
+	Additive,
+	/// Multiplied with the surface, darkening it, for blob shadows and dirt.
+	Multiply,
+}
+
+impl DecalBlend {
+	fn apply(self, gl: &GL) {
+		match self {
+			DecalBlend::Alpha => blend_over(gl),
+			DecalBlend::Additive => {
+				gl.enable(GL::BLEND);
+				gl.blend_func_separate(GL::SRC_ALPHA, GL::ONE, GL::ZERO, GL::ONE);
+			}
+			DecalBlend::Multiply => {
+				gl.enable(GL::BLEND);
+				gl.blend_func_separate(GL::DST_COLOR, GL::ZERO, GL::ZERO, GL::ONE);
+			}
+		}
+	}
+
+	fn uniform(self) -> i32 {
+		match self {
+			DecalBlend::Alpha => 0,
+			DecalBlend::Additive => 1,
+			DecalBlend::Multiply => 2,
+		}
+	}
+}
+
+/// A texture projected onto the surfaces inside a box.
+#[derive(Clone)]
+pub struct Decal {
+	/// Places and sizes the unit box the decal is projected through.
+	pub transform: Transform3D,
+	pub texture: Texture,
+	/// Multiplied with the texture, with alpha as the opacity.
+	pub color: Vec4,
+	pub blend: DecalBlend,
+	/// Surfaces whose normal is further from the box's up axis than this
+	/// cosine are left alone, so decals don't streak down steep sides.
+	pub normal_threshold: f32,
+	pub visible: bool,
+}
+
+impl Decal {
+	pub fn new(texture: Texture) -> Self {
+		Self {
+			transform: Transform3D::new(),
+			texture,
+			color: Vec4::ONE,
+			blend: DecalBlend::default(),
+			normal_threshold: 0.2,
+			visible: true,
+		}
+	}
+
+	pub fn with_transform(mut self, transform: Transform3D) -> Self {
+		self.transform = transform;
+		self
+	}
+
+	pub fn with_color(mut self, color: Vec4) -> Self {
+		self.color = color;
+		self
+	}
+
+	pub fn with_blend(mut self, blend: DecalBlend) -> Self {
+		self.blend = blend;
+		self
+	}
+
+	pub fn with_normal_threshold(mut self, threshold: f32) -> Self {
+		self.normal_threshold = threshold;
+		self
+	}
+}
+
+/// Draws decals for a [`Scene`](super::Scene) from a camera depth pre-pass.
+pub(crate) struct DecalRenderer {
+	program: WebGlProgram,
+	cube_buffer: WebGlBuffer,
+	/// Camera depth of every visible object, rendered like the contact
+	/// shadow depth but including objects that cast no shadows.
+	pub depth_map: ContactShadowMap,
+}
+
+impl DecalRenderer {
+	pub fn new(gl: &GL, width: i32, height: i32) -> Result<Self, String> {
+		let vert = compile_shader(gl, DECAL_VERT, GL::VERTEX_SHADER)?;
+		let frag = compile_shader(gl, DECAL_FRAG, GL::FRAGMENT_SHADER)?;
+		let program = link_program(gl, &vert, &frag)?;
+		let depth_map = ContactShadowMap::new(gl, width, height)?;
+
+		let vertices = cube_vertices();
+		let cube_buffer = gl.create_buffer().ok_or("Failed to create decal buffer")?;
+		let data = unsafe {
+			std::slice::from_raw_parts(vertices.as_ptr() as *const u8, std::mem::size_of_val(vertices.as_slice()))
+		};
+
+		gl.bind_buffer(GL::ARRAY_BUFFER, Some(&cube_buffer));
+		gl.buffer_data_with_u8_array(GL::ARRAY_BUFFER, data, GL::STATIC_DRAW);
+
+		Ok(Self { program, cube_buffer, depth_map })
+	}
+
+	/// Draws decals over the bound framebuffer.
+	///
+	/// Only back faces of each box are drawn, without depth testing, so
+	/// every covered pixel is shaded once, even with the camera inside the
+	/// box. The depth map must hold the camera's depth at `width` by
+	/// `height`, covering the current viewport. Blending, depth and culling
+	/// state is restored afterwards.
+	pub fn draw<'a>(&self, gl: &GL, camera: &Camera, decals: impl Iterator<Item = &'a Decal>, width: f32, height: f32) {
+		let program = &self.program;
+		let view_projection = camera.projection_matrix() * camera.view_matrix();
+
+		gl.use_program(Some(program));
+		set_matrix(gl, program, "viewProjection", &view_projection);
+		set_matrix(gl, program, "inverseViewProjection", &view_projection.inverse());
+
+		if let Some(loc) = gl.get_uniform_location(program, "resolution") {
+			gl.uniform2f(Some(&loc), width, height);
+		}
+		if let Some(loc) = gl.get_uniform_location(program, "viewportOrigin") {
+			let [x, y, _, _] = current_viewport(gl);
+			gl.uniform2f(Some(&loc), x as f32, y as f32);
+		}
+		if let Some(loc) = gl.get_uniform_location(program, "depthMap") {
+			gl.uniform1i(Some(&loc), 0);
+		}
+		if let Some(loc) = gl.get_uniform_location(program, "depthUvScale") {
+			gl.uniform2fv_with_f32_array(Some(&loc), &self.depth_map.uv_scale().to_array());
+		}
+		if let Some(loc) = gl.get_uniform_location(program, "decalTexture") {
+			gl.uniform1i(Some(&loc), 1);
+		}
+
+		self.depth_map.bind_texture(gl, 0);
+		gl.bind_buffer(GL::ARRAY_BUFFER, Some(&self.cube_buffer));
+
+		let position_loc = gl.get_attrib_location(program, "position");
+
+		if position_loc >= 0 {
+			gl.enable_vertex_attrib_array(position_loc as u32);
+			gl.vertex_attrib_pointer_with_i32(position_loc as u32, 3, GL::FLOAT, false, 12, 0);
+		}
+
+		let state = SavedState::capture(gl);
+
+		gl.enable(GL::CULL_FACE);
+		gl.cull_face(GL::FRONT);
+		gl.disable(GL::DEPTH_TEST);
+		gl.depth_mask(false);
+
+		for decal in decals.filter(|decal| decal.visible) {
+			let model = decal.transform.to_matrix();
+
+			set_matrix(gl, program, "model", &model);
+			set_matrix(gl, program, "inverseModel", &model.inverse());
+
+			if let Some(loc) = gl.get_uniform_location(program, "projectionAxis") {
+				gl.uniform3fv_with_f32_array(Some(&loc), &decal.transform.up().normalize_or_zero().to_array());
+			}
+			if let Some(loc) = gl.get_uniform_location(program, "color") {
+				gl.uniform4fv_with_f32_array(Some(&loc), &decal.color.to_array());
+			}
+			if let Some(loc) = gl.get_uniform_location(program, "normalThreshold") {
+				gl.uniform1f(Some(&loc), decal.normal_threshold);
+			}
+			if let Some(loc) = gl.get_uniform_location(program, "blendMode") {
+				gl.uniform1i(Some(&loc), decal.blend.uniform());
+			}
+
+			decal.texture.bind(gl, 1);
+			decal.blend.apply(gl);
+			gl.draw_arrays(GL::TRIANGLES, 0, 36);
+		}
+
+		state.restore(gl);
+	}
+}
+
+/// Counter-clockwise triangles of a unit cube centered on the origin.
+fn cube_vertices() -> Vec<f32> {
+	let corner = |i: usize| [
+		if i & 1 == 0 { -0.5 } else { 0.5 },
+		if i & 2 == 0 { -0.5 } else { 0.5 },
+		if i & 4 == 0 { -0.5 } else { 0.5 },
+	];
+	let faces: [[usize; 4]; 6] = [
+		[1, 3, 7, 5], [0, 4, 6, 2],
+		[2, 6, 7, 3], [0, 1, 5, 4],
+		[4, 5, 7, 6], [0, 2, 3, 1],
+	];
+
+	faces.iter()
+		.flat_map(|[a, b, c, d]| [a, b, c, a, c, d])
+		.flat_map(|&i| corner(i))
+		.collect()
+}
+
+fn set_matrix(gl: &GL, program: &WebGlProgram, name: &str, matrix: &Mat4) {
+	if let Some(loc) = gl.get_uniform_location(program, name) {
+		gl.uniform_matrix4fv_with_f32_array(Some(&loc), false, &matrix.to_cols_array());
+	}
+}
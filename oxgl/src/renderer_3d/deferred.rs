@@ -0,0 +1,447 @@
+//! Deferred Rendering
+//!
+//! Provides an alternative to forward rendering for scenes with many lights.
+//! With [`RenderPath::Deferred`], objects using the built-in Lambert and
+//! Phong materials are first drawn once into a G-buffer holding their albedo,
+//! normal, depth and material parameters. Each enabled light is then shaded
+//! in screen space over the part of the screen it can reach, so the number
+//! of lights isn't limited to [`MAX_LIGHTS`](super::light::MAX_LIGHTS) and
+//! their cost doesn't grow with the number of objects.
+//!
+//! Objects with other materials, such as unlit or custom shaders, are still
+//! drawn forward on top, depth tested against the deferred objects.
+//!
+//! Differences from the forward path:
+//! - Contact shadows aren't applied to deferred objects
+//! - The G-buffer needs WebGL2 multiple render targets. Normals and lighting
+//!   are stored as half floats when `EXT_color_buffer_float` is available,
+//!   and at 8 bits per channel otherwise
+//!
+//! ## Examples
+//!
+//! ```ignore
+//! use oxgl::renderer_3d::{Light, RenderPath};
+//!
+//! scene.set_render_path(RenderPath::Deferred);
+//!
+//! // Dozens of small lights
+//! for i in 0..64 {
+//!		let angle = i as f32 / 64.0 * std::f32::consts::TAU;
+//!		let position = Vec3::new(angle.cos() * 8.0, 0.5, angle.sin() * 8.0);
+//!		scene.add_light(Light::point(position, Vec3::new(1.0, 0.6, 0.3), 2.0, 3.0));
+//! }
+//! ```
+//!
+
+use glam::{Mat4, Vec3, Vec4};
+use web_sys::{
+	WebGlBuffer, WebGlFramebuffer, WebGlProgram, WebGlTexture,
+	WebGl2RenderingContext as GL,
+};
+
+use crate::common::{Camera, OxglError, compile_shader, link_program, render_state::current_depth_func};
+use super::{Light, LightType, ShadowSettings, apply_lights};
+
+const GEOMETRY_VERT: &str = include_str!("../shaders/deferred_geometry.vert");
+const GEOMETRY_FRAG: &str = include_str!("../shaders/deferred_geometry.frag");
+const QUAD_VERT: &str = include_str!("../shaders/deferred_quad.vert");
+const LIGHT_FRAG: &str = include_str!("../shaders/deferred_light.frag");
+const RESOLVE_FRAG: &str = include_str!("../shaders/deferred_resolve.frag");
+
+/// First texture unit used for G-buffer textures. Units 0 and 1 hold the
+/// shadow and contact shadow maps.
+const FIRST_UNIT: u32 = 2;
+
+/// How a scene's lit objects are drawn.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RenderPath {
+	/// Each object is shaded by up to [`MAX_LIGHTS`](super::light::MAX_LIGHTS)
+	/// lights as it is drawn.
+	#[default]
+	Forward,
+	/// Lambert and Phong objects are shaded by every light in screen space.
+	Deferred,
+}
+
+/// Parameters of the light pass shared by every light.
+pub(crate) struct LightPass<'a> {
+	pub camera: &'a Camera,
+	pub lights: &'a [Light],
+	/// Light space of the shadow map, if shadows are active. Like the
+	/// forward path, it darkens every light.
+	pub shadow: Option<Mat4>,
+	pub shadow_settings: ShadowSettings,
+	pub shadow_map_size: i32,
+	pub color_space: i32,
+}
+
+/// The G-buffer, light buffer and programs of the deferred path.
+pub(crate) struct DeferredRenderer {
+	gbuffer: WebGlFramebuffer,
+	albedo: WebGlTexture,
+	normal: WebGlTexture,
+	material: WebGlTexture,
+	depth: WebGlTexture,
+	light_framebuffer: WebGlFramebuffer,
+	light_texture: WebGlTexture,
+	/// Whether normals and lighting use half float textures.
+	half_float: bool,
+	/// Allocated size of the buffers.
+	width: i32,
+	height: i32,
+	/// Size of the view being drawn, in the bottom-left corner of the
+	/// buffers.
+	view_width: i32,
+	view_height: i32,
+	pub geometry_program: WebGlProgram,
+	light_program: WebGlProgram,
+	resolve_program: WebGlProgram,
+	quad: WebGlBuffer,
+}
+
+impl DeferredRenderer {
+	/// Creates the buffers and programs for a view of the given size.
+	///
+	/// # Errors
+	///
+	/// Returns an error if a shader fails to compile, a resource can't be
+	/// created or a framebuffer is incomplete.
+	pub fn new(gl: &GL, width: i32, height: i32) -> Result<Self, OxglError> {
+		let half_float = gl.get_extension("EXT_color_buffer_float").ok().flatten().is_some();
+
+		let program = |vert: &str, frag: &str| -> Result<WebGlProgram, OxglError> {
+			let vert = compile_shader(gl, vert, GL::VERTEX_SHADER)?;
+			let frag = compile_shader(gl, frag, GL::FRAGMENT_SHADER)?;
+			link_program(gl, &vert, &frag)
+		};
+
+		let geometry_program = program(GEOMETRY_VERT, GEOMETRY_FRAG)?;
+		let light_program = program(QUAD_VERT, LIGHT_FRAG)?;
+		let resolve_program = program(QUAD_VERT, RESOLVE_FRAG)?;
+
+		let texture = || gl.create_texture()
+			.ok_or_else(|| OxglError::ResourceCreation("Failed to create G-buffer texture".to_string()));
+		let framebuffer = || gl.create_framebuffer()
+			.ok_or_else(|| OxglError::ResourceCreation("Failed to create G-buffer framebuffer".to_string()));
+
+		let quad = gl.create_buffer()
+			.ok_or_else(|| OxglError::ResourceCreation("Failed to create light quad buffer".to_string()))?;
+		let corners: [f32; 12] = [0.0, 0.0, 1.0, 0.0, 1.0, 1.0, 0.0, 0.0, 1.0, 1.0, 0.0, 1.0];
+
+		gl.bind_buffer(GL::ARRAY_BUFFER, Some(&quad));
+		unsafe {
+			let view = js_sys::Float32Array::view(&corners);
+			gl.buffer_data_with_array_buffer_view(GL::ARRAY_BUFFER, &view, GL::STATIC_DRAW);
+		}
+
+		let mut renderer = Self {
+			gbuffer: framebuffer()?,
+			albedo: texture()?,
+			normal: texture()?,
+			material: texture()?,
+			depth: texture()?,
+			light_framebuffer: framebuffer()?,
+			light_texture: texture()?,
+			half_float,
+			width: 0,
+			height: 0,
+			view_width: 0,
+			view_height: 0,
+			geometry_program,
+			light_program,
+			resolve_program,
+			quad,
+		};
+
+		renderer.prepare(gl, width, height)?;
+
+		gl.bind_framebuffer(GL::FRAMEBUFFER, Some(&renderer.gbuffer));
+		for (i, texture) in [&renderer.albedo, &renderer.normal, &renderer.material].into_iter().enumerate() {
+			gl.framebuffer_texture_2d(GL::FRAMEBUFFER, GL::COLOR_ATTACHMENT0 + i as u32, GL::TEXTURE_2D, Some(texture), 0);
+		}
+		gl.framebuffer_texture_2d(GL::FRAMEBUFFER, GL::DEPTH_ATTACHMENT, GL::TEXTURE_2D, Some(&renderer.depth), 0);
+
+		let attachments = js_sys::Array::of3(
+			&GL::COLOR_ATTACHMENT0.into(),
+			&GL::COLOR_ATTACHMENT1.into(),
+			&GL::COLOR_ATTACHMENT2.into(),
+		);
+		gl.draw_buffers(&attachments);
+
+		let status = gl.check_framebuffer_status(GL::FRAMEBUFFER);
+
+		if status != GL::FRAMEBUFFER_COMPLETE {
+			gl.bind_framebuffer(GL::FRAMEBUFFER, None);
+			return Err(OxglError::FramebufferIncomplete { framebuffer: "G-buffer".to_string(), status });
+		}
+
+		gl.bind_framebuffer(GL::FRAMEBUFFER, Some(&renderer.light_framebuffer));
+		gl.framebuffer_texture_2d(GL::FRAMEBUFFER, GL::COLOR_ATTACHMENT0, GL::TEXTURE_2D, Some(&renderer.light_texture), 0);
+
+		let status = gl.check_framebuffer_status(GL::FRAMEBUFFER);
+		gl.bind_framebuffer(GL::FRAMEBUFFER, None);
+
+		if status != GL::FRAMEBUFFER_COMPLETE {
+			return Err(OxglError::FramebufferIncomplete { framebuffer: "Light buffer".to_string(), status });
+		}
+
+		Ok(renderer)
+	}
+
+	/// Sets the size of the next view, growing the buffers if it doesn't
+	/// fit. Buffers never shrink, so views of different sizes can share them.
+	pub fn prepare(&mut self, gl: &GL, width: i32, height: i32) -> Result<(), OxglError> {
+		self.view_width = width.max(1);
+		self.view_height = height.max(1);
+
+		if self.view_width <= self.width && self.view_height <= self.height {
+			return Ok(());
+		}
+
+		let width = self.view_width.max(self.width);
+		let height = self.view_height.max(self.height);
+
+		let (float_format, float_type) = if self.half_float {
+			(GL::RGBA16F, GL::HALF_FLOAT)
+		} else {
+			(GL::RGBA8, GL::UNSIGNED_BYTE)
+		};
+
+		allocate(gl, &self.albedo, GL::RGBA8, GL::RGBA, GL::UNSIGNED_BYTE, width, height)?;
+		allocate(gl, &self.normal, float_format, GL::RGBA, float_type, width, height)?;
+		allocate(gl, &self.material, GL::RGBA8, GL::RGBA, GL::UNSIGNED_BYTE, width, height)?;
+		allocate(gl, &self.depth, GL::DEPTH_COMPONENT24, GL::DEPTH_COMPONENT, GL::UNSIGNED_INT, width, height)?;
+		allocate(gl, &self.light_texture, float_format, GL::RGBA, float_type, width, height)?;
+
+		self.width = width;
+		self.height = height;
+
+		Ok(())
+	}
+
+	/// Binds and clears the G-buffer for the geometry pass.
+	pub fn begin_geometry(&self, gl: &GL) {
+		gl.bind_framebuffer(GL::FRAMEBUFFER, Some(&self.gbuffer));
+		gl.viewport(0, 0, self.view_width, self.view_height);
+		gl.clear_color(0.0, 0.0, 0.0, 0.0);
+		gl.clear(GL::COLOR_BUFFER_BIT | GL::DEPTH_BUFFER_BIT);
+		gl.use_program(Some(&self.geometry_program));
+	}
+
+	/// Sums the contribution of every enabled light into the light buffer.
+	///
+	/// Returns the number of lights drawn.
+	pub fn light(&self, gl: &GL, pass: &LightPass) -> u32 {
+		let program = &self.light_program;
+		let view_projection = pass.camera.projection_matrix() * pass.camera.view_matrix();
+
+		gl.bind_framebuffer(GL::FRAMEBUFFER, Some(&self.light_framebuffer));
+		gl.viewport(0, 0, self.view_width, self.view_height);
+		gl.clear_color(0.0, 0.0, 0.0, 0.0);
+		gl.clear(GL::COLOR_BUFFER_BIT);
+
+		gl.disable(GL::DEPTH_TEST);
+		gl.enable(GL::BLEND);
+		gl.blend_func(GL::ONE, GL::ONE);
+
+		gl.use_program(Some(program));
+		self.bind_gbuffer(gl, program);
+		self.set_view_uniforms(gl, program, pass.camera, &view_projection, pass.color_space);
+
+		if let Some(loc) = gl.get_uniform_location(program, "shadowMap") {
+			gl.uniform1i(Some(&loc), 0);
+		}
+
+		pass.shadow_settings.apply(gl, program, pass.shadow_map_size);
+
+		if let Some(loc) = gl.get_uniform_location(program, "shadowsEnabled") {
+			gl.uniform1i(Some(&loc), if pass.shadow.is_some() { 1 } else { 0 });
+		}
+		if let Some(light_space) = pass.shadow && let Some(loc) = gl.get_uniform_location(program, "lightSpace") {
+			gl.uniform_matrix4fv_with_f32_array(Some(&loc), false, &light_space.to_cols_array());
+		}
+
+		let mut drawn = 0;
+
+		for light in pass.lights.iter().filter(|light| light.enabled) {
+			let Some(rect) = light_rect(light, pass.camera, &view_projection) else {
+				continue;
+			};
+
+			apply_lights(gl, program, std::slice::from_ref(light));
+			self.draw_quad(gl, program, rect);
+			drawn += 1;
+		}
+
+		gl.disable(GL::BLEND);
+		gl.enable(GL::DEPTH_TEST);
+
+		drawn
+	}
+
+	/// Draws the lit result into the bound framebuffer, writing the
+	/// G-buffer depth so forward objects are hidden behind deferred ones.
+	pub fn resolve(&self, gl: &GL, camera: &Camera, color_space: i32, fog: (i32, [f32; 3]), fog_color: Vec3) {
+		let program = &self.resolve_program;
+		let view_projection = camera.projection_matrix() * camera.view_matrix();
+		let (fog_mode, fog_params) = fog;
+
+		gl.use_program(Some(program));
+		self.bind_gbuffer(gl, program);
+		self.set_view_uniforms(gl, program, camera, &view_projection, color_space);
+
+		gl.active_texture(GL::TEXTURE0 + FIRST_UNIT + 4);
+		gl.bind_texture(GL::TEXTURE_2D, Some(&self.light_texture));
+
+		if let Some(loc) = gl.get_uniform_location(program, "lightBuffer") {
+			gl.uniform1i(Some(&loc), (FIRST_UNIT + 4) as i32);
+		}
+
+		if let Some(loc) = gl.get_uniform_location(program, "fogMode") {
+			gl.uniform1i(Some(&loc), fog_mode);
+		}
+		if let Some(loc) = gl.get_uniform_location(program, "fogColor") {
+			gl.uniform3fv_with_f32_array(Some(&loc), &fog_color.to_array());
+		}
+		if let Some(loc) = gl.get_uniform_location(program, "fogParams") {
+			gl.uniform3fv_with_f32_array(Some(&loc), &fog_params);
+		}
+
+		let depth_func = current_depth_func(gl);
+
+		gl.depth_func(GL::ALWAYS);
+		self.draw_quad(gl, program, Vec4::new(-1.0, -1.0, 1.0, 1.0));
+		gl.depth_func(depth_func);
+	}
+
+	/// Returns the approximate GPU memory used by the buffers, in bytes.
+	pub fn texture_bytes(&self) -> usize {
+		let float_bytes = if self.half_float { 8 } else { 4 };
+		let pixels = self.width as usize * self.height as usize;
+
+		// Albedo, material and depth at 4 bytes, normal and light buffer
+		pixels * (3 * 4 + 2 * float_bytes)
+	}
+
+	fn bind_gbuffer(&self, gl: &GL, program: &WebGlProgram) {
+		let textures = [
+			("gAlbedo", &self.albedo),
+			("gNormal", &self.normal),
+			("gMaterial", &self.material),
+			("gDepth", &self.depth),
+		];
+
+		for (unit, (name, texture)) in (FIRST_UNIT..).zip(textures) {
+			gl.active_texture(GL::TEXTURE0 + unit);
+			gl.bind_texture(GL::TEXTURE_2D, Some(texture));
+
+			if let Some(loc) = gl.get_uniform_location(program, name) {
+				gl.uniform1i(Some(&loc), unit as i32);
+			}
+		}
+	}
+
+	fn set_view_uniforms(&self, gl: &GL, program: &WebGlProgram, camera: &Camera, view_projection: &Mat4, color_space: i32) {
+		if let Some(loc) = gl.get_uniform_location(program, "inverseViewProjection") {
+			gl.uniform_matrix4fv_with_f32_array(Some(&loc), false, &view_projection.inverse().to_cols_array());
+		}
+		if let Some(loc) = gl.get_uniform_location(program, "cameraPosition") {
+			gl.uniform3fv_with_f32_array(Some(&loc), &camera.position.to_array());
+		}
+		if let Some(loc) = gl.get_uniform_location(program, "colorSpace") {
+			gl.uniform1i(Some(&loc), color_space);
+		}
+		if let Some(loc) = gl.get_uniform_location(program, "logDepthFactor") {
+			gl.uniform1f(Some(&loc), camera.log_depth_factor());
+		}
+		if let Some(loc) = gl.get_uniform_location(program, "depthProjection") {
+			let projection = camera.projection_matrix();
+			gl.uniform2f(Some(&loc), projection.z_axis.z, projection.w_axis.z);
+		}
+		if let Some(loc) = gl.get_uniform_location(program, "uvScale") {
+			let scale = [self.view_width as f32 / self.width as f32, self.view_height as f32 / self.height as f32];
+			gl.uniform2fv_with_f32_array(Some(&loc), &scale);
+		}
+	}
+
+	/// Draws a quad over `rect`, given as `(min, max)` in normalized device
+	/// coordinates.
+	fn draw_quad(&self, gl: &GL, program: &WebGlProgram, rect: Vec4) {
+		if let Some(loc) = gl.get_uniform_location(program, "rect") {
+			gl.uniform4fv_with_f32_array(Some(&loc), &rect.to_array());
+		}
+
+		let loc = gl.get_attrib_location(program, "position");
+
+		if loc < 0 {
+			return;
+		}
+
+		gl.bind_buffer(GL::ARRAY_BUFFER, Some(&self.quad));
+		gl.enable_vertex_attrib_array(loc as u32);
+		gl.vertex_attrib_pointer_with_i32(loc as u32, 2, GL::FLOAT, false, 0, 0);
+		gl.draw_arrays(GL::TRIANGLES, 0, 6);
+		gl.disable_vertex_attrib_array(loc as u32);
+	}
+}
+
+/// Allocates storage for a G-buffer texture, sampled without filtering.
+fn allocate(gl: &GL, texture: &WebGlTexture, internal_format: u32, format: u32, data_type: u32, width: i32, height: i32) -> Result<(), OxglError> {
+	gl.bind_texture(GL::TEXTURE_2D, Some(texture));
+
+	gl.tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
+		GL::TEXTURE_2D, 0, internal_format as i32, width, height, 0, format, data_type, None,
+	).map_err(|e| OxglError::ResourceCreation(format!("Failed to allocate G-buffer texture: {:?}", e)))?;
+
+	gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_MIN_FILTER, GL::NEAREST as i32);
+	gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_MAG_FILTER, GL::NEAREST as i32);
+	gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_WRAP_S, GL::CLAMP_TO_EDGE as i32);
+	gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_WRAP_T, GL::CLAMP_TO_EDGE as i32);
+	gl.bind_texture(GL::TEXTURE_2D, None);
+
+	Ok(())
+}
+
+/// Returns the part of the screen a light can reach as `(min, max)` in
+/// normalized device coordinates, or `None` if it is off screen.
+///
+/// Directional lights, and lights whose range contains the camera or
+/// crosses the camera plane, cover the whole screen.
+fn light_rect(light: &Light, camera: &Camera, view_projection: &Mat4) -> Option<Vec4> {
+	const FULL: Vec4 = Vec4::new(-1.0, -1.0, 1.0, 1.0);
+
+	let radius = match light.light_type {
+		LightType::Directional => return Some(FULL),
+		LightType::Point { radius } => radius,
+		LightType::Spot { range, .. } => range,
+	};
+
+	if camera.position.distance(light.position) <= radius + camera.near {
+		return Some(FULL);
+	}
+
+	let mut min = Vec3::splat(f32::MAX);
+	let mut max = Vec3::splat(f32::MIN);
+
+	for i in 0..8 {
+		let corner = Vec3::new(
+			if i & 1 == 0 { -radius } else { radius },
+			if i & 2 == 0 { -radius } else { radius },
+			if i & 4 == 0 { -radius } else { radius },
+		);
+		let clip = *view_projection * (light.position + corner).extend(1.0);
+
+		if clip.w <= 0.0 {
+			return Some(FULL);
+		}
+
+		let ndc = clip.truncate() / clip.w;
+		min = min.min(ndc);
+		max = max.max(ndc);
+	}
+
+	let min = min.truncate().max(glam::Vec2::NEG_ONE);
+	let max = max.truncate().min(glam::Vec2::ONE);
+
+	(min.cmplt(max).all()).then(|| Vec4::new(min.x, min.y, max.x, max.y))
+}
@@ -0,0 +1,69 @@
+//! Scene Events
+//!
+//! Provides [`SceneEvent`], a notification sent to listeners registered with
+//! [`Scene::on_event`](super::Scene::on_event) when objects and lights are
+//! added, removed or changed, when the pointer clicks or hovers over an
+//! object, or when a [`Selection`](super::Selection) changes. UI layers can
+//! subscribe instead of polling the scene every frame.
+//!
+//! Like outline changes, events are queued and delivered in order at the
+//! start of the next frame, so listeners never run while the scene is being
+//! modified.
+//!
+//! ## Examples
+//!
+//! ```ignore
+//! use oxgl::renderer_3d::SceneEvent;
+//!
+//! let listener = scene.on_event(move |event| match event {
+//!		SceneEvent::Clicked(hit) => set_selected.set(Some(hit.object)),
+//!		SceneEvent::HoverEnter(id) => set_hovered.set(Some(*id)),
+//!		SceneEvent::HoverLeave(_) => set_hovered.set(None),
+//!		_ => {}
+//! });
+//!
+//! // Forward pointer input from the canvas
+//! scene.pointer_move(x, y, width, height);
+//! scene.pointer_click(x, y, width, height);
+//!
+//! // Unsubscribe when the component unmounts
+//! scene.remove_listener(listener);
+//! ```
+//!
+
+use crate::core::{LightId, ObjectId};
+use super::RayHit;
+
+/// A change to a scene or pointer interaction with one of its objects.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SceneEvent {
+	ObjectAdded(ObjectId),
+	ObjectRemoved(ObjectId),
+	LightAdded(LightId),
+	LightRemoved(LightId),
+	/// A light was changed through [`Scene::update_light`](super::Scene::update_light),
+	/// enabled or disabled, renamed or retagged.
+	LightChanged(LightId),
+	/// An object was shown or hidden with
+	/// [`Scene::set_visible`](super::Scene::set_visible).
+	VisibilityChanged { object: ObjectId, visible: bool },
+	/// A tag was added to or removed from an object with
+	/// [`Scene::add_tag`](super::Scene::add_tag) or
+	/// [`Scene::remove_tag`](super::Scene::remove_tag).
+	TagsChanged(ObjectId),
+	/// An object was clicked, see [`Scene::pointer_click`](super::Scene::pointer_click).
+	Clicked(RayHit),
+	/// The pointer moved onto an object, see
+	/// [`Scene::pointer_move`](super::Scene::pointer_move).
+	HoverEnter(ObjectId),
+	/// The pointer moved off an object.
+	HoverLeave(ObjectId),
+	/// An object was added to a [`Selection`](super::Selection).
+	Selected(ObjectId),
+	/// An object was removed from a [`Selection`](super::Selection).
+	Deselected(ObjectId),
+	/// An object's transform was edited with the handles of a
+	/// [`Selection`](super::Selection) or through
+	/// [`Scene::set_property`](super::Scene::set_property).
+	TransformEdited(ObjectId),
+}
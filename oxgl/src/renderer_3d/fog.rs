@@ -0,0 +1,75 @@
+//! Fog
+//!
+//! Provides distance fog for the built-in lit shaders. Set on a scene with
+//! [`Scene::set_fog`](super::Scene::set_fog), it blends surfaces towards a
+//! fog color with distance from the camera so large scenes fade out
+//! instead of clipping at the far plane.
+//!
+//! Custom shaders can opt in with `#include <fog>` and `applyFog`, passing
+//! the result through `toOutput` from the `colorspace` chunk, which fog
+//! includes.
+//!
+//! ## Examples
+//!
+//! ```ignore
+//! use oxgl::renderer_3d::Fog;
+//! use glam::Vec3;
+//!
+//! // Fully fogged from 80 units onwards
+//! scene.set_fog(Fog::Linear { start: 20.0, end: 80.0 }, Vec3::new(0.7, 0.8, 0.9));
+//!
+//! // Or a denser haze that never fully hides distant objects
+//! scene.set_fog(Fog::Exp { density: 0.03 }, Vec3::splat(0.5));
+//! ```
+//!
+
+/// How fog thickens with distance from the camera.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Fog {
+	/// No fog before `start`, full fog from `end`, linear in between.
+	Linear { start: f32, end: f32 },
+	/// Fog amount `1 - e^(-density × distance)`.
+	Exp { density: f32 },
+	/// Fog amount `1 - e^(-(density × distance)²)`, clear close to the
+	/// camera and falling off faster further out.
+	Exp2 { density: f32 },
+}
+
+impl Fog {
+	/// Returns how fogged a point at a distance from the camera is, from
+	/// `0.0` (clear) to `1.0` (fog color only).
+	///
+	/// Matches `applyFog` in the `fog` shader chunk.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use oxgl::renderer_3d::Fog;
+	///
+	/// let fog = Fog::Linear { start: 10.0, end: 20.0 };
+	///
+	/// assert_eq!(fog.factor(5.0), 0.0);
+	/// assert_eq!(fog.factor(15.0), 0.5);
+	/// assert_eq!(fog.factor(30.0), 1.0);
+	/// assert!(Fog::Exp { density: 0.1 }.factor(10.0) > 0.6);
+	/// ```
+	pub fn factor(&self, distance: f32) -> f32 {
+		let amount = match *self {
+			Self::Linear { start, end } => (distance - start) / (end - start).max(f32::EPSILON),
+			Self::Exp { density } => 1.0 - (-density * distance).exp(),
+			Self::Exp2 { density } => 1.0 - (-(density * distance).powi(2)).exp(),
+		};
+
+		amount.clamp(0.0, 1.0)
+	}
+
+	/// Returns the `fogMode` uniform value and the `fogParams` uniform
+	/// as `(start, end, density)`.
+	pub(crate) fn uniforms(&self) -> (i32, [f32; 3]) {
+		match *self {
+			Self::Linear { start, end } => (1, [start, end, 0.0]),
+			Self::Exp { density } => (2, [0.0, 0.0, density]),
+			Self::Exp2 { density } => (3, [0.0, 0.0, density]),
+		}
+	}
+}
@@ -21,47 +21,400 @@
 //!
 //! // Visualize a bounding sphere
 //! gizmos.wire_sphere(&gl, &camera, object_pos, radius, Vec3::new(1.0, 1.0, 0.0));
+//!
+//! // Batch many gizmos into a single draw call
+//! gizmos.begin();
+//! for point in &points {
+//!		gizmos.line(&gl, &camera, *point, *point + Vec3::Y, Vec3::new(0.0, 1.0, 0.0));
+//! }
+//! gizmos.flush(&gl, &camera);
+//!
+//! // Annotate a light with screen-facing text
+//! gizmos.label(&gl, &camera, light.position, "Key light", Vec3::new(1.0, 0.9, 0.6));
 //! ```
 //!
 
-use glam::{Vec3, Mat4};
-use web_sys::{WebGlBuffer, WebGlProgram, WebGl2RenderingContext as GL};
-use std::cell::RefCell;
+use glam::{Mat3, Mat4, Vec3};
+use web_sys::{
+	CanvasRenderingContext2d, HtmlCanvasElement, WebGlBuffer, WebGlProgram,
+	WebGl2RenderingContext as GL, wasm_bindgen::JsCast,
+};
+use std::cell::{Cell, RefCell};
 
-use crate::common::{compile_shader, link_program, Camera};
+use crate::{common::{compile_shader, link_program, Camera, Mesh, Texture}, core::Path};
+use super::background::blend_over;
+use crate::common::render_state::{SavedState, current_depth_func, current_viewport};
 
 const GIZMO_VERT: &str = r#"
+	#include <logdepth>
+
 	attribute vec3 position;
+	attribute vec3 color;
+	// Opacity where visible and where hidden behind geometry
+	attribute vec2 alpha;
 	uniform mat4 view;
 	uniform mat4 projection;
 	uniform mat4 model;
+	uniform float occludedPass;
+
+	varying vec3 vColor;
+	varying float vAlpha;
 	
 	void main() {
-		gl_Position = projection * view * model * vec4(position, 1.0);
+		vColor = color;
+		vAlpha = mix(alpha.x, alpha.y, occludedPass);
+		gl_Position = logDepth(projection * view * model * vec4(position, 1.0));
 	}
 "#;
 
 const GIZMO_FRAG: &str = r#"
 	precision mediump float;
-	uniform vec3 color;
+	varying vec3 vColor;
+	varying float vAlpha;
 	
 	void main() {
-		gl_FragColor = vec4(color, 1.0);
+		gl_FragColor = vec4(vColor, vAlpha);
 	}
 "#;
 
+const VECTOR_VERT: &str = r#"
+	#include <logdepth>
+
+	attribute float end;
+	attribute vec3 position;
+	attribute vec3 direction;
+	uniform mat4 view;
+	uniform mat4 projection;
+	uniform mat4 model;
+	uniform mat3 directionMatrix;
+	uniform float length;
+	uniform vec3 color;
+
+	varying vec3 vColor;
+	varying float vAlpha;
+
+	void main() {
+		// Lines fade in from the surface so they read as pointing away
+		vColor = color * mix(0.4, 1.0, end);
+		vAlpha = 1.0;
+
+		vec3 world = (model * vec4(position, 1.0)).xyz;
+		vec3 dir = directionMatrix * direction;
+
+		if (dot(dir, dir) > 0.0) {
+			world += normalize(dir) * length * end;
+		}
+
+		gl_Position = logDepth(projection * view * vec4(world, 1.0));
+	}
+"#;
+
+const GRID_VERT: &str = r#"
+	#include <logdepth>
+
+	attribute vec2 position;
+	uniform mat4 view;
+	uniform mat4 projection;
+	uniform vec3 cameraPosition;
+	uniform float extent;
+
+	varying vec3 vWorld;
+
+	void main() {
+		// The plane follows the camera, the lines stay put in world space
+		vWorld = vec3(cameraPosition.x + position.x * extent, 0.0, cameraPosition.z + position.y * extent);
+		gl_Position = logDepth(projection * view * vec4(vWorld, 1.0));
+	}
+"#;
+
+const GRID_FRAG: &str = r#"
+	#extension GL_OES_standard_derivatives : enable
+	precision highp float;
+
+	uniform vec3 cameraPosition;
+	uniform vec3 color;
+	uniform float cellSize;
+	uniform float majorSize;
+	uniform float fadeDistance;
+
+	varying vec3 vWorld;
+
+	// Coverage of one-pixel lines every `size` units, fading out where
+	// cells get too small on screen to draw without aliasing
+	float lines(vec2 coord, float size) {
+		vec2 scaled = coord / size;
+		vec2 width = fwidth(scaled);
+		vec2 offset = abs(fract(scaled - 0.5) - 0.5) / width;
+		float line = 1.0 - min(min(offset.x, offset.y), 1.0);
+
+		return line * (1.0 - smoothstep(0.1, 0.3, max(width.x, width.y)));
+	}
+
+	void main() {
+		vec2 coord = vWorld.xz;
+		vec2 width = fwidth(coord);
+		float minor = lines(coord, cellSize) * 0.5;
+		float major = lines(coord, majorSize);
+		float fade = 1.0 - smoothstep(fadeDistance * 0.5, fadeDistance, distance(vWorld, cameraPosition));
+
+		vec3 rgb = color;
+		float alpha = max(minor, major);
+
+		// The X axis runs along z = 0 and the Z axis along x = 0
+		float xAxis = 1.0 - min(abs(coord.y) / width.y, 1.0);
+		float zAxis = 1.0 - min(abs(coord.x) / width.x, 1.0);
+
+		if (xAxis > 0.0) {
+			rgb = mix(rgb, vec3(0.9, 0.2, 0.2), xAxis);
+			alpha = max(alpha, xAxis);
+		}
+		if (zAxis > 0.0) {
+			rgb = mix(rgb, vec3(0.2, 0.4, 0.9), zAxis);
+			alpha = max(alpha, zAxis);
+		}
+
+		alpha *= fade;
+
+		if (alpha < 0.01) {
+			discard;
+		}
+
+		gl_FragColor = vec4(rgb, alpha);
+	}
+"#;
+
+/// Floats per batched vertex: position (3) + color (3) + visible and
+/// occluded alpha (2).
+const BATCH_STRIDE: usize = 8;
+
+const LABEL_VERT: &str = r#"
+	#include <logdepth>
+
+	attribute vec3 position;
+	attribute vec2 offset;
+	attribute vec2 uv;
+	attribute vec3 color;
+	uniform mat4 view;
+	uniform mat4 projection;
+	uniform vec2 viewport;
+
+	varying vec2 vUv;
+	varying vec3 vColor;
+
+	void main() {
+		vUv = uv;
+		vColor = color;
+
+		// Offsets are in pixels, applied after projection to face the screen
+		vec4 clip = projection * view * vec4(position, 1.0);
+		clip.xy += offset / viewport * 2.0 * clip.w;
+		gl_Position = logDepth(clip);
+	}
+"#;
+
+const LABEL_FRAG: &str = r#"
+	precision mediump float;
+	uniform sampler2D atlas;
+
+	varying vec2 vUv;
+	varying vec3 vColor;
+
+	void main() {
+		float alpha = texture2D(atlas, vUv).a;
+
+		if (alpha < 0.01) {
+			discard;
+		}
+
+		gl_FragColor = vec4(vColor, alpha);
+	}
+"#;
+
+/// Floats per label vertex: position (3) + offset (2) + uv (2) + color (3).
+const LABEL_STRIDE: usize = 10;
+
+/// First character in the glyph atlas. The atlas covers printable ASCII.
+const ATLAS_FIRST_CHAR: u32 = 32;
+const ATLAS_COLUMNS: u32 = 16;
+const ATLAS_ROWS: u32 = 6;
+const LABEL_FONT: &str = "14px monospace";
+const LABEL_LINE_HEIGHT: f32 = 18.0;
+/// Pixel offset of a label from its anchor point.
+const LABEL_MARGIN: f32 = 4.0;
+
+/// Monospace glyph atlas for printable ASCII, rasterized with a 2D canvas.
+struct GlyphAtlas {
+	texture: Texture,
+	cell_width: f32,
+	cell_height: f32,
+}
+
+impl GlyphAtlas {
+	fn new(gl: &GL) -> Result<Self, String> {
+		let document = web_sys::window()
+			.and_then(|w| w.document())
+			.ok_or("No document")?;
+
+		let canvas = document
+			.create_element("canvas")
+			.map_err(|_| "Failed to create atlas canvas")?
+			.dyn_into::<HtmlCanvasElement>()
+			.map_err(|_| "Failed to cast atlas canvas")?;
+
+		let context = canvas
+			.get_context("2d")
+			.ok()
+			.flatten()
+			.and_then(|c| c.dyn_into::<CanvasRenderingContext2d>().ok())
+			.ok_or("Failed to get 2D context")?;
+
+		context.set_font(LABEL_FONT);
+
+		let cell_width = context
+			.measure_text("M")
+			.map_err(|_| "Failed to measure glyphs")?
+			.width()
+			.ceil() as f32;
+		let cell_height = LABEL_LINE_HEIGHT;
+
+		canvas.set_width(cell_width as u32 * ATLAS_COLUMNS);
+		canvas.set_height(cell_height as u32 * ATLAS_ROWS);
+
+		// Resizing the canvas resets the context state
+		context.set_font(LABEL_FONT);
+		context.set_fill_style_str("white");
+		context.set_text_baseline("middle");
+
+		for i in 0..ATLAS_COLUMNS * ATLAS_ROWS {
+			let Some(c) = char::from_u32(ATLAS_FIRST_CHAR + i) else { continue };
+			let x = (i % ATLAS_COLUMNS) as f32 * cell_width;
+			let y = (i / ATLAS_COLUMNS) as f32 * cell_height + cell_height * 0.5;
+
+			let _ = context.fill_text(&c.to_string(), x as f64, y as f64);
+		}
+
+		Ok(Self {
+			texture: Texture::from_canvas(gl, &canvas)?,
+			cell_width,
+			cell_height,
+		})
+	}
+
+	/// Returns the atlas UV rectangle `(u0, v0, u1, v1)` of a character.
+	///
+	/// Characters outside the atlas map to `?`.
+	fn uv(&self, c: char) -> (f32, f32, f32, f32) {
+		let count = ATLAS_COLUMNS * ATLAS_ROWS;
+		let index = (c as u32)
+			.checked_sub(ATLAS_FIRST_CHAR)
+			.filter(|&i| i < count)
+			.unwrap_or('?' as u32 - ATLAS_FIRST_CHAR);
+
+		let (column, row) = ((index % ATLAS_COLUMNS) as f32, (index / ATLAS_COLUMNS) as f32);
+		let (columns, rows) = (ATLAS_COLUMNS as f32, ATLAS_ROWS as f32);
+
+		(column / columns, row / rows, (column + 1.0) / columns, (row + 1.0) / rows)
+	}
+}
+
+/// How gizmos interact with the depth of the scene.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum GizmoDepth {
+	/// Hidden behind geometry, like any other object.
+	#[default]
+	Test,
+	/// Drawn through geometry, with the hidden parts at the given fraction
+	/// of their opacity.
+	XRay(f32),
+	/// Drawn over everything at full opacity.
+	AlwaysOnTop,
+}
+
+/// Depth behavior and opacity for gizmos, see [`GizmoRenderer::set_style`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GizmoStyle {
+	pub depth: GizmoDepth,
+	/// Opacity of the lines, from `0.0` to `1.0`.
+	pub opacity: f32,
+}
+
+impl Default for GizmoStyle {
+	fn default() -> Self {
+		Self { depth: GizmoDepth::Test, opacity: 1.0 }
+	}
+}
+
+impl GizmoStyle {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	pub fn with_depth(mut self, depth: GizmoDepth) -> Self {
+		self.depth = depth;
+		self
+	}
+
+	pub fn with_opacity(mut self, opacity: f32) -> Self {
+		self.opacity = opacity;
+		self
+	}
+
+	/// Returns the opacity where the lines are visible and where they are
+	/// hidden behind geometry.
+	fn alphas(&self) -> [f32; 2] {
+		let hidden = match self.depth {
+			GizmoDepth::Test => 0.0,
+			GizmoDepth::XRay(fraction) => fraction,
+			GizmoDepth::AlwaysOnTop => 1.0,
+		};
+
+		[self.opacity, self.opacity * hidden]
+	}
+}
+
 /// Immediate-mode debug gizmo renderer.
 ///
 /// Provides methods for drawing wireframe primitives useful for debugging
 /// and editor visualization. All gizmos are rendered as lines without
 /// depth writing by default.
 ///
+/// ## Batching
+///
+/// By default every call issues its own draw. Between [`begin`](Self::begin)
+/// and [`flush`](Self::flush), calls are instead queued into a single growable
+/// vertex buffer with per-vertex colors and drawn at once.
+///
+/// ## Depth and Opacity
+///
+/// Lines are drawn with the current [`GizmoStyle`], which can be changed
+/// between calls, also while batching. Lines hidden behind geometry can be
+/// left out, drawn faintly or drawn in full, see [`GizmoDepth`]. Hidden
+/// parts are found with a second pass using a `GREATER` depth test, so
+/// they need depth testing to be enabled. Labels ignore the style.
+///
 pub struct GizmoRenderer {
 	program: WebGlProgram,
 	line_buffer: WebGlBuffer,
+	batch_buffer: WebGlBuffer,
+	batch_capacity: Cell<usize>,
+	batch: RefCell<Vec<f32>>,
+	batching: Cell<bool>,
+	style: Cell<GizmoStyle>,
+	/// Whether any batched line is drawn where it's hidden.
+	batch_occluded: Cell<bool>,
 	batch_vertices: RefCell<Vec<f32>>,
 	unit_sphere_vertices: Vec<f32>,
 	unit_cube_vertices: Vec<f32>,
+	label_program: WebGlProgram,
+	label_buffer: WebGlBuffer,
+	labels: RefCell<Vec<f32>>,
+	atlas: Option<GlyphAtlas>,
+	vector_program: WebGlProgram,
+	/// The two ends of a vertex vector line, `0.0` and `1.0`.
+	vector_ends: WebGlBuffer,
+	grid_program: WebGlProgram,
+	/// Two triangles covering -1 to 1 on both axes.
+	grid_quad: WebGlBuffer,
 }
 
 impl GizmoRenderer {
@@ -79,13 +432,61 @@ impl GizmoRenderer {
 		let frag = compile_shader(gl, GIZMO_FRAG, GL::FRAGMENT_SHADER).unwrap();
 		let program = link_program(gl, &vert, &frag).unwrap();
 		let line_buffer = gl.create_buffer().expect("Failed to create gizmo buffer");
+		let batch_buffer = gl.create_buffer().expect("Failed to create gizmo batch buffer");
+
+		let label_vert = compile_shader(gl, LABEL_VERT, GL::VERTEX_SHADER).unwrap();
+		let label_frag = compile_shader(gl, LABEL_FRAG, GL::FRAGMENT_SHADER).unwrap();
+		let label_program = link_program(gl, &label_vert, &label_frag).unwrap();
+		let label_buffer = gl.create_buffer().expect("Failed to create gizmo label buffer");
+		let atlas = GlyphAtlas::new(gl)
+			.map_err(|e| log::warn!("Gizmo labels unavailable: {}", e))
+			.ok();
+
+		let vector_vert = compile_shader(gl, VECTOR_VERT, GL::VERTEX_SHADER).unwrap();
+		let vector_frag = compile_shader(gl, GIZMO_FRAG, GL::FRAGMENT_SHADER).unwrap();
+		let vector_program = link_program(gl, &vector_vert, &vector_frag).unwrap();
+		let vector_ends = gl.create_buffer().expect("Failed to create gizmo vector buffer");
+
+		let ends = [0.0f32, 1.0];
+
+		gl.bind_buffer(GL::ARRAY_BUFFER, Some(&vector_ends));
+		unsafe {
+			let view = js_sys::Float32Array::view(&ends);
+			gl.buffer_data_with_array_buffer_view(GL::ARRAY_BUFFER, &view, GL::STATIC_DRAW);
+		}
+
+		let grid_vert = compile_shader(gl, GRID_VERT, GL::VERTEX_SHADER).unwrap();
+		let grid_frag = compile_shader(gl, GRID_FRAG, GL::FRAGMENT_SHADER).unwrap();
+		let grid_program = link_program(gl, &grid_vert, &grid_frag).unwrap();
+		let grid_quad = gl.create_buffer().expect("Failed to create gizmo grid buffer");
+		let corners = [-1.0f32, -1.0, -1.0, 1.0, 1.0, 1.0, -1.0, -1.0, 1.0, 1.0, 1.0, -1.0];
+
+		gl.bind_buffer(GL::ARRAY_BUFFER, Some(&grid_quad));
+		unsafe {
+			let view = js_sys::Float32Array::view(&corners);
+			gl.buffer_data_with_array_buffer_view(GL::ARRAY_BUFFER, &view, GL::STATIC_DRAW);
+		}
 
 		Self { 
 			program, 
 			line_buffer,
+			batch_buffer,
+			batch_capacity: Cell::new(0),
+			batch: RefCell::new(Vec::new()),
+			batching: Cell::new(false),
+			style: Cell::new(GizmoStyle::default()),
+			batch_occluded: Cell::new(false),
 			batch_vertices: RefCell::new(Vec::with_capacity(1024)),
 			unit_sphere_vertices: Self::generate_sphere_vertices(24),
 			unit_cube_vertices: Self::generate_cube_vertices(),
+			label_program,
+			label_buffer,
+			labels: RefCell::new(Vec::new()),
+			atlas,
+			vector_program,
+			vector_ends,
+			grid_program,
+			grid_quad,
 		}
 	}
 
@@ -165,11 +566,26 @@ impl GizmoRenderer {
 		if let Some(loc) = gl.get_uniform_location(&self.program, "projection") {
 			gl.uniform_matrix4fv_with_f32_array(Some(&loc), false, &camera.projection_matrix().to_cols_array());
 		}
+		if let Some(loc) = gl.get_uniform_location(&self.program, "logDepthFactor") {
+			gl.uniform1f(Some(&loc), camera.log_depth_factor());
+		}
 		if let Some(loc) = gl.get_uniform_location(&self.program, "model") {
 			gl.uniform_matrix4fv_with_f32_array(Some(&loc), false, &model.to_cols_array());
 		}
-		if let Some(loc) = gl.get_uniform_location(&self.program, "color") {
-			gl.uniform3fv_with_f32_array(Some(&loc), &color.to_array());
+
+		let color_loc = gl.get_attrib_location(&self.program, "color");
+
+		if color_loc >= 0 {
+			gl.disable_vertex_attrib_array(color_loc as u32);
+			gl.vertex_attrib3f(color_loc as u32, color.x, color.y, color.z);
+		}
+
+		let alpha_loc = gl.get_attrib_location(&self.program, "alpha");
+
+		if alpha_loc >= 0 {
+			let [visible, hidden] = self.style.get().alphas();
+			gl.disable_vertex_attrib_array(alpha_loc as u32);
+			gl.vertex_attrib2f(alpha_loc as u32, visible, hidden);
 		}
 
 		let pos_loc = gl.get_attrib_location(&self.program, "position");
@@ -181,6 +597,333 @@ impl GizmoRenderer {
 		}
 	}
 
+	/// Draws line-list vertices immediately, or queues them when batching.
+	///
+	/// `vertices` are positions in model space, transformed by `model`.
+	fn submit(&self, gl: &GL, camera: &Camera, vertices: &[f32], model: Mat4, color: Vec3) {
+		let [visible, hidden] = self.style.get().alphas();
+
+		if self.batching.get() {
+			let mut batch = self.batch.borrow_mut();
+
+			for p in vertices.chunks_exact(3) {
+				let world = model.transform_point3(Vec3::new(p[0], p[1], p[2]));
+				batch.extend_from_slice(&[world.x, world.y, world.z, color.x, color.y, color.z, visible, hidden]);
+			}
+
+			if hidden > 0.0 {
+				self.batch_occluded.set(true);
+			}
+		} else {
+			self.upload_vertices(gl, vertices);
+			self.setup_draw(gl, camera, model, color);
+			self.draw_lines(gl, (vertices.len() / 3) as i32, visible < 1.0, hidden > 0.0);
+		}
+	}
+
+	/// Draws bound line vertices, then again where they are hidden if
+	/// `occluded` is set and depth testing is on.
+	fn draw_lines(&self, gl: &GL, count: i32, translucent: bool, occluded: bool) {
+		let program = &self.program;
+		let occluded = occluded && gl.is_enabled(GL::DEPTH_TEST);
+		let pass_loc = gl.get_uniform_location(program, "occludedPass");
+
+		gl.uniform1f(pass_loc.as_ref(), 0.0);
+
+		if !translucent && !occluded {
+			gl.draw_arrays(GL::LINES, 0, count);
+			return;
+		}
+
+		// Lines that can be seen through mustn't hide each other
+		let state = SavedState::capture(gl);
+
+		blend_over(gl);
+		gl.depth_mask(false);
+		gl.draw_arrays(GL::LINES, 0, count);
+
+		if occluded {
+			let depth_func = current_depth_func(gl);
+
+			gl.depth_func(GL::GREATER);
+			gl.uniform1f(pass_loc.as_ref(), 1.0);
+			gl.draw_arrays(GL::LINES, 0, count);
+			gl.depth_func(depth_func);
+		}
+
+		state.restore(gl);
+	}
+
+	/// Sets the depth behavior and opacity of the following gizmos, and
+	/// returns the previous style.
+	///
+	/// # Examples
+	///
+	/// ```ignore
+	/// use oxgl::renderer_3d::{GizmoDepth, GizmoStyle};
+	///
+	/// // Show the selected object's bounds through walls
+	/// let previous = gizmos.set_style(GizmoStyle::new().with_depth(GizmoDepth::XRay(0.3)));
+	/// gizmos.wire_aabb(&gl, &camera, bounds.min, bounds.max, Vec3::new(1.0, 0.6, 0.0));
+	/// gizmos.set_style(previous);
+	/// ```
+	pub fn set_style(&self, style: GizmoStyle) -> GizmoStyle {
+		self.style.replace(style)
+	}
+
+	/// Returns the current depth behavior and opacity.
+	pub fn style(&self) -> GizmoStyle {
+		self.style.get()
+	}
+
+	/// Draws with a style for the duration of `draw`, then restores the
+	/// previous one.
+	///
+	/// ```ignore
+	/// gizmos.with_style(GizmoStyle::new().with_opacity(0.4), |gizmos| {
+	///		gizmos.grid(&gl, &camera, 10.0, 10, Vec3::splat(0.5));
+	/// });
+	/// ```
+	pub fn with_style(&self, style: GizmoStyle, draw: impl FnOnce(&Self)) {
+		let previous = self.set_style(style);
+		draw(self);
+		self.set_style(previous);
+	}
+
+	/// Starts queueing gizmos into a batch instead of drawing them.
+	///
+	/// Any previously queued but unflushed gizmos are discarded.
+	pub fn begin(&self) {
+		self.batch.borrow_mut().clear();
+		self.batch_occluded.set(false);
+		self.labels.borrow_mut().clear();
+		self.batching.set(true);
+	}
+
+	/// Returns `true` between [`begin`](Self::begin) and [`flush`](Self::flush).
+	pub fn is_batching(&self) -> bool {
+		self.batching.get()
+	}
+
+	/// Draws all queued gizmos in a single draw call and ends the batch.
+	///
+	/// Queued labels are drawn afterwards in one additional draw call. The
+	/// batch buffer only grows, and is updated in place when the queued data
+	/// fits.
+	pub fn flush(&self, gl: &GL, camera: &Camera) {
+		self.batching.set(false);
+		self.draw_line_batch(gl, camera);
+		self.draw_labels(gl, camera, &self.labels.borrow());
+	}
+
+	/// Queues the gizmos drawn in `draw` into a batch of their own and
+	/// flushes it, leaving any batch the caller has open untouched.
+	///
+	/// Used by overlays that draw with their own camera and viewport.
+	pub(crate) fn isolated(&self, gl: &GL, camera: &Camera, draw: impl FnOnce(&Self)) {
+		let batch = self.batch.take();
+		let labels = self.labels.take();
+		let occluded = self.batch_occluded.get();
+		let batching = self.batching.get();
+
+		self.begin();
+		draw(self);
+		self.flush(gl, camera);
+
+		*self.batch.borrow_mut() = batch;
+		*self.labels.borrow_mut() = labels;
+		self.batch_occluded.set(occluded);
+		self.batching.set(batching);
+	}
+
+	fn draw_line_batch(&self, gl: &GL, camera: &Camera) {
+		let batch = self.batch.borrow();
+
+		if batch.is_empty() {
+			return;
+		}
+
+		gl.bind_buffer(GL::ARRAY_BUFFER, Some(&self.batch_buffer));
+
+		let data = unsafe {
+			std::slice::from_raw_parts(batch.as_ptr() as *const u8, std::mem::size_of_val(batch.as_slice()))
+		};
+
+		if data.len() > self.batch_capacity.get() {
+			let capacity = data.len().next_power_of_two();
+			gl.buffer_data_with_i32(GL::ARRAY_BUFFER, capacity as i32, GL::DYNAMIC_DRAW);
+			self.batch_capacity.set(capacity);
+		}
+
+		gl.buffer_sub_data_with_i32_and_u8_array(GL::ARRAY_BUFFER, 0, data);
+
+		gl.use_program(Some(&self.program));
+
+		if let Some(loc) = gl.get_uniform_location(&self.program, "view") {
+			gl.uniform_matrix4fv_with_f32_array(Some(&loc), false, &camera.view_matrix().to_cols_array());
+		}
+		if let Some(loc) = gl.get_uniform_location(&self.program, "projection") {
+			gl.uniform_matrix4fv_with_f32_array(Some(&loc), false, &camera.projection_matrix().to_cols_array());
+		}
+		if let Some(loc) = gl.get_uniform_location(&self.program, "logDepthFactor") {
+			gl.uniform1f(Some(&loc), camera.log_depth_factor());
+		}
+		if let Some(loc) = gl.get_uniform_location(&self.program, "model") {
+			gl.uniform_matrix4fv_with_f32_array(Some(&loc), false, &Mat4::IDENTITY.to_cols_array());
+		}
+
+		let stride = (BATCH_STRIDE * 4) as i32;
+		let pos_loc = gl.get_attrib_location(&self.program, "position");
+		let color_loc = gl.get_attrib_location(&self.program, "color");
+		let alpha_loc = gl.get_attrib_location(&self.program, "alpha");
+
+		if pos_loc >= 0 {
+			gl.enable_vertex_attrib_array(pos_loc as u32);
+			gl.vertex_attrib_pointer_with_i32(pos_loc as u32, 3, GL::FLOAT, false, stride, 0);
+		}
+		if color_loc >= 0 {
+			gl.enable_vertex_attrib_array(color_loc as u32);
+			gl.vertex_attrib_pointer_with_i32(color_loc as u32, 3, GL::FLOAT, false, stride, 12);
+		}
+		if alpha_loc >= 0 {
+			gl.enable_vertex_attrib_array(alpha_loc as u32);
+			gl.vertex_attrib_pointer_with_i32(alpha_loc as u32, 2, GL::FLOAT, false, stride, 24);
+		}
+
+		let translucent = batch.chunks_exact(BATCH_STRIDE).any(|vertex| vertex[6] < 1.0);
+		self.draw_lines(gl, (batch.len() / BATCH_STRIDE) as i32, translucent, self.batch_occluded.get());
+
+		if color_loc >= 0 {
+			gl.disable_vertex_attrib_array(color_loc as u32);
+		}
+		if alpha_loc >= 0 {
+			gl.disable_vertex_attrib_array(alpha_loc as u32);
+		}
+	}
+
+	/// Draws screen-facing text anchored at a world position.
+	///
+	/// Text is rendered from a monospace glyph atlas covering printable ASCII,
+	/// offset slightly up and to the right of `position`. Other characters are
+	/// shown as `?`, and `\n` starts a new line.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// gizmos.label(&gl, &camera, object_pos, &format!("y = {:.2}", object_pos.y), Vec3::ONE);
+	/// ```
+	pub fn label(&self, gl: &GL, camera: &Camera, position: Vec3, text: &str, color: Vec3) {
+		self.push_label(gl, camera, position, text, color, false);
+	}
+
+	/// Draws screen-facing text centered on a world position.
+	///
+	/// Like [`label`](Self::label), but each line is centered horizontally
+	/// and the block of lines vertically.
+	pub fn label_centered(&self, gl: &GL, camera: &Camera, position: Vec3, text: &str, color: Vec3) {
+		self.push_label(gl, camera, position, text, color, true);
+	}
+
+	fn push_label(&self, gl: &GL, camera: &Camera, position: Vec3, text: &str, color: Vec3, centered: bool) {
+		let Some(atlas) = &self.atlas else { return };
+
+		let mut vertices = Vec::new();
+		let lines = text.lines().count().max(1);
+		let base = if centered { -(lines as f32) * atlas.cell_height * 0.5 } else { LABEL_MARGIN };
+
+		for (row, line) in text.lines().enumerate() {
+			let top = base + (lines - row) as f32 * atlas.cell_height;
+			let start = if centered { -(line.chars().count() as f32) * atlas.cell_width * 0.5 } else { LABEL_MARGIN };
+
+			for (column, c) in line.chars().enumerate() {
+				let left = start + column as f32 * atlas.cell_width;
+				let (right, bottom) = (left + atlas.cell_width, top - atlas.cell_height);
+				let (u0, v0, u1, v1) = atlas.uv(c);
+
+				for (x, y, u, v) in [
+					(left, top, u0, v0), (left, bottom, u0, v1), (right, bottom, u1, v1),
+					(left, top, u0, v0), (right, bottom, u1, v1), (right, top, u1, v0),
+				] {
+					vertices.extend_from_slice(&[
+						position.x, position.y, position.z, x, y, u, v, color.x, color.y, color.z,
+					]);
+				}
+			}
+		}
+
+		if self.batching.get() {
+			self.labels.borrow_mut().extend_from_slice(&vertices);
+		} else {
+			self.draw_labels(gl, camera, &vertices);
+		}
+	}
+
+	fn draw_labels(&self, gl: &GL, camera: &Camera, vertices: &[f32]) {
+		let Some(atlas) = &self.atlas else { return };
+
+		if vertices.is_empty() {
+			return;
+		}
+
+		let program = &self.label_program;
+
+		gl.use_program(Some(program));
+		gl.bind_buffer(GL::ARRAY_BUFFER, Some(&self.label_buffer));
+
+		let data = unsafe {
+			std::slice::from_raw_parts(vertices.as_ptr() as *const u8, std::mem::size_of_val(vertices))
+		};
+
+		gl.buffer_data_with_u8_array(GL::ARRAY_BUFFER, data, GL::DYNAMIC_DRAW);
+
+		if let Some(loc) = gl.get_uniform_location(program, "view") {
+			gl.uniform_matrix4fv_with_f32_array(Some(&loc), false, &camera.view_matrix().to_cols_array());
+		}
+		if let Some(loc) = gl.get_uniform_location(program, "projection") {
+			gl.uniform_matrix4fv_with_f32_array(Some(&loc), false, &camera.projection_matrix().to_cols_array());
+		}
+		if let Some(loc) = gl.get_uniform_location(program, "logDepthFactor") {
+			gl.uniform1f(Some(&loc), camera.log_depth_factor());
+		}
+		if let Some(loc) = gl.get_uniform_location(program, "viewport") {
+			// Offsets are in pixels of the viewport being drawn into
+			let [_, _, width, height] = current_viewport(gl);
+			gl.uniform2f(Some(&loc), width as f32, height as f32);
+		}
+		if let Some(loc) = gl.get_uniform_location(program, "atlas") {
+			gl.uniform1i(Some(&loc), 0);
+		}
+
+		atlas.texture.bind(gl, 0);
+
+		let stride = (LABEL_STRIDE * 4) as i32;
+		let attributes = [("position", 3, 0), ("offset", 2, 12), ("uv", 2, 20), ("color", 3, 28)];
+		let mut enabled = Vec::with_capacity(attributes.len());
+
+		for (name, size, offset) in attributes {
+			let loc = gl.get_attrib_location(program, name);
+
+			if loc >= 0 {
+				gl.enable_vertex_attrib_array(loc as u32);
+				gl.vertex_attrib_pointer_with_i32(loc as u32, size, GL::FLOAT, false, stride, offset);
+
+				if name != "position" {
+					enabled.push(loc as u32);
+				}
+			}
+		}
+
+		let state = SavedState::capture(gl);
+
+		blend_over(gl);
+		gl.draw_arrays(GL::TRIANGLES, 0, (vertices.len() / LABEL_STRIDE) as i32);
+		state.restore(gl);
+
+		for loc in enabled {
+			gl.disable_vertex_attrib_array(loc);
+		}
+	}
+
 	/// Draws a single line segment.
 	///
 	/// # Examples
@@ -199,9 +942,7 @@ impl GizmoRenderer {
 	pub fn line(&self, gl: &GL, camera: &Camera, from: Vec3, to: Vec3, color: Vec3) {
 		let vertices = [from.x, from.y, from.z, to.x, to.y, to.z];
 
-		self.upload_vertices(gl, &vertices);
-		self.setup_draw(gl, camera, Mat4::IDENTITY, color);
-		gl.draw_arrays(GL::LINES, 0, 2);
+		self.submit(gl, camera, &vertices, Mat4::IDENTITY, color);
 	}
 
 	/// Draws a directional arrow with an arrowhead.
@@ -259,9 +1000,7 @@ impl GizmoRenderer {
 			]);
 		}
 
-		self.upload_vertices(gl, &self.batch_vertices.borrow());
-		self.setup_draw(gl, camera, Mat4::IDENTITY, color);
-		gl.draw_arrays(GL::LINES, 0, 10);
+		self.submit(gl, camera, &self.batch_vertices.borrow(), Mat4::IDENTITY, color);
 	}
 
 	/// Draws a wireframe cube.
@@ -277,14 +1016,58 @@ impl GizmoRenderer {
 	/// gizmos.wire_cube(&gl, &camera, object.position, object.bounds, Vec3::new(0.0, 1.0, 1.0));
 	/// ```
 	pub fn wire_cube(&self, gl: &GL, camera: &Camera, center: Vec3, size: f32, color: Vec3) {
-		self.upload_vertices(gl, &self.unit_cube_vertices);
 		let model = Mat4::from_scale_rotation_translation(
 			Vec3::splat(size),
 			glam::Quat::IDENTITY,
 			center
 		);
-		self.setup_draw(gl, camera, model, color);
-		gl.draw_arrays(GL::LINES, 0, 24);
+		self.submit(gl, camera, &self.unit_cube_vertices, model, color);
+	}
+
+	/// Draws a wireframe cone.
+	///
+	/// The cone's apex is at `apex`, opening along `direction` with the given
+	/// `length` and half-angle in radians. Renders the base circle and four
+	/// lines from the apex to the rim.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use glam::Vec3;
+	///
+	/// // Visualize a spot light's 30° cone
+	/// gizmos.wire_cone(&gl, &camera, light.position, light.direction, 5.0, 15f32.to_radians(), Vec3::new(1.0, 0.8, 0.0));
+	/// ```
+	#[allow(clippy::too_many_arguments)]
+	pub fn wire_cone(&self, gl: &GL, camera: &Camera, apex: Vec3, direction: Vec3, length: f32, half_angle: f32, color: Vec3) {
+		const SEGMENTS: usize = 24;
+
+		let dir = direction.normalize();
+		let (perp, perp2) = perpendicular_basis(dir);
+
+		let center = apex + dir * length;
+		let radius = length * half_angle.tan();
+		let rim = |i: usize| {
+			let a = (i as f32 / SEGMENTS as f32) * std::f32::consts::TAU;
+			center + (perp * a.cos() + perp2 * a.sin()) * radius
+		};
+
+		{
+			let mut verts = self.batch_vertices.borrow_mut();
+			verts.clear();
+
+			for i in 0..SEGMENTS {
+				let (p1, p2) = (rim(i), rim(i + 1));
+				verts.extend_from_slice(&[p1.x, p1.y, p1.z, p2.x, p2.y, p2.z]);
+			}
+
+			for i in (0..SEGMENTS).step_by(SEGMENTS / 4) {
+				let p = rim(i);
+				verts.extend_from_slice(&[apex.x, apex.y, apex.z, p.x, p.y, p.z]);
+			}
+		}
+
+		self.submit(gl, camera, &self.batch_vertices.borrow(), Mat4::IDENTITY, color);
 	}
 
 	/// Draws a wireframe sphere.
@@ -304,17 +1087,189 @@ impl GizmoRenderer {
 	/// gizmos.wire_sphere(&gl, &camera, light.position, light.range, Vec3::new(1.0, 1.0, 0.0));
 	/// ```
 	pub fn wire_sphere(&self, gl: &GL, camera: &Camera, center: Vec3, radius: f32, color: Vec3) {
-		self.upload_vertices(gl, &self.unit_sphere_vertices);
 		let model = Mat4::from_scale_rotation_translation(
 			Vec3::splat(radius),
 			glam::Quat::IDENTITY,
 			center
 		);
-		self.setup_draw(gl, camera, model, color);
-		gl.draw_arrays(GL::LINES, 0, (24 * 6) as i32);
+		self.submit(gl, camera, &self.unit_sphere_vertices, model, color);
+	}
+
+	/// Draws a wireframe axis-aligned box between two corners.
+	///
+	/// Unlike [`wire_cube`](Self::wire_cube), the box may have a different
+	/// size on every axis.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use glam::Vec3;
+	///
+	/// gizmos.wire_aabb(&gl, &camera, Vec3::new(-1.0, 0.0, -0.5), Vec3::new(1.0, 2.0, 0.5), Vec3::new(0.0, 1.0, 1.0));
+	/// ```
+	pub fn wire_aabb(&self, gl: &GL, camera: &Camera, min: Vec3, max: Vec3, color: Vec3) {
+		let model = Mat4::from_scale_rotation_translation(
+			max - min,
+			glam::Quat::IDENTITY,
+			(min + max) * 0.5
+		);
+		self.submit(gl, camera, &self.unit_cube_vertices, model, color);
+	}
+
+	/// Draws a wireframe box transformed by a matrix.
+	///
+	/// The unit cube from -0.5 to 0.5 is transformed by `model`, so rotated
+	/// and non-uniformly scaled boxes can be shown.
+	pub fn wire_box(&self, gl: &GL, camera: &Camera, model: Mat4, color: Vec3) {
+		self.submit(gl, camera, &self.unit_cube_vertices, model, color);
+	}
+
+	/// Draws a circle in the plane perpendicular to `normal`.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use glam::Vec3;
+	///
+	/// // Ground ring around an object
+	/// gizmos.circle(&gl, &camera, object_pos, Vec3::Y, 1.5, Vec3::new(1.0, 1.0, 0.0));
+	/// ```
+	pub fn circle(&self, gl: &GL, camera: &Camera, center: Vec3, normal: Vec3, radius: f32, color: Vec3) {
+		const SEGMENTS: usize = 32;
+
+		let (perp, perp2) = perpendicular_basis(normal.normalize());
+
+		{
+			let mut verts = self.batch_vertices.borrow_mut();
+			verts.clear();
+			push_arc(&mut verts, center, perp, perp2, radius, 0.0, std::f32::consts::TAU, SEGMENTS);
+		}
+
+		self.submit(gl, camera, &self.batch_vertices.borrow(), Mat4::IDENTITY, color);
+	}
+
+	/// Draws a wireframe capsule between two points.
+	///
+	/// Renders a circle at each end, four side lines, and the hemispherical
+	/// caps as half circles.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use glam::Vec3;
+	///
+	/// // Character collider
+	/// gizmos.wire_capsule(&gl, &camera, feet + Vec3::Y * 0.4, feet + Vec3::Y * 1.4, 0.4, Vec3::new(0.0, 1.0, 0.0));
+	/// ```
+	pub fn wire_capsule(&self, gl: &GL, camera: &Camera, start: Vec3, end: Vec3, radius: f32, color: Vec3) {
+		use std::f32::consts::{PI, TAU};
+		const SEGMENTS: usize = 24;
+
+		let axis = (end - start).try_normalize().unwrap_or(Vec3::Y);
+		let (perp, perp2) = perpendicular_basis(axis);
+
+		{
+			let mut verts = self.batch_vertices.borrow_mut();
+			verts.clear();
+
+			push_arc(&mut verts, start, perp, perp2, radius, 0.0, TAU, SEGMENTS);
+			push_arc(&mut verts, end, perp, perp2, radius, 0.0, TAU, SEGMENTS);
+
+			for side in [perp, -perp, perp2, -perp2] {
+				let (a, b) = (start + side * radius, end + side * radius);
+				verts.extend_from_slice(&[a.x, a.y, a.z, b.x, b.y, b.z]);
+			}
+
+			// Caps bulge away from the segment along the axis
+			push_arc(&mut verts, end, perp, axis, radius, 0.0, PI, SEGMENTS / 2);
+			push_arc(&mut verts, end, perp2, axis, radius, 0.0, PI, SEGMENTS / 2);
+			push_arc(&mut verts, start, perp, -axis, radius, 0.0, PI, SEGMENTS / 2);
+			push_arc(&mut verts, start, perp2, -axis, radius, 0.0, PI, SEGMENTS / 2);
+		}
+
+		self.submit(gl, camera, &self.batch_vertices.borrow(), Mat4::IDENTITY, color);
+	}
+
+	/// Draws the edges of a view frustum.
+	///
+	/// `inv_view_proj` is the inverse of a camera's `projection * view`
+	/// matrix, mapping the clip-space cube back to world space.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use glam::Vec3;
+	///
+	/// // Visualize another camera's view volume
+	/// let inv = (other.projection_matrix() * other.view_matrix()).inverse();
+	/// gizmos.wire_frustum(&gl, &camera, inv, Vec3::new(1.0, 0.5, 0.0));
+	/// ```
+	pub fn wire_frustum(&self, gl: &GL, camera: &Camera, inv_view_proj: Mat4, color: Vec3) {
+		let corner = |i: usize| {
+			let ndc = Vec3::new(
+				if i & 1 == 0 { -1.0 } else { 1.0 },
+				if i & 2 == 0 { -1.0 } else { 1.0 },
+				if i & 4 == 0 { -1.0 } else { 1.0 },
+			);
+			inv_view_proj.project_point3(ndc)
+		};
+
+		{
+			let mut verts = self.batch_vertices.borrow_mut();
+			verts.clear();
+
+			// Corners are indexed by bits (x, y, z), edges connect corners one bit apart
+			for i in 0..8 {
+				for bit in [1, 2, 4] {
+					if i & bit == 0 {
+						let (a, b) = (corner(i), corner(i | bit));
+						verts.extend_from_slice(&[a.x, a.y, a.z, b.x, b.y, b.z]);
+					}
+				}
+			}
+		}
+
+		self.submit(gl, camera, &self.batch_vertices.borrow(), Mat4::IDENTITY, color);
+	}
+
+	/// Draws a spline path with a small cube at each of its points.
+	///
+	/// Bezier paths also show the handles joining each end to its controls.
+	///
+	/// # Examples
+	///
+	/// ```ignore
+	/// use glam::Vec3;
+	///
+	/// gizmos.path(&gl, &camera, &camera_route, Vec3::new(1.0, 0.6, 0.0));
+	/// ```
+	pub fn path(&self, gl: &GL, camera: &Camera, path: &Path, color: Vec3) {
+		const SAMPLES_PER_SEGMENT: usize = 16;
+		const POINT_SIZE: f32 = 0.1;
+
+		let points = path.polyline(SAMPLES_PER_SEGMENT);
+
+		{
+			let mut verts = self.batch_vertices.borrow_mut();
+			verts.clear();
+
+			for pair in points.windows(2) {
+				let (a, b) = (pair[0], pair[1]);
+				verts.extend_from_slice(&[a.x, a.y, a.z, b.x, b.y, b.z]);
+			}
+
+			for (a, b) in path.handles() {
+				verts.extend_from_slice(&[a.x, a.y, a.z, b.x, b.y, b.z]);
+			}
+		}
+
+		self.submit(gl, camera, &self.batch_vertices.borrow(), Mat4::IDENTITY, color);
+
+		for &point in path.points() {
+			self.wire_cube(gl, camera, point, POINT_SIZE, color);
+		}
 	}
 
-	
 	/// Draws a ground plane grid.
 	///
 	/// Renders a square grid on the XZ plane (Y=0), useful for spatial
@@ -346,9 +1301,77 @@ impl GizmoRenderer {
 			}
 		}
 
-		self.upload_vertices(gl, &self.batch_vertices.borrow());
-		self.setup_draw(gl, camera, Mat4::IDENTITY, color);
-		gl.draw_arrays(GL::LINES, 0, ((divisions + 1) * 4) as i32);
+		self.submit(gl, camera, &self.batch_vertices.borrow(), Mat4::IDENTITY, color);
+	}
+
+	/// Draws an endless ground grid on the XZ plane (Y=0) with a shader.
+	///
+	/// Lines are anti-aliased per pixel, every `major_every`th line is
+	/// brighter, and the X and Z axes are drawn in red and blue. Lines too
+	/// dense to draw cleanly fade out rather than alias, and the grid fades
+	/// away towards `fade_distance` from the camera. Drawn right away, even
+	/// while batching. Blending, depth and culling state is restored
+	/// afterwards.
+	///
+	/// # Examples
+	///
+	/// ```ignore
+	/// use glam::Vec3;
+	///
+	/// // 1 meter cells with major lines every 10 meters, gone by 100 meters
+	/// gizmos.infinite_grid(&gl, &camera, 1.0, 10, 100.0, Vec3::new(0.5, 0.5, 0.5));
+	/// ```
+	pub fn infinite_grid(&self, gl: &GL, camera: &Camera, cell_size: f32, major_every: u32, fade_distance: f32, color: Vec3) {
+		let program = &self.grid_program;
+		let pos_loc = gl.get_attrib_location(program, "position");
+
+		if pos_loc < 0 || cell_size <= 0.0 {
+			return;
+		}
+
+		gl.use_program(Some(program));
+
+		if let Some(loc) = gl.get_uniform_location(program, "view") {
+			gl.uniform_matrix4fv_with_f32_array(Some(&loc), false, &camera.view_matrix().to_cols_array());
+		}
+		if let Some(loc) = gl.get_uniform_location(program, "projection") {
+			gl.uniform_matrix4fv_with_f32_array(Some(&loc), false, &camera.projection_matrix().to_cols_array());
+		}
+		if let Some(loc) = gl.get_uniform_location(program, "logDepthFactor") {
+			gl.uniform1f(Some(&loc), camera.log_depth_factor());
+		}
+		if let Some(loc) = gl.get_uniform_location(program, "cameraPosition") {
+			gl.uniform3fv_with_f32_array(Some(&loc), &camera.position.to_array());
+		}
+		if let Some(loc) = gl.get_uniform_location(program, "extent") {
+			gl.uniform1f(Some(&loc), fade_distance);
+		}
+		if let Some(loc) = gl.get_uniform_location(program, "color") {
+			gl.uniform3fv_with_f32_array(Some(&loc), &color.to_array());
+		}
+		if let Some(loc) = gl.get_uniform_location(program, "cellSize") {
+			gl.uniform1f(Some(&loc), cell_size);
+		}
+		if let Some(loc) = gl.get_uniform_location(program, "majorSize") {
+			gl.uniform1f(Some(&loc), cell_size * major_every.max(1) as f32);
+		}
+		if let Some(loc) = gl.get_uniform_location(program, "fadeDistance") {
+			gl.uniform1f(Some(&loc), fade_distance);
+		}
+
+		gl.bind_buffer(GL::ARRAY_BUFFER, Some(&self.grid_quad));
+		gl.enable_vertex_attrib_array(pos_loc as u32);
+		gl.vertex_attrib_pointer_with_i32(pos_loc as u32, 2, GL::FLOAT, false, 0, 0);
+
+		// The grid is seen from both sides and mustn't hide what's under it
+		let state = SavedState::capture(gl);
+
+		gl.disable(GL::CULL_FACE);
+		gl.depth_mask(false);
+		blend_over(gl);
+		gl.draw_arrays(GL::TRIANGLES, 0, 6);
+
+		state.restore(gl);
 	}
 
 	/// Draws RGB coordinate axes.
@@ -372,4 +1395,104 @@ impl GizmoRenderer {
 		self.arrow(gl, camera, position, Vec3::Y, size, Vec3::new(0.0, 1.0, 0.0));
 		self.arrow(gl, camera, position, Vec3::Z, size, Vec3::new(0.0, 0.0, 1.0));
 	}
-}
\ No newline at end of file
+
+	/// Draws a line from every vertex of a mesh along one of its
+	/// attributes, such as `normal` or `tangent`, to debug shading.
+	///
+	/// Lines are `length` long in world units and brighten towards their
+	/// tip. A `normal` attribute is transformed like a normal, anything
+	/// else like a direction. The lines are generated on the GPU from the
+	/// mesh's own buffers, so they follow vertex updates, and are drawn
+	/// right away even while batching. Returns `false` if the mesh has no
+	/// such attribute.
+	///
+	/// # Examples
+	///
+	/// ```ignore
+	/// let model = scene.world_matrix(id);
+	/// gizmos.vertex_vectors(&gl, &camera, &mesh, model, "normal", 0.1, Vec3::new(0.3, 0.6, 1.0));
+	/// ```
+	#[allow(clippy::too_many_arguments)]
+	pub fn vertex_vectors(&self, gl: &GL, camera: &Camera, mesh: &Mesh, model: Mat4, attribute: &str, length: f32, color: Vec3) -> bool {
+		let program = &self.vector_program;
+		let locations = ["end", "position", "direction"].map(|name| gl.get_attrib_location(program, name));
+
+		if locations.iter().any(|&loc| loc < 0) {
+			return false;
+		}
+
+		let [end_loc, pos_loc, dir_loc] = locations.map(|loc| loc as u32);
+		let direction_matrix = if attribute == "normal" {
+			Mat3::from_mat4(model).inverse().transpose()
+		} else {
+			Mat3::from_mat4(model)
+		};
+
+		gl.use_program(Some(program));
+
+		if let Some(loc) = gl.get_uniform_location(program, "view") {
+			gl.uniform_matrix4fv_with_f32_array(Some(&loc), false, &camera.view_matrix().to_cols_array());
+		}
+		if let Some(loc) = gl.get_uniform_location(program, "projection") {
+			gl.uniform_matrix4fv_with_f32_array(Some(&loc), false, &camera.projection_matrix().to_cols_array());
+		}
+		if let Some(loc) = gl.get_uniform_location(program, "logDepthFactor") {
+			gl.uniform1f(Some(&loc), camera.log_depth_factor());
+		}
+		if let Some(loc) = gl.get_uniform_location(program, "model") {
+			gl.uniform_matrix4fv_with_f32_array(Some(&loc), false, &model.to_cols_array());
+		}
+		if let Some(loc) = gl.get_uniform_location(program, "directionMatrix") {
+			gl.uniform_matrix3fv_with_f32_array(Some(&loc), false, &direction_matrix.to_cols_array());
+		}
+		if let Some(loc) = gl.get_uniform_location(program, "length") {
+			gl.uniform1f(Some(&loc), length);
+		}
+		if let Some(loc) = gl.get_uniform_location(program, "color") {
+			gl.uniform3fv_with_f32_array(Some(&loc), &color.to_array());
+		}
+
+		gl.bind_buffer(GL::ARRAY_BUFFER, Some(&self.vector_ends));
+		gl.enable_vertex_attrib_array(end_loc);
+		gl.vertex_attrib_pointer_with_i32(end_loc, 1, GL::FLOAT, false, 0, 0);
+
+		let count = mesh.bind_per_instance(gl, "position", pos_loc)
+			.zip(mesh.bind_per_instance(gl, attribute, dir_loc));
+
+		if let Some((count, _)) = count {
+			gl.draw_arrays_instanced(GL::LINES, 0, 2, count);
+		}
+
+		gl.vertex_attrib_divisor(pos_loc, 0);
+		gl.vertex_attrib_divisor(dir_loc, 0);
+		gl.disable_vertex_attrib_array(dir_loc);
+		gl.disable_vertex_attrib_array(end_loc);
+
+		count.is_some()
+	}
+}
+
+/// Returns two unit vectors perpendicular to `dir` and to each other.
+fn perpendicular_basis(dir: Vec3) -> (Vec3, Vec3) {
+	let perp = if dir.y.abs() < 0.9 {
+		dir.cross(Vec3::Y).normalize()
+	} else {
+		dir.cross(Vec3::X).normalize()
+	};
+
+	(perp, dir.cross(perp).normalize())
+}
+
+/// Appends an arc as line segments, sweeping from `u` towards `v`.
+#[allow(clippy::too_many_arguments)]
+fn push_arc(verts: &mut Vec<f32>, center: Vec3, u: Vec3, v: Vec3, radius: f32, from: f32, to: f32, segments: usize) {
+	let point = |i: usize| {
+		let a = from + (to - from) * i as f32 / segments as f32;
+		center + (u * a.cos() + v * a.sin()) * radius
+	};
+
+	for i in 0..segments {
+		let (p1, p2) = (point(i), point(i + 1));
+		verts.extend_from_slice(&[p1.x, p1.y, p1.z, p2.x, p2.y, p2.z]);
+	}
+}
@@ -0,0 +1,201 @@
+//! HTML Labels
+//!
+//! Provides [`HtmlLabels`], a lighter alternative to the
+//! [`CSS3DRenderer`](super::CSS3DRenderer) for HTML that should stay flat
+//! and readable. Each label is an absolutely placed element moved to the
+//! screen position of a 3D point every frame, and hidden while the point is
+//! behind the camera or outside the view.
+//!
+//! Positions are set as percentages of the overlay, so labels line up with
+//! the canvas at any device pixel ratio.
+//!
+//! ## Examples
+//!
+//! ```ignore
+//! use oxgl::renderer_3d::HtmlLabels;
+//! use glam::{Vec2, Vec3};
+//!
+//! let mut labels = HtmlLabels::new("webgl-canvas")?;
+//! let tag = labels.add("<span class=\"tag\">Door</span>", Vec3::new(2.0, 1.0, 0.0))?;
+//!
+//! // Sit the label just above its point
+//! if let Some(label) = labels.get_mut(tag) {
+//!		label.offset = Vec2::new(0.0, -12.0);
+//! }
+//!
+//! app.run(move |scene, _time| {
+//!		labels.update(&scene.camera);
+//! });
+//! ```
+//!
+
+use glam::{Vec2, Vec3};
+use slotmap::SlotMap;
+use web_sys::{HtmlElement, wasm_bindgen::JsCast};
+
+use crate::common::Camera;
+use crate::core::HtmlLabelId;
+
+/// An HTML element pinned to a point in the scene.
+pub struct HtmlLabel {
+	pub element: HtmlElement,
+	pub position: Vec3,
+	/// Offset from the projected point in CSS pixels, Y down.
+	pub offset: Vec2,
+	pub visible: bool,
+	shown: bool,
+}
+
+/// Positions HTML elements over the canvas at projected 3D points.
+pub struct HtmlLabels {
+	container: HtmlElement,
+	labels: SlotMap<HtmlLabelId, HtmlLabel>,
+}
+
+impl HtmlLabels {
+	/// Creates the label overlay as a sibling of the canvas element.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the canvas isn't found or the overlay can't be
+	/// created.
+	pub fn new(canvas_id: &str) -> Result<Self, String> {
+		let document = web_sys::window()
+			.and_then(|window| window.document())
+			.ok_or("No document")?;
+
+		let canvas = document
+			.get_element_by_id(canvas_id)
+			.ok_or("Canvas not found")?;
+
+		let canvas_parent = canvas
+			.parent_element()
+			.ok_or("Canvas has no parent")?;
+
+		if let Ok(parent_el) = canvas_parent.clone().dyn_into::<HtmlElement>() {
+			let _ = parent_el.style().set_property("position", "relative");
+		}
+
+		let container = document
+			.create_element("div")
+			.map_err(|_| "Failed to create label container")?
+			.dyn_into::<HtmlElement>()
+			.map_err(|_| "Failed to cast label container")?;
+
+		let style = container.style();
+		let _ = style.set_property("position", "absolute");
+		let _ = style.set_property("top", "0");
+		let _ = style.set_property("left", "0");
+		let _ = style.set_property("width", "100%");
+		let _ = style.set_property("height", "100%");
+		let _ = style.set_property("overflow", "hidden");
+		let _ = style.set_property("pointer-events", "none");
+		let _ = style.set_property("z-index", "1");
+
+		canvas_parent
+			.insert_before(&container, canvas.next_sibling().as_ref())
+			.map_err(|_| "Failed to insert label container")?;
+
+		Ok(Self {
+			container,
+			labels: SlotMap::with_key(),
+		})
+	}
+
+	/// Adds a label showing `html` at a world position.
+	pub fn add(&mut self, html: &str, position: Vec3) -> Result<HtmlLabelId, String> {
+		let document = web_sys::window()
+			.and_then(|window| window.document())
+			.ok_or("No document")?;
+
+		let element = document
+			.create_element("div")
+			.map_err(|_| "Failed to create label")?
+			.dyn_into::<HtmlElement>()
+			.map_err(|_| "Failed to cast label")?;
+
+		element.set_inner_html(html);
+
+		let style = element.style();
+		let _ = style.set_property("position", "absolute");
+		let _ = style.set_property("pointer-events", "auto");
+		let _ = style.set_property("white-space", "nowrap");
+		let _ = style.set_property("display", "none");
+
+		self.container
+			.append_child(&element)
+			.map_err(|_| "Failed to append label")?;
+
+		Ok(self.labels.insert(HtmlLabel {
+			element,
+			position,
+			offset: Vec2::ZERO,
+			visible: true,
+			shown: false,
+		}))
+	}
+
+	/// Removes a label and its element.
+	pub fn remove(&mut self, id: HtmlLabelId) -> bool {
+		if let Some(label) = self.labels.remove(id) {
+			label.element.remove();
+			true
+		} else {
+			false
+		}
+	}
+
+	pub fn get(&self, id: HtmlLabelId) -> Option<&HtmlLabel> {
+		self.labels.get(id)
+	}
+
+	pub fn get_mut(&mut self, id: HtmlLabelId) -> Option<&mut HtmlLabel> {
+		self.labels.get_mut(id)
+	}
+
+	/// Updates a label's HTML content.
+	pub fn set_html(&self, id: HtmlLabelId, html: &str) {
+		if let Some(label) = self.labels.get(id) {
+			label.element.set_inner_html(html);
+		}
+	}
+
+	/// Shows or hides the whole overlay.
+	pub fn set_visible(&self, visible: bool) {
+		let _ = self.container.style().set_property("display", if visible { "block" } else { "none" });
+	}
+
+	/// Moves every label to its projected position.
+	///
+	/// Labels are centered on their point, then moved by their offset.
+	pub fn update(&mut self, camera: &Camera) {
+		for label in self.labels.values_mut() {
+			// Project to fractions of the view, which match the overlay's size
+			let screen = camera
+				.project(label.position, 1.0, 1.0)
+				.filter(|p| label.visible && (0.0..=1.0).contains(&p.x) && (0.0..=1.0).contains(&p.y));
+
+			let style = label.element.style();
+
+			let Some(screen) = screen else {
+				if label.shown {
+					let _ = style.set_property("display", "none");
+					label.shown = false;
+				}
+				continue;
+			};
+
+			if !label.shown {
+				let _ = style.set_property("display", "block");
+				label.shown = true;
+			}
+
+			let _ = style.set_property("left", &format!("{}%", screen.x * 100.0));
+			let _ = style.set_property("top", &format!("{}%", screen.y * 100.0));
+			let _ = style.set_property(
+				"transform",
+				&format!("translate(-50%, -50%) translate({}px, {}px)", label.offset.x, label.offset.y),
+			);
+		}
+	}
+}
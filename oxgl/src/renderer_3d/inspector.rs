@@ -0,0 +1,470 @@
+//! Scene Inspector Data
+//!
+//! Provides plain-data snapshots of objects and lights, and
+//! [`PropertyValue`]s read and written by path, for building generic
+//! property panels in UI frameworks. Unlike the [outline](super::outline),
+//! which lists what's in the scene, snapshots describe one entry in detail:
+//! its transform, material uniforms and mesh statistics.
+//!
+//! Properties are addressed by dotted paths:
+//!
+//! | Path | Entry | Value |
+//! |------|-------|-------|
+//! | `name` | both | `Text` |
+//! | `visible`, `cast_shadows`, `receive_shadows`, `show_normals` | object | `Bool` |
+//! | `transform.position`, `transform.scale` | object | `Vec3` |
+//! | `transform.rotation` | object | `Vec3`, XYZ Euler angles in degrees |
+//! | `material.double_sided`, `material.flat_shading` | object | `Bool` |
+//! | `material.<uniform>` | object | the uniform's type |
+//! | `enabled`, `cast_shadows` | light | `Bool` |
+//! | `position`, `direction`, `color` | light | `Vec3` |
+//! | `intensity` | light | `Float` |
+//!
+//! Vector paths also accept a `.x`, `.y`, `.z` or `.w` suffix to address a
+//! single component as a `Float`.
+//!
+//! ## Examples
+//!
+//! ```ignore
+//! use oxgl::renderer_3d::{OutlineId, PropertyValue};
+//!
+//! // Fill a property panel
+//! if let Some(snapshot) = scene.inspect(id) {
+//!		for (name, value) in &snapshot.material.uniforms {
+//!			log::info!("{}: {:?}", name, value);
+//!		}
+//! }
+//!
+//! // Write back edits from the panel
+//! let target = OutlineId::Object(id);
+//! scene.set_property(target, "material.shininess", 64.0)?;
+//! scene.set_property(target, "transform.position.y", 1.5)?;
+//! scene.set_property(OutlineId::Light(sun), "color", Vec3::new(1.0, 0.9, 0.8))?;
+//! ```
+//!
+
+use glam::{EulerRot, Mat4, Quat, Vec2, Vec3, Vec4};
+
+use crate::{
+	common::{Material, Mesh, Uniform},
+	core::{Aabb, LightId, ObjectId, Transform3D},
+};
+use super::{Light, LightType, OutlineKind, SceneObject};
+
+/// A property value shown and edited by an inspector.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(tag = "type", content = "value", rename_all = "snake_case"))]
+pub enum PropertyValue {
+	Bool(bool),
+	Int(i32),
+	Float(f32),
+	Vec2(Vec2),
+	Vec3(Vec3),
+	Vec4(Vec4),
+	Mat4(Mat4),
+	Text(String),
+}
+
+impl PropertyValue {
+	/// Returns the value as a shader uniform, or `None` for booleans and text.
+	pub fn to_uniform(&self) -> Option<Uniform> {
+		Some(match self {
+			Self::Int(v) => Uniform::Int(*v),
+			Self::Float(v) => Uniform::Float(*v),
+			Self::Vec2(v) => Uniform::Vec2(*v),
+			Self::Vec3(v) => Uniform::Vec3(*v),
+			Self::Vec4(v) => Uniform::Vec4(*v),
+			Self::Mat4(v) => Uniform::Mat4(*v),
+			Self::Bool(_) | Self::Text(_) => return None,
+		})
+	}
+
+	/// Returns the name of the value's variant, for error messages and
+	/// choosing an editor widget.
+	pub fn type_name(&self) -> &'static str {
+		match self {
+			Self::Bool(_) => "Bool",
+			Self::Int(_) => "Int",
+			Self::Float(_) => "Float",
+			Self::Vec2(_) => "Vec2",
+			Self::Vec3(_) => "Vec3",
+			Self::Vec4(_) => "Vec4",
+			Self::Mat4(_) => "Mat4",
+			Self::Text(_) => "Text",
+		}
+	}
+
+	/// Returns one component of a vector value.
+	pub fn component(&self, index: usize) -> Option<f32> {
+		match self {
+			Self::Vec2(v) => v.to_array().get(index).copied(),
+			Self::Vec3(v) => v.to_array().get(index).copied(),
+			Self::Vec4(v) => v.to_array().get(index).copied(),
+			_ => None,
+		}
+	}
+
+	/// Returns a vector value with one component replaced.
+	pub fn with_component(&self, index: usize, value: f32) -> Option<Self> {
+		let mut result = self.clone();
+
+		match &mut result {
+			Self::Vec2(v) if index < 2 => v[index] = value,
+			Self::Vec3(v) if index < 3 => v[index] = value,
+			Self::Vec4(v) if index < 4 => v[index] = value,
+			_ => return None,
+		}
+
+		Some(result)
+	}
+
+	fn as_bool(&self) -> Option<bool> {
+		match self {
+			Self::Bool(v) => Some(*v),
+			_ => None,
+		}
+	}
+
+	fn as_float(&self) -> Option<f32> {
+		match self {
+			Self::Float(v) => Some(*v),
+			_ => None,
+		}
+	}
+
+	fn as_vec3(&self) -> Option<Vec3> {
+		match self {
+			Self::Vec3(v) => Some(*v),
+			_ => None,
+		}
+	}
+}
+
+impl From<&Uniform> for PropertyValue {
+	fn from(uniform: &Uniform) -> Self {
+		match uniform {
+			Uniform::Float(v) => Self::Float(*v),
+			Uniform::Vec2(v) => Self::Vec2(*v),
+			Uniform::Vec3(v) => Self::Vec3(*v),
+			Uniform::Vec4(v) => Self::Vec4(*v),
+			Uniform::Mat4(v) => Self::Mat4(*v),
+			Uniform::Int(v) => Self::Int(*v),
+		}
+	}
+}
+
+impl From<bool> for PropertyValue {
+	fn from(v: bool) -> Self {
+		Self::Bool(v)
+	}
+}
+
+impl From<i32> for PropertyValue {
+	fn from(v: i32) -> Self {
+		Self::Int(v)
+	}
+}
+
+impl From<f32> for PropertyValue {
+	fn from(v: f32) -> Self {
+		Self::Float(v)
+	}
+}
+
+impl From<Vec2> for PropertyValue {
+	fn from(v: Vec2) -> Self {
+		Self::Vec2(v)
+	}
+}
+
+impl From<Vec3> for PropertyValue {
+	fn from(v: Vec3) -> Self {
+		Self::Vec3(v)
+	}
+}
+
+impl From<Vec4> for PropertyValue {
+	fn from(v: Vec4) -> Self {
+		Self::Vec4(v)
+	}
+}
+
+impl From<Mat4> for PropertyValue {
+	fn from(v: Mat4) -> Self {
+		Self::Mat4(v)
+	}
+}
+
+impl From<&str> for PropertyValue {
+	fn from(v: &str) -> Self {
+		Self::Text(v.to_string())
+	}
+}
+
+impl From<String> for PropertyValue {
+	fn from(v: String) -> Self {
+		Self::Text(v)
+	}
+}
+
+/// An object's local transform, with the rotation as XYZ Euler angles in
+/// degrees.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TransformSnapshot {
+	pub position: Vec3,
+	pub rotation: Vec3,
+	pub scale: Vec3,
+}
+
+impl From<&Transform3D> for TransformSnapshot {
+	fn from(transform: &Transform3D) -> Self {
+		Self {
+			position: transform.position,
+			rotation: euler_degrees(transform),
+			scale: transform.scale,
+		}
+	}
+}
+
+/// A material's uniforms, sorted by name, and the names of its textures.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MaterialSnapshot {
+	pub uniforms: Vec<(String, PropertyValue)>,
+	pub textures: Vec<String>,
+	pub double_sided: bool,
+	pub flat_shading: bool,
+}
+
+impl From<&Material> for MaterialSnapshot {
+	fn from(material: &Material) -> Self {
+		let mut uniforms: Vec<(String, PropertyValue)> = material
+			.uniform_values()
+			.map(|(name, value)| (name.to_string(), value.into()))
+			.collect();
+		uniforms.sort_by(|a, b| a.0.cmp(&b.0));
+
+		Self {
+			uniforms,
+			textures: material.texture_names().map(str::to_string).collect(),
+			double_sided: material.double_sided,
+			flat_shading: material.flat_shading,
+		}
+	}
+}
+
+/// Statistics of an object's mesh.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MeshSnapshot {
+	pub name: Option<String>,
+	pub vertex_count: i32,
+	pub triangle_count: u32,
+	pub instance_count: Option<i32>,
+	/// Names of the vertex attributes, such as `position` and `normal`.
+	pub attributes: Vec<String>,
+	/// Local-space bounds.
+	pub bounds: Aabb,
+}
+
+impl From<&Mesh> for MeshSnapshot {
+	fn from(mesh: &Mesh) -> Self {
+		Self {
+			name: mesh.name.clone(),
+			vertex_count: mesh.vertex_count(),
+			triangle_count: mesh.triangle_count(),
+			instance_count: mesh.instance_count(),
+			attributes: mesh.attributes().map(|attribute| attribute.name.clone()).collect(),
+			bounds: mesh.bounds(),
+		}
+	}
+}
+
+/// A detailed snapshot of one object, see [`Scene::inspect`](super::Scene::inspect).
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ObjectSnapshot {
+	pub id: ObjectId,
+	pub name: Option<String>,
+	/// Tags, sorted alphabetically.
+	pub tags: Vec<String>,
+	pub parent: Option<ObjectId>,
+	pub visible: bool,
+	pub cast_shadows: bool,
+	pub receive_shadows: bool,
+	pub show_normals: bool,
+	pub transform: TransformSnapshot,
+	pub material: MaterialSnapshot,
+	pub mesh: MeshSnapshot,
+}
+
+impl ObjectSnapshot {
+	pub(crate) fn new(id: ObjectId, obj: &SceneObject) -> Self {
+		let mut tags: Vec<String> = obj.tags.iter().cloned().collect();
+		tags.sort();
+
+		Self {
+			id,
+			name: obj.name.clone(),
+			tags,
+			parent: obj.parent,
+			visible: obj.visible,
+			cast_shadows: obj.cast_shadows,
+			receive_shadows: obj.receive_shadows,
+			show_normals: obj.show_normals,
+			transform: (&obj.transform).into(),
+			material: (&obj.mesh.material).into(),
+			mesh: (&obj.mesh).into(),
+		}
+	}
+}
+
+/// A detailed snapshot of one light, see
+/// [`Scene::inspect_light`](super::Scene::inspect_light).
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LightSnapshot {
+	pub id: LightId,
+	pub name: Option<String>,
+	pub kind: OutlineKind,
+	pub position: Vec3,
+	pub direction: Vec3,
+	pub color: Vec3,
+	pub intensity: f32,
+	pub enabled: bool,
+	pub cast_shadows: bool,
+}
+
+impl LightSnapshot {
+	pub(crate) fn new(id: LightId, light: &Light) -> Self {
+		Self {
+			id,
+			name: light.name.clone(),
+			kind: match light.light_type {
+				LightType::Directional => OutlineKind::DirectionalLight,
+				LightType::Point { .. } => OutlineKind::PointLight,
+				LightType::Spot { .. } => OutlineKind::SpotLight,
+			},
+			position: light.position,
+			direction: light.direction,
+			color: light.color,
+			intensity: light.intensity,
+			enabled: light.enabled,
+			cast_shadows: light.cast_shadows,
+		}
+	}
+}
+
+/// Splits a trailing `.x`, `.y`, `.z` or `.w` off a property path.
+pub(crate) fn split_component(path: &str) -> (&str, Option<usize>) {
+	let Some((base, suffix)) = path.rsplit_once('.') else {
+		return (path, None);
+	};
+
+	match suffix {
+		"x" => (base, Some(0)),
+		"y" => (base, Some(1)),
+		"z" => (base, Some(2)),
+		"w" => (base, Some(3)),
+		_ => (path, None),
+	}
+}
+
+/// Reads a property of an object, without component suffixes.
+pub(crate) fn object_property(obj: &SceneObject, path: &str) -> Option<PropertyValue> {
+	Some(match path {
+		"name" => PropertyValue::Text(obj.name.clone().unwrap_or_default()),
+		"visible" => PropertyValue::Bool(obj.visible),
+		"cast_shadows" => PropertyValue::Bool(obj.cast_shadows),
+		"receive_shadows" => PropertyValue::Bool(obj.receive_shadows),
+		"show_normals" => PropertyValue::Bool(obj.show_normals),
+		"transform.position" => PropertyValue::Vec3(obj.transform.position),
+		"transform.rotation" => PropertyValue::Vec3(euler_degrees(&obj.transform)),
+		"transform.scale" => PropertyValue::Vec3(obj.transform.scale),
+		"material.double_sided" => PropertyValue::Bool(obj.mesh.material.double_sided),
+		"material.flat_shading" => PropertyValue::Bool(obj.mesh.material.flat_shading),
+		_ => {
+			let uniform = path.strip_prefix("material.")?;
+			obj.mesh.material.get(uniform)?.into()
+		}
+	})
+}
+
+/// Writes a property of an object that the scene doesn't need to report.
+///
+/// The value must have the property's type.
+pub(crate) fn set_object_property(obj: &mut SceneObject, path: &str, value: &PropertyValue) -> Result<(), String> {
+	let current = object_property(obj, path).ok_or_else(|| format!("Unknown object property '{}'", path))?;
+
+	if current.type_name() != value.type_name() {
+		return Err(format!("Property '{}' expects {}, got {}", path, current.type_name(), value.type_name()));
+	}
+
+	match path {
+		"receive_shadows" => obj.receive_shadows = value.as_bool().unwrap_or_default(),
+		"show_normals" => obj.show_normals = value.as_bool().unwrap_or_default(),
+		"transform.position" => obj.transform.position = value.as_vec3().unwrap_or_default(),
+		"transform.rotation" => {
+			let angles = value.as_vec3().unwrap_or_default();
+			obj.transform.rotation = Quat::from_euler(
+				EulerRot::XYZ,
+				angles.x.to_radians(),
+				angles.y.to_radians(),
+				angles.z.to_radians(),
+			);
+		}
+		"transform.scale" => obj.transform.scale = value.as_vec3().unwrap_or_default(),
+		"material.double_sided" => obj.mesh.material.double_sided = value.as_bool().unwrap_or_default(),
+		"material.flat_shading" => obj.mesh.material.flat_shading = value.as_bool().unwrap_or_default(),
+		_ => {
+			let (Some(uniform), Some(value)) = (path.strip_prefix("material."), value.to_uniform()) else {
+				return Err(format!("Property '{}' can't be set directly", path));
+			};
+			obj.mesh.material.set(uniform, value);
+		}
+	}
+
+	Ok(())
+}
+
+/// Reads a property of a light, without component suffixes.
+pub(crate) fn light_property(light: &Light, path: &str) -> Option<PropertyValue> {
+	Some(match path {
+		"name" => PropertyValue::Text(light.name.clone().unwrap_or_default()),
+		"enabled" => PropertyValue::Bool(light.enabled),
+		"cast_shadows" => PropertyValue::Bool(light.cast_shadows),
+		"position" => PropertyValue::Vec3(light.position),
+		"direction" => PropertyValue::Vec3(light.direction),
+		"color" => PropertyValue::Vec3(light.color),
+		"intensity" => PropertyValue::Float(light.intensity),
+		_ => return None,
+	})
+}
+
+/// Writes a property of a light that the scene doesn't need to report.
+///
+/// The value must have the property's type.
+pub(crate) fn set_light_property(light: &mut Light, path: &str, value: &PropertyValue) -> Result<(), String> {
+	let current = light_property(light, path).ok_or_else(|| format!("Unknown light property '{}'", path))?;
+
+	if current.type_name() != value.type_name() {
+		return Err(format!("Property '{}' expects {}, got {}", path, current.type_name(), value.type_name()));
+	}
+
+	match path {
+		"cast_shadows" => light.cast_shadows = value.as_bool().unwrap_or_default(),
+		"position" => light.position = value.as_vec3().unwrap_or_default(),
+		"direction" => light.direction = value.as_vec3().unwrap_or_default().normalize_or_zero(),
+		"color" => light.color = value.as_vec3().unwrap_or_default(),
+		"intensity" => light.intensity = value.as_float().unwrap_or_default(),
+		_ => return Err(format!("Property '{}' can't be set directly", path)),
+	}
+
+	Ok(())
+}
+
+fn euler_degrees(transform: &Transform3D) -> Vec3 {
+	let (x, y, z) = transform.rotation.to_euler(EulerRot::XYZ);
+	Vec3::new(x, y, z) * (180.0 / std::f32::consts::PI)
+}
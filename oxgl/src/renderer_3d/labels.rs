@@ -0,0 +1,614 @@
+//! Batched Text Labels
+//!
+//! Provides a label renderer for scenes with thousands of markers, such as
+//! data visualizations. Glyphs come from a signed distance field atlas, so
+//! labels stay crisp at any size, and every visible glyph is drawn in a
+//! single instanced draw call.
+//!
+//! Labels can be decluttered: when enabled, overlapping labels are hidden in
+//! screen space, keeping those with higher priority and closer to the camera.
+//!
+//! ## Examples
+//!
+//! ```ignore
+//! use oxgl::renderer_3d::{Label, LabelRenderer};
+//! use glam::{Vec3, Vec4};
+//!
+//! let mut labels = LabelRenderer::new(&gl)?;
+//!
+//! for city in &cities {
+//!		labels.add(
+//!			Label::new(city.position, &city.name)
+//!				.with_scale(if city.capital { 1.4 } else { 1.0 })
+//!				.with_priority(city.population as i32)
+//!		);
+//! }
+//!
+//! // Each frame, after rendering the scene
+//! let shown = labels.draw(&gl, &scene.camera, width as f32, height as f32);
+//! ```
+//!
+
+use std::collections::HashMap;
+use glam::{Vec2, Vec3, Vec4, Vec4Swizzles};
+use slotmap::SlotMap;
+use web_sys::{
+	CanvasRenderingContext2d, HtmlCanvasElement, WebGlBuffer, WebGlProgram,
+	WebGl2RenderingContext as GL, wasm_bindgen::JsCast,
+};
+
+use crate::common::{compile_shader, link_program, Camera, Texture, render_state::SavedState};
+use crate::core::LabelId;
+use super::background::blend_over;
+
+const LABEL_VERT: &str = r#"
+	#include <logdepth>
+
+	attribute vec2 corner;
+	attribute vec3 anchor;
+	attribute vec2 offset;
+	attribute vec2 size;
+	attribute vec4 uvRect;
+	attribute vec4 color;
+
+	uniform mat4 viewProjection;
+	uniform vec2 viewport;
+	uniform float smoothing;
+
+	varying vec2 vUv;
+	varying vec4 vColor;
+	varying float vSmoothing;
+
+	void main() {
+		vec4 clip = viewProjection * vec4(anchor, 1.0);
+		vec2 pixel = offset + corner * size;
+
+		clip.xy += pixel / viewport * 2.0 * clip.w;
+		gl_Position = logDepth(clip);
+
+		vUv = mix(uvRect.xy, uvRect.zw, vec2(corner.x, 1.0 - corner.y));
+		vColor = color;
+		vSmoothing = smoothing / size.y;
+	}
+"#;
+
+const LABEL_FRAG: &str = r#"
+	precision mediump float;
+
+	uniform sampler2D atlas;
+
+	varying vec2 vUv;
+	varying vec4 vColor;
+	varying float vSmoothing;
+
+	void main() {
+		float distance = texture2D(atlas, vUv).a;
+		float alpha = smoothstep(0.5 - vSmoothing, 0.5 + vSmoothing, distance);
+
+		if (alpha <= 0.0) discard;
+
+		gl_FragColor = vec4(vColor.rgb, vColor.a * alpha);
+	}
+"#;
+
+/// Font size the atlas is rasterized at, in pixels.
+const SDF_FONT_SIZE: f32 = 32.0;
+const SDF_FONT: &str = "32px sans-serif";
+/// Distance in atlas pixels covered by the field on each side of an edge.
+const SDF_SPREAD: f32 = 6.0;
+const ATLAS_WIDTH: u32 = 512;
+const ATLAS_FIRST_CHAR: u32 = 32;
+const ATLAS_LAST_CHAR: u32 = 126;
+
+/// Floats per glyph instance: anchor, offset, size, uv rect, color.
+const INSTANCE_STRIDE: usize = 15;
+/// Size of the screen-space buckets used for decluttering, in pixels.
+const DECLUTTER_CELL: f32 = 64.0;
+
+/// A text label anchored to a world position.
+#[derive(Clone, Debug)]
+pub struct Label {
+	pub position: Vec3,
+	pub text: String,
+	pub color: Vec4,
+	/// Size multiplier on top of [`LabelRenderer::font_size`].
+	pub scale: f32,
+	/// Labels with higher priority are kept when decluttering.
+	pub priority: i32,
+	pub visible: bool,
+}
+
+impl Label {
+	pub fn new(position: Vec3, text: &str) -> Self {
+		Self {
+			position,
+			text: text.to_string(),
+			color: Vec4::ONE,
+			scale: 1.0,
+			priority: 0,
+			visible: true,
+		}
+	}
+
+	pub fn with_color(mut self, color: Vec4) -> Self {
+		self.color = color;
+		self
+	}
+
+	pub fn with_scale(mut self, scale: f32) -> Self {
+		self.scale = scale;
+		self
+	}
+
+	pub fn with_priority(mut self, priority: i32) -> Self {
+		self.priority = priority;
+		self
+	}
+}
+
+#[derive(Clone, Copy, Default)]
+struct Glyph {
+	uv: [f32; 4],
+	/// Cell width in atlas pixels, including the field spread.
+	width: f32,
+	advance: f32,
+}
+
+/// Glyphs packed into rows of a signed distance field texture.
+struct SdfAtlas {
+	texture: Texture,
+	glyphs: Vec<Glyph>,
+	cell_height: f32,
+}
+
+impl SdfAtlas {
+	fn new(gl: &GL) -> Result<Self, String> {
+		let document = web_sys::window()
+			.and_then(|w| w.document())
+			.ok_or("No document")?;
+
+		let canvas = document
+			.create_element("canvas")
+			.map_err(|_| "Failed to create atlas canvas")?
+			.dyn_into::<HtmlCanvasElement>()
+			.map_err(|_| "Failed to cast atlas canvas")?;
+
+		let context = canvas
+			.get_context("2d")
+			.ok()
+			.flatten()
+			.and_then(|c| c.dyn_into::<CanvasRenderingContext2d>().ok())
+			.ok_or("Failed to get 2D context")?;
+
+		context.set_font(SDF_FONT);
+
+		let cell_height = (SDF_FONT_SIZE * 1.25 + SDF_SPREAD * 2.0).ceil();
+		let mut glyphs = Vec::new();
+		let mut positions = Vec::new();
+		let (mut x, mut y) = (0.0, 0.0);
+
+		// Shelf packing: glyphs fill rows left to right
+		for code in ATLAS_FIRST_CHAR..=ATLAS_LAST_CHAR {
+			let c = char::from_u32(code).unwrap_or('?');
+			let advance = context
+				.measure_text(&c.to_string())
+				.map_err(|_| "Failed to measure glyphs")?
+				.width() as f32;
+			let width = advance.ceil() + SDF_SPREAD * 2.0;
+
+			if x + width > ATLAS_WIDTH as f32 {
+				x = 0.0;
+				y += cell_height;
+			}
+
+			positions.push((c, x, y));
+			glyphs.push(Glyph { uv: [x, y, x + width, y + cell_height], width, advance });
+			x += width;
+		}
+
+		let width = ATLAS_WIDTH;
+		let height = ((y + cell_height) as u32).next_power_of_two();
+
+		canvas.set_width(width);
+		canvas.set_height(height);
+
+		// Resizing the canvas resets the context state
+		context.set_font(SDF_FONT);
+		context.set_fill_style_str("white");
+		context.set_text_baseline("middle");
+
+		for &(c, x, y) in &positions {
+			let _ = context.fill_text(&c.to_string(), (x + SDF_SPREAD) as f64, (y + cell_height * 0.5) as f64);
+		}
+
+		let image = context
+			.get_image_data(0.0, 0.0, width as f64, height as f64)
+			.map_err(|_| "Failed to read atlas pixels")?;
+
+		let inside: Vec<bool> = image.data().0.chunks_exact(4).map(|p| p[3] > 127).collect();
+		let field = signed_distance_field(&inside, width as usize, height as usize, SDF_SPREAD);
+		let pixels: Vec<u8> = field.iter().flat_map(|&d| [255, 255, 255, d]).collect();
+
+		for glyph in &mut glyphs {
+			let [x0, y0, x1, y1] = glyph.uv;
+			glyph.uv = [x0 / width as f32, y0 / height as f32, x1 / width as f32, y1 / height as f32];
+		}
+
+		Ok(Self {
+			texture: Texture::from_pixels(gl, width, height, &pixels)?,
+			glyphs,
+			cell_height,
+		})
+	}
+
+	/// Returns the glyph for a character, falling back to `?`.
+	fn glyph(&self, c: char) -> Glyph {
+		let index = (c as u32)
+			.checked_sub(ATLAS_FIRST_CHAR)
+			.filter(|&i| i <= ATLAS_LAST_CHAR - ATLAS_FIRST_CHAR)
+			.unwrap_or('?' as u32 - ATLAS_FIRST_CHAR);
+
+		self.glyphs[index as usize]
+	}
+
+	/// Returns the advance width of a string at the atlas font size.
+	fn text_width(&self, text: &str) -> f32 {
+		text.chars().map(|c| self.glyph(c).advance).sum()
+	}
+}
+
+/// Instanced, decluttered text labels.
+///
+/// Labels are stored in the renderer and projected every frame, so moving
+/// the camera re-evaluates which labels fit.
+pub struct LabelRenderer {
+	program: WebGlProgram,
+	quad_buffer: WebGlBuffer,
+	instance_buffer: WebGlBuffer,
+	instance_capacity: usize,
+	instances: Vec<f32>,
+	atlas: SdfAtlas,
+	labels: SlotMap<LabelId, Label>,
+	/// On-screen text height in pixels at a label scale of `1.0`.
+	pub font_size: f32,
+	/// Hides labels that overlap higher-priority or closer labels.
+	pub declutter: bool,
+	/// Minimum gap between decluttered labels, in pixels.
+	pub padding: f32,
+	/// Whether labels are hidden behind scene geometry.
+	pub depth_test: bool,
+}
+
+impl LabelRenderer {
+	/// Creates a label renderer and builds its glyph atlas.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the shaders, buffers or atlas can't be created.
+	pub fn new(gl: &GL) -> Result<Self, String> {
+		let vert = compile_shader(gl, LABEL_VERT, GL::VERTEX_SHADER)?;
+		let frag = compile_shader(gl, LABEL_FRAG, GL::FRAGMENT_SHADER)?;
+		let program = link_program(gl, &vert, &frag)?;
+
+		let quad_buffer = gl.create_buffer().ok_or("Failed to create label quad buffer")?;
+		let instance_buffer = gl.create_buffer().ok_or("Failed to create label instance buffer")?;
+
+		let corners: [f32; 8] = [0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 1.0, 1.0];
+		let data = unsafe {
+			std::slice::from_raw_parts(corners.as_ptr() as *const u8, std::mem::size_of_val(&corners))
+		};
+
+		gl.bind_buffer(GL::ARRAY_BUFFER, Some(&quad_buffer));
+		gl.buffer_data_with_u8_array(GL::ARRAY_BUFFER, data, GL::STATIC_DRAW);
+
+		Ok(Self {
+			program,
+			quad_buffer,
+			instance_buffer,
+			instance_capacity: 0,
+			instances: Vec::new(),
+			atlas: SdfAtlas::new(gl)?,
+			labels: SlotMap::with_key(),
+			font_size: 14.0,
+			declutter: true,
+			padding: 2.0,
+			depth_test: false,
+		})
+	}
+
+	pub fn add(&mut self, label: Label) -> LabelId {
+		self.labels.insert(label)
+	}
+
+	pub fn remove(&mut self, id: LabelId) -> Option<Label> {
+		self.labels.remove(id)
+	}
+
+	pub fn get(&self, id: LabelId) -> Option<&Label> {
+		self.labels.get(id)
+	}
+
+	pub fn get_mut(&mut self, id: LabelId) -> Option<&mut Label> {
+		self.labels.get_mut(id)
+	}
+
+	pub fn clear(&mut self) {
+		self.labels.clear();
+	}
+
+	pub fn len(&self) -> usize {
+		self.labels.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.labels.is_empty()
+	}
+
+	pub fn iter(&self) -> impl Iterator<Item = (LabelId, &Label)> {
+		self.labels.iter()
+	}
+
+	/// Draws all visible labels and returns how many were shown.
+	///
+	/// `width` and `height` are the viewport size in pixels.
+	pub fn draw(&mut self, gl: &GL, camera: &Camera, width: f32, height: f32) -> usize {
+		let view_projection = camera.projection_matrix() * camera.view_matrix();
+		let viewport = Vec2::new(width, height);
+
+		// Project labels and measure their screen rectangles
+		let mut placed: Vec<(&Label, Vec2, f32, Vec2)> = self.labels
+			.values()
+			.filter(|label| label.visible && !label.text.is_empty())
+			.filter_map(|label| {
+				let clip = view_projection * label.position.extend(1.0);
+
+				if clip.w <= 0.0 {
+					return None;
+				}
+
+				let ndc = clip.xyz() / clip.w;
+				let center = (ndc.truncate() * 0.5 + 0.5) * viewport;
+				let scale = self.font_size * label.scale / SDF_FONT_SIZE;
+				let size = Vec2::new(self.atlas.text_width(&label.text), SDF_FONT_SIZE) * scale;
+
+				let on_screen = ndc.z <= 1.0
+					&& center.cmpge(-size * 0.5).all()
+					&& center.cmple(viewport + size * 0.5).all();
+
+				on_screen.then_some((label, center, ndc.z, size))
+			})
+			.collect();
+
+		if self.declutter {
+			placed.sort_by(|a, b| b.0.priority.cmp(&a.0.priority).then(a.2.total_cmp(&b.2)));
+
+			let mut occupied = ScreenGrid::default();
+			let padding = Vec2::splat(self.padding);
+
+			placed.retain(|(_, center, _, size)| {
+				occupied.insert(*center - *size * 0.5 - padding, *center + *size * 0.5 + padding)
+			});
+		}
+
+		self.instances.clear();
+
+		for (label, _, _, size) in &placed {
+			let scale = self.font_size * label.scale / SDF_FONT_SIZE;
+			let mut cursor = -size.x * 0.5;
+
+			for c in label.text.chars() {
+				let glyph = self.atlas.glyph(c);
+
+				if c != ' ' {
+					let offset = Vec2::new(cursor - SDF_SPREAD * scale, -self.atlas.cell_height * scale * 0.5);
+
+					self.instances.extend_from_slice(&label.position.to_array());
+					self.instances.extend_from_slice(&offset.to_array());
+					self.instances.extend_from_slice(&[glyph.width * scale, self.atlas.cell_height * scale]);
+					self.instances.extend_from_slice(&glyph.uv);
+					self.instances.extend_from_slice(&label.color.to_array());
+				}
+
+				cursor += glyph.advance * scale;
+			}
+		}
+
+		let shown = placed.len();
+
+		if !self.instances.is_empty() {
+			self.draw_instances(gl, &view_projection.to_cols_array(), viewport, camera.log_depth_factor());
+		}
+
+		shown
+	}
+
+	fn draw_instances(&mut self, gl: &GL, view_projection: &[f32; 16], viewport: Vec2, log_depth_factor: f32) {
+		let program = &self.program;
+
+		gl.use_program(Some(program));
+
+		if let Some(loc) = gl.get_uniform_location(program, "viewProjection") {
+			gl.uniform_matrix4fv_with_f32_array(Some(&loc), false, view_projection);
+		}
+		if let Some(loc) = gl.get_uniform_location(program, "viewport") {
+			gl.uniform2f(Some(&loc), viewport.x, viewport.y);
+		}
+		if let Some(loc) = gl.get_uniform_location(program, "logDepthFactor") {
+			gl.uniform1f(Some(&loc), log_depth_factor);
+		}
+		if let Some(loc) = gl.get_uniform_location(program, "smoothing") {
+			// Roughly one screen pixel, in field units
+			gl.uniform1f(Some(&loc), 0.7 * self.atlas.cell_height / (SDF_SPREAD * 2.0));
+		}
+		if let Some(loc) = gl.get_uniform_location(program, "atlas") {
+			gl.uniform1i(Some(&loc), 0);
+		}
+
+		self.atlas.texture.bind(gl, 0);
+
+		gl.bind_buffer(GL::ARRAY_BUFFER, Some(&self.quad_buffer));
+
+		let corner_loc = gl.get_attrib_location(program, "corner");
+
+		if corner_loc >= 0 {
+			gl.enable_vertex_attrib_array(corner_loc as u32);
+			gl.vertex_attrib_pointer_with_i32(corner_loc as u32, 2, GL::FLOAT, false, 8, 0);
+		}
+
+		gl.bind_buffer(GL::ARRAY_BUFFER, Some(&self.instance_buffer));
+
+		let data = unsafe {
+			std::slice::from_raw_parts(self.instances.as_ptr() as *const u8, std::mem::size_of_val(self.instances.as_slice()))
+		};
+
+		if data.len() > self.instance_capacity {
+			let capacity = data.len().next_power_of_two();
+			gl.buffer_data_with_i32(GL::ARRAY_BUFFER, capacity as i32, GL::DYNAMIC_DRAW);
+			self.instance_capacity = capacity;
+		}
+
+		gl.buffer_sub_data_with_i32_and_u8_array(GL::ARRAY_BUFFER, 0, data);
+
+		let stride = (INSTANCE_STRIDE * 4) as i32;
+		let attributes = [("anchor", 3, 0), ("offset", 2, 12), ("size", 2, 20), ("uvRect", 4, 28), ("color", 4, 44)];
+		let mut enabled = Vec::with_capacity(attributes.len());
+
+		for (name, size, offset) in attributes {
+			let loc = gl.get_attrib_location(program, name);
+
+			if loc >= 0 {
+				gl.enable_vertex_attrib_array(loc as u32);
+				gl.vertex_attrib_pointer_with_i32(loc as u32, size, GL::FLOAT, false, stride, offset);
+				gl.vertex_attrib_divisor(loc as u32, 1);
+				enabled.push(loc as u32);
+			}
+		}
+
+		let state = SavedState::capture(gl);
+
+		if !self.depth_test {
+			gl.disable(GL::DEPTH_TEST);
+		}
+
+		gl.depth_mask(false);
+		blend_over(gl);
+
+		let count = (self.instances.len() / INSTANCE_STRIDE) as i32;
+		gl.draw_arrays_instanced(GL::TRIANGLE_STRIP, 0, 4, count);
+
+		state.restore(gl);
+
+		for loc in enabled {
+			gl.vertex_attrib_divisor(loc, 0);
+			gl.disable_vertex_attrib_array(loc);
+		}
+	}
+}
+
+/// Screen rectangles bucketed into a coarse grid for overlap tests.
+#[derive(Default)]
+struct ScreenGrid {
+	rects: Vec<(Vec2, Vec2)>,
+	cells: HashMap<(i32, i32), Vec<usize>>,
+}
+
+impl ScreenGrid {
+	/// Adds a rectangle unless it overlaps one already added.
+	fn insert(&mut self, min: Vec2, max: Vec2) -> bool {
+		let (x0, y0) = ((min.x / DECLUTTER_CELL).floor() as i32, (min.y / DECLUTTER_CELL).floor() as i32);
+		let (x1, y1) = ((max.x / DECLUTTER_CELL).floor() as i32, (max.y / DECLUTTER_CELL).floor() as i32);
+
+		let overlaps = (x0..=x1).flat_map(|x| (y0..=y1).map(move |y| (x, y))).any(|cell| {
+			self.cells.get(&cell).is_some_and(|indices| {
+				indices.iter().any(|&i| {
+					let (other_min, other_max) = self.rects[i];
+					min.cmplt(other_max).all() && max.cmpgt(other_min).all()
+				})
+			})
+		});
+
+		if overlaps {
+			return false;
+		}
+
+		let index = self.rects.len();
+		self.rects.push((min, max));
+
+		for x in x0..=x1 {
+			for y in y0..=y1 {
+				self.cells.entry((x, y)).or_default().push(index);
+			}
+		}
+
+		true
+	}
+}
+
+/// Computes a signed distance field from a coverage mask.
+///
+/// Returns one byte per pixel, with `128` on glyph edges and the field
+/// spanning `spread` pixels on either side.
+fn signed_distance_field(inside: &[bool], width: usize, height: usize, spread: f32) -> Vec<u8> {
+	let outside: Vec<bool> = inside.iter().map(|&i| !i).collect();
+	let to_inside = distance_to_seeds(inside, width, height);
+	let to_outside = distance_to_seeds(&outside, width, height);
+
+	to_inside
+		.iter()
+		.zip(&to_outside)
+		.map(|(&d_in, &d_out)| {
+			let signed = d_in - d_out;
+			((0.5 - signed / (spread * 2.0)).clamp(0.0, 1.0) * 255.0) as u8
+		})
+		.collect()
+}
+
+/// Returns the distance from every pixel to the nearest seed pixel, using
+/// the two-pass 8SSEDT sweep.
+fn distance_to_seeds(seeds: &[bool], width: usize, height: usize) -> Vec<f32> {
+	const FAR: (i32, i32) = (9999, 9999);
+
+	let mut grid: Vec<(i32, i32)> = seeds.iter().map(|&s| if s { (0, 0) } else { FAR }).collect();
+
+	let length = |(x, y): (i32, i32)| x * x + y * y;
+	let compare = |grid: &mut Vec<(i32, i32)>, x: usize, y: usize, dx: i32, dy: i32| {
+		let (nx, ny) = (x as i32 + dx, y as i32 + dy);
+
+		if nx < 0 || ny < 0 || nx >= width as i32 || ny >= height as i32 {
+			return;
+		}
+
+		let other = grid[ny as usize * width + nx as usize];
+		let candidate = (other.0 + dx, other.1 + dy);
+
+		if length(candidate) < length(grid[y * width + x]) {
+			grid[y * width + x] = candidate;
+		}
+	};
+
+	for y in 0..height {
+		for x in 0..width {
+			compare(&mut grid, x, y, -1, 0);
+			compare(&mut grid, x, y, 0, -1);
+			compare(&mut grid, x, y, -1, -1);
+			compare(&mut grid, x, y, 1, -1);
+		}
+		for x in (0..width).rev() {
+			compare(&mut grid, x, y, 1, 0);
+		}
+	}
+
+	for y in (0..height).rev() {
+		for x in (0..width).rev() {
+			compare(&mut grid, x, y, 1, 0);
+			compare(&mut grid, x, y, 0, 1);
+			compare(&mut grid, x, y, -1, 1);
+			compare(&mut grid, x, y, 1, 1);
+		}
+		for x in 0..width {
+			compare(&mut grid, x, y, -1, 0);
+		}
+	}
+
+	grid.iter().map(|&p| (length(p) as f32).sqrt()).collect()
+}
@@ -3,18 +3,88 @@
 //! Provides light types and utilities for scene illumination.
 //!
 
+use std::collections::HashSet;
 use glam::Vec3;
 use web_sys::{WebGlProgram, WebGl2RenderingContext as GL};
 
+use crate::core::Color;
+
 /// Maximum number of lights supported per draw call.
 pub const MAX_LIGHTS: usize = 4;
 
+/// Default range of spot lights created with [`Light::spot`].
+pub const DEFAULT_SPOT_RANGE: f32 = 10.0;
+
 /// The type of a light source.
+///
+/// Spot cone angles are full cone angles in radians. Intensity is constant
+/// inside `angle` and fades to zero at `outer_angle`.
 #[derive(Clone, Debug)]
 pub enum LightType {
 	Directional,
 	Point { radius: f32 },
-	Spot { angle: f32, outer_angle: f32 },
+	Spot { angle: f32, outer_angle: f32, range: f32 },
+}
+
+/// Distance attenuation curve for point and spot lights.
+///
+/// Every curve reaches zero at the light's radius. The physical inverse
+/// square law never does on its own, so [`Falloff::InverseSquare`] is
+/// multiplied by the smooth window to end there.
+///
+/// Point lights default to [`Falloff::Quadratic`] and spot lights to
+/// [`Falloff::Linear`], the curves they were shaded with before falloff
+/// became configurable.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Falloff {
+	/// `(1 - d / radius)²`, the default for point lights.
+	#[default]
+	Quadratic,
+	/// `1 - d / radius`, the default for spot lights.
+	Linear,
+	/// Physically based `1 / (1 + d²)`, windowed to zero at the radius.
+	InverseSquare,
+	/// `(1 - (d / radius)⁴)²`, full intensity near the light with a soft edge.
+	SmoothWindow,
+}
+
+impl Falloff {
+	/// Returns the falloff as an integer for shader use.
+	pub fn as_index(&self) -> i32 {
+		match self {
+			Falloff::Quadratic => 0,
+			Falloff::Linear => 1,
+			Falloff::InverseSquare => 2,
+			Falloff::SmoothWindow => 3,
+		}
+	}
+}
+
+/// Screen-space contact shadow settings for a light.
+///
+/// Contact shadows march a short ray towards the light through the camera's
+/// depth buffer, adding fine grounding detail that shadow maps miss. They
+/// require [`Scene::enable_contact_shadows`](super::Scene::enable_contact_shadows).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ContactShadows {
+	/// Length of the ray march in world units.
+	pub length: f32,
+	/// Shadow darkness from `0.0` to `1.0`.
+	pub intensity: f32,
+}
+
+/// A glow drawn around a point or spot light, so the light is visible even
+/// without emissive geometry at its position.
+///
+/// The halo is a soft additive sprite in the light's color, fading where it
+/// meets the geometry around the light.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LightHalo {
+	/// Diameter in world units at an intensity of `1.0`, growing with the
+	/// light's intensity.
+	pub size: f32,
+	/// Brightness from `0.0` to `1.0`.
+	pub opacity: f32,
 }
 
 /// A light source in the scene.
@@ -40,6 +110,19 @@ pub enum LightType {
 /// // Light with shadows
 /// let shadow_light = Light::point(pos, color, intensity, radius)
 ///		.with_shadows(true);
+///
+/// // Fine contact shadows within 30cm of occluders
+/// let key = Light::directional(dir, Vec3::ONE, 1.0)
+///		.with_contact_shadows(0.3, 0.8);
+///
+/// // Warm bulb with physically based falloff
+/// let bulb = Light::point(pos, Vec3::ONE, 2.0, 8.0)
+///		.with_temperature(2700.0)
+///		.with_falloff(Falloff::InverseSquare);
+///
+/// // Lantern that glows in the air around it
+/// let lantern = Light::point(pos, Vec3::new(1.0, 0.7, 0.3), 1.5, 6.0)
+///		.with_halo(0.6, 0.8);
 /// ```
 #[derive(Clone, Debug)]
 pub struct Light {
@@ -49,6 +132,16 @@ pub struct Light {
 	pub color: Vec3,
 	pub intensity: f32,
 	pub cast_shadows: bool,
+	pub contact_shadows: Option<ContactShadows>,
+	pub falloff: Falloff,
+	/// Glow drawn at the light's position, see [`with_halo`](Self::with_halo).
+	pub halo: Option<LightHalo>,
+	/// Disabled lights are skipped when uploading lights and rendering shadows.
+	pub enabled: bool,
+	/// Display name, shown in the scene outline and debug labels.
+	pub name: Option<String>,
+	/// Free-form tags for [`Scene::iter_lights_tagged`](super::Scene::iter_lights_tagged).
+	pub tags: HashSet<String>,
 }
 
 impl Light {
@@ -60,6 +153,12 @@ impl Light {
 			color,
 			intensity,
 			cast_shadows: false,
+			contact_shadows: None,
+			falloff: Falloff::default(),
+			halo: None,
+			enabled: true,
+			name: None,
+			tags: HashSet::new(),
 		}
 	}
 
@@ -71,17 +170,29 @@ impl Light {
 			color,
 			intensity,
 			cast_shadows: false,
+			contact_shadows: None,
+			falloff: Falloff::default(),
+			halo: None,
+			enabled: true,
+			name: None,
+			tags: HashSet::new(),
 		}
 	}
 
 	pub fn spot(position: Vec3, direction: Vec3, color: Vec3, intensity: f32, angle: f32) -> Self {
 		Self {
-			light_type: LightType::Spot { angle, outer_angle: angle * 1.2 },
+			light_type: LightType::Spot { angle, outer_angle: angle * 1.2, range: DEFAULT_SPOT_RANGE },
 			position,
 			direction: direction.normalize(),
 			color,
 			intensity,
 			cast_shadows: false,
+			contact_shadows: None,
+			falloff: Falloff::Linear,
+			halo: None,
+			enabled: true,
+			name: None,
+			tags: HashSet::new(),
 		}
 	}
 
@@ -94,18 +205,148 @@ impl Light {
 		}
 	}
 
+	/// Returns the range of a point or spot light, or `0.0` for directional lights.
 	pub fn radius(&self) -> f32 {
 		match self.light_type {
 			LightType::Point { radius } => radius,
-			_ => 0.0,
+			LightType::Spot { range, .. } => range,
+			LightType::Directional => 0.0,
 		}
 	}
 
+	/// Returns the cosines of the inner and outer spot cone half-angles.
+	///
+	/// The outer cutoff is kept strictly below the inner one, since the
+	/// shader's `smoothstep` between them is undefined for equal edges.
+	/// Non-spot lights return `(-1.0, -1.0)`, which disables the cone test.
+	pub fn cone_cutoffs(&self) -> (f32, f32) {
+		match self.light_type {
+			LightType::Spot { angle, outer_angle, .. } => {
+				let inner = (angle * 0.5).cos();
+				(inner, (outer_angle * 0.5).cos().min(inner - 1e-4))
+			}
+			_ => (-1.0, -1.0),
+		}
+	}
+
+	/// Sets the radius of a point light or the range of a spot light.
+	pub fn with_range(mut self, value: f32) -> Self {
+		match &mut self.light_type {
+			LightType::Point { radius } => *radius = value,
+			LightType::Spot { range, .. } => *range = value,
+			LightType::Directional => {}
+		}
+		self
+	}
+
+	/// Sets the outer cone angle of a spot light, in radians.
+	///
+	/// Angles no wider than the inner cone give a hard edge.
+	pub fn with_outer_angle(mut self, value: f32) -> Self {
+		if let LightType::Spot { outer_angle, .. } = &mut self.light_type {
+			*outer_angle = value;
+		}
+		self
+	}
+
 	pub fn with_shadows(mut self, cast: bool) -> Self {
 		self.cast_shadows = cast;
 		self
 	}
 
+	/// Enables screen-space contact shadows with a ray length and intensity.
+	pub fn with_contact_shadows(mut self, length: f32, intensity: f32) -> Self {
+		self.contact_shadows = Some(ContactShadows { length, intensity });
+		self
+	}
+
+	/// Draws a glow `size` world units across at the light, scaled by its
+	/// intensity. Directional lights have no position and draw no halo.
+	pub fn with_halo(mut self, size: f32, opacity: f32) -> Self {
+		self.halo = Some(LightHalo { size, opacity });
+		self
+	}
+
+	/// Returns the world diameter of the light's halo, or `None` for lights
+	/// drawing none.
+	pub fn halo_size(&self) -> Option<f32> {
+		match (&self.light_type, self.halo) {
+			(LightType::Directional, _) | (_, None) => None,
+			(_, Some(halo)) => Some(halo.size * self.intensity.max(0.0)),
+		}
+	}
+
+	pub fn with_falloff(mut self, falloff: Falloff) -> Self {
+		self.falloff = falloff;
+		self
+	}
+
+	pub fn with_enabled(mut self, enabled: bool) -> Self {
+		self.enabled = enabled;
+		self
+	}
+
+	pub fn with_name(mut self, name: &str) -> Self {
+		self.name = Some(name.to_string());
+		self
+	}
+
+	pub fn with_tag(mut self, tag: &str) -> Self {
+		self.tags.insert(tag.to_string());
+		self
+	}
+
+	/// Sets the color from a black-body temperature in Kelvin.
+	///
+	/// See [`Color::from_temperature`].
+	pub fn with_temperature(mut self, kelvin: f32) -> Self {
+		self.set_temperature(kelvin);
+		self
+	}
+
+	pub fn set_temperature(&mut self, kelvin: f32) {
+		self.color = Color::from_temperature(kelvin).to_vec3();
+	}
+
+	/// Evaluates the falloff curve at a distance from the light.
+	///
+	/// Matches the attenuation used by the preset shaders. Directional
+	/// lights always return `1.0`.
+	pub fn attenuation_at(&self, distance: f32) -> f32 {
+		let radius = self.radius();
+
+		if radius <= 0.0 {
+			return if matches!(self.light_type, LightType::Directional) { 1.0 } else { 0.0 };
+		}
+
+		let ratio = distance / radius;
+		let window = (1.0 - ratio.powi(4)).clamp(0.0, 1.0).powi(2);
+
+		match self.falloff {
+			Falloff::Quadratic => (1.0 - ratio).clamp(0.0, 1.0).powi(2),
+			Falloff::Linear => (1.0 - ratio).clamp(0.0, 1.0),
+			Falloff::InverseSquare => window / (distance * distance + 1.0),
+			Falloff::SmoothWindow => window,
+		}
+	}
+
+	/// Returns the distance at which the light falls to half its intensity.
+	pub fn half_intensity_distance(&self) -> f32 {
+		let (mut low, mut high) = (0.0, self.radius());
+
+		for _ in 0..16 {
+			let mid = (low + high) * 0.5;
+
+			if self.attenuation_at(mid) > 0.5 {
+				low = mid;
+			} else {
+				high = mid;
+			}
+		}
+
+		(low + high) * 0.5
+	}
+
 	pub fn apply_uniforms(&self, gl: &GL, program: &WebGlProgram) {
 		if let Some(loc) = gl.get_uniform_location(program, "lightType") {
 			gl.uniform1i(Some(&loc), self.type_id());
@@ -125,27 +366,41 @@ impl Light {
 		if let Some(loc) = gl.get_uniform_location(program, "lightRadius") {
 			gl.uniform1f(Some(&loc), self.radius());
 		}
+		if let Some(loc) = gl.get_uniform_location(program, "lightFalloff") {
+			gl.uniform1i(Some(&loc), self.falloff.as_index());
+		}
+
+		let (inner, outer) = self.cone_cutoffs();
+
+		if let Some(loc) = gl.get_uniform_location(program, "lightInnerCutoff") {
+			gl.uniform1f(Some(&loc), inner);
+		}
+		if let Some(loc) = gl.get_uniform_location(program, "lightOuterCutoff") {
+			gl.uniform1f(Some(&loc), outer);
+		}
 	}
 }
 
 // Hacky, but better than creating a new string every call
-const LIGHT_UNIFORM_NAMES: [[&str; 6]; 4] = [
-	["lights[0].type", "lights[0].direction", "lights[0].position", "lights[0].color", "lights[0].intensity", "lights[0].radius"],
-	["lights[1].type", "lights[1].direction", "lights[1].position", "lights[1].color", "lights[1].intensity", "lights[1].radius"],
-	["lights[2].type", "lights[2].direction", "lights[2].position", "lights[2].color", "lights[2].intensity", "lights[2].radius"],
-	["lights[3].type", "lights[3].direction", "lights[3].position", "lights[3].color", "lights[3].intensity", "lights[3].radius"],
+const LIGHT_UNIFORM_NAMES: [[&str; 11]; 4] = [
+	["lights[0].type", "lights[0].direction", "lights[0].position", "lights[0].color", "lights[0].intensity", "lights[0].radius", "lights[0].falloff", "lights[0].innerCutoff", "lights[0].outerCutoff", "lights[0].contactLength", "lights[0].contactIntensity"],
+	["lights[1].type", "lights[1].direction", "lights[1].position", "lights[1].color", "lights[1].intensity", "lights[1].radius", "lights[1].falloff", "lights[1].innerCutoff", "lights[1].outerCutoff", "lights[1].contactLength", "lights[1].contactIntensity"],
+	["lights[2].type", "lights[2].direction", "lights[2].position", "lights[2].color", "lights[2].intensity", "lights[2].radius", "lights[2].falloff", "lights[2].innerCutoff", "lights[2].outerCutoff", "lights[2].contactLength", "lights[2].contactIntensity"],
+	["lights[3].type", "lights[3].direction", "lights[3].position", "lights[3].color", "lights[3].intensity", "lights[3].radius", "lights[3].falloff", "lights[3].innerCutoff", "lights[3].outerCutoff", "lights[3].contactLength", "lights[3].contactIntensity"],
 ];
 
 /// Uploads light data to shader uniforms.
 ///
-/// Supports up to [`MAX_LIGHTS`] lights per draw call.
+/// Disabled lights are skipped. Supports up to [`MAX_LIGHTS`] enabled
+/// lights per draw call.
 pub fn apply_lights(gl: &GL, program: &WebGlProgram, lights: &[Light]) {
+	let enabled = lights.iter().filter(|l| l.enabled).take(MAX_LIGHTS);
 
 	if let Some(loc) = gl.get_uniform_location(program, "numLights") {
-		gl.uniform1i(Some(&loc), lights.len().min(MAX_LIGHTS) as i32);
+		gl.uniform1i(Some(&loc), enabled.clone().count() as i32);
 	}
 
-	for (i, light) in lights.iter().take(MAX_LIGHTS).enumerate() {
+	for (i, light) in enabled.enumerate() {
 		let names = &LIGHT_UNIFORM_NAMES[i];
 
 		if let Some(loc) = gl.get_uniform_location(program, names[0]) {
@@ -166,5 +421,26 @@ pub fn apply_lights(gl: &GL, program: &WebGlProgram, lights: &[Light]) {
 		if let Some(loc) = gl.get_uniform_location(program, names[5]) {
 			gl.uniform1f(Some(&loc), light.radius());
 		}
+		if let Some(loc) = gl.get_uniform_location(program, names[6]) {
+			gl.uniform1i(Some(&loc), light.falloff.as_index());
+		}
+
+		let (inner, outer) = light.cone_cutoffs();
+
+		if let Some(loc) = gl.get_uniform_location(program, names[7]) {
+			gl.uniform1f(Some(&loc), inner);
+		}
+		if let Some(loc) = gl.get_uniform_location(program, names[8]) {
+			gl.uniform1f(Some(&loc), outer);
+		}
+
+		let contact = light.contact_shadows.unwrap_or(ContactShadows { length: 0.0, intensity: 0.0 });
+
+		if let Some(loc) = gl.get_uniform_location(program, names[9]) {
+			gl.uniform1f(Some(&loc), contact.length);
+		}
+		if let Some(loc) = gl.get_uniform_location(program, names[10]) {
+			gl.uniform1f(Some(&loc), contact.intensity);
+		}
 	}
 }
\ No newline at end of file
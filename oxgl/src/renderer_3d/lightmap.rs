@@ -0,0 +1,193 @@
+//! Lightmap Baking
+//!
+//! Renders the direct light and shadows falling on a mesh into a texture,
+//! so static geometry can be shaded from the texture instead of real-time
+//! lights and shadow lookups. The mesh is drawn in lightmap UV space, one
+//! texel per surface point, and lit the same way the Lambert preset is.
+//!
+//! Texels around each UV island are filled by drawing the mesh again
+//! offset by one texel first, which keeps dark seams out of bilinear
+//! filtering.
+//!
+//! Baked light is stored in linear space, clamped to `1.0`.
+//!
+//! ## Examples
+//!
+//! ```ignore
+//! floor_mesh.set_lightmap_uvs(&gl, &floor_lightmap_uvs)?;
+//! let floor = scene.add(floor_mesh, Transform3D::new());
+//!
+//! scene.enable_shadows(&gl)?;
+//! scene.add_light(Light::directional(sun_dir, Vec3::ONE, 1.0).with_shadows(true));
+//!
+//! let lightmap = scene.bake_lightmap(&gl, floor, 512)?;
+//! scene.objects[floor].mesh.material.set_lightmap(lightmap, 1.0);
+//! ```
+//!
+
+use glam::{Mat4, Vec2};
+use web_sys::WebGl2RenderingContext as GL;
+
+use crate::common::{compile_shader, link_program, Mesh, Texture, render_state::{SavedState, current_viewport}};
+use super::{Light, ShadowSettings, apply_lights, light::MAX_LIGHTS};
+
+const BAKE_VERT: &str = r#"
+	attribute vec3 position;
+	attribute vec3 normal;
+	attribute vec2 uv2;
+
+	uniform mat4 model;
+	uniform mat4 lightSpace;
+	uniform bool shadowsEnabled;
+	uniform vec2 texelOffset;
+
+	varying vec3 vNormal;
+	varying vec3 vWorldPos;
+	varying vec4 vPosLightSpace;
+
+	void main() {
+		vec4 worldPos = model * vec4(position, 1.0);
+		vWorldPos = worldPos.xyz;
+		vNormal = mat3(model) * normal;
+		vPosLightSpace = shadowsEnabled ? lightSpace * worldPos : vec4(0.0);
+
+		gl_Position = vec4(uv2 * 2.0 - 1.0 + texelOffset, 0.0, 1.0);
+	}
+"#;
+
+const BAKE_FRAG: &str = r#"
+	precision highp float;
+
+	#include <lights>
+	#include <shadows>
+
+	// Index of the light casting the shadow map, or -1
+	uniform int shadowLight;
+
+	varying vec3 vNormal;
+	varying vec3 vWorldPos;
+	varying vec4 vPosLightSpace;
+
+	void main() {
+		vec3 normal = normalize(vNormal);
+		float shadow = calculateShadow(vPosLightSpace);
+		vec3 light = vec3(0.0);
+
+		for (int i = 0; i < MAX_LIGHTS; i++) {
+			if (i >= numLights) break;
+
+			float attenuation;
+			vec3 lightDir = lightDirection(lights[i], vWorldPos, attenuation);
+			float shadowed = i == shadowLight ? shadow : 0.0;
+
+			light += max(dot(normal, lightDir), 0.0) * lightRadiance(lights[i]) * attenuation * (1.0 - shadowed);
+		}
+
+		gl_FragColor = vec4(light, 1.0);
+	}
+"#;
+
+/// Lighting baked into a lightmap.
+pub(crate) struct LightmapBake<'a> {
+	/// Enabled lights. Lights past [`MAX_LIGHTS`] are baked in extra passes.
+	pub lights: &'a [Light],
+	/// Light space of the shadow map bound to unit 0, if shadows are
+	/// baked, and the index of the light casting them.
+	pub shadow: Option<(Mat4, usize)>,
+	pub shadow_settings: ShadowSettings,
+	pub shadow_map_size: i32,
+}
+
+/// Bakes the light falling on a mesh into a `size` by `size` texture.
+///
+/// Leaves the canvas framebuffer bound, and restores the viewport, blending,
+/// depth and culling state.
+pub(crate) fn bake(gl: &GL, mesh: &Mesh, model: &Mat4, size: u32, bake: &LightmapBake) -> Result<Texture, String> {
+	let vert = compile_shader(gl, BAKE_VERT, GL::VERTEX_SHADER)?;
+	let frag = compile_shader(gl, BAKE_FRAG, GL::FRAGMENT_SHADER)?;
+	let program = link_program(gl, &vert, &frag)?;
+
+	let size = size.max(1);
+	let texture = Texture::empty(gl, size, size)?;
+	let framebuffer = gl.create_framebuffer().ok_or("Failed to create lightmap framebuffer")?;
+
+	gl.bind_framebuffer(GL::FRAMEBUFFER, Some(&framebuffer));
+	gl.framebuffer_texture_2d(GL::FRAMEBUFFER, GL::COLOR_ATTACHMENT0, GL::TEXTURE_2D, Some(texture.texture()), 0);
+
+	if gl.check_framebuffer_status(GL::FRAMEBUFFER) != GL::FRAMEBUFFER_COMPLETE {
+		gl.bind_framebuffer(GL::FRAMEBUFFER, None);
+		gl.delete_framebuffer(Some(&framebuffer));
+		gl.delete_program(Some(&program));
+		return Err("Lightmap framebuffer is incomplete".to_string());
+	}
+
+	let state = SavedState::capture(gl);
+	let [x, y, width, height] = current_viewport(gl);
+
+	gl.viewport(0, 0, size as i32, size as i32);
+	gl.clear_color(0.0, 0.0, 0.0, 1.0);
+	gl.clear(GL::COLOR_BUFFER_BIT);
+
+	gl.disable(GL::DEPTH_TEST);
+	gl.disable(GL::CULL_FACE);
+	gl.blend_func(GL::ONE, GL::ONE);
+	gl.use_program(Some(&program));
+
+	if let Some(loc) = gl.get_uniform_location(&program, "model") {
+		gl.uniform_matrix4fv_with_f32_array(Some(&loc), false, &model.to_cols_array());
+	}
+	if let Some(loc) = gl.get_uniform_location(&program, "shadowsEnabled") {
+		gl.uniform1i(Some(&loc), bake.shadow.is_some() as i32);
+	}
+	if let Some((light_space, _)) = bake.shadow {
+		if let Some(loc) = gl.get_uniform_location(&program, "lightSpace") {
+			gl.uniform_matrix4fv_with_f32_array(Some(&loc), false, &light_space.to_cols_array());
+		}
+		if let Some(loc) = gl.get_uniform_location(&program, "shadowMap") {
+			gl.uniform1i(Some(&loc), 0);
+		}
+
+		bake.shadow_settings.apply(gl, &program, bake.shadow_map_size);
+	}
+
+	let texel = 2.0 / size as f32;
+	let offset_loc = gl.get_uniform_location(&program, "texelOffset");
+	let shadow_light_loc = gl.get_uniform_location(&program, "shadowLight");
+
+	// The ring of neighbours pads the islands, and the unshifted draw
+	// comes last so it overwrites them
+	let offsets = [(-1, -1), (0, -1), (1, -1), (-1, 0), (1, 0), (-1, 1), (0, 1), (1, 1), (0, 0)];
+
+	for (x, y) in offsets {
+		let offset = Vec2::new(x as f32, y as f32) * texel;
+		gl.uniform2f(offset_loc.as_ref(), offset.x, offset.y);
+
+		// Each group of lights adds to the first, which overwrites what
+		// earlier offsets drew
+		for (group, lights) in bake.lights.chunks(MAX_LIGHTS).enumerate() {
+			let first = group * MAX_LIGHTS;
+			let shadow_light = bake.shadow
+				.map(|(_, index)| index)
+				.filter(|index| (first..first + lights.len()).contains(index))
+				.map_or(-1, |index| (index - first) as i32);
+
+			if group == 0 {
+				gl.disable(GL::BLEND);
+			} else {
+				gl.enable(GL::BLEND);
+			}
+
+			gl.uniform1i(shadow_light_loc.as_ref(), shadow_light);
+			apply_lights(gl, &program, lights);
+			mesh.draw_depth_only(gl, &program);
+		}
+	}
+
+	state.restore(gl);
+	gl.viewport(x, y, width, height);
+	gl.bind_framebuffer(GL::FRAMEBUFFER, None);
+	gl.delete_framebuffer(Some(&framebuffer));
+	gl.delete_program(Some(&program));
+
+	Ok(texture)
+}
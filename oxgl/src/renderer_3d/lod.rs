@@ -0,0 +1,140 @@
+//! Level of Detail
+//!
+//! Provides [`Lod`], a set of simplified meshes that [`Scene`](super::Scene)
+//! swaps in for an object as it gets further from the camera. Levels can be
+//! chosen by camera distance or by how large the object appears on screen,
+//! and a hysteresis band stops objects near a threshold from flickering
+//! between levels.
+//!
+//! ## Examples
+//!
+//! ```ignore
+//! use oxgl::renderer_3d::{Lod, LodMetric};
+//!
+//! let tree = scene.add(tree_high, Transform3D::new());
+//!
+//! // Switch at 20 and 60 units from the camera
+//! scene.set_lod(tree, Lod::new(LodMetric::Distance)
+//!		.with_level(tree_medium, 20.0)
+//!		.with_level(tree_low, 60.0));
+//!
+//! // Or when the object covers less than 25% and 5% of the screen height
+//! scene.set_lod(rock, Lod::new(LodMetric::ScreenSize)
+//!		.with_level(rock_medium, 0.25)
+//!		.with_level(rock_low, 0.05)
+//!		.with_hysteresis(0.2));
+//! ```
+//!
+
+use crate::common::Mesh;
+
+/// How the detail level of an object is chosen.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LodMetric {
+	/// Distance from the camera to the object's bounds center, in world
+	/// units. Thresholds increase with each level.
+	#[default]
+	Distance,
+	/// Projected height of the object's bounding sphere as a fraction of
+	/// the viewport height. Thresholds decrease with each level.
+	///
+	/// Accounts for object size and camera field of view, so zooming in
+	/// keeps detail.
+	ScreenSize,
+}
+
+/// A simplified mesh and the metric value it's used from.
+#[derive(Clone)]
+pub struct LodLevel {
+	pub mesh: Mesh,
+	pub threshold: f32,
+}
+
+/// Lower-detail alternatives for an object's mesh.
+///
+/// The object's own mesh is level 0 and is used until the first threshold
+/// is crossed. Each level keeps its own material.
+#[derive(Clone)]
+pub struct Lod {
+	pub metric: LodMetric,
+	levels: Vec<LodLevel>,
+	/// Fraction a threshold must be overshot by before switching, in either
+	/// direction.
+	pub hysteresis: f32,
+	current: usize,
+}
+
+impl Lod {
+	pub fn new(metric: LodMetric) -> Self {
+		Self {
+			metric,
+			levels: Vec::new(),
+			hysteresis: 0.1,
+			current: 0,
+		}
+	}
+
+	/// Adds a level, used once the metric crosses `threshold`.
+	///
+	/// Levels are kept sorted from most to least detailed.
+	pub fn with_level(mut self, mesh: Mesh, threshold: f32) -> Self {
+		self.levels.push(LodLevel { mesh, threshold });
+
+		match self.metric {
+			LodMetric::Distance => self.levels.sort_by(|a, b| a.threshold.total_cmp(&b.threshold)),
+			LodMetric::ScreenSize => self.levels.sort_by(|a, b| b.threshold.total_cmp(&a.threshold)),
+		}
+
+		self
+	}
+
+	pub fn with_hysteresis(mut self, hysteresis: f32) -> Self {
+		self.hysteresis = hysteresis.max(0.0);
+		self
+	}
+
+	pub fn levels(&self) -> &[LodLevel] {
+		&self.levels
+	}
+
+	/// Returns the selected level, where `0` is the object's own mesh.
+	pub fn current_level(&self) -> usize {
+		self.current
+	}
+
+	/// Returns the mesh for the selected level, or `base` for level 0.
+	pub fn mesh<'a>(&'a self, base: &'a Mesh) -> &'a Mesh {
+		match self.current {
+			0 => base,
+			level => &self.levels[level - 1].mesh,
+		}
+	}
+
+	/// Updates the selected level from a metric value and returns it.
+	///
+	/// Called by the scene every frame.
+	pub fn select(&mut self, value: f32) -> usize {
+		// Scales a threshold away from or towards the current level
+		let (coarser, finer) = match self.metric {
+			LodMetric::Distance => (1.0 + self.hysteresis, 1.0 - self.hysteresis),
+			LodMetric::ScreenSize => (1.0 - self.hysteresis, 1.0 + self.hysteresis),
+		};
+		let beyond = |threshold: f32| match self.metric {
+			LodMetric::Distance => value > threshold,
+			LodMetric::ScreenSize => value < threshold,
+		};
+
+		let mut level = self.current.min(self.levels.len());
+
+		while level < self.levels.len() && beyond(self.levels[level].threshold * coarser) {
+			level += 1;
+		}
+
+		while level > 0 && !beyond(self.levels[level - 1].threshold * finer) {
+			level -= 1;
+		}
+
+		self.current = level;
+		level
+	}
+}
@@ -9,11 +9,84 @@ pub mod gizmo;
 pub mod primitive;
 pub mod scene;
 pub mod shadowmap;
+pub mod contact_shadows;
 pub mod cssrenderer;
+pub mod outline;
+pub mod binding;
+pub mod panorama;
+pub mod collision;
+pub mod transform_gizmo;
+pub mod labels;
+pub mod view_gizmo;
+pub mod terrain;
+pub mod lod;
+pub mod sprite;
+pub mod polyline;
+pub mod viewport;
+pub mod fog;
+pub mod stats;
+pub mod xr;
+pub mod background;
+pub mod html_labels;
+pub mod physics;
+pub mod events;
+pub mod prefab;
+pub mod deferred;
+pub mod occlusion;
+pub mod lightmap;
+pub mod reflection_probe;
+pub mod decal;
+pub mod scene_texture;
+pub mod scene_description;
+pub mod text;
+pub mod text_geometry;
+pub mod selection;
+pub mod inspector;
+pub mod cloth;
+pub mod trail;
+pub mod scatter;
 
 pub use scene::{Scene, DebugSettings, SceneObject};
-pub use primitive::{Primitive, VertexData};
-pub use light::{LightType, Light, apply_lights};
-pub use gizmo::GizmoRenderer;
-pub use shadowmap::ShadowMap;
-pub use cssrenderer::CSS3DRenderer;
\ No newline at end of file
+pub use primitive::{Primitive, VertexData, Lathe, Extrusion, Caps, circle_profile};
+pub use light::{LightType, Light, Falloff, ContactShadows, LightHalo, apply_lights};
+pub use gizmo::{GizmoRenderer, GizmoDepth, GizmoStyle};
+pub use transform_gizmo::{TransformGizmo, GizmoMode};
+pub use selection::Selection;
+pub use labels::{LabelRenderer, Label};
+pub use view_gizmo::{ViewGizmo, ViewGizmoStyle, StandardView, ViewCorner};
+pub use terrain::Terrain;
+pub use scatter::Scatter;
+pub use cloth::{Cloth, ClothCollider};
+pub use lod::{Lod, LodLevel, LodMetric};
+pub use sprite::{Sprite, SpriteAnimation, SpriteCollision, SpriteSize, SpriteHit};
+pub use polyline::{Polyline, LineJoin};
+pub use trail::TrailRenderer;
+pub use viewport::{Viewport, ViewportRect};
+pub use fog::Fog;
+pub use stats::{RenderStats, StatsOverlay};
+pub use xr::{XrSession, XrMode, XrFrame, XrController, XrButton, Handedness};
+pub use background::{Background, Skybox};
+pub(crate) use sprite::SpriteRenderer;
+pub(crate) use text::TextRenderer;
+pub(crate) use polyline::PolylineRenderer;
+pub use shadowmap::{ShadowMap, ShadowFilter, ShadowSettings};
+pub use contact_shadows::ContactShadowMap;
+pub use cssrenderer::CSS3DRenderer;
+pub use html_labels::{HtmlLabel, HtmlLabels};
+pub use physics::{BodyKind, ColliderShape, PhysicsBody, PhysicsWorld};
+pub(crate) use physics::PhysicsSync;
+pub use binding::{Binding, BindingSource, BindingTarget, Bindings};
+pub use collision::{CollisionMesh, RayHit};
+pub use panorama::{Panorama, Hotspot};
+pub use outline::{SceneOutline, OutlineNode, OutlineId, OutlineKind};
+pub use inspector::{PropertyValue, ObjectSnapshot, LightSnapshot, TransformSnapshot, MaterialSnapshot, MeshSnapshot};
+pub use events::SceneEvent;
+pub use prefab::{Prefab, PrefabInstance};
+pub use deferred::RenderPath;
+pub use reflection_probe::{ReflectionProbe, ProbeUpdate};
+pub use decal::{Decal, DecalBlend};
+pub use scene_texture::SceneTexture;
+pub use text::{SdfFont, FontGlyph, TextMesh, TextAlign};
+pub use text_geometry::TextGeometry;
+pub use scene_description::{SceneDescription, ObjectDescription, MaterialDescription, SceneInstance};
+pub(crate) use occlusion::OcclusionCuller;
\ No newline at end of file
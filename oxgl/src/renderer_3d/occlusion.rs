@@ -0,0 +1,272 @@
+//! Occlusion Culling
+//!
+//! Skips objects hidden behind other geometry, such as buildings behind a
+//! wall, using WebGL2 occlusion queries. After the opaque objects are drawn,
+//! the bounding box of each opted-in object is depth tested against them
+//! inside an occlusion query. Objects whose box had no visible
+//! samples are skipped until a later query finds them visible again.
+//!
+//! Query results arrive a frame or more after they are issued, so the scene
+//! keeps drawing with the last known result:
+//!
+//! - Hidden objects are queried every frame, so they reappear a frame late.
+//! - Visible objects are only queried every few frames.
+//! - Hidden objects whose query takes too long are drawn anyway.
+//! - Objects whose bounds contain the camera are always drawn.
+//!
+//! Only worth enabling for objects that are expensive to draw and often
+//! hidden. Culling applies to [`Scene::render`](super::Scene::render).
+//!
+//! ## Examples
+//!
+//! ```ignore
+//! let building = scene.add(building_mesh, Transform3D::new());
+//! scene.set_occlusion_culling(building, true);
+//!
+//! scene.render(&renderer, time);
+//!
+//! let stats = scene.stats();
+//! log::info!("{} occluded, {} queries", stats.occluded_objects, stats.occlusion_queries);
+//! ```
+//!
+
+use std::collections::HashMap;
+use glam::Vec3;
+use slotmap::SlotMap;
+use web_sys::{WebGlBuffer, WebGlProgram, WebGlQuery, WebGl2RenderingContext as GL};
+
+use crate::common::{compile_shader, link_program, Camera, render_state::SavedState};
+use crate::core::{Aabb, ObjectId};
+use super::SceneObject;
+
+const BOX_VERT: &str = r#"
+	#include <logdepth>
+
+	attribute vec3 position;
+
+	uniform mat4 mvp;
+
+	void main() {
+		gl_Position = logDepth(mvp * vec4(position, 1.0));
+	}
+"#;
+
+const BOX_FRAG: &str = r#"
+	precision mediump float;
+
+	void main() {
+		gl_FragColor = vec4(0.0);
+	}
+"#;
+
+/// Frames between queries for objects that were last seen visible.
+const VISIBLE_RETEST_FRAMES: u64 = 4;
+
+/// Frames a hidden object waits for its query before it is drawn anyway.
+const MAX_PENDING_FRAMES: u64 = 3;
+
+#[derive(Default)]
+struct OcclusionState {
+	query: Option<WebGlQuery>,
+	/// Whether `query` was issued and its result not read yet.
+	pending: bool,
+	/// Frame the last query was issued on.
+	issued: u64,
+	occluded: bool,
+}
+
+/// Occlusion queries for the objects of a scene.
+pub(crate) struct OcclusionCuller {
+	program: WebGlProgram,
+	box_buffer: WebGlBuffer,
+	states: HashMap<ObjectId, OcclusionState>,
+	/// Bounds to query once the opaque objects are drawn.
+	queued: Vec<(ObjectId, Aabb)>,
+	frame: u64,
+}
+
+impl OcclusionCuller {
+	pub fn new(gl: &GL) -> Result<Self, String> {
+		let vert = compile_shader(gl, BOX_VERT, GL::VERTEX_SHADER)?;
+		let frag = compile_shader(gl, BOX_FRAG, GL::FRAGMENT_SHADER)?;
+		let program = link_program(gl, &vert, &frag)?;
+
+		let box_buffer = gl.create_buffer().ok_or("Failed to create occlusion box buffer")?;
+		let vertices = unit_box();
+
+		gl.bind_buffer(GL::ARRAY_BUFFER, Some(&box_buffer));
+		unsafe {
+			let array = js_sys::Float32Array::view(&vertices);
+			gl.buffer_data_with_array_buffer_view(GL::ARRAY_BUFFER, &array, GL::STATIC_DRAW);
+		}
+
+		Ok(Self {
+			program,
+			box_buffer,
+			states: HashMap::new(),
+			queued: Vec::new(),
+			frame: 0,
+		})
+	}
+
+	/// Starts a frame, dropping the queries of removed objects and objects
+	/// that no longer use occlusion culling.
+	pub fn begin_frame(&mut self, gl: &GL, objects: &SlotMap<ObjectId, SceneObject>) {
+		self.frame += 1;
+		self.queued.clear();
+
+		self.states.retain(|id, state| {
+			let keep = objects.get(*id).is_some_and(|obj| obj.occlusion_culling);
+
+			if !keep && let Some(query) = state.query.take() {
+				gl.delete_query(Some(&query));
+			}
+
+			keep
+		});
+	}
+
+	/// Returns whether an object should be drawn, reading its last finished
+	/// query and queueing a new one when due.
+	///
+	/// `bounds` are the object's world bounds.
+	pub fn test(&mut self, gl: &GL, id: ObjectId, bounds: &Aabb, camera_position: Vec3, near: f32) -> bool {
+		let state = self.states.entry(id).or_default();
+
+		// The box's front faces are clipped when the camera is inside it
+		if bounds.expanded(near).contains(camera_position) {
+			state.occluded = false;
+			return true;
+		}
+
+		if state.pending && let Some(passed) = state.query.as_ref().and_then(|query| read_query(gl, query)) {
+			state.occluded = !passed;
+			state.pending = false;
+		}
+
+		let waited = self.frame - state.issued;
+
+		let due = state.query.is_none() || state.occluded || waited >= VISIBLE_RETEST_FRAMES;
+
+		if !state.pending && due {
+			self.queued.push((id, *bounds));
+		}
+
+		!state.occluded || (state.pending && waited > MAX_PENDING_FRAMES)
+	}
+
+	/// Draws the queued bounding boxes inside occlusion queries, without
+	/// writing color or depth.
+	///
+	/// Returns the number of queries issued.
+	pub fn flush(&mut self, gl: &GL, camera: &Camera) -> u32 {
+		if self.queued.is_empty() {
+			return 0;
+		}
+
+		let program = &self.program;
+
+		gl.use_program(Some(program));
+		gl.bind_buffer(GL::ARRAY_BUFFER, Some(&self.box_buffer));
+
+		let position_loc = gl.get_attrib_location(program, "position");
+
+		if position_loc >= 0 {
+			gl.enable_vertex_attrib_array(position_loc as u32);
+			gl.vertex_attrib_pointer_with_i32(position_loc as u32, 3, GL::FLOAT, false, 0, 0);
+		}
+
+		if let Some(loc) = gl.get_uniform_location(program, "logDepthFactor") {
+			gl.uniform1f(Some(&loc), camera.log_depth_factor());
+		}
+
+		let view_projection = camera.projection_matrix() * camera.view_matrix();
+		let mvp_loc = gl.get_uniform_location(program, "mvp");
+
+		let issued = query_pass(gl, || {
+			let mut issued = 0;
+
+			for (id, bounds) in self.queued.drain(..) {
+				let Some(state) = self.states.get_mut(&id) else { continue };
+				let mvp = view_projection * bounds.to_matrix();
+
+				gl.uniform_matrix4fv_with_f32_array(mvp_loc.as_ref(), false, &mvp.to_cols_array());
+
+				let Some(query) = issue_query(gl, state.query.take(), || gl.draw_arrays(GL::TRIANGLES, 0, 36)) else { continue };
+
+				state.query = Some(query);
+				state.pending = true;
+				state.issued = self.frame;
+				issued += 1;
+			}
+
+			issued
+		});
+
+		if position_loc >= 0 {
+			gl.disable_vertex_attrib_array(position_loc as u32);
+		}
+
+		issued
+	}
+}
+
+/// Draws occlusion query proxies with `draw`, depth tested against the bound
+/// framebuffer without writing color or depth, then restores the previous
+/// state.
+pub(crate) fn query_pass<R>(gl: &GL, draw: impl FnOnce() -> R) -> R {
+	let state = SavedState::capture(gl);
+
+	gl.enable(GL::DEPTH_TEST);
+	gl.depth_mask(false);
+	gl.color_mask(false, false, false, false);
+
+	let result = draw();
+
+	gl.color_mask(true, true, true, true);
+	state.restore(gl);
+
+	result
+}
+
+/// Runs `draw` inside an occlusion query, reusing `query` or creating one.
+///
+/// Conservative queries are used, which may count a hidden proxy as visible
+/// but never the reverse.
+pub(crate) fn issue_query(gl: &GL, query: Option<WebGlQuery>, draw: impl FnOnce()) -> Option<WebGlQuery> {
+	let query = query.or_else(|| gl.create_query())?;
+
+	gl.begin_query(GL::ANY_SAMPLES_PASSED_CONSERVATIVE, &query);
+	draw();
+	gl.end_query(GL::ANY_SAMPLES_PASSED_CONSERVATIVE);
+
+	Some(query)
+}
+
+/// Returns whether any samples of a finished query passed, or `None` while
+/// its result hasn't arrived.
+pub(crate) fn read_query(gl: &GL, query: &WebGlQuery) -> Option<bool> {
+	let available = gl.get_query_parameter(query, GL::QUERY_RESULT_AVAILABLE).as_bool().unwrap_or(false);
+
+	available.then(|| gl.get_query_parameter(query, GL::QUERY_RESULT).as_f64().unwrap_or(1.0) > 0.0)
+}
+
+/// Triangles of the unit cube from -0.5 to 0.5, matching [`Aabb::to_matrix`].
+fn unit_box() -> Vec<f32> {
+	let corners = Aabb::new(Vec3::splat(-0.5), Vec3::splat(0.5)).corners();
+	// Corner indices per face, as two triangles
+	const FACES: [[usize; 6]; 6] = [
+		[0, 2, 1, 1, 2, 3],
+		[4, 5, 6, 5, 7, 6],
+		[0, 1, 4, 1, 5, 4],
+		[2, 6, 3, 3, 6, 7],
+		[0, 4, 2, 2, 4, 6],
+		[1, 3, 5, 3, 7, 5],
+	];
+
+	FACES
+		.iter()
+		.flatten()
+		.flat_map(|&i| corners[i].to_array())
+		.collect()
+}
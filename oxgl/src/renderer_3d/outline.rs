@@ -0,0 +1,94 @@
+//! Scene Outline
+//!
+//! Provides a lightweight, hierarchical snapshot of a scene's contents for
+//! building object tree panels in UI frameworks.
+//!
+//! ## Examples
+//!
+//! ```ignore
+//! // Rebuild the tree panel whenever the scene structure changes
+//! scene.on_outline_change(move |outline| {
+//!		set_outline.set(outline.clone());
+//! });
+//!
+//! // Or take a snapshot on demand
+//! let outline = scene.outline();
+//! for node in &outline.roots {
+//!		log::info!("{} ({} children)", node.name, node.children.len());
+//! }
+//! ```
+//!
+
+use crate::core::{ObjectId, LightId};
+
+/// Identifies the scene entry an outline node refers to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OutlineId {
+	Object(ObjectId),
+	Light(LightId),
+}
+
+/// The kind of scene entry an outline node represents.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OutlineKind {
+	Mesh,
+	DirectionalLight,
+	PointLight,
+	SpotLight,
+}
+
+/// A single entry in the scene outline.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OutlineNode {
+	pub id: OutlineId,
+	pub name: String,
+	pub kind: OutlineKind,
+	pub children: Vec<OutlineNode>,
+}
+
+/// A hierarchical snapshot of the scene.
+///
+/// Root nodes are objects without a parent, followed by all lights.
+/// The `revision` increases every time the scene structure changes, so it can
+/// be compared cheaply to detect stale snapshots.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SceneOutline {
+	pub roots: Vec<OutlineNode>,
+	pub revision: u64,
+}
+
+impl SceneOutline {
+	/// Finds a node anywhere in the tree by its id.
+	pub fn find(&self, id: OutlineId) -> Option<&OutlineNode> {
+		fn search(nodes: &[OutlineNode], id: OutlineId) -> Option<&OutlineNode> {
+			for node in nodes {
+				if node.id == id {
+					return Some(node);
+				}
+				if let Some(found) = search(&node.children, id) {
+					return Some(found);
+				}
+			}
+			None
+		}
+
+		search(&self.roots, id)
+	}
+
+	/// Returns the total number of nodes in the tree.
+	pub fn len(&self) -> usize {
+		fn count(nodes: &[OutlineNode]) -> usize {
+			nodes.iter().map(|n| 1 + count(&n.children)).sum()
+		}
+
+		count(&self.roots)
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.roots.is_empty()
+	}
+}
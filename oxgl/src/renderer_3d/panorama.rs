@@ -0,0 +1,350 @@
+//! Panorama Viewer
+//!
+//! Displays an equirectangular 360° photo on an inward-facing sphere around
+//! the camera, with drag, wheel zoom and gyroscope controls, and clickable
+//! hotspots rendered through a [`CSS3DRenderer`].
+//!
+//! ## Examples
+//!
+//! ```ignore
+//! use oxgl::common::Texture;
+//! use oxgl::renderer_3d::Panorama;
+//!
+//! let texture = Texture::load(&gl, "assets/lobby.jpg")?;
+//! let mut panorama = Panorama::new(&gl, renderer.canvas(), texture)?;
+//!
+//! // Look around by moving the phone
+//! panorama.enable_gyro()?;
+//!
+//! // Clickable hotspot 30° to the left, slightly below the horizon
+//! let door = panorama.add_hotspot(&css, 30f32.to_radians(), -0.1, "<button>Kitchen</button>")?;
+//! panorama.on_hotspot_click(move |id| {
+//!		if id == door {
+//!			log::info!("Go to kitchen");
+//!		}
+//! });
+//!
+//! scene.panorama = Some(panorama);
+//! ```
+//!
+
+use std::{cell::RefCell, rc::Rc};
+use glam::{EulerRot, Quat, Vec3};
+use slotmap::SlotMap;
+use web_sys::{
+	Event, HtmlCanvasElement, PointerEvent, WheelEvent,
+	WebGl2RenderingContext as GL,
+	wasm_bindgen::{JsCast, prelude::Closure},
+};
+
+use crate::{
+	common::{Camera, DeviceOrientationController, Texture},
+	core::{CSS3DElementId, HotspotId, Transform3D},
+};
+use super::{CSS3DRenderer, Skybox};
+
+/// Maximum look angle above or below the horizon.
+const MAX_PITCH: f32 = 89.0 * std::f32::consts::PI / 180.0;
+
+/// A clickable point of interest in a panorama.
+pub struct Hotspot {
+	pub yaw: f32,
+	pub pitch: f32,
+	element: CSS3DElementId,
+	_listener: Closure<dyn FnMut()>,
+}
+
+#[derive(Default)]
+struct ViewState {
+	yaw: f32,
+	pitch: f32,
+	fov: f32,
+	min_fov: f32,
+	max_fov: f32,
+	drag: Option<(f32, f32)>,
+}
+
+type HotspotCallback = Rc<RefCell<Option<Box<dyn FnMut(HotspotId)>>>>;
+type CanvasListener = (&'static str, Closure<dyn FnMut(Event)>);
+
+/// An equirectangular panorama rendered behind the scene.
+///
+/// Assign to [`Scene::panorama`](super::Scene::panorama) to draw it as the
+/// background and let it control the scene camera.
+///
+/// Yaw and pitch are in radians. Positive yaw turns left, positive pitch
+/// looks up, and zero faces the center of the image.
+pub struct Panorama {
+	sky: Skybox,
+	canvas: HtmlCanvasElement,
+	view: Rc<RefCell<ViewState>>,
+	listeners: Vec<CanvasListener>,
+	gyro: Option<DeviceOrientationController>,
+	hotspots: SlotMap<HotspotId, Hotspot>,
+	on_hotspot_click: HotspotCallback,
+	/// Center of the panorama sphere, also used as the camera position.
+	pub center: Vec3,
+	/// Distance from the center at which hotspots are placed.
+	pub hotspot_distance: f32,
+	/// Whether the panorama moves the camera in [`update_camera`](Self::update_camera).
+	pub controls_enabled: bool,
+}
+
+impl Panorama {
+	/// Creates a panorama and attaches drag and zoom controls to the canvas.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the shaders fail to compile, the sphere buffer
+	/// can't be created, or the event listeners can't be added.
+	pub fn new(gl: &GL, canvas: &HtmlCanvasElement, texture: Texture) -> Result<Self, String> {
+		let sky = Skybox::new(gl, texture)?;
+
+		let view = Rc::new(RefCell::new(ViewState {
+			fov: 75f32.to_radians(),
+			min_fov: 30f32.to_radians(),
+			max_fov: 100f32.to_radians(),
+			..Default::default()
+		}));
+
+		let mut panorama = Self {
+			sky,
+			canvas: canvas.clone(),
+			view,
+			listeners: Vec::new(),
+			gyro: None,
+			hotspots: SlotMap::with_key(),
+			on_hotspot_click: Rc::new(RefCell::new(None)),
+			center: Vec3::ZERO,
+			hotspot_distance: 5.0,
+			controls_enabled: true,
+		};
+
+		panorama.attach_controls()?;
+
+		Ok(panorama)
+	}
+
+	pub fn texture(&self) -> &Texture {
+		self.sky.texture()
+	}
+
+	pub fn set_texture(&mut self, texture: Texture) {
+		self.sky.set_texture(texture);
+	}
+
+	pub fn yaw(&self) -> f32 {
+		self.view.borrow().yaw
+	}
+
+	pub fn pitch(&self) -> f32 {
+		self.view.borrow().pitch
+	}
+
+	/// Returns the vertical field of view in radians.
+	pub fn fov(&self) -> f32 {
+		self.view.borrow().fov
+	}
+
+	/// Turns the view to face a direction.
+	pub fn look_at(&self, yaw: f32, pitch: f32) {
+		let mut view = self.view.borrow_mut();
+		view.yaw = yaw;
+		view.pitch = pitch.clamp(-MAX_PITCH, MAX_PITCH);
+	}
+
+	/// Sets the field of view and the zoom limits, in radians.
+	pub fn set_fov(&self, fov: f32, min: f32, max: f32) {
+		let mut view = self.view.borrow_mut();
+		view.min_fov = min;
+		view.max_fov = max;
+		view.fov = fov.clamp(min, max);
+	}
+
+	/// Starts following the device orientation.
+	///
+	/// Dragging still adjusts the heading. On iOS, call
+	/// [`DeviceOrientationController::request_permission`] from a user gesture
+	/// first.
+	pub fn enable_gyro(&mut self) -> Result<(), String> {
+		if self.gyro.is_none() {
+			self.gyro = Some(DeviceOrientationController::new()?);
+		}
+		Ok(())
+	}
+
+	pub fn disable_gyro(&mut self) {
+		self.gyro = None;
+	}
+
+	/// Returns `true` if the gyroscope is enabled and delivering events.
+	pub fn is_gyro_active(&self) -> bool {
+		self.gyro.as_ref().is_some_and(|g| g.is_active())
+	}
+
+	/// Adds a hotspot at a direction, rendered as a billboard HTML element.
+	///
+	/// Clicking the element invokes the callback set with
+	/// [`on_hotspot_click`](Self::on_hotspot_click).
+	pub fn add_hotspot(&mut self, css: &CSS3DRenderer, yaw: f32, pitch: f32, html: &str) -> Result<HotspotId, String> {
+		let position = self.center + direction(yaw, pitch) * self.hotspot_distance;
+		let element = css.add_billboard(html, position)?;
+		let callback = self.on_hotspot_click.clone();
+		let mut result = Ok(());
+
+		let id = self.hotspots.insert_with_key(|id| {
+			let listener = Closure::<dyn FnMut()>::new(move || {
+				if let Some(f) = callback.borrow_mut().as_mut() {
+					f(id);
+				}
+			});
+
+			css.with_element_mut(element, |obj| {
+				result = obj.element
+					.add_event_listener_with_callback("click", listener.as_ref().unchecked_ref())
+					.map_err(|e| format!("Failed to add hotspot listener: {:?}", e));
+			});
+
+			Hotspot { yaw, pitch, element, _listener: listener }
+		});
+
+		if let Err(e) = result {
+			self.remove_hotspot(css, id);
+			return Err(e);
+		}
+
+		Ok(id)
+	}
+
+	/// Removes a hotspot and its HTML element.
+	pub fn remove_hotspot(&mut self, css: &CSS3DRenderer, id: HotspotId) -> bool {
+		match self.hotspots.remove(id) {
+			Some(hotspot) => css.remove_element(hotspot.element),
+			None => false,
+		}
+	}
+
+	/// Moves a hotspot to a new direction.
+	pub fn move_hotspot(&mut self, css: &CSS3DRenderer, id: HotspotId, yaw: f32, pitch: f32) {
+		let position = self.center + direction(yaw, pitch) * self.hotspot_distance;
+
+		if let Some(hotspot) = self.hotspots.get_mut(id) {
+			hotspot.yaw = yaw;
+			hotspot.pitch = pitch;
+			css.set_transform(hotspot.element, Transform3D::new().with_position(position));
+		}
+	}
+
+	pub fn hotspot(&self, id: HotspotId) -> Option<&Hotspot> {
+		self.hotspots.get(id)
+	}
+
+	/// Sets the callback invoked when a hotspot is clicked.
+	pub fn on_hotspot_click<F>(&self, callback: F)
+	where
+		F: FnMut(HotspotId) + 'static,
+	{
+		*self.on_hotspot_click.borrow_mut() = Some(Box::new(callback));
+	}
+
+	/// Applies the current view to a camera.
+	///
+	/// Places the camera at [`center`](Self::center) and sets its orientation
+	/// and field of view. Does nothing when `controls_enabled` is `false`.
+	pub fn update_camera(&mut self, camera: &mut Camera) {
+		if !self.controls_enabled {
+			return;
+		}
+
+		let view = self.view.borrow();
+		let rotation = match self.gyro.as_ref().filter(|g| g.is_active()) {
+			Some(gyro) => Quat::from_rotation_y(view.yaw) * gyro.orientation(),
+			None => Quat::from_euler(EulerRot::YXZ, view.yaw, view.pitch, 0.0),
+		};
+
+		camera.position = self.center;
+		camera.target = self.center + rotation * Vec3::NEG_Z;
+		camera.up = rotation * Vec3::Y;
+		camera.fov_y = view.fov;
+	}
+
+	/// Draws the panorama behind everything else.
+	///
+	/// Writes no depth, so it can be drawn at any point after clearing.
+	pub fn draw(&self, gl: &GL, camera: &Camera) {
+		self.sky.draw(gl, camera);
+	}
+
+	fn attach_controls(&mut self) -> Result<(), String> {
+		let canvas = self.canvas.clone();
+		let view = self.view.clone();
+
+		self.listen("pointerdown", move |event| {
+			let Some(event) = event.dyn_ref::<PointerEvent>() else { return };
+			let _ = canvas.set_pointer_capture(event.pointer_id());
+			view.borrow_mut().drag = Some((event.client_x() as f32, event.client_y() as f32));
+		})?;
+
+		let canvas = self.canvas.clone();
+		let view = self.view.clone();
+
+		self.listen("pointermove", move |event| {
+			let Some(event) = event.dyn_ref::<PointerEvent>() else { return };
+			let mut view = view.borrow_mut();
+			let Some((last_x, last_y)) = view.drag else { return };
+
+			let (x, y) = (event.client_x() as f32, event.client_y() as f32);
+
+			// Keep the image under the pointer regardless of zoom
+			let per_pixel = view.fov / canvas.client_height().max(1) as f32;
+
+			view.yaw += (x - last_x) * per_pixel;
+			view.pitch = (view.pitch + (y - last_y) * per_pixel).clamp(-MAX_PITCH, MAX_PITCH);
+			view.drag = Some((x, y));
+		})?;
+
+		for name in ["pointerup", "pointercancel"] {
+			let view = self.view.clone();
+
+			self.listen(name, move |_| {
+				view.borrow_mut().drag = None;
+			})?;
+		}
+
+		let view = self.view.clone();
+
+		self.listen("wheel", move |event| {
+			let Some(event) = event.dyn_ref::<WheelEvent>() else { return };
+			event.prevent_default();
+
+			let mut view = view.borrow_mut();
+			view.fov = (view.fov * (event.delta_y() as f32 * 0.001).exp()).clamp(view.min_fov, view.max_fov);
+		})?;
+
+		Ok(())
+	}
+
+	fn listen(&mut self, name: &'static str, handler: impl FnMut(Event) + 'static) -> Result<(), String> {
+		let closure = Closure::<dyn FnMut(Event)>::new(handler);
+
+		self.canvas
+			.add_event_listener_with_callback(name, closure.as_ref().unchecked_ref())
+			.map_err(|e| format!("Failed to add {} listener: {:?}", name, e))?;
+
+		self.listeners.push((name, closure));
+		Ok(())
+	}
+}
+
+impl Drop for Panorama {
+	fn drop(&mut self) {
+		for (name, closure) in &self.listeners {
+			let _ = self.canvas.remove_event_listener_with_callback(name, closure.as_ref().unchecked_ref());
+		}
+	}
+}
+
+/// Returns the unit view direction for a yaw and pitch.
+fn direction(yaw: f32, pitch: f32) -> Vec3 {
+	Quat::from_euler(EulerRot::YXZ, yaw, pitch, 0.0) * Vec3::NEG_Z
+}
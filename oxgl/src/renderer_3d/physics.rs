@@ -0,0 +1,150 @@
+//! Physics Bridge
+//!
+//! Connects a scene to an external physics engine such as rapier. Objects
+//! describe their collider with a [`PhysicsBody`], and any engine wrapped in
+//! the [`PhysicsWorld`] trait can simulate them. Once a world is set with
+//! [`Scene::set_physics`](super::Scene::set_physics), the scene registers
+//! bodies, pushes kinematic transforms, steps the world and copies dynamic
+//! bodies back into [`SceneObject::transform`](super::SceneObject) at the
+//! start of every frame.
+//!
+//! Bodies are simulated in world space. Shapes are given in world units, as
+//! the object's scale isn't applied to them.
+//!
+//! ## Examples
+//!
+//! ```ignore
+//! use oxgl::renderer_3d::{ColliderShape, PhysicsBody, PhysicsWorld};
+//!
+//! struct RapierWorld { /* pipeline, bodies, colliders, handles by ObjectId */ }
+//!
+//! impl PhysicsWorld for RapierWorld {
+//!		fn add_body(&mut self, id: ObjectId, body: &PhysicsBody, transform: &Transform3D) { /* ... */ }
+//!		fn remove_body(&mut self, id: ObjectId) { /* ... */ }
+//!		fn set_body_transform(&mut self, id: ObjectId, transform: &Transform3D) { /* ... */ }
+//!		fn step(&mut self, dt: f32) { /* ... */ }
+//!		fn body_transform(&self, id: ObjectId) -> Option<(Vec3, Quat)> { /* ... */ }
+//! }
+//!
+//! let world = Rc::new(RefCell::new(RapierWorld::new()));
+//! scene.set_physics(world.clone());
+//!
+//! scene.set_body(ground, PhysicsBody::fixed(ColliderShape::Box { half_extents: Vec3::new(10.0, 0.1, 10.0) }));
+//! scene.set_body(crate_id, PhysicsBody::dynamic(ColliderShape::Box { half_extents: Vec3::splat(0.5) }).with_mass(20.0));
+//!
+//! // The world is still available for forces and queries
+//! world.borrow_mut().apply_impulse(crate_id, Vec3::Y * 50.0);
+//! ```
+//!
+
+use std::{cell::RefCell, collections::HashSet, rc::Rc};
+use glam::{Quat, Vec3};
+
+use crate::core::{ObjectId, Transform3D};
+use super::CollisionMesh;
+
+/// The shape of a collider, in world units around the object's origin.
+#[derive(Clone, Debug)]
+pub enum ColliderShape {
+	Box { half_extents: Vec3 },
+	Sphere { radius: f32 },
+	/// A capsule along the local Y axis. `half_height` excludes the caps.
+	Capsule { half_height: f32, radius: f32 },
+	/// An arbitrary triangle mesh, usually only supported for fixed bodies.
+	Mesh(CollisionMesh),
+}
+
+/// How a body takes part in the simulation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BodyKind {
+	/// Moved by the simulation, with its transform copied back to the scene.
+	Dynamic,
+	/// Never moves. Its transform is only read when the body is added.
+	Fixed,
+	/// Moved by the scene, pushing dynamic bodies out of the way.
+	Kinematic,
+}
+
+/// A physics body attached to a scene object.
+#[derive(Clone, Debug)]
+pub struct PhysicsBody {
+	pub shape: ColliderShape,
+	pub kind: BodyKind,
+	/// Mass in kilograms, for dynamic bodies.
+	pub mass: f32,
+	pub friction: f32,
+	/// Bounciness, from `0.0` (none) to `1.0` (perfectly elastic).
+	pub restitution: f32,
+}
+
+impl PhysicsBody {
+	pub fn new(shape: ColliderShape, kind: BodyKind) -> Self {
+		Self {
+			shape,
+			kind,
+			mass: 1.0,
+			friction: 0.5,
+			restitution: 0.0,
+		}
+	}
+
+	pub fn dynamic(shape: ColliderShape) -> Self {
+		Self::new(shape, BodyKind::Dynamic)
+	}
+
+	pub fn fixed(shape: ColliderShape) -> Self {
+		Self::new(shape, BodyKind::Fixed)
+	}
+
+	pub fn kinematic(shape: ColliderShape) -> Self {
+		Self::new(shape, BodyKind::Kinematic)
+	}
+
+	pub fn with_mass(mut self, mass: f32) -> Self {
+		self.mass = mass;
+		self
+	}
+
+	pub fn with_friction(mut self, friction: f32) -> Self {
+		self.friction = friction;
+		self
+	}
+
+	pub fn with_restitution(mut self, restitution: f32) -> Self {
+		self.restitution = restitution;
+		self
+	}
+}
+
+/// An external physics simulation driven by a scene.
+///
+/// Bodies are identified by the [`ObjectId`] of the object they belong to,
+/// and all transforms are in world space.
+pub trait PhysicsWorld {
+	/// Creates a body for an object, placed at its world transform.
+	fn add_body(&mut self, id: ObjectId, body: &PhysicsBody, transform: &Transform3D);
+
+	/// Removes an object's body, if it has one.
+	fn remove_body(&mut self, id: ObjectId);
+
+	/// Moves a kinematic body to the object's current world transform.
+	fn set_body_transform(&mut self, id: ObjectId, transform: &Transform3D);
+
+	/// Advances the simulation by `dt` seconds.
+	fn step(&mut self, dt: f32);
+
+	/// Returns the world position and rotation of an object's body.
+	fn body_transform(&self, id: ObjectId) -> Option<(Vec3, Quat)>;
+}
+
+/// A physics world and the objects registered with it.
+pub(crate) struct PhysicsSync {
+	pub world: Rc<RefCell<dyn PhysicsWorld>>,
+	pub registered: HashSet<ObjectId>,
+}
+
+impl PhysicsSync {
+	pub fn new(world: Rc<RefCell<dyn PhysicsWorld>>) -> Self {
+		Self { world, registered: HashSet::new() }
+	}
+}
@@ -0,0 +1,384 @@
+//! Polylines
+//!
+//! Provides persistent wide lines for trajectories, graphs and wire art.
+//! WebGL caps `gl.lineWidth` at 1 pixel on most platforms, so each segment
+//! is drawn as a quad expanded to the line width in the vertex shader.
+//!
+//! Polylines live in the [`Scene`](super::Scene) next to meshes and are drawn
+//! after them. Their vertex data is rebuilt only when points change.
+//!
+//! ## Examples
+//!
+//! ```ignore
+//! use oxgl::renderer_3d::{LineJoin, Polyline};
+//! use glam::{Vec3, Vec4};
+//!
+//! // A 4 pixel wide orbit
+//! let orbit: Vec<Vec3> = (0..64)
+//!		.map(|i| {
+//!			let angle = i as f32 / 64.0 * std::f32::consts::TAU;
+//!			Vec3::new(angle.cos() * 5.0, 0.0, angle.sin() * 5.0)
+//!		})
+//!		.collect();
+//!
+//! scene.add_polyline(Polyline::new(orbit).with_width(4.0).closed());
+//!
+//! // A trail that fades out, extended every frame
+//! let trail = scene.add_polyline(
+//!		Polyline::new(Vec::new())
+//!			.with_join(LineJoin::Miter)
+//!			.with_color(Vec4::new(1.0, 0.6, 0.1, 1.0))
+//! );
+//!
+//! scene.polylines[trail].push(player_position);
+//! ```
+//!
+
+use glam::{Vec3, Vec4};
+use web_sys::{WebGlBuffer, WebGlProgram, WebGl2RenderingContext as GL};
+
+use crate::common::{compile_shader, link_program, Camera};
+use super::background::blend_over;
+
+const POLYLINE_VERT: &str = r#"
+	#include <logdepth>
+
+	attribute vec3 position;
+	attribute vec3 previous;
+	attribute vec3 next;
+	attribute float side;
+	attribute vec4 color;
+
+	uniform mat4 viewProjection;
+	uniform vec2 viewport;
+	uniform float width;
+	uniform float miterLimit;
+
+	varying vec4 vColor;
+
+	vec2 toScreen(vec4 clip) {
+		return clip.xy / clip.w * viewport * 0.5;
+	}
+
+	void main() {
+		vec4 clip = viewProjection * vec4(position, 1.0);
+		vec2 here = toScreen(clip);
+		vec2 before = toScreen(viewProjection * vec4(previous, 1.0));
+		vec2 after = toScreen(viewProjection * vec4(next, 1.0));
+
+		vec2 incoming = here - before;
+		vec2 outgoing = after - here;
+
+		// Endpoints reuse the only segment they have
+		if (length(incoming) < 1e-4) incoming = outgoing;
+		if (length(outgoing) < 1e-4) outgoing = incoming;
+
+		incoming = normalize(incoming);
+		outgoing = normalize(outgoing);
+
+		vec2 normal = vec2(-incoming.y, incoming.x);
+		vec2 offset = normal * width * 0.5 * side;
+
+		// A line turning straight back has no miter, so the corner is cut
+		// flat across the incoming segment instead
+		if (length(incoming + outgoing) > 1e-4) {
+			vec2 tangent = normalize(incoming + outgoing);
+			vec2 miter = vec2(-tangent.y, tangent.x);
+
+			// Sharp corners are clamped instead of spiking
+			float extent = width * 0.5 / max(dot(miter, normal), 1.0 / miterLimit);
+			offset = miter * extent * side;
+		}
+
+		clip.xy += offset / (viewport * 0.5) * clip.w;
+		gl_Position = logDepth(clip);
+		vColor = color;
+	}
+"#;
+
+const POLYLINE_FRAG: &str = r#"
+	precision mediump float;
+
+	varying vec4 vColor;
+
+	void main() {
+		gl_FragColor = vColor;
+	}
+"#;
+
+/// Floats per vertex: position, previous, next, side, color.
+const VERTEX_STRIDE: usize = 14;
+
+/// How consecutive segments of a [`Polyline`] are connected.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LineJoin {
+	/// Segments meet at a mitered corner, clamped at sharp angles. A
+	/// corner turning straight back is cut flat.
+	#[default]
+	Miter,
+	/// Each segment is a separate quad, leaving gaps on the outside of
+	/// corners. Suited to disconnected dashes.
+	None,
+}
+
+/// A wide line through a list of points.
+pub struct Polyline {
+	points: Vec<Vec3>,
+	colors: Vec<Vec4>,
+	/// Color of points without a per-point color.
+	pub color: Vec4,
+	/// Width in pixels.
+	pub width: f32,
+	pub join: LineJoin,
+	/// Corners sharper than this ratio of miter length to line width are
+	/// clamped.
+	pub miter_limit: f32,
+	/// Connects the last point back to the first.
+	pub is_closed: bool,
+	pub visible: bool,
+	pub name: Option<String>,
+	buffer: Option<WebGlBuffer>,
+	vertex_count: i32,
+	dirty: bool,
+}
+
+impl Polyline {
+	pub fn new(points: Vec<Vec3>) -> Self {
+		Self {
+			points,
+			colors: Vec::new(),
+			color: Vec4::ONE,
+			width: 2.0,
+			join: LineJoin::default(),
+			miter_limit: 4.0,
+			is_closed: false,
+			visible: true,
+			name: None,
+			buffer: None,
+			vertex_count: 0,
+			dirty: true,
+		}
+	}
+
+	pub fn with_width(mut self, width: f32) -> Self {
+		self.width = width;
+		self
+	}
+
+	pub fn with_color(mut self, color: Vec4) -> Self {
+		self.color = color;
+		self.dirty = true;
+		self
+	}
+
+	/// Sets a color per point. Points past the end use [`color`](Self::color).
+	pub fn with_colors(mut self, colors: Vec<Vec4>) -> Self {
+		self.colors = colors;
+		self.dirty = true;
+		self
+	}
+
+	pub fn with_join(mut self, join: LineJoin) -> Self {
+		self.join = join;
+		self.dirty = true;
+		self
+	}
+
+	pub fn with_name(mut self, name: &str) -> Self {
+		self.name = Some(name.to_string());
+		self
+	}
+
+	/// Connects the last point back to the first.
+	pub fn closed(mut self) -> Self {
+		self.is_closed = true;
+		self.dirty = true;
+		self
+	}
+
+	pub fn points(&self) -> &[Vec3] {
+		&self.points
+	}
+
+	pub fn colors(&self) -> &[Vec4] {
+		&self.colors
+	}
+
+	pub fn set_points(&mut self, points: Vec<Vec3>) {
+		self.points = points;
+		self.dirty = true;
+	}
+
+	pub fn set_colors(&mut self, colors: Vec<Vec4>) {
+		self.colors = colors;
+		self.dirty = true;
+	}
+
+	/// Appends a point to the end of the line.
+	pub fn push(&mut self, point: Vec3) {
+		self.points.push(point);
+		self.dirty = true;
+	}
+
+	/// Marks the vertex data for rebuilding after changing
+	/// [`color`](Self::color), [`join`](Self::join) or
+	/// [`is_closed`](Self::is_closed) directly.
+	pub fn mark_dirty(&mut self) {
+		self.dirty = true;
+	}
+
+	fn upload(&mut self, gl: &GL) {
+		let vertices = self.vertices();
+
+		if self.buffer.is_none() {
+			self.buffer = gl.create_buffer();
+		}
+
+		let Some(buffer) = &self.buffer else {
+			log::error!("Failed to create polyline buffer");
+			return;
+		};
+
+		let data = unsafe {
+			std::slice::from_raw_parts(vertices.as_ptr() as *const u8, std::mem::size_of_val(vertices.as_slice()))
+		};
+
+		gl.bind_buffer(GL::ARRAY_BUFFER, Some(buffer));
+		gl.buffer_data_with_u8_array(GL::ARRAY_BUFFER, data, GL::DYNAMIC_DRAW);
+
+		self.vertex_count = (vertices.len() / VERTEX_STRIDE) as i32;
+		self.dirty = false;
+	}
+
+	/// Builds the expanded vertex data, two triangles per segment.
+	fn vertices(&self) -> Vec<f32> {
+		let count = self.points.len();
+
+		if count < 2 {
+			return Vec::new();
+		}
+
+		let segments = if self.is_closed { count } else { count - 1 };
+		let wrap = |i: isize| -> Option<usize> {
+			if self.is_closed {
+				Some(i.rem_euclid(count as isize) as usize)
+			} else {
+				(0..count as isize).contains(&i).then_some(i as usize)
+			}
+		};
+
+		let mut vertices = Vec::with_capacity(segments * 6 * VERTEX_STRIDE);
+
+		for segment in 0..segments {
+			let a = segment;
+			let b = (segment + 1) % count;
+
+			// Neighbors outside the segment only matter for joined corners
+			let (before, after) = match self.join {
+				LineJoin::Miter => (
+					wrap(a as isize - 1).unwrap_or(a),
+					wrap(segment as isize + 2).unwrap_or(b),
+				),
+				LineJoin::None => (a, b),
+			};
+
+			let corner = |point: usize, side: f32, vertices: &mut Vec<f32>| {
+				let (previous, next) = if point == a { (before, b) } else { (a, after) };
+				let color = self.colors.get(point).copied().unwrap_or(self.color);
+
+				vertices.extend_from_slice(&self.points[point].to_array());
+				vertices.extend_from_slice(&self.points[previous].to_array());
+				vertices.extend_from_slice(&self.points[next].to_array());
+				vertices.push(side);
+				vertices.extend_from_slice(&color.to_array());
+			};
+
+			for (point, side) in [(a, 1.0), (a, -1.0), (b, 1.0), (b, 1.0), (a, -1.0), (b, -1.0)] {
+				corner(point, side, &mut vertices);
+			}
+		}
+
+		vertices
+	}
+}
+
+/// Draws polylines for a [`Scene`](super::Scene).
+pub(crate) struct PolylineRenderer {
+	program: WebGlProgram,
+}
+
+impl PolylineRenderer {
+	pub fn new(gl: &GL) -> Result<Self, String> {
+		let vert = compile_shader(gl, POLYLINE_VERT, GL::VERTEX_SHADER)?;
+		let frag = compile_shader(gl, POLYLINE_FRAG, GL::FRAGMENT_SHADER)?;
+
+		Ok(Self { program: link_program(gl, &vert, &frag)? })
+	}
+
+	/// Draws visible polylines, uploading vertex data for changed ones.
+	pub fn draw<'a>(&self, gl: &GL, camera: &Camera, polylines: impl Iterator<Item = &'a mut Polyline>, width: f32, height: f32) {
+		let program = &self.program;
+		let view_projection = camera.projection_matrix() * camera.view_matrix();
+
+		gl.use_program(Some(program));
+
+		if let Some(loc) = gl.get_uniform_location(program, "viewProjection") {
+			gl.uniform_matrix4fv_with_f32_array(Some(&loc), false, &view_projection.to_cols_array());
+		}
+		if let Some(loc) = gl.get_uniform_location(program, "viewport") {
+			gl.uniform2f(Some(&loc), width, height);
+		}
+		if let Some(loc) = gl.get_uniform_location(program, "logDepthFactor") {
+			gl.uniform1f(Some(&loc), camera.log_depth_factor());
+		}
+
+		let stride = (VERTEX_STRIDE * 4) as i32;
+		let attributes = [("position", 3, 0), ("previous", 3, 12), ("next", 3, 24), ("side", 1, 36), ("color", 4, 40)];
+
+		blend_over(gl);
+
+		let mut enabled = Vec::with_capacity(attributes.len());
+
+		for line in polylines.filter(|line| line.visible) {
+			if line.dirty {
+				line.upload(gl);
+			}
+
+			let Some(buffer) = &line.buffer else { continue };
+
+			if line.vertex_count == 0 {
+				continue;
+			}
+
+			if let Some(loc) = gl.get_uniform_location(program, "width") {
+				gl.uniform1f(Some(&loc), line.width);
+			}
+			if let Some(loc) = gl.get_uniform_location(program, "miterLimit") {
+				gl.uniform1f(Some(&loc), line.miter_limit.max(1.0));
+			}
+
+			gl.bind_buffer(GL::ARRAY_BUFFER, Some(buffer));
+
+			for (name, size, offset) in attributes {
+				let loc = gl.get_attrib_location(program, name);
+
+				if loc >= 0 {
+					gl.enable_vertex_attrib_array(loc as u32);
+					gl.vertex_attrib_pointer_with_i32(loc as u32, size, GL::FLOAT, false, stride, offset);
+
+					if name != "position" && !enabled.contains(&(loc as u32)) {
+						enabled.push(loc as u32);
+					}
+				}
+			}
+
+			gl.draw_arrays(GL::TRIANGLES, 0, line.vertex_count);
+		}
+
+		gl.disable(GL::BLEND);
+
+		for loc in enabled {
+			gl.disable_vertex_attrib_array(loc);
+		}
+	}
+}
@@ -0,0 +1,199 @@
+//! Prefabs
+//!
+//! Provides [`Prefab`], a template of objects and lights that can be spawned
+//! into a scene any number of times with
+//! [`Scene::spawn`](super::Scene::spawn). Instances share vertex buffers and
+//! shader programs with the template, so spawning only copies the object
+//! data on the CPU.
+//!
+//! ## Examples
+//!
+//! ```ignore
+//! use oxgl::renderer_3d::Prefab;
+//! use oxgl::core::Transform3D;
+//! use glam::Vec3;
+//!
+//! // Capture a lamp post built in the scene, with its children and its light
+//! let lamp_post = Prefab::capture(&scene, post, &[bulb_light]).ok_or("Lamp post not found")?;
+//!
+//! for i in 0..10 {
+//!		let position = Vec3::new(i as f32 * 4.0, 0.0, 0.0);
+//!		scene.spawn(&lamp_post, Transform3D::new().with_position(position));
+//! }
+//!
+//! // Or build a template directly
+//! let mut tree = Prefab::new();
+//! let trunk = tree.add(trunk_mesh, Transform3D::new());
+//! tree.add_child(trunk, leaves_mesh, Transform3D::new().with_position(Vec3::Y * 2.0));
+//!
+//! let instance = scene.spawn(&tree, Transform3D::new().with_scale(Vec3::splat(1.5)));
+//! scene.add_tag(instance.root(), "tree");
+//! ```
+//!
+
+use glam::{Mat4, Vec3};
+
+use crate::common::Mesh;
+use crate::core::{LightId, ObjectId, Transform3D, Transformable};
+use super::{Light, Scene, SceneObject};
+
+/// A reusable template of objects and lights.
+#[derive(Clone, Default)]
+pub struct Prefab {
+	/// Objects in an order where parents come before their children.
+	pub(crate) nodes: Vec<PrefabNode>,
+	/// Lights placed relative to the prefab's origin.
+	pub(crate) lights: Vec<Light>,
+}
+
+#[derive(Clone)]
+pub(crate) struct PrefabNode {
+	pub object: SceneObject,
+	/// Index of the parent node.
+	pub parent: Option<usize>,
+}
+
+/// The objects and lights created by [`Scene::spawn`](super::Scene::spawn).
+#[derive(Clone, Debug, Default)]
+pub struct PrefabInstance {
+	/// Spawned objects, in the order they were added to the prefab.
+	pub objects: Vec<ObjectId>,
+	pub lights: Vec<LightId>,
+}
+
+impl PrefabInstance {
+	/// Returns the first spawned object, which is the captured root for
+	/// prefabs made with [`Prefab::capture`].
+	///
+	/// # Panics
+	///
+	/// Panics if the prefab had no objects.
+	pub fn root(&self) -> ObjectId {
+		self.objects[0]
+	}
+}
+
+impl Prefab {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Adds a root object, placed relative to the spawn transform.
+	///
+	/// Returns the node index for [`add_child`](Self::add_child).
+	pub fn add(&mut self, mesh: Mesh, transform: Transform3D) -> usize {
+		self.push(mesh, transform, None)
+	}
+
+	/// Adds an object parented to a node.
+	///
+	/// # Panics
+	///
+	/// Panics if `parent` isn't an index returned by this prefab.
+	pub fn add_child(&mut self, parent: usize, mesh: Mesh, transform: Transform3D) -> usize {
+		assert!(parent < self.nodes.len(), "Prefab node {} doesn't exist", parent);
+		self.push(mesh, transform, Some(parent))
+	}
+
+	/// Adds a light, placed relative to the spawn transform.
+	pub fn add_light(&mut self, light: Light) {
+		self.lights.push(light);
+	}
+
+	/// Captures an object, all of its descendants and the given lights.
+	///
+	/// The root keeps its world rotation and scale, including those of its
+	/// parents, but not its position, so instances are placed by the spawn
+	/// transform. Lights are stored
+	/// relative to the root's world position. Names, tags, colliders,
+	/// level-of-detail meshes and physics bodies are captured too.
+	///
+	/// Returns `None` if the root doesn't exist.
+	pub fn capture(scene: &Scene, root: ObjectId, lights: &[LightId]) -> Option<Self> {
+		let root_object = scene.get(root)?;
+		let (scale, rotation, origin) = scene.world_matrix(root).to_scale_rotation_translation();
+
+		let mut object = root_object.clone();
+		object.parent = None;
+		object.transform.position = Vec3::ZERO;
+		object.transform.rotation = rotation;
+		object.transform.scale = scale;
+
+		let mut prefab = Self::new();
+		prefab.nodes.push(PrefabNode { object, parent: None });
+
+		// Children are captured after the parent that queued them
+		let mut pending = vec![(root, 0)];
+
+		while let Some((id, index)) = pending.pop() {
+			for child in scene.children(id) {
+				let mut object = scene.objects[child].clone();
+				object.parent = None;
+
+				prefab.nodes.push(PrefabNode { object, parent: Some(index) });
+				pending.push((child, prefab.nodes.len() - 1));
+			}
+		}
+
+		prefab.lights = lights
+			.iter()
+			.filter_map(|&id| scene.lights.get(id))
+			.map(|light| {
+				let mut light = light.clone();
+				light.position -= origin;
+				light
+			})
+			.collect();
+
+		Some(prefab)
+	}
+
+	/// Returns the number of objects in the prefab.
+	pub fn len(&self) -> usize {
+		self.nodes.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.nodes.is_empty() && self.lights.is_empty()
+	}
+
+	/// Returns the template object of a node, to adjust its material or
+	/// other settings before spawning.
+	pub fn node_mut(&mut self, index: usize) -> Option<&mut SceneObject> {
+		self.nodes.get_mut(index).map(|node| &mut node.object)
+	}
+
+	fn push(&mut self, mesh: Mesh, transform: Transform3D, parent: Option<usize>) -> usize {
+		let name = mesh.name.clone();
+
+		self.nodes.push(PrefabNode {
+			object: SceneObject {
+				mesh,
+				transform,
+				name,
+				tags: Default::default(),
+				parent: None,
+				collider: None,
+				lod: None,
+				body: None,
+				trail: None,
+				distortion: None,
+				visible: true,
+				occlusion_culling: false,
+				cast_shadows: true,
+				receive_shadows: true,
+				show_normals: false,
+			},
+			parent,
+		});
+
+		self.nodes.len() - 1
+	}
+}
+
+/// Applies a parent matrix to a transform.
+pub(crate) fn compose(parent: Mat4, transform: &Transform3D) -> Transform3D {
+	let (scale, rotation, position) = (parent * transform.to_matrix()).to_scale_rotation_translation();
+
+	Transform3D { position, rotation, scale }
+}
@@ -18,9 +18,67 @@
 //! // Create an unlit quad
 //! let quad_vertices = Primitive::Quad.vertices();
 //! let quad = Mesh::new(&gl, &quad_vertices, presets::unlit(&gl, Vec3::ONE));
+//!
+//! // A cube with a different color per face, for checking orientation
+//! let debug = Mesh::from_data(&gl, &Primitive::Cube.with_face_colors(), presets::lambert(&gl, Vec3::ONE));
+//! ```
+//!
+//! ## Procedural Meshes
+//!
+//! [`Lathe`] spins a 2D profile around an axis and [`Extrusion`] sweeps one
+//! along a path, for pipes, vases, rails and other simple shapes.
+//!
+//! ```ignore
+//! use oxgl::common::{Mesh, material::presets};
+//! use oxgl::core::Path;
+//! use oxgl::renderer_3d::{Caps, Extrusion, Lathe, circle_profile};
+//! use glam::{Vec2, Vec3};
+//!
+//! // A vase, from the profile of its outer wall
+//! let vase = Lathe::new(vec![
+//!		Vec2::new(0.4, 0.0),
+//!		Vec2::new(0.7, 0.6),
+//!		Vec2::new(0.3, 1.4),
+//!		Vec2::new(0.45, 1.8),
+//! ])
+//!		.with_segments(48)
+//!		.with_caps(Caps::Start)
+//!		.build();
+//!
+//! // A pipe following a curve
+//! let curve = Path::catmull_rom(vec![Vec3::ZERO, Vec3::new(2.0, 1.0, 0.0), Vec3::new(4.0, 0.0, 2.0)])?;
+//! let pipe = Extrusion::along_path(circle_profile(0.1, 16), &curve, 16).build();
+//!
+//! // A rail with a T-shaped cross section along straight points
+//! let rail = Extrusion::new(
+//!		vec![
+//!			Vec2::new(-0.2, 0.0), Vec2::new(0.2, 0.0), Vec2::new(0.05, 0.05),
+//!			Vec2::new(0.05, 0.25), Vec2::new(-0.05, 0.25), Vec2::new(-0.05, 0.05),
+//!		],
+//!		vec![Vec3::ZERO, Vec3::new(0.0, 0.0, -10.0)],
+//! ).build();
+//!
+//! let mesh = Mesh::from_data(&gl, &vase, presets::phong(&gl, Vec3::new(0.8, 0.5, 0.3)));
 //! ```
 //!
 
+use std::f32::consts::TAU;
+
+use glam::{Quat, Vec2, Vec3};
+
+use crate::common::MeshData;
+use crate::core::Path;
+
+/// Colors cycled through by [`Primitive::with_face_colors`].
+const FACE_COLORS: [[f32; 3]; 6] = [
+	[0.9, 0.2, 0.2],
+	[0.2, 0.8, 0.3],
+	[0.2, 0.4, 0.9],
+	[0.9, 0.8, 0.2],
+	[0.8, 0.3, 0.8],
+	[0.2, 0.8, 0.8],
+];
+
 /// Built-in geometric primitive shapes.
 pub enum Primitive {
 	Quad,
@@ -174,4 +232,512 @@ impl Primitive {
 			}
 		}
 	}
-}
\ No newline at end of file
+
+	/// Returns mesh data with a distinct vertex color per face.
+	///
+	/// Faces are the quads of a cube or quad and the single triangle of a
+	/// triangle. Use with [`Mesh::from_data`](crate::common::Mesh::from_data)
+	/// to check winding, orientation and lighting at a glance.
+	///
+	/// ```
+	/// use oxgl::renderer_3d::Primitive;
+	///
+	/// let data = Primitive::Cube.with_face_colors();
+	/// assert_eq!(data.colors.len(), data.positions.len());
+	///
+	/// // The two triangles of a face share a color, neighbors don't
+	/// assert_eq!(&data.colors[0..3], &data.colors[15..18]);
+	/// assert_ne!(&data.colors[0..3], &data.colors[18..21]);
+	/// ```
+	pub fn with_face_colors(&self) -> MeshData {
+		let vertex_data = self.vertices_with_normals();
+		let face_vertices = match self {
+			Primitive::Triangle => 3,
+			Primitive::Quad | Primitive::Cube => 6,
+		};
+
+		let mut data = MeshData::default();
+
+		for (i, vertex) in vertex_data.data.chunks_exact(6).enumerate() {
+			data.positions.extend_from_slice(&vertex[0..3]);
+			data.normals.extend_from_slice(&vertex[3..6]);
+			data.colors.extend_from_slice(&FACE_COLORS[i / face_vertices % FACE_COLORS.len()]);
+		}
+
+		data
+	}
+}
+
+/// Which ends of a [`Lathe`] or [`Extrusion`] are closed off.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Caps {
+	None,
+	Start,
+	End,
+	#[default]
+	Both,
+}
+
+impl Caps {
+	/// Returns whether the start is capped.
+	pub fn start(self) -> bool {
+		matches!(self, Caps::Start | Caps::Both)
+	}
+
+	/// Returns whether the end is capped.
+	pub fn end(self) -> bool {
+		matches!(self, Caps::End | Caps::Both)
+	}
+}
+
+/// Builds surfaces of revolution by spinning a profile around an axis.
+///
+/// The profile is a list of `(radius, height)` points. The surface faces
+/// to the right of the direction the profile is drawn in, so a profile
+/// drawn from bottom to top faces away from the axis and one drawn from
+/// top to bottom faces into it.
+#[derive(Clone, Debug)]
+pub struct Lathe {
+	profile: Vec<Vec2>,
+	/// Steps around the axis for a full turn.
+	pub segments: u32,
+	/// Angle swept around the axis, in radians.
+	pub sweep: f32,
+	/// Angle the sweep starts at, in radians from +Z.
+	pub start_angle: f32,
+	/// Joins the last profile point back to the first, for rings.
+	pub closed_profile: bool,
+	/// Discs closing the first and last profile points off at the axis.
+	/// Ignored for closed profiles.
+	pub caps: Caps,
+	/// Axis to spin around, through the origin.
+	pub axis: Vec3,
+}
+
+impl Lathe {
+	pub fn new(profile: Vec<Vec2>) -> Self {
+		Self {
+			profile,
+			segments: 32,
+			sweep: TAU,
+			start_angle: 0.0,
+			closed_profile: false,
+			caps: Caps::Both,
+			axis: Vec3::Y,
+		}
+	}
+
+	pub fn with_segments(mut self, segments: u32) -> Self {
+		self.segments = segments;
+		self
+	}
+
+	/// Sweeps less than a full turn. The cut sides are left open.
+	pub fn with_sweep(mut self, sweep: f32) -> Self {
+		self.sweep = sweep;
+		self
+	}
+
+	pub fn with_start_angle(mut self, angle: f32) -> Self {
+		self.start_angle = angle;
+		self
+	}
+
+	pub fn with_closed_profile(mut self, closed: bool) -> Self {
+		self.closed_profile = closed;
+		self
+	}
+
+	pub fn with_caps(mut self, caps: Caps) -> Self {
+		self.caps = caps;
+		self
+	}
+
+	pub fn with_axis(mut self, axis: Vec3) -> Self {
+		self.axis = axis;
+		self
+	}
+
+	/// Generates the mesh.
+	///
+	/// ```
+	/// use glam::Vec2;
+	/// use oxgl::renderer_3d::{Caps, Lathe};
+	///
+	/// // An open tube: 16 quads around, one along
+	/// let tube = Lathe::new(vec![Vec2::new(1.0, 0.0), Vec2::new(1.0, 2.0)])
+	///		.with_segments(16)
+	///		.with_caps(Caps::None)
+	///		.build();
+	///
+	/// assert_eq!(tube.positions.len(), 16 * 6 * 3);
+	/// ```
+	pub fn build(&self) -> MeshData {
+		let mut data = MeshData::default();
+		let mut profile = self.profile.clone();
+
+		if profile.len() < 2 || self.segments == 0 {
+			return data;
+		}
+
+		if self.closed_profile && signed_area(&profile) < 0.0 {
+			profile.reverse();
+		}
+
+		let segments = self.segments as usize;
+		let full = self.sweep.abs() >= TAU - 1e-4;
+		let rotation = Quat::from_rotation_arc(Vec3::Y, self.axis.normalize_or(Vec3::Y));
+
+		let angles: Vec<(f32, f32)> = (0..=segments)
+			.map(|j| {
+				let step = if full { j % segments } else { j };
+				(self.start_angle + self.sweep * step as f32 / segments as f32).sin_cos()
+			})
+			.collect();
+
+		// Sweeping clockwise mirrors the winding
+		let order = if self.sweep > 0.0 { [0, 1, 2, 2, 1, 3] } else { [0, 2, 1, 1, 2, 3] };
+		let spin = |point: Vec2, (sin, cos): (f32, f32)| rotation * Vec3::new(point.x * sin, point.y, point.x * cos);
+
+		for (i, (start_normal, end_normal)) in profile_normals(&profile, self.closed_profile).into_iter().enumerate() {
+			let (a, b) = (profile[i], profile[(i + 1) % profile.len()]);
+
+			for j in 0..segments {
+				let (here, next) = (angles[j], angles[j + 1]);
+				let corners = [
+					(spin(a, here), spin(start_normal, here)),
+					(spin(a, next), spin(start_normal, next)),
+					(spin(b, here), spin(end_normal, here)),
+					(spin(b, next), spin(end_normal, next)),
+				];
+
+				for k in order {
+					push_vertex(&mut data, corners[k].0, corners[k].1.normalize_or_zero());
+				}
+			}
+		}
+
+		if self.closed_profile {
+			return data;
+		}
+
+		let last = profile.len() - 1;
+		let ends = [(self.caps.start(), profile[0], false), (self.caps.end(), profile[last], true)];
+
+		for (capped, point, end) in ends {
+			if !capped || point.x.abs() < 1e-6 {
+				continue;
+			}
+
+			// Caps face the same side as the surface, so the end cap of a
+			// profile drawn upward faces up and the start cap down
+			let up = end == (point.x > 0.0);
+
+			let center = rotation * Vec3::new(0.0, point.y, 0.0);
+			let normal = rotation * if up { Vec3::Y } else { Vec3::NEG_Y };
+
+			for j in 0..segments {
+				let (here, next) = (spin(point, angles[j]), spin(point, angles[j + 1]));
+				let (b, c) = if up == (self.sweep > 0.0) { (here, next) } else { (next, here) };
+
+				for position in [center, b, c] {
+					push_vertex(&mut data, position, normal);
+				}
+			}
+		}
+
+		data
+	}
+}
+
+/// Builds meshes by sweeping a profile along a path.
+///
+/// The profile's x axis points to the right and its y axis up, as seen
+/// looking along the path from its start. Closed profiles are wound
+/// counter-clockwise so their sides face out; open profiles face to the
+/// right of the direction they are drawn in.
+///
+/// Profiles are carried along the path without twisting, starting upright
+/// relative to +Y, so straight runs and gentle curves keep their shape.
+#[derive(Clone, Debug)]
+pub struct Extrusion {
+	profile: Vec<Vec2>,
+	path: Vec<Vec3>,
+	/// Joins the last path point back to the first, for loops.
+	pub closed_path: bool,
+	/// Joins the last profile point back to the first, giving a solid.
+	pub closed_profile: bool,
+	/// Faces closing off the path's ends. Ignored for open profiles and
+	/// closed paths.
+	pub caps: Caps,
+}
+
+impl Extrusion {
+	/// Sweeps `profile` through a list of points.
+	pub fn new(profile: Vec<Vec2>, path: Vec<Vec3>) -> Self {
+		Self {
+			profile,
+			path,
+			closed_path: false,
+			closed_profile: true,
+			caps: Caps::Both,
+		}
+	}
+
+	/// Sweeps `profile` along a curve, sampled `samples_per_segment` times
+	/// per segment. Closed curves give closed extrusions.
+	pub fn along_path(profile: Vec<Vec2>, path: &Path, samples_per_segment: usize) -> Self {
+		let mut points = path.polyline(samples_per_segment);
+
+		if path.is_closed() {
+			points.pop();
+		}
+
+		Self::new(profile, points).with_closed_path(path.is_closed())
+	}
+
+	pub fn with_closed_path(mut self, closed: bool) -> Self {
+		self.closed_path = closed;
+		self
+	}
+
+	pub fn with_closed_profile(mut self, closed: bool) -> Self {
+		self.closed_profile = closed;
+		self
+	}
+
+	pub fn with_caps(mut self, caps: Caps) -> Self {
+		self.caps = caps;
+		self
+	}
+
+	/// Generates the mesh.
+	///
+	/// ```
+	/// use glam::{Vec2, Vec3};
+	/// use oxgl::renderer_3d::{Caps, Extrusion};
+	///
+	/// let square = vec![Vec2::new(-1.0, -1.0), Vec2::new(1.0, -1.0), Vec2::new(1.0, 1.0), Vec2::new(-1.0, 1.0)];
+	/// let beam = Extrusion::new(square, vec![Vec3::ZERO, Vec3::new(0.0, 0.0, 4.0)]).build();
+	///
+	/// // Four sides and two caps of two triangles each
+	/// assert_eq!(beam.positions.len(), 6 * 2 * 3 * 3);
+	/// ```
+	pub fn build(&self) -> MeshData {
+		let mut data = MeshData::default();
+		let mut profile = self.profile.clone();
+		let path = &self.path;
+
+		if profile.len() < 2 || path.len() < 2 {
+			return data;
+		}
+
+		if self.closed_profile && signed_area(&profile) < 0.0 {
+			profile.reverse();
+		}
+
+		let frames = self.frames();
+		let place = |k: usize, point: Vec2| {
+			let (_, normal, side) = frames[k];
+			path[k] + side * point.x + normal * point.y
+		};
+		let turn = |k: usize, direction: Vec2| {
+			let (_, normal, side) = frames[k];
+			(side * direction.x + normal * direction.y).normalize_or_zero()
+		};
+
+		let runs = if self.closed_path { path.len() } else { path.len() - 1 };
+
+		for (i, (start_normal, end_normal)) in profile_normals(&profile, self.closed_profile).into_iter().enumerate() {
+			let (a, b) = (profile[i], profile[(i + 1) % profile.len()]);
+
+			for k in 0..runs {
+				let next = (k + 1) % path.len();
+				let corners = [
+					(place(k, a), turn(k, start_normal)),
+					(place(next, a), turn(next, start_normal)),
+					(place(k, b), turn(k, end_normal)),
+					(place(next, b), turn(next, end_normal)),
+				];
+
+				for c in [0, 1, 2, 2, 1, 3] {
+					push_vertex(&mut data, corners[c].0, corners[c].1);
+				}
+			}
+		}
+
+		if !self.closed_profile || self.closed_path {
+			return data;
+		}
+
+		let triangles = triangulate(&profile);
+		let last = path.len() - 1;
+
+		if self.caps.start() {
+			for [a, b, c] in &triangles {
+				for i in [a, b, c] {
+					push_vertex(&mut data, place(0, profile[*i]), -frames[0].0);
+				}
+			}
+		}
+
+		if self.caps.end() {
+			for [a, b, c] in &triangles {
+				for i in [a, c, b] {
+					push_vertex(&mut data, place(last, profile[*i]), frames[last].0);
+				}
+			}
+		}
+
+		data
+	}
+
+	/// Returns the tangent, normal and side direction at each path point.
+	fn frames(&self) -> Vec<(Vec3, Vec3, Vec3)> {
+		let path = &self.path;
+		let count = path.len();
+
+		let tangents: Vec<Vec3> = (0..count)
+			.map(|k| {
+				let (before, after) = if self.closed_path {
+					(path[(k + count - 1) % count], path[(k + 1) % count])
+				} else {
+					(path[k.saturating_sub(1)], path[(k + 1).min(count - 1)])
+				};
+
+				(after - before).normalize_or(Vec3::Z)
+			})
+			.collect();
+
+		let up = if tangents[0].dot(Vec3::Y).abs() > 0.99 { Vec3::NEG_Z } else { Vec3::Y };
+		let mut normals = vec![up.reject_from_normalized(tangents[0]).normalize()];
+
+		for k in 1..count {
+			let carried = Quat::from_rotation_arc(tangents[k - 1], tangents[k]) * normals[k - 1];
+			normals.push(carried.reject_from_normalized(tangents[k]).normalize_or(normals[k - 1]));
+		}
+
+		if self.closed_path {
+			// Spread the twist left over after a lap so the seam lines up
+			let carried = Quat::from_rotation_arc(tangents[count - 1], tangents[0]) * normals[count - 1];
+			let twist = tangents[0].dot(carried.cross(normals[0])).atan2(carried.dot(normals[0]));
+
+			for (k, normal) in normals.iter_mut().enumerate() {
+				*normal = Quat::from_axis_angle(tangents[k], twist * k as f32 / count as f32) * *normal;
+			}
+		}
+
+		tangents.into_iter()
+			.zip(normals)
+			.map(|(tangent, normal)| (tangent, normal, tangent.cross(normal)))
+			.collect()
+	}
+}
+
+/// Returns the points of a circle, counter-clockwise from +X, for use as
+/// a [`Lathe`] or [`Extrusion`] profile.
+pub fn circle_profile(radius: f32, segments: u32) -> Vec<Vec2> {
+	(0..segments.max(3))
+		.map(|i| Vec2::from_angle(TAU * i as f32 / segments.max(3) as f32) * radius)
+		.collect()
+}
+
+pub(crate) fn push_vertex(data: &mut MeshData, position: Vec3, normal: Vec3) {
+	data.positions.extend_from_slice(&position.to_array());
+	data.normals.extend_from_slice(&normal.to_array());
+}
+
+/// Returns the normals at the start and end of each profile edge, facing
+/// right of the edge and smoothed across gentle corners.
+fn profile_normals(profile: &[Vec2], closed: bool) -> Vec<(Vec2, Vec2)> {
+	let count = profile.len();
+	let edges = if closed { count } else { count - 1 };
+	let normal = |i: usize| {
+		let edge = profile[(i + 1) % count] - profile[i];
+		Vec2::new(edge.y, -edge.x).normalize_or_zero()
+	};
+
+	(0..edges)
+		.map(|i| {
+			let own = normal(i);
+			let before = if closed || i > 0 { corner_normal(normal((i + edges - 1) % edges), own, own) } else { own };
+			let after = if closed || i + 1 < edges { corner_normal(own, normal((i + 1) % edges), own) } else { own };
+
+			(before, after)
+		})
+		.collect()
+}
+
+/// Side normals are averaged across corners flatter than this, in
+/// radians, so curves look round and sharp corners stay crisp.
+pub(crate) const SMOOTH_ANGLE: f32 = 0.5;
+
+/// Returns the normal at a corner between two edges, averaged if the corner
+/// is smooth, or `own` otherwise.
+pub(crate) fn corner_normal(before: Vec2, after: Vec2, own: Vec2) -> Vec2 {
+	if before.dot(after) > SMOOTH_ANGLE.cos() {
+		(before + after).normalize_or(own)
+	} else {
+		own
+	}
+}
+
+/// Twice the signed area of a polygon, positive when counter-clockwise.
+pub(crate) fn signed_area(polygon: &[Vec2]) -> f32 {
+	(0..polygon.len())
+		.map(|i| polygon[i].perp_dot(polygon[(i + 1) % polygon.len()]))
+		.sum()
+}
+
+/// Triangulates a counter-clockwise simple polygon by ear clipping.
+pub(crate) fn triangulate(polygon: &[Vec2]) -> Vec<[usize; 3]> {
+	let mut remaining: Vec<usize> = (0..polygon.len()).collect();
+	let mut triangles = Vec::with_capacity(polygon.len().saturating_sub(2));
+
+	while remaining.len() > 3 {
+		let count = remaining.len();
+		let corner = |i: usize| {
+			let (a, b, c) = (remaining[(i + count - 1) % count], remaining[i], remaining[(i + 1) % count]);
+			(a, b, c, (polygon[b] - polygon[a]).perp_dot(polygon[c] - polygon[b]))
+		};
+
+		let ear = (0..count).find(|&i| {
+			let (a, b, c, turn) = corner(i);
+
+			turn > 0.0 && remaining.iter().all(|&p| {
+				let point = polygon[p];
+				point == polygon[a] || point == polygon[b] || point == polygon[c]
+					|| !in_triangle(point, polygon[a], polygon[b], polygon[c])
+			})
+		});
+
+		// Degenerate polygons may have no clean ear, so clip the flattest
+		// corner rather than stopping
+		let i = ear.unwrap_or_else(|| {
+			(0..count).min_by(|&x, &y| corner(x).3.abs().total_cmp(&corner(y).3.abs())).unwrap_or(0)
+		});
+
+		let (a, b, c, turn) = corner(i);
+
+		if turn > 0.0 {
+			triangles.push([a, b, c]);
+		}
+
+		remaining.remove(i);
+	}
+
+	if let [a, b, c] = remaining[..] && (polygon[b] - polygon[a]).perp_dot(polygon[c] - polygon[b]) > 0.0 {
+		triangles.push([a, b, c]);
+	}
+
+	triangles
+}
+
+/// Returns whether a point is inside or on the edge of a triangle.
+fn in_triangle(p: Vec2, a: Vec2, b: Vec2, c: Vec2) -> bool {
+	let d1 = (b - a).perp_dot(p - a);
+	let d2 = (c - b).perp_dot(p - b);
+	let d3 = (a - c).perp_dot(p - c);
+
+	d1 >= 0.0 && d2 >= 0.0 && d3 >= 0.0
+}
@@ -0,0 +1,223 @@
+//! Reflection Probes
+//!
+//! Provides [`ReflectionProbe`], a cube map of the scene captured at a
+//! point and reflected by [reflective](crate::common::Material::set_reflectivity)
+//! materials inside its influence volume. Reflections are box-projected
+//! onto the volume, so a probe filling a room lines its reflections up
+//! with the walls instead of treating them as infinitely far away.
+//!
+//! Probes are rendered at the start of [`Scene::render`](super::Scene::render)
+//! when due, once per cube face. Other probes are left out of the capture,
+//! so reflections don't show reflective surfaces' reflections.
+//!
+//! ## Examples
+//!
+//! ```ignore
+//! use oxgl::renderer_3d::{ProbeUpdate, ReflectionProbe};
+//! use oxgl::core::Aabb;
+//!
+//! let room = Aabb::new(Vec3::new(-5.0, 0.0, -5.0), Vec3::new(5.0, 3.0, 5.0));
+//! let probe = ReflectionProbe::new(&gl, Vec3::new(0.0, 1.5, 0.0), room, 256)?
+//!		.with_update(ProbeUpdate::Interval(0.5));
+//!
+//! scene.add_reflection_probe(probe);
+//! floor.mesh.material.set_reflectivity(0.3);
+//! ```
+//!
+
+use glam::Vec3;
+use web_sys::{WebGlFramebuffer, WebGlProgram, WebGl2RenderingContext as GL};
+
+use crate::{
+	common::{Camera, CubeMap, OxglError, cubemap::CUBE_FACES},
+	core::Aabb,
+};
+
+/// Texture unit the probe cube map is bound to, past the units used by
+/// material textures.
+pub(crate) const PROBE_UNIT: u32 = 15;
+
+/// When a [`ReflectionProbe`] renders the scene again.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ProbeUpdate {
+	/// Only after [`ReflectionProbe::request_update`], and when added.
+	#[default]
+	OnDemand,
+	/// Every given number of seconds.
+	Interval(f32),
+	/// Every frame.
+	EveryFrame,
+}
+
+/// A cube map of the scene around a point, reflected by materials inside
+/// its influence volume.
+pub struct ReflectionProbe {
+	/// Where the scene is captured from. Should lie inside `bounds`.
+	pub position: Vec3,
+	/// Objects whose center lies inside are reflected by this probe, and
+	/// reflections are projected onto its sides.
+	pub bounds: Aabb,
+	pub update: ProbeUpdate,
+	/// Clip distances of the capture.
+	pub near: f32,
+	pub far: f32,
+	pub enabled: bool,
+	cubemap: CubeMap,
+	framebuffer: WebGlFramebuffer,
+	last_update: Option<f32>,
+	requested: bool,
+}
+
+impl ReflectionProbe {
+	/// Creates a probe at `position` with `size` by `size` cube faces.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the cube map or framebuffer can't be created or
+	/// the framebuffer is incomplete.
+	pub fn new(gl: &GL, position: Vec3, bounds: Aabb, size: u32) -> Result<Self, OxglError> {
+		let size = size.max(1);
+		let cubemap = CubeMap::empty(gl, size).map_err(OxglError::ResourceCreation)?;
+
+		let framebuffer = gl.create_framebuffer()
+			.ok_or_else(|| OxglError::ResourceCreation("Failed to create probe framebuffer".to_string()))?;
+
+		let depth_renderbuffer = gl.create_renderbuffer()
+			.ok_or_else(|| OxglError::ResourceCreation("Failed to create probe depth renderbuffer".to_string()))?;
+		gl.bind_renderbuffer(GL::RENDERBUFFER, Some(&depth_renderbuffer));
+		gl.renderbuffer_storage(GL::RENDERBUFFER, GL::DEPTH_COMPONENT24, size as i32, size as i32);
+
+		gl.bind_framebuffer(GL::FRAMEBUFFER, Some(&framebuffer));
+		gl.framebuffer_texture_2d(
+			GL::FRAMEBUFFER, GL::COLOR_ATTACHMENT0, CUBE_FACES[0].0, Some(cubemap.texture()), 0,
+		);
+		gl.framebuffer_renderbuffer(
+			GL::FRAMEBUFFER, GL::DEPTH_ATTACHMENT, GL::RENDERBUFFER, Some(&depth_renderbuffer),
+		);
+
+		let status = gl.check_framebuffer_status(GL::FRAMEBUFFER);
+		gl.bind_framebuffer(GL::FRAMEBUFFER, None);
+
+		if status != GL::FRAMEBUFFER_COMPLETE {
+			return Err(OxglError::FramebufferIncomplete { framebuffer: "Reflection probe".to_string(), status });
+		}
+
+		Ok(Self {
+			position,
+			bounds,
+			update: ProbeUpdate::default(),
+			near: 0.1,
+			far: 100.0,
+			enabled: true,
+			cubemap,
+			framebuffer,
+			last_update: None,
+			requested: true,
+		})
+	}
+
+	pub fn with_update(mut self, update: ProbeUpdate) -> Self {
+		self.update = update;
+		self
+	}
+
+	pub fn with_range(mut self, near: f32, far: f32) -> Self {
+		self.near = near;
+		self.far = far;
+		self
+	}
+
+	/// Renders the probe again on the next frame, for
+	/// [`ProbeUpdate::OnDemand`] probes after the scene changed.
+	pub fn request_update(&mut self) {
+		self.requested = true;
+	}
+
+	/// Returns the captured scene, which materials can also sample directly.
+	pub fn cubemap(&self) -> &CubeMap {
+		&self.cubemap
+	}
+
+	/// Returns whether the probe should be rendered at `time`, in seconds.
+	pub(crate) fn is_due(&self, time: f32) -> bool {
+		if !self.enabled {
+			return false;
+		}
+
+		match (self.update, self.last_update) {
+			_ if self.requested => true,
+			(_, None) => true,
+			(ProbeUpdate::OnDemand, Some(_)) => false,
+			(ProbeUpdate::Interval(seconds), Some(last)) => time - last >= seconds || time < last,
+			(ProbeUpdate::EveryFrame, Some(_)) => true,
+		}
+	}
+
+	pub(crate) fn mark_updated(&mut self, time: f32) {
+		self.last_update = Some(time);
+		self.requested = false;
+	}
+
+	/// Returns the camera capturing cube face `face`, from `0` to `5`.
+	pub(crate) fn face_camera(&self, face: usize) -> Camera {
+		let (_, direction, up) = CUBE_FACES[face];
+		let mut camera = Camera::new(1.0);
+
+		camera.position = self.position;
+		camera.target = self.position + direction;
+		camera.up = up;
+		camera.fov_y = std::f32::consts::FRAC_PI_2;
+		camera.near = self.near;
+		camera.far = self.far;
+		camera
+	}
+
+	/// Binds the framebuffer drawing into cube face `face`, sets the
+	/// viewport to cover it and clears it.
+	pub(crate) fn bind_face(&self, gl: &GL, face: usize, clear_color: [f32; 4]) {
+		let [r, g, b, a] = clear_color;
+		let size = self.cubemap.size() as i32;
+
+		gl.bind_framebuffer(GL::FRAMEBUFFER, Some(&self.framebuffer));
+		gl.framebuffer_texture_2d(
+			GL::FRAMEBUFFER, GL::COLOR_ATTACHMENT0, CUBE_FACES[face].0, Some(self.cubemap.texture()), 0,
+		);
+		gl.viewport(0, 0, size, size);
+		gl.clear_color(r, g, b, a);
+		gl.clear(GL::COLOR_BUFFER_BIT | GL::DEPTH_BUFFER_BIT);
+	}
+
+	/// Returns the volume of the influence box, used to prefer the
+	/// smallest probe around an object.
+	pub(crate) fn volume(&self) -> f32 {
+		let size = self.bounds.size();
+		size.x * size.y * size.z
+	}
+}
+
+/// Sets the `probe*` uniforms of the `probes` shader chunk from the probe
+/// around an object, binding its cube map, or turns reflections off.
+///
+/// The `probeMap` sampler itself is pointed at [`PROBE_UNIT`] by
+/// [`Material::apply`](crate::common::Material::apply).
+pub(crate) fn apply_probe(gl: &GL, program: &WebGlProgram, probe: Option<&ReflectionProbe>) {
+	if let Some(loc) = gl.get_uniform_location(program, "probeEnabled") {
+		gl.uniform1i(Some(&loc), probe.is_some() as i32);
+	}
+
+	let Some(probe) = probe else {
+		return;
+	};
+
+	probe.cubemap.bind(gl, PROBE_UNIT);
+
+	if let Some(loc) = gl.get_uniform_location(program, "probePosition") {
+		gl.uniform3fv_with_f32_array(Some(&loc), &probe.position.to_array());
+	}
+	if let Some(loc) = gl.get_uniform_location(program, "probeBoxMin") {
+		gl.uniform3fv_with_f32_array(Some(&loc), &probe.bounds.min.to_array());
+	}
+	if let Some(loc) = gl.get_uniform_location(program, "probeBoxMax") {
+		gl.uniform3fv_with_f32_array(Some(&loc), &probe.bounds.max.to_array());
+	}
+}
@@ -0,0 +1,309 @@
+//! Scattering
+//!
+//! Provides [`Scatter`], which spreads instances of a mesh over the surface
+//! of another, for grass, rocks and trees on terrain. Instances are placed
+//! by density per square unit of surface, turned and scaled at random, and
+//! can be kept off steep slopes or outside a band of heights. The same seed
+//! always gives the same placement.
+//!
+//! The result is a single instanced [`Mesh`] drawing every instance in one
+//! call. Its material reads the instance transforms through the
+//! `instancing` shader chunk.
+//!
+//! ## Examples
+//!
+//! ```ignore
+//! use oxgl::renderer_3d::Scatter;
+//! use oxgl::common::{Material, MeshData};
+//! use oxgl::core::Transform3D;
+//! use glam::Mat4;
+//!
+//! let vert = r#"
+//!		#include <logdepth>
+//!		#include <instancing>
+//!
+//!		attribute vec3 position;
+//!		attribute vec3 normal;
+//!
+//!		uniform mat4 model;
+//!		uniform mat4 view;
+//!		uniform mat4 projection;
+//!
+//!		varying vec3 vNormal;
+//!
+//!		void main() {
+//!			mat4 world = model * instanceMatrix();
+//!			vNormal = mat3(world) * normal;
+//!			gl_Position = logDepth(projection * view * world * vec4(position, 1.0));
+//!		}
+//! "#;
+//! let material = Material::from_source(&gl, vert, GRASS_FRAG)?;
+//!
+//! // Two tufts per square unit on gentle slopes above the waterline
+//! let grass = Scatter::new(2.0)
+//!		.with_seed(7)
+//!		.with_scale_range(0.6, 1.2)
+//!		.with_tilt_jitter(0.2)
+//!		.with_max_slope(30f32.to_radians())
+//!		.with_height_range(0.5, 20.0);
+//!
+//! let tuft = MeshData::from_obj(include_str!("assets/tuft.obj"))?.remove(0);
+//!
+//! for chunk in terrain.chunk_data() {
+//!		let mesh = grass.build(&gl, &tuft, &chunk, Mat4::IDENTITY, material.clone())?;
+//!		scene.add(mesh, Transform3D::new());
+//! }
+//! ```
+//!
+
+use std::f32::consts::TAU;
+
+use glam::{Mat4, Quat, Vec3};
+use web_sys::WebGl2RenderingContext as GL;
+
+use crate::{
+	common::{Material, Mesh, MeshBuilder, MeshData, mesh::INSTANCE_MATRIX_ATTRIBUTES},
+	core::{Aabb, Transform3D, Transformable},
+};
+
+/// Floats per instance: a column-major model matrix.
+const INSTANCE_STRIDE: usize = 16;
+
+/// Settings for spreading instances over a surface.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Scatter {
+	/// Average instances per square world unit of surface.
+	pub density: f32,
+	pub seed: u32,
+	/// Largest random turn around the instance's up axis, in radians.
+	pub rotation_jitter: f32,
+	/// Largest random lean away from the instance's up axis, in radians.
+	pub tilt_jitter: f32,
+	pub min_scale: f32,
+	pub max_scale: f32,
+	/// Stands instances along the surface normal instead of world up.
+	pub align_to_normal: bool,
+	/// Steepest surface instances are placed on, in radians from
+	/// horizontal.
+	pub max_slope: Option<f32>,
+	/// World heights instances are placed between.
+	pub height_range: Option<(f32, f32)>,
+}
+
+impl Scatter {
+	/// Creates a scatter with any turn around world up and no other jitter
+	/// or constraints.
+	pub fn new(density: f32) -> Self {
+		Self {
+			density,
+			seed: 0,
+			rotation_jitter: TAU,
+			tilt_jitter: 0.0,
+			min_scale: 1.0,
+			max_scale: 1.0,
+			align_to_normal: false,
+			max_slope: None,
+			height_range: None,
+		}
+	}
+
+	pub fn with_seed(mut self, seed: u32) -> Self {
+		self.seed = seed;
+		self
+	}
+
+	pub fn with_rotation_jitter(mut self, radians: f32) -> Self {
+		self.rotation_jitter = radians;
+		self
+	}
+
+	pub fn with_tilt_jitter(mut self, radians: f32) -> Self {
+		self.tilt_jitter = radians;
+		self
+	}
+
+	/// Scales instances uniformly by a random factor between `min` and `max`.
+	pub fn with_scale_range(mut self, min: f32, max: f32) -> Self {
+		self.min_scale = min;
+		self.max_scale = max;
+		self
+	}
+
+	pub fn with_align_to_normal(mut self, align: bool) -> Self {
+		self.align_to_normal = align;
+		self
+	}
+
+	pub fn with_max_slope(mut self, radians: f32) -> Self {
+		self.max_slope = Some(radians);
+		self
+	}
+
+	pub fn with_height_range(mut self, min: f32, max: f32) -> Self {
+		self.height_range = Some((min, max));
+		self
+	}
+
+	/// Returns world transforms for instances on a surface placed by
+	/// `matrix`.
+	///
+	/// Every triangle gets instances in proportion to its area. Smooth
+	/// normals are used for slopes and alignment when the surface has them,
+	/// face normals otherwise.
+	///
+	/// ```
+	/// use oxgl::common::MeshData;
+	/// use oxgl::renderer_3d::Scatter;
+	/// use glam::{Mat4, Vec3};
+	///
+	/// // A flat 10x10 floor and a wall of the same size
+	/// let floor = [[-5.0, 0.0, -5.0], [-5.0, 0.0, 5.0], [5.0, 0.0, 5.0], [-5.0, 0.0, -5.0], [5.0, 0.0, 5.0], [5.0, 0.0, -5.0]];
+	/// let wall = [[-5.0, 0.0, -5.0], [5.0, 0.0, -5.0], [5.0, 10.0, -5.0], [-5.0, 0.0, -5.0], [5.0, 10.0, -5.0], [-5.0, 10.0, -5.0]];
+	/// let surface = MeshData {
+	///		positions: floor.iter().chain(&wall).flatten().copied().collect(),
+	///		..Default::default()
+	/// };
+	///
+	/// let scatter = Scatter::new(1.0).with_seed(3).with_max_slope(0.5);
+	/// let placed = scatter.place(&surface, Mat4::IDENTITY);
+	///
+	/// assert!((80..120).contains(&placed.len()));
+	/// assert!(placed.iter().all(|t| t.position.y == 0.0));
+	///
+	/// // The same seed places the same instances
+	/// assert_eq!(placed[0].position, scatter.place(&surface, Mat4::IDENTITY)[0].position);
+	/// ```
+	pub fn place(&self, surface: &MeshData, matrix: Mat4) -> Vec<Transform3D> {
+		let mut random = Random::new(self.seed);
+		let normal_matrix = matrix.inverse().transpose();
+		let smooth = surface.normals.len() == surface.positions.len();
+
+		let vertex = |data: &[f32], i: usize| Vec3::from_slice(&data[i * 3..i * 3 + 3]);
+		let mut placed = Vec::new();
+
+		for triangle in 0..surface.positions.len() / 9 {
+			let [a, b, c] = [0, 1, 2].map(|k| matrix.transform_point3(vertex(&surface.positions, triangle * 3 + k)));
+			let face = (b - a).cross(c - a);
+
+			let expected = face.length() * 0.5 * self.density.max(0.0);
+			let count = expected as usize + (random.next() < expected.fract()) as usize;
+
+			for _ in 0..count {
+				// Uniform over the triangle
+				let (r1, r2) = (random.next().sqrt(), random.next());
+				let weights = [1.0 - r1, r1 * (1.0 - r2), r1 * r2];
+				let position = a * weights[0] + b * weights[1] + c * weights[2];
+
+				let normal = if smooth {
+					let interpolated = (0..3).fold(Vec3::ZERO, |sum, k| sum + vertex(&surface.normals, triangle * 3 + k) * weights[k]);
+					normal_matrix.transform_vector3(interpolated)
+				} else {
+					face
+				}
+				.normalize_or(Vec3::Y);
+
+				// Drawn before rejecting, so constraints don't shift the others
+				let [yaw, tilt, lean, scale] = [random.next(), random.next(), random.next(), random.next()];
+
+				if !self.accepts(position, normal) {
+					continue;
+				}
+
+				let up = if self.align_to_normal { normal } else { Vec3::Y };
+				let lean_axis = Quat::from_rotation_y(lean * TAU) * Vec3::X;
+				let rotation = Quat::from_rotation_arc(Vec3::Y, up)
+					* Quat::from_axis_angle(lean_axis, tilt * self.tilt_jitter)
+					* Quat::from_rotation_y(yaw * self.rotation_jitter);
+				let scale = self.min_scale + (self.max_scale - self.min_scale) * scale;
+
+				placed.push(
+					Transform3D::new()
+						.with_position(position)
+						.with_rotation(rotation)
+						.with_scale(Vec3::splat(scale))
+				);
+			}
+		}
+
+		placed
+	}
+
+	/// Returns the model matrices of instances, 16 floats each, for the
+	/// `instanceMatrix0` to `instanceMatrix3` attributes.
+	pub fn instance_data(transforms: &[Transform3D]) -> Vec<f32> {
+		transforms.iter().flat_map(|transform| transform.to_matrix().to_cols_array()).collect()
+	}
+
+	/// Places instances on a surface and builds an instanced mesh drawing
+	/// `instance` at each of them.
+	///
+	/// Instances are in world space, so add the mesh to the scene without a
+	/// transform. Its bounds cover every instance. Texture coordinates of
+	/// `instance` are passed as the `uv` attribute.
+	///
+	/// # Errors
+	///
+	/// Returns an error if a buffer can't be created.
+	pub fn build(&self, gl: &GL, instance: &MeshData, surface: &MeshData, matrix: Mat4, material: Material) -> Result<Mesh, String> {
+		let transforms = self.place(surface, matrix);
+		let local = Aabb::from_vertices(&instance.positions, 3);
+		let bounds = transforms
+			.iter()
+			.map(|transform| local.transformed(&transform.to_matrix()))
+			.fold(Aabb::EMPTY, Aabb::union);
+
+		let mut builder = MeshBuilder::new(&instance.interleaved_vertices(), instance.vertex_stride())
+			.attribute("position", 3, 0)
+			.attribute("normal", 3, 3);
+
+		if !instance.colors.is_empty() {
+			builder = builder.attribute("vertexColor", 3, 6);
+		}
+
+		if !instance.uvs.is_empty() {
+			builder = builder.buffer(&instance.uvs, 2).attribute("uv", 2, 0);
+		}
+
+		builder = builder.buffer(&Self::instance_data(&transforms), INSTANCE_STRIDE);
+
+		for (column, name) in INSTANCE_MATRIX_ATTRIBUTES.iter().enumerate() {
+			builder = builder.attribute_with_divisor(name, 4, column as i32 * 4, 1);
+		}
+
+		if let Some(name) = &instance.name {
+			builder = builder.with_name(name);
+		}
+
+		builder
+			.instances(transforms.len() as i32)
+			.with_bounds(bounds)
+			.build(gl, material)
+	}
+
+	/// Returns whether a surface point passes the slope and height limits.
+	fn accepts(&self, position: Vec3, normal: Vec3) -> bool {
+		let steep = self.max_slope.is_some_and(|max| normal.y.clamp(-1.0, 1.0).acos() > max);
+		let outside = self.height_range.is_some_and(|(min, max)| position.y < min || position.y > max);
+
+		!steep && !outside
+	}
+}
+
+/// A splitmix32 stream of floats in `[0, 1)`.
+struct Random(u32);
+
+impl Random {
+	fn new(seed: u32) -> Self {
+		Self(seed)
+	}
+
+	fn next(&mut self) -> f32 {
+		self.0 = self.0.wrapping_add(0x9E37_79B9);
+		let mut z = self.0;
+		z = (z ^ (z >> 16)).wrapping_mul(0x85EB_CA6B);
+		z = (z ^ (z >> 13)).wrapping_mul(0xC2B2_AE35);
+		z ^= z >> 16;
+
+		(z >> 8) as f32 / (1 << 24) as f32
+	}
+}
@@ -28,30 +28,91 @@
 //! ```
 //!
 
-use glam::{Vec3, Mat4};
+use std::{cell::RefCell, collections::HashSet, rc::Rc};
+use glam::{Vec2, Vec3, Vec4, Mat4};
 use slotmap::SlotMap;
-use web_sys::WebGl2RenderingContext as GL;
-use super::{Light, LightType, GizmoRenderer, ShadowMap};
+use web_sys::{WebGlFramebuffer, WebGlProgram, WebGl2RenderingContext as GL, wasm_bindgen::JsCast};
+use super::{Background, Fog, RenderStats, Light, LightType, Lod, LodMetric, Polyline, PolylineRenderer, Sprite, SpriteHit, SpriteRenderer, sprite::DepthReadback, TrailRenderer, trail::TrailPass, TextMesh, TextRenderer, Viewport, GizmoRenderer, GizmoStyle, Panorama, CollisionMesh, RayHit, collision::sphere_cast_aabb, ShadowMap, ContactShadowMap, Bindings, OutlineId, OutlineKind, OutlineNode, SceneOutline, SceneEvent, inspector::{self, LightSnapshot, ObjectSnapshot, PropertyValue}, RenderPath, ShadowSettings, shadowmap::SHADOW_MAP_SIZE, deferred::{DeferredRenderer, LightPass}, lightmap::{self, LightmapBake}, ReflectionProbe, reflection_probe::apply_probe, Decal, decal::DecalRenderer, SceneTexture, OcclusionCuller, Prefab, PrefabInstance, prefab, BodyKind, PhysicsBody, PhysicsSync, PhysicsWorld};
 use crate::{
-	common::{Mesh, Camera, Material, PostProcessStack}, 
-	core::{ObjectId, LightId, Transform3D, Transformable},
+	common::{Mesh, Camera, Material, OxglError, PostProcessStack, RenderState, RenderTarget, Texture, Uniform, apply_clip_planes, render_state::{SavedState, current_depth_func, current_viewport}}, 
+	core::{Aabb, ColorSpace, Frustum, ObjectId, LightId, PolylineId, SceneListenerId, SpriteId, ReflectionProbeId, DecalId, SceneTextureId, TextId, CameraId, IkSolver, Ray, Transform3D, Transformable, audio::AudioPlayer},
 	Renderer
 };
 
+type EventListener = Box<dyn FnMut(&SceneEvent)>;
+type OutlineListener = Box<dyn FnMut(&SceneOutline)>;
+
+/// Texture unit of the camera depth read by
+/// [`scene_depth`](Material::scene_depth) materials, below the reflection
+/// probe's.
+pub(crate) const SCENE_DEPTH_UNIT: u32 = 14;
+
+/// An in-progress blend from the view at the time of the switch to the
+/// active camera.
+#[derive(Clone, Debug)]
+struct CameraTransition {
+	from: Camera,
+	duration: f32,
+	elapsed: f32,
+}
+
 /// A renderable object in the scene.
 ///
 /// Combines a mesh with a transform to define both the geometry
-/// and its position/orientation/scale in world space.
+/// and its position/orientation/scale. The transform is relative to the
+/// object's `parent`, or to world space if it has none.
+#[derive(Clone)]
 pub struct SceneObject {
 	pub mesh: Mesh,
 	pub transform: Transform3D,
+	pub name: Option<String>,
+	/// Free-form tags for [`Scene::iter_tagged`].
+	pub tags: HashSet<String>,
+	pub parent: Option<ObjectId>,
+	/// Simplified mesh used for picking instead of the render mesh.
+	pub collider: Option<CollisionMesh>,
+	/// Lower-detail meshes swapped in with distance, see [`Lod`].
+	pub lod: Option<Lod>,
+	/// Collider simulated by the scene's [physics world](Scene::set_physics).
+	pub body: Option<PhysicsBody>,
+	/// Ribbon through the object's recent positions, see [`Scene::set_trail`].
+	pub trail: Option<TrailRenderer>,
+	/// Mask drawn instead of the object, bending the image behind it, see
+	/// [`Scene::set_distortion`].
+	pub distortion: Option<Material>,
+	/// Hidden objects and their children aren't drawn, cast no shadows,
+	/// can't be picked and keep their id. See [`Scene::set_visible`].
+	pub visible: bool,
+	/// Skips drawing the object while other geometry hides its bounds, see
+	/// [`Scene::set_occlusion_culling`].
+	pub occlusion_culling: bool,
+	/// Whether the object is drawn into shadow maps, see
+	/// [`Scene::set_cast_shadows`].
+	pub cast_shadows: bool,
+	/// Whether shadows are sampled when shading the object, see
+	/// [`Scene::set_receive_shadows`].
+	pub receive_shadows: bool,
+	/// Draws the object's vertex normals and tangents in
+	/// [`Scene::render_debug`], see [`Scene::set_show_normals`].
+	pub show_normals: bool,
+}
+
+impl SceneObject {
+	/// Returns the mesh drawn this frame, taking [`lod`](Self::lod) into
+	/// account.
+	pub fn visible_mesh(&self) -> &Mesh {
+		match &self.lod {
+			Some(lod) => lod.mesh(&self.mesh),
+			None => &self.mesh,
+		}
+	}
 }
 
 /// Container for 3D objects, lights, and rendering state.
 ///
 /// The scene manages:
 /// - Object and light storage with stable IDs
-/// - Shadow map generation
+/// - Shadow map and contact shadow generation
 /// - Post-processing pipeline
 /// - Camera configuration
 ///
@@ -60,14 +121,92 @@ pub struct SceneObject {
 /// Objects and lights are stored in slot maps with stable IDs that remain
 /// valid even after other items are removed.
 ///
+/// ## Hierarchy
+///
+/// Objects can be parented with [`Scene::set_parent`]. Structural changes made
+/// through `Scene` methods are tracked and reported to outline listeners, see
+/// [`Scene::on_outline_change`].
+///
 pub struct Scene {
+	/// The view rendered from. Follows the [active camera](Scene::set_active_camera)
+	/// while one is set.
 	pub camera: Camera,
+	/// Cameras to switch between, see [`Scene::set_active_camera`].
+	pub cameras: SlotMap<CameraId, Camera>,
+	active_camera: Option<CameraId>,
+	camera_transition: Option<CameraTransition>,
 	pub objects: SlotMap<ObjectId, SceneObject>,
 	pub lights: SlotMap<LightId, Light>,
 	pub shadow_map: Option<ShadowMap>,
 	shadow_material: Option<Material>,
 	pub shadows_enabled: bool,
+	/// Area covered by the shadow map, see [`Scene::set_shadow_bounds`].
+	shadow_bounds: Option<Aabb>,
+	/// Filtering of the shadow map, such as soft [PCSS](super::ShadowFilter::Pcss) shadows.
+	pub shadow_settings: ShadowSettings,
+	/// Camera depth used by lights with [`contact_shadows`](Light::contact_shadows).
+	pub contact_shadow_map: Option<ContactShadowMap>,
+	pub contact_shadows_enabled: bool,
 	pub post_process: Option<PostProcessStack>,
+	/// Values written to uniforms and lights at the start of every frame.
+	pub bindings: Bindings,
+	/// Wide lines drawn after the objects, see [`Polyline`].
+	pub polylines: SlotMap<PolylineId, Polyline>,
+	polyline_renderer: Option<PolylineRenderer>,
+	trail_pass: Option<TrailPass>,
+	/// Camera-facing quads drawn after the objects, see [`Sprite`].
+	pub sprites: SlotMap<SpriteId, Sprite>,
+	sprite_renderer: Option<SpriteRenderer>,
+	/// SDF text drawn after the sprites, see [`TextMesh`].
+	pub texts: SlotMap<TextId, TextMesh>,
+	text_renderer: Option<TextRenderer>,
+	/// Textures projected onto the objects, see [`Decal`].
+	pub decals: SlotMap<DecalId, Decal>,
+	decal_renderer: Option<DecalRenderer>,
+	/// Textures showing the scene from other cameras, see [`SceneTexture`].
+	pub scene_textures: SlotMap<SceneTextureId, SceneTexture>,
+	/// Cube maps reflected by reflective materials, see [`ReflectionProbe`].
+	pub reflection_probes: SlotMap<ReflectionProbeId, ReflectionProbe>,
+	/// 360° background that also drives the camera, see [`Panorama`].
+	pub panorama: Option<Panorama>,
+	/// Skips objects whose bounds are outside the camera's view.
+	///
+	/// Bounds come from the mesh's vertices, grown by its material's
+	/// [`cull_padding`](Material::cull_padding) for shaders that displace
+	/// them.
+	pub frustum_culling: bool,
+	/// Overrides the [renderer's render state](Renderer::set_render_state)
+	/// for this scene.
+	pub render_state: Option<RenderState>,
+	/// What is drawn behind the objects, see [`Background`].
+	pub background: Background,
+	/// Distance fog applied by the built-in lit shaders, see [`set_fog`](Self::set_fog).
+	pub fog: Option<Fog>,
+	pub fog_color: Vec3,
+	clip_planes: Vec<Vec4>,
+	/// Camera depth for soft sprites and materials reading the scene depth,
+	/// when there are no decals to share theirs.
+	depth_map: Option<ContactShadowMap>,
+	/// Copy of the camera depth that colliding sprites bounce off.
+	sprite_depth: Option<DepthReadback>,
+	time: f32,
+	delta_time: f32,
+	/// Output color space, taken from the [`Renderer`] each frame.
+	pub(crate) color_space: ColorSpace,
+	/// Canvas size in pixels at the last render, for [`project`](Self::project).
+	screen_size: Vec2,
+	stats: RenderStats,
+	frame_stats: RenderStats,
+	outline_revision: u64,
+	outline_dirty: bool,
+	outline_listeners: Vec<OutlineListener>,
+	event_listeners: SlotMap<SceneListenerId, EventListener>,
+	pending_events: Vec<SceneEvent>,
+	hovered: Option<ObjectId>,
+	physics: Option<PhysicsSync>,
+	render_path: RenderPath,
+	deferred: Option<DeferredRenderer>,
+	occlusion: Option<OcclusionCuller>,
 }
 
 /// Configuration for debug visualization.
@@ -84,8 +223,27 @@ pub struct DebugSettings {
 	pub show_axes: bool,
 	pub show_light_gizmos: bool,
 	pub show_object_bounds: bool,
+	/// Draws the vertex normals, and tangents of meshes that have them, of
+	/// every object. Single objects can be shown with
+	/// [`Scene::set_show_normals`].
+	pub show_normals: bool,
+	/// Length of the normal and tangent lines in world units.
+	pub normal_length: f32,
+	/// Labels named objects, and lights with their name, type and intensity.
+	pub show_labels: bool,
+	/// Shows a [`StatsOverlay`](super::StatsOverlay) with FPS and
+	/// [`RenderStats`]. Only used by [`App`](crate::App).
+	pub show_stats: bool,
 	pub grid_size: f32,
 	pub grid_divisions: u32,
+	/// Draws the grid with a shader on an endless plane instead of as
+	/// `grid_size` wide line geometry. Cells are `grid_size / grid_divisions`
+	/// across with major lines every `grid_size`, and fade out towards
+	/// `grid_fade_distance`.
+	pub infinite_grid: bool,
+	pub grid_fade_distance: f32,
+	/// Depth behavior and opacity of the debug gizmos.
+	pub gizmo_style: GizmoStyle,
 }
 
 impl Default for DebugSettings {
@@ -95,8 +253,15 @@ impl Default for DebugSettings {
 			show_axes: false,
 			show_light_gizmos: false,
 			show_object_bounds: false,
+			show_normals: false,
+			normal_length: 0.1,
+			show_labels: false,
+			show_stats: false,
 			grid_size: 10.0,
 			grid_divisions: 10,
+			infinite_grid: false,
+			grid_fade_distance: 100.0,
+			gizmo_style: GizmoStyle::default(),
 		}
 	}
 }
@@ -117,277 +282,2991 @@ impl Scene {
 	pub fn new(camera: Camera) -> Self {
 		Self { 
 			camera, 
+			cameras: SlotMap::with_key(),
+			active_camera: None,
+			camera_transition: None,
 			objects: SlotMap::with_key(),
 			lights: SlotMap::with_key(),
 			shadow_map: None,
 			shadow_material: None,
 			shadows_enabled: false,
+			shadow_bounds: None,
+			shadow_settings: ShadowSettings::default(),
+			contact_shadow_map: None,
+			contact_shadows_enabled: false,
 			post_process: None,
+			bindings: Bindings::new(),
+			polylines: SlotMap::with_key(),
+			polyline_renderer: None,
+			trail_pass: None,
+			sprites: SlotMap::with_key(),
+			sprite_renderer: None,
+			texts: SlotMap::with_key(),
+			text_renderer: None,
+			decals: SlotMap::with_key(),
+			decal_renderer: None,
+			scene_textures: SlotMap::with_key(),
+			reflection_probes: SlotMap::with_key(),
+			panorama: None,
+			frustum_culling: true,
+			render_state: None,
+			background: Background::default(),
+			fog: None,
+			fog_color: Vec3::splat(0.1),
+			clip_planes: Vec::new(),
+			depth_map: None,
+			sprite_depth: None,
+			stats: RenderStats::default(),
+			time: 0.0,
+			delta_time: 0.0,
+			color_space: ColorSpace::default(),
+			screen_size: Vec2::ZERO,
+			frame_stats: RenderStats::default(),
+			outline_revision: 0,
+			outline_dirty: false,
+			outline_listeners: Vec::new(),
+			event_listeners: SlotMap::with_key(),
+			pending_events: Vec::new(),
+			hovered: None,
+			physics: None,
+			render_path: RenderPath::Forward,
+			deferred: None,
+			occlusion: None,
 		}
 	}
 
+	/// Adds a mesh to the scene.
+	///
+	/// The object is named after the mesh, if it has a name.
 	pub fn add(&mut self, mesh: Mesh, transform: Transform3D) -> ObjectId {
-		self.objects.insert(SceneObject { mesh, transform })
+		let name = mesh.name.clone();
+
+		self.insert_object(SceneObject {
+			mesh, transform, name, tags: HashSet::new(), parent: None, collider: None, lod: None, body: None, trail: None, distortion: None, visible: true, occlusion_culling: false,
+			cast_shadows: true, receive_shadows: true, show_normals: false,
+		})
+	}
+
+	fn insert_object(&mut self, object: SceneObject) -> ObjectId {
+		self.mark_outline_dirty();
+
+		let id = self.objects.insert(object);
+		self.emit(SceneEvent::ObjectAdded(id));
+		id
 	}
 
 	pub fn add_light(&mut self, light: Light) -> LightId {
-		self.lights.insert(light)
+		self.mark_outline_dirty();
+
+		let id = self.lights.insert(light);
+		self.emit(SceneEvent::LightAdded(id));
+		id
 	}
 
+	/// Removes an object from the scene.
+	///
+	/// Children of the removed object are re-parented to its parent.
 	pub fn remove(&mut self, id: ObjectId) -> Option<SceneObject> {
-		self.objects.remove(id)
+		let removed = self.objects.remove(id)?;
+
+		for obj in self.objects.values_mut() {
+			if obj.parent == Some(id) {
+				obj.parent = removed.parent;
+			}
+		}
+
+		if self.hovered == Some(id) {
+			self.hovered = None;
+		}
+
+		self.mark_outline_dirty();
+		self.emit(SceneEvent::ObjectRemoved(id));
+		Some(removed)
 	}
 
 	pub fn remove_light(&mut self, id: LightId) -> Option<Light> {
-		self.lights.remove(id)
+		let removed = self.lights.remove(id)?;
+
+		self.mark_outline_dirty();
+		self.emit(SceneEvent::LightRemoved(id));
+		Some(removed)
 	}
 
-	pub fn get_mut(&mut self, id: ObjectId) -> Option<&mut SceneObject> {
-		self.objects.get_mut(id)
+	/// Creates a copy of a prefab's objects and lights, placed by
+	/// `transform`.
+	///
+	/// Root objects are moved into the spawn transform's space, and children
+	/// keep their transforms relative to their parents.
+	pub fn spawn(&mut self, prefab: &Prefab, transform: Transform3D) -> PrefabInstance {
+		let placement = transform.to_matrix();
+		let mut instance = PrefabInstance::default();
+
+		for node in &prefab.nodes {
+			let mut object = node.object.clone();
+
+			match node.parent {
+				Some(parent) => object.parent = Some(instance.objects[parent]),
+				None => object.transform = prefab::compose(placement, &object.transform),
+			}
+
+			instance.objects.push(self.insert_object(object));
+		}
+
+		for light in &prefab.lights {
+			let mut light = light.clone();
+
+			light.position = placement.transform_point3(light.position);
+
+			if !matches!(light.light_type, LightType::Point { .. }) {
+				light.direction = placement.transform_vector3(light.direction).normalize_or_zero();
+			}
+
+			instance.lights.push(self.add_light(light));
+		}
+
+		instance
 	}
 
-	pub fn get_light_mut(&mut self, id: LightId) -> Option<&mut Light> {
-		self.lights.get_mut(id)
+	pub fn add_polyline(&mut self, polyline: Polyline) -> PolylineId {
+		self.polylines.insert(polyline)
 	}
 
-	/// Enables shadow mapping for the scene.
-	///
-	/// Creates the shadow map framebuffer and compiles the shadow depth shader.
-	/// Shadows will be cast from the first light with `cast_shadows` enabled.
-	///
-	/// # Errors
+	pub fn remove_polyline(&mut self, id: PolylineId) -> Option<Polyline> {
+		self.polylines.remove(id)
+	}
+
+	pub fn add_sprite(&mut self, sprite: Sprite) -> SpriteId {
+		self.sprites.insert(sprite)
+	}
+
+	pub fn remove_sprite(&mut self, id: SpriteId) -> Option<Sprite> {
+		self.sprites.remove(id)
+	}
+
+	pub fn add_text(&mut self, text: TextMesh) -> TextId {
+		self.texts.insert(text)
+	}
+
+	pub fn remove_text(&mut self, id: TextId) -> Option<TextMesh> {
+		self.texts.remove(id)
+	}
+
+	/// Registers a camera to switch to with
+	/// [`set_active_camera`](Self::set_active_camera).
+	pub fn add_camera(&mut self, camera: Camera) -> CameraId {
+		self.cameras.insert(camera)
+	}
+
+	/// Removes a registered camera. If it was active, the view stays where
+	/// it was.
+	pub fn remove_camera(&mut self, id: CameraId) -> Option<Camera> {
+		if self.active_camera == Some(id) {
+			self.release_camera();
+		}
+
+		self.cameras.remove(id)
+	}
+
+	/// Renders from a registered camera, cutting to it immediately.
 	///
-	/// Returns an error if:
-	/// - Shadow map framebuffer creation fails
-	/// - Shadow shader compilation fails
+	/// Every frame, [`camera`](Self::camera) is replaced by a copy of the
+	/// active camera that keeps its own aspect ratio, so move the
+	/// registered camera in [`cameras`](Self::cameras) rather than `camera`
+	/// while it is active. Returns `false` if the id is unknown.
 	///
 	/// # Examples
 	///
-	/// ```
-	/// scene.enable_shadows(&gl)?;
+	/// ```ignore
+	/// let overview = scene.add_camera(Camera::new(aspect).with_position(Vec3::new(0.0, 20.0, 20.0)));
+	/// let closeup = scene.add_camera(Camera::new(aspect).with_position(Vec3::new(2.0, 1.0, 2.0)));
 	///
-	/// // Make a light cast shadows
-	/// let mut light = Light::directional(Vec3::new(-1.0, -1.0, -1.0), Vec3::ONE, 1.0);
-	/// light.cast_shadows = true;
-	/// scene.add_light(light);
+	/// scene.set_active_camera(overview);
+	///
+	/// // Later, glide to the close-up over two seconds
+	/// scene.transition_to_camera(closeup, 2.0);
 	/// ```
-	pub fn enable_shadows(&mut self, gl: &GL) -> Result<(), String> {
-		self.shadow_map = Some(ShadowMap::new(gl)?);
-		self.shadows_enabled = true;
-		
-		let shadow_vert = include_str!("../shaders/shadow_depth.vert");
-		let shadow_frag = include_str!("../shaders/shadow_depth.frag");
-		self.shadow_material = Some(Material::from_source(gl, shadow_vert, shadow_frag)?);
-		
-		Ok(())
+	pub fn set_active_camera(&mut self, id: CameraId) -> bool {
+		self.transition_to_camera(id, 0.0)
 	}
 
-	/// Disables shadow rendering.
+	/// Switches to a registered camera, blending from the current view over
+	/// `duration` seconds with [`Camera::blend`].
 	///
-	/// Shadows will no longer be rendered, but the shadow map resources
-	/// are retained for quick re-enabling.
-	pub fn disable_shadows(&mut self) {
-		self.shadows_enabled = false;
+	/// The blend eases in and out and follows the target camera if it moves.
+	/// Switching again mid-way starts from wherever the view is. Returns
+	/// `false` if the id is unknown.
+	pub fn transition_to_camera(&mut self, id: CameraId, duration: f32) -> bool {
+		if !self.cameras.contains_key(id) {
+			return false;
+		}
+
+		self.active_camera = Some(id);
+		self.camera_transition = (duration > 0.0).then(|| CameraTransition {
+			from: self.camera.clone(),
+			duration,
+			elapsed: 0.0,
+		});
+
+		self.update_active_camera(0.0);
+		true
 	}
 
-	/// Checks if any light in the scene casts shadows.
-	fn has_shadow_casting_light(&self) -> bool {
-		self.lights.values().any(|l| l.cast_shadows)
+	/// Stops following the active camera, leaving [`camera`](Self::camera)
+	/// free to move from where it is.
+	pub fn release_camera(&mut self) {
+		self.active_camera = None;
+		self.camera_transition = None;
 	}
 
-	/// Renders the shadow depth pass.
-	///
-	/// Renders all objects from the light's perspective into the shadow map.
-	fn render_shadow_pass(&mut self, gl: &GL, canvas_width: i32, canvas_height: i32) {
-		if !self.shadows_enabled || !self.has_shadow_casting_light() {
-			return;
-		}
+	/// Returns the camera being rendered from, if any.
+	pub fn active_camera(&self) -> Option<CameraId> {
+		self.active_camera
+	}
 
-		let shadow_light = self.lights.values().find(|l| l.cast_shadows);
-		
-		let (shadow_map, shadow_material) = match (&mut self.shadow_map, &self.shadow_material) {
-			(Some(sm), Some(mat)) => (sm, mat),
-			_ => return,
-		};
+	/// Returns `true` while blending to the active camera.
+	pub fn is_camera_transitioning(&self) -> bool {
+		self.camera_transition.is_some()
+	}
 
-		let light = match shadow_light {
-			Some(l) => l.clone(),
-			None => return,
+	/// Moves the view to the active camera, advancing any transition.
+	fn update_active_camera(&mut self, delta_time: f32) {
+		let Some(target) = self.active_camera.and_then(|id| self.cameras.get(id)) else {
+			return;
 		};
 
-		match &light.light_type {
-			LightType::Directional => {
-				shadow_map.update_directional(light.direction, Vec3::ZERO, 10.0);
-			}
-			LightType::Point { radius } => {
-				let target = Vec3::ZERO;
-				shadow_map.update_point(light.position, target, std::f32::consts::FRAC_PI_2, 0.1, *radius);
-			}
-			LightType::Spot { angle, .. } => {
-				let target = light.position + light.direction;
-				shadow_map.update_point(light.position, target, *angle, 0.1, 50.0);
+		let aspect = self.camera.aspect;
+
+		self.camera = match &mut self.camera_transition {
+			Some(transition) => {
+				transition.elapsed += delta_time;
+
+				let t = (transition.elapsed / transition.duration).min(1.0);
+				transition.from.blend(target, t * t * (3.0 - 2.0 * t))
 			}
+			None => target.clone(),
+		};
+		self.camera.aspect = aspect;
+
+		if self.camera_transition.as_ref().is_some_and(|transition| transition.elapsed >= transition.duration) {
+			self.camera_transition = None;
 		}
+	}
 
-		shadow_map.bind(gl);
+	pub fn add_decal(&mut self, decal: Decal) -> DecalId {
+		self.decals.insert(decal)
+	}
 
-		gl.enable(GL::DEPTH_TEST);
-		gl.clear(GL::DEPTH_BUFFER_BIT);
+	pub fn remove_decal(&mut self, id: DecalId) -> Option<Decal> {
+		self.decals.remove(id)
+	}
 
-		let program = shadow_material.program();
-		gl.use_program(Some(program));
+	/// Adds a scene texture, rendered at the start of the next frame.
+	pub fn add_scene_texture(&mut self, texture: SceneTexture) -> SceneTextureId {
+		self.scene_textures.insert(texture)
+	}
 
-		if let Some(loc) = gl.get_uniform_location(program, "lightSpace") {
-			gl.uniform_matrix4fv_with_f32_array(
-				Some(&loc), false, &shadow_map.light_space.to_cols_array()
-			);
-		}
+	pub fn remove_scene_texture(&mut self, id: SceneTextureId) -> Option<SceneTexture> {
+		self.scene_textures.remove(id)
+	}
 
-		for obj in self.objects.values() {
-			if let Some(loc) = gl.get_uniform_location(program, "model") {
-				gl.uniform_matrix4fv_with_f32_array(
-					Some(&loc), false, &obj.transform.to_matrix().to_cols_array()
-				);
-			}
+	/// Adds a reflection probe, rendered at the start of the next frame.
+	pub fn add_reflection_probe(&mut self, probe: ReflectionProbe) -> ReflectionProbeId {
+		self.reflection_probes.insert(probe)
+	}
 
-			obj.mesh.draw_depth_only(gl, program);
-		}
+	pub fn remove_reflection_probe(&mut self, id: ReflectionProbeId) -> Option<ReflectionProbe> {
+		self.reflection_probes.remove(id)
+	}
 
-		shadow_map.unbind(gl, canvas_width, canvas_height);
+	pub fn get(&self, id: ObjectId) -> Option<&SceneObject> {
+		self.objects.get(id)
 	}
 
-	/// Sets the post-processing effect stack.
+	pub fn get_mut(&mut self, id: ObjectId) -> Option<&mut SceneObject> {
+		self.objects.get_mut(id)
+	}
+
+	pub fn get_light(&self, id: LightId) -> Option<&Light> {
+		self.lights.get(id)
+	}
+
+	/// Iterates over every object and its id.
+	pub fn iter(&self) -> impl Iterator<Item = (ObjectId, &SceneObject)> {
+		self.objects.iter()
+	}
+
+	/// Iterates over every object for bulk updates.
 	///
 	/// # Examples
 	///
+	/// ```ignore
+	/// app.run(move |scene, time| {
+	///		for (_, obj) in scene.iter_mut().filter(|(_, obj)| obj.tags.contains("spinner")) {
+	///			obj.transform.rotation = Quat::from_rotation_y(time);
+	///		}
+	/// });
 	/// ```
-	/// use oxgl::common::{PostProcessStack, pp_presets};
-	///
-	/// let mut pp = PostProcessStack::new(&gl, 800, 600)?;
-	/// pp.push(pp_presets::vignette(&gl, 0.8, 0.4));
-	/// pp.push(pp_presets::chromatic_aberration(&gl, 5.0));
-	///
-	/// scene.set_post_process(pp);
-	/// ```
-	pub fn set_post_process(&mut self, stack: PostProcessStack) {
-		self.post_process = Some(stack);
+	pub fn iter_mut(&mut self) -> impl Iterator<Item = (ObjectId, &mut SceneObject)> {
+		self.objects.iter_mut()
 	}
 
-	/// Renders the scene.
+	pub fn iter_lights(&self) -> impl Iterator<Item = (LightId, &Light)> {
+		self.lights.iter()
+	}
+
+	/// Iterates over every light for bulk updates. Changes aren't reported
+	/// to [event listeners](Self::on_event).
+	pub fn iter_lights_mut(&mut self) -> impl Iterator<Item = (LightId, &mut Light)> {
+		self.lights.iter_mut()
+	}
+
+	/// Returns the ids of the objects matching a predicate, so they can be
+	/// changed or removed afterwards.
+	pub fn filter<F>(&self, mut predicate: F) -> Vec<ObjectId>
+	where
+		F: FnMut(ObjectId, &SceneObject) -> bool,
+	{
+		self.objects
+			.iter()
+			.filter(|&(id, obj)| predicate(id, obj))
+			.map(|(id, _)| id)
+			.collect()
+	}
+
+	/// Keeps only the objects for which `keep` returns `true`.
 	///
-	/// Executes the full rendering pipeline:
-	/// 1. Binds post-process framebuffer (if enabled)
-	/// 2. Clears color and depth buffers
-	/// 3. Renders shadow pass (if enabled)
-	/// 4. Renders all objects with lighting
-	/// 5. Applies post-processing effects (if enabled)
+	/// Objects are removed as with [`remove`](Self::remove), so children of
+	/// removed objects are re-parented and listeners are notified.
 	///
 	/// # Examples
 	///
+	/// ```ignore
+	/// // Despawn everything that fell out of the world
+	/// scene.retain(|_, obj| obj.transform.position.y > -50.0);
 	/// ```
-	/// // In your render loop
-	/// scene.render(&renderer, elapsed_time);
-	/// ```
-	pub fn render(&mut self, renderer: &Renderer, time: f32) {
-		let gl = &renderer.gl;
-		let canvas = renderer.canvas();
-		let width = canvas.width() as i32;
-		let height = canvas.height() as i32;
-		let shadows_active = self.shadows_enabled && self.has_shadow_casting_light();
+	pub fn retain<F>(&mut self, mut keep: F)
+	where
+		F: FnMut(ObjectId, &mut SceneObject) -> bool,
+	{
+		let removed: Vec<ObjectId> = self.objects
+			.iter_mut()
+			.filter_map(|(id, obj)| (!keep(id, obj)).then_some(id))
+			.collect();
 
-		if let Some(pp) = &self.post_process {
-			pp.begin(gl);
-		} else {
-			gl.bind_framebuffer(GL::FRAMEBUFFER, None);
-			gl.viewport(0, 0, width, height);
+		for id in removed {
+			self.remove(id);
 		}
+	}
 
-		gl.clear_color(0.1, 0.1, 0.1, 1.0);
-		gl.clear(GL::COLOR_BUFFER_BIT | GL::DEPTH_BUFFER_BIT);
-
-		if shadows_active {
-			self.render_shadow_pass(gl, width, height);
+	/// Keeps only the lights for which `keep` returns `true`.
+	pub fn retain_lights<F>(&mut self, mut keep: F)
+	where
+		F: FnMut(LightId, &mut Light) -> bool,
+	{
+		let removed: Vec<LightId> = self.lights
+			.iter_mut()
+			.filter_map(|(id, light)| (!keep(id, light)).then_some(id))
+			.collect();
 
-			if let Some(pp) = &self.post_process {
-				pp.begin(gl);
-			}
+		for id in removed {
+			self.remove_light(id);
 		}
+	}
 
-		gl.enable(GL::DEPTH_TEST);
-		
-		let lights: Vec<Light> = self.lights.values().cloned().collect();
-		
-		let light_space = if shadows_active {
-			self.shadow_map.as_ref()
-				.map(|sm| {
-					sm.bind_texture(gl, 0);
-					sm.light_space
-				})
-				.unwrap_or(Mat4::IDENTITY)
-		} else {
-			Mat4::IDENTITY
-		};
+	/// Returns a light for editing.
+	///
+	/// Changes made this way aren't reported to listeners, which suits
+	/// per-frame animation. Use [`update_light`](Self::update_light) for
+	/// edits the UI should see.
+	pub fn get_light_mut(&mut self, id: LightId) -> Option<&mut Light> {
+		self.lights.get_mut(id)
+	}
 
-		for obj in self.objects.values_mut() {
-			let program = obj.mesh.material.program();
+	/// Edits a light and reports a [`SceneEvent::LightChanged`].
+	///
+	/// Returns `false` if the light doesn't exist.
+	pub fn update_light(&mut self, id: LightId, edit: impl FnOnce(&mut Light)) -> bool {
+		let Some(light) = self.lights.get_mut(id) else {
+			return false;
+		};
 
-			gl.use_program(Some(program));
-			
-			if let Some(loc) = gl.get_uniform_location(program, "shadowsEnabled") {
-				gl.uniform1i(Some(&loc), if shadows_active { 1 } else { 0 });
-			}
+		edit(light);
+		self.emit(SceneEvent::LightChanged(id));
+		true
+	}
 
-			if shadows_active {
-				if let Some(loc) = gl.get_uniform_location(program, "lightSpace") {
-					gl.uniform_matrix4fv_with_f32_array(
-						Some(&loc), false, &light_space.to_cols_array()
-					);
-				}
-				if let Some(loc) = gl.get_uniform_location(program, "shadowMap") {
-					gl.uniform1i(Some(&loc), 0);
-				}
-			}
-			
-			obj.mesh.draw(gl, &obj.transform, &self.camera, &lights);
+	/// Shows or hides an object and its children.
+	pub fn set_visible(&mut self, id: ObjectId, visible: bool) {
+		if let Some(obj) = self.objects.get_mut(id) && obj.visible != visible {
+			obj.visible = visible;
+			self.emit(SceneEvent::VisibilityChanged { object: id, visible });
 		}
+	}
 
-		if let Some(pp) = &mut self.post_process {
-			pp.end(gl, time);
+	/// Enables [occlusion culling](super::occlusion) for an object.
+	///
+	/// Worth it for objects that are expensive to draw and often hidden
+	/// behind others. The object may appear a frame late when it comes
+	/// into view.
+	pub fn set_occlusion_culling(&mut self, id: ObjectId, enabled: bool) {
+		if let Some(obj) = self.objects.get_mut(id) {
+			obj.occlusion_culling = enabled;
 		}
 	}
 
-	/// Renders debug visualization gizmos.
-	///
-	/// Draws wireframe debug primitives based on the provided settings.
-	/// Should be called after [`render`](Self::render) for proper layering.
-	///
-	/// # Examples
-	///
-	/// ```
-	/// use oxgl::renderer_3d::{GizmoRenderer, DebugSettings};
-	///
-	/// let gizmos = GizmoRenderer::new(&gl);
-	/// let settings = DebugSettings {
-	///		show_grid: true,
-	///		show_axes: true,
-	///		show_light_gizmos: true,
-	///		..Default::default()
-	/// };
+	/// Sets whether an object casts shadows and contact shadows.
 	///
-	/// scene.render(&renderer, time);
-	/// scene.render_debug(&renderer, &gizmos, &settings, true);
-	/// ```
-	pub fn render_debug(&self, renderer: &Renderer, gizmos: &GizmoRenderer, settings: &DebugSettings, disable_depth: bool) {
-		let gl = &renderer.gl;
+	/// Useful for ground planes, skies and effects that shouldn't darken
+	/// what's around them.
+	pub fn set_cast_shadows(&mut self, id: ObjectId, enabled: bool) {
+		if let Some(obj) = self.objects.get_mut(id) {
+			obj.cast_shadows = enabled;
+		}
+	}
 
-		if disable_depth {
-			gl.disable(GL::DEPTH_TEST);
+	/// Sets whether an object's vertex normals and tangents are drawn by
+	/// [`Scene::render_debug`], to track down shading artifacts.
+	pub fn set_show_normals(&mut self, id: ObjectId, enabled: bool) {
+		if let Some(obj) = self.objects.get_mut(id) {
+			obj.show_normals = enabled;
 		}
+	}
 
-		if settings.show_grid {
-			gizmos.grid(
-				gl, 
-				&self.camera, 
+	/// Sets whether shadows fall on an object.
+	///
+	/// Only affects shaders that sample shadows, such as the lit presets.
+	pub fn set_receive_shadows(&mut self, id: ObjectId, enabled: bool) {
+		if let Some(obj) = self.objects.get_mut(id) {
+			obj.receive_shadows = enabled;
+		}
+	}
+
+	/// Returns whether an object and all of its parents are visible.
+	pub fn is_visible(&self, id: ObjectId) -> bool {
+		self.objects.contains_key(id) && is_visible_in(&self.objects, id)
+	}
+
+	/// Turns a light on or off without removing it.
+	///
+	/// Disabled lights don't light objects or cast shadows.
+	pub fn set_light_enabled(&mut self, id: LightId, enabled: bool) {
+		if let Some(light) = self.lights.get_mut(id) && light.enabled != enabled {
+			light.enabled = enabled;
+			self.emit(SceneEvent::LightChanged(id));
+		}
+	}
+
+	/// Sets the display name of an object.
+	pub fn set_name(&mut self, id: ObjectId, name: &str) {
+		if let Some(obj) = self.objects.get_mut(id) {
+			obj.name = Some(name.to_string());
+			self.mark_outline_dirty();
+		}
+	}
+
+	/// Sets the display name of a light.
+	pub fn set_light_name(&mut self, id: LightId, name: &str) {
+		if let Some(light) = self.lights.get_mut(id) {
+			light.name = Some(name.to_string());
+			self.mark_outline_dirty();
+			self.emit(SceneEvent::LightChanged(id));
+		}
+	}
+
+	/// Returns the first object with the given name.
+	///
+	/// Names aren't required to be unique, see [`iter_named`](Self::iter_named).
+	pub fn find_by_name(&self, name: &str) -> Option<ObjectId> {
+		self.iter_named(name).next().map(|(id, _)| id)
+	}
+
+	/// Returns the first light with the given name.
+	pub fn find_light_by_name(&self, name: &str) -> Option<LightId> {
+		self.lights
+			.iter()
+			.find(|(_, light)| light.name.as_deref() == Some(name))
+			.map(|(id, _)| id)
+	}
+
+	/// Iterates over every object with the given name.
+	pub fn iter_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = (ObjectId, &'a SceneObject)> + 'a {
+		self.objects.iter().filter(move |(_, obj)| obj.name.as_deref() == Some(name))
+	}
+
+	/// Adds a tag to an object and reports a [`SceneEvent::TagsChanged`].
+	/// Returns `false` if the object doesn't exist.
+	pub fn add_tag(&mut self, id: ObjectId, tag: &str) -> bool {
+		let Some(obj) = self.objects.get_mut(id) else {
+			return false;
+		};
+
+		obj.tags.insert(tag.to_string());
+		self.emit(SceneEvent::TagsChanged(id));
+		true
+	}
+
+	/// Removes a tag from an object, returning whether it had the tag.
+	pub fn remove_tag(&mut self, id: ObjectId, tag: &str) -> bool {
+		let removed = self.objects.get_mut(id).is_some_and(|obj| obj.tags.remove(tag));
+
+		if removed {
+			self.emit(SceneEvent::TagsChanged(id));
+		}
+		removed
+	}
+
+	pub fn has_tag(&self, id: ObjectId, tag: &str) -> bool {
+		self.objects.get(id).is_some_and(|obj| obj.tags.contains(tag))
+	}
+
+	/// Iterates over every object with the given tag.
+	///
+	/// # Examples
+	///
+	/// ```ignore
+	/// for (id, enemy) in scene.iter_tagged("enemy") {
+	///		log::info!("{:?} at {}", enemy.name, scene.world_matrix(id).w_axis);
+	/// }
+	///
+	/// let enemies: Vec<ObjectId> = scene.iter_tagged("enemy").map(|(id, _)| id).collect();
+	/// for id in enemies {
+	///		scene.remove(id);
+	/// }
+	/// ```
+	pub fn iter_tagged<'a>(&'a self, tag: &'a str) -> impl Iterator<Item = (ObjectId, &'a SceneObject)> + 'a {
+		self.objects.iter().filter(move |(_, obj)| obj.tags.contains(tag))
+	}
+
+	/// Adds a tag to a light. Returns `false` if the light doesn't exist.
+	pub fn add_light_tag(&mut self, id: LightId, tag: &str) -> bool {
+		self.update_light(id, |light| {
+			light.tags.insert(tag.to_string());
+		})
+	}
+
+	/// Removes a tag from a light, returning whether it had the tag.
+	pub fn remove_light_tag(&mut self, id: LightId, tag: &str) -> bool {
+		let removed = self.lights.get_mut(id).is_some_and(|light| light.tags.remove(tag));
+
+		if removed {
+			self.emit(SceneEvent::LightChanged(id));
+		}
+		removed
+	}
+
+	/// Iterates over every light with the given tag.
+	pub fn iter_lights_tagged<'a>(&'a self, tag: &'a str) -> impl Iterator<Item = (LightId, &'a Light)> + 'a {
+		self.lights.iter().filter(move |(_, light)| light.tags.contains(tag))
+	}
+
+	/// Parents `child` to `parent`, or detaches it when `parent` is `None`.
+	///
+	/// The child's transform becomes relative to the parent.
+	///
+	/// Returns `false` if either object doesn't exist or if the change
+	/// would create a cycle.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// let body = scene.add(body_mesh, Transform3D::new());
+	/// let wheel = scene.add(wheel_mesh, Transform3D::new().with_position(Vec3::X));
+	///
+	/// scene.set_parent(wheel, Some(body));
+	/// ```
+	pub fn set_parent(&mut self, child: ObjectId, parent: Option<ObjectId>) -> bool {
+		if !self.objects.contains_key(child) {
+			return false;
+		}
+
+		if let Some(parent) = parent {
+			let mut current = Some(parent);
+
+			while let Some(id) = current {
+				if id == child {
+					return false;
+				}
+
+				match self.objects.get(id) {
+					Some(obj) => current = obj.parent,
+					None => return false,
+				}
+			}
+		}
+
+		self.objects[child].parent = parent;
+		self.mark_outline_dirty();
+		true
+	}
+
+	/// Returns the ids of the direct children of an object.
+	pub fn children(&self, id: ObjectId) -> Vec<ObjectId> {
+		self.objects
+			.iter()
+			.filter(|(_, obj)| obj.parent == Some(id))
+			.map(|(child, _)| child)
+			.collect()
+	}
+
+	/// Computes the world matrix of an object by walking up its parents.
+	pub fn world_matrix(&self, id: ObjectId) -> Mat4 {
+		world_matrix_of(&self.objects, id)
+	}
+
+	/// Rotates a chain of objects, each the child of the one before, so the
+	/// origin of the last reaches the solver's target.
+	///
+	/// Returns `false` if the target is out of reach, in which case the
+	/// chain stretches towards it. Chains with a missing object, or one not
+	/// parented to the previous, are left unchanged and return `false`.
+	///
+	/// ## Examples
+	///
+	/// ```ignore
+	/// use oxgl::core::IkSolver;
+	///
+	/// // Plant the foot on the ground, bending the knee forwards
+	/// let solver = IkSolver::new(ground_point).with_pole(hip_position + forward);
+	/// scene.solve_ik(&[thigh, shin, foot], &solver);
+	/// ```
+	pub fn solve_ik(&mut self, chain: &[ObjectId], solver: &IkSolver) -> bool {
+		let linked = chain.windows(2).all(|pair| self.objects.get(pair[1]).is_some_and(|obj| obj.parent == Some(pair[0])));
+
+		if chain.is_empty() || !linked || !self.objects.contains_key(chain[0]) {
+			return false;
+		}
+
+		let parent = self.objects[chain[0]].parent.map_or(Mat4::IDENTITY, |p| self.world_matrix(p));
+		let mut transforms: Vec<Transform3D> = chain.iter().map(|&id| self.objects[id].transform.clone()).collect();
+		let reached = solver.solve_transforms(parent, &mut transforms);
+
+		for (&id, transform) in chain.iter().zip(transforms) {
+			self.objects[id].transform = transform;
+		}
+
+		reached
+	}
+
+	/// Returns the world-space axis-aligned bounds of an object's mesh.
+	pub fn world_bounds(&self, id: ObjectId) -> Option<Aabb> {
+		let obj = self.objects.get(id)?;
+		Some(obj.mesh.bounds().transformed(&self.world_matrix(id)))
+	}
+
+	/// Sets the simplified mesh used to pick an object.
+	///
+	/// Returns `false` if the object doesn't exist.
+	pub fn set_collider(&mut self, id: ObjectId, collider: CollisionMesh) -> bool {
+		match self.objects.get_mut(id) {
+			Some(obj) => {
+				obj.collider = Some(collider);
+				true
+			}
+			None => false,
+		}
+	}
+
+	/// Sets the level-of-detail meshes for an object.
+	///
+	/// Returns `false` if the object doesn't exist.
+	pub fn set_lod(&mut self, id: ObjectId, lod: Lod) -> bool {
+		match self.objects.get_mut(id) {
+			Some(obj) => {
+				obj.lod = Some(lod);
+				true
+			}
+			None => false,
+		}
+	}
+
+	/// Sets the physics body simulated for an object, or removes it.
+	///
+	/// A changed body is recreated in the physics world on the next frame.
+	/// Returns `false` if the object doesn't exist.
+	pub fn set_body(&mut self, id: ObjectId, body: Option<PhysicsBody>) -> bool {
+		let Some(obj) = self.objects.get_mut(id) else { return false };
+
+		obj.body = body;
+
+		if let Some(physics) = &mut self.physics && physics.registered.remove(&id) {
+			physics.world.borrow_mut().remove_body(id);
+		}
+
+		true
+	}
+
+	/// Sets the trail following an object, or removes it.
+	///
+	/// Returns `false` if the object doesn't exist.
+	pub fn set_trail(&mut self, id: ObjectId, trail: Option<TrailRenderer>) -> bool {
+		match self.objects.get_mut(id) {
+			Some(obj) => {
+				obj.trail = trail;
+				true
+			}
+			None => false,
+		}
+	}
+
+	/// Turns an object into a distorter, drawn with a mask material that
+	/// bends the image behind it instead of being drawn itself, or back
+	/// into a regular object.
+	///
+	/// Masks are drawn into the distortion texture of the scene's
+	/// post-processing, read by the
+	/// [distortion](crate::common::postprocessing::presets::distortion)
+	/// effect. Masks write screen offsets; the
+	/// [`distortion`](crate::common::presets::distortion) preset covers
+	/// heat haze, glass and shockwaves. Distorters cast no shadows and hide
+	/// nothing behind them. For visible glass, add a second object with
+	/// the glass material.
+	///
+	/// Returns `false` if the object doesn't exist.
+	///
+	/// # Examples
+	///
+	/// ```ignore
+	/// use oxgl::common::{presets, pp_presets};
+	///
+	/// // An expanding shockwave sphere
+	/// let wave = scene.add(sphere, Transform3D::new().with_position(impact));
+	/// scene.set_distortion(wave, Some(presets::distortion(&gl, 0.5)));
+	/// ```
+	pub fn set_distortion(&mut self, id: ObjectId, distortion: Option<Material>) -> bool {
+		match self.objects.get_mut(id) {
+			Some(obj) => {
+				obj.distortion = distortion;
+				true
+			}
+			None => false,
+		}
+	}
+
+	/// Moves an audio player's listener to the camera and its emitters to
+	/// their objects. Call once per frame.
+	pub fn update_audio(&self, player: &mut AudioPlayer) {
+		let camera = &self.camera;
+		let forward = (camera.target - camera.position).normalize_or_zero();
+
+		player.set_listener(camera.position, forward, camera.up);
+		player.update_emitters(|object| {
+			self.objects.get(object).map(|_| self.world_matrix(object).w_axis.truncate())
+		});
+	}
+
+	/// Sets the physics world that simulates objects with a
+	/// [`body`](SceneObject::body), stepped at the start of every frame.
+	///
+	/// Keep a clone of the `Rc` to apply forces or run queries on the world.
+	pub fn set_physics<W: PhysicsWorld + 'static>(&mut self, world: Rc<RefCell<W>>) {
+		self.clear_physics();
+		self.physics = Some(PhysicsSync::new(world));
+	}
+
+	/// Removes the physics world, along with every body registered with it.
+	pub fn clear_physics(&mut self) {
+		if let Some(physics) = self.physics.take() {
+			let mut world = physics.world.borrow_mut();
+
+			for id in physics.registered {
+				world.remove_body(id);
+			}
+		}
+	}
+
+	/// Registers new bodies, moves kinematic bodies, steps the physics world
+	/// and copies dynamic bodies back into object transforms.
+	///
+	/// Called by [`render`](Self::render) with the frame's delta time, so it
+	/// only needs calling directly when rendering some other way.
+	pub fn step_physics(&mut self, dt: f32) {
+		let Some(physics) = &mut self.physics else { return };
+		let mut world = physics.world.borrow_mut();
+		let objects = &mut self.objects;
+
+		physics.registered.retain(|&id| {
+			let keep = objects.get(id).is_some_and(|obj| obj.body.is_some());
+
+			if !keep {
+				world.remove_body(id);
+			}
+
+			keep
+		});
+
+		for (id, obj) in objects.iter() {
+			let Some(body) = &obj.body else { continue };
+
+			if physics.registered.insert(id) {
+				world.add_body(id, body, &world_transform_of(objects, id));
+			} else if body.kind == BodyKind::Kinematic {
+				world.set_body_transform(id, &world_transform_of(objects, id));
+			}
+		}
+
+		world.step(dt);
+
+		let dynamic: Vec<ObjectId> = objects
+			.iter()
+			.filter(|(_, obj)| obj.body.as_ref().is_some_and(|body| body.kind == BodyKind::Dynamic))
+			.map(|(id, _)| id)
+			.collect();
+
+		for id in dynamic {
+			let Some((position, rotation)) = world.body_transform(id) else { continue };
+			let parent = objects[id].parent.map(|parent| world_matrix_of(objects, parent)).unwrap_or(Mat4::IDENTITY);
+			let world_scale = world_transform_of(objects, id).scale;
+
+			let local = parent.inverse() * Mat4::from_scale_rotation_translation(world_scale, rotation, position);
+			let (_, local_rotation, local_position) = local.to_scale_rotation_translation();
+
+			let transform = &mut objects[id].transform;
+			transform.position = local_position;
+			transform.rotation = local_rotation;
+		}
+	}
+
+	/// Finds the closest object hit by a world-space ray.
+	///
+	/// Only visible objects with a [`collider`](SceneObject::collider) are
+	/// tested.
+	pub fn raycast(&self, ray: &Ray) -> Option<RayHit> {
+		self.objects
+			.iter()
+			.filter(|&(id, _)| is_visible_in(&self.objects, id))
+			.filter_map(|(id, obj)| {
+				let collider = obj.collider.as_ref()?;
+				let world = self.world_matrix(id);
+				let local = ray.transform(&world.inverse());
+				let point = world.transform_point3(local.at(collider.raycast(&local)?));
+
+				Some(RayHit { object: id, distance: ray.origin.distance(point), point })
+			})
+			.min_by(|a, b| a.distance.total_cmp(&b.distance))
+	}
+
+	/// Returns the visible objects whose world bounds overlap those of `id`.
+	///
+	/// Returns an empty list if the object doesn't exist.
+	pub fn overlapping(&self, id: ObjectId) -> Vec<ObjectId> {
+		let Some(bounds) = self.world_bounds(id).filter(|b| !b.is_empty()) else {
+			return Vec::new();
+		};
+
+		self.objects
+			.keys()
+			.filter(|&other| other != id && is_visible_in(&self.objects, other))
+			.filter(|&other| self.world_bounds(other).is_some_and(|b| bounds.intersects(&b)))
+			.collect()
+	}
+
+	/// Returns the visible objects whose world bounds overlap a sphere.
+	pub fn overlap_sphere(&self, center: Vec3, radius: f32) -> Vec<ObjectId> {
+		self.objects
+			.keys()
+			.filter(|&id| is_visible_in(&self.objects, id))
+			.filter(|&id| self.world_bounds(id).is_some_and(|b| b.intersects_sphere(center, radius)))
+			.collect()
+	}
+
+	/// Moves a sphere from `origin` along `direction` and returns the first
+	/// visible object whose world bounds it touches.
+	///
+	/// The hit point is the sphere's center at the moment of contact. See
+	/// [`sphere_cast_aabb`](super::collision::sphere_cast_aabb). Returns
+	/// `None` for a zero `direction`.
+	pub fn sphere_cast(&self, origin: Vec3, direction: Vec3, radius: f32) -> Option<RayHit> {
+		if direction.length_squared() == 0.0 {
+			return None;
+		}
+
+		let ray = Ray::new(origin, direction);
+
+		self.objects
+			.keys()
+			.filter(|&id| is_visible_in(&self.objects, id))
+			.filter_map(|id| {
+				let bounds = self.world_bounds(id)?;
+				let distance = sphere_cast_aabb(&ray, radius, &bounds)?;
+
+				Some(RayHit { object: id, distance, point: ray.at(distance) })
+			})
+			.min_by(|a, b| a.distance.total_cmp(&b.distance))
+	}
+
+	/// Finds the closest object under a pixel of the viewport.
+	///
+	/// `x` and `y` are measured from the top-left corner, as in mouse events.
+	/// See [`raycast`](Self::raycast).
+	pub fn pick(&self, x: f32, y: f32, width: f32, height: f32) -> Option<RayHit> {
+		self.raycast(&self.camera.screen_ray(x, y, width, height))
+	}
+
+	/// Picks the object under the pointer and reports
+	/// [`SceneEvent::HoverEnter`] and [`SceneEvent::HoverLeave`] when it
+	/// changes. Call from a `pointermove` handler.
+	pub fn pointer_move(&mut self, x: f32, y: f32, width: f32, height: f32) -> Option<RayHit> {
+		let hit = self.pick(x, y, width, height);
+		let hovered = hit.map(|hit| hit.object);
+
+		if hovered != self.hovered {
+			if let Some(previous) = self.hovered {
+				self.emit(SceneEvent::HoverLeave(previous));
+			}
+			if let Some(current) = hovered {
+				self.emit(SceneEvent::HoverEnter(current));
+			}
+			self.hovered = hovered;
+		}
+
+		hit
+	}
+
+	/// Picks the object under the pointer and reports a
+	/// [`SceneEvent::Clicked`] if there is one. Call from a `click` handler.
+	pub fn pointer_click(&mut self, x: f32, y: f32, width: f32, height: f32) -> Option<RayHit> {
+		let hit = self.pick(x, y, width, height);
+
+		if let Some(hit) = hit {
+			self.emit(SceneEvent::Clicked(hit));
+		}
+
+		hit
+	}
+
+	/// Returns the object last found under the pointer by
+	/// [`pointer_move`](Self::pointer_move).
+	pub fn hovered(&self) -> Option<ObjectId> {
+		self.hovered
+	}
+
+	/// Projects a world position to canvas pixels, measured from the top-left
+	/// corner, using the canvas size of the last [`render`](Self::render).
+	///
+	/// Returns `None` for points behind the camera or before the first
+	/// render. See [`Camera::project`].
+	pub fn project(&self, world_pos: Vec3) -> Option<Vec2> {
+		if self.screen_size == Vec2::ZERO {
+			return None;
+		}
+
+		self.camera.project(world_pos, self.screen_size.x, self.screen_size.y)
+	}
+
+	/// Finds the closest visible sprite under a pixel of the viewport.
+	///
+	/// Sprites are hit anywhere inside their quad, including transparent
+	/// parts of the texture.
+	pub fn pick_sprite(&self, x: f32, y: f32, width: f32, height: f32) -> Option<SpriteHit> {
+		let view = self.camera.view_matrix();
+		let view_projection = self.camera.projection_matrix() * view;
+		let viewport = Vec2::new(width, height);
+
+		self.sprites
+			.iter()
+			.filter(|(_, sprite)| sprite.visible)
+			.filter_map(|(id, sprite)| {
+				let depth = -view.transform_point3(sprite.position).z;
+
+				if depth <= self.camera.near {
+					return None;
+				}
+
+				let ndc = view_projection.project_point3(sprite.position).truncate();
+				let center = Vec2::new(ndc.x + 1.0, 1.0 - ndc.y) * 0.5 * viewport;
+
+				// Pointer offset in the sprite's rotated frame, Y up
+				let offset = Vec2::new(x - center.x, center.y - y);
+				let local = Vec2::from_angle(-sprite.rotation).rotate(offset);
+				let half = sprite.screen_size(&self.camera, depth, height) * 0.5;
+
+				(local.abs().cmple(half).all()).then(|| SpriteHit {
+					sprite: id,
+					distance: self.camera.position.distance(sprite.position),
+				})
+			})
+			.min_by(|a, b| a.distance.total_cmp(&b.distance))
+	}
+
+	/// Builds a snapshot of the scene hierarchy.
+	///
+	/// Root objects appear in insertion order, followed by lights.
+	pub fn outline(&self) -> SceneOutline {
+		let mut roots: Vec<OutlineNode> = self.objects
+			.iter()
+			.filter(|(_, obj)| obj.parent.is_none_or(|p| !self.objects.contains_key(p)))
+			.map(|(id, _)| self.outline_node(id))
+			.collect();
+
+		roots.extend(self.lights.iter().map(|(id, light)| {
+			let (kind, default_name) = match light.light_type {
+				LightType::Directional => (OutlineKind::DirectionalLight, "Directional Light"),
+				LightType::Point { .. } => (OutlineKind::PointLight, "Point Light"),
+				LightType::Spot { .. } => (OutlineKind::SpotLight, "Spot Light"),
+			};
+
+			OutlineNode {
+				id: OutlineId::Light(id),
+				name: light.name.clone().unwrap_or_else(|| default_name.to_string()),
+				kind,
+				children: Vec::new(),
+			}
+		}));
+
+		SceneOutline { roots, revision: self.outline_revision }
+	}
+
+	fn outline_node(&self, id: ObjectId) -> OutlineNode {
+		let name = self.objects[id].name.clone().unwrap_or_else(|| "Object".to_string());
+
+		OutlineNode {
+			id: OutlineId::Object(id),
+			name,
+			kind: OutlineKind::Mesh,
+			children: self.children(id).into_iter().map(|child| self.outline_node(child)).collect(),
+		}
+	}
+
+	/// Builds a detailed snapshot of an object for an inspector panel.
+	///
+	/// See [`inspector`](super::inspector) for the properties it covers.
+	pub fn inspect(&self, id: ObjectId) -> Option<ObjectSnapshot> {
+		self.objects.get(id).map(|obj| ObjectSnapshot::new(id, obj))
+	}
+
+	/// Builds a detailed snapshot of a light for an inspector panel.
+	pub fn inspect_light(&self, id: LightId) -> Option<LightSnapshot> {
+		self.lights.get(id).map(|light| LightSnapshot::new(id, light))
+	}
+
+	/// Reads an object or light property by its
+	/// [path](super::inspector), such as `"transform.position.y"` or
+	/// `"material.shininess"`.
+	pub fn property(&self, id: OutlineId, path: &str) -> Option<PropertyValue> {
+		let (base, component) = inspector::split_component(path);
+
+		let value = match id {
+			OutlineId::Object(id) => inspector::object_property(self.objects.get(id)?, base)?,
+			OutlineId::Light(id) => inspector::light_property(self.lights.get(id)?, base)?,
+		};
+
+		match component {
+			Some(index) => value.component(index).map(PropertyValue::Float),
+			None => Some(value),
+		}
+	}
+
+	/// Writes an object or light property by its [path](super::inspector).
+	///
+	/// Material uniforms can only be changed, not added, and values must
+	/// have the property's type. Changes are reported like the equivalent
+	/// `Scene` methods, with transform edits reported as
+	/// [`SceneEvent::TransformEdited`] and light edits as
+	/// [`SceneEvent::LightChanged`].
+	///
+	/// ## Errors
+	///
+	/// Returns an error if the entry doesn't exist, the path is unknown or
+	/// the value has the wrong type.
+	///
+	/// ## Examples
+	///
+	/// ```ignore
+	/// scene.set_property(OutlineId::Object(id), "material.shininess", 64.0)?;
+	/// scene.set_property(OutlineId::Object(id), "transform.rotation.y", 45.0)?;
+	/// ```
+	pub fn set_property(&mut self, id: OutlineId, path: &str, value: impl Into<PropertyValue>) -> Result<(), String> {
+		let mut value = value.into();
+		let (base, component) = inspector::split_component(path);
+
+		if let Some(index) = component {
+			let current = self.property(id, base).ok_or_else(|| format!("Unknown property '{}'", base))?;
+			let PropertyValue::Float(v) = value else {
+				return Err(format!("Property '{}' expects Float, got {}", path, value.type_name()));
+			};
+
+			value = current
+				.with_component(index, v)
+				.ok_or_else(|| format!("Property '{}' has no such component", path))?;
+		}
+
+		match id {
+			OutlineId::Object(id) => {
+				match (base, &value) {
+					("name", PropertyValue::Text(name)) if self.objects.contains_key(id) => self.set_name(id, name),
+					("visible", PropertyValue::Bool(visible)) if self.objects.contains_key(id) => self.set_visible(id, *visible),
+					("cast_shadows", PropertyValue::Bool(enabled)) if self.objects.contains_key(id) => self.set_cast_shadows(id, *enabled),
+					_ => {
+						let obj = self.objects.get_mut(id).ok_or("Object not found")?;
+						inspector::set_object_property(obj, base, &value)?;
+
+						if base.starts_with("transform.") {
+							self.emit(SceneEvent::TransformEdited(id));
+						}
+					}
+				}
+			}
+			OutlineId::Light(id) => {
+				match (base, &value) {
+					("name", PropertyValue::Text(name)) if self.lights.contains_key(id) => self.set_light_name(id, name),
+					("enabled", PropertyValue::Bool(enabled)) if self.lights.contains_key(id) => self.set_light_enabled(id, *enabled),
+					_ => {
+						let light = self.lights.get_mut(id).ok_or("Light not found")?;
+						inspector::set_light_property(light, base, &value)?;
+						self.emit(SceneEvent::LightChanged(id));
+					}
+				}
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Returns the current structural revision of the scene.
+	///
+	/// Increases whenever objects or lights are added, removed, renamed or
+	/// re-parented through `Scene` methods.
+	pub fn outline_revision(&self) -> u64 {
+		self.outline_revision
+	}
+
+	/// Registers a callback invoked with a fresh [`SceneOutline`] when the
+	/// scene structure changes.
+	///
+	/// Changes are batched and reported at most once per frame, at the start
+	/// of [`Scene::render`].
+	///
+	/// # Examples
+	///
+	/// ```
+	/// let (outline, set_outline) = signal(SceneOutline::default());
+	///
+	/// scene.on_outline_change(move |snapshot| set_outline.set(snapshot.clone()));
+	/// ```
+	pub fn on_outline_change<F>(&mut self, callback: F)
+	where
+		F: FnMut(&SceneOutline) + 'static,
+	{
+		self.outline_listeners.push(Box::new(callback));
+		self.outline_dirty = true;
+	}
+
+	/// Registers a callback invoked with every [`SceneEvent`].
+	///
+	/// Events are queued and delivered at the start of the next frame, in the
+	/// order they happened.
+	pub fn on_event<F>(&mut self, callback: F) -> SceneListenerId
+	where
+		F: FnMut(&SceneEvent) + 'static,
+	{
+		self.event_listeners.insert(Box::new(callback))
+	}
+
+	/// Unregisters an event callback.
+	pub fn remove_listener(&mut self, id: SceneListenerId) -> bool {
+		self.event_listeners.remove(id).is_some()
+	}
+
+	pub(crate) fn emit(&mut self, event: SceneEvent) {
+		if !self.event_listeners.is_empty() {
+			self.pending_events.push(event);
+		}
+	}
+
+	fn notify_event_listeners(&mut self) {
+		if self.pending_events.is_empty() {
+			return;
+		}
+
+		let events = std::mem::take(&mut self.pending_events);
+
+		for listener in self.event_listeners.values_mut() {
+			for event in &events {
+				listener(event);
+			}
+		}
+	}
+
+	fn mark_outline_dirty(&mut self) {
+		self.outline_revision += 1;
+		self.outline_dirty = true;
+	}
+
+	fn notify_outline_listeners(&mut self) {
+		if !self.outline_dirty || self.outline_listeners.is_empty() {
+			return;
+		}
+
+		self.outline_dirty = false;
+
+		let outline = self.outline();
+		for listener in &mut self.outline_listeners {
+			listener(&outline);
+		}
+	}
+
+	/// Enables shadow mapping for the scene.
+	///
+	/// Creates the shadow map framebuffer and compiles the shadow depth shader.
+	/// Shadows will be cast from the first light with `cast_shadows` enabled.
+	///
+	/// # Errors
+	///
+	/// Returns an error if:
+	/// - Shadow map framebuffer creation fails
+	/// - Shadow shader compilation fails
+	///
+	/// # Examples
+	///
+	/// ```
+	/// scene.enable_shadows(&gl)?;
+	///
+	/// // Make a light cast shadows
+	/// let mut light = Light::directional(Vec3::new(-1.0, -1.0, -1.0), Vec3::ONE, 1.0);
+	/// light.cast_shadows = true;
+	/// scene.add_light(light);
+	/// ```
+	pub fn enable_shadows(&mut self, gl: &GL) -> Result<(), OxglError> {
+		self.shadow_map = Some(ShadowMap::new(gl)?);
+		self.shadows_enabled = true;
+		self.ensure_depth_material(gl)
+	}
+
+	/// Sets the world-space area the shadow map covers.
+	///
+	/// By default the shadow map is fitted every frame to the visible
+	/// objects inside the camera's view, with its depth range extended to
+	/// every shadow caster. A fixed area gives steadier shadows in scenes
+	/// with large or moving objects, such as a huge ground plane.
+	///
+	/// # Examples
+	///
+	/// ```ignore
+	/// use oxgl::core::Aabb;
+	///
+	/// // Only the playable area
+	/// scene.set_shadow_bounds(Some(Aabb::new(Vec3::new(-30.0, 0.0, -30.0), Vec3::new(30.0, 15.0, 30.0))));
+	///
+	/// // Back to fitting automatically
+	/// scene.set_shadow_bounds(None);
+	/// ```
+	pub fn set_shadow_bounds(&mut self, bounds: Option<Aabb>) {
+		self.shadow_bounds = bounds;
+	}
+
+	pub fn shadow_bounds(&self) -> Option<Aabb> {
+		self.shadow_bounds
+	}
+
+	/// Bakes the direct light and shadows falling on an object into a
+	/// `size` by `size` lightmap.
+	///
+	/// The object's mesh needs lightmap UVs from
+	/// [`Mesh::set_lightmap_uvs`], laid out without overlaps and with a few
+	/// texels of padding between islands. Every enabled light is baked, and
+	/// shadows are included when they're enabled. Without
+	/// [`set_shadow_bounds`](Self::set_shadow_bounds), the shadow map covers
+	/// every visible object for the bake rather than the camera's view.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// let lightmap = scene.bake_lightmap(&gl, floor, 512)?;
+	/// scene.objects[floor].mesh.material.set_lightmap(lightmap, 1.0);
+	/// ```
+	pub fn bake_lightmap(&mut self, gl: &GL, id: ObjectId, size: u32) -> Result<Texture, String> {
+		let obj = self.objects.get(id).ok_or("Object not found")?;
+
+		if !obj.mesh.has_lightmap_uvs() {
+			return Err("Object mesh has no lightmap UVs".to_string());
+		}
+
+		let model = world_matrix_of(&self.objects, id);
+		let canvas_width = gl.drawing_buffer_width();
+		let canvas_height = gl.drawing_buffer_height();
+		let shadows = self.shadows_enabled && self.shadow_map.is_some() && self.has_shadow_casting_light();
+
+		if shadows {
+			let fitted = self.shadow_bounds;
+
+			if fitted.is_none() {
+				let bounds = self.objects.iter()
+					.filter(|(id, _)| is_visible_in(&self.objects, *id))
+					.fold(Aabb::EMPTY, |bounds, (id, obj)| {
+						bounds.union(obj.mesh.bounds().transformed(&world_matrix_of(&self.objects, id)))
+					});
+
+				self.shadow_bounds = Some(bounds);
+			}
+
+			self.render_shadow_pass(gl, canvas_width, canvas_height);
+			self.shadow_bounds = fitted;
+		}
+
+		let lights: Vec<Light> = self.lights.values().filter(|l| l.enabled).cloned().collect();
+		let shadow = match (&self.shadow_map, lights.iter().position(|l| l.cast_shadows)) {
+			(Some(shadow_map), Some(index)) if shadows => {
+				shadow_map.bind_texture(gl, 0);
+				Some((shadow_map.light_space, index))
+			}
+			_ => None,
+		};
+
+		let result = lightmap::bake(gl, &self.objects[id].mesh, &model, size, &LightmapBake {
+			lights: &lights,
+			shadow,
+			shadow_settings: self.shadow_settings,
+			shadow_map_size: self.shadow_map.as_ref().map_or(SHADOW_MAP_SIZE, |map| map.size),
+		});
+
+		gl.viewport(0, 0, canvas_width, canvas_height);
+
+		result
+	}
+
+	/// Enables screen-space contact shadows.
+	///
+	/// Adds a depth pre-pass from the camera, which lit materials use to
+	/// trace short shadow rays for lights with
+	/// [`contact_shadows`](Light::contact_shadows) set.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the depth framebuffer or shader can't be created.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// scene.enable_contact_shadows(&gl)?;
+	/// scene.add_light(Light::directional(dir, Vec3::ONE, 1.0).with_contact_shadows(0.3, 0.8));
+	/// ```
+	pub fn enable_contact_shadows(&mut self, gl: &GL) -> Result<(), OxglError> {
+		if self.contact_shadow_map.is_none() {
+			self.contact_shadow_map = Some(ContactShadowMap::new(gl, 1, 1)?);
+		}
+
+		self.contact_shadows_enabled = true;
+		self.ensure_depth_material(gl)
+	}
+
+	/// Disables contact shadows, keeping the depth buffer for quick re-enabling.
+	pub fn disable_contact_shadows(&mut self) {
+		self.contact_shadows_enabled = false;
+	}
+
+	fn ensure_depth_material(&mut self, gl: &GL) -> Result<(), OxglError> {
+		if self.shadow_material.is_none() {
+			let shadow_vert = include_str!("../shaders/shadow_depth.vert");
+			let shadow_frag = include_str!("../shaders/shadow_depth.frag");
+			self.shadow_material = Some(Material::from_source(gl, shadow_vert, shadow_frag)?);
+		}
+
+		Ok(())
+	}
+
+	/// Disables shadow rendering.
+	///
+	/// Shadows will no longer be rendered, but the shadow map resources
+	/// are retained for quick re-enabling.
+	pub fn disable_shadows(&mut self) {
+		self.shadows_enabled = false;
+	}
+
+	/// Enables distance fog in the lambert, phong and material graph
+	/// shaders.
+	///
+	/// The canvas is cleared to the fog color so fully fogged objects blend
+	/// into the background.
+	///
+	/// # Examples
+	///
+	/// ```ignore
+	/// use oxgl::renderer_3d::Fog;
+	///
+	/// scene.set_fog(Fog::Linear { start: 30.0, end: 90.0 }, Vec3::new(0.6, 0.7, 0.8));
+	/// ```
+	pub fn set_fog(&mut self, fog: Fog, color: Vec3) {
+		self.fog = Some(fog);
+		self.fog_color = color;
+	}
+
+	/// Disables fog.
+	pub fn clear_fog(&mut self) {
+		self.fog = None;
+	}
+
+	/// Sets world-space planes that cut away every object, for cutaway and
+	/// section views.
+	///
+	/// Each plane is `(normal, distance)`; the parts of objects where
+	/// `normal.dot(point) + distance` is negative are hidden and cast no
+	/// shadows. Planes set on a material with
+	/// [`Material::set_clip_planes`] apply on top of these, up to
+	/// [`MAX_CLIP_PLANES`](crate::common::MAX_CLIP_PLANES) in total. Pass an
+	/// empty slice to stop clipping.
+	///
+	/// # Examples
+	///
+	/// ```ignore
+	/// use glam::Vec4;
+	///
+	/// // Hide everything above y = 2
+	/// scene.set_clip_planes(&[Vec4::new(0.0, -1.0, 0.0, 2.0)]);
+	/// ```
+	pub fn set_clip_planes(&mut self, planes: &[Vec4]) {
+		self.clip_planes = planes.to_vec();
+	}
+
+	pub fn clip_planes(&self) -> &[Vec4] {
+		&self.clip_planes
+	}
+
+	/// Checks if any light in the scene casts shadows.
+	fn has_shadow_casting_light(&self) -> bool {
+		self.lights.values().any(|l| l.enabled && l.cast_shadows)
+	}
+
+	/// Renders the shadow depth pass.
+	///
+	/// Renders all objects from the light's perspective into the shadow map.
+	fn render_shadow_pass(&mut self, gl: &GL, canvas_width: i32, canvas_height: i32) {
+		if !self.shadows_enabled || !self.has_shadow_casting_light() {
+			return;
+		}
+
+		let shadow_light = self.lights.values().find(|l| l.enabled && l.cast_shadows);
+		
+		let (shadow_map, shadow_material) = match (&mut self.shadow_map, &self.shadow_material) {
+			(Some(sm), Some(mat)) => (sm, mat),
+			_ => return,
+		};
+
+		let light = match shadow_light {
+			Some(l) => l.clone(),
+			None => return,
+		};
+
+		let (receivers, casters) = match self.shadow_bounds {
+			Some(bounds) => (bounds, bounds),
+			None => shadow_fit_bounds(&self.objects, &self.camera),
+		};
+
+		match &light.light_type {
+			LightType::Directional => {
+				shadow_map.fit_directional(light.direction, &receivers, &casters);
+			}
+			LightType::Point { radius } => {
+				shadow_map.fit_point(light.position, None, std::f32::consts::FRAC_PI_2, *radius, &receivers, &casters);
+			}
+			LightType::Spot { angle, outer_angle, range } => {
+				shadow_map.fit_point(light.position, Some(light.direction), outer_angle.max(*angle), *range, &receivers, &casters);
+			}
+		}
+
+		shadow_map.bind(gl);
+
+		gl.enable(GL::DEPTH_TEST);
+		gl.clear(GL::DEPTH_BUFFER_BIT);
+
+		let program = shadow_material.program();
+		gl.use_program(Some(program));
+
+		if let Some(loc) = gl.get_uniform_location(program, "lightSpace") {
+			gl.uniform_matrix4fv_with_f32_array(
+				Some(&loc), false, &shadow_map.light_space.to_cols_array()
+			);
+		}
+
+		for (id, obj) in &self.objects {
+			if !obj.cast_shadows || obj.distortion.is_some() || !is_visible_in(&self.objects, id) {
+				continue;
+			}
+
+			let mesh = draw_depth(gl, program, &self.objects, id, &self.clip_planes, &shadow_map.light_space, self.time);
+			self.frame_stats.draw(mesh.triangle_count());
+		}
+
+		shadow_map.unbind(gl, canvas_width, canvas_height);
+	}
+
+	/// Draws polylines, creating the polyline renderer on first use.
+	fn render_polylines(&mut self, gl: &GL, width: i32, height: i32) {
+		if self.polylines.is_empty() {
+			return;
+		}
+
+		if self.polyline_renderer.is_none() {
+			match PolylineRenderer::new(gl) {
+				Ok(renderer) => self.polyline_renderer = Some(renderer),
+				Err(e) => {
+					log::error!("{}", e);
+					return;
+				}
+			}
+		}
+
+		for line in self.polylines.values().filter(|line| line.visible && line.points().len() >= 2) {
+			let segments = if line.is_closed { line.points().len() } else { line.points().len() - 1 };
+			self.frame_stats.draw(segments as u32 * 2);
+		}
+
+		if let Some(renderer) = &self.polyline_renderer {
+			renderer.draw(gl, &self.camera, self.polylines.values_mut(), width as f32, height as f32);
+		}
+	}
+
+	/// Records the world position of every object with a trail.
+	fn update_trails(&mut self) {
+		let positions: Vec<(ObjectId, Vec3)> = self.objects
+			.iter()
+			.filter(|(_, obj)| obj.trail.is_some())
+			.map(|(id, _)| (id, world_matrix_of(&self.objects, id).w_axis.truncate()))
+			.collect();
+
+		for (id, position) in positions {
+			if let Some(trail) = self.objects[id].trail.as_mut() {
+				trail.record(position, self.time);
+			}
+		}
+	}
+
+	/// Draws the trails of visible objects, creating the trail pass on
+	/// first use.
+	fn render_trails(&mut self, gl: &GL) {
+		if !self.objects.values().any(|obj| obj.trail.is_some()) {
+			return;
+		}
+
+		if self.trail_pass.is_none() {
+			match TrailPass::new(gl) {
+				Ok(pass) => self.trail_pass = Some(pass),
+				Err(e) => {
+					log::error!("{}", e);
+					return;
+				}
+			}
+		}
+
+		let trails = self.objects
+			.iter()
+			.filter(|&(id, _)| is_visible_in(&self.objects, id))
+			.filter_map(|(_, obj)| obj.trail.as_ref());
+
+		if let Some(pass) = &self.trail_pass {
+			pass.draw(gl, &self.camera, trails, self.time, &mut self.frame_stats);
+		}
+	}
+
+	/// Moves sprites by their velocity, bouncing colliding ones off the
+	/// camera depth of the previous frame.
+	fn update_sprites(&mut self, dt: f32) {
+		if dt <= 0.0 {
+			return;
+		}
+
+		let depth = self.sprite_depth.as_ref();
+
+		for sprite in self.sprites.values_mut().filter(|sprite| sprite.is_moving()) {
+			sprite.advance(dt, depth);
+		}
+	}
+
+	/// Returns whether any visible sprite collides with the scene, needing
+	/// the depth readback.
+	fn has_colliding_sprites(&self) -> bool {
+		self.sprites.values().any(|sprite| sprite.visible && sprite.collision.is_some() && sprite.is_moving())
+	}
+
+	/// Copies the camera depth of this frame to the CPU for colliding
+	/// sprites, creating the readback on first use.
+	fn read_sprite_depth(&mut self, gl: &GL) {
+		if !self.has_colliding_sprites() {
+			self.sprite_depth = None;
+			return;
+		}
+
+		let readback = match self.sprite_depth.take() {
+			Some(readback) => Ok(readback),
+			None => DepthReadback::new(gl),
+		};
+
+		let mut readback = match readback {
+			Ok(readback) => readback,
+			Err(e) => {
+				log::error!("{}", e);
+				return;
+			}
+		};
+
+		if let Some(map) = self.scene_depth_map()
+			&& let Err(e) = readback.read(gl, map, self.camera.view_matrix())
+		{
+			log::error!("{}", e);
+		}
+
+		self.sprite_depth = Some(readback);
+	}
+
+	/// Draws sprites and light halos, creating the sprite renderer on first
+	/// use.
+	fn render_sprites(&mut self, gl: &GL, width: i32, height: i32) {
+		if self.sprites.is_empty() && !self.has_light_halos() {
+			return;
+		}
+
+		if self.sprite_renderer.is_none() {
+			match SpriteRenderer::new(gl) {
+				Ok(renderer) => self.sprite_renderer = Some(renderer),
+				Err(e) => {
+					log::error!("{}", e);
+					return;
+				}
+			}
+		}
+
+		let Some(renderer) = &self.sprite_renderer else {
+			return;
+		};
+
+		let halos: Vec<Sprite> = self.lights.values().filter_map(|light| renderer.halo(light)).collect();
+		let visible = self.sprites.values().filter(|sprite| sprite.visible).count() as u32 + halos.len() as u32;
+		self.frame_stats.draw_calls += visible;
+		self.frame_stats.triangles += visible * 2;
+
+		let sprites = self.sprites.values().chain(&halos);
+		renderer.draw(gl, &self.camera, sprites, self.scene_depth_map(), width as f32, height as f32, self.time);
+	}
+
+	/// Returns whether any enabled light draws a [halo](Light::with_halo).
+	fn has_light_halos(&self) -> bool {
+		self.lights.values().any(|light| light.enabled && light.halo_size().is_some())
+	}
+
+	/// Draws text, creating the text renderer on first use.
+	fn render_texts(&mut self, gl: &GL) {
+		if self.texts.is_empty() {
+			return;
+		}
+
+		if self.text_renderer.is_none() {
+			match TextRenderer::new(gl) {
+				Ok(renderer) => self.text_renderer = Some(renderer),
+				Err(e) => {
+					log::error!("{}", e);
+					return;
+				}
+			}
+		}
+
+		if let Some(renderer) = &self.text_renderer {
+			let (draw_calls, triangles) = renderer.draw(gl, &self.camera, self.texts.values_mut());
+			self.frame_stats.draw_calls += draw_calls;
+			self.frame_stats.triangles += triangles;
+		}
+	}
+
+	/// Selects the detail level of every object with [`Lod`] meshes.
+	fn update_lods(&mut self) {
+		let half_fov = (self.camera.fov_y * 0.5).tan();
+
+		let values: Vec<(ObjectId, f32)> = self.objects
+			.iter()
+			.filter_map(|(id, obj)| {
+				let lod = obj.lod.as_ref()?;
+				let bounds = obj.mesh.bounds().transformed(&world_matrix_of(&self.objects, id));
+				let distance = self.camera.position.distance(bounds.center());
+
+				let value = match lod.metric {
+					LodMetric::Distance => distance,
+					LodMetric::ScreenSize => bounds.size().length() * 0.5 / (distance * half_fov).max(f32::EPSILON),
+				};
+
+				Some((id, value))
+			})
+			.collect();
+
+		for (id, value) in values {
+			if let Some(lod) = self.objects[id].lod.as_mut() {
+				lod.select(value);
+			}
+		}
+	}
+
+	/// Checks if any light in the scene uses contact shadows.
+	fn has_contact_shadow_light(&self) -> bool {
+		self.lights.values().any(|l| l.enabled && l.contact_shadows.is_some())
+	}
+
+	/// Renders the camera depth pre-pass for contact shadows.
+	fn render_contact_pass(&mut self, gl: &GL, canvas_width: i32, canvas_height: i32) {
+		let (map, material) = match (&mut self.contact_shadow_map, &self.shadow_material) {
+			(Some(map), Some(mat)) => (map, mat),
+			_ => return,
+		};
+
+		if let Err(e) = map.fit(gl, canvas_width, canvas_height) {
+			log::error!("{}", e);
+			return;
+		}
+
+		map.view_projection = self.camera.projection_matrix() * self.camera.view_matrix();
+		map.bind(gl);
+
+		gl.enable(GL::DEPTH_TEST);
+
+		let program = material.program();
+		gl.use_program(Some(program));
+
+		if let Some(loc) = gl.get_uniform_location(program, "lightSpace") {
+			gl.uniform_matrix4fv_with_f32_array(
+				Some(&loc), false, &map.view_projection.to_cols_array()
+			);
+		}
+
+		for (id, obj) in &self.objects {
+			if !obj.cast_shadows || obj.distortion.is_some() || !is_visible_in(&self.objects, id) {
+				continue;
+			}
+
+			let mesh = draw_depth(gl, program, &self.objects, id, &self.clip_planes, &map.view_projection, self.time);
+			self.frame_stats.draw(mesh.triangle_count());
+		}
+
+		map.unbind(gl);
+	}
+
+	/// Renders the camera depth pre-pass decals are projected from,
+	/// creating the decal renderer on first use.
+	fn render_decal_depth_pass(&mut self, gl: &GL, width: i32, height: i32) {
+		if let Err(e) = self.ensure_depth_material(gl) {
+			log::error!("{}", e);
+			return;
+		}
+
+		if self.decal_renderer.is_none() {
+			match DecalRenderer::new(gl, width, height) {
+				Ok(renderer) => self.decal_renderer = Some(renderer),
+				Err(e) => {
+					log::error!("{}", e);
+					return;
+				}
+			}
+		}
+
+		let Some(mut renderer) = self.decal_renderer.take() else {
+			return;
+		};
+
+		match renderer.depth_map.fit(gl, width, height) {
+			Ok(()) => self.draw_camera_depth(gl, &mut renderer.depth_map),
+			Err(e) => log::error!("{}", e),
+		}
+
+		self.decal_renderer = Some(renderer);
+	}
+
+	/// Renders the camera depth pre-pass soft sprites and
+	/// [`scene_depth`](Material::scene_depth) materials read, creating the
+	/// depth map on first use.
+	fn render_scene_depth_pass(&mut self, gl: &GL, width: i32, height: i32) {
+		if let Err(e) = self.ensure_depth_material(gl) {
+			log::error!("{}", e);
+			return;
+		}
+
+		let map = match self.depth_map.take() {
+			Some(mut map) => map.fit(gl, width, height).map(|()| map),
+			None => ContactShadowMap::new(gl, width, height),
+		};
+
+		match map {
+			Ok(mut map) => {
+				self.draw_camera_depth(gl, &mut map);
+				self.depth_map = Some(map);
+			}
+			Err(e) => log::error!("{}", e),
+		}
+	}
+
+	/// Returns the camera depth of the current frame for soft sprites and
+	/// [`scene_depth`](Material::scene_depth) materials, shared with the
+	/// decals when they're drawn.
+	fn scene_depth_map(&self) -> Option<&ContactShadowMap> {
+		match &self.decal_renderer {
+			Some(decals) if self.has_visible_decals() => Some(&decals.depth_map),
+			_ => self.depth_map.as_ref().filter(|_| self.needs_scene_depth()),
+		}
+	}
+
+	/// Renders the depth of every visible object, as seen by the camera,
+	/// into a screen-sized map.
+	///
+	/// Objects with [`scene_depth`](Material::scene_depth) materials are
+	/// left out, so they only see what's behind them.
+	fn draw_camera_depth(&mut self, gl: &GL, map: &mut ContactShadowMap) {
+		let Some(material) = &self.shadow_material else {
+			return;
+		};
+
+		map.view_projection = self.camera.projection_matrix() * self.camera.view_matrix();
+		map.bind(gl);
+
+		gl.enable(GL::DEPTH_TEST);
+
+		let program = material.program();
+		gl.use_program(Some(program));
+
+		if let Some(loc) = gl.get_uniform_location(program, "lightSpace") {
+			gl.uniform_matrix4fv_with_f32_array(
+				Some(&loc), false, &map.view_projection.to_cols_array()
+			);
+		}
+
+		let frustum = Frustum::from_matrix(&map.view_projection);
+
+		for (id, obj) in &self.objects {
+			if !is_visible_in(&self.objects, id) || obj.distortion.is_some() || obj.visible_mesh().material.scene_depth {
+				continue;
+			}
+
+			let bounds = obj.visible_mesh().cull_bounds().transformed(&world_matrix_of(&self.objects, id));
+
+			if self.frustum_culling && !frustum.intersects_aabb(&bounds) {
+				continue;
+			}
+
+			let mesh = draw_depth(gl, program, &self.objects, id, &self.clip_planes, &map.view_projection, self.time);
+			self.frame_stats.draw(mesh.triangle_count());
+		}
+
+		map.unbind(gl);
+	}
+
+	/// Returns whether any decal is drawn, needing the decal depth pass.
+	fn has_visible_decals(&self) -> bool {
+		self.decals.values().any(|decal| decal.visible)
+	}
+
+	/// Returns whether any soft or colliding sprite, light halo or
+	/// [`scene_depth`](Material::scene_depth) material is drawn, needing a
+	/// depth pass.
+	fn needs_scene_depth(&self) -> bool {
+		self.sprites.values().any(|sprite| sprite.visible && sprite.is_soft())
+			|| self.has_colliding_sprites()
+			|| self.has_light_halos()
+			|| self.objects.iter().any(|(id, obj)| obj.visible_mesh().material.scene_depth && is_visible_in(&self.objects, id))
+	}
+
+	/// Sets the post-processing effect stack.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use oxgl::common::{PostProcessStack, pp_presets};
+	///
+	/// let mut pp = PostProcessStack::new(&gl, 800, 600)?;
+	/// pp.push(pp_presets::vignette(&gl, 0.8, 0.4));
+	/// pp.push(pp_presets::chromatic_aberration(&gl, 5.0));
+	///
+	/// scene.set_post_process(pp);
+	/// ```
+	pub fn set_post_process(&mut self, stack: PostProcessStack) {
+		self.post_process = Some(stack);
+	}
+
+	/// Chooses between forward and [deferred](super::deferred) shading.
+	///
+	/// Deferred buffers are created on the next render. If they can't be,
+	/// the error is logged and the scene falls back to forward rendering.
+	pub fn set_render_path(&mut self, path: RenderPath) {
+		self.render_path = path;
+
+		if path == RenderPath::Forward {
+			self.deferred = None;
+		}
+	}
+
+	pub fn render_path(&self) -> RenderPath {
+		self.render_path
+	}
+
+	/// Creates or grows the deferred buffers for a view, if the deferred
+	/// path is selected.
+	///
+	/// Returns whether the view should be drawn deferred.
+	fn prepare_deferred(&mut self, gl: &GL, width: i32, height: i32) -> bool {
+		if self.render_path != RenderPath::Deferred {
+			return false;
+		}
+
+		let result = match &mut self.deferred {
+			Some(deferred) => deferred.prepare(gl, width, height),
+			None => DeferredRenderer::new(gl, width, height).map(|deferred| self.deferred = Some(deferred)),
+		};
+
+		if let Err(e) = result {
+			log::error!("Deferred rendering unavailable, using forward rendering: {}", e);
+			self.set_render_path(RenderPath::Forward);
+			return false;
+		}
+
+		true
+	}
+
+	/// Creates the occlusion culler once an object opts in, and starts its
+	/// frame.
+	///
+	/// Returns whether occlusion culling should be applied.
+	fn prepare_occlusion(&mut self, gl: &GL) -> bool {
+		if self.occlusion.is_none() {
+			if !self.objects.values().any(|obj| obj.occlusion_culling) {
+				return false;
+			}
+
+			match OcclusionCuller::new(gl) {
+				Ok(culler) => self.occlusion = Some(culler),
+				Err(e) => {
+					log::error!("{}", e);
+					return false;
+				}
+			}
+		}
+
+		if let Some(culler) = &mut self.occlusion {
+			culler.begin_frame(gl, &self.objects);
+		}
+
+		true
+	}
+
+	/// Draws objects with deferred materials into the G-buffer, shades them
+	/// with every enabled light and resolves the result into the framebuffer
+	/// the view is being drawn to.
+	fn draw_deferred(&mut self, gl: &GL, frustum: &Frustum, lights: &[Light], light_space: Option<Mat4>, occlusion: bool) {
+		let Some(deferred) = &self.deferred else { return };
+
+		// The G-buffer passes rebind, so remember where the view is drawn
+		let framebuffer = gl.get_parameter(GL::FRAMEBUFFER_BINDING).ok()
+			.and_then(|value| value.dyn_into::<WebGlFramebuffer>().ok());
+		let viewport = gl.get_parameter(GL::VIEWPORT).ok()
+			.and_then(|value| value.dyn_into::<js_sys::Int32Array>().ok())
+			.map(|value| value.to_vec())
+			.filter(|value| value.len() == 4);
+		let scissor = gl.is_enabled(GL::SCISSOR_TEST);
+
+		gl.disable(GL::SCISSOR_TEST);
+		deferred.begin_geometry(gl);
+
+		let program = &deferred.geometry_program;
+
+		if let Some(loc) = gl.get_uniform_location(program, "view") {
+			gl.uniform_matrix4fv_with_f32_array(Some(&loc), false, &self.camera.view_matrix().to_cols_array());
+		}
+		if let Some(loc) = gl.get_uniform_location(program, "projection") {
+			gl.uniform_matrix4fv_with_f32_array(Some(&loc), false, &self.camera.projection_matrix().to_cols_array());
+		}
+		if let Some(loc) = gl.get_uniform_location(program, "colorSpace") {
+			gl.uniform1i(Some(&loc), self.color_space.uniform());
+		}
+		if let Some(loc) = gl.get_uniform_location(program, "logDepthFactor") {
+			gl.uniform1f(Some(&loc), self.camera.log_depth_factor());
+		}
+
+		for (id, obj) in &self.objects {
+			let mesh = obj.visible_mesh();
+
+			if !mesh.material.uses_deferred() || obj.distortion.is_some() || !is_visible_in(&self.objects, id) {
+				continue;
+			}
+
+			let world = self.world_matrix(id);
+			let bounds = mesh.cull_bounds().transformed(&world);
+
+			if self.frustum_culling && !frustum.intersects_aabb(&bounds) {
+				continue;
+			}
+
+			if occlusion && obj.occlusion_culling && let Some(culler) = &mut self.occlusion
+				&& !culler.test(gl, id, &bounds, self.camera.position, self.camera.near) {
+				self.frame_stats.occluded_objects += 1;
+				continue;
+			}
+
+			let material = &mesh.material;
+			let color = match material.get("color") {
+				Some(Uniform::Vec3(color)) => *color,
+				Some(Uniform::Vec4(color)) => color.truncate(),
+				_ => Vec3::ONE,
+			};
+			let float = |name: &str, default: f32| match material.get(name) {
+				Some(Uniform::Float(value)) => *value,
+				_ => default,
+			};
+
+			if let Some(loc) = gl.get_uniform_location(program, "model") {
+				gl.uniform_matrix4fv_with_f32_array(Some(&loc), false, &world.to_cols_array());
+			}
+			if let Some(loc) = gl.get_uniform_location(program, "color") {
+				gl.uniform3fv_with_f32_array(Some(&loc), &color.to_array());
+			}
+			if let Some(loc) = gl.get_uniform_location(program, "ambient") {
+				gl.uniform1f(Some(&loc), float("ambient", 0.0));
+			}
+			if let Some(loc) = gl.get_uniform_location(program, "shininess") {
+				gl.uniform1f(Some(&loc), float("shininess", 32.0));
+			}
+			if let Some(loc) = gl.get_uniform_location(program, "specularStrength") {
+				gl.uniform1f(Some(&loc), float("specularStrength", 0.0));
+			}
+			if let Some(loc) = gl.get_uniform_location(program, "doubleSided") {
+				gl.uniform1i(Some(&loc), material.double_sided as i32);
+			}
+			if let Some(loc) = gl.get_uniform_location(program, "flatShading") {
+				gl.uniform1i(Some(&loc), material.flat_shading as i32);
+			}
+			if let Some(loc) = gl.get_uniform_location(program, "receiveShadows") {
+				gl.uniform1f(Some(&loc), if obj.receive_shadows { 1.0 } else { 0.0 });
+			}
+
+			let culling = material.double_sided && gl.is_enabled(GL::CULL_FACE);
+
+			if culling {
+				gl.disable(GL::CULL_FACE);
+			}
+
+			apply_clip_planes(gl, program, &self.clip_planes, &material.clip_planes);
+			mesh.draw_depth_only(gl, program);
+
+			if culling {
+				gl.enable(GL::CULL_FACE);
+			}
+			self.frame_stats.draw(mesh.triangle_count());
+			self.frame_stats.visible_objects += 1;
+		}
+
+		let drawn = deferred.light(gl, &LightPass {
+			camera: &self.camera,
+			lights,
+			shadow: light_space,
+			shadow_settings: self.shadow_settings,
+			shadow_map_size: self.shadow_map.as_ref().map_or(SHADOW_MAP_SIZE, |map| map.size),
+			color_space: self.color_space.uniform(),
+		});
+
+		gl.bind_framebuffer(GL::FRAMEBUFFER, framebuffer.as_ref());
+		if let Some(viewport) = viewport {
+			gl.viewport(viewport[0], viewport[1], viewport[2], viewport[3]);
+		}
+		if scissor {
+			gl.enable(GL::SCISSOR_TEST);
+		}
+
+		let fog = self.fog.map(|fog| fog.uniforms()).unwrap_or((0, [0.0; 3]));
+		deferred.resolve(gl, &self.camera, self.color_space.uniform(), fog, self.fog_color);
+
+		// One quad per light and the resolve
+		self.frame_stats.draw_calls += drawn + 1;
+	}
+
+	/// Renders the scene.
+	///
+	/// Executes the full rendering pipeline:
+	/// 1. Applies value [`bindings`](Self::bindings) and advances the `time`
+	///    and `deltaTime` uniforms
+	/// 2. Binds post-process framebuffer (if enabled)
+	/// 3. Clears color and depth buffers
+	/// 4. Updates the camera from the [`panorama`](Self::panorama) (if set)
+	/// 5. Selects [`Lod`] levels and renders shadow and contact shadow depth
+	///    passes (if enabled)
+	/// 6. Draws the panorama, renders all visible objects with lighting, then
+	///    draws [`polylines`](Self::polylines), [`sprites`](Self::sprites)
+	///    and [`texts`](Self::texts)
+	/// 7. Applies post-processing effects (if enabled)
+	///
+	/// # Examples
+	///
+	/// ```
+	/// // In your render loop
+	/// scene.render(&renderer, elapsed_time);
+	/// ```
+	pub fn render(&mut self, renderer: &Renderer, time: f32) {
+		let gl = &renderer.gl;
+		let canvas = renderer.surface();
+		let width = canvas.width() as i32;
+		let height = canvas.height() as i32;
+
+		self.color_space = renderer.color_space();
+		self.screen_size = Vec2::new(width as f32, height as f32);
+		self.begin_frame(time);
+		self.size_depth_maps(gl, width, height);
+		self.update_scene_textures(renderer);
+		self.update_reflection_probes(renderer);
+
+		if let Some(pp) = &self.post_process {
+			pp.begin(gl);
+		} else {
+			gl.bind_framebuffer(GL::FRAMEBUFFER, None);
+			gl.viewport(0, 0, width, height);
+		}
+
+		let state = self.apply_render_state(renderer);
+		let [r, g, b, a] = state.clear_color.map_or(self.clear_color(), |color| color.to_array());
+		gl.clear_color(r, g, b, a);
+		gl.clear(GL::COLOR_BUFFER_BIT | GL::DEPTH_BUFFER_BIT);
+
+		if let Some(panorama) = &mut self.panorama {
+			panorama.update_camera(&mut self.camera);
+		}
+
+		let (shadows_active, contact_active) = self.render_depth_passes(gl, width, height);
+		self.read_sprite_depth(gl);
+
+		if (shadows_active || contact_active || self.has_visible_decals() || self.needs_scene_depth()) && let Some(pp) = &self.post_process {
+			pp.begin(gl);
+		}
+
+		self.draw_view(gl, width, height, shadows_active, contact_active, true);
+
+		if let Some(pp) = &self.post_process && pp.begin_emissive(gl) {
+			self.draw_emissive(gl);
+		}
+
+		if let Some(pp) = &self.post_process && pp.begin_distortion(gl) {
+			self.draw_distortion(gl);
+		}
+
+		if let Some(pp) = &mut self.post_process {
+			pp.set_camera(&self.camera);
+			pp.update_lights(&self.lights);
+			pp.end(gl, time);
+		}
+
+		self.finish_stats();
+	}
+
+	/// Sets what is drawn behind the objects.
+	///
+	/// A [`panorama`](Self::panorama) takes the place of the background
+	/// while set, and fog replaces a solid color with the fog color so
+	/// distant objects blend into it.
+	///
+	/// # Examples
+	///
+	/// ```ignore
+	/// use oxgl::renderer_3d::Background;
+	/// use oxgl::core::Color;
+	///
+	/// scene.set_background(Background::Color(Color::from_hex("#87CEEB").unwrap()));
+	/// ```
+	pub fn set_background(&mut self, background: Background) {
+		self.background = background;
+	}
+
+	/// Returns the premultiplied color the canvas is cleared to each frame.
+	pub(crate) fn clear_color(&self) -> [f32; 4] {
+		match (&self.background, self.fog) {
+			(Background::Color(_), Some(_)) => [self.fog_color.x, self.fog_color.y, self.fog_color.z, 1.0],
+			(background, _) => background.clear_color(),
+		}
+	}
+
+	/// Returns counters for the last frame rendered with
+	/// [`render`](Self::render) or [`render_viewports`](Self::render_viewports).
+	pub fn stats(&self) -> &RenderStats {
+		&self.stats
+	}
+
+	/// Returns the time in seconds passed to the last rendered frame.
+	///
+	/// Uploaded to every material as the `time` uniform.
+	pub fn time(&self) -> f32 {
+		self.time
+	}
+
+	/// Returns the seconds between the last two rendered frames.
+	///
+	/// Uploaded to every material as the `deltaTime` uniform.
+	pub fn delta_time(&self) -> f32 {
+		self.delta_time
+	}
+
+	/// Advances the frame time, reports outline changes and events, and
+	/// applies [`bindings`](Self::bindings) before a frame is drawn.
+	pub(crate) fn begin_frame(&mut self, time: f32) {
+		self.delta_time = if self.time > 0.0 { (time - self.time).max(0.0) } else { 0.0 };
+		self.time = time;
+
+		self.update_active_camera(self.delta_time);
+		self.notify_outline_listeners();
+		self.notify_event_listeners();
+		self.step_physics(self.delta_time);
+		self.bindings.apply(&mut self.objects, &mut self.lights, self.post_process.as_mut());
+		self.update_trails();
+		self.update_sprites(self.delta_time);
+	}
+
+	/// Publishes the counters gathered since the previous frame and starts
+	/// new ones.
+	pub(crate) fn finish_stats(&mut self) {
+		let mut stats = std::mem::take(&mut self.frame_stats);
+
+		stats.objects = self.objects.len();
+		stats.lights = self.lights.values().filter(|light| light.enabled).count();
+		stats.texture_bytes = self.estimate_texture_bytes();
+
+		self.stats = stats;
+	}
+
+	/// Estimates GPU texture memory, counting shared textures once and
+	/// depth and color buffers at 4 bytes per pixel.
+	fn estimate_texture_bytes(&self) -> usize {
+		let mut seen = Vec::new();
+		let mut bytes = 0;
+
+		let materials = self.objects.values().flat_map(|obj| {
+			let levels = obj.lod.iter().flat_map(|lod| lod.levels().iter().map(|level| &level.mesh));
+			std::iter::once(&obj.mesh).chain(levels).map(|mesh| &mesh.material)
+		});
+		let textures = materials
+			.flat_map(|material| material.textures())
+			.chain(self.sprites.values().filter_map(|sprite| sprite.texture.as_ref()))
+			.chain(self.texts.values().map(|text| &text.font().texture));
+
+		for texture in textures {
+			if !seen.contains(texture.texture()) {
+				seen.push(texture.texture().clone());
+				bytes += texture.width() as usize * texture.height() as usize * 4;
+			}
+		}
+
+		if let Some(map) = &self.shadow_map {
+			bytes += map.size as usize * map.size as usize * 4;
+		}
+
+		if let Some(map) = &self.contact_shadow_map {
+			bytes += map.width as usize * map.height as usize * 4;
+		}
+
+		if let Some(renderer) = &self.decal_renderer {
+			bytes += renderer.depth_map.width as usize * renderer.depth_map.height as usize * 4;
+		}
+
+		if let Some(map) = &self.depth_map {
+			bytes += map.width as usize * map.height as usize * 4;
+		}
+
+		// Six color faces and a depth buffer
+		for probe in self.reflection_probes.values() {
+			let size = probe.cubemap().size() as usize;
+			bytes += size * size * 4 * 7;
+		}
+
+		// Scene color and depth plus two ping-pong buffers
+		if let Some(pp) = &self.post_process {
+			bytes += pp.width() as usize * pp.height() as usize * 4 * 5;
+		}
+
+		if let Some(deferred) = &self.deferred {
+			bytes += deferred.texture_bytes();
+		}
+
+		bytes
+	}
+
+	/// Renders the scene from another camera into an offscreen target.
+	///
+	/// Shadows, contact shadows, level of detail and culling follow the
+	/// given camera; post-processing is not applied. Call this before
+	/// [`render`](Self::render) so the target's texture is up to date when
+	/// materials sample it. An object must not sample a target it is being
+	/// rendered into, so hide mirrors and screens from their own cameras.
+	/// [`SceneTexture`] does this every few frames, hiding them itself.
+	///
+	/// # Examples
+	///
+	/// ```ignore
+	/// use oxgl::common::RenderTarget;
+	///
+	/// let target = RenderTarget::new(&gl, 512, 512)?;
+	/// screen_material.set_texture("screenTexture", target.texture().clone());
+	///
+	/// // Each frame
+	/// scene.render_to_target(&renderer, &target, &security_camera);
+	/// scene.render(&renderer, time);
+	/// ```
+	pub fn render_to_target(&mut self, renderer: &Renderer, target: &RenderTarget, camera: &Camera) {
+		let gl = &renderer.gl;
+		let main_camera = std::mem::replace(&mut self.camera, camera.clone());
+		let (width, height) = (target.width(), target.height());
+
+		self.color_space = renderer.color_space();
+		self.camera.aspect = width as f32 / height as f32;
+
+		self.apply_render_state(renderer);
+		gl.disable(GL::SCISSOR_TEST);
+
+		let (shadows_active, contact_active) = self.render_depth_passes(gl, width, height);
+
+		target.bind(gl);
+		self.draw_view(gl, width, height, shadows_active, contact_active, false);
+
+		let canvas = renderer.surface();
+		target.unbind(gl, canvas.width() as i32, canvas.height() as i32);
+
+		self.camera = main_camera;
+	}
+
+	/// Renders the scene once per viewport, each from its own camera into
+	/// its own rectangle of the canvas.
+	///
+	/// Used instead of [`render`](Self::render) for split-screen and
+	/// multi-view layouts. Shadows, level of detail and culling are
+	/// evaluated per viewport. Drawing is scissored to each rectangle, and
+	/// a viewport's post-processing only affects its own rectangle. The
+	/// scene's own [`camera`](Self::camera) and post-processing are unused.
+	///
+	/// # Examples
+	///
+	/// ```ignore
+	/// use oxgl::renderer_3d::{Viewport, ViewportRect};
+	///
+	/// // Editor quad view
+	/// let mut viewports = vec![
+	///		Viewport::new(top, ViewportRect::grid(2, 2, 0, 0)),
+	///		Viewport::new(front, ViewportRect::grid(2, 2, 1, 0)),
+	///		Viewport::new(side, ViewportRect::grid(2, 2, 0, 1)),
+	///		Viewport::new(perspective, ViewportRect::grid(2, 2, 1, 1)),
+	/// ];
+	///
+	/// scene.render_viewports(&renderer, &mut viewports, time);
+	/// ```
+	pub fn render_viewports(&mut self, renderer: &Renderer, viewports: &mut [Viewport], time: f32) {
+		let gl = &renderer.gl;
+		let canvas = renderer.surface();
+		let canvas_width = canvas.width() as i32;
+		let canvas_height = canvas.height() as i32;
+
+		self.color_space = renderer.color_space();
+		self.screen_size = Vec2::new(canvas_width as f32, canvas_height as f32);
+		self.begin_frame(time);
+
+		let (width, height) = viewports.iter()
+			.filter(|v| v.visible)
+			.map(|v| v.rect.to_pixels(canvas_width, canvas_height))
+			.fold((0, 0), |(w, h), (_, _, width, height)| (w.max(width), h.max(height)));
+
+		self.size_depth_maps(gl, width, height);
+		self.update_scene_textures(renderer);
+		self.update_reflection_probes(renderer);
+
+		gl.bind_framebuffer(GL::FRAMEBUFFER, None);
+
+		let state = self.apply_render_state(renderer);
+		gl.disable(GL::SCISSOR_TEST);
+
+		let [r, g, b, a] = state.clear_color.map_or(self.clear_color(), |color| color.to_array());
+		gl.clear_color(r, g, b, a);
+		gl.clear(GL::COLOR_BUFFER_BIT | GL::DEPTH_BUFFER_BIT);
+
+		for viewport in viewports.iter_mut().filter(|v| v.visible) {
+			let (x, y, width, height) = viewport.rect.to_pixels(canvas_width, canvas_height);
+
+			if width <= 0 || height <= 0 {
+				continue;
+			}
+
+			let mut camera = viewport.camera.clone();
+			camera.aspect = width as f32 / height as f32;
+			let main_camera = std::mem::replace(&mut self.camera, camera);
+
+			let (shadows_active, contact_active) = self.render_depth_passes(gl, width, height);
+			let [r, g, b, a] = viewport.clear_color;
+			gl.clear_color(r, g, b, a);
+
+			match viewport.post_process.as_mut().filter(|pp| pp.enabled) {
+				Some(pp) => {
+					if (pp.width(), pp.height()) != (width, height) {
+						pp.resize(gl, width, height);
+					}
+
+					pp.begin(gl);
+				}
+				None => {
+					gl.bind_framebuffer(GL::FRAMEBUFFER, None);
+					gl.viewport(x, y, width, height);
+					gl.enable(GL::SCISSOR_TEST);
+					gl.scissor(x, y, width, height);
+					gl.clear(GL::COLOR_BUFFER_BIT | GL::DEPTH_BUFFER_BIT);
+				}
+			}
+
+			self.draw_view(gl, width, height, shadows_active, contact_active, false);
+			gl.disable(GL::SCISSOR_TEST);
+
+			if let Some(pp) = &viewport.post_process && pp.begin_emissive(gl) {
+				self.draw_emissive(gl);
+			}
+
+			if let Some(pp) = &viewport.post_process && pp.begin_distortion(gl) {
+				self.draw_distortion(gl);
+			}
+
+			if let Some(pp) = viewport.post_process.as_mut().filter(|pp| pp.enabled) {
+				pp.set_camera(&self.camera);
+				pp.update_lights(&self.lights);
+				pp.end_at(gl, time, x, y);
+			}
+
+			self.camera = main_camera;
+		}
+
+		gl.viewport(0, 0, canvas_width, canvas_height);
+
+		self.finish_stats();
+	}
+
+	/// Renders the scene from a camera into a region of a framebuffer,
+	/// without clearing it.
+	///
+	/// Used for WebXR, where every eye is a viewport of one framebuffer
+	/// provided by the browser. `None` draws to the canvas. Shadows and
+	/// level of detail follow the given camera; post-processing is not
+	/// applied.
+	pub fn render_into(&mut self, gl: &GL, framebuffer: Option<&WebGlFramebuffer>, viewport: [i32; 4], camera: &Camera) {
+		let [x, y, width, height] = viewport;
+
+		if width <= 0 || height <= 0 {
+			return;
+		}
+
+		let main_camera = std::mem::replace(&mut self.camera, camera.clone());
+		let (shadows_active, contact_active) = self.render_depth_passes(gl, width, height);
+
+		gl.bind_framebuffer(GL::FRAMEBUFFER, framebuffer);
+		gl.viewport(x, y, width, height);
+		self.draw_view(gl, width, height, shadows_active, contact_active, false);
+
+		self.camera = main_camera;
+	}
+
+	/// Renders the scene textures that are due, hiding the objects that
+	/// show each one while it renders.
+	fn update_scene_textures(&mut self, renderer: &Renderer) {
+		if self.scene_textures.is_empty() {
+			return;
+		}
+
+		let gl = &renderer.gl;
+		let mut textures = std::mem::take(&mut self.scene_textures);
+
+		for scene_texture in textures.values_mut() {
+			if !scene_texture.tick() {
+				continue;
+			}
+
+			let texture = scene_texture.texture();
+			let screens: Vec<ObjectId> = self.objects.iter()
+				.filter(|(_, obj)| obj.visible && obj.visible_mesh().material.textures().any(|t| t.texture() == texture.texture()))
+				.map(|(id, _)| id)
+				.collect();
+
+			for &id in &screens {
+				self.objects[id].visible = false;
+			}
+
+			let camera = scene_texture.view_camera(&self.camera);
+
+			if let Some(plane) = scene_texture.mirror {
+				self.clip_planes.push(plane);
+			}
+
+			self.render_to_target(renderer, scene_texture.target(), &camera);
+
+			if scene_texture.mirror.is_some() {
+				self.clip_planes.pop();
+			}
+
+			let view_projection = camera.projection_matrix() * camera.view_matrix();
+
+			for &id in &screens {
+				let obj = &mut self.objects[id];
+				obj.visible = true;
+
+				if scene_texture.mirror.is_some() {
+					obj.mesh.material.set("reflectionViewProjection", Uniform::Mat4(view_projection));
+				}
+			}
+
+			if texture.sampler().mipmap_filter.is_some() {
+				texture.generate_mipmaps(gl);
+			}
+		}
+
+		self.scene_textures = textures;
+	}
+
+	/// Renders the reflection probes that are due into their cube maps.
+	fn update_reflection_probes(&mut self, renderer: &Renderer) {
+		if !self.reflection_probes.values().any(|probe| probe.is_due(self.time)) {
+			return;
+		}
+
+		let gl = &renderer.gl;
+		let canvas = renderer.surface();
+
+		// Taken out while capturing, so no object samples a cube map that
+		// is being drawn into
+		let mut probes = std::mem::take(&mut self.reflection_probes);
+		let main_camera = self.camera.clone();
+		let time = self.time;
+
+		let state = self.apply_render_state(renderer);
+		let clear_color = state.clear_color.map_or(self.clear_color(), |color| color.to_array());
+		gl.disable(GL::SCISSOR_TEST);
+
+		for probe in probes.values_mut().filter(|probe| probe.is_due(time)) {
+			let size = probe.cubemap().size() as i32;
+
+			for face in 0..6 {
+				self.camera = probe.face_camera(face);
+
+				let (shadows_active, contact_active) = self.render_depth_passes(gl, size, size);
+
+				probe.bind_face(gl, face, clear_color);
+				self.draw_view(gl, size, size, shadows_active, contact_active, false);
+			}
+
+			probe.mark_updated(time);
+		}
+
+		self.camera = main_camera;
+		self.reflection_probes = probes;
+
+		gl.bind_framebuffer(GL::FRAMEBUFFER, None);
+		gl.viewport(0, 0, canvas.width() as i32, canvas.height() as i32);
+	}
+
+	/// Sizes the camera depth maps to the largest view rendered this frame:
+	/// `width` by `height`, a scene texture or a reflection probe face.
+	///
+	/// Smaller views draw into a corner of the maps, so they aren't
+	/// reallocated per view. Views rendered outside a frame grow them if
+	/// needed.
+	fn size_depth_maps(&mut self, gl: &GL, width: i32, height: i32) {
+		let targets = self.scene_textures.values()
+			.filter(|texture| texture.enabled)
+			.map(|texture| (texture.target().width(), texture.target().height()))
+			.chain(self.reflection_probes.values().map(|probe| {
+				let size = probe.cubemap().size() as i32;
+				(size, size)
+			}));
+		let (width, height) = targets.fold((width, height), |(w, h), (width, height)| (w.max(width), h.max(height)));
+
+		if width <= 0 || height <= 0 {
+			return;
+		}
+
+		let maps = [
+			self.contact_shadow_map.as_mut(),
+			self.decal_renderer.as_mut().map(|renderer| &mut renderer.depth_map),
+			self.depth_map.as_mut(),
+		];
+
+		for map in maps.into_iter().flatten() {
+			if let Err(e) = map.resize(gl, width, height) {
+				log::error!("{}", e);
+			}
+		}
+	}
+
+	/// Applies the scene's render state, or the renderer's if it has none,
+	/// and returns it.
+	fn apply_render_state(&self, renderer: &Renderer) -> RenderState {
+		let state = self.render_state.unwrap_or_else(|| renderer.render_state());
+
+		state.apply(&renderer.gl);
+		state
+	}
+
+	/// Selects [`Lod`] levels and renders the shadow and contact shadow
+	/// depth passes that are active.
+	///
+	/// Returns whether shadows and contact shadows are active.
+	fn render_depth_passes(&mut self, gl: &GL, width: i32, height: i32) -> (bool, bool) {
+		let shadows_active = self.shadows_enabled && self.has_shadow_casting_light();
+		let contact_active = self.contact_shadows_enabled && self.has_contact_shadow_light();
+
+		self.update_lods();
+
+		// A render state scissor is in canvas pixels and would crop the maps
+		let scissor = gl.is_enabled(GL::SCISSOR_TEST);
+		gl.disable(GL::SCISSOR_TEST);
+
+		if shadows_active {
+			self.render_shadow_pass(gl, width, height);
+		}
+
+		if contact_active {
+			self.render_contact_pass(gl, width, height);
+		}
+
+		if self.has_visible_decals() {
+			self.render_decal_depth_pass(gl, width, height);
+		} else if self.needs_scene_depth() {
+			self.render_scene_depth_pass(gl, width, height);
+		}
+
+		if scissor {
+			gl.enable(GL::SCISSOR_TEST);
+		}
+
+		(shadows_active, contact_active)
+	}
+
+	/// Draws the panorama, objects, polylines, sprites and text into the bound
+	/// framebuffer.
+	///
+	/// Occlusion results are kept per object, so only the main camera's
+	/// view uses them.
+	fn draw_view(&mut self, gl: &GL, width: i32, height: i32, shadows_active: bool, contact_active: bool, occlusion: bool) {
+		gl.enable(GL::DEPTH_TEST);
+
+		if let Some(panorama) = &self.panorama {
+			panorama.draw(gl, &self.camera);
+		} else if let Background::Skybox(skybox) = &self.background {
+			skybox.draw(gl, &self.camera);
+		}
+		
+		let lights: Vec<Light> = self.lights.values().cloned().collect();
+		
+		let light_space = if shadows_active {
+			self.shadow_map.as_ref()
+				.map(|sm| {
+					sm.bind_texture(gl, 0);
+					sm.light_space
+				})
+				.unwrap_or(Mat4::IDENTITY)
+		} else {
+			Mat4::IDENTITY
+		};
+
+		let (contact_view_projection, contact_uv_scale) = match &self.contact_shadow_map {
+			Some(map) if contact_active => {
+				map.bind_texture(gl, 1);
+				(map.view_projection, map.uv_scale())
+			}
+			_ => (Mat4::IDENTITY, Vec2::ONE),
+		};
+
+		let scene_depth = self.scene_depth_map().map(|map| {
+			map.bind_texture(gl, SCENE_DEPTH_UNIT);
+			[map.width as f32, map.height as f32]
+		});
+		// The depth map covers the viewport, which may not start at the corner
+		let [viewport_x, viewport_y, _, _] = current_viewport(gl);
+		let projection = self.camera.projection_matrix();
+		let depth_projection = [projection.z_axis.z, projection.w_axis.z];
+
+		let frustum = Frustum::from_matrix(&(self.camera.projection_matrix() * self.camera.view_matrix()));
+		let (fog_mode, fog_params) = self.fog.map(|fog| fog.uniforms()).unwrap_or((0, [0.0; 3]));
+		let shadow_map_size = self.shadow_map.as_ref().map_or(SHADOW_MAP_SIZE, |map| map.size);
+
+		let deferred_active = self.prepare_deferred(gl, width, height);
+		let occlusion_active = occlusion && self.prepare_occlusion(gl);
+
+		if deferred_active {
+			self.draw_deferred(gl, &frustum, &lights, shadows_active.then_some(light_space), occlusion_active);
+		}
+
+		for (id, obj) in &self.objects {
+			if !is_visible_in(&self.objects, id) || obj.distortion.is_some() {
+				continue;
+			}
+
+			let world = self.world_matrix(id);
+
+			let mesh = obj.visible_mesh();
+
+			if deferred_active && mesh.material.uses_deferred() {
+				continue;
+			}
+
+			let bounds = mesh.cull_bounds().transformed(&world);
+
+			if self.frustum_culling && !frustum.intersects_aabb(&bounds) {
+				continue;
+			}
+
+			if occlusion_active && obj.occlusion_culling && let Some(culler) = &mut self.occlusion
+				&& !culler.test(gl, id, &bounds, self.camera.position, self.camera.near) {
+				self.frame_stats.occluded_objects += 1;
+				continue;
+			}
+
+			let program = mesh.material.program();
+
+			gl.use_program(Some(program));
+			
+			if let Some(loc) = gl.get_uniform_location(program, "shadowsEnabled") {
+				gl.uniform1i(Some(&loc), if shadows_active && obj.receive_shadows { 1 } else { 0 });
+			}
+
+			if shadows_active {
+				if let Some(loc) = gl.get_uniform_location(program, "lightSpace") {
+					gl.uniform_matrix4fv_with_f32_array(
+						Some(&loc), false, &light_space.to_cols_array()
+					);
+				}
+				if let Some(loc) = gl.get_uniform_location(program, "shadowMap") {
+					gl.uniform1i(Some(&loc), 0);
+				}
+
+				self.shadow_settings.apply(gl, program, shadow_map_size);
+			}
+
+			apply_clip_planes(gl, program, &self.clip_planes, &mesh.material.clip_planes);
+
+			let probe = self.reflection_probes.values()
+				.filter(|probe| probe.enabled && probe.bounds.contains(bounds.center()))
+				.min_by(|a, b| a.volume().total_cmp(&b.volume()));
+
+			apply_probe(gl, program, probe);
+
+			if let Some(loc) = gl.get_uniform_location(program, "time") {
+				gl.uniform1f(Some(&loc), self.time);
+			}
+			if let Some(loc) = gl.get_uniform_location(program, "deltaTime") {
+				gl.uniform1f(Some(&loc), self.delta_time);
+			}
+
+			if let Some(loc) = gl.get_uniform_location(program, "colorSpace") {
+				gl.uniform1i(Some(&loc), self.color_space.uniform());
+			}
+
+			if let Some(loc) = gl.get_uniform_location(program, "fogMode") {
+				gl.uniform1i(Some(&loc), fog_mode);
+			}
+
+			if fog_mode != 0 {
+				if let Some(loc) = gl.get_uniform_location(program, "fogColor") {
+					gl.uniform3fv_with_f32_array(Some(&loc), &self.fog_color.to_array());
+				}
+				if let Some(loc) = gl.get_uniform_location(program, "fogParams") {
+					gl.uniform3fv_with_f32_array(Some(&loc), &fog_params);
+				}
+			}
+
+			if let Some(loc) = gl.get_uniform_location(program, "contactShadowsEnabled") {
+				gl.uniform1i(Some(&loc), if contact_active && obj.receive_shadows { 1 } else { 0 });
+			}
+
+			if contact_active {
+				if let Some(loc) = gl.get_uniform_location(program, "contactDepthMap") {
+					gl.uniform1i(Some(&loc), 1);
+				}
+				if let Some(loc) = gl.get_uniform_location(program, "contactUvScale") {
+					gl.uniform2fv_with_f32_array(Some(&loc), &contact_uv_scale.to_array());
+				}
+				if let Some(loc) = gl.get_uniform_location(program, "contactBias") {
+					gl.uniform1f(Some(&loc), self.shadow_settings.contact_bias);
+				}
+				if let Some(loc) = gl.get_uniform_location(program, "cameraViewProjection") {
+					gl.uniform_matrix4fv_with_f32_array(
+						Some(&loc), false, &contact_view_projection.to_cols_array()
+					);
+				}
+				if let Some(loc) = gl.get_uniform_location(program, "cameraNear") {
+					gl.uniform1f(Some(&loc), self.camera.near);
+				}
+				if let Some(loc) = gl.get_uniform_location(program, "cameraFar") {
+					gl.uniform1f(Some(&loc), self.camera.far);
+				}
+			}
+
+			if mesh.material.scene_depth {
+				if let Some(loc) = gl.get_uniform_location(program, "sceneDepthEnabled") {
+					gl.uniform1i(Some(&loc), scene_depth.is_some() as i32);
+				}
+
+				if let Some(resolution) = scene_depth {
+					if let Some(loc) = gl.get_uniform_location(program, "sceneDepth") {
+						gl.uniform1i(Some(&loc), SCENE_DEPTH_UNIT as i32);
+					}
+					if let Some(loc) = gl.get_uniform_location(program, "sceneResolution") {
+						gl.uniform2fv_with_f32_array(Some(&loc), &resolution);
+					}
+					if let Some(loc) = gl.get_uniform_location(program, "sceneViewportOrigin") {
+						gl.uniform2f(Some(&loc), viewport_x as f32, viewport_y as f32);
+					}
+					if let Some(loc) = gl.get_uniform_location(program, "sceneDepthProjection") {
+						gl.uniform2fv_with_f32_array(Some(&loc), &depth_projection);
+					}
+				}
+			}
+			
+			mesh.draw_with_matrix(gl, &world, &self.camera, &lights);
+			self.frame_stats.draw(mesh.triangle_count());
+			self.frame_stats.visible_objects += 1;
+		}
+
+		if occlusion_active && let Some(culler) = &mut self.occlusion {
+			self.frame_stats.occlusion_queries += culler.flush(gl, &self.camera);
+		}
+
+		if self.has_visible_decals() && let Some(renderer) = &self.decal_renderer {
+			renderer.draw(gl, &self.camera, self.decals.values(), width as f32, height as f32);
+		}
+
+		self.render_polylines(gl, width, height);
+		self.render_trails(gl);
+		self.render_sprites(gl, width, height);
+		self.render_texts(gl);
+	}
+
+	/// Draws the emission of visible emissive objects into the bound
+	/// framebuffer, depth tested against the view already drawn.
+	fn draw_emissive(&self, gl: &GL) {
+		let lights: Vec<Light> = Vec::new();
+		let frustum = Frustum::from_matrix(&(self.camera.projection_matrix() * self.camera.view_matrix()));
+		let depth_func = current_depth_func(gl);
+		let state = SavedState::capture(gl);
+
+		gl.enable(GL::DEPTH_TEST);
+		gl.depth_func(GL::LEQUAL);
+		gl.depth_mask(false);
+
+		for (id, obj) in &self.objects {
+			let mesh = obj.visible_mesh();
+
+			if !mesh.material.is_emissive() || obj.distortion.is_some() || !is_visible_in(&self.objects, id) {
+				continue;
+			}
+
+			let world = self.world_matrix(id);
+
+			if self.frustum_culling && !frustum.intersects_aabb(&mesh.cull_bounds().transformed(&world)) {
+				continue;
+			}
+
+			let program = mesh.material.program();
+			let pass_loc = gl.get_uniform_location(program, "emissivePass");
+
+			gl.use_program(Some(program));
+			gl.uniform1i(pass_loc.as_ref(), 1);
+
+			if let Some(loc) = gl.get_uniform_location(program, "colorSpace") {
+				gl.uniform1i(Some(&loc), self.color_space.uniform());
+			}
+
+			apply_clip_planes(gl, program, &self.clip_planes, &mesh.material.clip_planes);
+			mesh.draw_with_matrix(gl, &world, &self.camera, &lights);
+
+			gl.uniform1i(pass_loc.as_ref(), 0);
+		}
+
+		state.restore(gl);
+		gl.depth_func(depth_func);
+	}
+
+	/// Draws the masks of visible distorters into the bound framebuffer,
+	/// depth tested against the view already drawn.
+	fn draw_distortion(&self, gl: &GL) {
+		let lights: Vec<Light> = Vec::new();
+		let frustum = Frustum::from_matrix(&(self.camera.projection_matrix() * self.camera.view_matrix()));
+		let depth_func = current_depth_func(gl);
+		let state = SavedState::capture(gl);
+
+		gl.enable(GL::DEPTH_TEST);
+		gl.depth_func(GL::LEQUAL);
+		gl.depth_mask(false);
+
+		for (id, obj) in &self.objects {
+			let Some(material) = &obj.distortion else {
+				continue;
+			};
+
+			if !is_visible_in(&self.objects, id) {
+				continue;
+			}
+
+			let mesh = obj.visible_mesh();
+			let world = self.world_matrix(id);
+
+			if self.frustum_culling && !frustum.intersects_aabb(&mesh.cull_bounds().transformed(&world)) {
+				continue;
+			}
+
+			let program = material.program();
+			gl.use_program(Some(program));
+
+			if let Some(loc) = gl.get_uniform_location(program, "time") {
+				gl.uniform1f(Some(&loc), self.time);
+			}
+
+			apply_clip_planes(gl, program, &self.clip_planes, &material.clip_planes);
+			mesh.draw_with_material(gl, &world, &self.camera, &lights, material);
+		}
+
+		state.restore(gl);
+		gl.depth_func(depth_func);
+	}
+
+	/// Renders debug visualization gizmos.
+	///
+	/// Draws wireframe debug primitives based on the provided settings.
+	/// Should be called after [`render`](Self::render) for proper layering.
+	///
+	/// All gizmos are drawn in a single batched draw call. If `gizmos` is
+	/// already batching, they are queued into the caller's batch instead and
+	/// drawn with whatever depth state is active when the caller flushes.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use oxgl::renderer_3d::{GizmoRenderer, DebugSettings};
+	///
+	/// let gizmos = GizmoRenderer::new(&gl);
+	/// let settings = DebugSettings {
+	///		show_grid: true,
+	///		show_axes: true,
+	///		show_light_gizmos: true,
+	///		..Default::default()
+	/// };
+	///
+	/// scene.render(&renderer, time);
+	/// scene.render_debug(&renderer, &gizmos, &settings, true);
+	/// ```
+	pub fn render_debug(&self, renderer: &Renderer, gizmos: &GizmoRenderer, settings: &DebugSettings, disable_depth: bool) {
+		let gl = &renderer.gl;
+
+		if disable_depth {
+			gl.disable(GL::DEPTH_TEST);
+		}
+
+		// Draw everything in one call unless the caller is already batching
+		let owns_batch = !gizmos.is_batching();
+
+		if owns_batch {
+			gizmos.begin();
+		}
+
+		let previous_style = gizmos.set_style(settings.gizmo_style);
+
+		if settings.show_grid && settings.infinite_grid {
+			let divisions = settings.grid_divisions.max(1);
+
+			gizmos.infinite_grid(
+				gl,
+				&self.camera,
+				settings.grid_size / divisions as f32,
+				divisions,
+				settings.grid_fade_distance,
+				Vec3::new(0.5, 0.5, 0.5)
+			);
+		} else if settings.show_grid {
+			gizmos.grid(
+				gl, 
+				&self.camera, 
 				settings.grid_size, 
 				settings.grid_divisions, 
 				Vec3::new(0.3, 0.3, 0.3)
@@ -400,30 +3279,197 @@ impl Scene {
 
 		if settings.show_light_gizmos {
 			for light in self.lights.values() {
+				let color = if light.enabled { light.color } else { Vec3::splat(0.3) };
+
 				match &light.light_type {
 					LightType::Directional => {
 						let origin = Vec3::new(0.0, 3.0, 0.0);
-						gizmos.arrow(gl, &self.camera, origin, light.direction, 2.0, Vec3::new(1.0, 1.0, 0.0));
+						gizmos.arrow(gl, &self.camera, origin, light.direction, 2.0, color);
 					}
 					LightType::Point { radius } => {
-						gizmos.wire_sphere(gl, &self.camera, light.position, *radius * 0.1, Vec3::new(1.0, 1.0, 0.0));
-						gizmos.wire_sphere(gl, &self.camera, light.position, *radius, Vec3::new(0.5, 0.5, 0.0));
+						gizmos.wire_sphere(gl, &self.camera, light.position, *radius * 0.1, color);
+						gizmos.wire_sphere(gl, &self.camera, light.position, light.half_intensity_distance(), color * 0.75);
+						gizmos.wire_sphere(gl, &self.camera, light.position, *radius, color * 0.5);
 					}
-					LightType::Spot { .. } => {
-						gizmos.arrow(gl, &self.camera, light.position, light.direction, 1.5, Vec3::new(1.0, 0.8, 0.0));
+					LightType::Spot { angle, outer_angle, range } => {
+						gizmos.wire_cone(gl, &self.camera, light.position, light.direction, *range, *angle * 0.5, color);
+						gizmos.wire_cone(gl, &self.camera, light.position, light.direction, *range, outer_angle.max(*angle) * 0.5, color * 0.5);
 					}
 				}
 			}
 		}
 
-		if settings.show_object_bounds {
-			for obj in self.objects.values() {
-				gizmos.wire_cube(gl, &self.camera, obj.transform.position, obj.transform.scale.max_element(), Vec3::new(0.0, 1.0, 1.0));
+		for (id, obj) in &self.objects {
+			if !settings.show_object_bounds || !is_visible_in(&self.objects, id) {
+				continue;
+			}
+
+			let bounds = obj.mesh.bounds();
+
+			if !bounds.is_empty() {
+				gizmos.wire_box(gl, &self.camera, self.world_matrix(id) * bounds.to_matrix(), Vec3::new(0.0, 1.0, 1.0));
+			}
+		}
+
+		for (id, obj) in &self.objects {
+			if !(settings.show_normals || obj.show_normals) || !is_visible_in(&self.objects, id) {
+				continue;
+			}
+
+			let (mesh, model) = (obj.visible_mesh(), self.world_matrix(id));
+
+			gizmos.vertex_vectors(gl, &self.camera, mesh, model, "normal", settings.normal_length, Vec3::new(0.3, 0.6, 1.0));
+			gizmos.vertex_vectors(gl, &self.camera, mesh, model, "tangent", settings.normal_length, Vec3::new(1.0, 0.4, 0.3));
+		}
+
+		if settings.show_labels {
+			for (id, obj) in &self.objects {
+				if let Some(name) = &obj.name && is_visible_in(&self.objects, id) {
+					let position = self.world_matrix(id).transform_point3(Vec3::ZERO);
+					gizmos.label(gl, &self.camera, position, name, Vec3::ONE);
+				}
+			}
+
+			for light in self.lights.values() {
+				let (kind, position) = match light.light_type {
+					LightType::Directional => ("Directional", Vec3::new(0.0, 3.0, 0.0)),
+					LightType::Point { .. } => ("Point", light.position),
+					LightType::Spot { .. } => ("Spot", light.position),
+				};
+				let text = match &light.name {
+					Some(name) => format!("{} ({} {:.2})", name, kind, light.intensity),
+					None => format!("{} {:.2}", kind, light.intensity),
+				};
+
+				gizmos.label(gl, &self.camera, position, &text, light.color);
 			}
 		}
 
+		gizmos.set_style(previous_style);
+
+		if owns_batch {
+			gizmos.flush(gl, &self.camera);
+		}
+
 		if disable_depth {
 			gl.enable(GL::DEPTH_TEST);
 		}
 	}
-}
\ No newline at end of file
+}
+
+/// Returns whether an object and all of its parents are visible.
+fn is_visible_in(objects: &SlotMap<ObjectId, SceneObject>, id: ObjectId) -> bool {
+	let mut current = Some(id);
+
+	while let Some(obj) = current.and_then(|id| objects.get(id)) {
+		if !obj.visible {
+			return false;
+		}
+		current = obj.parent;
+	}
+
+	true
+}
+
+/// Decomposes an object's world matrix into a transform, see
+/// [`world_matrix_of`]. Shear from non-uniformly scaled parents is lost.
+fn world_transform_of(objects: &SlotMap<ObjectId, SceneObject>, id: ObjectId) -> Transform3D {
+	let (scale, rotation, position) = world_matrix_of(objects, id).to_scale_rotation_translation();
+
+	Transform3D { position, rotation, scale }
+}
+
+/// Draws an object's visible mesh with a depth-only program whose
+/// `model` uniform is set here, and returns the mesh.
+fn draw_depth<'a>(gl: &GL, program: &WebGlProgram, objects: &'a SlotMap<ObjectId, SceneObject>, id: ObjectId, clip_planes: &[Vec4], light_space: &Mat4, time: f32) -> &'a Mesh {
+	let mesh = objects[id].visible_mesh();
+	let material = &mesh.material;
+
+	// Materials that move or cut out their surface draw their own depth
+	let own_program = material.depth_pass.then(|| material.program());
+	let pass_program = own_program.unwrap_or(program);
+
+	if let Some(own) = own_program {
+		gl.use_program(Some(own));
+		material.apply(gl, &[]);
+
+		if let Some(loc) = gl.get_uniform_location(own, "lightSpace") {
+			gl.uniform_matrix4fv_with_f32_array(Some(&loc), false, &light_space.to_cols_array());
+		}
+		if let Some(loc) = gl.get_uniform_location(own, "time") {
+			gl.uniform1f(Some(&loc), time);
+		}
+		if let Some(loc) = gl.get_uniform_location(own, "depthPass") {
+			gl.uniform1i(Some(&loc), 1);
+		}
+	}
+
+	if let Some(loc) = gl.get_uniform_location(pass_program, "model") {
+		gl.uniform_matrix4fv_with_f32_array(
+			Some(&loc), false, &world_matrix_of(objects, id).to_cols_array()
+		);
+	}
+
+	apply_clip_planes(gl, pass_program, clip_planes, &material.clip_planes);
+	mesh.draw_depth_only(gl, pass_program);
+
+	if let Some(own) = own_program {
+		if let Some(loc) = gl.get_uniform_location(own, "depthPass") {
+			gl.uniform1i(Some(&loc), 0);
+		}
+
+		gl.use_program(Some(program));
+	}
+
+	mesh
+}
+
+/// Computes the world matrix of an object by walking up its parents.
+fn world_matrix_of(objects: &SlotMap<ObjectId, SceneObject>, id: ObjectId) -> Mat4 {
+	let mut matrix = Mat4::IDENTITY;
+	let mut current = Some(id);
+
+	while let Some(obj) = current.and_then(|id| objects.get(id)) {
+		matrix = obj.transform.to_matrix() * matrix;
+		current = obj.parent;
+	}
+
+	matrix
+}
+
+/// Returns the bounds shadows are fitted to: the visible objects inside the
+/// camera's view, and every visible shadow caster.
+///
+/// Falls back to the area around the origin shadows used to cover when the
+/// scene has nothing to fit.
+fn shadow_fit_bounds(objects: &SlotMap<ObjectId, SceneObject>, camera: &Camera) -> (Aabb, Aabb) {
+	let mut scene = Aabb::EMPTY;
+	let mut casters = Aabb::EMPTY;
+
+	for (id, obj) in objects {
+		if !is_visible_in(objects, id) {
+			continue;
+		}
+
+		let bounds = obj.visible_mesh().bounds().transformed(&world_matrix_of(objects, id));
+
+		scene = scene.union(bounds);
+
+		if obj.cast_shadows {
+			casters = casters.union(bounds);
+		}
+	}
+
+	let inverse = (camera.projection_matrix() * camera.view_matrix()).inverse();
+	let view = Aabb::from_points(&Aabb::new(Vec3::NEG_ONE, Vec3::ONE).corners().map(|c| inverse.project_point3(c)));
+	let receivers = scene.intersection(view);
+
+	match (receivers.is_empty(), casters.is_empty()) {
+		(false, false) => (receivers, casters),
+		(false, true) => (receivers, receivers),
+		_ => {
+			let fallback = Aabb::new(Vec3::splat(-10.0), Vec3::splat(10.0));
+			(fallback, fallback)
+		}
+	}
+}
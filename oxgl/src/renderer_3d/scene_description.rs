@@ -0,0 +1,387 @@
+//! Scene Descriptions
+//!
+//! Provides [`SceneDescription`], a scene's objects, lights and camera as
+//! plain data without any GPU resources, and [`SceneInstance`], a
+//! [`Scene`] kept in sync with a description on one WebGL context.
+//!
+//! WebGL contexts can't share buffers, textures or shaders, so showing the
+//! same scene in several canvases means building it once per context.
+//! Instead of mirroring every change by hand, describe the scene once and
+//! let each canvas's instance create and update its own meshes and
+//! materials. [`MultiApp`](crate::MultiApp) does this for a set of canvases.
+//!
+//! ## Examples
+//!
+//! ```ignore
+//! use oxgl::renderer_3d::{Light, MaterialDescription, Primitive, SceneDescription, SceneInstance};
+//! use std::rc::Rc;
+//!
+//! let mut description = SceneDescription::new(camera);
+//! let cube = description.add(
+//!		Rc::new(Primitive::Cube.with_face_colors()),
+//!		MaterialDescription::Phong(Vec3::ONE),
+//!		Transform3D::new(),
+//! );
+//! description.add_light(Light::directional(Vec3::NEG_ONE, Vec3::ONE, 1.0));
+//!
+//! // One instance per canvas
+//! let mut main_view = SceneInstance::new(main_camera);
+//! let mut minimap = SceneInstance::new(top_down_camera);
+//! minimap.follow_camera = false;
+//!
+//! // Every frame
+//! description.objects[cube].transform.rotation = Quat::from_rotation_y(time);
+//! main_view.sync(&main_renderer.gl, &description)?;
+//! minimap.sync(&minimap_renderer.gl, &description)?;
+//! ```
+//!
+
+use std::rc::{Rc, Weak};
+use glam::{Vec3, Vec4};
+use slotmap::{SecondaryMap, SlotMap};
+use web_sys::WebGl2RenderingContext as GL;
+
+use crate::{
+	common::{Camera, Material, Mesh, MeshData, Uniform, presets},
+	core::{Color, LightId, ObjectId, Transform3D},
+};
+use super::{Background, Fog, Light, Scene};
+
+/// A material to build on each context, from a preset or shader source.
+#[derive(Clone, Debug)]
+pub enum MaterialDescription {
+	/// [`presets::unlit`] with an RGBA color.
+	Unlit(Vec4),
+	/// [`presets::lambert`] with an RGB color.
+	Lambert(Vec3),
+	/// [`presets::phong`] with an RGB color.
+	Phong(Vec3),
+	/// [`presets::toon`] with an RGB color and band count.
+	Toon(Vec3, u32),
+	/// Custom shaders with uniform values.
+	Custom {
+		vert: String,
+		frag: String,
+		uniforms: Vec<(String, Uniform)>,
+	},
+}
+
+impl MaterialDescription {
+	/// Builds the material on a context.
+	///
+	/// # Errors
+	///
+	/// Returns an error if custom shaders fail to compile or link.
+	pub fn build(&self, gl: &GL) -> Result<Material, String> {
+		Ok(match self {
+			Self::Unlit(color) => presets::unlit(gl, *color),
+			Self::Lambert(color) => presets::lambert(gl, *color),
+			Self::Phong(color) => presets::phong(gl, *color),
+			Self::Toon(color, bands) => presets::toon(gl, *color, *bands),
+			Self::Custom { vert, frag, uniforms } => {
+				let mut material = Material::from_source(gl, vert, frag)?;
+
+				for (name, value) in uniforms {
+					material.set(name, value.clone());
+				}
+
+				material
+			}
+		})
+	}
+}
+
+/// An object in a [`SceneDescription`].
+///
+/// The mesh and material are only rebuilt on instances after changing them
+/// through [`set_mesh`](Self::set_mesh) and [`set_material`](Self::set_material),
+/// while the public fields are copied on every sync.
+#[derive(Clone)]
+pub struct ObjectDescription {
+	pub transform: Transform3D,
+	pub name: Option<String>,
+	/// Parent in the description.
+	pub parent: Option<ObjectId>,
+	pub visible: bool,
+	pub cast_shadows: bool,
+	pub receive_shadows: bool,
+	mesh: Rc<MeshData>,
+	material: MaterialDescription,
+	/// Bumped when the mesh or material changes.
+	revision: u32,
+}
+
+impl ObjectDescription {
+	pub fn new(mesh: Rc<MeshData>, material: MaterialDescription) -> Self {
+		Self {
+			transform: Transform3D::new(),
+			name: mesh.name.clone(),
+			parent: None,
+			visible: true,
+			cast_shadows: true,
+			receive_shadows: true,
+			mesh,
+			material,
+			revision: 0,
+		}
+	}
+
+	pub fn with_transform(mut self, transform: Transform3D) -> Self {
+		self.transform = transform;
+		self
+	}
+
+	pub fn with_parent(mut self, parent: ObjectId) -> Self {
+		self.parent = Some(parent);
+		self
+	}
+
+	pub fn mesh(&self) -> &Rc<MeshData> {
+		&self.mesh
+	}
+
+	/// Replaces the geometry. Objects sharing one `Rc` share vertex
+	/// buffers on each instance.
+	pub fn set_mesh(&mut self, mesh: Rc<MeshData>) {
+		self.mesh = mesh;
+		self.revision = self.revision.wrapping_add(1);
+	}
+
+	pub fn material(&self) -> &MaterialDescription {
+		&self.material
+	}
+
+	pub fn set_material(&mut self, material: MaterialDescription) {
+		self.material = material;
+		self.revision = self.revision.wrapping_add(1);
+	}
+}
+
+/// A scene as plain data, instantiated on any number of contexts with
+/// [`SceneInstance`].
+///
+/// Ids returned here belong to the description. Map them to an instance's
+/// own ids with [`SceneInstance::object`] and [`SceneInstance::light`].
+pub struct SceneDescription {
+	/// Copied by instances that [follow](SceneInstance::follow_camera) it,
+	/// except for the aspect ratio.
+	pub camera: Camera,
+	pub objects: SlotMap<ObjectId, ObjectDescription>,
+	pub lights: SlotMap<LightId, Light>,
+	/// Background color of every instance, or `None` to let each keep its
+	/// own [`Background`].
+	pub background: Option<Color>,
+	pub fog: Option<Fog>,
+	pub fog_color: Vec3,
+}
+
+impl SceneDescription {
+	pub fn new(camera: Camera) -> Self {
+		Self {
+			camera,
+			objects: SlotMap::with_key(),
+			lights: SlotMap::with_key(),
+			background: None,
+			fog: None,
+			fog_color: Vec3::ONE,
+		}
+	}
+
+	/// Adds an object built from `mesh` and `material` on every instance.
+	pub fn add(&mut self, mesh: Rc<MeshData>, material: MaterialDescription, transform: Transform3D) -> ObjectId {
+		self.insert(ObjectDescription::new(mesh, material).with_transform(transform))
+	}
+
+	pub fn insert(&mut self, object: ObjectDescription) -> ObjectId {
+		self.objects.insert(object)
+	}
+
+	/// Removes an object, re-parenting its children to its parent.
+	pub fn remove(&mut self, id: ObjectId) -> Option<ObjectDescription> {
+		let removed = self.objects.remove(id)?;
+
+		for object in self.objects.values_mut() {
+			if object.parent == Some(id) {
+				object.parent = removed.parent;
+			}
+		}
+
+		Some(removed)
+	}
+
+	pub fn add_light(&mut self, light: Light) -> LightId {
+		self.lights.insert(light)
+	}
+
+	pub fn remove_light(&mut self, id: LightId) -> Option<Light> {
+		self.lights.remove(id)
+	}
+}
+
+/// A [`Scene`] on one context, kept in sync with a [`SceneDescription`].
+///
+/// The scene is otherwise a regular scene, so instances can add their own
+/// post effects, skyboxes, helpers and objects that only they show.
+pub struct SceneInstance {
+	pub scene: Scene,
+	/// Copies the description's camera on every sync, keeping this
+	/// instance's aspect ratio. Turn off to give the instance its own view,
+	/// such as a minimap.
+	pub follow_camera: bool,
+	/// Scene object and mesh revision of every described object.
+	objects: SecondaryMap<ObjectId, (ObjectId, u32)>,
+	lights: SecondaryMap<LightId, LightId>,
+	/// Meshes built on this context, shared by objects using the same data.
+	meshes: Vec<(Weak<MeshData>, Mesh)>,
+}
+
+impl SceneInstance {
+	/// Creates an empty instance, filled in by the first [`sync`](Self::sync).
+	pub fn new(camera: Camera) -> Self {
+		Self {
+			scene: Scene::new(camera),
+			follow_camera: true,
+			objects: SecondaryMap::new(),
+			lights: SecondaryMap::new(),
+			meshes: Vec::new(),
+		}
+	}
+
+	/// Returns the scene object showing a described object.
+	pub fn object(&self, id: ObjectId) -> Option<ObjectId> {
+		self.objects.get(id).map(|&(object, _)| object)
+	}
+
+	/// Returns the scene light copying a described light.
+	pub fn light(&self, id: LightId) -> Option<LightId> {
+		self.lights.get(id).copied()
+	}
+
+	/// Updates the scene to match the description, adding, removing and
+	/// rebuilding objects and lights as needed.
+	/// Objects and lights removed from the scene directly are added again.
+	///
+	/// # Errors
+	///
+	/// Returns an error if a material fails to build. Objects that did
+	/// build are still synced.
+	pub fn sync(&mut self, gl: &GL, description: &SceneDescription) -> Result<(), String> {
+		let mut result = Ok(());
+
+		let removed: Vec<_> = self.objects.keys().filter(|&id| !description.objects.contains_key(id)).collect();
+
+		for id in removed {
+			if let Some((object, _)) = self.objects.remove(id) {
+				self.scene.remove(object);
+			}
+		}
+
+		self.meshes.retain(|(data, _)| data.strong_count() > 0);
+
+		self.objects.retain(|_, (object, _)| self.scene.objects.contains_key(*object));
+
+		for (id, described) in &description.objects {
+			let current = self.objects.get(id).copied();
+
+			if current.is_none_or(|(_, revision)| revision != described.revision) {
+				let mesh = match described.material.build(gl) {
+					Ok(material) => self.mesh(gl, &described.mesh, material),
+					Err(e) => {
+						result = Err(e);
+						continue;
+					}
+				};
+
+				let object = match current {
+					Some((object, _)) => {
+						self.scene.objects[object].mesh = mesh;
+						object
+					}
+					None => self.scene.add(mesh, described.transform.clone()),
+				};
+
+				self.objects.insert(id, (object, described.revision));
+			}
+
+			let (object, _) = self.objects[id];
+
+			self.scene.set_visible(object, described.visible);
+
+			let scene_object = &mut self.scene.objects[object];
+			scene_object.transform = described.transform.clone();
+			scene_object.cast_shadows = described.cast_shadows;
+			scene_object.receive_shadows = described.receive_shadows;
+
+			if scene_object.name != described.name {
+				match &described.name {
+					Some(name) => self.scene.set_name(object, name),
+					None => scene_object.name = None,
+				}
+			}
+		}
+
+		for (id, described) in &description.objects {
+			let Some(&(object, _)) = self.objects.get(id) else {
+				continue;
+			};
+			let parent = described.parent.and_then(|parent| self.object(parent));
+
+			if self.scene.objects[object].parent != parent {
+				self.scene.set_parent(object, parent);
+			}
+		}
+
+		self.sync_lights(description);
+
+		if self.follow_camera {
+			let aspect = self.scene.camera.aspect;
+			self.scene.camera = description.camera.clone();
+			self.scene.camera.aspect = aspect;
+		}
+
+		if let Some(color) = description.background {
+			self.scene.set_background(Background::Color(color));
+		}
+
+		self.scene.fog = description.fog;
+		self.scene.fog_color = description.fog_color;
+
+		result
+	}
+
+	fn sync_lights(&mut self, description: &SceneDescription) {
+		let removed: Vec<_> = self.lights.keys().filter(|&id| !description.lights.contains_key(id)).collect();
+
+		for id in removed {
+			if let Some(light) = self.lights.remove(id) {
+				self.scene.remove_light(light);
+			}
+		}
+
+		self.lights.retain(|_, light| self.scene.lights.contains_key(*light));
+
+		for (id, described) in &description.lights {
+			match self.lights.get(id) {
+				Some(&light) => self.scene.lights[light] = described.clone(),
+				None => {
+					let light = self.scene.add_light(described.clone());
+					self.lights.insert(id, light);
+				}
+			}
+		}
+	}
+
+	/// Returns a mesh for `data` with `material`, sharing the buffers of a
+	/// mesh already built from the same data.
+	fn mesh(&mut self, gl: &GL, data: &Rc<MeshData>, material: Material) -> Mesh {
+		if let Some((_, mesh)) = self.meshes.iter().find(|(built, _)| std::ptr::eq(built.as_ptr(), Rc::as_ptr(data))) {
+			let mut mesh = mesh.clone();
+			mesh.material = material;
+			return mesh;
+		}
+
+		let mesh = Mesh::from_data(gl, data, material);
+		self.meshes.push((Rc::downgrade(data), mesh.clone()));
+		mesh
+	}
+}
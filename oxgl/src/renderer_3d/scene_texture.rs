@@ -0,0 +1,153 @@
+//! Scene Textures
+//!
+//! Provides [`SceneTexture`], a texture the [`Scene`](super::Scene) keeps
+//! filled with its own view from another camera, for mirrors, portals and
+//! in-world screens. It wraps a [`RenderTarget`] and
+//! [`Scene::render_to_target`](super::Scene::render_to_target), rendering at
+//! the start of every frame or every few frames.
+//!
+//! Objects showing the texture are hidden while it renders, so a screen
+//! never samples itself.
+//!
+//! A texture with a [mirror](SceneTexture::with_mirror) plane ignores its
+//! own camera and follows the scene's, reflected across the plane, for
+//! planar reflections on water and polished floors. Materials showing it
+//! get the `reflectionViewProjection` uniform to project their surface into
+//! it.
+//!
+//! ## Examples
+//!
+//! ```ignore
+//! use oxgl::renderer_3d::SceneTexture;
+//! use oxgl::common::Camera;
+//!
+//! let security_camera = Camera::new(1.0)
+//!		.with_position(Vec3::new(8.0, 4.0, 8.0))
+//!		.with_target(Vec3::ZERO);
+//!
+//! // A security monitor refreshing every third frame
+//! let feed = SceneTexture::new(&gl, 512, 512, security_camera)?.with_interval(3);
+//! monitor_material.set_texture("screenTexture", feed.texture().clone());
+//!
+//! let feed = scene.add_scene_texture(feed);
+//!
+//! // Pan the camera later on
+//! scene.scene_textures[feed].camera.target = Vec3::new(2.0, 0.0, 0.0);
+//!
+//! // Reflections on a lake at y = 0
+//! let reflection = SceneTexture::new(&gl, 1024, 512, Camera::new(1.0))?
+//!		.with_mirror(Vec4::new(0.0, 1.0, 0.0, 0.0));
+//! let lake_material = presets::water(&gl, Vec3::new(0.1, 0.4, 0.5), Some(reflection.texture().clone()));
+//!
+//! scene.add_scene_texture(reflection);
+//! ```
+//!
+
+use glam::{Vec3, Vec4};
+use web_sys::WebGl2RenderingContext as GL;
+
+use crate::common::{Camera, OxglError, RenderTarget, Texture};
+
+/// A texture showing the scene from a camera, re-rendered automatically.
+pub struct SceneTexture {
+	pub camera: Camera,
+	/// Renders every this many frames, with `1` rendering every frame.
+	pub interval: u32,
+	pub enabled: bool,
+	/// Plane `(normal, distance)` the scene's camera is reflected across,
+	/// replacing [`camera`](Self::camera). Geometry behind the plane is
+	/// clipped.
+	pub mirror: Option<Vec4>,
+	target: RenderTarget,
+	frames_until_update: u32,
+}
+
+impl SceneTexture {
+	/// Creates a `width` by `height` texture showing the scene from `camera`.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the render target can't be created.
+	pub fn new(gl: &GL, width: i32, height: i32, camera: Camera) -> Result<Self, OxglError> {
+		Ok(Self {
+			camera,
+			interval: 1,
+			enabled: true,
+			mirror: None,
+			target: RenderTarget::new(gl, width, height)?,
+			frames_until_update: 0,
+		})
+	}
+
+	pub fn with_interval(mut self, frames: u32) -> Self {
+		self.interval = frames;
+		self
+	}
+
+	/// Shows the reflection of the scene's view in a plane, given as
+	/// `(normal, distance)` keeping points where `dot(normal, p) + distance`
+	/// is positive, like [clip planes](super::Scene::set_clip_planes).
+	pub fn with_mirror(mut self, plane: Vec4) -> Self {
+		self.mirror = Some(plane);
+		self
+	}
+
+	/// Returns the camera the texture renders from: `main` reflected across
+	/// the mirror plane, or the texture's own camera.
+	///
+	/// The reflection keeps `main`'s projection, so surfaces project into
+	/// the texture the way they appear on screen.
+	pub fn view_camera(&self, main: &Camera) -> Camera {
+		let Some(plane) = self.mirror else {
+			return self.camera.clone();
+		};
+
+		let normal = plane.truncate().normalize_or(Vec3::Y);
+		let distance = plane.w / plane.truncate().length().max(f32::EPSILON);
+		let reflect_point = |p: Vec3| p - 2.0 * (normal.dot(p) + distance) * normal;
+		let reflect_vector = |v: Vec3| v - 2.0 * normal.dot(v) * normal;
+
+		let mut camera = main.clone();
+		camera.position = reflect_point(main.position);
+		camera.target = reflect_point(main.target);
+		camera.up = reflect_vector(main.up);
+		camera.custom_projection = Some(main.projection_matrix());
+		camera
+	}
+
+	/// Returns the texture to assign to materials. It's the same texture
+	/// for the lifetime of the scene texture, even after resizing.
+	pub fn texture(&self) -> &Texture {
+		self.target.texture()
+	}
+
+	pub fn target(&self) -> &RenderTarget {
+		&self.target
+	}
+
+	/// Changes the resolution, discarding the current image.
+	pub fn resize(&mut self, gl: &GL, width: i32, height: i32) {
+		self.target.resize(gl, width, height);
+		self.request_update();
+	}
+
+	/// Renders again on the next frame, regardless of the interval.
+	pub fn request_update(&mut self) {
+		self.frames_until_update = 0;
+	}
+
+	/// Counts a frame and returns whether the texture renders in it.
+	pub(crate) fn tick(&mut self) -> bool {
+		if !self.enabled {
+			return false;
+		}
+
+		if self.frames_until_update == 0 {
+			self.frames_until_update = self.interval.max(1) - 1;
+			true
+		} else {
+			self.frames_until_update -= 1;
+			false
+		}
+	}
+}
@@ -0,0 +1,253 @@
+//! Editor Selection
+//!
+//! Provides [`Selection`], which ties picking, hover highlighting and the
+//! [`TransformGizmo`] together into click-to-select editing. Clicking an
+//! object selects it, clicking with a modifier adds or removes it from the
+//! selection, and clicking empty space clears it. The most recently selected
+//! object is the primary selection and gets the transform handles.
+//!
+//! Changes are reported as [`SceneEvent::Selected`],
+//! [`SceneEvent::Deselected`] and [`SceneEvent::TransformEdited`] through
+//! [`Scene::on_event`], so inspector panels can follow the selection
+//! without polling.
+//!
+//! [`App::enable_selection`](crate::App::enable_selection) forwards the
+//! canvas pointer events and draws the selection every frame.
+//!
+//! ## Examples
+//!
+//! ```ignore
+//! use oxgl::renderer_3d::{GizmoMode, SceneEvent};
+//!
+//! app.enable_selection()?;
+//! app.selection.borrow_mut().gizmo.mode = GizmoMode::Rotate;
+//!
+//! // Show the primary selection in a Leptos inspector
+//! let selection = app.selection.clone();
+//! app.scene.borrow_mut().on_event(move |event| match event {
+//!		SceneEvent::Selected(_) | SceneEvent::Deselected(_) => {
+//!			set_inspected.set(selection.borrow().primary());
+//!		}
+//!		SceneEvent::TransformEdited(id) => refresh_transform.set(Some(*id)),
+//!		_ => {}
+//! });
+//! ```
+//!
+
+use glam::Vec3;
+use web_sys::WebGl2RenderingContext as GL;
+
+use crate::core::ObjectId;
+use super::{GizmoDepth, GizmoRenderer, GizmoStyle, Scene, SceneEvent, TransformGizmo};
+
+/// Selected objects, the hovered object and the transform handles of an
+/// editor view.
+///
+/// Like the [`TransformGizmo`], the selection doesn't listen to events
+/// itself; forward pointer positions to [`pointer_down`](Self::pointer_down),
+/// [`pointer_move`](Self::pointer_move) and [`pointer_up`](Self::pointer_up).
+#[derive(Clone, Debug)]
+pub struct Selection {
+	/// Handles drawn on the primary selection.
+	pub gizmo: TransformGizmo,
+	pub hover_color: Vec3,
+	pub selected_color: Vec3,
+	/// How the bounds of selected objects are drawn behind other geometry.
+	pub selected_depth: GizmoDepth,
+	/// Pixels the pointer may move between press and release for it to
+	/// still count as a click.
+	pub click_tolerance: f32,
+	selected: Vec<ObjectId>,
+	press: Option<(f32, f32)>,
+}
+
+impl Default for Selection {
+	fn default() -> Self {
+		Self {
+			gizmo: TransformGizmo::new(),
+			hover_color: Vec3::new(0.6, 0.8, 1.0),
+			selected_color: Vec3::new(1.0, 0.6, 0.1),
+			selected_depth: GizmoDepth::AlwaysOnTop,
+			click_tolerance: 4.0,
+			selected: Vec::new(),
+			press: None,
+		}
+	}
+}
+
+impl Selection {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Returns the selected objects, in the order they were selected.
+	pub fn selected(&self) -> &[ObjectId] {
+		&self.selected
+	}
+
+	/// Returns the most recently selected object, which the transform
+	/// handles are attached to.
+	pub fn primary(&self) -> Option<ObjectId> {
+		self.selected.last().copied()
+	}
+
+	pub fn is_selected(&self, id: ObjectId) -> bool {
+		self.selected.contains(&id)
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.selected.is_empty()
+	}
+
+	/// Replaces the selection with a single object.
+	pub fn select(&mut self, scene: &mut Scene, id: ObjectId) {
+		self.set(scene, vec![id]);
+	}
+
+	/// Adds an object to the selection and makes it the primary selection.
+	pub fn add(&mut self, scene: &mut Scene, id: ObjectId) {
+		let mut selected = self.selected.clone();
+		selected.retain(|&other| other != id);
+		selected.push(id);
+		self.set(scene, selected);
+	}
+
+	/// Removes an object from the selection, returning whether it was selected.
+	pub fn deselect(&mut self, scene: &mut Scene, id: ObjectId) -> bool {
+		if !self.is_selected(id) {
+			return false;
+		}
+
+		let selected = self.selected.iter().copied().filter(|&other| other != id).collect();
+		self.set(scene, selected);
+		true
+	}
+
+	/// Adds an object to the selection, or removes it if already selected.
+	pub fn toggle(&mut self, scene: &mut Scene, id: ObjectId) {
+		if !self.deselect(scene, id) {
+			self.add(scene, id);
+		}
+	}
+
+	pub fn clear(&mut self, scene: &mut Scene) {
+		self.set(scene, Vec::new());
+	}
+
+	/// Forgets objects that have been removed from the scene.
+	///
+	/// No events are sent, as the scene already reports
+	/// [`SceneEvent::ObjectRemoved`].
+	pub fn prune(&mut self, scene: &Scene) {
+		self.selected.retain(|&id| scene.get(id).is_some());
+		self.gizmo.target = self.primary();
+	}
+
+	/// Starts dragging the transform handle under a pixel, or remembers the
+	/// press so [`pointer_up`](Self::pointer_up) can tell a click from a drag.
+	///
+	/// Returns `true` if a handle was grabbed, in which case the press
+	/// shouldn't also move the camera.
+	pub fn pointer_down(&mut self, scene: &Scene, x: f32, y: f32, width: f32, height: f32) -> bool {
+		if self.gizmo.pointer_down(scene, x, y, width, height) {
+			self.press = None;
+			return true;
+		}
+
+		self.press = Some((x, y));
+		false
+	}
+
+	/// Applies the current handle drag, or updates the hovered object.
+	///
+	/// Returns `true` if the primary selection's transform changed.
+	pub fn pointer_move(&mut self, scene: &mut Scene, x: f32, y: f32, width: f32, height: f32) -> bool {
+		if self.gizmo.is_dragging() {
+			return self.gizmo.pointer_move(scene, x, y, width, height);
+		}
+
+		self.gizmo.pointer_move(scene, x, y, width, height);
+		scene.pointer_move(x, y, width, height);
+		false
+	}
+
+	/// Ends a handle drag, or selects what was clicked.
+	///
+	/// With `additive`, usually while shift is held, the clicked object is
+	/// toggled in the selection and clicking empty space keeps it.
+	pub fn pointer_up(&mut self, scene: &mut Scene, x: f32, y: f32, width: f32, height: f32, additive: bool) {
+		if self.gizmo.is_dragging() {
+			self.gizmo.pointer_up();
+
+			if let Some(id) = self.gizmo.target {
+				scene.emit(SceneEvent::TransformEdited(id));
+			}
+			return;
+		}
+
+		let Some((press_x, press_y)) = self.press.take() else {
+			return;
+		};
+
+		if (x - press_x).hypot(y - press_y) > self.click_tolerance {
+			return;
+		}
+
+		match (scene.pick(x, y, width, height).map(|hit| hit.object), additive) {
+			(Some(id), true) => self.toggle(scene, id),
+			(Some(id), false) => self.select(scene, id),
+			(None, true) => {}
+			(None, false) => self.clear(scene),
+		}
+	}
+
+	/// Ends a handle drag, restoring the transform from before it.
+	pub fn cancel(&mut self, scene: &mut Scene) {
+		self.gizmo.cancel(scene);
+		self.press = None;
+	}
+
+	/// Outlines the hovered and selected objects and draws the transform
+	/// handles on top of the scene.
+	pub fn draw(&self, gl: &GL, gizmos: &GizmoRenderer, scene: &Scene) {
+		let outline = |id: ObjectId, color: Vec3| {
+			let Some(obj) = scene.get(id).filter(|_| scene.is_visible(id)) else {
+				return;
+			};
+			let bounds = obj.mesh.bounds();
+
+			if !bounds.is_empty() {
+				gizmos.wire_box(gl, &scene.camera, scene.world_matrix(id) * bounds.to_matrix(), color);
+			}
+		};
+
+		if let Some(id) = scene.hovered().filter(|&id| !self.is_selected(id)) && !self.gizmo.is_dragging() {
+			outline(id, self.hover_color);
+		}
+
+		gizmos.with_style(GizmoStyle::new().with_depth(self.selected_depth), |_| {
+			for &id in &self.selected {
+				outline(id, self.selected_color);
+			}
+		});
+
+		self.gizmo.draw(gl, gizmos, scene);
+	}
+
+	/// Replaces the selection, reporting the objects that left and joined it.
+	fn set(&mut self, scene: &mut Scene, selected: Vec<ObjectId>) {
+		for &id in self.selected.iter().filter(|id| !selected.contains(id)) {
+			scene.emit(SceneEvent::Deselected(id));
+		}
+		for &id in selected.iter().filter(|id| !self.selected.contains(id)) {
+			scene.emit(SceneEvent::Selected(id));
+		}
+
+		self.selected = selected;
+
+		if self.gizmo.target != self.primary() {
+			self.gizmo.cancel(scene);
+			self.gizmo.target = self.primary();
+		}
+	}
+}
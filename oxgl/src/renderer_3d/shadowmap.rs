@@ -28,15 +28,118 @@
 
 use glam::{Mat4, Vec3};
 use web_sys::{
-	WebGlFramebuffer, WebGlTexture,
+	WebGlFramebuffer, WebGlProgram, WebGlTexture,
 	WebGl2RenderingContext as GL,
 };
 
+use crate::common::OxglError;
+use crate::core::Aabb;
+
+/// Near plane of point and spot light shadows.
+const SHADOW_NEAR: f32 = 0.1;
+
+/// Padding around fitted shadow depth ranges, so surfaces lying exactly on
+/// the bounds aren't clipped.
+const FIT_MARGIN: f32 = 0.5;
+
 /// Default resolution of the shadow map texture.
 ///
 /// Higher values produce sharper shadows but use more memory.
 pub const SHADOW_MAP_SIZE: i32 = 1024;
 
+/// How shadow map lookups are filtered.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ShadowFilter {
+	/// A 3x3 percentage-closer filter with evenly soft edges.
+	#[default]
+	Pcf,
+	/// Percentage-closer soft shadows. Shadows are sharp where they touch
+	/// their caster and soften with distance from it, at the cost of 32
+	/// lookups per pixel.
+	Pcss,
+}
+
+/// Filtering settings for shadow maps, see [`Scene::shadow_settings`](super::Scene::shadow_settings).
+///
+/// ## Examples
+///
+/// ```ignore
+/// use oxgl::renderer_3d::{ShadowFilter, ShadowSettings};
+///
+/// scene.shadow_settings = ShadowSettings::new()
+///		.with_filter(ShadowFilter::Pcss)
+///		.with_light_size(0.03);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ShadowSettings {
+	pub filter: ShadowFilter,
+	/// Depth offset that keeps surfaces from shadowing themselves.
+	pub bias: f32,
+	/// Size of the light for [`ShadowFilter::Pcss`], as a fraction of the
+	/// shadow map's width. Larger lights give wider penumbras.
+	pub light_size: f32,
+	/// Distance in world units a contact shadow ray must pass behind a
+	/// surface to be occluded, which keeps surfaces from shadowing
+	/// themselves.
+	pub contact_bias: f32,
+}
+
+impl Default for ShadowSettings {
+	fn default() -> Self {
+		Self {
+			filter: ShadowFilter::default(),
+			bias: 0.005,
+			light_size: 0.02,
+			contact_bias: 0.01,
+		}
+	}
+}
+
+impl ShadowSettings {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	pub fn with_filter(mut self, filter: ShadowFilter) -> Self {
+		self.filter = filter;
+		self
+	}
+
+	pub fn with_bias(mut self, bias: f32) -> Self {
+		self.bias = bias;
+		self
+	}
+
+	pub fn with_light_size(mut self, size: f32) -> Self {
+		self.light_size = size;
+		self
+	}
+
+	pub fn with_contact_bias(mut self, bias: f32) -> Self {
+		self.contact_bias = bias;
+		self
+	}
+
+	/// Uploads the settings to the `shadows` chunk uniforms of a program.
+	pub(crate) fn apply(&self, gl: &GL, program: &WebGlProgram, map_size: i32) {
+		if let Some(loc) = gl.get_uniform_location(program, "shadowFilter") {
+			gl.uniform1i(Some(&loc), match self.filter {
+				ShadowFilter::Pcf => 0,
+				ShadowFilter::Pcss => 1,
+			});
+		}
+		if let Some(loc) = gl.get_uniform_location(program, "shadowBias") {
+			gl.uniform1f(Some(&loc), self.bias);
+		}
+		if let Some(loc) = gl.get_uniform_location(program, "shadowLightSize") {
+			gl.uniform1f(Some(&loc), self.light_size);
+		}
+		if let Some(loc) = gl.get_uniform_location(program, "shadowMapSize") {
+			gl.uniform1f(Some(&loc), map_size as f32);
+		}
+	}
+}
+
 /// A depth-based shadow map for shadow rendering.
 ///
 /// Renders the scene from the light's perspective into a depth texture,
@@ -79,16 +182,16 @@ impl ShadowMap {
 	/// let shadow_map = ShadowMap::new(&gl)?;
 	/// println!("Shadow map size: {}x{}", shadow_map.size, shadow_map.size);
 	/// ```
-	pub fn new(gl: &GL) -> Result<Self, String> {
+	pub fn new(gl: &GL) -> Result<Self, OxglError> {
 		let size = SHADOW_MAP_SIZE;
 
 		let framebuffer = gl
 			.create_framebuffer()
-			.ok_or("Failed to create shadow framebuffer")?;
+			.ok_or_else(|| OxglError::ResourceCreation("Failed to create shadow framebuffer".to_string()))?;
 
 		let depth_texture = gl
 			.create_texture()
-			.ok_or("Failed to create shadow texture")?;
+			.ok_or_else(|| OxglError::ResourceCreation("Failed to create shadow texture".to_string()))?;
 
 		gl.bind_texture(GL::TEXTURE_2D, Some(&depth_texture));
 		
@@ -102,7 +205,7 @@ impl ShadowMap {
 			GL::DEPTH_COMPONENT,
 			GL::UNSIGNED_INT,
 			None,
-		).map_err(|e| format!("Failed to create depth texture: {:?}", e))?;
+		).map_err(|e| OxglError::ResourceCreation(format!("Failed to create depth texture: {:?}", e)))?;
 
 		gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_MIN_FILTER, GL::NEAREST as i32);
 		gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_MAG_FILTER, GL::NEAREST as i32);
@@ -123,14 +226,7 @@ impl ShadowMap {
 		let status = gl.check_framebuffer_status(GL::FRAMEBUFFER);
 
 		if status != GL::FRAMEBUFFER_COMPLETE {
-			let error_msg = match status {
-				GL::FRAMEBUFFER_INCOMPLETE_ATTACHMENT => "INCOMPLETE_ATTACHMENT",
-				GL::FRAMEBUFFER_INCOMPLETE_MISSING_ATTACHMENT => "MISSING_ATTACHMENT",
-				GL::FRAMEBUFFER_INCOMPLETE_DIMENSIONS => "INCOMPLETE_DIMENSIONS",
-				GL::FRAMEBUFFER_UNSUPPORTED => "UNSUPPORTED",
-				_ => "UNKNOWN",
-			};
-			return Err(format!("Shadow framebuffer incomplete: {} ({})", error_msg, status));
+			return Err(OxglError::FramebufferIncomplete { framebuffer: "Shadow".to_string(), status });
 		}
 
 		gl.bind_framebuffer(GL::FRAMEBUFFER, None);
@@ -165,7 +261,7 @@ impl ShadowMap {
 	pub fn update_directional(&mut self, direction: Vec3, scene_center: Vec3, scene_radius: f32) {
 		let light_pos = scene_center - direction.normalize() * scene_radius * 2.0;
 		
-		let view = Mat4::look_at_rh(light_pos, scene_center, Vec3::Y);
+		let view = Mat4::look_at_rh(light_pos, scene_center, up_for(direction));
 		let projection = Mat4::orthographic_rh_gl(
 			-scene_radius, scene_radius,
 			-scene_radius, scene_radius,
@@ -196,12 +292,65 @@ impl ShadowMap {
 	/// );
 	/// ```
 	pub fn update_point(&mut self, position: Vec3, target: Vec3, fov: f32, near: f32, far: f32) {
-		let view = Mat4::look_at_rh(position, target, Vec3::Y);
+		let view = Mat4::look_at_rh(position, target, up_for(target - position));
 		let projection = Mat4::perspective_rh_gl(fov, 1.0, near, far);
 
 		self.light_space = projection * view;
 	}
 
+	/// Fits a directional light's projection to the scene.
+	///
+	/// The projection covers `receivers` from the light's side, and its depth
+	/// range is extended to `casters` so objects outside the receiving area
+	/// still cast shadows into it.
+	///
+	/// # Examples
+	///
+	/// ```ignore
+	/// let bounds = Aabb::new(Vec3::new(90.0, 0.0, 40.0), Vec3::new(130.0, 20.0, 80.0));
+	///
+	/// shadow_map.fit_directional(Vec3::new(-1.0, -1.0, -0.5), &bounds, &bounds);
+	/// ```
+	pub fn fit_directional(&mut self, direction: Vec3, receivers: &Aabb, casters: &Aabb) {
+		let direction = direction.normalize();
+		let center = receivers.center();
+		let view = Mat4::look_at_rh(center - direction, center, up_for(direction));
+
+		let receiving = receivers.transformed(&view);
+		let depth = receiving.union(casters.transformed(&view));
+
+		// The light looks down -Z, so nearer points have larger Z
+		let projection = Mat4::orthographic_rh_gl(
+			receiving.min.x, receiving.max.x,
+			receiving.min.y, receiving.max.y,
+			-depth.max.z - FIT_MARGIN, -depth.min.z + FIT_MARGIN,
+		);
+
+		self.light_space = projection * view;
+	}
+
+	/// Fits a point or spot light's projection to the scene.
+	///
+	/// Point lights aim at the center of `receivers`, spot lights keep their
+	/// direction. The far plane is pulled in to the farthest corner of
+	/// `receivers` or `casters`, up to `range`.
+	pub fn fit_point(&mut self, position: Vec3, direction: Option<Vec3>, fov: f32, range: f32, receivers: &Aabb, casters: &Aabb) {
+		let target = match direction {
+			Some(direction) => position + direction,
+			None => receivers.center(),
+		};
+
+		let farthest = receivers.corners()
+			.into_iter()
+			.chain(casters.corners())
+			.map(|corner| corner.distance(position))
+			.fold(0.0, f32::max);
+
+		let far = (farthest + FIT_MARGIN).min(range).max(SHADOW_NEAR * 2.0);
+
+		self.update_point(position, target, fov, SHADOW_NEAR, far);
+	}
+
 	/// Binds the shadow map framebuffer for rendering.
 	///
 	/// After calling this, all draw calls will render to the shadow map's
@@ -252,4 +401,14 @@ impl ShadowMap {
 		gl.active_texture(GL::TEXTURE0 + unit);
 		gl.bind_texture(GL::TEXTURE_2D, Some(&self.depth_texture));
 	}
-}
\ No newline at end of file
+}
+
+/// Returns an up vector for looking along `direction`, avoiding `Y` when
+/// the light points straight up or down.
+fn up_for(direction: Vec3) -> Vec3 {
+	if direction.normalize_or_zero().y.abs() > 0.99 {
+		Vec3::Z
+	} else {
+		Vec3::Y
+	}
+}
@@ -0,0 +1,903 @@
+//! Sprites
+//!
+//! Provides camera-facing textured quads anchored at 3D positions, for
+//! markers, impostors and one-off effects. Sprites live in the
+//! [`Scene`](super::Scene) next to meshes, are drawn after them with
+//! blending, and can be picked with [`Scene::pick_sprite`](super::Scene::pick_sprite).
+//!
+//! Sizes are either in world units, shrinking with distance like any other
+//! object, or in pixels, staying the same size on screen.
+//!
+//! Soft sprites fade out where they meet the geometry behind them, so smoke,
+//! dust and glows don't end in a hard line where the quad cuts through a
+//! wall or the ground. They need a camera depth pre-pass, which the scene
+//! renders while any soft sprite is visible.
+//!
+//! Moving sprites, such as sparks or smoke puffs, can bounce off the scene
+//! with a [`SpriteCollision`]. Collisions are found in a small copy of the
+//! camera depth read back each frame, so they only hit surfaces the camera
+//! sees, and cost the same however many sprites move.
+//!
+//! Sprites can show one region of a [`TextureAtlas`](crate::common::TextureAtlas),
+//! or play a flipbook through a sprite sheet's frames with a
+//! [`SpriteAnimation`], driven by the scene time.
+//!
+//! ## Examples
+//!
+//! ```ignore
+//! use oxgl::renderer_3d::{Sprite, SpriteSize};
+//! use oxgl::common::Texture;
+//! use glam::{Vec2, Vec3, Vec4};
+//!
+//! let pin = Texture::load(&gl, "assets/pin.png")?;
+//!
+//! // A 32x32 pixel marker above a building
+//! let marker = scene.add_sprite(
+//!		Sprite::new(Vec3::new(4.0, 12.0, -2.0), SpriteSize::Pixels(Vec2::splat(32.0)))
+//!			.with_texture(pin)
+//!			.with_name("Town hall")
+//! );
+//!
+//! // A tinted 2x2 unit quad in the world
+//! scene.add_sprite(
+//!		Sprite::new(Vec3::ZERO, SpriteSize::World(Vec2::splat(2.0)))
+//!			.with_color(Vec4::new(1.0, 0.5, 0.0, 0.8))
+//! );
+//!
+//! // A looping 24 fps explosion from an 8x4 sprite sheet
+//! let sheet = TextureAtlas::grid(512, 256, 8, 4);
+//! scene.add_sprite(
+//!		Sprite::new(Vec3::ZERO, SpriteSize::World(Vec2::splat(3.0)))
+//!			.with_texture(explosion)
+//!			.with_animation(SpriteAnimation::new(sheet.frame_range(0, 32), 24.0))
+//! );
+//!
+//! // A smoke puff fading over half a unit where it meets the ground
+//! scene.add_sprite(
+//!		Sprite::new(Vec3::new(0.0, 0.3, 0.0), SpriteSize::World(Vec2::splat(2.0)))
+//!			.with_texture(smoke)
+//!			.with_soft_distance(0.5)
+//! );
+//!
+//! // A spark thrown up that falls and bounces off the floor
+//! scene.add_sprite(
+//!		Sprite::new(Vec3::new(0.0, 1.0, 0.0), SpriteSize::Pixels(Vec2::splat(6.0)))
+//!			.with_color(Vec4::new(1.0, 0.7, 0.2, 1.0))
+//!			.with_additive(true)
+//!			.with_velocity(Vec3::new(1.0, 4.0, 0.0))
+//!			.with_acceleration(Vec3::new(0.0, -9.81, 0.0))
+//!			.with_collision(SpriteCollision::new().with_bounce(0.4))
+//! );
+//!
+//! if let Some(hit) = scene.pick_sprite(x, y, width, height) {
+//!		log::info!("Clicked {:?}", scene.sprites[hit.sprite].name);
+//! }
+//! ```
+//!
+
+use glam::{Mat4, Vec2, Vec3, Vec4};
+use web_sys::{WebGlBuffer, WebGlFramebuffer, WebGlProgram, WebGlTexture, WebGl2RenderingContext as GL};
+
+use crate::common::{compile_shader, link_program, Camera, Material, Texture, Uniform, UvRect, render_state::{SavedState, current_viewport}};
+use super::{ContactShadowMap, Light, background::blend_over};
+use crate::core::SpriteId;
+
+const SPRITE_VERT: &str = r#"
+	#include <logdepth>
+
+	attribute vec2 corner;
+
+	uniform mat4 view;
+	uniform mat4 projection;
+	uniform vec3 center;
+	uniform vec2 size;
+	uniform float rotation;
+	uniform bool pixelSize;
+	uniform vec2 viewport;
+	uniform vec4 uvRect;
+
+	varying vec2 vUv;
+	varying float vViewDepth;
+
+	void main() {
+		float s = sin(rotation);
+		float c = cos(rotation);
+		vec2 offset = mat2(c, s, -s, c) * corner * size;
+		vec4 viewPos = view * vec4(center, 1.0);
+
+		if (pixelSize) {
+			vec4 clip = projection * viewPos;
+			clip.xy += offset / viewport * 2.0 * clip.w;
+			gl_Position = logDepth(clip);
+		} else {
+			viewPos.xy += offset;
+			gl_Position = logDepth(projection * viewPos);
+		}
+
+		vUv = uvRect.xy + vec2(corner.x + 0.5, 0.5 - corner.y) * uvRect.zw;
+		vViewDepth = -viewPos.z;
+	}
+"#;
+
+const SPRITE_FRAG: &str = r#"
+	precision highp float;
+
+	uniform vec4 color;
+	uniform bool useTexture;
+	uniform sampler2D spriteTexture;
+
+	// Camera depth pre-pass, stored without logarithmic depth
+	uniform float softDistance;
+	uniform sampler2D depthMap;
+	uniform vec2 resolution;
+	// Bottom-left corner of the viewport the depth map covers
+	uniform vec2 viewportOrigin;
+	// Part of the depth map the viewport covers, from its bottom-left corner
+	uniform vec2 depthUvScale;
+	// projection[2][2] and projection[3][2]
+	uniform vec2 depthProjection;
+
+	varying vec2 vUv;
+	varying float vViewDepth;
+
+	float viewDepth(float depth) {
+		return depthProjection.y / (depth * 2.0 - 1.0 + depthProjection.x);
+	}
+
+	void main() {
+		vec4 result = color;
+
+		if (useTexture) {
+			result *= texture2D(spriteTexture, vUv);
+		}
+
+		if (softDistance > 0.0) {
+			float depth = texture2D(depthMap, (gl_FragCoord.xy - viewportOrigin) / resolution * depthUvScale).r;
+
+			if (depth < 1.0) {
+				result.a *= clamp((viewDepth(depth) - vViewDepth) / softDistance, 0.0, 1.0);
+			}
+		}
+
+		if (result.a < 0.01) discard;
+
+		gl_FragColor = result;
+	}
+"#;
+
+const READBACK_VERT: &str = r#"
+	attribute vec2 position;
+
+	varying vec2 vUv;
+
+	void main() {
+		vUv = position * 0.5 + 0.5;
+		gl_Position = vec4(position, 0.0, 1.0);
+	}
+"#;
+
+const READBACK_FRAG: &str = r#"
+	precision highp float;
+
+	uniform sampler2D depthMap;
+	uniform vec2 uvScale;
+
+	varying vec2 vUv;
+
+	// Spreads a depth in [0, 1) over four bytes, and marks empty pixels
+	// with all bits set
+	void main() {
+		float depth = texture2D(depthMap, vUv * uvScale).r;
+
+		if (depth >= 1.0) {
+			gl_FragColor = vec4(1.0);
+			return;
+		}
+
+		vec4 encoded = fract(depth * vec4(1.0, 255.0, 65025.0, 16581375.0));
+		gl_FragColor = encoded - encoded.yzww * vec4(vec3(1.0 / 255.0), 0.0);
+	}
+"#;
+
+/// Ratio between the camera depth and the copy collisions are found in.
+const READBACK_DOWNSCALE: i32 = 4;
+
+/// How a sprite's size is measured.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SpriteSize {
+	/// Width and height in world units.
+	World(Vec2),
+	/// Width and height in pixels, regardless of distance.
+	Pixels(Vec2),
+}
+
+/// A flipbook animation through frames of a sprite sheet.
+///
+/// Frames are picked from the time alone, so the animation follows the
+/// scene's clock, pausing and slowing down with it, and the same animation
+/// can drive any number of sprites or materials.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpriteAnimation {
+	pub frames: Vec<UvRect>,
+	pub fps: f32,
+	/// Restarts after the last frame instead of holding it.
+	pub looping: bool,
+	/// Time the first frame is shown at, in seconds.
+	pub start_time: f32,
+}
+
+impl SpriteAnimation {
+	/// Creates a looping animation starting at time zero.
+	pub fn new(frames: Vec<UvRect>, fps: f32) -> Self {
+		Self { frames, fps, looping: true, start_time: 0.0 }
+	}
+
+	pub fn with_looping(mut self, looping: bool) -> Self {
+		self.looping = looping;
+		self
+	}
+
+	pub fn with_start_time(mut self, time: f32) -> Self {
+		self.start_time = time;
+		self
+	}
+
+	/// Restarts the animation from the first frame at `time`.
+	pub fn play(&mut self, time: f32) {
+		self.start_time = time;
+	}
+
+	/// Returns the length of one pass through the frames, in seconds.
+	pub fn duration(&self) -> f32 {
+		if self.fps > 0.0 { self.frames.len() as f32 / self.fps } else { 0.0 }
+	}
+
+	/// Returns whether a non-looping animation has reached its last frame.
+	pub fn is_finished(&self, time: f32) -> bool {
+		!self.looping && time - self.start_time >= self.duration()
+	}
+
+	/// Returns the index of the frame shown at `time`. Times before the
+	/// start show the first frame.
+	///
+	/// ```
+	/// use oxgl::common::UvRect;
+	/// use oxgl::renderer_3d::SpriteAnimation;
+	///
+	/// let animation = SpriteAnimation::new(vec![UvRect::FULL; 4], 10.0);
+	///
+	/// assert_eq!(animation.frame_index(0.25), 2);
+	/// assert_eq!(animation.frame_index(0.45), 0);
+	/// assert_eq!(animation.with_looping(false).frame_index(0.45), 3);
+	/// ```
+	pub fn frame_index(&self, time: f32) -> usize {
+		let count = self.frames.len();
+
+		if count == 0 || self.fps <= 0.0 {
+			return 0;
+		}
+
+		let frame = ((time - self.start_time).max(0.0) * self.fps) as usize;
+
+		if self.looping { frame % count } else { frame.min(count - 1) }
+	}
+
+	/// Returns the region shown at `time`, or the whole texture without
+	/// frames.
+	pub fn frame_at(&self, time: f32) -> UvRect {
+		self.frames.get(self.frame_index(time)).copied().unwrap_or_default()
+	}
+
+	/// Sets the `uvRect` uniform of a custom material to the frame shown
+	/// at `time`, packed as `(offset.x, offset.y, scale.x, scale.y)`.
+	pub fn apply(&self, material: &mut Material, time: f32) {
+		material.set("uvRect", Uniform::Vec4(self.frame_at(time).to_vec4()));
+	}
+}
+
+/// How a moving sprite bounces off the scene.
+///
+/// Only surfaces the camera sees are hit, and a sprite passes behind
+/// anything more than [`thickness`](Self::thickness) in front of it.
+///
+/// ## Defaults
+///
+/// A bounce of `0.5`, a friction of `0.1` and a thickness of `0.5`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SpriteCollision {
+	/// Fraction of the speed into a surface kept when bouncing off it, from
+	/// 0 to 1.
+	pub bounce: f32,
+	/// Fraction of the speed along a surface lost on each hit, from 0 to 1.
+	pub friction: f32,
+	/// How far behind a visible surface a sprite still hits it, in world
+	/// units.
+	pub thickness: f32,
+}
+
+impl Default for SpriteCollision {
+	fn default() -> Self {
+		Self { bounce: 0.5, friction: 0.1, thickness: 0.5 }
+	}
+}
+
+impl SpriteCollision {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	pub fn with_bounce(mut self, bounce: f32) -> Self {
+		self.bounce = bounce.clamp(0.0, 1.0);
+		self
+	}
+
+	pub fn with_friction(mut self, friction: f32) -> Self {
+		self.friction = friction.clamp(0.0, 1.0);
+		self
+	}
+
+	pub fn with_thickness(mut self, thickness: f32) -> Self {
+		self.thickness = thickness.max(0.0);
+		self
+	}
+
+	/// Returns `velocity` after hitting a surface facing `normal`.
+	///
+	/// ```
+	/// use glam::Vec3;
+	/// use oxgl::renderer_3d::SpriteCollision;
+	///
+	/// let collision = SpriteCollision::new().with_bounce(0.5).with_friction(0.0);
+	/// let velocity = collision.respond(Vec3::new(1.0, -2.0, 0.0), Vec3::Y);
+	///
+	/// assert!((velocity - Vec3::new(1.0, 1.0, 0.0)).length() < 1e-5);
+	/// ```
+	pub fn respond(&self, velocity: Vec3, normal: Vec3) -> Vec3 {
+		let into = velocity.dot(normal);
+
+		if into >= 0.0 {
+			return velocity;
+		}
+
+		let along = velocity - normal * into;
+		along * (1.0 - self.friction) - normal * into * self.bounce
+	}
+}
+
+/// A camera-facing quad at a world position.
+#[derive(Clone)]
+pub struct Sprite {
+	pub position: Vec3,
+	pub size: SpriteSize,
+	/// Multiplied with the color. Untextured sprites are solid.
+	pub texture: Option<Texture>,
+	/// Region of the texture to show, such as one entry of a
+	/// [`TextureAtlas`](crate::common::TextureAtlas).
+	pub uv: UvRect,
+	/// Flipbook played instead of [`uv`](Self::uv).
+	pub animation: Option<SpriteAnimation>,
+	pub color: Vec4,
+	/// Rotation around the view direction, in radians.
+	pub rotation: f32,
+	/// Distance in world units over which the sprite fades out in front of
+	/// the geometry behind it, or `0.0` for a hard edge.
+	pub soft_distance: f32,
+	/// Adds the sprite's color to what's behind it instead of blending over
+	/// it, for glows, sparks and fire.
+	pub additive: bool,
+	/// Movement in world units per second, applied by the scene each frame.
+	pub velocity: Vec3,
+	/// Change of [`velocity`](Self::velocity) per second, such as gravity.
+	pub acceleration: Vec3,
+	/// Bounces the sprite off the scene while it moves.
+	pub collision: Option<SpriteCollision>,
+	pub visible: bool,
+	pub name: Option<String>,
+}
+
+impl Sprite {
+	pub fn new(position: Vec3, size: SpriteSize) -> Self {
+		Self {
+			position,
+			size,
+			texture: None,
+			uv: UvRect::FULL,
+			animation: None,
+			color: Vec4::ONE,
+			rotation: 0.0,
+			soft_distance: 0.0,
+			additive: false,
+			velocity: Vec3::ZERO,
+			acceleration: Vec3::ZERO,
+			collision: None,
+			visible: true,
+			name: None,
+		}
+	}
+
+	pub fn with_texture(mut self, texture: Texture) -> Self {
+		self.texture = Some(texture);
+		self
+	}
+
+	pub fn with_uv(mut self, uv: UvRect) -> Self {
+		self.uv = uv;
+		self
+	}
+
+	pub fn with_animation(mut self, animation: SpriteAnimation) -> Self {
+		self.animation = Some(animation);
+		self
+	}
+
+	/// Returns the texture region shown at `time`.
+	pub fn uv_at(&self, time: f32) -> UvRect {
+		self.animation.as_ref().map_or(self.uv, |animation| animation.frame_at(time))
+	}
+
+	pub fn with_color(mut self, color: Vec4) -> Self {
+		self.color = color;
+		self
+	}
+
+	pub fn with_rotation(mut self, rotation: f32) -> Self {
+		self.rotation = rotation;
+		self
+	}
+
+	pub fn with_soft_distance(mut self, distance: f32) -> Self {
+		self.soft_distance = distance;
+		self
+	}
+
+	pub fn with_additive(mut self, additive: bool) -> Self {
+		self.additive = additive;
+		self
+	}
+
+	pub fn with_velocity(mut self, velocity: Vec3) -> Self {
+		self.velocity = velocity;
+		self
+	}
+
+	pub fn with_acceleration(mut self, acceleration: Vec3) -> Self {
+		self.acceleration = acceleration;
+		self
+	}
+
+	pub fn with_collision(mut self, collision: SpriteCollision) -> Self {
+		self.collision = Some(collision);
+		self
+	}
+
+	/// Returns whether the sprite moves on its own.
+	pub fn is_moving(&self) -> bool {
+		self.velocity != Vec3::ZERO || self.acceleration != Vec3::ZERO
+	}
+
+	/// Moves the sprite by its velocity over `dt` seconds, bouncing off the
+	/// surfaces in `depth` if it collides.
+	pub(crate) fn advance(&mut self, dt: f32, depth: Option<&DepthReadback>) {
+		self.velocity += self.acceleration * dt;
+		let next = self.position + self.velocity * dt;
+
+		let contact = self.collision
+			.zip(depth)
+			.and_then(|(collision, depth)| depth.contact(next, collision.thickness).map(|normal| (collision, normal)));
+
+		match contact {
+			Some((collision, normal)) => {
+				self.velocity = collision.respond(self.velocity, normal);
+				self.position += self.velocity * dt;
+			}
+			None => self.position = next,
+		}
+	}
+
+	/// Returns whether the sprite fades against the scene depth.
+	pub fn is_soft(&self) -> bool {
+		self.soft_distance > 0.0
+	}
+
+	pub fn with_name(mut self, name: &str) -> Self {
+		self.name = Some(name.to_string());
+		self
+	}
+
+	/// Returns the on-screen size in pixels at a view-space depth.
+	pub fn screen_size(&self, camera: &Camera, depth: f32, viewport_height: f32) -> Vec2 {
+		match self.size {
+			SpriteSize::Pixels(size) => size,
+			SpriteSize::World(size) => {
+				let pixels_per_unit = viewport_height * 0.5 / (depth * (camera.fov_y * 0.5).tan());
+				size * pixels_per_unit
+			}
+		}
+	}
+}
+
+/// The result of picking a sprite.
+#[derive(Clone, Copy, Debug)]
+pub struct SpriteHit {
+	pub sprite: SpriteId,
+	/// Distance from the camera in world units.
+	pub distance: f32,
+}
+
+/// Draws sprites for a [`Scene`](super::Scene).
+pub(crate) struct SpriteRenderer {
+	program: WebGlProgram,
+	quad_buffer: WebGlBuffer,
+	/// Radial falloff shared by light halos.
+	halo_texture: Texture,
+}
+
+impl SpriteRenderer {
+	pub fn new(gl: &GL) -> Result<Self, String> {
+		let vert = compile_shader(gl, SPRITE_VERT, GL::VERTEX_SHADER)?;
+		let frag = compile_shader(gl, SPRITE_FRAG, GL::FRAGMENT_SHADER)?;
+		let program = link_program(gl, &vert, &frag)?;
+
+		let quad_buffer = gl.create_buffer().ok_or("Failed to create sprite buffer")?;
+		let corners: [f32; 8] = [-0.5, -0.5, 0.5, -0.5, -0.5, 0.5, 0.5, 0.5];
+		let data = unsafe {
+			std::slice::from_raw_parts(corners.as_ptr() as *const u8, std::mem::size_of_val(&corners))
+		};
+
+		gl.bind_buffer(GL::ARRAY_BUFFER, Some(&quad_buffer));
+		gl.buffer_data_with_u8_array(GL::ARRAY_BUFFER, data, GL::STATIC_DRAW);
+
+		let halo_texture = Texture::from_fn(gl, 64, 64, |u, v| {
+			let distance = (Vec2::new(u, v) - 0.5).length() * 2.0;
+			(1.0 - distance).max(0.0).powi(2)
+		})?;
+
+		Ok(Self { program, quad_buffer, halo_texture })
+	}
+
+	/// Returns the halo sprite of a light, if it draws one.
+	///
+	/// Halos fade over half their radius where they meet geometry, so a
+	/// bulb against a wall doesn't show a hard line.
+	pub fn halo(&self, light: &Light) -> Option<Sprite> {
+		let size = light.halo_size().filter(|&size| size > 0.0 && light.enabled)?;
+		let opacity = light.halo.map_or(1.0, |halo| halo.opacity);
+
+		Some(
+			Sprite::new(light.position, SpriteSize::World(Vec2::splat(size)))
+				.with_texture(self.halo_texture.clone())
+				.with_color(light.color.extend(opacity))
+				.with_soft_distance(size * 0.25)
+				.with_additive(true)
+		)
+	}
+
+	/// Draws sprites back to front with blending, additive sprites adding
+	/// to the image.
+	///
+	/// Sprites are depth tested against the scene but don't write depth.
+	/// Animated sprites show their frame at `time`. Soft sprites fade
+	/// against `depth`, which must hold the camera's depth at `width` by
+	/// `height` over the current viewport, and are drawn hard without it.
+	#[allow(clippy::too_many_arguments)]
+	pub fn draw<'a>(&self, gl: &GL, camera: &Camera, sprites: impl Iterator<Item = &'a Sprite>, depth: Option<&ContactShadowMap>, width: f32, height: f32, time: f32) {
+		let view = camera.view_matrix();
+
+		let mut sorted: Vec<(&Sprite, f32)> = sprites
+			.filter(|s| s.visible)
+			.map(|s| (s, view.transform_point3(s.position).z))
+			.collect();
+
+		if sorted.is_empty() {
+			return;
+		}
+
+		// View-space z is negative in front of the camera
+		sorted.sort_by(|a, b| a.1.total_cmp(&b.1));
+
+		let program = &self.program;
+
+		gl.use_program(Some(program));
+		set_matrix(gl, program, "view", &view);
+		let projection = camera.projection_matrix();
+		set_matrix(gl, program, "projection", &projection);
+
+		if let Some(loc) = gl.get_uniform_location(program, "viewport") {
+			gl.uniform2f(Some(&loc), width, height);
+		}
+		if let Some(loc) = gl.get_uniform_location(program, "logDepthFactor") {
+			gl.uniform1f(Some(&loc), camera.log_depth_factor());
+		}
+		if let Some(loc) = gl.get_uniform_location(program, "spriteTexture") {
+			gl.uniform1i(Some(&loc), 0);
+		}
+		if let Some(loc) = gl.get_uniform_location(program, "depthMap") {
+			gl.uniform1i(Some(&loc), 1);
+		}
+		if let Some(loc) = gl.get_uniform_location(program, "resolution") {
+			gl.uniform2f(Some(&loc), width, height);
+		}
+		if let Some(loc) = gl.get_uniform_location(program, "viewportOrigin") {
+			let [x, y, _, _] = current_viewport(gl);
+			gl.uniform2f(Some(&loc), x as f32, y as f32);
+		}
+		if let Some(loc) = gl.get_uniform_location(program, "depthProjection") {
+			gl.uniform2f(Some(&loc), projection.z_axis.z, projection.w_axis.z);
+		}
+
+		if let Some(map) = depth {
+			map.bind_texture(gl, 1);
+
+			if let Some(loc) = gl.get_uniform_location(program, "depthUvScale") {
+				gl.uniform2fv_with_f32_array(Some(&loc), &map.uv_scale().to_array());
+			}
+		}
+
+		gl.bind_buffer(GL::ARRAY_BUFFER, Some(&self.quad_buffer));
+
+		let corner_loc = gl.get_attrib_location(program, "corner");
+
+		if corner_loc >= 0 {
+			gl.enable_vertex_attrib_array(corner_loc as u32);
+			gl.vertex_attrib_pointer_with_i32(corner_loc as u32, 2, GL::FLOAT, false, 8, 0);
+		}
+
+		gl.depth_mask(false);
+		gl.enable(GL::BLEND);
+
+		for (sprite, _) in sorted {
+			if sprite.additive {
+				gl.blend_func_separate(GL::SRC_ALPHA, GL::ONE, GL::ZERO, GL::ONE);
+			} else {
+				blend_over(gl);
+			}
+
+			let (size, pixels) = match sprite.size {
+				SpriteSize::World(size) => (size, false),
+				SpriteSize::Pixels(size) => (size, true),
+			};
+
+			if let Some(loc) = gl.get_uniform_location(program, "center") {
+				gl.uniform3fv_with_f32_array(Some(&loc), &sprite.position.to_array());
+			}
+			if let Some(loc) = gl.get_uniform_location(program, "size") {
+				gl.uniform2fv_with_f32_array(Some(&loc), &size.to_array());
+			}
+			if let Some(loc) = gl.get_uniform_location(program, "pixelSize") {
+				gl.uniform1i(Some(&loc), pixels as i32);
+			}
+			if let Some(loc) = gl.get_uniform_location(program, "rotation") {
+				gl.uniform1f(Some(&loc), sprite.rotation);
+			}
+			if let Some(loc) = gl.get_uniform_location(program, "color") {
+				gl.uniform4fv_with_f32_array(Some(&loc), &sprite.color.to_array());
+			}
+			if let Some(loc) = gl.get_uniform_location(program, "uvRect") {
+				gl.uniform4fv_with_f32_array(Some(&loc), &sprite.uv_at(time).to_vec4().to_array());
+			}
+			if let Some(loc) = gl.get_uniform_location(program, "useTexture") {
+				gl.uniform1i(Some(&loc), sprite.texture.is_some() as i32);
+			}
+			if let Some(loc) = gl.get_uniform_location(program, "softDistance") {
+				let soft_distance = if depth.is_some() { sprite.soft_distance } else { 0.0 };
+				gl.uniform1f(Some(&loc), soft_distance);
+			}
+
+			if let Some(texture) = &sprite.texture {
+				texture.bind(gl, 0);
+			}
+
+			gl.draw_arrays(GL::TRIANGLE_STRIP, 0, 4);
+		}
+
+		gl.disable(GL::BLEND);
+		gl.depth_mask(true);
+	}
+}
+
+/// A small copy of the camera depth on the CPU, that moving sprites collide
+/// with.
+pub(crate) struct DepthReadback {
+	program: WebGlProgram,
+	triangle_buffer: WebGlBuffer,
+	framebuffer: WebGlFramebuffer,
+	texture: WebGlTexture,
+	width: i32,
+	height: i32,
+	pixels: Vec<u8>,
+	/// Depth in `[0, 1]` per pixel, from the bottom row up.
+	depth: Vec<f32>,
+	view: Mat4,
+	inverse_view_projection: Mat4,
+	view_projection: Mat4,
+}
+
+impl DepthReadback {
+	pub fn new(gl: &GL) -> Result<Self, String> {
+		let vert = compile_shader(gl, READBACK_VERT, GL::VERTEX_SHADER)?;
+		let frag = compile_shader(gl, READBACK_FRAG, GL::FRAGMENT_SHADER)?;
+		let program = link_program(gl, &vert, &frag)?;
+
+		let triangle_buffer = gl.create_buffer().ok_or("Failed to create depth readback buffer")?;
+		let corners: [f32; 6] = [-1.0, -1.0, 3.0, -1.0, -1.0, 3.0];
+		let data = unsafe {
+			std::slice::from_raw_parts(corners.as_ptr() as *const u8, std::mem::size_of_val(&corners))
+		};
+
+		gl.bind_buffer(GL::ARRAY_BUFFER, Some(&triangle_buffer));
+		gl.buffer_data_with_u8_array(GL::ARRAY_BUFFER, data, GL::STATIC_DRAW);
+
+		let framebuffer = gl.create_framebuffer().ok_or("Failed to create depth readback framebuffer")?;
+		let texture = gl.create_texture().ok_or("Failed to create depth readback texture")?;
+
+		gl.bind_texture(GL::TEXTURE_2D, Some(&texture));
+		gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_MIN_FILTER, GL::NEAREST as i32);
+		gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_MAG_FILTER, GL::NEAREST as i32);
+		gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_WRAP_S, GL::CLAMP_TO_EDGE as i32);
+		gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_WRAP_T, GL::CLAMP_TO_EDGE as i32);
+
+		Ok(Self {
+			program,
+			triangle_buffer,
+			framebuffer,
+			texture,
+			width: 0,
+			height: 0,
+			pixels: Vec::new(),
+			depth: Vec::new(),
+			view: Mat4::IDENTITY,
+			inverse_view_projection: Mat4::IDENTITY,
+			view_projection: Mat4::IDENTITY,
+		})
+	}
+
+	/// Copies the region of `map` the last depth pass covered, rendered
+	/// with the `view` matrix, to the CPU at a quarter of its resolution.
+	///
+	/// Reading back waits for the GPU, so this is done once per frame. The
+	/// framebuffer is unbound afterwards and the viewport restored.
+	pub fn read(&mut self, gl: &GL, map: &ContactShadowMap, view: Mat4) -> Result<(), String> {
+		let width = (map.view_width / READBACK_DOWNSCALE).max(1);
+		let height = (map.view_height / READBACK_DOWNSCALE).max(1);
+
+		if (self.width, self.height) != (width, height) {
+			self.width = width;
+			self.height = height;
+			self.pixels = vec![0; (width * height * 4) as usize];
+
+			gl.bind_texture(GL::TEXTURE_2D, Some(&self.texture));
+			gl.tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
+				GL::TEXTURE_2D, 0, GL::RGBA as i32, width, height, 0, GL::RGBA, GL::UNSIGNED_BYTE, None,
+			).map_err(|e| format!("Failed to allocate depth readback texture: {:?}", e))?;
+
+			gl.bind_framebuffer(GL::FRAMEBUFFER, Some(&self.framebuffer));
+			gl.framebuffer_texture_2d(GL::FRAMEBUFFER, GL::COLOR_ATTACHMENT0, GL::TEXTURE_2D, Some(&self.texture), 0);
+		}
+
+		let state = SavedState::capture(gl);
+		let [x, y, viewport_width, viewport_height] = current_viewport(gl);
+		let program = &self.program;
+
+		gl.bind_framebuffer(GL::FRAMEBUFFER, Some(&self.framebuffer));
+		gl.viewport(0, 0, width, height);
+		gl.disable(GL::DEPTH_TEST);
+		gl.disable(GL::BLEND);
+		gl.disable(GL::CULL_FACE);
+
+		gl.use_program(Some(program));
+
+		if let Some(loc) = gl.get_uniform_location(program, "depthMap") {
+			gl.uniform1i(Some(&loc), 0);
+		}
+		if let Some(loc) = gl.get_uniform_location(program, "uvScale") {
+			gl.uniform2fv_with_f32_array(Some(&loc), &map.uv_scale().to_array());
+		}
+
+		map.bind_texture(gl, 0);
+		gl.bind_buffer(GL::ARRAY_BUFFER, Some(&self.triangle_buffer));
+
+		let position_loc = gl.get_attrib_location(program, "position");
+
+		if position_loc >= 0 {
+			gl.enable_vertex_attrib_array(position_loc as u32);
+			gl.vertex_attrib_pointer_with_i32(position_loc as u32, 2, GL::FLOAT, false, 8, 0);
+		}
+
+		gl.draw_arrays(GL::TRIANGLES, 0, 3);
+
+		let read = gl.read_pixels_with_opt_u8_array(0, 0, width, height, GL::RGBA, GL::UNSIGNED_BYTE, Some(&mut self.pixels));
+
+		gl.bind_framebuffer(GL::FRAMEBUFFER, None);
+		gl.viewport(x, y, viewport_width, viewport_height);
+		state.restore(gl);
+
+		read.map_err(|e| format!("Failed to read back depth: {:?}", e))?;
+
+		self.depth.clear();
+		self.depth.extend(self.pixels.chunks_exact(4).map(|rgba| {
+			if rgba == [255; 4] {
+				return 1.0;
+			}
+
+			rgba.iter()
+				.zip([1.0, 1.0 / 255.0, 1.0 / 65025.0, 1.0 / 16581375.0])
+				.map(|(&byte, scale)| byte as f32 / 255.0 * scale)
+				.sum()
+		}));
+
+		self.view = view;
+		self.view_projection = map.view_projection;
+		self.inverse_view_projection = map.view_projection.inverse();
+
+		Ok(())
+	}
+
+	/// Returns the world position of the surface seen at a pixel, or `None`
+	/// off screen and where nothing was drawn.
+	fn surface(&self, x: i32, y: i32) -> Option<Vec3> {
+		if x < 0 || y < 0 || x >= self.width || y >= self.height {
+			return None;
+		}
+
+		let depth = *self.depth.get((y * self.width + x) as usize)?;
+
+		if depth >= 1.0 {
+			return None;
+		}
+
+		let ndc = Vec3::new(
+			(x as f32 + 0.5) / self.width as f32 * 2.0 - 1.0,
+			(y as f32 + 0.5) / self.height as f32 * 2.0 - 1.0,
+			depth * 2.0 - 1.0,
+		);
+
+		Some(self.inverse_view_projection.project_point3(ndc))
+	}
+
+	/// Returns the normal of the surface `position` is behind, if it's
+	/// less than `thickness` behind it.
+	pub fn contact(&self, position: Vec3, thickness: f32) -> Option<Vec3> {
+		let clip = self.view_projection * position.extend(1.0);
+
+		if clip.w <= 0.0 {
+			return None;
+		}
+
+		let ndc = clip.truncate() / clip.w;
+		let x = ((ndc.x * 0.5 + 0.5) * self.width as f32).floor() as i32;
+		let y = ((ndc.y * 0.5 + 0.5) * self.height as f32).floor() as i32;
+
+		let surface = self.surface(x, y)?;
+		let view_depth = |point: Vec3| -self.view.transform_point3(point).z;
+		let behind = view_depth(position) - view_depth(surface);
+
+		if !(0.0..=thickness).contains(&behind) {
+			return None;
+		}
+
+		// Neighbours on either side, falling back to the other one at edges
+		// and silhouettes
+		let neighbour = |dx: i32, dy: i32| {
+			self.surface(x + dx, y + dy)
+				.map(|point| point - surface)
+				.or_else(|| self.surface(x - dx, y - dy).map(|point| surface - point))
+		};
+
+		let normal = neighbour(1, 0)?.cross(neighbour(0, 1)?).normalize_or_zero();
+
+		if normal == Vec3::ZERO {
+			return None;
+		}
+
+		// Turn the normal towards the camera, the side the sprite came from
+		let to_camera = self.inverse_view_projection.project_point3(Vec3::new(ndc.x, ndc.y, -1.0)) - surface;
+
+		Some(if normal.dot(to_camera) < 0.0 { -normal } else { normal })
+	}
+}
+
+fn set_matrix(gl: &GL, program: &WebGlProgram, name: &str, matrix: &Mat4) {
+	if let Some(loc) = gl.get_uniform_location(program, name) {
+		gl.uniform_matrix4fv_with_f32_array(Some(&loc), false, &matrix.to_cols_array());
+	}
+}
@@ -0,0 +1,238 @@
+//! Renderer Statistics
+//!
+//! Provides per-frame [`RenderStats`] collected by the [`Scene`](super::Scene),
+//! and a [`StatsOverlay`] that shows them over the canvas with an FPS
+//! counter and frame time graph.
+//!
+//! [`App`](crate::App) shows the overlay when
+//! [`DebugSettings::show_stats`](super::DebugSettings::show_stats) is set.
+//! Apps with their own render loop can drive it directly.
+//!
+//! ## Examples
+//!
+//! ```ignore
+//! use oxgl::renderer_3d::StatsOverlay;
+//!
+//! let mut overlay = StatsOverlay::new(renderer.canvas())?;
+//!
+//! // In the render loop, after rendering
+//! scene.render(&renderer, time);
+//! overlay.update(scene.stats(), time);
+//! ```
+//!
+
+use std::collections::VecDeque;
+use web_sys::{
+	CanvasRenderingContext2d, HtmlCanvasElement, HtmlElement, wasm_bindgen::JsCast,
+};
+
+/// Number of frames shown in the frame time graph.
+const GRAPH_FRAMES: usize = 120;
+
+/// Frame time at the top of the graph, in milliseconds.
+const GRAPH_MAX_MS: f32 = 50.0;
+
+/// Seconds between text refreshes, so the numbers stay readable.
+const TEXT_INTERVAL: f32 = 0.25;
+
+/// Counters for the last rendered frame.
+///
+/// Includes every pass: shadow and contact shadow depth passes, render
+/// targets and viewports rendered since the previous frame.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RenderStats {
+	pub draw_calls: u32,
+	pub triangles: u32,
+	/// Objects in the scene.
+	pub objects: usize,
+	/// Objects drawn after culling, summed over all views.
+	pub visible_objects: usize,
+	/// Objects skipped because their last occlusion query found them hidden.
+	pub occluded_objects: usize,
+	/// Occlusion queries issued, see [`Scene::set_occlusion_culling`](super::Scene::set_occlusion_culling).
+	pub occlusion_queries: u32,
+	/// Enabled lights.
+	pub lights: usize,
+	/// Estimated GPU memory used by material and sprite textures, shadow
+	/// maps and post-processing buffers, in bytes.
+	pub texture_bytes: usize,
+}
+
+impl RenderStats {
+	/// Records a draw call.
+	pub(crate) fn draw(&mut self, triangles: u32) {
+		self.draw_calls += 1;
+		self.triangles += triangles;
+	}
+}
+
+/// An on-screen panel showing FPS, a frame time graph and [`RenderStats`].
+///
+/// Built from HTML elements placed over the top-left corner of the canvas,
+/// so it doesn't affect rendering.
+pub struct StatsOverlay {
+	container: HtmlElement,
+	text: HtmlElement,
+	context: CanvasRenderingContext2d,
+	frame_times: VecDeque<f32>,
+	last_time: Option<f32>,
+	last_text_time: f32,
+}
+
+impl StatsOverlay {
+	/// Creates the overlay as a sibling of the canvas.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the canvas has no parent or the elements can't
+	/// be created.
+	pub fn new(canvas: &HtmlCanvasElement) -> Result<Self, String> {
+		let document = web_sys::window()
+			.and_then(|w| w.document())
+			.ok_or("No document")?;
+		let parent = canvas.parent_element().ok_or("Canvas has no parent")?;
+
+		if let Ok(parent) = parent.clone().dyn_into::<HtmlElement>() {
+			let _ = parent.style().set_property("position", "relative");
+		}
+
+		let create = |tag: &str| {
+			document
+				.create_element(tag)
+				.map_err(|_| format!("Failed to create {}", tag))?
+				.dyn_into::<HtmlElement>()
+				.map_err(|_| format!("Failed to cast {}", tag))
+		};
+
+		let container = create("div")?;
+		let style = container.style();
+		let _ = style.set_property("position", "absolute");
+		let _ = style.set_property("top", "8px");
+		let _ = style.set_property("left", "8px");
+		let _ = style.set_property("padding", "6px 8px");
+		let _ = style.set_property("background", "rgba(0, 0, 0, 0.65)");
+		let _ = style.set_property("color", "#e0e0e0");
+		let _ = style.set_property("font", "11px monospace");
+		let _ = style.set_property("line-height", "1.4");
+		let _ = style.set_property("white-space", "pre");
+		let _ = style.set_property("pointer-events", "none");
+		let _ = style.set_property("z-index", "2");
+
+		let text = create("div")?;
+
+		let graph = create("canvas")?
+			.dyn_into::<HtmlCanvasElement>()
+			.map_err(|_| "Failed to cast graph canvas")?;
+
+		graph.set_width(GRAPH_FRAMES as u32);
+		graph.set_height(40);
+		let _ = graph.style().set_property("display", "block");
+		let _ = graph.style().set_property("margin-top", "4px");
+
+		let context = graph
+			.get_context("2d")
+			.ok()
+			.flatten()
+			.and_then(|c| c.dyn_into::<CanvasRenderingContext2d>().ok())
+			.ok_or("Failed to get 2D context")?;
+
+		container.append_child(&text).map_err(|_| "Failed to append stats text")?;
+		container.append_child(&graph).map_err(|_| "Failed to append stats graph")?;
+
+		parent
+			.insert_before(&container, canvas.next_sibling().as_ref())
+			.map_err(|_| "Failed to insert stats overlay")?;
+
+		Ok(Self {
+			container,
+			text,
+			context,
+			frame_times: VecDeque::with_capacity(GRAPH_FRAMES),
+			last_time: None,
+			last_text_time: f32::NEG_INFINITY,
+		})
+	}
+
+	/// Records a frame and refreshes the panel.
+	///
+	/// Call once per frame with the elapsed time in seconds.
+	pub fn update(&mut self, stats: &RenderStats, time: f32) {
+		if let Some(last) = self.last_time.replace(time) {
+			if self.frame_times.len() == GRAPH_FRAMES {
+				self.frame_times.pop_front();
+			}
+
+			self.frame_times.push_back((time - last) * 1000.0);
+		}
+
+		self.draw_graph();
+
+		if time - self.last_text_time >= TEXT_INTERVAL {
+			self.last_text_time = time;
+			self.text.set_text_content(Some(&self.describe(stats)));
+		}
+	}
+
+	pub fn set_visible(&self, visible: bool) {
+		let _ = self.container.style().set_property("display", if visible { "block" } else { "none" });
+	}
+
+	/// Returns the average frame time over the graph, in milliseconds.
+	pub fn average_frame_time(&self) -> f32 {
+		if self.frame_times.is_empty() {
+			return 0.0;
+		}
+
+		self.frame_times.iter().sum::<f32>() / self.frame_times.len() as f32
+	}
+
+	fn describe(&self, stats: &RenderStats) -> String {
+		let frame_time = self.average_frame_time();
+		let fps = if frame_time > 0.0 { 1000.0 / frame_time } else { 0.0 };
+
+		format!(
+			"FPS        {:.0} ({:.1} ms)\nDraw calls {}\nTriangles  {}\nObjects    {} / {} ({} occluded)\nLights     {}\nTextures   ~{:.1} MB",
+			fps,
+			frame_time,
+			stats.draw_calls,
+			stats.triangles,
+			stats.visible_objects,
+			stats.objects,
+			stats.occluded_objects,
+			stats.lights,
+			stats.texture_bytes as f64 / (1024.0 * 1024.0),
+		)
+	}
+
+	fn draw_graph(&self) {
+		let context = &self.context;
+		let height = 40.0;
+		let scale = height / GRAPH_MAX_MS as f64;
+
+		context.clear_rect(0.0, 0.0, GRAPH_FRAMES as f64, height);
+
+		let offset = GRAPH_FRAMES - self.frame_times.len();
+
+		for (i, &ms) in self.frame_times.iter().enumerate() {
+			let color = match ms {
+				ms if ms > 33.4 => "#e05050",
+				ms if ms > 16.8 => "#e0c050",
+				_ => "#50c070",
+			};
+			let bar = (ms as f64 * scale).min(height);
+
+			context.set_fill_style_str(color);
+			context.fill_rect((offset + i) as f64, height - bar, 1.0, bar);
+		}
+
+		// 60 FPS reference line
+		context.set_fill_style_str("rgba(255, 255, 255, 0.4)");
+		context.fill_rect(0.0, height - 16.7 * scale, GRAPH_FRAMES as f64, 1.0);
+	}
+}
+
+impl Drop for StatsOverlay {
+	fn drop(&mut self) {
+		self.container.remove();
+	}
+}
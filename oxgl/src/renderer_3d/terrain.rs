@@ -0,0 +1,299 @@
+//! Heightmap Terrain
+//!
+//! Provides a [`Terrain`] generator that turns a grid of height samples into
+//! lit meshes. Heights can come from a grayscale image or any function, such
+//! as a noise generator.
+//!
+//! Large terrains can be split into chunks, each its own mesh, so the scene
+//! can skip chunks outside the camera's view.
+//!
+//! ## Examples
+//!
+//! ```ignore
+//! use oxgl::renderer_3d::Terrain;
+//! use oxgl::common::material::presets;
+//! use oxgl::core::Transform3D;
+//! use glam::Vec3;
+//!
+//! // From a function of the normalized grid coordinates
+//! let terrain = Terrain::from_fn(256, |u, v| ((u * 12.0).sin() * (v * 9.0).cos()) * 0.5 + 0.5)
+//!		.with_size(200.0, 200.0)
+//!		.with_height_scale(12.0)
+//!		.with_chunks(8);
+//!
+//! for mesh in terrain.build(&gl, presets::lambert(&gl, Vec3::new(0.3, 0.6, 0.2))) {
+//!		scene.add(mesh, Transform3D::new());
+//! }
+//!
+//! // Or from a heightmap image that has finished loading
+//! let terrain = Terrain::from_image(&image)?.with_height_scale(20.0);
+//!
+//! // Keep objects on the ground
+//! player.position.y = terrain.height_at(player.position.x, player.position.z);
+//! ```
+//!
+
+use glam::{Vec2, Vec3};
+use web_sys::{
+	CanvasRenderingContext2d, HtmlCanvasElement, HtmlImageElement,
+	WebGl2RenderingContext as GL, wasm_bindgen::JsCast,
+};
+
+use crate::common::{Material, Mesh, MeshData};
+
+/// A grid of height samples that generates terrain meshes.
+///
+/// Samples are laid out in rows along X, with rows advancing along +Z. The
+/// terrain is centered on the origin, and heights of `0.0` to `1.0` map to
+/// `0.0` to [`height_scale`](Self::height_scale) world units.
+#[derive(Clone, Debug)]
+pub struct Terrain {
+	heights: Vec<f32>,
+	columns: u32,
+	rows: u32,
+	/// World-space extent along X and Z.
+	pub size: Vec2,
+	pub height_scale: f32,
+	/// Number of times texture coordinates repeat across the terrain.
+	pub uv_tiling: f32,
+	/// Number of chunks along each side.
+	pub chunks: u32,
+}
+
+impl Terrain {
+	/// Creates a terrain from height samples.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the grid is smaller than 2×2 samples or the
+	/// number of heights doesn't match it.
+	pub fn from_heights(columns: u32, rows: u32, heights: Vec<f32>) -> Result<Self, String> {
+		if columns < 2 || rows < 2 {
+			return Err(format!("Terrain needs at least 2x2 samples, got {}x{}", columns, rows));
+		}
+
+		if heights.len() != (columns * rows) as usize {
+			return Err(format!(
+				"Expected {} heights for a {}x{} terrain, got {}",
+				columns * rows, columns, rows, heights.len()
+			));
+		}
+
+		Ok(Self {
+			heights,
+			columns,
+			rows,
+			size: Vec2::splat(10.0),
+			height_scale: 1.0,
+			uv_tiling: 1.0,
+			chunks: 1,
+		})
+	}
+
+	/// Creates a square terrain by sampling a function.
+	///
+	/// The function receives grid coordinates from `0.0` to `1.0` along X
+	/// and Z, and returns the height at that point.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use oxgl::renderer_3d::Terrain;
+	///
+	/// let ramp = Terrain::from_fn(3, |u, _| u).with_size(2.0, 2.0);
+	///
+	/// assert_eq!(ramp.height_at(1.0, 0.0), 1.0);
+	/// assert_eq!(ramp.height_at(-0.5, 0.0), 0.25);
+	/// ```
+	pub fn from_fn(resolution: u32, height: impl Fn(f32, f32) -> f32) -> Self {
+		let resolution = resolution.max(2);
+		let last = (resolution - 1) as f32;
+
+		let heights = (0..resolution)
+			.flat_map(|row| (0..resolution).map(move |column| (column, row)))
+			.map(|(column, row)| height(column as f32 / last, row as f32 / last))
+			.collect();
+
+		Self::from_heights(resolution, resolution, heights)
+			.expect("Sample count matches the grid")
+	}
+
+	/// Creates a terrain from the brightness of an image, one sample per
+	/// pixel.
+	///
+	/// The image must have finished loading.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the image can't be read back, for example when it
+	/// is cross-origin without CORS, or is smaller than 2×2 pixels.
+	pub fn from_image(image: &HtmlImageElement) -> Result<Self, String> {
+		let (width, height) = (image.natural_width(), image.natural_height());
+
+		let canvas = web_sys::window()
+			.and_then(|w| w.document())
+			.ok_or("No document")?
+			.create_element("canvas")
+			.map_err(|_| "Failed to create heightmap canvas")?
+			.dyn_into::<HtmlCanvasElement>()
+			.map_err(|_| "Failed to cast heightmap canvas")?;
+
+		canvas.set_width(width);
+		canvas.set_height(height);
+
+		let context = canvas
+			.get_context("2d")
+			.ok()
+			.flatten()
+			.and_then(|c| c.dyn_into::<CanvasRenderingContext2d>().ok())
+			.ok_or("Failed to get 2D context")?;
+
+		context
+			.draw_image_with_html_image_element(image, 0.0, 0.0)
+			.map_err(|e| format!("Failed to draw heightmap: {:?}", e))?;
+
+		let pixels = context
+			.get_image_data(0.0, 0.0, width as f64, height as f64)
+			.map_err(|e| format!("Failed to read heightmap: {:?}", e))?
+			.data();
+
+		let heights = pixels
+			.chunks_exact(4)
+			.map(|p| (0.299 * p[0] as f32 + 0.587 * p[1] as f32 + 0.114 * p[2] as f32) / 255.0)
+			.collect();
+
+		Self::from_heights(width, height, heights)
+	}
+
+	pub fn with_size(mut self, width: f32, depth: f32) -> Self {
+		self.size = Vec2::new(width, depth);
+		self
+	}
+
+	pub fn with_height_scale(mut self, scale: f32) -> Self {
+		self.height_scale = scale;
+		self
+	}
+
+	pub fn with_uv_tiling(mut self, tiling: f32) -> Self {
+		self.uv_tiling = tiling;
+		self
+	}
+
+	/// Splits the terrain into `chunks × chunks` meshes.
+	pub fn with_chunks(mut self, chunks: u32) -> Self {
+		self.chunks = chunks.max(1);
+		self
+	}
+
+	pub fn columns(&self) -> u32 {
+		self.columns
+	}
+
+	pub fn rows(&self) -> u32 {
+		self.rows
+	}
+
+	/// Returns the world-space height at a point, interpolated between
+	/// samples.
+	///
+	/// Points outside the terrain use the nearest edge.
+	pub fn height_at(&self, x: f32, z: f32) -> f32 {
+		let grid = (Vec2::new(x, z) / self.size + 0.5) * self.cell_count();
+		let max = self.cell_count();
+		let grid = grid.clamp(Vec2::ZERO, max);
+
+		let (c0, r0) = (grid.x.floor().min(max.x - 1.0), grid.y.floor().min(max.y - 1.0));
+		let (tx, tz) = (grid.x - c0, grid.y - r0);
+		let (c0, r0) = (c0 as u32, r0 as u32);
+
+		let top = lerp(self.sample(c0, r0), self.sample(c0 + 1, r0), tx);
+		let bottom = lerp(self.sample(c0, r0 + 1), self.sample(c0 + 1, r0 + 1), tx);
+
+		lerp(top, bottom, tz) * self.height_scale
+	}
+
+	/// Returns the mesh data for every chunk, in row order.
+	pub fn chunk_data(&self) -> Vec<MeshData> {
+		let cells = (self.columns - 1, self.rows - 1);
+		let chunks = self.chunks.min(cells.0).min(cells.1);
+
+		let mut result = Vec::with_capacity((chunks * chunks) as usize);
+
+		for chunk_row in 0..chunks {
+			for chunk_column in 0..chunks {
+				let columns = (cells.0 * chunk_column / chunks)..(cells.0 * (chunk_column + 1) / chunks);
+				let rows = (cells.1 * chunk_row / chunks)..(cells.1 * (chunk_row + 1) / chunks);
+
+				let mut data = MeshData {
+					name: (chunks > 1).then(|| format!("Terrain {}x{}", chunk_column, chunk_row)),
+					..Default::default()
+				};
+
+				for row in rows {
+					for column in columns.clone() {
+						let (a, b) = ((column, row), (column + 1, row));
+						let (c, d) = ((column, row + 1), (column + 1, row + 1));
+
+						for (column, row) in [a, c, b, b, c, d] {
+							self.push_vertex(&mut data, column, row);
+						}
+					}
+				}
+
+				result.push(data);
+			}
+		}
+
+		result
+	}
+
+	/// Creates one mesh per chunk, sharing a material.
+	///
+	/// Each chunk has tight bounds, so chunks outside the view are culled
+	/// by [`Scene`](super::Scene) when
+	/// [`frustum_culling`](super::Scene::frustum_culling) is enabled.
+	pub fn build(&self, gl: &GL, material: Material) -> Vec<Mesh> {
+		self.chunk_data()
+			.iter()
+			.map(|data| Mesh::from_data(gl, data, material.clone()))
+			.collect()
+	}
+
+	fn cell_count(&self) -> Vec2 {
+		Vec2::new((self.columns - 1) as f32, (self.rows - 1) as f32)
+	}
+
+	fn sample(&self, column: u32, row: u32) -> f32 {
+		let column = column.min(self.columns - 1);
+		let row = row.min(self.rows - 1);
+
+		self.heights[(row * self.columns + column) as usize]
+	}
+
+	fn push_vertex(&self, data: &mut MeshData, column: u32, row: u32) {
+		let uv = Vec2::new(column as f32, row as f32) / self.cell_count();
+		let spacing = self.size / self.cell_count();
+		let ground = (uv - 0.5) * self.size;
+		let height = self.sample(column, row) * self.height_scale;
+
+		// Central differences, one-sided at the edges
+		let (left, right) = (column.saturating_sub(1), (column + 1).min(self.columns - 1));
+		let (back, front) = (row.saturating_sub(1), (row + 1).min(self.rows - 1));
+
+		let dx = (self.sample(right, row) - self.sample(left, row)) * self.height_scale
+			/ ((right - left) as f32 * spacing.x);
+		let dz = (self.sample(column, front) - self.sample(column, back)) * self.height_scale
+			/ ((front - back) as f32 * spacing.y);
+
+		let normal = Vec3::new(-dx, 1.0, -dz).normalize();
+
+		data.positions.extend_from_slice(&[ground.x, height, ground.y]);
+		data.normals.extend_from_slice(&normal.to_array());
+		data.uvs.extend_from_slice(&(uv * self.uv_tiling).to_array());
+	}
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+	a + (b - a) * t
+}
@@ -0,0 +1,646 @@
+//! SDF Text
+//!
+//! Provides text laid out as flat quads in the world, such as signs, titles
+//! and floating damage numbers. Glyphs come from a signed distance field
+//! font atlas, so edges stay sharp up close and can be outlined.
+//!
+//! Fonts are loaded from the JSON and texture written by
+//! [msdf-bmfont-xml](https://github.com/soimy/msdf-bmfont-xml) or any tool
+//! following its layout, with either single-channel (`sdf`, `psdf`) or
+//! multi-channel (`msdf`, `mtsdf`) fields.
+//!
+//! Text is drawn after the scene's sprites, sorted back to front and
+//! without writing depth, so overlapping text blends correctly.
+//!
+//! ## Examples
+//!
+//! ```ignore
+//! use oxgl::common::AssetLoader;
+//! use oxgl::renderer_3d::{SdfFont, TextAlign, TextMesh};
+//! use std::rc::Rc;
+//!
+//! let loader = AssetLoader::new().with_base_url("assets/");
+//!
+//! // The atlas texture is found through the JSON's `pages`
+//! loader.on_font(&gl, "fonts/roboto-msdf.json", move |font| {
+//!		let font = Rc::new(font?);
+//!
+//!		let sign = TextMesh::new(&gl, &font, "Welcome\nto the museum")?
+//!			.with_size(0.5)
+//!			.with_align(TextAlign::Center)
+//!			.with_color(Vec4::new(1.0, 0.9, 0.6, 1.0))
+//!			.with_outline(Vec4::new(0.0, 0.0, 0.0, 1.0), 0.15)
+//!			.with_transform(Transform3D::new().with_position(Vec3::new(0.0, 3.0, -4.0)));
+//!
+//!		let id = scene.borrow_mut().add_text(sign);
+//!		Ok(())
+//! });
+//!
+//! // Later
+//! scene.texts[id].set_text("Closed");
+//! ```
+//!
+
+use std::{collections::HashMap, rc::Rc};
+use glam::{Mat4, Vec2, Vec3, Vec4};
+use js_sys::{Array, JSON, Reflect};
+use web_sys::{WebGlBuffer, WebGlProgram, WebGl2RenderingContext as GL, wasm_bindgen::JsValue};
+
+use crate::common::{compile_shader, link_program, AssetHandle, AssetLoader, Camera, Texture, render_state::SavedState};
+use crate::core::{Transform3D, Transformable};
+use super::background::blend_over;
+
+const TEXT_VERT: &str = r#"
+	#include <logdepth>
+
+	attribute vec2 position;
+	attribute vec2 uv;
+
+	uniform mat4 model;
+	uniform mat4 view;
+	uniform mat4 projection;
+
+	varying vec2 vUv;
+
+	void main() {
+		vUv = uv;
+		gl_Position = logDepth(projection * view * model * vec4(position, 0.0, 1.0));
+	}
+"#;
+
+const TEXT_FRAG: &str = r#"
+	#extension GL_OES_standard_derivatives : enable
+
+	precision mediump float;
+
+	uniform sampler2D fontAtlas;
+	uniform bool multiChannel;
+	uniform vec4 color;
+	uniform vec4 outlineColor;
+	uniform float outlineWidth;
+
+	varying vec2 vUv;
+
+	float median(float r, float g, float b) {
+		return max(min(r, g), min(max(r, g), b));
+	}
+
+	void main() {
+		vec4 texel = texture2D(fontAtlas, vUv);
+
+		// Single-channel tools store the field in either red or alpha and
+		// leave the other at full
+		float distance = multiChannel ? median(texel.r, texel.g, texel.b) : min(texel.r, texel.a);
+		float width = max(fwidth(distance) * 0.7, 0.001);
+
+		float fill = smoothstep(0.5 - width, 0.5 + width, distance);
+		vec4 result = color;
+		result.a *= fill;
+
+		if (outlineWidth > 0.0) {
+			float outer = smoothstep(0.5 - outlineWidth - width, 0.5 - outlineWidth + width, distance);
+			result = mix(outlineColor, color, fill);
+			result.a *= outer;
+		}
+
+		if (result.a < 0.01) discard;
+
+		gl_FragColor = result;
+	}
+"#;
+
+/// Floats per vertex: position, uv.
+const VERTEX_STRIDE: usize = 4;
+
+/// A glyph in an [`SdfFont`], in atlas pixels.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FontGlyph {
+	/// Top left corner in the atlas.
+	pub x: f32,
+	pub y: f32,
+	pub width: f32,
+	pub height: f32,
+	/// Offset of the quad from the pen position, down from the line top.
+	pub offset: Vec2,
+	/// Distance the pen moves after this glyph.
+	pub advance: f32,
+}
+
+/// A signed distance field font: glyph metrics and the atlas texture.
+///
+/// Share one font between many [`TextMesh`]es with an `Rc`.
+pub struct SdfFont {
+	pub texture: Texture,
+	glyphs: HashMap<char, FontGlyph>,
+	kerning: HashMap<(char, char), f32>,
+	/// Size the font was generated at, in pixels.
+	size: f32,
+	line_height: f32,
+	/// Atlas size the glyph rectangles are measured in.
+	scale: Vec2,
+	multi_channel: bool,
+}
+
+impl SdfFont {
+	/// Parses a BMFont JSON description for an atlas texture.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the JSON is malformed or has no glyphs.
+	pub fn from_json(json: &str, texture: Texture) -> Result<Self, String> {
+		Self::from_json_with(json, |_| Ok(texture))
+	}
+
+	/// Parses a BMFont JSON description, getting the atlas texture from the
+	/// file name of its first page.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the JSON is malformed, has no glyphs or the
+	/// texture can't be created.
+	pub fn from_json_with(json: &str, texture: impl FnOnce(&str) -> Result<Texture, String>) -> Result<Self, String> {
+		let root = JSON::parse(json).map_err(|e| format!("Invalid font JSON: {:?}", e))?;
+		let common = field(&root, "common");
+		let info = field(&root, "info");
+		let distance_field = field(&root, "distanceField");
+
+		let scale = Vec2::new(number(&common, "scaleW"), number(&common, "scaleH"));
+
+		if scale.x <= 0.0 || scale.y <= 0.0 {
+			return Err("Font JSON has no atlas size in `common`".into());
+		}
+
+		let mut glyphs = HashMap::new();
+
+		for entry in Array::from(&field(&root, "chars")).iter() {
+			let Some(c) = char::from_u32(number(&entry, "id") as u32) else {
+				continue;
+			};
+
+			glyphs.insert(c, FontGlyph {
+				x: number(&entry, "x"),
+				y: number(&entry, "y"),
+				width: number(&entry, "width"),
+				height: number(&entry, "height"),
+				offset: Vec2::new(number(&entry, "xoffset"), number(&entry, "yoffset")),
+				advance: number(&entry, "xadvance"),
+			});
+		}
+
+		if glyphs.is_empty() {
+			return Err("Font JSON has no `chars`".into());
+		}
+
+		let mut kerning = HashMap::new();
+
+		for entry in Array::from(&field(&root, "kernings")).iter() {
+			let first = char::from_u32(number(&entry, "first") as u32);
+			let second = char::from_u32(number(&entry, "second") as u32);
+
+			if let (Some(first), Some(second)) = (first, second) {
+				kerning.insert((first, second), number(&entry, "amount"));
+			}
+		}
+
+		let page = Array::from(&field(&root, "pages")).get(0).as_string().unwrap_or_default();
+		let field_type = field(&distance_field, "fieldType").as_string().unwrap_or_default();
+		let line_height = number(&common, "lineHeight");
+		// BMFont writes negative sizes to mean character height
+		let size = number(&info, "size").abs();
+
+		Ok(Self {
+			texture: texture(&page)?,
+			glyphs,
+			kerning,
+			size: if size > 0.0 { size } else { line_height },
+			line_height,
+			scale,
+			multi_channel: matches!(field_type.as_str(), "msdf" | "mtsdf"),
+		})
+	}
+
+	pub fn glyph(&self, c: char) -> Option<&FontGlyph> {
+		self.glyphs.get(&c)
+	}
+
+	/// Returns the extra advance between two characters, in font pixels.
+	pub fn kerning(&self, first: char, second: char) -> f32 {
+		self.kerning.get(&(first, second)).copied().unwrap_or(0.0)
+	}
+
+	/// Returns the size the font was generated at, in pixels.
+	pub fn size(&self) -> f32 {
+		self.size
+	}
+
+	/// Returns the distance between lines, in font pixels.
+	pub fn line_height(&self) -> f32 {
+		self.line_height
+	}
+
+	/// Returns whether the atlas is a multi-channel field.
+	pub fn is_multi_channel(&self) -> bool {
+		self.multi_channel
+	}
+
+	/// Returns the width of a line of text, in font pixels.
+	pub fn measure(&self, line: &str) -> f32 {
+		let mut width = 0.0;
+		let mut previous = None;
+
+		for c in line.chars() {
+			let Some(glyph) = self.glyph(c).or_else(|| self.glyph('?')) else {
+				continue;
+			};
+
+			if let Some(previous) = previous {
+				width += self.kerning(previous, c);
+			}
+
+			width += glyph.advance;
+			previous = Some(c);
+		}
+
+		width
+	}
+}
+
+impl AssetLoader {
+	/// Loads a BMFont JSON font and starts loading its atlas texture, see
+	/// [`SdfFont::from_json_with`].
+	pub fn load_font(&self, gl: &GL, url: &str) -> AssetHandle<SdfFont> {
+		let handle = AssetHandle::new();
+		let target = handle.clone();
+		self.on_font(gl, url, move |result| target.complete(result));
+		handle
+	}
+
+	/// Fetches and parses a BMFont JSON font, and passes it to a callback.
+	///
+	/// The atlas texture is loaded from the font's first page, relative to
+	/// the JSON file, and fills in after the callback runs.
+	pub fn on_font(&self, gl: &GL, url: &str, callback: impl FnOnce(Result<SdfFont, String>) + 'static) {
+		let gl = gl.clone();
+		let resolved = self.resolve(url);
+		let directory = resolved[..resolved.rfind('/').map_or(0, |end| end + 1)].to_string();
+
+		self.on_text(url, move |result| callback(result.and_then(|json| {
+			SdfFont::from_json_with(&json, |page| Texture::load(&gl, &format!("{}{}", directory, page)))
+		})));
+	}
+}
+
+/// Horizontal alignment of each line of a [`TextMesh`] around its origin.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TextAlign {
+	/// Lines start at the origin.
+	#[default]
+	Left,
+	/// Lines are centered on the origin.
+	Center,
+	/// Lines end at the origin.
+	Right,
+}
+
+/// Text drawn as quads in the scene.
+///
+/// Text lies in the XY plane of its transform, reading along +X with the
+/// first line's top at the origin, facing +Z.
+pub struct TextMesh {
+	pub transform: Transform3D,
+	pub color: Vec4,
+	pub outline_color: Vec4,
+	/// Outline thickness as a fraction of the font's distance range, from
+	/// `0.0` for none to about `0.4`.
+	pub outline_width: f32,
+	/// Height of one em in world units.
+	pub size: f32,
+	pub visible: bool,
+	font: Rc<SdfFont>,
+	text: String,
+	align: TextAlign,
+	buffer: WebGlBuffer,
+	vertex_count: i32,
+	/// Layout changed since the buffer was last uploaded.
+	dirty: bool,
+	/// Bounds of the laid out text in em units.
+	bounds: (Vec2, Vec2),
+}
+
+impl TextMesh {
+	/// Lays out `text` with `font`. Newlines start a new line.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the vertex buffer can't be created.
+	pub fn new(gl: &GL, font: &Rc<SdfFont>, text: &str) -> Result<Self, String> {
+		let buffer = gl.create_buffer().ok_or("Failed to create text buffer")?;
+
+		let mut mesh = Self {
+			transform: Transform3D::new(),
+			color: Vec4::ONE,
+			outline_color: Vec4::new(0.0, 0.0, 0.0, 1.0),
+			outline_width: 0.0,
+			size: 1.0,
+			visible: true,
+			font: font.clone(),
+			text: text.to_string(),
+			align: TextAlign::Left,
+			buffer,
+			vertex_count: 0,
+			dirty: true,
+			bounds: (Vec2::ZERO, Vec2::ZERO),
+		};
+
+		mesh.bounds = mesh.measure();
+		Ok(mesh)
+	}
+
+	pub fn with_transform(mut self, transform: Transform3D) -> Self {
+		self.transform = transform;
+		self
+	}
+
+	pub fn with_color(mut self, color: Vec4) -> Self {
+		self.color = color;
+		self
+	}
+
+	pub fn with_outline(mut self, color: Vec4, width: f32) -> Self {
+		self.outline_color = color;
+		self.outline_width = width;
+		self
+	}
+
+	pub fn with_size(mut self, size: f32) -> Self {
+		self.size = size;
+		self
+	}
+
+	pub fn with_align(mut self, align: TextAlign) -> Self {
+		self.set_align(align);
+		self
+	}
+
+	pub fn text(&self) -> &str {
+		&self.text
+	}
+
+	/// Replaces the text, laid out again before the next draw.
+	pub fn set_text(&mut self, text: &str) {
+		if self.text != text {
+			self.text = text.to_string();
+			self.bounds = self.measure();
+			self.dirty = true;
+		}
+	}
+
+	pub fn align(&self) -> TextAlign {
+		self.align
+	}
+
+	pub fn set_align(&mut self, align: TextAlign) {
+		if self.align != align {
+			self.align = align;
+			self.bounds = self.measure();
+			self.dirty = true;
+		}
+	}
+
+	pub fn font(&self) -> &Rc<SdfFont> {
+		&self.font
+	}
+
+	/// Returns the model matrix, including the text size.
+	pub fn matrix(&self) -> Mat4 {
+		self.transform.to_matrix() * Mat4::from_scale(Vec3::new(self.size, self.size, 1.0))
+	}
+
+	/// Returns the world position of the center of the text.
+	pub fn center(&self) -> Vec3 {
+		let (min, max) = self.bounds;
+		self.matrix().transform_point3(((min + max) * 0.5).extend(0.0))
+	}
+
+	/// Returns the local bounds of the text as `(min, max)`, in world
+	/// units before the transform.
+	pub fn bounds(&self) -> (Vec2, Vec2) {
+		(self.bounds.0 * self.size, self.bounds.1 * self.size)
+	}
+
+	/// Lays out the text as two triangles per glyph, in em units.
+	fn layout(&self) -> Vec<f32> {
+		let font = &self.font;
+		let em = 1.0 / font.size;
+		let mut vertices = Vec::with_capacity(self.text.len() * 6 * VERTEX_STRIDE);
+
+		for (row, line) in self.text.split('\n').enumerate() {
+			let mut pen = match self.align {
+				TextAlign::Left => 0.0,
+				TextAlign::Center => -font.measure(line) * 0.5,
+				TextAlign::Right => -font.measure(line),
+			};
+			let top = -(row as f32) * font.line_height;
+			let mut previous = None;
+
+			for c in line.chars() {
+				let Some(glyph) = font.glyph(c).or_else(|| font.glyph('?')) else {
+					continue;
+				};
+
+				if let Some(previous) = previous {
+					pen += font.kerning(previous, c);
+				}
+
+				if glyph.width > 0.0 && glyph.height > 0.0 {
+					let x0 = (pen + glyph.offset.x) * em;
+					let y0 = (top - glyph.offset.y) * em;
+					let x1 = x0 + glyph.width * em;
+					let y1 = y0 - glyph.height * em;
+
+					let u0 = glyph.x / font.scale.x;
+					let v0 = glyph.y / font.scale.y;
+					let u1 = (glyph.x + glyph.width) / font.scale.x;
+					let v1 = (glyph.y + glyph.height) / font.scale.y;
+
+					vertices.extend_from_slice(&[
+						x0, y1, u0, v1,
+						x1, y1, u1, v1,
+						x1, y0, u1, v0,
+						x0, y1, u0, v1,
+						x1, y0, u1, v0,
+						x0, y0, u0, v0,
+					]);
+				}
+
+				pen += glyph.advance;
+				previous = Some(c);
+			}
+		}
+
+		vertices
+	}
+
+	fn measure(&self) -> (Vec2, Vec2) {
+		let font = &self.font;
+		let lines: Vec<&str> = self.text.split('\n').collect();
+		let widest = lines.iter().map(|line| font.measure(line)).fold(0.0, f32::max);
+		let height = lines.len() as f32 * font.line_height;
+
+		let left = match self.align {
+			TextAlign::Left => 0.0,
+			TextAlign::Center => -widest * 0.5,
+			TextAlign::Right => -widest,
+		};
+
+		let em = 1.0 / font.size;
+		(Vec2::new(left, -height) * em, Vec2::new(left + widest, 0.0) * em)
+	}
+
+	/// Uploads the layout if it changed since the last draw.
+	fn upload(&mut self, gl: &GL) {
+		if !self.dirty {
+			return;
+		}
+
+		let vertices = self.layout();
+		let data = unsafe {
+			std::slice::from_raw_parts(vertices.as_ptr() as *const u8, std::mem::size_of_val(vertices.as_slice()))
+		};
+
+		gl.bind_buffer(GL::ARRAY_BUFFER, Some(&self.buffer));
+		gl.buffer_data_with_u8_array(GL::ARRAY_BUFFER, data, GL::DYNAMIC_DRAW);
+
+		self.vertex_count = (vertices.len() / VERTEX_STRIDE) as i32;
+		self.dirty = false;
+	}
+
+	/// Returns the number of glyph triangles.
+	pub fn triangle_count(&self) -> u32 {
+		(self.vertex_count / 3) as u32
+	}
+}
+
+/// Draws text meshes for a [`Scene`](super::Scene).
+pub(crate) struct TextRenderer {
+	program: WebGlProgram,
+}
+
+impl TextRenderer {
+	pub fn new(gl: &GL) -> Result<Self, String> {
+		let vert = compile_shader(gl, TEXT_VERT, GL::VERTEX_SHADER)?;
+		let frag = compile_shader(gl, TEXT_FRAG, GL::FRAGMENT_SHADER)?;
+		let program = link_program(gl, &vert, &frag)?;
+
+		Ok(Self { program })
+	}
+
+	/// Draws visible text back to front with blending.
+	///
+	/// Text is depth tested against the scene but doesn't write depth, and
+	/// shows from both sides. Blending, depth and culling state is restored
+	/// afterwards. Returns the number of draw calls and triangles.
+	pub fn draw<'a>(&self, gl: &GL, camera: &Camera, texts: impl Iterator<Item = &'a mut TextMesh>) -> (u32, u32) {
+		let view = camera.view_matrix();
+
+		let mut sorted: Vec<(&mut TextMesh, f32)> = texts
+			.filter(|text| text.visible)
+			.map(|text| {
+				let depth = view.transform_point3(text.center()).z;
+				(text, depth)
+			})
+			.collect();
+
+		if sorted.is_empty() {
+			return (0, 0);
+		}
+
+		// View-space z is negative in front of the camera
+		sorted.sort_by(|a, b| a.1.total_cmp(&b.1));
+
+		let program = &self.program;
+
+		gl.use_program(Some(program));
+		set_matrix(gl, program, "view", &view);
+		set_matrix(gl, program, "projection", &camera.projection_matrix());
+
+		if let Some(loc) = gl.get_uniform_location(program, "logDepthFactor") {
+			gl.uniform1f(Some(&loc), camera.log_depth_factor());
+		}
+		if let Some(loc) = gl.get_uniform_location(program, "fontAtlas") {
+			gl.uniform1i(Some(&loc), 0);
+		}
+
+		let position_loc = gl.get_attrib_location(program, "position");
+		let uv_loc = gl.get_attrib_location(program, "uv");
+		let stride = (VERTEX_STRIDE * 4) as i32;
+
+		let state = SavedState::capture(gl);
+
+		gl.disable(GL::CULL_FACE);
+		gl.depth_mask(false);
+		blend_over(gl);
+
+		let (mut draws, mut triangles) = (0, 0);
+
+		for (text, _) in sorted {
+			text.upload(gl);
+
+			if text.vertex_count == 0 {
+				continue;
+			}
+
+			gl.bind_buffer(GL::ARRAY_BUFFER, Some(&text.buffer));
+
+			if position_loc >= 0 {
+				gl.enable_vertex_attrib_array(position_loc as u32);
+				gl.vertex_attrib_pointer_with_i32(position_loc as u32, 2, GL::FLOAT, false, stride, 0);
+			}
+			if uv_loc >= 0 {
+				gl.enable_vertex_attrib_array(uv_loc as u32);
+				gl.vertex_attrib_pointer_with_i32(uv_loc as u32, 2, GL::FLOAT, false, stride, 8);
+			}
+
+			set_matrix(gl, program, "model", &text.matrix());
+
+			if let Some(loc) = gl.get_uniform_location(program, "multiChannel") {
+				gl.uniform1i(Some(&loc), text.font.multi_channel as i32);
+			}
+			if let Some(loc) = gl.get_uniform_location(program, "color") {
+				gl.uniform4fv_with_f32_array(Some(&loc), &text.color.to_array());
+			}
+			if let Some(loc) = gl.get_uniform_location(program, "outlineColor") {
+				gl.uniform4fv_with_f32_array(Some(&loc), &text.outline_color.to_array());
+			}
+			if let Some(loc) = gl.get_uniform_location(program, "outlineWidth") {
+				gl.uniform1f(Some(&loc), text.outline_width.clamp(0.0, 0.49));
+			}
+
+			text.font.texture.bind(gl, 0);
+			gl.draw_arrays(GL::TRIANGLES, 0, text.vertex_count);
+
+			draws += 1;
+			triangles += text.triangle_count();
+		}
+
+		state.restore(gl);
+
+		(draws, triangles)
+	}
+}
+
+/// Returns a property of a parsed JSON object, or `undefined`.
+fn field(object: &JsValue, key: &str) -> JsValue {
+	Reflect::get(object, &JsValue::from_str(key)).unwrap_or(JsValue::UNDEFINED)
+}
+
+/// Returns a numeric property of a parsed JSON object, or zero.
+fn number(object: &JsValue, key: &str) -> f32 {
+	field(object, key).as_f64().unwrap_or(0.0) as f32
+}
+
+fn set_matrix(gl: &GL, program: &WebGlProgram, name: &str, matrix: &Mat4) {
+	if let Some(loc) = gl.get_uniform_location(program, name) {
+		gl.uniform_matrix4fv_with_f32_array(Some(&loc), false, &matrix.to_cols_array());
+	}
+}
@@ -0,0 +1,322 @@
+//! Extruded Text Geometry
+//!
+//! Provides [`TextGeometry`], which turns text into solid meshes by
+//! triangulating the glyph outlines of a [`TrueTypeFont`] and extruding
+//! them. Unlike [SDF text](super::TextMesh), the result is regular
+//! geometry, lit and shadowed like any other object, for logos, titles and
+//! signage.
+//!
+//! The front face points along +Z, the text reads along +X and the first
+//! line's baseline is at the origin.
+//!
+//! ## Examples
+//!
+//! ```ignore
+//! use oxgl::common::{Mesh, presets};
+//! use oxgl::renderer_3d::{TextAlign, TextGeometry};
+//!
+//! loader.on_truetype("fonts/Inter-Bold.ttf", move |result| {
+//!		let font = result?;
+//!
+//!		let data = TextGeometry::new(&font, "OXGL")
+//!			.with_size(2.0)
+//!			.with_depth(0.4)
+//!			.with_align(TextAlign::Center)
+//!			.build();
+//!
+//!		let mesh = Mesh::from_data(&gl, &data, presets::phong(&gl, Vec3::new(0.9, 0.5, 0.1)));
+//!		scene.borrow_mut().add(mesh, Transform3D::new());
+//!		Ok(())
+//! });
+//! ```
+//!
+
+use glam::{Vec2, Vec3};
+
+use crate::common::{MeshData, TrueTypeFont};
+use super::{TextAlign, primitive::{corner_normal, push_vertex, signed_area, triangulate}};
+
+/// Builds extruded meshes from text.
+#[derive(Clone, Debug)]
+pub struct TextGeometry<'a> {
+	font: &'a TrueTypeFont,
+	text: String,
+	/// Height of one em in world units.
+	pub size: f32,
+	/// Thickness along Z in world units, or zero for flat text.
+	pub depth: f32,
+	/// Lines each quadratic curve of the outlines is split into.
+	pub curve_segments: u32,
+	pub align: TextAlign,
+	/// Extra space between characters, in ems.
+	pub letter_spacing: f32,
+}
+
+impl<'a> TextGeometry<'a> {
+	/// Lays out `text` in `font`. Newlines start a new line.
+	pub fn new(font: &'a TrueTypeFont, text: &str) -> Self {
+		Self {
+			font,
+			text: text.to_string(),
+			size: 1.0,
+			depth: 0.2,
+			curve_segments: 8,
+			align: TextAlign::Left,
+			letter_spacing: 0.0,
+		}
+	}
+
+	pub fn with_size(mut self, size: f32) -> Self {
+		self.size = size;
+		self
+	}
+
+	pub fn with_depth(mut self, depth: f32) -> Self {
+		self.depth = depth;
+		self
+	}
+
+	pub fn with_curve_segments(mut self, segments: u32) -> Self {
+		self.curve_segments = segments;
+		self
+	}
+
+	pub fn with_align(mut self, align: TextAlign) -> Self {
+		self.align = align;
+		self
+	}
+
+	pub fn with_letter_spacing(mut self, spacing: f32) -> Self {
+		self.letter_spacing = spacing;
+		self
+	}
+
+	/// Triangulates and extrudes the text.
+	///
+	/// Characters missing from the font show the font's missing glyph.
+	/// Flat text only has a front face.
+	pub fn build(&self) -> MeshData {
+		let font = self.font;
+		let scale = self.size / font.units_per_em();
+		let spacing = self.letter_spacing * font.units_per_em();
+		let mut data = MeshData {
+			name: Some(self.text.clone()),
+			..Default::default()
+		};
+
+		for (row, line) in self.text.split('\n').enumerate() {
+			let glyphs: Vec<u16> = line.chars().map(|c| font.glyph_index(c)).collect();
+			let width = line_width(font, &glyphs, spacing);
+
+			let mut pen = match self.align {
+				TextAlign::Left => 0.0,
+				TextAlign::Center => -width * 0.5,
+				TextAlign::Right => -width,
+			};
+			let baseline = -(row as f32) * font.line_height();
+
+			for (i, &glyph) in glyphs.iter().enumerate() {
+				let origin = Vec2::new(pen, baseline);
+				let contours: Vec<Vec<Vec2>> = font.contours(glyph, self.curve_segments)
+					.into_iter()
+					.map(|contour| contour.into_iter().map(|point| (point + origin) * scale).collect())
+					.collect();
+
+				for (outline, holes) in group_shapes(contours) {
+					self.extrude(&mut data, outline, holes);
+				}
+
+				pen += font.advance(glyph) + spacing;
+
+				if let Some(&next) = glyphs.get(i + 1) {
+					pen += font.kerning(glyph, next);
+				}
+			}
+		}
+
+		data
+	}
+
+	/// Appends the caps and sides of one outline with its holes.
+	fn extrude(&self, data: &mut MeshData, outline: Vec<Vec2>, holes: Vec<Vec<Vec2>>) {
+		let front = self.depth * 0.5;
+		let back = -front;
+		let sides: Vec<Vec<Vec2>> = std::iter::once(outline.clone()).chain(holes.iter().cloned()).collect();
+
+		let polygon = merge_holes(outline, holes);
+		let triangles = triangulate(&polygon);
+
+		for [a, b, c] in &triangles {
+			for &i in &[a, b, c] {
+				push_vertex(data, polygon[*i].extend(front), Vec3::Z);
+			}
+		}
+
+		if self.depth <= 0.0 {
+			return;
+		}
+
+		for [a, b, c] in &triangles {
+			for &i in &[a, c, b] {
+				push_vertex(data, polygon[*i].extend(back), Vec3::NEG_Z);
+			}
+		}
+
+		for contour in &sides {
+			let count = contour.len();
+			let edge_normal = |i: usize| {
+				let edge = contour[(i + 1) % count] - contour[i];
+				Vec2::new(edge.y, -edge.x).normalize_or_zero()
+			};
+
+			for i in 0..count {
+				let j = (i + 1) % count;
+				let normal = edge_normal(i);
+				let start_normal = corner_normal(edge_normal((i + count - 1) % count), normal, normal);
+				let end_normal = corner_normal(normal, edge_normal(j), normal);
+
+				let (p, q) = (contour[i], contour[j]);
+				let (np, nq) = (start_normal.extend(0.0), end_normal.extend(0.0));
+
+				push_vertex(data, p.extend(front), np);
+				push_vertex(data, p.extend(back), np);
+				push_vertex(data, q.extend(back), nq);
+				push_vertex(data, p.extend(front), np);
+				push_vertex(data, q.extend(back), nq);
+				push_vertex(data, q.extend(front), nq);
+			}
+		}
+	}
+}
+
+/// Returns the advance of a line of glyphs, in font units.
+fn line_width(font: &TrueTypeFont, glyphs: &[u16], spacing: f32) -> f32 {
+	let advances: f32 = glyphs.iter().map(|&glyph| font.advance(glyph) + spacing).sum();
+	let kerning: f32 = glyphs.windows(2).map(|pair| font.kerning(pair[0], pair[1])).sum();
+
+	advances + kerning - if glyphs.is_empty() { 0.0 } else { spacing }
+}
+
+fn contains(polygon: &[Vec2], point: Vec2) -> bool {
+	let mut inside = false;
+	let mut j = polygon.len() - 1;
+
+	for i in 0..polygon.len() {
+		let (a, b) = (polygon[i], polygon[j]);
+
+		if (a.y > point.y) != (b.y > point.y) && point.x < (b.x - a.x) * (point.y - a.y) / (b.y - a.y) + a.x {
+			inside = !inside;
+		}
+
+		j = i;
+	}
+
+	inside
+}
+
+/// Splits contours into outlines, wound counter-clockwise, and the holes
+/// inside each, wound clockwise.
+///
+/// Contours nested an even number of times are outlines, so this works
+/// for fonts of either winding convention.
+fn group_shapes(contours: Vec<Vec<Vec2>>) -> Vec<(Vec<Vec2>, Vec<Vec<Vec2>>)> {
+	let contours: Vec<Vec<Vec2>> = contours.into_iter().filter(|c| signed_area(c).abs() > 1e-9).collect();
+
+	let depths: Vec<usize> = contours.iter().enumerate().map(|(i, contour)| {
+		contours.iter().enumerate().filter(|&(j, other)| j != i && contains(other, contour[0])).count()
+	}).collect();
+
+	let mut shapes: Vec<(usize, Vec<Vec2>, Vec<Vec<Vec2>>)> = Vec::new();
+
+	for (i, contour) in contours.iter().enumerate() {
+		if depths[i].is_multiple_of(2) {
+			let mut outline = contour.clone();
+
+			if signed_area(&outline) < 0.0 {
+				outline.reverse();
+			}
+
+			shapes.push((i, outline, Vec::new()));
+		}
+	}
+
+	for (i, contour) in contours.iter().enumerate() {
+		if depths[i].is_multiple_of(2) {
+			continue;
+		}
+
+		// The hole belongs to the smallest outline one level up around it
+		let parent = shapes.iter_mut()
+			.filter(|(j, outline, _)| depths[*j] + 1 == depths[i] && contains(outline, contour[0]))
+			.min_by(|a, b| signed_area(&a.1).total_cmp(&signed_area(&b.1)));
+
+		if let Some((_, _, holes)) = parent {
+			let mut hole = contour.clone();
+
+			if signed_area(&hole) > 0.0 {
+				hole.reverse();
+			}
+
+			holes.push(hole);
+		}
+	}
+
+	shapes.into_iter().map(|(_, outline, holes)| (outline, holes)).collect()
+}
+
+/// Joins holes into their outline with bridge edges, giving one polygon
+/// that ear clipping can triangulate.
+fn merge_holes(outline: Vec<Vec2>, mut holes: Vec<Vec<Vec2>>) -> Vec<Vec2> {
+	// Bridging the rightmost holes first keeps bridges from crossing
+	holes.sort_by(|a, b| max_x(b).total_cmp(&max_x(a)));
+
+	let mut polygon = outline;
+
+	for h in 0..holes.len() {
+		let hole = &holes[h];
+		let m = (0..hole.len()).max_by(|&a, &b| hole[a].x.total_cmp(&hole[b].x)).unwrap_or(0);
+		let from = hole[m];
+
+		let mut candidates: Vec<usize> = (0..polygon.len()).collect();
+		candidates.sort_by(|&a, &b| polygon[a].distance_squared(from).total_cmp(&polygon[b].distance_squared(from)));
+
+		let visible = candidates.into_iter().find(|&v| {
+			let to = polygon[v];
+			!crosses_any(&polygon, from, to) && holes[h..].iter().all(|other| !crosses_any(other, from, to))
+		});
+
+		let Some(v) = visible else {
+			continue;
+		};
+
+		let mut merged = Vec::with_capacity(polygon.len() + hole.len() + 2);
+		merged.extend_from_slice(&polygon[..=v]);
+		merged.extend(hole[m..].iter().chain(&hole[..=m]));
+		merged.extend_from_slice(&polygon[v..]);
+		polygon = merged;
+	}
+
+	polygon
+}
+
+fn max_x(polygon: &[Vec2]) -> f32 {
+	polygon.iter().map(|p| p.x).fold(f32::MIN, f32::max)
+}
+
+/// Returns whether segment `a`-`b` properly crosses an edge of a polygon.
+/// Edges touching `a` or `b` don't count.
+fn crosses_any(polygon: &[Vec2], a: Vec2, b: Vec2) -> bool {
+	(0..polygon.len()).any(|i| {
+		let (c, d) = (polygon[i], polygon[(i + 1) % polygon.len()]);
+
+		if c == a || c == b || d == a || d == b {
+			return false;
+		}
+
+		let side = |p: Vec2, q: Vec2, r: Vec2| (q - p).perp_dot(r - p);
+		let (d1, d2) = (side(a, b, c), side(a, b, d));
+		let (d3, d4) = (side(c, d, a), side(c, d, b));
+
+		d1 * d2 < 0.0 && d3 * d4 < 0.0
+	})
+}
@@ -0,0 +1,304 @@
+//! Trails
+//!
+//! Provides [`TrailRenderer`], a ribbon through the positions an object
+//! recently passed, for projectiles, sword swipes and visualizing motion.
+//! The ribbon turns to face the camera along its length, and narrows and
+//! fades from the object towards its tail as the positions age.
+//!
+//! Trails are attached to objects with
+//! [`Scene::set_trail`](super::Scene::set_trail), record the object's world
+//! position every frame and are drawn after the scene's objects with
+//! blending.
+//!
+//! ## Examples
+//!
+//! ```ignore
+//! use oxgl::renderer_3d::TrailRenderer;
+//! use glam::Vec4;
+//!
+//! // A half-second streak behind an arrow, fading from white to clear
+//! scene.set_trail(arrow, Some(
+//!		TrailRenderer::new(0.5)
+//!			.with_width(0.1, 0.0)
+//!			.with_color(Vec4::ONE, Vec4::new(1.0, 1.0, 1.0, 0.0))
+//! ));
+//!
+//! // Teleporting shouldn't leave a streak across the level
+//! scene.get_mut(arrow).unwrap().transform.position = spawn;
+//! scene.get_mut(arrow).unwrap().trail.as_mut().unwrap().clear();
+//! ```
+//!
+
+use std::collections::VecDeque;
+
+use glam::{Vec3, Vec4};
+use web_sys::{WebGlBuffer, WebGlProgram, WebGl2RenderingContext as GL};
+
+use crate::common::{compile_shader, link_program, Camera};
+use super::{RenderStats, background::blend_over};
+
+const TRAIL_VERT: &str = r#"
+	#include <logdepth>
+
+	attribute vec3 position;
+	attribute vec4 color;
+
+	uniform mat4 viewProjection;
+
+	varying vec4 vColor;
+
+	void main() {
+		gl_Position = logDepth(viewProjection * vec4(position, 1.0));
+		vColor = color;
+	}
+"#;
+
+const TRAIL_FRAG: &str = r#"
+	precision mediump float;
+
+	varying vec4 vColor;
+
+	void main() {
+		gl_FragColor = vColor;
+	}
+"#;
+
+/// Floats per vertex: position, color.
+const VERTEX_STRIDE: usize = 7;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct TrailPoint {
+	position: Vec3,
+	time: f32,
+}
+
+/// A camera-facing ribbon following an object.
+///
+/// Width and color are interpolated from the `start_*` values at the
+/// object to the `end_*` values at positions `lifetime` seconds old.
+#[derive(Clone, Debug)]
+pub struct TrailRenderer {
+	/// Seconds a recorded position stays on the trail.
+	pub lifetime: f32,
+	/// Width in world units at the object.
+	pub start_width: f32,
+	/// Width in world units at the end of the trail.
+	pub end_width: f32,
+	pub start_color: Vec4,
+	pub end_color: Vec4,
+	/// Distance the object moves before another position is recorded.
+	pub min_distance: f32,
+	/// Whether new positions are recorded. Recorded positions still age
+	/// out, so the trail shrinks away behind the object.
+	pub emitting: bool,
+	pub visible: bool,
+	points: VecDeque<TrailPoint>,
+	head: Option<TrailPoint>,
+}
+
+impl TrailRenderer {
+	/// Creates a trail narrowing to a point and fading out over `lifetime`
+	/// seconds.
+	pub fn new(lifetime: f32) -> Self {
+		Self {
+			lifetime,
+			start_width: 0.2,
+			end_width: 0.0,
+			start_color: Vec4::ONE,
+			end_color: Vec4::new(1.0, 1.0, 1.0, 0.0),
+			min_distance: 0.05,
+			emitting: true,
+			visible: true,
+			points: VecDeque::new(),
+			head: None,
+		}
+	}
+
+	pub fn with_width(mut self, start: f32, end: f32) -> Self {
+		self.start_width = start;
+		self.end_width = end;
+		self
+	}
+
+	pub fn with_color(mut self, start: Vec4, end: Vec4) -> Self {
+		self.start_color = start;
+		self.end_color = end;
+		self
+	}
+
+	pub fn with_min_distance(mut self, distance: f32) -> Self {
+		self.min_distance = distance;
+		self
+	}
+
+	/// Ages out old positions and records the object's position at `time`.
+	///
+	/// Called by the scene every frame for objects with a trail.
+	///
+	/// ```
+	/// use oxgl::renderer_3d::TrailRenderer;
+	/// use glam::Vec3;
+	///
+	/// let mut trail = TrailRenderer::new(1.0).with_min_distance(0.5);
+	///
+	/// trail.record(Vec3::ZERO, 0.0);
+	/// trail.record(Vec3::new(0.2, 0.0, 0.0), 0.1);
+	/// trail.record(Vec3::new(1.0, 0.0, 0.0), 0.2);
+	/// assert_eq!(trail.positions().count(), 2);
+	///
+	/// // The object's latest position is always the head of the trail
+	/// trail.record(Vec3::new(1.2, 0.0, 0.0), 0.3);
+	/// assert_eq!(trail.positions().last(), Some(Vec3::new(1.2, 0.0, 0.0)));
+	///
+	/// trail.record(Vec3::new(1.2, 0.0, 0.0), 5.0);
+	/// assert_eq!(trail.positions().count(), 1);
+	/// ```
+	pub fn record(&mut self, position: Vec3, time: f32) {
+		while self.points.front().is_some_and(|point| time - point.time >= self.lifetime) {
+			self.points.pop_front();
+		}
+
+		if !self.emitting {
+			self.head = None;
+			return;
+		}
+
+		let head = TrailPoint { position, time };
+
+		if self.points.back().is_none_or(|last| last.position.distance(position) >= self.min_distance) {
+			self.points.push_back(head);
+		}
+
+		self.head = Some(head);
+	}
+
+	/// Returns the positions along the trail, from the oldest to the object.
+	pub fn positions(&self) -> impl Iterator<Item = Vec3> + '_ {
+		self.points().map(|point| point.position)
+	}
+
+	/// Forgets every recorded position, such as after teleporting the object.
+	pub fn clear(&mut self) {
+		self.points.clear();
+		self.head = None;
+	}
+
+	fn points(&self) -> impl Iterator<Item = TrailPoint> + '_ {
+		let head = self.head.filter(|head| self.points.back() != Some(head));
+		self.points.iter().copied().chain(head)
+	}
+
+	/// Builds a triangle strip facing `eye`, two vertices per position.
+	fn vertices(&self, eye: Vec3, time: f32) -> Vec<f32> {
+		let points: Vec<TrailPoint> = self.points().collect();
+		let count = points.len();
+
+		if count < 2 {
+			return Vec::new();
+		}
+
+		let mut vertices = Vec::with_capacity(count * 2 * VERTEX_STRIDE);
+
+		for (i, point) in points.iter().enumerate() {
+			let tangent = points[(i + 1).min(count - 1)].position - points[i.saturating_sub(1)].position;
+			let side = tangent
+				.cross(eye - point.position)
+				.try_normalize()
+				.unwrap_or_else(|| tangent.normalize_or(Vec3::Y).any_orthonormal_vector());
+
+			let age = if self.lifetime > 0.0 { ((time - point.time) / self.lifetime).clamp(0.0, 1.0) } else { 0.0 };
+			let half_width = (self.start_width + (self.end_width - self.start_width) * age) * 0.5;
+			let color = self.start_color.lerp(self.end_color, age);
+
+			for offset in [side * half_width, -side * half_width] {
+				vertices.extend_from_slice(&(point.position + offset).to_array());
+				vertices.extend_from_slice(&color.to_array());
+			}
+		}
+
+		vertices
+	}
+}
+
+/// Draws trails for a [`Scene`](super::Scene).
+pub(crate) struct TrailPass {
+	program: WebGlProgram,
+	buffer: WebGlBuffer,
+}
+
+impl TrailPass {
+	pub fn new(gl: &GL) -> Result<Self, String> {
+		let vert = compile_shader(gl, TRAIL_VERT, GL::VERTEX_SHADER)?;
+		let frag = compile_shader(gl, TRAIL_FRAG, GL::FRAGMENT_SHADER)?;
+		let program = link_program(gl, &vert, &frag)?;
+		let buffer = gl.create_buffer().ok_or("Failed to create trail buffer")?;
+
+		Ok(Self { program, buffer })
+	}
+
+	/// Draws trails as they look at `time` with blending, double-sided and
+	/// without writing depth.
+	pub fn draw<'a>(&self, gl: &GL, camera: &Camera, trails: impl Iterator<Item = &'a TrailRenderer>, time: f32, stats: &mut RenderStats) {
+		let program = &self.program;
+		let view_projection = camera.projection_matrix() * camera.view_matrix();
+
+		gl.use_program(Some(program));
+
+		if let Some(loc) = gl.get_uniform_location(program, "viewProjection") {
+			gl.uniform_matrix4fv_with_f32_array(Some(&loc), false, &view_projection.to_cols_array());
+		}
+		if let Some(loc) = gl.get_uniform_location(program, "logDepthFactor") {
+			gl.uniform1f(Some(&loc), camera.log_depth_factor());
+		}
+
+		gl.bind_buffer(GL::ARRAY_BUFFER, Some(&self.buffer));
+
+		let stride = (VERTEX_STRIDE * 4) as i32;
+		let position_loc = gl.get_attrib_location(program, "position");
+		let color_loc = gl.get_attrib_location(program, "color");
+
+		if position_loc >= 0 {
+			gl.enable_vertex_attrib_array(position_loc as u32);
+			gl.vertex_attrib_pointer_with_i32(position_loc as u32, 3, GL::FLOAT, false, stride, 0);
+		}
+		if color_loc >= 0 {
+			gl.enable_vertex_attrib_array(color_loc as u32);
+			gl.vertex_attrib_pointer_with_i32(color_loc as u32, 4, GL::FLOAT, false, stride, 12);
+		}
+
+		let culling = gl.is_enabled(GL::CULL_FACE);
+
+		gl.disable(GL::CULL_FACE);
+		gl.depth_mask(false);
+		blend_over(gl);
+
+		for trail in trails.filter(|trail| trail.visible) {
+			let vertices = trail.vertices(camera.position, time);
+
+			if vertices.is_empty() {
+				continue;
+			}
+
+			let data = unsafe {
+				std::slice::from_raw_parts(vertices.as_ptr() as *const u8, std::mem::size_of_val(vertices.as_slice()))
+			};
+
+			gl.buffer_data_with_u8_array(GL::ARRAY_BUFFER, data, GL::STREAM_DRAW);
+
+			let count = (vertices.len() / VERTEX_STRIDE) as i32;
+			gl.draw_arrays(GL::TRIANGLE_STRIP, 0, count);
+			stats.draw(count as u32 - 2);
+		}
+
+		gl.disable(GL::BLEND);
+		gl.depth_mask(true);
+
+		if culling {
+			gl.enable(GL::CULL_FACE);
+		}
+
+		if color_loc >= 0 {
+			gl.disable_vertex_attrib_array(color_loc as u32);
+		}
+	}
+}
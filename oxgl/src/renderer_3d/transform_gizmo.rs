@@ -0,0 +1,368 @@
+//! Transform Gizmos
+//!
+//! Provides editor-style handles for moving, rotating and scaling a selected
+//! object with the mouse. Handles are hit-tested against mouse rays and
+//! write straight into the object's [`Transform3D`].
+//!
+//! Translation and rotation use world axes; scaling uses the object's local
+//! axes. Handles keep a constant size on screen.
+//!
+//! ## Examples
+//!
+//! ```ignore
+//! use oxgl::renderer_3d::{GizmoMode, TransformGizmo};
+//!
+//! let mut gizmo = TransformGizmo::new();
+//!
+//! // pointerdown: grab a handle, or select whatever is under the cursor
+//! if !gizmo.pointer_down(&scene, x, y, width, height) {
+//!		gizmo.target = scene.pick(x, y, width, height).map(|hit| hit.object);
+//! }
+//!
+//! // pointermove / pointerup
+//! gizmo.pointer_move(&mut scene, x, y, width, height);
+//! gizmo.pointer_up();
+//!
+//! // Each frame, after rendering the scene
+//! gizmo.draw(&gl, &gizmos, &scene);
+//!
+//! // Switch tools with keyboard shortcuts
+//! gizmo.mode = GizmoMode::Rotate;
+//! ```
+//!
+
+use glam::{Mat4, Quat, Vec3};
+use web_sys::WebGl2RenderingContext as GL;
+
+use crate::{common::render_state::SavedState, core::{ObjectId, Ray, Transform3D}};
+use super::{GizmoRenderer, Scene};
+
+const AXES: [Vec3; 3] = [Vec3::X, Vec3::Y, Vec3::Z];
+const AXIS_COLORS: [Vec3; 3] = [
+	Vec3::new(1.0, 0.2, 0.2),
+	Vec3::new(0.2, 1.0, 0.2),
+	Vec3::new(0.2, 0.4, 1.0),
+];
+const ACTIVE_COLOR: Vec3 = Vec3::new(1.0, 1.0, 0.2);
+
+/// Hit tolerance around a handle, as a fraction of the handle length.
+const PICK_TOLERANCE: f32 = 0.08;
+
+/// Smallest scale a drag can reach on an axis, so objects can't be
+/// flattened or flipped through zero.
+const MIN_SCALE: f32 = 0.001;
+
+/// Which transform component a [`TransformGizmo`] edits.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GizmoMode {
+	/// Arrows along the world axes.
+	#[default]
+	Translate,
+	/// Rings around the world axes.
+	Rotate,
+	/// Box-tipped handles along the object's local axes.
+	Scale,
+}
+
+/// An in-progress drag of a single handle.
+#[derive(Clone, Debug)]
+struct Drag {
+	/// Mode the drag started in. Changing modes ends the drag.
+	mode: GizmoMode,
+	axis: usize,
+	/// World-space handle origin when the drag started.
+	origin: Vec3,
+	/// World-space axis being dragged along or around.
+	direction: Vec3,
+	/// Axis coordinate, or in-plane vector for rings, at the start.
+	start: Vec3,
+	transform: Transform3D,
+}
+
+/// Interactive translate, rotate and scale handles for one object.
+///
+/// The gizmo doesn't listen to events itself; forward pointer positions to
+/// [`pointer_down`](Self::pointer_down), [`pointer_move`](Self::pointer_move)
+/// and [`pointer_up`](Self::pointer_up).
+#[derive(Clone, Debug)]
+pub struct TransformGizmo {
+	pub mode: GizmoMode,
+	/// The object being manipulated.
+	pub target: Option<ObjectId>,
+	/// Handle length as a fraction of the distance to the camera.
+	pub size: f32,
+	hovered: Option<usize>,
+	drag: Option<Drag>,
+}
+
+impl Default for TransformGizmo {
+	fn default() -> Self {
+		Self {
+			mode: GizmoMode::default(),
+			target: None,
+			size: 0.15,
+			hovered: None,
+			drag: None,
+		}
+	}
+}
+
+impl TransformGizmo {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	pub fn with_mode(mut self, mode: GizmoMode) -> Self {
+		self.mode = mode;
+		self
+	}
+
+	pub fn is_dragging(&self) -> bool {
+		self.drag.is_some()
+	}
+
+	/// Returns the axis (0 = X, 1 = Y, 2 = Z) under the pointer or being dragged.
+	pub fn active_axis(&self) -> Option<usize> {
+		self.drag.as_ref().map(|d| d.axis).or(self.hovered)
+	}
+
+	/// Starts dragging the handle under a pixel, if any.
+	///
+	/// Returns `true` if a handle was grabbed, in which case the click
+	/// shouldn't also be used for picking.
+	pub fn pointer_down(&mut self, scene: &Scene, x: f32, y: f32, width: f32, height: f32) -> bool {
+		let ray = scene.camera.screen_ray(x, y, width, height);
+
+		let Some((origin, axes, length)) = self.handles(scene) else {
+			return false;
+		};
+		let Some(axis) = self.hit_test(&ray, origin, &axes, length) else {
+			return false;
+		};
+		let Some(start) = self.drag_point(&ray, origin, axes[axis]) else {
+			return false;
+		};
+		let Some(obj) = self.target.and_then(|id| scene.get(id)) else {
+			return false;
+		};
+
+		self.drag = Some(Drag {
+			mode: self.mode,
+			axis,
+			origin,
+			direction: axes[axis],
+			start,
+			transform: obj.transform.clone(),
+		});
+
+		true
+	}
+
+	/// Updates the hovered handle, or applies the current drag.
+	///
+	/// A drag ends, keeping the edit so far, if the [`mode`](Self::mode)
+	/// changed since it started. Returns `true` if the target's transform
+	/// changed.
+	pub fn pointer_move(&mut self, scene: &mut Scene, x: f32, y: f32, width: f32, height: f32) -> bool {
+		let ray = scene.camera.screen_ray(x, y, width, height);
+
+		if self.drag.as_ref().is_some_and(|drag| drag.mode != self.mode) {
+			self.drag = None;
+		}
+
+		let Some(drag) = self.drag.clone() else {
+			self.hovered = self
+				.handles(scene)
+				.and_then(|(origin, axes, length)| self.hit_test(&ray, origin, &axes, length));
+			return false;
+		};
+
+		let Some(current) = self.drag_point(&ray, drag.origin, drag.direction) else {
+			return false;
+		};
+		let Some(id) = self.target else {
+			return false;
+		};
+
+		// Edits are made in world space, then mapped into the parent's space
+		let parent = scene.get(id).and_then(|obj| obj.parent);
+		let parent_world = parent.map_or(Mat4::IDENTITY, |p| scene.world_matrix(p));
+		let (_, parent_rotation, _) = parent_world.to_scale_rotation_translation();
+
+		let Some(obj) = scene.get_mut(id) else {
+			return false;
+		};
+
+		let mut transform = drag.transform.clone();
+
+		match self.mode {
+			GizmoMode::Translate => {
+				let delta = drag.direction * (current.x - drag.start.x);
+				transform.position += parent_world.inverse().transform_vector3(delta);
+			}
+			GizmoMode::Rotate => {
+				let angle = drag.start
+					.cross(current)
+					.dot(drag.direction)
+					.atan2(drag.start.dot(current));
+				let rotation = Quat::from_axis_angle(drag.direction, angle);
+				transform.rotation = (parent_rotation.inverse() * rotation * parent_rotation * transform.rotation).normalize();
+			}
+			GizmoMode::Scale => {
+				if drag.start.x.abs() > f32::EPSILON {
+					let ratio = (current.x / drag.start.x).max(0.0);
+					let scale = &mut transform.scale[drag.axis];
+					*scale = scale.signum() * (scale.abs() * ratio).max(MIN_SCALE);
+				}
+			}
+		}
+
+		obj.transform = transform;
+		true
+	}
+
+	/// Ends the current drag.
+	pub fn pointer_up(&mut self) {
+		self.drag = None;
+	}
+
+	/// Restores the transform from before the current drag and ends it.
+	pub fn cancel(&mut self, scene: &mut Scene) {
+		if let Some(drag) = self.drag.take()
+			&& let Some(obj) = self.target.and_then(|id| scene.get_mut(id))
+		{
+			obj.transform = drag.transform;
+		}
+	}
+
+	/// Draws the handles for the current mode on top of the scene.
+	///
+	/// Depth testing is disabled while drawing, and the previous depth and
+	/// blend state is restored afterwards.
+	pub fn draw(&self, gl: &GL, gizmos: &GizmoRenderer, scene: &Scene) {
+		let Some((origin, axes, length)) = self.handles(scene) else {
+			return;
+		};
+
+		let state = SavedState::capture(gl);
+		gl.disable(GL::DEPTH_TEST);
+
+		let owns_batch = !gizmos.is_batching();
+
+		if owns_batch {
+			gizmos.begin();
+		}
+
+		let active = self.active_axis();
+
+		for (axis, direction) in axes.iter().enumerate() {
+			let color = if active == Some(axis) { ACTIVE_COLOR } else { AXIS_COLORS[axis] };
+
+			match self.mode {
+				GizmoMode::Translate => {
+					gizmos.arrow(gl, &scene.camera, origin, *direction, length, color);
+				}
+				GizmoMode::Rotate => {
+					gizmos.circle(gl, &scene.camera, origin, *direction, length, color);
+				}
+				GizmoMode::Scale => {
+					let tip = origin + *direction * length;
+					let rotation = Quat::from_mat3(&glam::Mat3::from_cols(axes[0], axes[1], axes[2]));
+					let model = Mat4::from_scale_rotation_translation(Vec3::splat(length * 0.12), rotation, tip);
+
+					gizmos.line(gl, &scene.camera, origin, tip, color);
+					gizmos.wire_box(gl, &scene.camera, model, color);
+				}
+			}
+		}
+
+		if owns_batch {
+			gizmos.flush(gl, &scene.camera);
+		}
+
+		state.restore(gl);
+	}
+
+	/// Returns the world-space origin, axes and handle length for the target.
+	fn handles(&self, scene: &Scene) -> Option<(Vec3, [Vec3; 3], f32)> {
+		let id = self.target?;
+		scene.get(id)?;
+
+		let world = scene.world_matrix(id);
+		let origin = world.transform_point3(Vec3::ZERO);
+		let length = scene.camera.position.distance(origin) * self.size;
+
+		let axes = match self.mode {
+			GizmoMode::Scale => {
+				let (_, rotation, _) = world.to_scale_rotation_translation();
+				AXES.map(|axis| rotation * axis)
+			}
+			_ => AXES,
+		};
+
+		Some((origin, axes, length))
+	}
+
+	/// Returns the closest handle hit by a ray.
+	fn hit_test(&self, ray: &Ray, origin: Vec3, axes: &[Vec3; 3], length: f32) -> Option<usize> {
+		let tolerance = length * PICK_TOLERANCE;
+
+		axes.iter()
+			.enumerate()
+			.filter_map(|(axis, &direction)| {
+				let distance = match self.mode {
+					GizmoMode::Rotate => {
+						let hit = ray.at(ray.intersect_plane(origin, direction)?);
+						let off_ring = (hit.distance(origin) - length).abs();
+						(off_ring < tolerance).then(|| ray.origin.distance(hit))?
+					}
+					GizmoMode::Translate | GizmoMode::Scale => {
+						let (along_ray, along_axis) = closest_to_line(ray, origin, direction)?;
+						let gap = ray.at(along_ray).distance(origin + direction * along_axis);
+						let on_handle = (0.0..=length * 1.1).contains(&along_axis);
+						(on_handle && gap < tolerance).then_some(along_ray)?
+					}
+				};
+
+				Some((axis, distance))
+			})
+			.min_by(|a, b| a.1.total_cmp(&b.1))
+			.map(|(axis, _)| axis)
+	}
+
+	/// Returns where a ray meets a handle: the coordinate along the axis in
+	/// `x` for linear handles, or the in-plane direction for rings.
+	fn drag_point(&self, ray: &Ray, origin: Vec3, direction: Vec3) -> Option<Vec3> {
+		match self.mode {
+			GizmoMode::Rotate => {
+				let hit = ray.at(ray.intersect_plane(origin, direction)?);
+				(hit - origin).try_normalize()
+			}
+			GizmoMode::Translate | GizmoMode::Scale => {
+				let (_, along_axis) = closest_to_line(ray, origin, direction)?;
+				Some(Vec3::new(along_axis, 0.0, 0.0))
+			}
+		}
+	}
+}
+
+/// Returns the parameters of the closest points between a ray and an
+/// infinite line, as `(along_ray, along_line)`.
+///
+/// Returns `None` when the two are nearly parallel.
+fn closest_to_line(ray: &Ray, point: Vec3, direction: Vec3) -> Option<(f32, f32)> {
+	let offset = ray.origin - point;
+	let b = ray.direction.dot(direction);
+	let d = ray.direction.dot(offset);
+	let e = direction.dot(offset);
+	let denom = 1.0 - b * b;
+
+	if denom.abs() < 1e-4 {
+		return None;
+	}
+
+	let along_ray = (b * e - d) / denom;
+	let along_line = (e - b * d) / denom;
+
+	Some((along_ray.max(0.0), along_line))
+}
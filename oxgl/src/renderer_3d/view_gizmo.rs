@@ -0,0 +1,456 @@
+//! Viewport Navigation Gizmo
+//!
+//! Provides an axis triad or orientation cube drawn in a corner of the
+//! canvas that mirrors the camera's orientation. Clicking an axis tip or
+//! cube face swings the camera around its target to look along that axis,
+//! like the navigation widgets found in modelling tools.
+//!
+//! The gizmo only edits the [`Camera`]'s position and up vector, keeping the
+//! target and distance, so it composes with any controller that orbits the
+//! camera target.
+//!
+//! ## Examples
+//!
+//! ```ignore
+//! use oxgl::renderer_3d::{StandardView, ViewGizmo, ViewGizmoStyle};
+//!
+//! let mut view_gizmo = ViewGizmo::new().with_style(ViewGizmoStyle::Cube);
+//!
+//! // pointerdown: snap the camera if an axis was clicked
+//! if view_gizmo.pointer_down(&scene.camera, x, y, width, height) {
+//!		return;
+//! }
+//!
+//! // pointermove: highlight the axis under the cursor
+//! view_gizmo.pointer_move(&scene.camera, x, y, width, height);
+//!
+//! // Each frame
+//! view_gizmo.update(&mut scene.camera, delta_time);
+//! view_gizmo.draw(&gl, &gizmos, &scene.camera, width, height);
+//!
+//! // Or snap from a keyboard shortcut
+//! view_gizmo.snap(&scene.camera, StandardView::Top);
+//! ```
+//!
+
+use glam::{Quat, Vec2, Vec3};
+use web_sys::WebGl2RenderingContext as GL;
+
+use crate::common::{Camera, render_state::current_viewport};
+use super::GizmoRenderer;
+
+const AXIS_COLORS: [Vec3; 3] = [
+	Vec3::new(1.0, 0.2, 0.2),
+	Vec3::new(0.2, 1.0, 0.2),
+	Vec3::new(0.2, 0.4, 1.0),
+];
+const AXIS_NAMES: [&str; 3] = ["X", "Y", "Z"];
+const ACTIVE_COLOR: Vec3 = Vec3::new(1.0, 1.0, 0.2);
+
+/// Distance of the widget camera from the triad origin.
+const WIDGET_DISTANCE: f32 = 3.2;
+/// Radius of the clickable disc at each axis tip, in triad units.
+const TIP_RADIUS: f32 = 0.18;
+/// Half the edge length of the orientation cube, in triad units.
+const CUBE_HALF_SIZE: f32 = 0.6;
+/// Size of the face outlines relative to the faces.
+const FACE_INSET: f32 = 0.85;
+const EDGE_COLOR: Vec3 = Vec3::new(0.6, 0.6, 0.6);
+
+/// An axis-aligned camera direction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StandardView {
+	/// Looking along -Z.
+	Front,
+	/// Looking along +Z.
+	Back,
+	/// Looking along +X.
+	Left,
+	/// Looking along -X.
+	Right,
+	/// Looking down along -Y.
+	Top,
+	/// Looking up along +Y.
+	Bottom,
+}
+
+impl StandardView {
+	/// Returns the direction from the target to the camera.
+	pub fn direction(&self) -> Vec3 {
+		match self {
+			StandardView::Front => Vec3::Z,
+			StandardView::Back => Vec3::NEG_Z,
+			StandardView::Left => Vec3::NEG_X,
+			StandardView::Right => Vec3::X,
+			StandardView::Top => Vec3::Y,
+			StandardView::Bottom => Vec3::NEG_Y,
+		}
+	}
+
+	/// Returns the camera up vector for this view.
+	pub fn up(&self) -> Vec3 {
+		match self {
+			StandardView::Top => Vec3::NEG_Z,
+			StandardView::Bottom => Vec3::Z,
+			_ => Vec3::Y,
+		}
+	}
+
+	/// Returns the name of the view, as shown on the orientation cube.
+	pub fn name(&self) -> &'static str {
+		match self {
+			StandardView::Front => "Front",
+			StandardView::Back => "Back",
+			StandardView::Left => "Left",
+			StandardView::Right => "Right",
+			StandardView::Top => "Top",
+			StandardView::Bottom => "Bottom",
+		}
+	}
+
+	/// Returns the view whose camera sits on an axis tip.
+	fn from_axis(axis: usize, positive: bool) -> Self {
+		match (axis, positive) {
+			(0, true) => StandardView::Right,
+			(0, false) => StandardView::Left,
+			(1, true) => StandardView::Top,
+			(1, false) => StandardView::Bottom,
+			(_, true) => StandardView::Front,
+			(_, false) => StandardView::Back,
+		}
+	}
+}
+
+/// Look of a [`ViewGizmo`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ViewGizmoStyle {
+	/// Colored axes with clickable tips.
+	#[default]
+	Axes,
+	/// A cube with labelled, clickable faces.
+	Cube,
+}
+
+/// Canvas corner the gizmo is drawn in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ViewCorner {
+	TopLeft,
+	#[default]
+	TopRight,
+	BottomLeft,
+	BottomRight,
+}
+
+/// An in-progress camera swing towards a standard view.
+#[derive(Clone, Debug)]
+struct Transition {
+	view: StandardView,
+	from_direction: Vec3,
+	from_up: Vec3,
+	elapsed: f32,
+}
+
+/// Corner axis triad or orientation cube for snapping the camera to
+/// standard views.
+///
+/// The gizmo doesn't listen to events itself; forward pointer positions to
+/// [`pointer_down`](Self::pointer_down) and
+/// [`pointer_move`](Self::pointer_move), and call [`update`](Self::update)
+/// every frame to animate snapping.
+#[derive(Clone, Debug)]
+pub struct ViewGizmo {
+	pub style: ViewGizmoStyle,
+	pub corner: ViewCorner,
+	/// Width and height of the widget, in pixels.
+	pub size: f32,
+	/// Distance from the canvas edges, in pixels.
+	pub margin: f32,
+	/// Duration of the camera swing, in seconds. `0.0` snaps instantly.
+	pub transition_time: f32,
+	hovered: Option<StandardView>,
+	transition: Option<Transition>,
+}
+
+impl Default for ViewGizmo {
+	fn default() -> Self {
+		Self {
+			style: ViewGizmoStyle::default(),
+			corner: ViewCorner::default(),
+			size: 100.0,
+			margin: 12.0,
+			transition_time: 0.3,
+			hovered: None,
+			transition: None,
+		}
+	}
+}
+
+impl ViewGizmo {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	pub fn with_style(mut self, style: ViewGizmoStyle) -> Self {
+		self.style = style;
+		self
+	}
+
+	pub fn with_corner(mut self, corner: ViewCorner) -> Self {
+		self.corner = corner;
+		self
+	}
+
+	pub fn with_size(mut self, size: f32) -> Self {
+		self.size = size;
+		self
+	}
+
+	/// Returns `true` while the camera is swinging to a view.
+	pub fn is_animating(&self) -> bool {
+		self.transition.is_some()
+	}
+
+	/// Returns the view under the pointer, if any.
+	pub fn hovered(&self) -> Option<StandardView> {
+		self.hovered
+	}
+
+	/// Returns the widget's top-left corner in pixels, measured from the
+	/// top-left of the canvas.
+	pub fn origin(&self, width: f32, height: f32) -> Vec2 {
+		let (left, top) = match self.corner {
+			ViewCorner::TopLeft => (true, true),
+			ViewCorner::TopRight => (false, true),
+			ViewCorner::BottomLeft => (true, false),
+			ViewCorner::BottomRight => (false, false),
+		};
+
+		Vec2::new(
+			if left { self.margin } else { width - self.margin - self.size },
+			if top { self.margin } else { height - self.margin - self.size },
+		)
+	}
+
+	/// Returns `true` if a pixel lies inside the widget.
+	pub fn contains(&self, x: f32, y: f32, width: f32, height: f32) -> bool {
+		let min = self.origin(width, height);
+		let max = min + Vec2::splat(self.size);
+
+		(min.x..=max.x).contains(&x) && (min.y..=max.y).contains(&y)
+	}
+
+	/// Returns the view whose axis tip or cube face is under a pixel.
+	pub fn pick(&self, camera: &Camera, x: f32, y: f32, width: f32, height: f32) -> Option<StandardView> {
+		if !self.contains(x, y, width, height) {
+			return None;
+		}
+
+		let widget = self.widget_camera(camera);
+		let local = Vec2::new(x, y) - self.origin(width, height);
+
+		if self.style == ViewGizmoStyle::Cube {
+			let ndc = Vec2::new(local.x, self.size - local.y) / self.size * 2.0 - 1.0;
+			let ray = widget.ray_from_ndc(ndc);
+			let hit = ray.at(ray.intersect_aabb(Vec3::splat(-CUBE_HALF_SIZE), Vec3::splat(CUBE_HALF_SIZE))?);
+
+			// The face hit is the one the point lies furthest along
+			let axis = (0..3).max_by(|&a, &b| hit[a].abs().total_cmp(&hit[b].abs())).unwrap_or(0);
+			return Some(StandardView::from_axis(axis, hit[axis] > 0.0));
+		}
+
+		let radius = TIP_RADIUS / (WIDGET_DISTANCE * (widget.fov_y * 0.5).tan()) * self.size * 0.5;
+
+		// Tips nearer the viewer win when they overlap
+		self.tips(&widget)
+			.into_iter()
+			.filter(|(_, point, _)| point.distance(local) <= radius)
+			.min_by(|a, b| a.2.total_cmp(&b.2))
+			.map(|(view, _, _)| view)
+	}
+
+	/// Snaps the camera if an axis tip was clicked.
+	///
+	/// Returns `true` if the click landed on the widget, in which case it
+	/// shouldn't also be used for picking or camera control.
+	pub fn pointer_down(&mut self, camera: &Camera, x: f32, y: f32, width: f32, height: f32) -> bool {
+		if let Some(view) = self.pick(camera, x, y, width, height) {
+			self.snap(camera, view);
+		}
+
+		self.contains(x, y, width, height)
+	}
+
+	/// Updates the highlighted axis tip.
+	pub fn pointer_move(&mut self, camera: &Camera, x: f32, y: f32, width: f32, height: f32) {
+		self.hovered = self.pick(camera, x, y, width, height);
+	}
+
+	/// Starts swinging the camera around its target to a standard view.
+	///
+	/// The camera is moved by [`update`](Self::update).
+	pub fn snap(&mut self, camera: &Camera, view: StandardView) {
+		let from_direction = (camera.position - camera.target).try_normalize().unwrap_or(Vec3::Z);
+
+		self.transition = Some(Transition {
+			view,
+			from_direction,
+			from_up: camera.up,
+			elapsed: 0.0,
+		});
+	}
+
+	/// Advances the current transition.
+	///
+	/// Returns `true` if the camera moved.
+	pub fn update(&mut self, camera: &mut Camera, delta_time: f32) -> bool {
+		let Some(transition) = &mut self.transition else {
+			return false;
+		};
+
+		transition.elapsed += delta_time;
+
+		let t = if self.transition_time > 0.0 {
+			(transition.elapsed / self.transition_time).min(1.0)
+		} else {
+			1.0
+		};
+		let eased = t * t * (3.0 - 2.0 * t);
+
+		let view = transition.view;
+		let distance = camera.position.distance(camera.target);
+		let rotation = Quat::IDENTITY.slerp(Quat::from_rotation_arc(transition.from_direction, view.direction()), eased);
+
+		camera.position = camera.target + rotation * transition.from_direction * distance;
+		camera.up = (rotation * transition.from_up).lerp(view.up(), eased).normalize_or(view.up());
+
+		if t >= 1.0 {
+			camera.position = camera.target + view.direction() * distance;
+			camera.up = view.up();
+			self.transition = None;
+		}
+
+		true
+	}
+
+	/// Draws the triad in its corner.
+	///
+	/// The widget is drawn in a batch of its own, so a batch the caller has
+	/// open is left for the main camera. The viewport and depth test are
+	/// restored afterwards.
+	pub fn draw(&self, gl: &GL, gizmos: &GizmoRenderer, camera: &Camera, width: f32, height: f32) {
+		let widget = self.widget_camera(camera);
+		let origin = self.origin(width, height);
+
+		// GL viewports are measured from the bottom-left, in drawing buffer pixels
+		let scale = gl.drawing_buffer_width() as f32 / width;
+		let x = (origin.x * scale) as i32;
+		let y = ((height - origin.y - self.size) * scale) as i32;
+		let size = (self.size * scale) as i32;
+
+		let viewport = current_viewport(gl);
+		let depth_test = gl.is_enabled(GL::DEPTH_TEST);
+
+		gl.viewport(x, y, size, size);
+		gl.disable(GL::DEPTH_TEST);
+
+		gizmos.isolated(gl, &widget, |gizmos| match self.style {
+			ViewGizmoStyle::Axes => self.draw_axes(gl, gizmos, &widget),
+			ViewGizmoStyle::Cube => self.draw_cube(gl, gizmos, &widget),
+		});
+
+		if depth_test {
+			gl.enable(GL::DEPTH_TEST);
+		}
+
+		let [x, y, width, height] = viewport;
+		gl.viewport(x, y, width, height);
+	}
+
+	fn draw_axes(&self, gl: &GL, gizmos: &GizmoRenderer, widget: &Camera) {
+		let facing = (widget.position - widget.target).normalize();
+
+		for (axis, color) in AXIS_COLORS.iter().enumerate() {
+			let direction = Vec3::AXES[axis];
+
+			for positive in [true, false] {
+				let view = StandardView::from_axis(axis, positive);
+				let tip = if positive { direction } else { -direction };
+				let color = if self.hovered == Some(view) { ACTIVE_COLOR } else { *color };
+
+				if positive {
+					gizmos.line(gl, widget, Vec3::ZERO, tip, color);
+					gizmos.label(gl, widget, tip, AXIS_NAMES[axis], color);
+				}
+
+				gizmos.circle(gl, widget, tip, facing, TIP_RADIUS, if positive { color } else { color * 0.5 });
+			}
+		}
+	}
+
+	/// Draws the cube's edges, and an outline and label on each face
+	/// turned towards the viewer.
+	fn draw_cube(&self, gl: &GL, gizmos: &GizmoRenderer, widget: &Camera) {
+		let facing = (widget.position - widget.target).normalize();
+
+		gizmos.wire_cube(gl, widget, Vec3::ZERO, CUBE_HALF_SIZE * 2.0, EDGE_COLOR);
+
+		for (axis, color) in AXIS_COLORS.iter().enumerate() {
+			for positive in [true, false] {
+				let view = StandardView::from_axis(axis, positive);
+				let normal = view.direction();
+
+				if normal.dot(facing) < 0.05 {
+					continue;
+				}
+
+				let color = if self.hovered == Some(view) {
+					ACTIVE_COLOR
+				} else if positive {
+					*color
+				} else {
+					*color * 0.6
+				};
+
+				let center = normal * CUBE_HALF_SIZE;
+				let (u, v) = (Vec3::AXES[(axis + 1) % 3], Vec3::AXES[(axis + 2) % 3]);
+				let corner = |a: f32, b: f32| center + (u * a + v * b) * CUBE_HALF_SIZE * FACE_INSET;
+				let outline = [corner(-1.0, -1.0), corner(1.0, -1.0), corner(1.0, 1.0), corner(-1.0, 1.0)];
+
+				for i in 0..4 {
+					gizmos.line(gl, widget, outline[i], outline[(i + 1) % 4], color);
+				}
+
+				gizmos.label_centered(gl, widget, center, view.name(), color);
+			}
+		}
+	}
+
+	/// Returns a camera looking at the triad from the main camera's direction.
+	fn widget_camera(&self, camera: &Camera) -> Camera {
+		let direction = (camera.position - camera.target).try_normalize().unwrap_or(Vec3::Z);
+
+		let mut widget = Camera::new(1.0)
+			.with_position(direction * WIDGET_DISTANCE)
+			.with_target(Vec3::ZERO);
+
+		widget.up = camera.up;
+		widget.fov_y = 40f32.to_radians();
+		widget
+	}
+
+	/// Returns every axis tip's view, widget-local pixel position and depth.
+	fn tips(&self, widget: &Camera) -> Vec<(StandardView, Vec2, f32)> {
+		let view_projection = widget.projection_matrix() * widget.view_matrix();
+
+		(0..3)
+			.flat_map(|axis| [(axis, true), (axis, false)])
+			.map(|(axis, positive)| {
+				let tip = if positive { Vec3::AXES[axis] } else { -Vec3::AXES[axis] };
+				let ndc = view_projection.project_point3(tip);
+				let pixel = Vec2::new(ndc.x + 1.0, 1.0 - ndc.y) * 0.5 * self.size;
+
+				(StandardView::from_axis(axis, positive), pixel, ndc.z)
+			})
+			.collect()
+	}
+}
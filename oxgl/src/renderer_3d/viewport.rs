@@ -0,0 +1,140 @@
+//! Viewports
+//!
+//! Provides [`Viewport`], a camera drawing into a rectangle of the canvas,
+//! for split-screen games and editor quad views. A list of viewports is
+//! rendered with [`Scene::render_viewports`](super::Scene::render_viewports).
+//!
+//! Rectangles are fractions of the canvas, so layouts follow canvas
+//! resizes. Each viewport can have its own post-processing stack.
+//!
+//! ## Examples
+//!
+//! ```ignore
+//! use oxgl::renderer_3d::{Viewport, ViewportRect};
+//! use oxgl::common::{PostProcessStack, presets};
+//!
+//! // Two players side by side, the second in grayscale
+//! let mut grayscale = PostProcessStack::new(&gl, 1, 1)?;
+//! grayscale.push(presets::grayscale(&gl));
+//!
+//! let mut viewports = vec![
+//!		Viewport::new(player_one_camera, ViewportRect::grid(2, 1, 0, 0)),
+//!		Viewport::new(player_two_camera, ViewportRect::grid(2, 1, 1, 0))
+//!			.with_post_process(grayscale),
+//! ];
+//!
+//! // In the render loop
+//! viewports[0].camera.position = player_one.position;
+//! scene.render_viewports(&renderer, &mut viewports, time);
+//! ```
+//!
+
+use crate::common::{Camera, PostProcessStack};
+
+/// A rectangle of the canvas as fractions of its size, measured from the
+/// bottom-left corner.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ViewportRect {
+	pub x: f32,
+	pub y: f32,
+	pub width: f32,
+	pub height: f32,
+}
+
+impl ViewportRect {
+	pub fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+		Self { x, y, width, height }
+	}
+
+	/// Covers the whole canvas.
+	pub fn full() -> Self {
+		Self::new(0.0, 0.0, 1.0, 1.0)
+	}
+
+	/// Returns a cell of an evenly divided canvas, with row `0` at the top.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use oxgl::renderer_3d::ViewportRect;
+	///
+	/// // Top-right quarter of a quad view
+	/// let rect = ViewportRect::grid(2, 2, 1, 0);
+	///
+	/// assert_eq!(rect, ViewportRect::new(0.5, 0.5, 0.5, 0.5));
+	/// assert_eq!(rect.to_pixels(800, 600), (400, 300, 400, 300));
+	/// ```
+	pub fn grid(columns: u32, rows: u32, column: u32, row: u32) -> Self {
+		let (columns, rows) = (columns.max(1) as f32, rows.max(1) as f32);
+
+		Self::new(
+			column as f32 / columns,
+			1.0 - (row + 1) as f32 / rows,
+			1.0 / columns,
+			1.0 / rows,
+		)
+	}
+
+	/// Returns `(x, y, width, height)` in pixels for a canvas size.
+	///
+	/// Edges are rounded so adjacent rectangles neither overlap nor leave
+	/// gaps.
+	pub fn to_pixels(&self, canvas_width: i32, canvas_height: i32) -> (i32, i32, i32, i32) {
+		let (w, h) = (canvas_width as f32, canvas_height as f32);
+
+		let left = (self.x * w).round() as i32;
+		let bottom = (self.y * h).round() as i32;
+		let right = ((self.x + self.width) * w).round() as i32;
+		let top = ((self.y + self.height) * h).round() as i32;
+
+		(left, bottom, right - left, top - bottom)
+	}
+
+	/// Returns `true` if a point in canvas pixels, measured from the
+	/// top-left corner like pointer events, is inside the rectangle.
+	pub fn contains(&self, x: f32, y: f32, canvas_width: f32, canvas_height: f32) -> bool {
+		let (u, v) = (x / canvas_width, 1.0 - y / canvas_height);
+
+		u >= self.x && u < self.x + self.width && v >= self.y && v < self.y + self.height
+	}
+}
+
+impl Default for ViewportRect {
+	fn default() -> Self {
+		Self::full()
+	}
+}
+
+/// A camera rendering into a rectangle of the canvas.
+pub struct Viewport {
+	/// The camera's aspect ratio is replaced with the rectangle's when
+	/// rendering.
+	pub camera: Camera,
+	pub rect: ViewportRect,
+	/// Applied to this viewport only. Resized to the rectangle as needed.
+	pub post_process: Option<PostProcessStack>,
+	pub clear_color: [f32; 4],
+	pub visible: bool,
+}
+
+impl Viewport {
+	pub fn new(camera: Camera, rect: ViewportRect) -> Self {
+		Self {
+			camera,
+			rect,
+			post_process: None,
+			clear_color: [0.1, 0.1, 0.1, 1.0],
+			visible: true,
+		}
+	}
+
+	pub fn with_post_process(mut self, post_process: PostProcessStack) -> Self {
+		self.post_process = Some(post_process);
+		self
+	}
+
+	pub fn with_clear_color(mut self, color: [f32; 4]) -> Self {
+		self.clear_color = color;
+		self
+	}
+}
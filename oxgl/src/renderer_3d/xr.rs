@@ -0,0 +1,423 @@
+//! WebXR
+//!
+//! Provides [`XrSession`], which presents a [`Scene`] on a VR headset or AR
+//! device. The session drives its own render loop from the headset's frame
+//! callback, renders the scene once per eye with the matrices the device
+//! provides, and reports the head and controller poses every frame.
+//!
+//! WebXR bindings are still unstable in `web-sys`, so the API is reached
+//! through JavaScript reflection and works without extra build flags.
+//! Sessions must be requested from a user gesture, such as a click on an
+//! "Enter VR" button.
+//!
+//! ## Examples
+//!
+//! ```ignore
+//! use oxgl::renderer_3d::{XrMode, XrSession};
+//!
+//! XrSession::is_supported(XrMode::ImmersiveVr, |supported| {
+//!		enter_vr_button.set_hidden(!supported);
+//! });
+//!
+//! // In the button's click handler
+//! XrSession::request(&renderer.gl, XrMode::ImmersiveVr, move |result| {
+//!		match result {
+//!			Ok(session) => session.run(renderer, scene, move |scene, frame| {
+//!				for controller in &frame.controllers {
+//!					if controller.trigger() > 0.5 {
+//!						// Fire from controller.target_ray
+//!					}
+//!				}
+//!			}),
+//!			Err(e) => log::error!("{}", e),
+//!		}
+//! });
+//! ```
+//!
+
+use std::{cell::{Cell, RefCell}, rc::Rc};
+use glam::{Mat4, Vec2};
+use js_sys::{Array, Float32Array, Function, Object, Promise, Reflect};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{
+	WebGlFramebuffer, WebGl2RenderingContext as GL,
+	wasm_bindgen::{JsCast, JsValue, prelude::Closure},
+};
+
+use super::Scene;
+use crate::{common::Camera, Renderer};
+
+/// The kind of XR session to start.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum XrMode {
+	/// Fully virtual surroundings on a headset.
+	ImmersiveVr,
+	/// The scene drawn over the real world. The canvas is cleared to
+	/// transparent so the camera feed shows through.
+	ImmersiveAr,
+}
+
+impl XrMode {
+	fn as_str(&self) -> &'static str {
+		match self {
+			XrMode::ImmersiveVr => "immersive-vr",
+			XrMode::ImmersiveAr => "immersive-ar",
+		}
+	}
+}
+
+/// Which hand a controller is held in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Handedness {
+	None,
+	Left,
+	Right,
+}
+
+/// The state of a controller button.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct XrButton {
+	pub pressed: bool,
+	pub touched: bool,
+	/// Analog value from `0.0` to `1.0`.
+	pub value: f32,
+}
+
+/// A tracked controller or hand.
+#[derive(Clone, Debug)]
+pub struct XrController {
+	pub handedness: Handedness,
+	/// Pose of the pointing ray, looking down -Z.
+	pub target_ray: Mat4,
+	/// Pose of the grip, for placing a held object. `None` for inputs that
+	/// can't be held, such as gaze or screen taps.
+	pub grip: Option<Mat4>,
+	/// Buttons in the `xr-standard` order: trigger, squeeze, touchpad,
+	/// thumbstick, then face buttons.
+	pub buttons: Vec<XrButton>,
+	/// Axes in the `xr-standard` order: touchpad X and Y, then thumbstick
+	/// X and Y.
+	pub axes: Vec<f32>,
+}
+
+impl XrController {
+	/// Returns how far the trigger is pulled.
+	pub fn trigger(&self) -> f32 {
+		self.buttons.first().map_or(0.0, |b| b.value)
+	}
+
+	/// Returns how firmly the grip is squeezed.
+	pub fn squeeze(&self) -> f32 {
+		self.buttons.get(1).map_or(0.0, |b| b.value)
+	}
+
+	/// Returns the thumbstick position, with Y up.
+	pub fn thumbstick(&self) -> Vec2 {
+		match self.axes.get(2..4) {
+			Some(&[x, y]) => Vec2::new(x, -y),
+			_ => Vec2::ZERO,
+		}
+	}
+}
+
+/// Poses reported for an XR frame, in the session's reference space.
+#[derive(Clone, Debug)]
+pub struct XrFrame {
+	/// Seconds since the page loaded.
+	pub time: f32,
+	/// Pose of the viewer's head, or `None` while tracking is lost.
+	pub head: Option<Mat4>,
+	pub controllers: Vec<XrController>,
+}
+
+struct XrInner {
+	session: JsValue,
+	layer: JsValue,
+	space: JsValue,
+	mode: XrMode,
+	running: Rc<Cell<bool>>,
+}
+
+/// A running WebXR session.
+///
+/// Cloning is cheap and shares the session.
+#[derive(Clone)]
+pub struct XrSession {
+	inner: Rc<XrInner>,
+}
+
+type FrameCallback = Closure<dyn FnMut(f64, JsValue)>;
+
+impl XrSession {
+	/// Checks whether the browser and device support a mode.
+	pub fn is_supported(mode: XrMode, on_result: impl FnOnce(bool) + 'static) {
+		let promise = navigator_xr().and_then(|xr| call(&xr, "isSessionSupported", &[&mode.as_str().into()]));
+
+		wasm_bindgen_futures::spawn_local(async move {
+			let supported = match promise {
+				Ok(promise) => JsFuture::from(Promise::from(promise)).await.ok().and_then(|value| value.as_bool()),
+				Err(_) => None,
+			};
+
+			on_result(supported.unwrap_or(false));
+		});
+	}
+
+	/// Starts a session and passes it to `on_ready`.
+	///
+	/// Must be called from a user gesture. The WebGL context is made XR
+	/// compatible, and a floor-level reference space is used when the
+	/// device supports one.
+	pub fn request(gl: &GL, mode: XrMode, on_ready: impl FnOnce(Result<XrSession, String>) + 'static) {
+		let options = Object::new();
+		let features = Array::of1(&"local-floor".into());
+		let _ = Reflect::set(&options, &"optionalFeatures".into(), &features);
+
+		// The session is requested right away, while the gesture is active
+		let promise = navigator_xr().and_then(|xr| call(&xr, "requestSession", &[&mode.as_str().into(), &options]));
+		let gl = gl.clone();
+
+		wasm_bindgen_futures::spawn_local(async move {
+			let result = match promise {
+				Ok(promise) => start(&gl, Promise::from(promise), mode).await,
+				Err(e) => Err(e),
+			};
+
+			on_ready(result);
+		});
+	}
+
+	/// Runs the render loop until the session ends.
+	///
+	/// `update` is called every frame before rendering, with the latest
+	/// poses. The scene is then rendered once per eye; its own
+	/// [`camera`](Scene::camera) and post-processing are unused.
+	pub fn run<F>(&self, renderer: Rc<Renderer>, scene: Rc<RefCell<Scene>>, mut update: F)
+	where
+		F: FnMut(&mut Scene, &XrFrame) + 'static,
+	{
+		let inner = self.inner.clone();
+		let f: Rc<RefCell<Option<FrameCallback>>> = Rc::new(RefCell::new(None));
+		let g = f.clone();
+
+		*g.borrow_mut() = Some(Closure::new(move |time_ms: f64, frame: JsValue| {
+			if !inner.running.get() {
+				return;
+			}
+
+			if let Some(callback) = f.borrow().as_ref() {
+				let _ = call(&inner.session, "requestAnimationFrame", &[callback.as_ref()]);
+			}
+
+			let pose = call(&frame, "getViewerPose", &[&inner.space]).unwrap_or(JsValue::NULL);
+			let xr_frame = XrFrame {
+				time: (time_ms / 1000.0) as f32,
+				head: (!pose.is_null()).then(|| matrix(&get(&pose, "transform"))),
+				controllers: read_controllers(&inner, &frame),
+			};
+
+			let mut scene = scene.borrow_mut();
+			update(&mut scene, &xr_frame);
+
+			if pose.is_null() {
+				return;
+			}
+
+			let gl = &renderer.gl;
+			let framebuffer = get(&inner.layer, "framebuffer").dyn_into::<WebGlFramebuffer>().ok();
+			// Passthrough AR shows the camera feed behind the scene
+			let [r, g, b, a] = if inner.mode == XrMode::ImmersiveAr { [0.0; 4] } else { scene.clear_color() };
+
+			scene.color_space = renderer.color_space();
+			scene.begin_frame(xr_frame.time);
+
+			gl.bind_framebuffer(GL::FRAMEBUFFER, framebuffer.as_ref());
+			gl.clear_color(r, g, b, a);
+			gl.clear(GL::COLOR_BUFFER_BIT | GL::DEPTH_BUFFER_BIT);
+
+			for view in Array::from(&get(&pose, "views")).iter() {
+				let Ok(viewport) = call(&inner.layer, "getViewport", &[&view]) else { continue };
+				let rect = ["x", "y", "width", "height"].map(|key| get(&viewport, key).as_f64().unwrap_or(0.0) as i32);
+
+				let view_matrix = matrix(&get(&get(&view, "transform"), "inverse"));
+				let projection = to_mat4(&get(&view, "projectionMatrix"));
+
+				scene.render_into(gl, framebuffer.as_ref(), rect, &Camera::from_matrices(view_matrix, projection));
+			}
+
+			scene.finish_stats();
+		}));
+
+		self.inner.running.set(true);
+
+		if let Some(callback) = g.borrow().as_ref()
+			&& let Err(e) = call(&self.inner.session, "requestAnimationFrame", &[callback.as_ref()])
+		{
+			log::error!("{}", e);
+		}
+	}
+
+	/// Ends the session and its render loop.
+	pub fn end(&self) {
+		self.inner.running.set(false);
+
+		if let Err(e) = call(&self.inner.session, "end", &[]) {
+			log::error!("{}", e);
+		}
+	}
+
+	/// Returns `false` once the session has ended, including when the user
+	/// exits through the browser or headset.
+	pub fn is_running(&self) -> bool {
+		self.inner.running.get()
+	}
+
+	pub fn mode(&self) -> XrMode {
+		self.inner.mode
+	}
+
+	/// Returns the underlying `XRSession` object.
+	pub fn session(&self) -> &JsValue {
+		&self.inner.session
+	}
+}
+
+/// Completes a session request once the session, its layer and a
+/// reference space are ready.
+///
+/// A floor-level reference space is used when the device supports one,
+/// falling back to a head-level one.
+async fn start(gl: &GL, request: Promise, mode: XrMode) -> Result<XrSession, String> {
+	let session = resolve(request).await?;
+	resolve(Promise::from(call(gl, "makeXRCompatible", &[])?)).await?;
+
+	let layer = create_layer(&session, gl)?;
+	let state = Object::new();
+	let _ = Reflect::set(&state, &"baseLayer".into(), &layer);
+	call(&session, "updateRenderState", &[&state])?;
+
+	// Devices without floor tracking refuse the floor space
+	let floor = call(&session, "requestReferenceSpace", &[&"local-floor".into()]);
+	let space = match floor {
+		Ok(promise) => resolve(Promise::from(promise)).await.ok(),
+		Err(_) => None,
+	};
+	let space = match space {
+		Some(space) => space,
+		None => resolve(Promise::from(call(&session, "requestReferenceSpace", &[&"local".into()])?)).await?,
+	};
+
+	let running = Rc::new(Cell::new(true));
+	let ended = running.clone();
+
+	let on_end = Closure::once_into_js(move || ended.set(false));
+	let _ = call(&session, "addEventListener", &[&"end".into(), &on_end]);
+
+	Ok(XrSession {
+		inner: Rc::new(XrInner { session, layer, space, mode, running }),
+	})
+}
+
+fn create_layer(session: &JsValue, gl: &GL) -> Result<JsValue, String> {
+	let window = web_sys::window().ok_or("No window")?;
+	let constructor = get(&window, "XRWebGLLayer")
+		.dyn_into::<Function>()
+		.map_err(|_| "XRWebGLLayer is not available")?;
+
+	Reflect::construct(&constructor, &Array::of2(session, gl))
+		.map_err(|e| format!("Failed to create XR layer: {:?}", e))
+}
+
+fn read_controllers(inner: &XrInner, frame: &JsValue) -> Vec<XrController> {
+	let sources = Array::from(&get(&inner.session, "inputSources"));
+	let pose_of = |space: JsValue| -> Option<Mat4> {
+		if space.is_undefined() || space.is_null() {
+			return None;
+		}
+
+		let pose = call(frame, "getPose", &[&space, &inner.space]).ok()?;
+		(!pose.is_null()).then(|| matrix(&get(&pose, "transform")))
+	};
+
+	sources
+		.iter()
+		.filter_map(|source| {
+			let target_ray = pose_of(get(&source, "targetRaySpace"))?;
+			let gamepad = get(&source, "gamepad");
+
+			let handedness = match get(&source, "handedness").as_string().as_deref() {
+				Some("left") => Handedness::Left,
+				Some("right") => Handedness::Right,
+				_ => Handedness::None,
+			};
+
+			let (buttons, axes) = if gamepad.is_object() {
+				let buttons = Array::from(&get(&gamepad, "buttons"))
+					.iter()
+					.map(|button| XrButton {
+						pressed: get(&button, "pressed").is_truthy(),
+						touched: get(&button, "touched").is_truthy(),
+						value: get(&button, "value").as_f64().unwrap_or(0.0) as f32,
+					})
+					.collect();
+				let axes = Array::from(&get(&gamepad, "axes"))
+					.iter()
+					.map(|axis| axis.as_f64().unwrap_or(0.0) as f32)
+					.collect();
+
+				(buttons, axes)
+			} else {
+				(Vec::new(), Vec::new())
+			};
+
+			Some(XrController {
+				handedness,
+				target_ray,
+				grip: pose_of(get(&source, "gripSpace")),
+				buttons,
+				axes,
+			})
+		})
+		.collect()
+}
+
+fn navigator_xr() -> Result<JsValue, String> {
+	let window = web_sys::window().ok_or("No window")?;
+	let xr = get(&get(&window, "navigator"), "xr");
+
+	if xr.is_undefined() {
+		return Err("WebXR is not supported by this browser".into());
+	}
+
+	Ok(xr)
+}
+
+/// Waits for a promise, reporting a rejection as an error.
+async fn resolve(promise: Promise) -> Result<JsValue, String> {
+	JsFuture::from(promise).await.map_err(|e| format!("WebXR request failed: {:?}", e))
+}
+
+fn get(target: &JsValue, key: &str) -> JsValue {
+	Reflect::get(target, &key.into()).unwrap_or(JsValue::UNDEFINED)
+}
+
+fn call(target: &JsValue, method: &str, args: &[&JsValue]) -> Result<JsValue, String> {
+	let function = get(target, method)
+		.dyn_into::<Function>()
+		.map_err(|_| format!("{} is not available", method))?;
+	let args: Array = args.iter().copied().collect();
+
+	function.apply(target, &args).map_err(|e| format!("{} failed: {:?}", method, e))
+}
+
+/// Reads the matrix of an `XRRigidTransform`.
+fn matrix(transform: &JsValue) -> Mat4 {
+	to_mat4(&get(transform, "matrix"))
+}
+
+/// Converts a column-major `Float32Array` to a matrix.
+fn to_mat4(value: &JsValue) -> Mat4 {
+	let values = value.clone().unchecked_into::<Float32Array>().to_vec();
+
+	if values.len() == 16 { Mat4::from_cols_slice(&values) } else { Mat4::IDENTITY }
+}
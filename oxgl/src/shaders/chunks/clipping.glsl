@@ -0,0 +1,13 @@
+const int MAX_CLIP_PLANES = 6;
+
+uniform int numClipPlanes;
+uniform vec4 clipPlanes[MAX_CLIP_PLANES];
+
+// Discards fragments behind any clip plane. Planes are (normal, distance)
+// in world space, keeping points where dot(normal, p) + distance >= 0.
+void applyClipping(vec3 worldPos) {
+	for (int i = 0; i < MAX_CLIP_PLANES; i++) {
+		if (i >= numClipPlanes) break;
+		if (dot(clipPlanes[i].xyz, worldPos) + clipPlanes[i].w < 0.0) discard;
+	}
+}
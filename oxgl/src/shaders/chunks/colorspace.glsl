@@ -0,0 +1,25 @@
+// colorSpace is 0 for linear output and 1 for sRGB output.
+uniform int colorSpace;
+
+// Decodes an sRGB input color to linear light for shading.
+vec3 toLinear(vec3 color) {
+	if (colorSpace == 0) return color;
+
+	vec3 low = color / 12.92;
+	vec3 high = pow((max(color, 0.0) + 0.055) / 1.055, vec3(2.4));
+	return mix(low, high, step(0.04045, color));
+}
+
+// Decodes an sRGB input color, keeping its alpha.
+vec4 toLinear(vec4 color) {
+	return vec4(toLinear(color.rgb), color.a);
+}
+
+// Encodes a shaded linear color for display.
+vec3 toOutput(vec3 color) {
+	if (colorSpace == 0) return color;
+
+	vec3 low = color * 12.92;
+	vec3 high = 1.055 * pow(max(color, 0.0), vec3(1.0 / 2.4)) - 0.055;
+	return mix(low, high, step(0.0031308, color));
+}
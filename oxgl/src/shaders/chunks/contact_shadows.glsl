@@ -0,0 +1,48 @@
+#include <lights>
+
+uniform sampler2D contactDepthMap;
+// Part of the depth map the view covers, from its bottom-left corner
+uniform vec2 contactUvScale;
+uniform bool contactShadowsEnabled;
+uniform mat4 cameraViewProjection;
+uniform float cameraNear;
+uniform float cameraFar;
+// Distance a ray must pass behind a surface to be occluded
+uniform float contactBias;
+
+const int CONTACT_STEPS = 16;
+
+float linearizeDepth(float depth) {
+	float z = depth * 2.0 - 1.0;
+	return 2.0 * cameraNear * cameraFar / (cameraFar + cameraNear - z * (cameraFar - cameraNear));
+}
+
+// Marches a short ray from a world position towards a light through the
+// camera depth buffer. Returns how much of the light is blocked.
+float calculateContactShadow(Light light, vec3 worldPos) {
+	if (!contactShadowsEnabled || light.contactLength <= 0.0) return 0.0;
+
+	float attenuation;
+	vec3 lightDir = lightDirection(light, worldPos, attenuation);
+	float stepSize = light.contactLength / float(CONTACT_STEPS);
+
+	for (int i = 1; i <= CONTACT_STEPS; i++) {
+		vec4 clip = cameraViewProjection * vec4(worldPos + lightDir * stepSize * float(i), 1.0);
+		vec3 coords = clip.xyz / clip.w * 0.5 + 0.5;
+
+		if (coords.x < 0.0 || coords.x > 1.0 || coords.y < 0.0 || coords.y > 1.0) {
+			return 0.0;
+		}
+
+		float rayDepth = linearizeDepth(coords.z);
+		float sceneDepth = linearizeDepth(texture2D(contactDepthMap, coords.xy * contactUvScale).r);
+		float delta = rayDepth - sceneDepth;
+
+		// Occluded if the ray passed just behind a surface
+		if (delta > contactBias && delta < light.contactLength) {
+			return light.contactIntensity;
+		}
+	}
+
+	return 0.0;
+}
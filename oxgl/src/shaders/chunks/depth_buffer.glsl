@@ -0,0 +1,33 @@
+#include <logdepth>
+
+// The scene depth and camera set by PostProcessStack
+uniform sampler2D depthTexture;
+uniform mat4 inverseProjection;
+uniform vec2 depthProjection;
+uniform vec2 resolution;
+
+// Returns the view-space position of the surface at a screen coordinate.
+vec3 viewPosition(vec2 uv) {
+	float depth = standardDepth(texture2D(depthTexture, uv).r, depthProjection);
+	vec4 view = inverseProjection * vec4(vec3(uv, depth) * 2.0 - 1.0, 1.0);
+
+	return view.xyz / view.w;
+}
+
+// Returns the view-space normal at a screen coordinate, from neighbouring
+// depths. The closer neighbour on each axis is used so edges don't blend
+// surfaces together.
+vec3 viewNormal(vec2 uv) {
+	vec2 texel = 1.0 / resolution;
+	vec3 center = viewPosition(uv);
+
+	vec3 right = viewPosition(uv + vec2(texel.x, 0.0)) - center;
+	vec3 left = center - viewPosition(uv - vec2(texel.x, 0.0));
+	vec3 up = viewPosition(uv + vec2(0.0, texel.y)) - center;
+	vec3 down = center - viewPosition(uv - vec2(0.0, texel.y));
+
+	vec3 dx = abs(right.z) < abs(left.z) ? right : left;
+	vec3 dy = abs(up.z) < abs(down.z) ? up : down;
+
+	return normalize(cross(dx, dy));
+}
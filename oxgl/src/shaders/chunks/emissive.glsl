@@ -0,0 +1,11 @@
+#include <colorspace>
+
+uniform vec3 emissive;
+uniform float emissiveIntensity;
+// Set while the scene draws the emissive texture for post effects
+uniform bool emissivePass;
+
+// Light given off by the surface, unaffected by lights and shadows
+vec3 emission() {
+	return toLinear(emissive) * emissiveIntensity;
+}
@@ -0,0 +1,25 @@
+#include <colorspace>
+
+uniform int fogMode;
+uniform vec3 fogColor;
+uniform vec3 fogParams;
+
+// Blends a color towards the fog color by distance from the camera.
+// fogMode is 0 for none, 1 linear, 2 exponential and 3 exponential squared.
+// fogParams holds (start, end, density). The color is blended in linear light.
+vec3 applyFog(vec3 color, float distance) {
+	if (fogMode == 0) return color;
+
+	float amount;
+
+	if (fogMode == 1) {
+		amount = (distance - fogParams.x) / max(fogParams.y - fogParams.x, 1e-5);
+	} else if (fogMode == 2) {
+		amount = 1.0 - exp(-fogParams.z * distance);
+	} else {
+		float scaled = fogParams.z * distance;
+		amount = 1.0 - exp(-scaled * scaled);
+	}
+
+	return mix(color, toLinear(fogColor), clamp(amount, 0.0, 1.0));
+}
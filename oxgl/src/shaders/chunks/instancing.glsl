@@ -0,0 +1,10 @@
+// Per-instance model matrix, one column per attribute, as written by
+// Scatter::instance_data. Meshes without instances get the identity.
+attribute vec4 instanceMatrix0;
+attribute vec4 instanceMatrix1;
+attribute vec4 instanceMatrix2;
+attribute vec4 instanceMatrix3;
+
+mat4 instanceMatrix() {
+	return mat4(instanceMatrix0, instanceMatrix1, instanceMatrix2, instanceMatrix3);
+}
@@ -0,0 +1,28 @@
+#include <lights>
+#include <shadows>
+#include <contact_shadows>
+
+uniform float ambient;
+
+// Shades a surface with every active light, using Blinn-Phong specular.
+// A specularStrength of 0.0 gives Lambert shading.
+vec3 shadeSurface(vec3 albedo, vec3 normal, vec3 viewDir, vec3 worldPos, float shadow, float shininess, float specularStrength) {
+	vec3 result = ambient * albedo;
+
+	for (int i = 0; i < MAX_LIGHTS; i++) {
+		if (i >= numLights) break;
+
+		float attenuation;
+		vec3 lightDir = lightDirection(lights[i], worldPos, attenuation);
+		float contact = calculateContactShadow(lights[i], worldPos);
+		vec3 radiance = lightRadiance(lights[i]) * attenuation * (1.0 - shadow) * (1.0 - contact);
+
+		float diff = max(dot(normal, lightDir), 0.0);
+		vec3 halfDir = normalize(lightDir + viewDir);
+		float spec = specularStrength * pow(max(dot(normal, halfDir), 0.0), shininess);
+
+		result += (diff * albedo + spec) * radiance;
+	}
+
+	return result;
+}
@@ -0,0 +1,14 @@
+// Set from Material::set_lightmap. The lightmap holds linear light.
+uniform sampler2D lightmap;
+uniform float lightmapIntensity;
+
+varying vec2 vLightmapUv;
+
+bool hasLightmap() {
+	return lightmapIntensity > 0.0;
+}
+
+// Light baked into the lightmap for this surface, shadows included
+vec3 bakedLight() {
+	return texture2D(lightmap, vLightmapUv).rgb * lightmapIntensity;
+}
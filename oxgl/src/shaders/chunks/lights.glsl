@@ -0,0 +1,72 @@
+#include <colorspace>
+
+const int MAX_LIGHTS = 4;
+
+struct Light {
+	int type;
+	vec3 position;
+	vec3 direction;
+	vec3 color;
+	float intensity;
+	float radius;
+	int falloff;
+	float innerCutoff;
+	float outerCutoff;
+	float contactLength;
+	float contactIntensity;
+};
+
+uniform int numLights;
+uniform Light lights[MAX_LIGHTS];
+
+float calculateAttenuation(Light light, float distance) {
+	float ratio = distance / light.radius;
+	float window = clamp(1.0 - ratio * ratio * ratio * ratio, 0.0, 1.0);
+	window *= window;
+
+	if (light.falloff == 1) {
+		// Linear
+		return clamp(1.0 - ratio, 0.0, 1.0);
+	} else if (light.falloff == 2) {
+		// Inverse square, windowed at the radius
+		return window / (distance * distance + 1.0);
+	} else if (light.falloff == 3) {
+		// Smooth window
+		return window;
+	}
+
+	// Quadratic
+	float attenuation = clamp(1.0 - ratio, 0.0, 1.0);
+	return attenuation * attenuation;
+}
+
+// Returns a light's color scaled by its intensity, in linear light
+vec3 lightRadiance(Light light) {
+	return toLinear(light.color) * light.intensity;
+}
+
+float calculateSpotCone(Light light, vec3 lightDir) {
+	float theta = dot(lightDir, normalize(-light.direction));
+	return smoothstep(light.outerCutoff, light.innerCutoff, theta);
+}
+
+// Returns the direction towards a light from a world position, and its
+// distance and cone attenuation
+vec3 lightDirection(Light light, vec3 worldPos, out float attenuation) {
+	if (light.type == 0) {
+		// Directional
+		attenuation = 1.0;
+		return normalize(-light.direction);
+	}
+
+	vec3 toLight = light.position - worldPos;
+	vec3 lightDir = normalize(toLight);
+	attenuation = calculateAttenuation(light, length(toLight));
+
+	if (light.type == 2) {
+		// Spot
+		attenuation *= calculateSpotCone(light, lightDir);
+	}
+
+	return lightDir;
+}
@@ -0,0 +1,26 @@
+// Logarithmic depth, enabled by a non-zero logDepthFactor of
+// 2 / log2(far + 1). See Camera::logarithmic_depth.
+uniform float logDepthFactor;
+
+// Replaces the depth of a clip-space position with one spread evenly over
+// orders of magnitude. Depth is computed per vertex, so large triangles
+// close to the camera can intersect slightly wrong.
+vec4 logDepth(vec4 clip) {
+	if (logDepthFactor > 0.0) {
+		clip.z = (log2(max(1e-6, 1.0 + clip.w)) * logDepthFactor - 1.0) * clip.w;
+	}
+
+	return clip;
+}
+
+// Converts a depth buffer value back to standard perspective depth, for
+// reconstructing positions with an inverse view-projection.
+// depthProjection holds projection[2][2] and projection[3][2].
+float standardDepth(float depth, vec2 depthProjection) {
+	if (logDepthFactor <= 0.0) return depth;
+
+	float w = exp2(depth * 2.0 / logDepthFactor) - 1.0;
+	float ndc = (depthProjection.y - depthProjection.x * w) / w;
+
+	return ndc * 0.5 + 0.5;
+}
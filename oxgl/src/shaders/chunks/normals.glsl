@@ -0,0 +1,17 @@
+// Set from Material::double_sided and Material::flat_shading
+uniform bool doubleSided;
+uniform bool flatShading;
+
+// Returns the normal to shade with. Flat normals come from the screen-space
+// derivatives of the world position, which ES 1.00 shaders only have when
+// they enable GL_OES_standard_derivatives on their first line.
+vec3 surfaceNormal(vec3 normal, vec3 worldPos) {
+#if __VERSION__ >= 300 || defined(GL_OES_standard_derivatives)
+	// Always faces the camera, so backfaces need no flip
+	if (flatShading) return normalize(cross(dFdx(worldPos), dFdy(worldPos)));
+#endif
+
+	normal = normalize(normal);
+
+	return doubleSided && !gl_FrontFacing ? -normal : normal;
+}
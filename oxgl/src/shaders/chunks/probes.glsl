@@ -0,0 +1,41 @@
+#include <colorspace>
+
+// Set by the scene from the reflection probe around the object.
+uniform samplerCube probeMap;
+uniform bool probeEnabled;
+uniform vec3 probePosition;
+uniform vec3 probeBoxMin;
+uniform vec3 probeBoxMax;
+
+// Set from Material::set_reflectivity, the reflectance facing the surface.
+uniform float reflectivity;
+
+// Intersects the ray with the probe's box and returns the direction from
+// the probe to the hit, so nearby walls reflect in the right place.
+vec3 boxProject(vec3 worldPos, vec3 direction) {
+	vec3 toMax = (probeBoxMax - worldPos) / direction;
+	vec3 toMin = (probeBoxMin - worldPos) / direction;
+	vec3 exit = max(toMax, toMin);
+	float distance = min(min(exit.x, exit.y), exit.z);
+
+	return worldPos + direction * distance - probePosition;
+}
+
+// Linear color the probe sees along a reflected direction.
+vec3 probeReflection(vec3 worldPos, vec3 direction) {
+	return toLinear(textureCube(probeMap, boxProject(worldPos, direction)).rgb);
+}
+
+// Share of light reflected, stronger at grazing angles (Schlick).
+float reflectance(vec3 normal, vec3 viewDir) {
+	return reflectivity + (1.0 - reflectivity) * pow(1.0 - max(dot(normal, viewDir), 0.0), 5.0);
+}
+
+// Blends the probe's reflection into a shaded color.
+vec3 applyReflection(vec3 color, vec3 normal, vec3 viewDir, vec3 worldPos) {
+	if (!probeEnabled || reflectivity <= 0.0) return color;
+
+	vec3 reflection = probeReflection(worldPos, reflect(-viewDir, normal));
+
+	return mix(color, reflection, reflectance(normal, viewDir));
+}
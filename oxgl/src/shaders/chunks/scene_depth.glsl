@@ -0,0 +1,20 @@
+// The camera depth behind the mesh, set by the scene for materials with
+// scene_depth enabled
+uniform bool sceneDepthEnabled;
+uniform sampler2D sceneDepth;
+uniform vec2 sceneResolution;
+// Bottom-left corner of the viewport the depth map covers
+uniform vec2 sceneViewportOrigin;
+uniform vec2 sceneDepthProjection;
+
+// Returns the view distance of the nearest surface behind the current
+// fragment, or a large value where nothing was drawn.
+float sceneViewDepth() {
+	if (!sceneDepthEnabled) return 1e6;
+
+	float depth = texture2D(sceneDepth, (gl_FragCoord.xy - sceneViewportOrigin) / sceneResolution).r;
+
+	if (depth >= 1.0) return 1e6;
+
+	return sceneDepthProjection.y / (depth * 2.0 - 1.0 + sceneDepthProjection.x);
+}
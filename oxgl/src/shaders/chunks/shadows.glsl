@@ -0,0 +1,86 @@
+uniform sampler2D shadowMap;
+uniform bool shadowsEnabled;
+// Set from the scene's ShadowSettings
+uniform int shadowFilter;
+uniform float shadowBias;
+uniform float shadowLightSize;
+uniform float shadowMapSize;
+
+const int SHADOW_SAMPLES = 16;
+const float GOLDEN_ANGLE = 2.39996323;
+
+// Offset of a sample on a spiral filling the unit disk, rotated per pixel
+// to trade banding for noise
+vec2 shadowDiskSample(int index, float rotation) {
+	float i = float(index);
+	float radius = sqrt((i + 0.5) / float(SHADOW_SAMPLES));
+	float angle = i * GOLDEN_ANGLE + rotation;
+
+	return vec2(cos(angle), sin(angle)) * radius;
+}
+
+float shadowPcf(vec3 projCoords) {
+	float texelSize = 1.0 / shadowMapSize;
+	float shadow = 0.0;
+
+	for (int x = -1; x <= 1; x++) {
+		for (int y = -1; y <= 1; y++) {
+			float pcfDepth = texture2D(shadowMap, projCoords.xy + vec2(float(x), float(y)) * texelSize).r;
+			shadow += projCoords.z - shadowBias > pcfDepth ? 1.0 : 0.0;
+		}
+	}
+
+	return shadow / 9.0;
+}
+
+// Percentage-closer soft shadows: the penumbra widens with the distance
+// between the receiver and the average blocker found around it
+float shadowPcss(vec3 projCoords) {
+	float rotation = fract(sin(dot(projCoords.xy, vec2(12.9898, 78.233))) * 43758.5453) * 6.28318531;
+	float receiver = projCoords.z - shadowBias;
+
+	float blockerDepth = 0.0;
+	float blockers = 0.0;
+
+	for (int i = 0; i < SHADOW_SAMPLES; i++) {
+		vec2 offset = shadowDiskSample(i, rotation) * shadowLightSize;
+		float depth = texture2D(shadowMap, projCoords.xy + offset).r;
+
+		if (depth < receiver) {
+			blockerDepth += depth;
+			blockers += 1.0;
+		}
+	}
+
+	if (blockers == 0.0) return 0.0;
+
+	blockerDepth /= blockers;
+
+	float penumbra = (receiver - blockerDepth) / max(blockerDepth, 1.0e-4) * shadowLightSize;
+	float radius = max(penumbra, 1.0 / shadowMapSize);
+	float shadow = 0.0;
+
+	for (int i = 0; i < SHADOW_SAMPLES; i++) {
+		vec2 offset = shadowDiskSample(i, rotation) * radius;
+		float depth = texture2D(shadowMap, projCoords.xy + offset).r;
+
+		shadow += receiver > depth ? 1.0 : 0.0;
+	}
+
+	return shadow / float(SHADOW_SAMPLES);
+}
+
+float calculateShadow(vec4 posLightSpace) {
+	if (!shadowsEnabled) return 0.0;
+	
+	vec3 projCoords = posLightSpace.xyz / posLightSpace.w;
+	projCoords = projCoords * 0.5 + 0.5;
+	
+	if (projCoords.x < 0.0 || projCoords.x > 1.0 ||
+		projCoords.y < 0.0 || projCoords.y > 1.0 ||
+		projCoords.z > 1.0) {
+		return 0.0;
+	}
+
+	return shadowFilter == 1 ? shadowPcss(projCoords) : shadowPcf(projCoords);
+}
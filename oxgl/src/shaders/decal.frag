@@ -0,0 +1,52 @@
+#version 300 es
+precision highp float;
+
+uniform sampler2D depthMap;
+uniform sampler2D decalTexture;
+
+uniform mat4 inverseViewProjection;
+uniform mat4 inverseModel;
+uniform vec3 projectionAxis;
+uniform vec2 resolution;
+// Bottom-left corner of the viewport, as the depth map only covers it
+uniform vec2 viewportOrigin;
+// Part of the depth map the viewport covers, from its bottom-left corner
+uniform vec2 depthUvScale;
+
+uniform vec4 color;
+uniform float normalThreshold;
+// 0 alpha, 1 additive, 2 multiply
+uniform int blendMode;
+
+out vec4 fragColor;
+
+void main() {
+	vec2 uv = (gl_FragCoord.xy - viewportOrigin) / resolution;
+	float depth = texture(depthMap, uv * depthUvScale).r;
+
+	if (depth >= 1.0) discard;
+
+	vec4 world = inverseViewProjection * vec4(vec3(uv, depth) * 2.0 - 1.0, 1.0);
+	vec3 worldPos = world.xyz / world.w;
+	vec3 local = (inverseModel * vec4(worldPos, 1.0)).xyz;
+
+	if (any(greaterThan(abs(local), vec3(0.5)))) discard;
+
+	// Faces the camera, so only surfaces turned towards the decal are hit
+	vec3 normal = normalize(cross(dFdx(worldPos), dFdy(worldPos)));
+
+	if (dot(normal, projectionAxis) < normalThreshold) discard;
+
+	// Fade out towards the top and bottom of the box
+	float fade = 1.0 - smoothstep(0.4, 0.5, abs(local.y));
+	vec4 result = texture(decalTexture, local.xz + 0.5) * color;
+	result.a *= fade;
+
+	if (result.a < 0.01) discard;
+
+	if (blendMode == 2) {
+		fragColor = vec4(mix(vec3(1.0), result.rgb, result.a), 1.0);
+	} else {
+		fragColor = result;
+	}
+}
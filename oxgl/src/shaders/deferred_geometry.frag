@@ -0,0 +1,32 @@
+#version 300 es
+precision highp float;
+
+#include <colorspace>
+#include <clipping>
+#include <normals>
+
+uniform vec3 color;
+uniform float ambient;
+uniform float shininess;
+uniform float specularStrength;
+// 1.0 when shadows fall on the object
+uniform float receiveShadows;
+
+in vec3 vNormal;
+in vec3 vWorldPos;
+in vec3 vColor;
+
+// Linear albedo and specular strength
+layout(location = 0) out vec4 gAlbedo;
+// Normal packed into 0-1
+layout(location = 1) out vec4 gNormal;
+// Ambient, shininess / 256 and whether shadows are received
+layout(location = 2) out vec4 gMaterial;
+
+void main() {
+	applyClipping(vWorldPos);
+
+	gAlbedo = vec4(toLinear(color) * toLinear(vColor), specularStrength);
+	gNormal = vec4(surfaceNormal(vNormal, vWorldPos) * 0.5 + 0.5, 1.0);
+	gMaterial = vec4(ambient, shininess / 256.0, receiveShadows, 1.0);
+}
@@ -0,0 +1,24 @@
+#version 300 es
+
+#include <logdepth>
+
+in vec3 position;
+in vec3 normal;
+in vec3 vertexColor;
+
+uniform mat4 model;
+uniform mat4 view;
+uniform mat4 projection;
+
+out vec3 vNormal;
+out vec3 vWorldPos;
+out vec3 vColor;
+
+void main() {
+	vec4 worldPos = model * vec4(position, 1.0);
+	vWorldPos = worldPos.xyz;
+	vNormal = mat3(model) * normal;
+	vColor = vertexColor;
+
+	gl_Position = logDepth(projection * view * worldPos);
+}
@@ -0,0 +1,49 @@
+#version 300 es
+precision highp float;
+
+// The shadow chunk is written for GLSL ES 1.00
+#define texture2D texture
+
+#include <lights>
+#include <shadows>
+#include <logdepth>
+
+uniform sampler2D gAlbedo;
+uniform sampler2D gNormal;
+uniform sampler2D gMaterial;
+uniform sampler2D gDepth;
+
+uniform mat4 inverseViewProjection;
+uniform vec2 depthProjection;
+uniform mat4 lightSpace;
+uniform vec3 cameraPosition;
+
+in vec2 vUv;
+
+out vec4 fragColor;
+
+// Adds one light's contribution to the light buffer. The light is lights[0].
+void main() {
+	float depth = texture(gDepth, vUv).r;
+	if (depth >= 1.0) discard;
+
+	vec4 world = inverseViewProjection * vec4(vec3(vUv, standardDepth(depth, depthProjection)) * 2.0 - 1.0, 1.0);
+	vec3 worldPos = world.xyz / world.w;
+
+	vec4 albedo = texture(gAlbedo, vUv);
+	vec3 normal = normalize(texture(gNormal, vUv).xyz * 2.0 - 1.0);
+	vec4 material = texture(gMaterial, vUv);
+	float shininess = material.g * 256.0;
+	vec3 viewDir = normalize(cameraPosition - worldPos);
+
+	float attenuation;
+	vec3 lightDir = lightDirection(lights[0], worldPos, attenuation);
+	float shadow = material.b * calculateShadow(lightSpace * vec4(worldPos, 1.0));
+	vec3 radiance = lightRadiance(lights[0]) * attenuation * (1.0 - shadow);
+
+	float diff = max(dot(normal, lightDir), 0.0);
+	vec3 halfDir = normalize(lightDir + viewDir);
+	float spec = albedo.a * pow(max(dot(normal, halfDir), 0.0), shininess);
+
+	fragColor = vec4((diff * albedo.rgb + spec) * radiance, 1.0);
+}
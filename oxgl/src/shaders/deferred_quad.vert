@@ -0,0 +1,17 @@
+#version 300 es
+
+// Corners of a unit quad
+in vec2 position;
+
+// Area to cover in normalized device coordinates, as (min, max)
+uniform vec4 rect;
+// Fraction of the G-buffer covered by the view
+uniform vec2 uvScale;
+
+out vec2 vUv;
+
+void main() {
+	vec2 ndc = mix(rect.xy, rect.zw, position);
+	vUv = (ndc * 0.5 + 0.5) * uvScale;
+	gl_Position = vec4(ndc, 0.0, 1.0);
+}
@@ -0,0 +1,36 @@
+#version 300 es
+precision highp float;
+
+#include <colorspace>
+#include <fog>
+#include <logdepth>
+
+uniform sampler2D lightBuffer;
+uniform sampler2D gAlbedo;
+uniform sampler2D gMaterial;
+uniform sampler2D gDepth;
+
+uniform mat4 inverseViewProjection;
+uniform vec2 depthProjection;
+uniform vec3 cameraPosition;
+
+in vec2 vUv;
+
+out vec4 fragColor;
+
+// Adds ambient light to the summed lights, applies fog and the output color
+// space, and restores the depth of the G-buffer for forward-drawn objects.
+void main() {
+	float depth = texture(gDepth, vUv).r;
+	if (depth >= 1.0) discard;
+
+	vec4 world = inverseViewProjection * vec4(vec3(vUv, standardDepth(depth, depthProjection)) * 2.0 - 1.0, 1.0);
+	vec3 worldPos = world.xyz / world.w;
+
+	vec3 albedo = texture(gAlbedo, vUv).rgb;
+	float ambient = texture(gMaterial, vUv).r;
+	vec3 result = ambient * albedo + texture(lightBuffer, vUv).rgb;
+
+	fragColor = vec4(toOutput(applyFog(result, length(worldPos - cameraPosition))), 1.0);
+	gl_FragDepth = depth;
+}
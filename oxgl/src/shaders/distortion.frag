@@ -0,0 +1,39 @@
+precision highp float;
+
+uniform float strength;
+uniform float noiseStrength;
+uniform float noiseScale;
+uniform float noiseSpeed;
+uniform float edgeFade;
+uniform float time;
+
+#include <clipping>
+
+varying vec3 vViewNormal;
+varying vec3 vViewPos;
+varying vec3 vWorldPos;
+
+// Shimmer drifting upwards, like air rising off a hot surface
+vec2 shimmer(vec3 p) {
+	vec3 q = p * noiseScale;
+	float rise = (p.y - time * noiseSpeed) * noiseScale;
+
+	return vec2(
+		sin(q.x + rise * 1.7) + 0.5 * sin(q.z * 2.1 - rise * 2.9),
+		cos(q.z * 1.3 + rise * 2.3) + 0.5 * cos(q.x * 1.9 + rise * 3.1)
+	) / 1.5;
+}
+
+void main() {
+	applyClipping(vWorldPos);
+
+	vec3 normal = normalize(vViewNormal);
+	float facing = abs(dot(normal, normalize(-vViewPos)));
+
+	// Normals bend the image like a lens, strongest at the rim
+	vec2 offset = normal.xy * strength + shimmer(vWorldPos) * noiseStrength;
+	float weight = mix(1.0, facing, edgeFade);
+
+	// Stored around 0.5, as the mask can't hold negative values
+	gl_FragColor = vec4(clamp(offset, -1.0, 1.0) * 0.5 + 0.5, 0.0, weight);
+}
@@ -0,0 +1,22 @@
+#include <logdepth>
+
+attribute vec3 position;
+attribute vec3 normal;
+
+uniform mat4 model;
+uniform mat4 view;
+uniform mat4 projection;
+
+varying vec3 vViewNormal;
+varying vec3 vViewPos;
+varying vec3 vWorldPos;
+
+void main() {
+	vec4 worldPos = model * vec4(position, 1.0);
+	vec4 viewPos = view * worldPos;
+
+	vWorldPos = worldPos.xyz;
+	vViewPos = viewPos.xyz;
+	vViewNormal = mat3(view) * mat3(model) * normal;
+	gl_Position = logDepth(projection * viewPos);
+}
@@ -1,51 +1,51 @@
+#extension GL_OES_standard_derivatives : enable
+
 precision mediump float;
 
 uniform vec3 color;
 uniform float ambient;
 
-const int MAX_LIGHTS = 4;
-
-struct Light {
-	int type;
-	vec3 position;
-	vec3 direction;
-	vec3 color;
-	float intensity;
-	float radius;
-};
+uniform vec3 cameraPosition;
 
-uniform int numLights;
-uniform Light lights[MAX_LIGHTS];
+#include <lights>
+#include <fog>
+#include <clipping>
+#include <emissive>
+#include <normals>
+#include <lightmap>
 
 varying vec3 vNormal;
 varying vec3 vWorldPos;
+varying vec3 vColor;
 
 vec3 calculateLight(Light light, vec3 normal) {
-	vec3 lightDir;
-	float attenuation = 1.0;
-
-	if (light.type == 0) {
-		lightDir = normalize(-light.direction);
-	} else {
-		vec3 toLight = light.position - vWorldPos;
-		float distance = length(toLight);
-		lightDir = normalize(toLight);
-		attenuation = clamp(1.0 - (distance / light.radius), 0.0, 1.0);
-		attenuation *= attenuation;
-	}
+	float attenuation;
+	vec3 lightDir = lightDirection(light, vWorldPos, attenuation);
 
 	float diff = max(dot(normal, lightDir), 0.0);
-	return diff * light.color * light.intensity * attenuation;
+	return diff * lightRadiance(light) * attenuation;
 }
 
 void main() {
-	vec3 normal = normalize(vNormal);
-	vec3 result = ambient * color;
+	applyClipping(vWorldPos);
 
-	for (int i = 0; i < MAX_LIGHTS; i++) {
-		if (i >= numLights) break;
-		result += calculateLight(lights[i], normal) * color;
+	if (emissivePass) {
+		gl_FragColor = vec4(toOutput(emission()), 1.0);
+		return;
+	}
+
+	vec3 normal = surfaceNormal(vNormal, vWorldPos);
+	vec3 albedo = toLinear(color) * toLinear(vColor);
+	vec3 result = ambient * albedo;
+
+	if (hasLightmap()) {
+		result += bakedLight() * albedo;
+	} else {
+		for (int i = 0; i < MAX_LIGHTS; i++) {
+			if (i >= numLights) break;
+			result += calculateLight(lights[i], normal) * albedo;
+		}
 	}
 
-	gl_FragColor = vec4(result, 1.0);
+	gl_FragColor = vec4(toOutput(applyFog(result + emission(), length(vWorldPos - cameraPosition))), 1.0);
 }
\ No newline at end of file
@@ -1,5 +1,9 @@
+#include <logdepth>
+
 attribute vec3 position;
 attribute vec3 normal;
+attribute vec3 vertexColor;
+attribute vec2 uv2;
 
 uniform mat4 model;
 uniform mat4 view;
@@ -7,10 +11,14 @@ uniform mat4 projection;
 
 varying vec3 vNormal;
 varying vec3 vWorldPos;
+varying vec3 vColor;
+varying vec2 vLightmapUv;
 
 void main() {
 	vec4 worldPos = model * vec4(position, 1.0);
 	vWorldPos = worldPos.xyz;
 	vNormal = mat3(model) * normal;
-	gl_Position = projection * view * worldPos;
+	vColor = vertexColor;
+	vLightmapUv = uv2;
+	gl_Position = logDepth(projection * view * worldPos);
 }
\ No newline at end of file
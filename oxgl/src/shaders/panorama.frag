@@ -0,0 +1,27 @@
+precision mediump float;
+uniform sampler2D panorama;
+uniform samplerCube cubeMap;
+uniform bool useCubeMap;
+uniform float yawOffset;
+
+varying vec3 vDirection;
+
+const float PI = 3.14159265359;
+
+void main() {
+	vec3 dir = normalize(vDirection);
+
+	if (useCubeMap) {
+		// Turn the sky with the yaw, matching the equirectangular mapping
+		float c = cos(yawOffset);
+		float s = sin(yawOffset);
+		gl_FragColor = textureCube(cubeMap, vec3(dir.x * c - dir.z * s, dir.y, dir.z * c + dir.x * s));
+		return;
+	}
+
+	// Equirectangular mapping, with the image center facing -Z
+	float u = atan(dir.x, -dir.z) / (2.0 * PI) + 0.5 + yawOffset / (2.0 * PI);
+	float v = acos(clamp(dir.y, -1.0, 1.0)) / PI;
+
+	gl_FragColor = texture2D(panorama, vec2(fract(u), v));
+}
@@ -0,0 +1,15 @@
+attribute vec3 position;
+uniform mat4 view;
+uniform mat4 projection;
+
+varying vec3 vDirection;
+
+void main() {
+	vDirection = position;
+
+	// Drop the translation so the sphere stays centered on the camera
+	vec4 clip = projection * mat4(mat3(view)) * vec4(position, 1.0);
+
+	// Push to the far plane so scene geometry is always drawn in front
+	gl_Position = clip.xyww;
+}
\ No newline at end of file
@@ -1,5 +1,9 @@
+#include <logdepth>
+
 attribute vec3 position;
 attribute vec3 normal;
+attribute vec3 vertexColor;
+attribute vec2 uv2;
 
 uniform mat4 model;
 uniform mat4 view;
@@ -9,12 +13,16 @@ uniform bool shadowsEnabled;
 
 varying vec3 vNormal;
 varying vec3 vWorldPos;
+varying vec3 vColor;
+varying vec2 vLightmapUv;
 varying vec4 vPosLightSpace;
 
 void main() {
 	vec4 worldPos = model * vec4(position, 1.0);
 	vWorldPos = worldPos.xyz;
 	vNormal = mat3(model) * normal;
+	vColor = vertexColor;
+	vLightmapUv = uv2;
 	
 	if (shadowsEnabled) {
 		vPosLightSpace = lightSpace * worldPos;
@@ -22,5 +30,5 @@ void main() {
 		vPosLightSpace = vec4(0.0);
 	}
 
-	gl_Position = projection * view * worldPos;
+	gl_Position = logDepth(projection * view * worldPos);
 }
\ No newline at end of file
@@ -0,0 +1,73 @@
+#extension GL_OES_standard_derivatives : enable
+
+precision highp float;
+
+uniform vec3 color;
+uniform float ambient;
+uniform float shininess;
+uniform float specularStrength;
+
+uniform vec3 cameraPosition;
+
+// Reflected wherever no reflection probe surrounds the object, usually the
+// skybox cube map.
+uniform samplerCube environment;
+
+#include <lights>
+#include <shadows>
+#include <contact_shadows>
+#include <fog>
+#include <clipping>
+#include <emissive>
+#include <normals>
+#include <probes>
+
+varying vec3 vNormal;
+varying vec3 vWorldPos;
+varying vec3 vColor;
+varying vec4 vPosLightSpace;
+
+vec3 calculateLight(Light light, vec3 normal, vec3 viewDir) {
+	float attenuation;
+	vec3 lightDir = lightDirection(light, vWorldPos, attenuation);
+
+	float diff = max(dot(normal, lightDir), 0.0);
+	vec3 diffuse = diff * lightRadiance(light);
+
+	vec3 halfDir = normalize(lightDir + viewDir);
+	float spec = pow(max(dot(normal, halfDir), 0.0), shininess);
+	vec3 specular = specularStrength * spec * lightRadiance(light);
+
+	return (diffuse + specular) * attenuation;
+}
+
+void main() {
+	applyClipping(vWorldPos);
+
+	if (emissivePass) {
+		gl_FragColor = vec4(toOutput(emission()), 1.0);
+		return;
+	}
+
+	vec3 normal = surfaceNormal(vNormal, vWorldPos);
+	vec3 viewDir = normalize(cameraPosition - vWorldPos);
+
+	vec3 albedo = toLinear(color) * toLinear(vColor);
+	vec3 result = ambient * albedo;
+	float shadow = calculateShadow(vPosLightSpace);
+
+	for (int i = 0; i < MAX_LIGHTS; i++) {
+		if (i >= numLights) break;
+		float contact = calculateContactShadow(lights[i], vWorldPos);
+		result += (1.0 - shadow) * (1.0 - contact) * calculateLight(lights[i], normal, viewDir) * albedo;
+	}
+
+	vec3 direction = reflect(-viewDir, normal);
+	vec3 reflection = probeEnabled
+		? probeReflection(vWorldPos, direction)
+		: toLinear(textureCube(environment, direction).rgb);
+
+	result = mix(result, reflection, reflectance(normal, viewDir));
+
+	gl_FragColor = vec4(toOutput(applyFog(result + emission(), length(vWorldPos - cameraPosition))), 1.0);
+}
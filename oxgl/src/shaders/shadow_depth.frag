@@ -1,4 +1,9 @@
 precision mediump float;
 
+#include <clipping>
+
+varying vec3 vWorldPos;
+
 void main() {
+	applyClipping(vWorldPos);
 }
\ No newline at end of file
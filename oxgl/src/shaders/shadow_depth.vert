@@ -1,8 +1,14 @@
+#include <instancing>
+
 attribute vec3 position;
 
 uniform mat4 model;
 uniform mat4 lightSpace;
 
+varying vec3 vWorldPos;
+
 void main() {
-	gl_Position = lightSpace * model * vec4(position, 1.0);
+	vec4 worldPos = model * instanceMatrix() * vec4(position, 1.0);
+	vWorldPos = worldPos.xyz;
+	gl_Position = lightSpace * worldPos;
 }
\ No newline at end of file
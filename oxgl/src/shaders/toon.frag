@@ -0,0 +1,68 @@
+#extension GL_OES_standard_derivatives : enable
+
+precision highp float;
+
+uniform vec3 color;
+uniform float ambient;
+uniform float bands;
+uniform float rimStrength;
+uniform float rimPower;
+uniform vec3 rimColor;
+
+uniform vec3 cameraPosition;
+
+#include <lights>
+#include <shadows>
+#include <contact_shadows>
+#include <fog>
+#include <clipping>
+#include <emissive>
+#include <normals>
+
+varying vec3 vNormal;
+varying vec3 vWorldPos;
+varying vec3 vColor;
+varying vec4 vPosLightSpace;
+
+// Steps a 0-1 intensity up to the next of `bands` levels, so any lit
+// surface gets at least the first band
+float quantize(float value) {
+	float steps = max(bands, 1.0);
+
+	return ceil(value * steps - 0.001) / steps;
+}
+
+void main() {
+	applyClipping(vWorldPos);
+
+	if (emissivePass) {
+		gl_FragColor = vec4(toOutput(emission()), 1.0);
+		return;
+	}
+
+	vec3 normal = surfaceNormal(vNormal, vWorldPos);
+	vec3 viewDir = normalize(cameraPosition - vWorldPos);
+
+	// Shadows are hard edged like the bands
+	float shadow = step(0.5, calculateShadow(vPosLightSpace));
+
+	vec3 albedo = toLinear(color) * toLinear(vColor);
+	vec3 result = ambient * albedo;
+
+	for (int i = 0; i < MAX_LIGHTS; i++) {
+		if (i >= numLights) break;
+
+		float attenuation;
+		vec3 lightDir = lightDirection(lights[i], vWorldPos, attenuation);
+		float contact = step(0.5, calculateContactShadow(lights[i], vWorldPos));
+
+		float diff = max(dot(normal, lightDir), 0.0) * (1.0 - shadow) * (1.0 - contact);
+
+		result += quantize(diff) * lightRadiance(lights[i]) * attenuation * albedo;
+	}
+
+	float rim = pow(1.0 - max(dot(normal, viewDir), 0.0), rimPower);
+	result += rimStrength * step(0.5, rim) * toLinear(rimColor);
+
+	gl_FragColor = vec4(toOutput(applyFog(result + emission(), length(vWorldPos - cameraPosition))), 1.0);
+}
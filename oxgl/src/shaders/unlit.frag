@@ -1,6 +1,20 @@
 precision mediump float;
 uniform vec4 color;
 
+#include <colorspace>
+#include <clipping>
+#include <emissive>
+
+varying vec3 vWorldPos;
+varying vec3 vColor;
+
 void main() {
-	gl_FragColor = color;
+	applyClipping(vWorldPos);
+
+	if (emissivePass) {
+		gl_FragColor = vec4(toOutput(emission()), 1.0);
+		return;
+	}
+
+	gl_FragColor = vec4(toOutput(toLinear(color.rgb) * toLinear(vColor) + emission()), color.a);
 }
\ No newline at end of file
@@ -1,8 +1,17 @@
+#include <logdepth>
+
 attribute vec3 position;
+attribute vec3 vertexColor;
 uniform mat4 model;
 uniform mat4 view;
 uniform mat4 projection;
 
+varying vec3 vWorldPos;
+varying vec3 vColor;
+
 void main() {
-	gl_Position = projection * view * model * vec4(position, 1.0);
+	vec4 worldPos = model * vec4(position, 1.0);
+	vWorldPos = worldPos.xyz;
+	vColor = vertexColor;
+	gl_Position = logDepth(projection * view * worldPos);
 }
\ No newline at end of file
@@ -0,0 +1,65 @@
+#extension GL_OES_standard_derivatives : enable
+
+precision highp float;
+
+uniform vec3 color;
+uniform float ambient;
+
+uniform bool useTexture;
+uniform sampler2D leafTexture;
+uniform float alphaCutoff;
+uniform bool depthPass;
+
+uniform vec3 cameraPosition;
+
+#include <lights>
+#include <shadows>
+#include <fog>
+#include <clipping>
+#include <emissive>
+#include <normals>
+
+varying vec3 vNormal;
+varying vec3 vWorldPos;
+varying vec3 vColor;
+varying vec2 vUv;
+varying vec4 vPosLightSpace;
+
+vec3 calculateLight(Light light, vec3 normal) {
+	float attenuation;
+	vec3 lightDir = lightDirection(light, vWorldPos, attenuation);
+
+	float diff = max(dot(normal, lightDir), 0.0);
+	return diff * lightRadiance(light) * attenuation;
+}
+
+void main() {
+	applyClipping(vWorldPos);
+
+	vec4 base = useTexture ? texture2D(leafTexture, vUv) : vec4(1.0);
+
+	// Leaf cards are cut out instead of blended, so they need no sorting
+	if (base.a < alphaCutoff) discard;
+
+	if (depthPass) {
+		gl_FragColor = vec4(1.0);
+		return;
+	}
+
+	if (emissivePass) {
+		gl_FragColor = vec4(toOutput(emission()), 1.0);
+		return;
+	}
+
+	vec3 normal = surfaceNormal(vNormal, vWorldPos);
+	vec3 albedo = toLinear(color) * toLinear(vColor) * toLinear(base.rgb);
+	vec3 result = ambient * albedo;
+	float shadow = calculateShadow(vPosLightSpace);
+
+	for (int i = 0; i < MAX_LIGHTS; i++) {
+		if (i >= numLights) break;
+		result += (1.0 - shadow) * calculateLight(lights[i], normal) * albedo;
+	}
+
+	gl_FragColor = vec4(toOutput(applyFog(result + emission(), length(vWorldPos - cameraPosition))), 1.0);
+}
@@ -0,0 +1,64 @@
+#include <logdepth>
+#include <instancing>
+
+attribute vec3 position;
+attribute vec3 normal;
+attribute vec3 vertexColor;
+attribute vec2 uv;
+
+uniform mat4 model;
+uniform mat4 view;
+uniform mat4 projection;
+uniform mat4 lightSpace;
+uniform bool shadowsEnabled;
+// Set while drawing into a shadow or camera depth map through lightSpace
+uniform bool depthPass;
+
+uniform float time;
+uniform vec3 windDirection;
+uniform float windAmplitude;
+uniform float windFrequency;
+
+varying vec3 vNormal;
+varying vec3 vWorldPos;
+varying vec3 vColor;
+varying vec2 vUv;
+varying vec4 vPosLightSpace;
+
+// A phase in [0, 2pi) from the instance's position, so neighbours sway
+// out of step
+float instancePhase(vec3 origin) {
+	return fract(sin(dot(origin.xz, vec2(12.9898, 78.233))) * 43758.5453) * 6.2831853;
+}
+
+void main() {
+	mat4 world = model * instanceMatrix();
+	vec4 worldPos = world * vec4(position, 1.0);
+
+	// Bends more towards the top, keeping the roots in place
+	float bend = max(position.y, 0.0);
+	float t = time * windFrequency * 6.2831853 + instancePhase(world[3].xyz);
+	float sway = sin(t) + 0.3 * sin(t * 2.3 + 1.7);
+	vec3 direction = length(windDirection) > 0.0 ? normalize(windDirection) : vec3(0.0);
+
+	worldPos.xyz += direction * sway * windAmplitude * bend * bend;
+
+	vWorldPos = worldPos.xyz;
+	vUv = uv;
+
+	if (depthPass) {
+		gl_Position = lightSpace * worldPos;
+		return;
+	}
+
+	vNormal = mat3(world) * normal;
+	vColor = vertexColor;
+
+	if (shadowsEnabled) {
+		vPosLightSpace = lightSpace * worldPos;
+	} else {
+		vPosLightSpace = vec4(0.0);
+	}
+
+	gl_Position = logDepth(projection * view * worldPos);
+}
@@ -0,0 +1,103 @@
+#extension GL_OES_standard_derivatives : enable
+
+precision highp float;
+
+uniform vec3 color;
+uniform vec3 deepColor;
+uniform float absorption;
+uniform float ambient;
+uniform float shininess;
+uniform float specularStrength;
+
+uniform float time;
+uniform float rippleScale;
+uniform float rippleStrength;
+uniform float rippleSpeed;
+
+uniform bool useReflection;
+uniform sampler2D reflectionTexture;
+uniform mat4 reflectionViewProjection;
+uniform float reflectionStrength;
+uniform float reflectionDistortion;
+
+uniform mat4 view;
+uniform vec3 cameraPosition;
+
+#include <lights>
+#include <shadows>
+#include <fog>
+#include <clipping>
+#include <emissive>
+#include <normals>
+#include <scene_depth>
+
+varying vec3 vNormal;
+varying vec3 vWorldPos;
+varying vec3 vColor;
+varying vec4 vPosLightSpace;
+
+// Slope of small crossing ripples along x and z, too fine for the mesh to
+// follow, so they only bend the normal.
+vec2 ripple(vec2 p) {
+	vec2 q = p * rippleScale;
+	float t = time * rippleSpeed;
+
+	float x = cos(q.x * 1.3 + t) + 0.7 * cos((q.x + q.y) * 0.9 - t * 1.2);
+	float z = cos(q.y * 1.7 - t * 0.8) + 0.6 * cos((q.x - q.y) * 1.1 + t * 1.4);
+
+	return vec2(x, z) * rippleStrength;
+}
+
+vec3 calculateLight(Light light, vec3 normal, vec3 viewDir) {
+	float attenuation;
+	vec3 lightDir = lightDirection(light, vWorldPos, attenuation);
+
+	float diff = max(dot(normal, lightDir), 0.0);
+	vec3 diffuse = diff * lightRadiance(light);
+
+	vec3 halfDir = normalize(lightDir + viewDir);
+	float spec = pow(max(dot(normal, halfDir), 0.0), shininess);
+	vec3 specular = specularStrength * spec * lightRadiance(light);
+
+	return (diffuse + specular) * attenuation;
+}
+
+void main() {
+	applyClipping(vWorldPos);
+
+	if (emissivePass) {
+		gl_FragColor = vec4(toOutput(emission()), 1.0);
+		return;
+	}
+
+	vec2 slope = ripple(vWorldPos.xz);
+	vec3 normal = normalize(surfaceNormal(vNormal, vWorldPos) - vec3(slope.x, 0.0, slope.y));
+	vec3 viewDir = normalize(cameraPosition - vWorldPos);
+
+	// Light is absorbed over the distance it travels through the water, so
+	// shallows show the shallow color and depths fade to the deep one
+	float surfaceDepth = -(view * vec4(vWorldPos, 1.0)).z;
+	float thickness = max(sceneViewDepth() - surfaceDepth, 0.0);
+	float transmittance = exp(-absorption * thickness);
+
+	vec3 albedo = mix(toLinear(deepColor), toLinear(color), transmittance) * toLinear(vColor);
+	vec3 result = ambient * albedo;
+	float shadow = calculateShadow(vPosLightSpace);
+
+	for (int i = 0; i < MAX_LIGHTS; i++) {
+		if (i >= numLights) break;
+		result += (1.0 - shadow) * calculateLight(lights[i], normal, viewDir) * albedo;
+	}
+
+	if (useReflection) {
+		vec4 clip = reflectionViewProjection * vec4(vWorldPos, 1.0);
+		vec2 uv = clip.xy / clip.w * 0.5 + 0.5 + slope * reflectionDistortion;
+		vec3 reflection = toLinear(texture2D(reflectionTexture, clamp(uv, 0.0, 1.0)).rgb);
+
+		// Schlick's approximation, with water's reflectance head-on
+		float fresnel = 0.02 + 0.98 * pow(1.0 - max(dot(normal, viewDir), 0.0), 5.0);
+		result = mix(result, reflection, fresnel * reflectionStrength);
+	}
+
+	gl_FragColor = vec4(toOutput(applyFog(result + emission(), length(vWorldPos - cameraPosition))), 1.0);
+}
@@ -0,0 +1,59 @@
+#include <logdepth>
+
+attribute vec3 position;
+attribute vec3 normal;
+attribute vec3 vertexColor;
+attribute vec2 uv2;
+
+uniform mat4 model;
+uniform mat4 view;
+uniform mat4 projection;
+uniform mat4 lightSpace;
+uniform bool shadowsEnabled;
+
+uniform float time;
+uniform float waveAmplitude;
+uniform float waveLength;
+uniform float waveSpeed;
+
+varying vec3 vNormal;
+varying vec3 vWorldPos;
+varying vec3 vColor;
+varying vec2 vLightmapUv;
+varying vec4 vPosLightSpace;
+
+// Two crossing sine waves. Returns the height and its slope along x and z.
+vec3 wave(vec2 p) {
+	float k = 6.2831853 / waveLength;
+	float phase = time * waveSpeed * k;
+
+	vec2 dirA = vec2(1.0, 0.0);
+	vec2 dirB = vec2(0.6, 0.8);
+
+	float a = dot(dirA, p) * k + phase;
+	float b = dot(dirB, p) * k * 1.7 + phase * 1.3;
+
+	float height = waveAmplitude * (sin(a) + 0.5 * sin(b));
+	vec2 slope = waveAmplitude * k * (cos(a) * dirA + 0.85 * cos(b) * dirB);
+
+	return vec3(height, slope);
+}
+
+void main() {
+	vec3 w = wave(position.xz);
+	vec3 displaced = position + vec3(0.0, w.x, 0.0);
+
+	vec4 worldPos = model * vec4(displaced, 1.0);
+	vWorldPos = worldPos.xyz;
+	vNormal = mat3(model) * normalize(vec3(-w.y, 1.0, -w.z));
+	vColor = vertexColor;
+	vLightmapUv = uv2;
+
+	if (shadowsEnabled) {
+		vPosLightSpace = lightSpace * worldPos;
+	} else {
+		vPosLightSpace = vec4(0.0);
+	}
+
+	gl_Position = logDepth(projection * view * worldPos);
+}
@@ -0,0 +1,790 @@
+//! Worker Rendering
+//!
+//! Runs the renderer and scene in a web worker drawing into an
+//! `OffscreenCanvas`, so a busy main thread doesn't make frames stutter.
+//!
+//! The main thread creates a [`WorkerCanvas`], which hands control of a
+//! canvas to a worker and sends it [`SceneCommand`]s. Inside the worker,
+//! [`WorkerApp::start`] receives the canvas, applies the commands to a
+//! [`SceneDescription`] at the start of every frame and renders it through
+//! a [`SceneInstance`].
+//!
+//! Objects and lights are referred to by ids the main thread picks, as the
+//! worker's own ids only exist on its side.
+//!
+//! ## Examples
+//!
+//! On the main thread:
+//!
+//! ```ignore
+//! use oxgl::worker::{SceneCommand, WorkerCanvas};
+//! use oxgl::renderer_3d::{Light, MaterialDescription, Primitive};
+//!
+//! // The script loads the wasm module and calls the worker entry point
+//! let canvas = WorkerCanvas::new("webgl-canvas", "./render_worker.js")?;
+//!
+//! let cube = canvas.add_object(&Primitive::Cube.with_face_colors(), MaterialDescription::Phong(Vec3::ONE), Transform3D::new())?;
+//! canvas.add_light(&Light::directional(Vec3::NEG_ONE, Vec3::ONE, 1.0))?;
+//!
+//! // Later, from UI events
+//! canvas.set_transform(cube, &Transform3D::new().with_position(Vec3::X))?;
+//! canvas.send(&SceneCommand::Custom(JsValue::from_str("pause")))?;
+//! ```
+//!
+//! In the worker:
+//!
+//! ```ignore
+//! use oxgl::worker::WorkerApp;
+//!
+//! #[wasm_bindgen]
+//! pub fn worker_main() -> Result<(), JsValue> {
+//!		WorkerApp::start(|app| {
+//!			app.on_message(|description, message| {
+//!				log::info!("{:?}", message);
+//!			});
+//!
+//!			app.run(|description, time| {
+//!				// Worker-side animation
+//!			});
+//!		})?;
+//!
+//!		Ok(())
+//! }
+//! ```
+//!
+
+use std::{cell::{Cell, RefCell}, collections::HashMap, rc::Rc};
+use glam::{Mat4, Quat, Vec3, Vec4};
+use js_sys::{Array, Float32Array, Object, Reflect};
+use web_sys::{
+	DedicatedWorkerGlobalScope, HtmlCanvasElement, MessageEvent, OffscreenCanvas, Worker, WorkerOptions, WorkerType,
+	wasm_bindgen::{JsCast, JsValue, prelude::Closure},
+};
+
+use crate::{
+	Renderer,
+	common::{Camera, MeshData, Uniform},
+	core::{Animator, Color, LightId, ObjectId, Transform3D},
+	renderer_3d::{ContactShadows, Falloff, Light, LightHalo, LightType, MaterialDescription, SceneDescription, SceneInstance},
+};
+
+/// An object in a worker's scene, picked by the main thread.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct RemoteObjectId(pub u32);
+
+/// A light in a worker's scene, picked by the main thread.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct RemoteLightId(pub u32);
+
+/// A change to a worker's scene, sent from the main thread.
+#[derive(Clone, Debug)]
+pub enum SceneCommand {
+	AddObject {
+		id: RemoteObjectId,
+		mesh: MeshData,
+		material: MaterialDescription,
+		transform: Transform3D,
+	},
+	RemoveObject(RemoteObjectId),
+	SetTransform(RemoteObjectId, Transform3D),
+	SetVisible(RemoteObjectId, bool),
+	SetMaterial(RemoteObjectId, MaterialDescription),
+	/// Adds a light, or replaces the light with the same id. Names and
+	/// tags aren't sent.
+	SetLight(RemoteLightId, Light),
+	RemoveLight(RemoteLightId),
+	/// Moves the camera. The worker keeps the canvas's aspect ratio.
+	SetCamera(Camera),
+	SetBackground(Color),
+	/// Resizes the canvas's drawing buffer, which the main thread can no
+	/// longer do once control is transferred.
+	Resize { width: u32, height: u32 },
+	/// Any structured-cloneable value, passed to [`WorkerApp::on_message`].
+	Custom(JsValue),
+}
+
+impl SceneCommand {
+	/// Encodes the command as a message for `postMessage`.
+	pub fn to_js(&self) -> JsValue {
+		let message = Object::new();
+
+		match self {
+			Self::AddObject { id, mesh, material, transform } => {
+				set(&message, "type", &"addObject".into());
+				set(&message, "id", &id.0.into());
+				set(&message, "mesh", &mesh_to_js(mesh));
+				set(&message, "material", &material_to_js(material));
+				set(&message, "transform", &transform_to_js(transform));
+			}
+			Self::RemoveObject(id) => {
+				set(&message, "type", &"removeObject".into());
+				set(&message, "id", &id.0.into());
+			}
+			Self::SetTransform(id, transform) => {
+				set(&message, "type", &"setTransform".into());
+				set(&message, "id", &id.0.into());
+				set(&message, "transform", &transform_to_js(transform));
+			}
+			Self::SetVisible(id, visible) => {
+				set(&message, "type", &"setVisible".into());
+				set(&message, "id", &id.0.into());
+				set(&message, "visible", &(*visible).into());
+			}
+			Self::SetMaterial(id, material) => {
+				set(&message, "type", &"setMaterial".into());
+				set(&message, "id", &id.0.into());
+				set(&message, "material", &material_to_js(material));
+			}
+			Self::SetLight(id, light) => {
+				set(&message, "type", &"setLight".into());
+				set(&message, "id", &id.0.into());
+				set(&message, "light", &light_to_js(light));
+			}
+			Self::RemoveLight(id) => {
+				set(&message, "type", &"removeLight".into());
+				set(&message, "id", &id.0.into());
+			}
+			Self::SetCamera(camera) => {
+				set(&message, "type", &"setCamera".into());
+				set(&message, "camera", &floats(&[
+					camera.position.x, camera.position.y, camera.position.z,
+					camera.target.x, camera.target.y, camera.target.z,
+					camera.up.x, camera.up.y, camera.up.z,
+					camera.fov_y, camera.near, camera.far,
+				]));
+				set(&message, "logarithmicDepth", &camera.logarithmic_depth.into());
+
+				if let Some(projection) = &camera.custom_projection {
+					set(&message, "projection", &floats(&projection.to_cols_array()));
+				}
+			}
+			Self::SetBackground(color) => {
+				set(&message, "type", &"setBackground".into());
+				set(&message, "color", &floats(&color.to_vec4().to_array()));
+			}
+			Self::Resize { width, height } => {
+				set(&message, "type", &"resize".into());
+				set(&message, "width", &(*width).into());
+				set(&message, "height", &(*height).into());
+			}
+			Self::Custom(value) => {
+				set(&message, "type", &"custom".into());
+				set(&message, "value", value);
+			}
+		}
+
+		message.into()
+	}
+
+	/// Decodes a message made by [`to_js`](Self::to_js).
+	///
+	/// # Errors
+	///
+	/// Returns an error if the message isn't a scene command or is missing
+	/// fields.
+	pub fn from_js(message: &JsValue) -> Result<Self, String> {
+		let kind = get_string(message, "type")?;
+
+		Ok(match kind.as_str() {
+			"addObject" => Self::AddObject {
+				id: RemoteObjectId(get_u32(message, "id")?),
+				mesh: mesh_from_js(&get(message, "mesh")?)?,
+				material: material_from_js(&get(message, "material")?)?,
+				transform: transform_from_js(&get_floats(message, "transform")?)?,
+			},
+			"removeObject" => Self::RemoveObject(RemoteObjectId(get_u32(message, "id")?)),
+			"setTransform" => Self::SetTransform(
+				RemoteObjectId(get_u32(message, "id")?),
+				transform_from_js(&get_floats(message, "transform")?)?,
+			),
+			"setVisible" => Self::SetVisible(
+				RemoteObjectId(get_u32(message, "id")?),
+				get(message, "visible")?.is_truthy(),
+			),
+			"setMaterial" => Self::SetMaterial(
+				RemoteObjectId(get_u32(message, "id")?),
+				material_from_js(&get(message, "material")?)?,
+			),
+			"setLight" => Self::SetLight(
+				RemoteLightId(get_u32(message, "id")?),
+				light_from_js(&get(message, "light")?)?,
+			),
+			"removeLight" => Self::RemoveLight(RemoteLightId(get_u32(message, "id")?)),
+			"setCamera" => {
+				let values = get_floats(message, "camera")?;
+				let [px, py, pz, tx, ty, tz, ux, uy, uz, fov_y, near, far] = values[..] else {
+					return Err("Expected 12 camera values".into());
+				};
+				let mut camera = Camera::new(1.0)
+					.with_position(Vec3::new(px, py, pz))
+					.with_target(Vec3::new(tx, ty, tz));
+
+				camera.up = Vec3::new(ux, uy, uz);
+				camera.fov_y = fov_y;
+				camera.near = near;
+				camera.far = far;
+				camera.logarithmic_depth = get(message, "logarithmicDepth")?.is_truthy();
+				camera.custom_projection = match get(message, "projection") {
+					Ok(_) => {
+						let values = get_floats(message, "projection")?;
+
+						if values.len() != 16 {
+							return Err("Expected 16 projection values".into());
+						}
+
+						Some(Mat4::from_cols_slice(&values))
+					}
+					Err(_) => None,
+				};
+				Self::SetCamera(camera)
+			}
+			"setBackground" => {
+				let color = get_floats(message, "color")?;
+				let [r, g, b, a] = color[..] else {
+					return Err("Expected 4 color values".into());
+				};
+				Self::SetBackground(Color::from_vec4(Vec4::new(r, g, b, a)))
+			}
+			"resize" => Self::Resize {
+				width: get_u32(message, "width")?,
+				height: get_u32(message, "height")?,
+			},
+			"custom" => Self::Custom(get(message, "value")?),
+			other => return Err(format!("Unknown scene command `{}`", other)),
+		})
+	}
+}
+
+/// The main thread's handle to a canvas rendered by a worker.
+pub struct WorkerCanvas {
+	worker: Worker,
+	next_object: Cell<u32>,
+	next_light: Cell<u32>,
+}
+
+impl WorkerCanvas {
+	/// Starts a module worker from `script_url` and hands it the canvas
+	/// with the given id.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the canvas isn't found, was already transferred,
+	/// or the worker can't be started.
+	pub fn new(canvas_id: &str, script_url: &str) -> Result<Self, String> {
+		let canvas = web_sys::window()
+			.and_then(|window| window.document())
+			.and_then(|document| document.get_element_by_id(canvas_id))
+			.ok_or_else(|| format!("No canvas with id `{}`", canvas_id))?
+			.dyn_into::<HtmlCanvasElement>()
+			.map_err(|_| format!("`{}` is not a canvas", canvas_id))?;
+
+		let options = WorkerOptions::new();
+		options.set_type(WorkerType::Module);
+
+		let worker = Worker::new_with_options(script_url, &options)
+			.map_err(|e| format!("Failed to start worker: {:?}", e))?;
+
+		Self::with_worker(&canvas, worker)
+	}
+
+	/// Hands the canvas to an already running worker.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the canvas was already transferred or the
+	/// message can't be posted.
+	pub fn with_worker(canvas: &HtmlCanvasElement, worker: Worker) -> Result<Self, String> {
+		let offscreen = canvas.transfer_control_to_offscreen()
+			.map_err(|e| format!("Failed to transfer canvas: {:?}", e))?;
+
+		let message = Object::new();
+		set(&message, "type", &"init".into());
+		set(&message, "canvas", &offscreen);
+
+		worker.post_message_with_transfer(&message, &Array::of1(&offscreen))
+			.map_err(|e| format!("Failed to send canvas to worker: {:?}", e))?;
+
+		Ok(Self {
+			worker,
+			next_object: Cell::new(0),
+			next_light: Cell::new(0),
+		})
+	}
+
+	pub fn worker(&self) -> &Worker {
+		&self.worker
+	}
+
+	/// Sends a command, applied at the start of the worker's next frame.
+	pub fn send(&self, command: &SceneCommand) -> Result<(), String> {
+		self.worker.post_message(&command.to_js())
+			.map_err(|e| format!("Failed to send scene command: {:?}", e))
+	}
+
+	/// Adds an object to the worker's scene, returning its id.
+	pub fn add_object(&self, mesh: &MeshData, material: MaterialDescription, transform: Transform3D) -> Result<RemoteObjectId, String> {
+		let id = RemoteObjectId(self.next_object.get());
+		self.next_object.set(id.0 + 1);
+
+		self.send(&SceneCommand::AddObject { id, mesh: mesh.clone(), material, transform })?;
+		Ok(id)
+	}
+
+	pub fn remove_object(&self, id: RemoteObjectId) -> Result<(), String> {
+		self.send(&SceneCommand::RemoveObject(id))
+	}
+
+	pub fn set_transform(&self, id: RemoteObjectId, transform: &Transform3D) -> Result<(), String> {
+		self.send(&SceneCommand::SetTransform(id, transform.clone()))
+	}
+
+	/// Adds a light to the worker's scene, returning its id.
+	pub fn add_light(&self, light: &Light) -> Result<RemoteLightId, String> {
+		let id = RemoteLightId(self.next_light.get());
+		self.next_light.set(id.0 + 1);
+
+		self.send(&SceneCommand::SetLight(id, light.clone()))?;
+		Ok(id)
+	}
+
+	pub fn remove_light(&self, id: RemoteLightId) -> Result<(), String> {
+		self.send(&SceneCommand::RemoveLight(id))
+	}
+
+	pub fn set_camera(&self, camera: &Camera) -> Result<(), String> {
+		self.send(&SceneCommand::SetCamera(camera.clone()))
+	}
+
+	/// Resizes the canvas's drawing buffer, usually after the page layout
+	/// changed its displayed size.
+	pub fn resize(&self, width: u32, height: u32) -> Result<(), String> {
+		self.send(&SceneCommand::Resize { width, height })
+	}
+
+	/// Stops the worker immediately.
+	pub fn terminate(&self) {
+		self.worker.terminate();
+	}
+}
+
+type MessageCallback = Box<dyn FnMut(&mut SceneDescription, JsValue)>;
+
+/// The worker side of a [`WorkerCanvas`], rendering the scene it describes.
+pub struct WorkerApp {
+	pub renderer: Rc<Renderer>,
+	pub description: Rc<RefCell<SceneDescription>>,
+	pub instance: Rc<RefCell<SceneInstance>>,
+	commands: Rc<RefCell<Vec<SceneCommand>>>,
+	on_message: Rc<RefCell<Option<MessageCallback>>>,
+}
+
+impl WorkerApp {
+	/// Waits for the canvas from a [`WorkerCanvas`], then calls `init` with
+	/// the app. Must be called from the worker's entry point.
+	///
+	/// # Errors
+	///
+	/// Returns an error if not called inside a dedicated worker.
+	pub fn start<F>(init: F) -> Result<(), String>
+	where
+		F: FnOnce(WorkerApp) + 'static,
+	{
+		let scope = js_sys::global()
+			.dyn_into::<DedicatedWorkerGlobalScope>()
+			.map_err(|_| "WorkerApp::start must be called inside a dedicated worker")?;
+
+		let commands: Rc<RefCell<Vec<SceneCommand>>> = Rc::new(RefCell::new(Vec::new()));
+		let mut init = Some(init);
+
+		let on_message = Closure::<dyn FnMut(MessageEvent)>::new(move |event: MessageEvent| {
+			let data = event.data();
+
+			if get_string(&data, "type").is_ok_and(|kind| kind == "init") {
+				let Some(init) = init.take() else {
+					return;
+				};
+
+				match Self::create(&data, commands.clone()) {
+					Ok(app) => init(app),
+					Err(e) => log::error!("{}", e),
+				}
+				return;
+			}
+
+			match SceneCommand::from_js(&data) {
+				Ok(command) => commands.borrow_mut().push(command),
+				Err(e) => log::error!("{}", e),
+			}
+		});
+
+		scope.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+		on_message.forget();
+
+		Ok(())
+	}
+
+	fn create(message: &JsValue, commands: Rc<RefCell<Vec<SceneCommand>>>) -> Result<Self, String> {
+		let canvas = get(message, "canvas")?
+			.dyn_into::<OffscreenCanvas>()
+			.map_err(|_| "The init message has no OffscreenCanvas")?;
+		let aspect = canvas.width() as f32 / canvas.height().max(1) as f32;
+		let renderer = Renderer::from_offscreen_canvas(canvas)?;
+
+		let camera = Camera::new(aspect)
+			.with_position(Vec3::new(0.0, 2.0, 5.0))
+			.with_target(Vec3::ZERO);
+
+		Ok(Self {
+			renderer: Rc::new(renderer),
+			description: Rc::new(RefCell::new(SceneDescription::new(camera.clone()))),
+			instance: Rc::new(RefCell::new(SceneInstance::new(camera))),
+			commands,
+			on_message: Rc::new(RefCell::new(None)),
+		})
+	}
+
+	/// Sets the callback receiving [`SceneCommand::Custom`] messages.
+	pub fn on_message<F>(&self, callback: F)
+	where
+		F: FnMut(&mut SceneDescription, JsValue) + 'static,
+	{
+		*self.on_message.borrow_mut() = Some(Box::new(callback));
+	}
+
+	/// Starts the render loop with the provided update callback.
+	///
+	/// Every frame, commands received since the last frame are applied,
+	/// then the callback is called with the description and the elapsed
+	/// time in seconds, and the scene is synced and rendered.
+	pub fn run<F>(self, mut update: F) -> Animator
+	where
+		F: FnMut(&mut SceneDescription, f32) + 'static,
+	{
+		let mut objects: HashMap<RemoteObjectId, ObjectId> = HashMap::new();
+		let mut lights: HashMap<RemoteLightId, LightId> = HashMap::new();
+
+		Animator::start(move |time| {
+			let commands = std::mem::take(&mut *self.commands.borrow_mut());
+
+			{
+				let mut description = self.description.borrow_mut();
+
+				for command in commands {
+					self.apply(&mut description, &mut objects, &mut lights, command);
+				}
+
+				update(&mut description, time);
+			}
+
+			let description = self.description.borrow();
+			let mut instance = self.instance.borrow_mut();
+
+			if let Err(e) = instance.sync(&self.renderer.gl, &description) {
+				log::error!("{}", e);
+			}
+
+			instance.scene.render(&self.renderer, time);
+		})
+	}
+
+	fn apply(
+		&self,
+		description: &mut SceneDescription,
+		objects: &mut HashMap<RemoteObjectId, ObjectId>,
+		lights: &mut HashMap<RemoteLightId, LightId>,
+		command: SceneCommand,
+	) {
+		match command {
+			SceneCommand::AddObject { id, mesh, material, transform } => {
+				if let Some(previous) = objects.remove(&id) {
+					description.remove(previous);
+				}
+				objects.insert(id, description.add(Rc::new(mesh), material, transform));
+			}
+			SceneCommand::RemoveObject(id) => {
+				if let Some(object) = objects.remove(&id) {
+					description.remove(object);
+				}
+			}
+			SceneCommand::SetTransform(id, transform) => {
+				if let Some(object) = objects.get(&id).and_then(|&object| description.objects.get_mut(object)) {
+					object.transform = transform;
+				}
+			}
+			SceneCommand::SetVisible(id, visible) => {
+				if let Some(object) = objects.get(&id).and_then(|&object| description.objects.get_mut(object)) {
+					object.visible = visible;
+				}
+			}
+			SceneCommand::SetMaterial(id, material) => {
+				if let Some(object) = objects.get(&id).and_then(|&object| description.objects.get_mut(object)) {
+					object.set_material(material);
+				}
+			}
+			SceneCommand::SetLight(id, light) => {
+				match lights.get(&id).and_then(|&existing| description.lights.get_mut(existing)) {
+					Some(existing) => *existing = light,
+					None => {
+						lights.insert(id, description.add_light(light));
+					}
+				}
+			}
+			SceneCommand::RemoveLight(id) => {
+				if let Some(light) = lights.remove(&id) {
+					description.remove_light(light);
+				}
+			}
+			SceneCommand::SetCamera(camera) => description.camera = camera,
+			SceneCommand::SetBackground(color) => description.background = Some(color),
+			SceneCommand::Resize { width, height } => {
+				self.renderer.surface().set_size(width, height);
+				self.instance.borrow_mut().scene.camera.aspect = width as f32 / height.max(1) as f32;
+			}
+			SceneCommand::Custom(value) => {
+				if let Some(callback) = self.on_message.borrow_mut().as_mut() {
+					callback(description, value);
+				}
+			}
+		}
+	}
+}
+
+fn set(object: &Object, key: &str, value: &JsValue) {
+	let _ = Reflect::set(object, &JsValue::from_str(key), value);
+}
+
+fn get(value: &JsValue, key: &str) -> Result<JsValue, String> {
+	Reflect::get(value, &JsValue::from_str(key))
+		.ok()
+		.filter(|field| !field.is_undefined())
+		.ok_or_else(|| format!("Scene command is missing `{}`", key))
+}
+
+fn get_string(value: &JsValue, key: &str) -> Result<String, String> {
+	get(value, key)?.as_string().ok_or_else(|| format!("`{}` is not a string", key))
+}
+
+fn get_f64(value: &JsValue, key: &str) -> Result<f64, String> {
+	get(value, key)?.as_f64().ok_or_else(|| format!("`{}` is not a number", key))
+}
+
+fn get_u32(value: &JsValue, key: &str) -> Result<u32, String> {
+	get_f64(value, key).map(|v| v as u32)
+}
+
+fn get_floats(value: &JsValue, key: &str) -> Result<Vec<f32>, String> {
+	get(value, key)?
+		.dyn_into::<Float32Array>()
+		.map(|array| array.to_vec())
+		.map_err(|_| format!("`{}` is not a Float32Array", key))
+}
+
+fn floats(values: &[f32]) -> JsValue {
+	Float32Array::from(values).into()
+}
+
+fn transform_to_js(transform: &Transform3D) -> JsValue {
+	let (p, r, s) = (transform.position, transform.rotation, transform.scale);
+	floats(&[p.x, p.y, p.z, r.x, r.y, r.z, r.w, s.x, s.y, s.z])
+}
+
+fn transform_from_js(values: &[f32]) -> Result<Transform3D, String> {
+	let [px, py, pz, rx, ry, rz, rw, sx, sy, sz] = values[..] else {
+		return Err("Expected 10 transform values".into());
+	};
+
+	Ok(Transform3D::new()
+		.with_position(Vec3::new(px, py, pz))
+		.with_rotation(Quat::from_xyzw(rx, ry, rz, rw))
+		.with_scale(Vec3::new(sx, sy, sz)))
+}
+
+fn mesh_to_js(mesh: &MeshData) -> JsValue {
+	let object = Object::new();
+	set(&object, "positions", &floats(&mesh.positions));
+	set(&object, "normals", &floats(&mesh.normals));
+	set(&object, "uvs", &floats(&mesh.uvs));
+	set(&object, "colors", &floats(&mesh.colors));
+
+	if let Some(name) = &mesh.name {
+		set(&object, "name", &name.into());
+	}
+
+	object.into()
+}
+
+fn mesh_from_js(value: &JsValue) -> Result<MeshData, String> {
+	Ok(MeshData {
+		positions: get_floats(value, "positions")?,
+		normals: get_floats(value, "normals")?,
+		uvs: get_floats(value, "uvs")?,
+		colors: get_floats(value, "colors")?,
+		name: get_string(value, "name").ok(),
+		material: None,
+	})
+}
+
+fn material_to_js(material: &MaterialDescription) -> JsValue {
+	let object = Object::new();
+
+	match material {
+		MaterialDescription::Unlit(color) => {
+			set(&object, "kind", &"unlit".into());
+			set(&object, "color", &floats(&color.to_array()));
+		}
+		MaterialDescription::Lambert(color) => {
+			set(&object, "kind", &"lambert".into());
+			set(&object, "color", &floats(&color.to_array()));
+		}
+		MaterialDescription::Phong(color) => {
+			set(&object, "kind", &"phong".into());
+			set(&object, "color", &floats(&color.to_array()));
+		}
+		MaterialDescription::Toon(color, bands) => {
+			set(&object, "kind", &"toon".into());
+			set(&object, "color", &floats(&color.to_array()));
+			set(&object, "bands", &(*bands).into());
+		}
+		MaterialDescription::Custom { vert, frag, uniforms } => {
+			set(&object, "kind", &"custom".into());
+			set(&object, "vert", &vert.into());
+			set(&object, "frag", &frag.into());
+
+			let list = Array::new();
+
+			for (name, value) in uniforms {
+				let (kind, values) = match value {
+					Uniform::Float(v) => ("float", vec![*v]),
+					Uniform::Vec2(v) => ("vec2", v.to_array().to_vec()),
+					Uniform::Vec3(v) => ("vec3", v.to_array().to_vec()),
+					Uniform::Vec4(v) => ("vec4", v.to_array().to_vec()),
+					Uniform::Mat4(m) => ("mat4", m.to_cols_array().to_vec()),
+					Uniform::Int(v) => ("int", vec![*v as f32]),
+				};
+
+				let uniform = Object::new();
+				set(&uniform, "name", &name.into());
+				set(&uniform, "kind", &kind.into());
+				set(&uniform, "value", &floats(&values));
+				list.push(&uniform);
+			}
+
+			set(&object, "uniforms", &list);
+		}
+	}
+
+	object.into()
+}
+
+fn material_from_js(value: &JsValue) -> Result<MaterialDescription, String> {
+	let kind = get_string(value, "kind")?;
+	let vec3 = |values: Vec<f32>| match values[..] {
+		[x, y, z] => Ok(Vec3::new(x, y, z)),
+		_ => Err("Expected 3 color values".to_string()),
+	};
+
+	Ok(match kind.as_str() {
+		"unlit" => {
+			let color = get_floats(value, "color")?;
+			let [r, g, b, a] = color[..] else {
+				return Err("Expected 4 color values".into());
+			};
+			MaterialDescription::Unlit(Vec4::new(r, g, b, a))
+		}
+		"lambert" => MaterialDescription::Lambert(vec3(get_floats(value, "color")?)?),
+		"phong" => MaterialDescription::Phong(vec3(get_floats(value, "color")?)?),
+		"toon" => MaterialDescription::Toon(vec3(get_floats(value, "color")?)?, get_u32(value, "bands")?),
+		"custom" => {
+			let list = get(value, "uniforms")?.dyn_into::<Array>().map_err(|_| "`uniforms` is not an array")?;
+			let mut uniforms = Vec::new();
+
+			for uniform in list.iter() {
+				let values = get_floats(&uniform, "value")?;
+				let value = match (get_string(&uniform, "kind")?.as_str(), &values[..]) {
+					("float", &[v]) => Uniform::Float(v),
+					("vec2", &[x, y]) => Uniform::Vec2(glam::Vec2::new(x, y)),
+					("vec3", &[x, y, z]) => Uniform::Vec3(Vec3::new(x, y, z)),
+					("vec4", &[x, y, z, w]) => Uniform::Vec4(Vec4::new(x, y, z, w)),
+					("mat4", values) if values.len() == 16 => Uniform::Mat4(glam::Mat4::from_cols_slice(values)),
+					("int", &[v]) => Uniform::Int(v as i32),
+					(kind, _) => return Err(format!("Invalid `{}` uniform", kind)),
+				};
+
+				uniforms.push((get_string(&uniform, "name")?, value));
+			}
+
+			MaterialDescription::Custom {
+				vert: get_string(value, "vert")?,
+				frag: get_string(value, "frag")?,
+				uniforms,
+			}
+		}
+		other => return Err(format!("Unknown material kind `{}`", other)),
+	})
+}
+
+fn light_to_js(light: &Light) -> JsValue {
+	let object = Object::new();
+	let (kind, shape) = match light.light_type {
+		LightType::Directional => ("directional", vec![]),
+		LightType::Point { radius } => ("point", vec![radius]),
+		LightType::Spot { angle, outer_angle, range } => ("spot", vec![angle, outer_angle, range]),
+	};
+	let (p, d, c) = (light.position, light.direction, light.color);
+
+	set(&object, "kind", &kind.into());
+	set(&object, "shape", &floats(&shape));
+	set(&object, "values", &floats(&[p.x, p.y, p.z, d.x, d.y, d.z, c.x, c.y, c.z, light.intensity]));
+	set(&object, "falloff", &light.falloff.as_index().into());
+	set(&object, "castShadows", &light.cast_shadows.into());
+	set(&object, "enabled", &light.enabled.into());
+
+	if let Some(contact) = light.contact_shadows {
+		set(&object, "contactShadows", &floats(&[contact.length, contact.intensity]));
+	}
+
+	if let Some(halo) = light.halo {
+		set(&object, "halo", &floats(&[halo.size, halo.opacity]));
+	}
+
+	object.into()
+}
+
+fn light_from_js(value: &JsValue) -> Result<Light, String> {
+	let light_type = match (get_string(value, "kind")?.as_str(), &get_floats(value, "shape")?[..]) {
+		("directional", []) => LightType::Directional,
+		("point", &[radius]) => LightType::Point { radius },
+		("spot", &[angle, outer_angle, range]) => LightType::Spot { angle, outer_angle, range },
+		(kind, _) => return Err(format!("Invalid `{}` light", kind)),
+	};
+	let values = get_floats(value, "values")?;
+	let [px, py, pz, dx, dy, dz, r, g, b, intensity] = values[..] else {
+		return Err("Expected 10 light values".into());
+	};
+	let falloff = match get_u32(value, "falloff")? {
+		1 => Falloff::Linear,
+		2 => Falloff::InverseSquare,
+		3 => Falloff::SmoothWindow,
+		_ => Falloff::Quadratic,
+	};
+	let contact_shadows = get_floats(value, "contactShadows").ok().and_then(|values| match values[..] {
+		[length, intensity] => Some(ContactShadows { length, intensity }),
+		_ => None,
+	});
+	let halo = get_floats(value, "halo").ok().and_then(|values| match values[..] {
+		[size, opacity] => Some(LightHalo { size, opacity }),
+		_ => None,
+	});
+
+	let mut light = Light::directional(Vec3::NEG_Y, Vec3::new(r, g, b), intensity);
+	light.light_type = light_type;
+	light.position = Vec3::new(px, py, pz);
+	light.direction = Vec3::new(dx, dy, dz);
+	light.falloff = falloff;
+	light.cast_shadows = get(value, "castShadows")?.is_truthy();
+	light.enabled = get(value, "enabled")?.is_truthy();
+	light.contact_shadows = contact_shadows;
+	light.halo = halo;
+
+	Ok(light)
+}